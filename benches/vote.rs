@@ -0,0 +1,170 @@
+// 投票电路性能基准（BN254 上的 Groth16）
+//
+// 按选项数量参数化，便于多选项电路接入后对比约束规模增长带来的开销；
+// 目前电路只支持二选一，NUM_OPTIONS_CASES 中只有 tally::NUM_OPTIONS。
+// 运行：cargo bench --bench vote
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::Zero;
+use ark_groth16::{Groth16, Proof};
+use ark_serialize::{CanonicalDeserialize, Validate};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use zkp_vote::tally::NUM_OPTIONS;
+use zkp_vote::{Compression, ProofSystem, VoteCircuit, VoteSystem};
+
+type Backend = Groth16<Bn254>;
+
+// 参与基准的选项数量
+const NUM_OPTIONS_CASES: &[usize] = &[NUM_OPTIONS];
+
+// 批量证明/验证的选票数量
+const BATCH_SIZES: &[usize] = &[16, 64];
+
+// 密钥和选票所属的选举
+const ELECTION_ID: u64 = 2024;
+
+// 固定种子，保证各次运行的密钥和选票一致
+fn rng() -> ChaCha20Rng {
+    ChaCha20Rng::from_seed([42u8; 32])
+}
+
+// 构造一张投给最后一个选项的选票
+fn ballot(num_options: usize, index: u64) -> VoteCircuit<Fr> {
+    VoteCircuit {
+        vote: Fr::from((num_options - 1) as u64),
+        secret: Fr::from(10_000 + index),
+        randomness: Fr::from(20_000 + index),
+        election_id: Fr::from(ELECTION_ID),
+        sequence: Fr::zero(),
+        epoch: Fr::zero(),
+    }
+}
+
+fn bench_setup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("setup");
+    group.sample_size(10);
+    for &num_options in NUM_OPTIONS_CASES {
+        group.bench_with_input(BenchmarkId::from_parameter(num_options), &num_options, |b, _| {
+            b.iter(|| VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_vote(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vote");
+    group.sample_size(10);
+    for &num_options in NUM_OPTIONS_CASES {
+        let (system, pk, _) = VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(num_options), &num_options, |b, &num_options| {
+            let mut rng = rng();
+            b.iter(|| system.vote(&pk, ballot(num_options, 0), &mut rng).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify");
+    for &num_options in NUM_OPTIONS_CASES {
+        let (system, pk, vk) = VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap();
+        let circuit = ballot(num_options, 0);
+        let public_inputs = circuit.public_inputs().to_vec();
+        let proof = system.vote(&pk, circuit, &mut rng()).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(num_options), &num_options, |b, _| {
+            b.iter(|| assert!(system.verify(&vk, &public_inputs, &proof).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+// 单个证明内部使用不同线程数的耗时（需启用 parallel 特性，否则各线程数耗时相同）
+fn bench_vote_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vote_threads");
+    group.sample_size(10);
+    let max_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut thread_counts = vec![1, max_threads];
+    thread_counts.dedup();
+    for threads in thread_counts {
+        // 固定种子，各线程数下的密钥相同
+        let (system, pk, vk) = VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap();
+        let system = system.with_threads(threads);
+        let public_inputs = ballot(NUM_OPTIONS, 0).public_inputs().to_vec();
+        let proof = system.vote(&pk, ballot(NUM_OPTIONS, 0), &mut rng()).unwrap();
+        assert!(system.verify(&vk, &public_inputs, &proof).unwrap());
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, _| {
+            let mut rng = rng();
+            b.iter(|| system.vote(&pk, ballot(NUM_OPTIONS, 0), &mut rng).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// 并行证明与逐张证明的对比数据
+fn bench_prove_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prove_batch");
+    group.sample_size(10);
+    let (system, pk, _) = VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap();
+    for &num_options in NUM_OPTIONS_CASES {
+        for &size in BATCH_SIZES {
+            let circuits: Vec<_> = (0..size as u64).map(|i| ballot(num_options, i)).collect();
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("options_{}", num_options), size),
+                &circuits,
+                |b, circuits| b.iter(|| system.prove_batch(&pk, circuits.clone(), [7u8; 32]).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+// 批量验证与逐张验证的对比数据
+fn bench_verify_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_batch");
+    let (system, pk, vk) = VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap();
+    for &num_options in NUM_OPTIONS_CASES {
+        for &size in BATCH_SIZES {
+            let circuits: Vec<_> = (0..size as u64).map(|i| ballot(num_options, i)).collect();
+            let inputs: Vec<Vec<Fr>> = circuits.iter().map(|c| c.public_inputs().to_vec()).collect();
+            let proofs = system.prove_batch(&pk, circuits, [7u8; 32]).unwrap();
+            let batch: Vec<(Vec<Fr>, <Backend as ProofSystem<Fr>>::Proof)> = inputs.into_iter().zip(proofs).collect();
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("options_{}", num_options), size),
+                &batch,
+                |b, batch| b.iter(|| system.verify_batch(&vk, batch)),
+            );
+        }
+    }
+    group.finish();
+}
+
+// 两种编码格式的证明解码耗时（只包含点的解码，不含两种格式相同的子群检查）
+fn bench_decode_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_proof");
+    let (system, pk, _) = VoteSystem::<Fr, Backend>::setup(Fr::from(ELECTION_ID), &mut rng()).unwrap();
+    let proof = system.vote(&pk, ballot(NUM_OPTIONS, 0), &mut rng()).unwrap();
+    for compression in [Compression::Compressed, Compression::Uncompressed] {
+        let bytes = <Backend as ProofSystem<Fr>>::serialize_proof_with(&proof, compression).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{:?}", compression)), &bytes, |b, bytes| {
+            b.iter(|| Proof::<Bn254>::deserialize_with_mode(&bytes[..], compression.into(), Validate::No).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_setup,
+    bench_vote,
+    bench_vote_threads,
+    bench_verify,
+    bench_prove_batch,
+    bench_verify_batch,
+    bench_decode_proof
+);
+criterion_main!(benches);
@@ -0,0 +1,29 @@
+// 证明解码：任何字节串都只能得到 Ok 或 Err，不能 panic；
+// 解码成功的证明重新编码后必须解码为同一个证明
+#![no_main]
+
+use ark_groth16::Groth16;
+use ark_serialize::Compress;
+use libfuzzer_sys::fuzz_target;
+use zkp_vote::curve::{DefaultField, DefaultPairing};
+use zkp_vote::ProofSystem;
+
+type P = Groth16<DefaultPairing>;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = <P as ProofSystem<DefaultField>>::deserialize_proof(data) {
+        let bytes = <P as ProofSystem<DefaultField>>::serialize_proof(&proof).unwrap();
+        assert_eq!(<P as ProofSystem<DefaultField>>::deserialize_proof(&bytes).unwrap(), proof);
+    }
+    for mode in [Compress::Yes, Compress::No] {
+        let _ = <P as ProofSystem<DefaultField>>::deserialize_proof_with_mode(data, mode);
+    }
+    if let Ok((proof, mode)) = <P as ProofSystem<DefaultField>>::deserialize_proof_auto(data) {
+        let bytes = match mode {
+            Compress::Yes => <P as ProofSystem<DefaultField>>::serialize_proof_compressed(&proof),
+            Compress::No => <P as ProofSystem<DefaultField>>::serialize_proof_uncompressed(&proof),
+        }
+        .unwrap();
+        assert_eq!(<P as ProofSystem<DefaultField>>::deserialize_proof_with_mode(&bytes, mode).unwrap(), proof);
+    }
+});
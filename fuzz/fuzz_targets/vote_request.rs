@@ -0,0 +1,11 @@
+// /vote 请求正文的解析与字段校验：任何输入都只能得到 Ok 或 Err，不能 panic
+#![no_main]
+
+use ark_groth16::Groth16;
+use libfuzzer_sys::fuzz_target;
+use zkp_vote::curve::DefaultPairing;
+use zkp_vote::web::check_vote_request;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = check_vote_request::<Groth16<DefaultPairing>>(data);
+});
@@ -0,0 +1,82 @@
+# zkp_vote_py 往返测试：maturin develop 后运行 pytest python/tests
+import pytest
+
+import zkp_vote_py
+
+
+@pytest.fixture(scope="module")
+def keys():
+    return zkp_vote_py.setup(hex(2024))
+
+
+def ballot(vote, secret, randomness="0x1234"):
+    return {
+        "vote": hex(vote),
+        "secret": hex(secret),
+        "randomness": randomness,
+        "election_id": hex(2024),
+        "sequence": "0x0",
+        "epoch": "0x0",
+    }
+
+
+def test_prove_and_verify_round_trip(keys):
+    pk, vk = keys
+    proved = zkp_vote_py.prove_ballot(pk, ballot(1, 0xABCDEF))
+    assert len(proved["public_inputs"]) == 5
+    assert all(x.startswith("0x") for x in proved["public_inputs"])
+    assert zkp_vote_py.verify_ballot(vk, proved["public_inputs"], proved["proof"])
+
+    # 篡改公开输入后验证失败
+    tampered = list(proved["public_inputs"])
+    tampered[1] = hex(2025)
+    assert not zkp_vote_py.verify_ballot(vk, tampered, proved["proof"])
+
+
+def test_tally(keys):
+    pk, vk = keys
+    ballots = []
+    for i, vote in enumerate([1, 0, 1]):
+        proved = zkp_vote_py.prove_ballot(pk, ballot(vote, 1000 + i))
+        ballots.append({**proved, "vote": hex(vote), "randomness": "0x1234"})
+    # 重复提交同一张选票
+    ballots.append(dict(ballots[0]))
+
+    result = zkp_vote_py.tally(vk, ballots)
+    assert result["counts"] == [1, 2]
+    assert result["accepted"] == [0, 1, 2]
+    assert result["rejected"] == [(3, "duplicate_nullifier")]
+    assert result["commitments"][0] == zkp_vote_py.ballot_commitment("0x1", "0x1234")
+
+
+def test_key_import_export(keys, tmp_path):
+    pk, vk = keys
+    zkp_vote_py.export_proving_key(pk, str(tmp_path / "pk.bin"))
+    zkp_vote_py.export_verifying_key(vk, str(tmp_path / "vk.bin"))
+    assert zkp_vote_py.import_proving_key(str(tmp_path / "pk.bin")) == pk
+    assert zkp_vote_py.import_verifying_key(str(tmp_path / "vk.bin")) == vk
+    assert zkp_vote_py.verifying_key_from_proving_key(pk) == vk
+
+
+def test_check_key_pair(keys):
+    pk, vk = keys
+    zkp_vote_py.check_key_pair(pk, vk)
+    _, other_vk = zkp_vote_py.setup(hex(2024))
+    with pytest.raises(zkp_vote_py.KeyMismatch, match="verifying key is suspect"):
+        zkp_vote_py.check_key_pair(pk, other_vk)
+
+
+def test_hex_decimal_conversion():
+    assert zkp_vote_py.hex_from_decimal("255") == "0xff"
+    assert zkp_vote_py.decimal_from_hex("0xff") == "255"
+
+
+def test_errors_named_by_variant(keys):
+    pk, _ = keys
+    with pytest.raises(zkp_vote_py.InvalidBallot):
+        zkp_vote_py.prove_ballot(pk, {"vote": "0x1"})
+    with pytest.raises(zkp_vote_py.FieldRangeExceeded):
+        zkp_vote_py.prove_ballot(pk, ballot(1, 1, randomness="0x" + "f" * 64))
+    with pytest.raises(zkp_vote_py.VoteError):
+        zkp_vote_py.import_proving_key("/nonexistent/pk.bin")
+    assert issubclass(zkp_vote_py.Serialization, zkp_vote_py.VoteError)
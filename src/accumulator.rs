@@ -0,0 +1,341 @@
+// 防重标识唯一性的分批证明
+//
+// 这是"整场选举计票正确性的递归证明"的第一个里程碑：证明全部计入的防重标识互不相同。
+// 计票方把防重标识排序后按批（默认64个）切分，每批生成一个证明，证明：
+//   1. 批内防重标识严格递增，且第一个大于上一批的最后一个（因此全局互不相同）；
+//   2. 运行累加器 acc_out 由 acc_in 依次吸收本批防重标识得到（MiMC 链式哈希）。
+// 各批证明通过 (acc, last) 首尾相接，验证者只需检查链接关系和每个批次证明。
+// 后续里程碑将把批次证明折叠进递归包装证明，使验证者只需检查一个证明。
+//
+// 防重标识按规范整数表示比较，链的起点为 acc = 0、last = 0，因此值为0的防重标识不被接受。
+
+use crate::tally::{commit_ballot, enforce_commit_ballot};
+use crate::{ProofSystem, VoteError};
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::lc;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+use ark_std::rand::{CryptoRng, RngCore};
+use tracing::{debug, error, info};
+
+// 默认批大小
+pub const NULLIFIER_BATCH_SIZE: usize = 64;
+
+// 批次证明的公开输入
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NullifierBatchInputs<F: PrimeField> {
+    pub acc_in: F,    // 上一批结束时的累加器
+    pub prev_last: F, // 上一批的最后一个防重标识
+    pub acc_out: F,   // 本批结束时的累加器
+    pub last: F,      // 本批的最后一个防重标识
+    pub count: F,     // 本批实际包含的防重标识数量
+}
+
+impl<F: PrimeField> NullifierBatchInputs<F> {
+    // 按电路分配顺序输出公开输入
+    pub fn to_vec(&self) -> Vec<F> {
+        vec![self.acc_in, self.prev_last, self.acc_out, self.last, self.count]
+    }
+}
+
+// 批次电路
+// nullifiers 长度即批大小，前 count 个为有效值且已严格递增排序，其余为填充
+#[derive(Clone, Debug)]
+pub struct NullifierBatchCircuit<F: PrimeField> {
+    pub nullifiers: Vec<F>,
+    pub count: usize,
+    pub acc_in: F,
+    pub prev_last: F,
+}
+
+impl<F: PrimeField> NullifierBatchCircuit<F> {
+    // 构造仅用于密钥生成的空电路
+    fn blank(batch_size: usize) -> Self {
+        Self {
+            nullifiers: vec![F::zero(); batch_size],
+            count: 0,
+            acc_in: F::zero(),
+            prev_last: F::zero(),
+        }
+    }
+
+    // 该电路实例对应的公开输入
+    pub fn public_inputs(&self) -> NullifierBatchInputs<F> {
+        let mut acc = self.acc_in;
+        let mut last = self.prev_last;
+        for n in &self.nullifiers[..self.count] {
+            acc = commit_ballot(*n, acc);
+            last = *n;
+        }
+        NullifierBatchInputs {
+            acc_in: self.acc_in,
+            prev_last: self.prev_last,
+            acc_out: acc,
+            last,
+            count: F::from(self.count as u64),
+        }
+    }
+}
+
+// 电路内的比特：线性组合及其值
+struct Bit<F: PrimeField> {
+    lc: LinearCombination<F>,
+    value: bool,
+}
+
+// 常量比特
+fn constant_bits<F: PrimeField>(value: F::BigInt) -> Vec<Bit<F>> {
+    value
+        .to_bits_le()
+        .into_iter()
+        .take(F::MODULUS_BIT_SIZE as usize)
+        .map(|b| Bit { lc: if b { lc!() + Variable::One } else { lc!() }, value: b })
+        .collect()
+}
+
+// 比较两个等长的小端比特串，返回 a < b 的布尔值（线性组合形式）
+// 从最高位向低位扫描：eq 表示已扫描的高位全部相等，
+// 某一位 a=0、b=1 且更高位全部相等时 a < b，各位的该项互斥，求和即为结果
+fn less_than<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    a: &[Bit<F>],
+    b: &[Bit<F>],
+) -> Result<(LinearCombination<F>, bool), SynthesisError> {
+    let mut eq: LinearCombination<F> = lc!() + Variable::One;
+    let mut eq_val = true;
+    let mut lt: LinearCombination<F> = lc!();
+    let mut lt_val = false;
+
+    for (a, b) in a.iter().zip(b).rev() {
+        let ab_val = a.value && b.value;
+        let ab = cs.new_witness_variable(|| Ok(F::from(ab_val)))?;
+        cs.enforce_constraint(a.lc.clone(), b.lc.clone(), lc!() + ab)?;
+
+        // u = eq * b * (1 - a)
+        let u_val = eq_val && b.value && !a.value;
+        let u = cs.new_witness_variable(|| Ok(F::from(u_val)))?;
+        cs.enforce_constraint(eq.clone(), b.lc.clone() - ab, lc!() + u)?;
+
+        // eq' = eq * (a == b)，其中 (a == b) = 1 - a - b + 2ab
+        let next_eq_val = eq_val && a.value == b.value;
+        let next_eq = cs.new_witness_variable(|| Ok(F::from(next_eq_val)))?;
+        cs.enforce_constraint(
+            eq,
+            lc!() + Variable::One - &a.lc - &b.lc + (F::from(2u64), ab),
+            lc!() + next_eq,
+        )?;
+
+        lt = lt + u;
+        lt_val |= u_val;
+        eq = lc!() + next_eq;
+        eq_val = next_eq_val;
+    }
+
+    Ok((lt, lt_val))
+}
+
+// 分配 value 的规范比特分解，约束其打包值等于 var 且小于模数
+fn canonical_bits<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    var: Variable,
+    value: F,
+) -> Result<Vec<Bit<F>>, SynthesisError> {
+    let bits = value.into_bigint().to_bits_le();
+    let mut out = Vec::with_capacity(F::MODULUS_BIT_SIZE as usize);
+    let mut packed: LinearCombination<F> = lc!();
+    let mut coeff = F::one();
+    for bit in bits.into_iter().take(F::MODULUS_BIT_SIZE as usize) {
+        let bit_var = cs.new_witness_variable(|| Ok(F::from(bit)))?;
+        cs.enforce_constraint(lc!() + bit_var, lc!() + bit_var - Variable::One, lc!())?;
+        packed += (coeff, bit_var);
+        coeff.double_in_place();
+        out.push(Bit { lc: lc!() + bit_var, value: bit });
+    }
+    cs.enforce_constraint(packed, lc!() + Variable::One, lc!() + var)?;
+
+    // 比特串表示的整数必须小于模数，否则同一字段元素存在两种分解
+    let (lt, _) = less_than(cs, &out, &constant_bits::<F>(F::MODULUS))?;
+    cs.enforce_constraint(lt, lc!() + Variable::One, lc!() + Variable::One)?;
+    Ok(out)
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for NullifierBatchCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.count > self.nullifiers.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // 按 NullifierBatchInputs::to_vec() 的顺序分配公开输入
+        let inputs = self.public_inputs();
+        let acc_in_var = cs.new_input_variable(|| Ok(inputs.acc_in))?;
+        let prev_last_var = cs.new_input_variable(|| Ok(inputs.prev_last))?;
+        let acc_out_var = cs.new_input_variable(|| Ok(inputs.acc_out))?;
+        let last_var = cs.new_input_variable(|| Ok(inputs.last))?;
+        let count_var = cs.new_input_variable(|| Ok(inputs.count))?;
+
+        let mut prev_bits = canonical_bits(&cs, prev_last_var, self.prev_last)?;
+        let mut acc: LinearCombination<F> = lc!() + acc_in_var;
+        let mut acc_val = self.acc_in;
+        let mut acc_var = acc_in_var;
+        let mut last: LinearCombination<F> = lc!() + prev_last_var;
+        let mut last_val = self.prev_last;
+        let mut count: LinearCombination<F> = lc!();
+        let mut prev_active: Option<Variable> = None;
+
+        for (i, n) in self.nullifiers.iter().enumerate() {
+            let n_var = cs.new_witness_variable(|| Ok(*n))?;
+            let active_val = i < self.count;
+            let active = cs.new_witness_variable(|| Ok(F::from(active_val)))?;
+
+            // 有效标记为布尔值，且有效位只能出现在前缀
+            cs.enforce_constraint(lc!() + active, lc!() + active - Variable::One, lc!())?;
+            if let Some(prev) = prev_active {
+                cs.enforce_constraint(lc!() + active, lc!() + Variable::One - prev, lc!())?;
+            }
+
+            // 有效时必须严格大于前一个防重标识
+            let bits = canonical_bits(&cs, n_var, *n)?;
+            let (lt, _) = less_than(&cs, &prev_bits, &bits)?;
+            cs.enforce_constraint(lc!() + active, lc!() + Variable::One - lt, lc!())?;
+
+            // 有效时吸收进累加器：acc' = acc + active * (H(n, acc) - acc)
+            let (hash, hash_val) = enforce_commit_ballot(&cs, lc!() + n_var, *n, acc_var, acc_val)?;
+            let next_acc_val = if active_val { hash_val } else { acc_val };
+            let next_acc = cs.new_witness_variable(|| Ok(next_acc_val))?;
+            cs.enforce_constraint(lc!() + active, hash - &acc, lc!() + next_acc - &acc)?;
+
+            // 有效时更新最后一个防重标识
+            let next_last_val = if active_val { *n } else { last_val };
+            let next_last = cs.new_witness_variable(|| Ok(next_last_val))?;
+            cs.enforce_constraint(lc!() + active, lc!() + n_var - &last, lc!() + next_last - &last)?;
+
+            acc = lc!() + next_acc;
+            acc_val = next_acc_val;
+            acc_var = next_acc;
+            last = lc!() + next_last;
+            last_val = next_last_val;
+            count = count + active;
+            prev_active = Some(active);
+            prev_bits = bits;
+        }
+
+        cs.enforce_constraint(acc, lc!() + Variable::One, lc!() + acc_out_var)?;
+        cs.enforce_constraint(last, lc!() + Variable::One, lc!() + last_var)?;
+        cs.enforce_constraint(count, lc!() + Variable::One, lc!() + count_var)?;
+
+        Ok(())
+    }
+}
+
+// 单个批次的证明
+#[derive(Debug)]
+pub struct NullifierBatchProof<F: PrimeField, P: ProofSystem<F>> {
+    pub proof: P::Proof,
+    pub inputs: NullifierBatchInputs<F>,
+}
+
+// 手动实现Clone，派生实现会要求证明系统类型 P 本身实现Clone
+impl<F: PrimeField, P: ProofSystem<F>> Clone for NullifierBatchProof<F, P> {
+    fn clone(&self) -> Self {
+        Self { proof: self.proof.clone(), inputs: self.inputs.clone() }
+    }
+}
+
+// 为固定批大小生成批次电路的密钥
+pub fn setup_nullifier_batch<F, P, R>(
+    batch_size: usize,
+    rng: &mut R,
+) -> Result<(P::ProvingKey, P::VerifyingKey), VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+    R: RngCore + CryptoRng,
+{
+    info!("Setting up nullifier batch circuit for batch size {}", batch_size);
+
+    P::setup(NullifierBatchCircuit::<F>::blank(batch_size), rng).map_err(|e| {
+        error!("Failed to setup nullifier batch circuit: {:?}", e);
+        e
+    })
+}
+
+// 证明一组防重标识互不相同，返回首尾相接的批次证明链
+pub fn prove_nullifier_uniqueness<F, P, R>(
+    pk: &P::ProvingKey,
+    batch_size: usize,
+    nullifiers: &[F],
+    rng: &mut R,
+) -> Result<Vec<NullifierBatchProof<F, P>>, VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+    R: RngCore + CryptoRng,
+{
+    debug!("Proving uniqueness of {} nullifiers in batches of {}", nullifiers.len(), batch_size);
+
+    if batch_size == 0 {
+        return Err(VoteError::InvalidBallot("batch size must be positive".to_string()));
+    }
+    let mut sorted = nullifiers.to_vec();
+    sorted.sort_by_key(|n| n.into_bigint());
+    if sorted.windows(2).any(|w| w[0] == w[1]) {
+        return Err(VoteError::DuplicateNullifier);
+    }
+    if sorted.first().is_some_and(|n| n.is_zero()) {
+        return Err(VoteError::InvalidBallot("zero nullifier cannot be proven unique".to_string()));
+    }
+
+    let mut acc = F::zero();
+    let mut prev_last = F::zero();
+    let mut proofs = Vec::with_capacity(sorted.len().div_ceil(batch_size));
+    for chunk in sorted.chunks(batch_size) {
+        let mut padded = chunk.to_vec();
+        padded.resize(batch_size, F::zero());
+        let circuit = NullifierBatchCircuit {
+            nullifiers: padded,
+            count: chunk.len(),
+            acc_in: acc,
+            prev_last,
+        };
+        let inputs = circuit.public_inputs();
+        let proof = P::prove(pk, circuit, rng).map_err(|e| {
+            error!("Failed to generate nullifier batch proof: {:?}", e);
+            e
+        })?;
+
+        acc = inputs.acc_out;
+        prev_last = inputs.last;
+        proofs.push(NullifierBatchProof { proof, inputs });
+    }
+
+    Ok(proofs)
+}
+
+// 验证批次证明链，通过时返回最终累加器值
+pub fn verify_nullifier_uniqueness<F, P>(
+    vk: &P::VerifyingKey,
+    proofs: &[NullifierBatchProof<F, P>],
+) -> Result<Option<F>, VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+{
+    debug!("Verifying nullifier uniqueness chain of {} batches", proofs.len());
+
+    let mut acc = F::zero();
+    let mut last = F::zero();
+    for batch in proofs {
+        // 各批次必须首尾相接
+        if batch.inputs.acc_in != acc || batch.inputs.prev_last != last {
+            return Ok(None);
+        }
+        if !P::verify(vk, &batch.inputs.to_vec(), &batch.proof)? {
+            return Ok(None);
+        }
+        acc = batch.inputs.acc_out;
+        last = batch.inputs.last;
+    }
+
+    Ok(Some(acc))
+}
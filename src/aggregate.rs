@@ -0,0 +1,555 @@
+// Groth16 证明聚合（SnarkPack）
+//
+// 审计时逐个验证大量选票证明代价过高。聚合方把 n 个证明压缩为一个聚合证明，
+// 验证者只需 O(log n) 次群运算和常数次配对即可确认全部证明有效。
+//
+// 原理：取随机数 r，n 个 Groth16 验证方程按 r^i 线性组合后得到
+//   Z_AB = e(α, β)^{Σr^i} · e(Σ r^i·IC(x_i), γ) · e(Z_C, δ)
+// 其中 Z_AB = ∏ e(A_i, B_i)^{r^i}，Z_C = Σ r^i·C_i。
+// 聚合方对 (A, B) 和 C 做配对承诺，再分别用 TIPP 和 MIPP 两个内积论证
+// 证明 Z_AB、Z_C 与承诺一致；论证按 GIPA 逐轮折半，最后用 KZG 打开证明
+// 折叠后的承诺密钥构造正确。
+//
+// 承诺密钥来自两个独立秘密 a、b 的幂：
+//   v = (h^{a^i}, h^{b^i})，w = (g^{a^{n+i}}, g^{b^{n+i}})，i ∈ [0, n)
+
+use crate::VoteError;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup, Group, VariableBaseMSM};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_std::UniformRand;
+use tracing::{debug, warn};
+
+// 聚合用结构化参考串
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationSrs<E: Pairing> {
+    pub g_alpha: Vec<E::G1Affine>, // [a^i]_1，i ∈ [0, 2n)
+    pub g_beta: Vec<E::G1Affine>,  // [b^i]_1，i ∈ [0, 2n)
+    pub h_alpha: Vec<E::G2Affine>, // [a^i]_2，i ∈ [0, n)
+    pub h_beta: Vec<E::G2Affine>,  // [b^i]_2，i ∈ [0, n)
+}
+
+// 聚合验证密钥（与聚合规模无关）
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationVerifierKey<E: Pairing> {
+    pub g: E::G1Affine,
+    pub h: E::G2Affine,
+    pub g_alpha: E::G1Affine,
+    pub g_beta: E::G1Affine,
+    pub h_alpha: E::G2Affine,
+    pub h_beta: E::G2Affine,
+}
+
+impl<E: Pairing> AggregationSrs<E> {
+    // 由随机秘密直接生成参考串（秘密即有毒废料，生产环境应通过多方仪式生成）
+    pub fn setup_insecure<R: RngCore + CryptoRng>(max_proofs: usize, rng: &mut R) -> Self {
+        warn!("Generating aggregation SRS from local secrets; this is NOT a trusted setup");
+
+        let n = aggregation_size(max_proofs);
+        let a = E::ScalarField::rand(rng);
+        let b = E::ScalarField::rand(rng);
+        let powers = |s: E::ScalarField, len: usize| -> Vec<E::ScalarField> {
+            std::iter::successors(Some(E::ScalarField::one()), |p| Some(*p * s))
+                .take(len)
+                .collect()
+        };
+        let g = E::G1::generator();
+        let h = E::G2::generator();
+        let g_powers = |s| E::G1::normalize_batch(&powers(s, 2 * n).into_iter().map(|p| g * p).collect::<Vec<_>>());
+        let h_powers = |s| E::G2::normalize_batch(&powers(s, n).into_iter().map(|p| h * p).collect::<Vec<_>>());
+
+        Self {
+            g_alpha: g_powers(a),
+            g_beta: g_powers(b),
+            h_alpha: h_powers(a),
+            h_beta: h_powers(b),
+        }
+    }
+
+    // 参考串支持的最大聚合规模
+    pub fn max_proofs(&self) -> usize {
+        self.h_alpha.len()
+    }
+
+    // 提取验证密钥
+    pub fn verifier_key(&self) -> AggregationVerifierKey<E> {
+        AggregationVerifierKey {
+            g: self.g_alpha[0],
+            h: self.h_alpha[0],
+            g_alpha: self.g_alpha[1],
+            g_beta: self.g_beta[1],
+            h_alpha: self.h_alpha[1],
+            h_beta: self.h_beta[1],
+        }
+    }
+}
+
+// GIPA 单轮的交叉项
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GipaRound<E: Pairing> {
+    pub com_ab_l: [PairingOutput<E>; 2],
+    pub com_ab_r: [PairingOutput<E>; 2],
+    pub z_ab_l: PairingOutput<E>,
+    pub z_ab_r: PairingOutput<E>,
+    pub com_c_l: [PairingOutput<E>; 2],
+    pub com_c_r: [PairingOutput<E>; 2],
+    pub z_c_l: E::G1Affine,
+    pub z_c_r: E::G1Affine,
+}
+
+// 聚合证明
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregateProof<E: Pairing> {
+    pub num_proofs: u64,              // 聚合的证明数量（补齐前）
+    pub com_ab: [PairingOutput<E>; 2], // (A, B) 的配对承诺
+    pub com_c: [PairingOutput<E>; 2],  // C 的配对承诺
+    pub z_ab: PairingOutput<E>,        // ∏ e(A_i, B_i)^{r^i}
+    pub z_c: E::G1Affine,              // Σ r^i·C_i
+    pub rounds: Vec<GipaRound<E>>,
+    pub final_a: E::G1Affine,
+    pub final_b: E::G2Affine,
+    pub final_c: E::G1Affine,
+    pub final_vp: [E::G2Affine; 2], // 折叠后的TIPP密钥（按 r^{-i} 缩放）
+    pub final_v: [E::G2Affine; 2],  // 折叠后的MIPP密钥
+    pub final_w: [E::G1Affine; 2],
+    pub opening_vp: [E::G2Affine; 2], // 折叠密钥的KZG打开证明
+    pub opening_v: [E::G2Affine; 2],
+    pub opening_w: [E::G1Affine; 2],
+}
+
+// 分块聚合：选票陆续到达时按块聚合，无需等待全部选票
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChunkedAggregateProof<E: Pairing> {
+    pub chunks: Vec<AggregateProof<E>>,
+}
+
+// 实际聚合规模：补齐到2的幂且至少为2
+fn aggregation_size(num_proofs: usize) -> usize {
+    num_proofs.next_power_of_two().max(2)
+}
+
+// Fiat-Shamir 转录
+struct Transcript {
+    hasher: blake3::Hasher,
+}
+
+impl Transcript {
+    fn new() -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"zkp-vote/aggregate/snarkpack");
+        Self { hasher }
+    }
+
+    fn append<T: CanonicalSerialize>(&mut self, item: &T) {
+        let mut bytes = Vec::new();
+        item.serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        self.hasher.update(&bytes);
+    }
+
+    // 导出非零挑战值并记入转录
+    fn challenge<F: PrimeField>(&mut self) -> F {
+        let mut counter = 0u64;
+        loop {
+            let mut hasher = self.hasher.clone();
+            hasher.update(&counter.to_le_bytes());
+            let c = F::from_le_bytes_mod_order(hasher.finalize().as_bytes());
+            if !c.is_zero() {
+                self.append(&c);
+                return c;
+            }
+            counter += 1;
+        }
+    }
+}
+
+// 配对内积 ∏ e(a_i, b_i)
+fn ipp<E: Pairing>(a: &[E::G1], b: &[E::G2]) -> PairingOutput<E> {
+    E::multi_pairing(a.iter().copied(), b.iter().copied())
+}
+
+// 多标量乘法 Σ s_i·g_i
+fn msm<G: CurveGroup>(bases: &[G], scalars: &[G::ScalarField]) -> G {
+    G::msm_unchecked(&G::normalize_batch(bases), scalars)
+}
+
+// 按 left + right·x 折叠向量
+fn fold<T: Copy + std::ops::Add<Output = T> + std::ops::Mul<S, Output = T>, S: Copy>(v: &[T], x: S) -> Vec<T> {
+    let (left, right) = v.split_at(v.len() / 2);
+    left.iter().zip(right).map(|(l, r)| *l + *r * x).collect()
+}
+
+// 折叠系数多项式 ∏_j (1 + y_j·X^{2^{l-1-j}}) 的系数（y_j 为第 j 轮使用的系数）
+fn folding_poly<F: Field>(ys: &[F]) -> Vec<F> {
+    let mut coeffs = vec![F::one()];
+    for y in ys.iter().rev() {
+        let shifted: Vec<F> = coeffs.iter().map(|c| *c * y).collect();
+        coeffs.extend(shifted);
+    }
+    coeffs
+}
+
+// 在 z 处计算折叠系数多项式的值
+fn eval_folding_poly<F: Field>(ys: &[F], z: F) -> F {
+    let mut result = F::one();
+    let mut power = z;
+    for y in ys.iter().rev() {
+        result *= F::one() + *y * power;
+        power.square_in_place();
+    }
+    result
+}
+
+// 多项式除以 (X - z) 的商（舍去余数）
+fn divide_by_linear<F: Field>(coeffs: &[F], z: F) -> Vec<F> {
+    let mut quotient = vec![F::zero(); coeffs.len().saturating_sub(1)];
+    let mut carry = F::zero();
+    for i in (1..coeffs.len()).rev() {
+        carry = coeffs[i] + carry * z;
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
+
+// KZG 打开证明：[q(s)]，q = (p - p(z)) / (X - z)
+fn kzg_open<G: AffineRepr>(powers: &[G], coeffs: &[G::ScalarField], z: G::ScalarField) -> G {
+    let quotient = divide_by_linear(coeffs, z);
+    <G::Group as VariableBaseMSM>::msm_unchecked(&powers[..quotient.len()], &quotient).into_affine()
+}
+
+// 按聚合规模补齐公开输入（重复最后一个，与补齐证明的方式一致）
+fn padded_inputs<F: Clone>(public_inputs: &[Vec<F>], n: usize) -> Vec<Vec<F>> {
+    let mut padded = public_inputs.to_vec();
+    if let Some(last) = public_inputs.last().cloned() {
+        padded.resize(n, last);
+    }
+    padded
+}
+
+// 聚合 Groth16 证明
+pub fn aggregate_proofs<E: Pairing>(
+    srs: &AggregationSrs<E>,
+    proofs: &[Proof<E>],
+    public_inputs: &[Vec<E::ScalarField>],
+) -> Result<AggregateProof<E>, VoteError> {
+    if proofs.is_empty() || proofs.len() != public_inputs.len() {
+        return Err(VoteError::Aggregation(format!(
+            "expected matching non-empty proofs and public inputs, got {} and {}",
+            proofs.len(),
+            public_inputs.len()
+        )));
+    }
+    let n = aggregation_size(proofs.len());
+    if n > srs.max_proofs() {
+        return Err(VoteError::Aggregation(format!(
+            "aggregating {} proofs requires an SRS for {}, but it only supports {}",
+            proofs.len(),
+            n,
+            srs.max_proofs()
+        )));
+    }
+    debug!("Aggregating {} proofs (padded to {})", proofs.len(), n);
+
+    // 补齐到2的幂：重复最后一个证明
+    let last = proofs.last().expect("checked non-empty");
+    let padded = proofs.iter().chain(std::iter::repeat(last)).take(n);
+    let mut a: Vec<E::G1> = Vec::with_capacity(n);
+    let mut b: Vec<E::G2> = Vec::with_capacity(n);
+    let mut c: Vec<E::G1> = Vec::with_capacity(n);
+    for p in padded {
+        a.push(p.a.into_group());
+        b.push(p.b.into_group());
+        c.push(p.c.into_group());
+    }
+
+    let to_g1 = |v: &[E::G1Affine]| v.iter().map(|p| p.into_group()).collect::<Vec<E::G1>>();
+    let to_g2 = |v: &[E::G2Affine]| v.iter().map(|p| p.into_group()).collect::<Vec<E::G2>>();
+    let mut v = [to_g2(&srs.h_alpha[..n]), to_g2(&srs.h_beta[..n])];
+    let mut w = [to_g1(&srs.g_alpha[n..2 * n]), to_g1(&srs.g_beta[n..2 * n])];
+
+    // 承诺
+    let com_ab = [ipp::<E>(&a, &v[0]) + ipp::<E>(&w[0], &b), ipp::<E>(&a, &v[1]) + ipp::<E>(&w[1], &b)];
+    let com_c = [ipp::<E>(&c, &v[0]), ipp::<E>(&c, &v[1])];
+
+    let mut transcript = Transcript::new();
+    transcript.append(&(n as u64));
+    transcript.append(&padded_inputs(public_inputs, n));
+    transcript.append(&com_ab);
+    transcript.append(&com_c);
+    let r: E::ScalarField = transcript.challenge();
+    let r_inv = r.inverse().expect("challenge is non-zero");
+
+    // A'_i = r^i·A_i；TIPP密钥 v'_i = r^{-i}·v_i，使 (A', v') 的承诺等于 (A, v) 的承诺
+    let mut r_vec: Vec<E::ScalarField> = std::iter::successors(Some(E::ScalarField::one()), |p| Some(*p * r))
+        .take(n)
+        .collect();
+    let r_inv_vec: Vec<E::ScalarField> = std::iter::successors(Some(E::ScalarField::one()), |p| Some(*p * r_inv))
+        .take(n)
+        .collect();
+    let mut a: Vec<E::G1> = a.iter().zip(&r_vec).map(|(p, s)| *p * s).collect();
+    let mut vp = [
+        v[0].iter().zip(&r_inv_vec).map(|(p, s)| *p * s).collect::<Vec<_>>(),
+        v[1].iter().zip(&r_inv_vec).map(|(p, s)| *p * s).collect::<Vec<_>>(),
+    ];
+
+    let z_ab = ipp::<E>(&a, &b);
+    let z_c = msm(&c, &r_vec).into_affine();
+    transcript.append(&z_ab);
+    transcript.append(&z_c);
+
+    // GIPA：逐轮折半
+    let mut rounds = Vec::new();
+    let mut challenges = Vec::new();
+    while a.len() > 1 {
+        let m = a.len() / 2;
+        let (a_l, a_r) = a.split_at(m);
+        let (b_l, b_r) = b.split_at(m);
+        let (c_l, c_r) = c.split_at(m);
+        let (r_l, r_r) = r_vec.split_at(m);
+        let half = |k: &[E::G2]| (k[..m].to_vec(), k[m..].to_vec());
+        let half_g1 = |k: &[E::G1]| (k[..m].to_vec(), k[m..].to_vec());
+        let (vp0_l, vp0_r) = half(&vp[0]);
+        let (vp1_l, vp1_r) = half(&vp[1]);
+        let (v0_l, v0_r) = half(&v[0]);
+        let (v1_l, v1_r) = half(&v[1]);
+        let (w0_l, w0_r) = half_g1(&w[0]);
+        let (w1_l, w1_r) = half_g1(&w[1]);
+
+        let round = GipaRound {
+            com_ab_l: [
+                ipp::<E>(a_r, &vp0_l) + ipp::<E>(&w0_r, b_l),
+                ipp::<E>(a_r, &vp1_l) + ipp::<E>(&w1_r, b_l),
+            ],
+            com_ab_r: [
+                ipp::<E>(a_l, &vp0_r) + ipp::<E>(&w0_l, b_r),
+                ipp::<E>(a_l, &vp1_r) + ipp::<E>(&w1_l, b_r),
+            ],
+            z_ab_l: ipp::<E>(a_r, b_l),
+            z_ab_r: ipp::<E>(a_l, b_r),
+            com_c_l: [ipp::<E>(c_r, &v0_l), ipp::<E>(c_r, &v1_l)],
+            com_c_r: [ipp::<E>(c_l, &v0_r), ipp::<E>(c_l, &v1_r)],
+            z_c_l: msm(c_r, r_l).into_affine(),
+            z_c_r: msm(c_l, r_r).into_affine(),
+        };
+        transcript.append(&round);
+        let x: E::ScalarField = transcript.challenge();
+        let x_inv = x.inverse().expect("challenge is non-zero");
+
+        a = fold(&a, x);
+        b = fold(&b, x_inv);
+        c = fold(&c, x);
+        r_vec = fold(&r_vec, x_inv);
+        vp = [fold(&vp[0], x_inv), fold(&vp[1], x_inv)];
+        v = [fold(&v[0], x_inv), fold(&v[1], x_inv)];
+        w = [fold(&w[0], x), fold(&w[1], x)];
+
+        rounds.push(round);
+        challenges.push(x);
+    }
+
+    let final_vp = [vp[0][0].into_affine(), vp[1][0].into_affine()];
+    let final_v = [v[0][0].into_affine(), v[1][0].into_affine()];
+    let final_w = [w[0][0].into_affine(), w[1][0].into_affine()];
+    transcript.append(&final_vp);
+    transcript.append(&final_v);
+    transcript.append(&final_w);
+    let z: E::ScalarField = transcript.challenge();
+
+    // 折叠后的密钥是原密钥按折叠多项式系数的组合，用KZG证明之
+    let challenges_inv: Vec<E::ScalarField> = challenges.iter().map(|x| x.inverse().expect("non-zero")).collect();
+    let f_v = folding_poly(&challenges_inv);
+    let f_vp: Vec<E::ScalarField> = f_v.iter().zip(&r_inv_vec).map(|(c, s)| *c * s).collect();
+    let mut f_w = vec![E::ScalarField::zero(); n];
+    f_w.extend(folding_poly(&challenges));
+
+    Ok(AggregateProof {
+        num_proofs: proofs.len() as u64,
+        com_ab,
+        com_c,
+        z_ab,
+        z_c,
+        rounds,
+        final_a: a[0].into_affine(),
+        final_b: b[0].into_affine(),
+        final_c: c[0].into_affine(),
+        final_vp,
+        final_v,
+        final_w,
+        opening_vp: [kzg_open(&srs.h_alpha, &f_vp, z), kzg_open(&srs.h_beta, &f_vp, z)],
+        opening_v: [kzg_open(&srs.h_alpha, &f_v, z), kzg_open(&srs.h_beta, &f_v, z)],
+        opening_w: [kzg_open(&srs.g_alpha, &f_w, z), kzg_open(&srs.g_beta, &f_w, z)],
+    })
+}
+
+// 验证聚合证明
+// 证明无效时返回 Ok(false)，公开输入与证明结构不匹配时返回错误
+pub fn verify_aggregate_proof<E: Pairing>(
+    vk: &VerifyingKey<E>,
+    srs_vk: &AggregationVerifierKey<E>,
+    public_inputs: &[Vec<E::ScalarField>],
+    proof: &AggregateProof<E>,
+) -> Result<bool, VoteError> {
+    if public_inputs.is_empty() || proof.num_proofs != public_inputs.len() as u64 {
+        return Err(VoteError::Aggregation(format!(
+            "aggregate covers {} proofs but {} public input vectors were given",
+            proof.num_proofs,
+            public_inputs.len()
+        )));
+    }
+    let n = aggregation_size(public_inputs.len());
+    if proof.rounds.len() != n.trailing_zeros() as usize {
+        return Ok(false);
+    }
+    if public_inputs.iter().any(|x| x.len() + 1 != vk.gamma_abc_g1.len()) {
+        return Err(VoteError::Aggregation("public input length does not match verifying key".to_string()));
+    }
+
+    // 重放转录得到全部挑战
+    let padded = padded_inputs(public_inputs, n);
+    let mut transcript = Transcript::new();
+    transcript.append(&(n as u64));
+    transcript.append(&padded);
+    transcript.append(&proof.com_ab);
+    transcript.append(&proof.com_c);
+    let r: E::ScalarField = transcript.challenge();
+    transcript.append(&proof.z_ab);
+    transcript.append(&proof.z_c);
+
+    let mut com_ab = proof.com_ab;
+    let mut com_c = proof.com_c;
+    let mut z_ab = proof.z_ab;
+    let mut z_c = proof.z_c.into_group();
+    let mut challenges = Vec::with_capacity(proof.rounds.len());
+    for round in &proof.rounds {
+        transcript.append(round);
+        let x: E::ScalarField = transcript.challenge();
+        let x_inv = x.inverse().expect("challenge is non-zero");
+        for k in 0..2 {
+            com_ab[k] = round.com_ab_l[k] * x + com_ab[k] + round.com_ab_r[k] * x_inv;
+            com_c[k] = round.com_c_l[k] * x + com_c[k] + round.com_c_r[k] * x_inv;
+        }
+        z_ab = round.z_ab_l * x + z_ab + round.z_ab_r * x_inv;
+        z_c = round.z_c_l * x + z_c + round.z_c_r * x_inv;
+        challenges.push(x);
+    }
+    transcript.append(&proof.final_vp);
+    transcript.append(&proof.final_v);
+    transcript.append(&proof.final_w);
+    let z: E::ScalarField = transcript.challenge();
+
+    // 1. Groth16 验证方程的随机线性组合
+    let r_powers: Vec<E::ScalarField> = std::iter::successors(Some(E::ScalarField::one()), |p| Some(*p * r))
+        .take(n)
+        .collect();
+    let r_sum: E::ScalarField = r_powers.iter().sum();
+    let mut input_scalars = vec![r_sum];
+    for j in 0..vk.gamma_abc_g1.len() - 1 {
+        input_scalars.push(padded.iter().zip(&r_powers).map(|(x, s)| x[j] * s).sum());
+    }
+    let vk_x = E::G1::msm_unchecked(&vk.gamma_abc_g1, &input_scalars);
+    let expected_z_ab = E::pairing(vk.alpha_g1, vk.beta_g2) * r_sum
+        + E::pairing(vk_x, vk.gamma_g2)
+        + E::pairing(proof.z_c, vk.delta_g2);
+    if expected_z_ab != proof.z_ab {
+        debug!("Aggregate rejected: Groth16 equation does not hold");
+        return Ok(false);
+    }
+
+    // 2. TIPP/MIPP 最终一步
+    let final_a = proof.final_a;
+    let final_b = proof.final_b;
+    let final_c = proof.final_c;
+    for k in 0..2 {
+        if com_ab[k] != E::pairing(final_a, proof.final_vp[k]) + E::pairing(proof.final_w[k], final_b) {
+            debug!("Aggregate rejected: TIPP commitment mismatch");
+            return Ok(false);
+        }
+        if com_c[k] != E::pairing(final_c, proof.final_v[k]) {
+            debug!("Aggregate rejected: MIPP commitment mismatch");
+            return Ok(false);
+        }
+    }
+    if z_ab != E::pairing(final_a, final_b) {
+        debug!("Aggregate rejected: TIPP inner product mismatch");
+        return Ok(false);
+    }
+    let challenges_inv: Vec<E::ScalarField> = challenges.iter().map(|x| x.inverse().expect("non-zero")).collect();
+    let r_final = eval_folding_poly(&challenges_inv, r);
+    if z_c != final_c * r_final {
+        debug!("Aggregate rejected: MIPP inner product mismatch");
+        return Ok(false);
+    }
+
+    // 3. 折叠密钥的KZG打开
+    let r_inv = r.inverse().expect("challenge is non-zero");
+    let v_eval = eval_folding_poly(&challenges_inv, z);
+    let vp_eval = eval_folding_poly(&challenges_inv, z * r_inv);
+    let w_eval = z.pow([n as u64]) * eval_folding_poly(&challenges, z);
+
+    let g = srs_vk.g.into_group();
+    let h = srs_vk.h.into_group();
+    let g_secrets = [srs_vk.g_alpha, srs_vk.g_beta];
+    let h_secrets = [srs_vk.h_alpha, srs_vk.h_beta];
+    for k in 0..2 {
+        let g_shift = g_secrets[k].into_group() - g * z;
+        let h_shift = h_secrets[k].into_group() - h * z;
+        let checks = [
+            E::pairing(g, proof.final_v[k].into_group() - h * v_eval) == E::pairing(g_shift, proof.opening_v[k]),
+            E::pairing(g, proof.final_vp[k].into_group() - h * vp_eval) == E::pairing(g_shift, proof.opening_vp[k]),
+            E::pairing(proof.final_w[k].into_group() - g * w_eval, h) == E::pairing(proof.opening_w[k], h_shift),
+        ];
+        if checks.contains(&false) {
+            debug!("Aggregate rejected: commitment key opening failed");
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// 分块聚合，每块至多 chunk_size 个证明
+pub fn aggregate_chunked<E: Pairing>(
+    srs: &AggregationSrs<E>,
+    proofs: &[Proof<E>],
+    public_inputs: &[Vec<E::ScalarField>],
+    chunk_size: usize,
+) -> Result<ChunkedAggregateProof<E>, VoteError> {
+    if chunk_size == 0 || proofs.len() != public_inputs.len() {
+        return Err(VoteError::Aggregation("invalid chunk size or mismatched inputs".to_string()));
+    }
+    let chunks = proofs
+        .chunks(chunk_size)
+        .zip(public_inputs.chunks(chunk_size))
+        .map(|(p, x)| aggregate_proofs(srs, p, x))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ChunkedAggregateProof { chunks })
+}
+
+// 验证分块聚合证明，公开输入按各块的证明数量依次切分
+pub fn verify_chunked_aggregate<E: Pairing>(
+    vk: &VerifyingKey<E>,
+    srs_vk: &AggregationVerifierKey<E>,
+    public_inputs: &[Vec<E::ScalarField>],
+    proof: &ChunkedAggregateProof<E>,
+) -> Result<bool, VoteError> {
+    let total: u64 = proof.chunks.iter().map(|c| c.num_proofs).sum();
+    if total != public_inputs.len() as u64 {
+        return Err(VoteError::Aggregation(format!(
+            "chunked aggregate covers {} proofs but {} public input vectors were given",
+            total,
+            public_inputs.len()
+        )));
+    }
+    let mut offset = 0;
+    for chunk in &proof.chunks {
+        let end = offset + chunk.num_proofs as usize;
+        if !verify_aggregate_proof(vk, srs_vk, &public_inputs[offset..end], chunk)? {
+            return Ok(false);
+        }
+        offset = end;
+    }
+    Ok(true)
+}
@@ -0,0 +1,120 @@
+// 验证事件审计日志
+//
+// 只追加的哈希链：每条记录的摘要覆盖其序号、时间、事件内容和上一条记录的摘要，
+// 第一条记录的上一摘要为全零。修改、删除或调换任何一条记录都会使之后的链接断开，
+// 外部可以取回整条链（GET /audit）用 verify_chain 独立复核。
+// 哈希链只能发现篡改，不能防止运营方整体重写日志；需要时应定期在外部（如区块链）公布链头摘要。
+
+use crate::VoteError;
+use chrono::{DateTime, Utc};
+use tracing::error;
+
+// 摘要的域分隔标签
+const AUDIT_DIGEST_CONTEXT: &str = "zkp-vote 2024 audit log entry";
+
+// 验证入口
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Verify,       // POST /verify
+    VerifyBatch,  // POST /verify/batch 中的一个条目
+    ClientBallot, // POST /vote 提交的客户端证明
+}
+
+// 一次验证事件
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+    pub election_id: Option<String>, // 选举标识（请求中提供时）
+    pub nullifier: Option<String>,   // 十进制防重标识（请求格式错误、无法解析时为空）
+    pub outcome: String,             // 结果："valid"、证明无效的原因码或错误码
+}
+
+// 审计日志中的一条记录
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AuditEntry {
+    pub index: u64,
+    pub timestamp: DateTime<Utc>,
+    pub event: AuditEvent,
+    pub prev_hash: String, // 上一条记录的十六进制摘要
+    pub hash: String,      // 本记录的十六进制摘要
+}
+
+impl AuditEntry {
+    // 计算除摘要字段外全部内容的摘要
+    pub fn compute_hash(&self) -> String {
+        let mut content = self.clone();
+        content.hash = String::new();
+        let bytes = serde_json::to_vec(&content).expect("serializing an audit entry cannot fail");
+        hex::encode(blake3::derive_key(AUDIT_DIGEST_CONTEXT, &bytes))
+    }
+}
+
+// 只追加的审计日志
+#[derive(Clone, Debug, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 追加一条事件，返回新记录
+    pub fn append(&mut self, event: AuditEvent) -> &AuditEntry {
+        let mut entry = AuditEntry {
+            index: self.entries.len() as u64,
+            timestamp: Utc::now(),
+            event,
+            prev_hash: self.head(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        self.entries.push(entry);
+        self.entries.last().expect("entry was just appended")
+    }
+
+    // 链头摘要（空日志为全零）
+    pub fn head(&self) -> String {
+        self.entries
+            .last()
+            .map(|entry| entry.hash.clone())
+            .unwrap_or_else(|| hex::encode([0u8; 32]))
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// 复核哈希链：序号连续、每条记录的摘要与内容一致、且链接到上一条记录
+// 失败时返回 AuditIntegrity，信息中指出第一条出错的记录
+pub fn verify_chain(entries: &[AuditEntry]) -> Result<(), VoteError> {
+    let mut prev_hash = hex::encode([0u8; 32]);
+    for (position, entry) in entries.iter().enumerate() {
+        let problem = if entry.index != position as u64 {
+            Some("index is out of sequence")
+        } else if entry.prev_hash != prev_hash {
+            Some("does not link to the previous entry")
+        } else if entry.compute_hash() != entry.hash {
+            Some("hash does not match the entry content")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            error!("Audit log entry {} failed the integrity check: {}", position, problem);
+            return Err(VoteError::AuditIntegrity(format!("entry {} {}", position, problem)));
+        }
+        prev_hash = entry.hash.clone();
+    }
+    Ok(())
+}
@@ -0,0 +1,531 @@
+// 证明系统后端抽象
+//
+// VoteSystem、计票证明和Web层都只依赖 ProofSystem 这一抽象，
+// 具体后端为 Groth16；实现 ProofSystem（通用可信设置的后端再实现 UniversalProofSystem）即可接入其他证明系统。
+
+use crate::VoteError;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate};
+use ark_std::rand::{CryptoRng, RngCore};
+
+// 点的编码格式：压缩格式只保存x坐标和符号位，体积约为非压缩格式的一半，但反序列化时需要开方
+// 序列化时由调用方选择，反序列化时自动识别（密钥文件和证明信封在头部记录所用格式）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    Compressed,
+    Uncompressed,
+}
+
+impl Compression {
+    // 头部中的编码
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Compression::Compressed => 0,
+            Compression::Uncompressed => 1,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Result<Self, VoteError> {
+        match value {
+            0 => Ok(Compression::Compressed),
+            1 => Ok(Compression::Uncompressed),
+            _ => Err(VoteError::Serialization(SerializationError::InvalidData)),
+        }
+    }
+}
+
+impl From<Compression> for Compress {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Compressed => Compress::Yes,
+            Compression::Uncompressed => Compress::No,
+        }
+    }
+}
+
+impl From<Compress> for Compression {
+    fn from(compress: Compress) -> Self {
+        match compress {
+            Compress::Yes => Compression::Compressed,
+            Compress::No => Compression::Uncompressed,
+        }
+    }
+}
+
+// 按指定格式序列化
+pub(crate) fn serialize_with<T: CanonicalSerialize>(value: &T, compression: Compression) -> Result<Vec<u8>, VoteError> {
+    let compress = Compress::from(compression);
+    let mut bytes = Vec::with_capacity(value.serialized_size(compress));
+    value.serialize_with_mode(&mut bytes, compress)?;
+    Ok(bytes)
+}
+
+// 自动识别格式反序列化（先试压缩格式），要求输入被完整消费，返回识别出的格式
+pub(crate) fn deserialize_auto<T: CanonicalDeserialize>(bytes: &[u8], validate: Validate) -> Result<(T, Compression), VoteError> {
+    match deserialize_exact(bytes, Compress::Yes, validate) {
+        Ok(value) => Ok((value, Compression::Compressed)),
+        Err(_) => Ok((deserialize_exact(bytes, Compress::No, validate)?, Compression::Uncompressed)),
+    }
+}
+
+// 按指定格式反序列化，要求输入被完整消费（多余的字节视为格式错误）
+pub(crate) fn deserialize_exact<T: CanonicalDeserialize>(bytes: &[u8], compress: Compress, validate: Validate) -> Result<T, VoteError> {
+    let mut reader = bytes;
+    let value = T::deserialize_with_mode(&mut reader, compress, validate)?;
+    if !reader.is_empty() {
+        return Err(VoteError::Serialization(SerializationError::InvalidData));
+    }
+    Ok(value)
+}
+
+// 证明系统后端
+pub trait ProofSystem<F: PrimeField>: Send + Sync + 'static {
+    type ProvingKey: Clone + Send + Sync + CanonicalSerialize + CanonicalDeserialize + 'static;
+    type VerifyingKey: Clone + Send + Sync + CanonicalSerialize + CanonicalDeserialize + 'static;
+    type Proof: Clone + Send + Sync + CanonicalSerialize + CanonicalDeserialize + 'static;
+
+    // 后端名称（用于日志和序列化格式标识）
+    const NAME: &'static str;
+
+    // 为给定电路生成证明密钥和验证密钥
+    fn setup<C, R>(circuit: C, rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+    where
+        C: ConstraintSynthesizer<F> + Clone,
+        R: RngCore + CryptoRng;
+
+    // 生成证明
+    fn prove<C, R>(pk: &Self::ProvingKey, circuit: C, rng: &mut R) -> Result<Self::Proof, VoteError>
+    where
+        C: ConstraintSynthesizer<F>,
+        R: RngCore + CryptoRng;
+
+    // 验证证明
+    fn verify(vk: &Self::VerifyingKey, public_inputs: &[F], proof: &Self::Proof) -> Result<bool, VoteError>;
+
+    // 验证密钥期望的公开输入数量，后端无法精确给出时返回None
+    fn num_public_inputs(_vk: &Self::VerifyingKey) -> Option<usize> {
+        None
+    }
+
+    // 证明密钥中内嵌的验证密钥，证明密钥不携带验证密钥的后端返回None
+    fn embedded_verifying_key(_pk: &Self::ProvingKey) -> Option<Self::VerifyingKey> {
+        None
+    }
+
+    // 检查验证密钥的结构合法性（反序列化时调用），后端应拒绝群单位元等退化元素，返回 KeyMismatch
+    fn check_verifying_key(_vk: &Self::VerifyingKey) -> Result<(), VoteError> {
+        Ok(())
+    }
+
+    // 检查证明密钥自身是否一致（内嵌的验证密钥与证明密钥的其余部分出自同一次设置），
+    // 不一致时返回 KeyMismatch；后端无法检查时返回Ok
+    fn check_proving_key(_pk: &Self::ProvingKey) -> Result<(), VoteError> {
+        Ok(())
+    }
+
+    // 批量验证证明，逐条返回结果；默认实现逐个验证，后端可覆盖为合并的批量检查
+    fn verify_batch(vk: &Self::VerifyingKey, batch: &[(Vec<F>, Self::Proof)]) -> Vec<Result<bool, VoteError>> {
+        batch
+            .iter()
+            .map(|(public_inputs, proof)| Self::verify(vk, public_inputs, proof))
+            .collect()
+    }
+
+    // 序列化证明（压缩格式）
+    fn serialize_proof(proof: &Self::Proof) -> Result<Vec<u8>, VoteError> {
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // 按指定格式序列化证明
+    fn serialize_proof_with(proof: &Self::Proof, compression: Compression) -> Result<Vec<u8>, VoteError> {
+        match compression {
+            Compression::Compressed => Self::serialize_proof_compressed(proof),
+            Compression::Uncompressed => Self::serialize_proof_uncompressed(proof),
+        }
+    }
+
+    // 检查证明的结构合法性（在验证之前调用）
+    // 恶意客户端可能提交由群单位元等退化点构成的证明，部分验证实现对此处理不当；
+    // 后端应拒绝单位元和不在素数阶子群中的点，返回 InvalidProofEncoding
+    fn check_proof(_proof: &Self::Proof) -> Result<(), VoteError> {
+        Ok(())
+    }
+
+    // 反序列化证明（自动识别压缩或非压缩格式）
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, VoteError> {
+        Ok(Self::deserialize_proof_auto(bytes)?.0)
+    }
+
+    // 以压缩格式序列化证明（只保存点的x坐标和符号位，体积小，反序列化时需要开方）
+    // BN254上的Groth16证明为128字节
+    fn serialize_proof_compressed(proof: &Self::Proof) -> Result<Vec<u8>, VoteError> {
+        let mut bytes = Vec::with_capacity(proof.compressed_size());
+        proof.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // 以非压缩格式序列化证明（保存完整坐标，体积翻倍，反序列化更快）
+    // BN254上的Groth16证明为256字节
+    fn serialize_proof_uncompressed(proof: &Self::Proof) -> Result<Vec<u8>, VoteError> {
+        let mut bytes = Vec::with_capacity(proof.uncompressed_size());
+        proof.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // 按指定格式反序列化证明，要求输入被完整消费
+    // 点必须在曲线上且位于素数阶子群中，之后再做 check_proof 的结构检查
+    fn deserialize_proof_with_mode(bytes: &[u8], compress: Compress) -> Result<Self::Proof, VoteError> {
+        let proof: Self::Proof = deserialize_exact(bytes, compress, Validate::Yes)?;
+        Self::check_proof(&proof)?;
+        Ok(proof)
+    }
+
+    // 自动识别格式反序列化证明，返回证明及识别出的格式
+    // 能按某种格式解码但结构非法的证明直接报 InvalidProofEncoding，不再尝试其他格式
+    fn deserialize_proof_auto(bytes: &[u8]) -> Result<(Self::Proof, Compress), VoteError> {
+        for compress in [Compress::Yes, Compress::No] {
+            match Self::deserialize_proof_with_mode(bytes, compress) {
+                Ok(proof) => return Ok((proof, compress)),
+                Err(e @ VoteError::InvalidProofEncoding(_)) => return Err(e),
+                Err(_) => {}
+            }
+        }
+        Err(VoteError::Serialization(SerializationError::InvalidData))
+    }
+
+    // 序列化证明密钥（压缩格式）
+    fn serialize_proving_key(pk: &Self::ProvingKey) -> Result<Vec<u8>, VoteError> {
+        Self::serialize_proving_key_with(pk, Compression::Compressed)
+    }
+
+    // 按指定格式序列化证明密钥；非压缩格式体积约翻倍，但加载时省去大量开方
+    fn serialize_proving_key_with(pk: &Self::ProvingKey, compression: Compression) -> Result<Vec<u8>, VoteError> {
+        serialize_with(pk, compression)
+    }
+
+    // 反序列化证明密钥（自动识别格式，校验曲线点与子群，并检查证明密钥自身一致）
+    fn deserialize_proving_key(bytes: &[u8]) -> Result<Self::ProvingKey, VoteError> {
+        let (pk, _) = deserialize_auto(bytes, Validate::Yes)?;
+        Self::check_proving_key(&pk)?;
+        Ok(pk)
+    }
+
+    // 不做任何校验地反序列化证明密钥（自动识别格式），仅用于本机生成且未经他人之手的可信文件
+    // 证明密钥很大时校验耗时明显；来源不可信的密钥必须使用 deserialize_proving_key
+    fn deserialize_proving_key_unchecked(bytes: &[u8]) -> Result<Self::ProvingKey, VoteError> {
+        Ok(deserialize_auto(bytes, Validate::No)?.0)
+    }
+
+    // 序列化验证密钥（压缩格式）
+    fn serialize_verifying_key(vk: &Self::VerifyingKey) -> Result<Vec<u8>, VoteError> {
+        Self::serialize_verifying_key_with(vk, Compression::Compressed)
+    }
+
+    // 按指定格式序列化验证密钥
+    fn serialize_verifying_key_with(vk: &Self::VerifyingKey, compression: Compression) -> Result<Vec<u8>, VoteError> {
+        serialize_with(vk, compression)
+    }
+
+    // 反序列化验证密钥（自动识别格式，校验曲线点与子群，并拒绝退化的密钥）
+    fn deserialize_verifying_key(bytes: &[u8]) -> Result<Self::VerifyingKey, VoteError> {
+        let (vk, _) = deserialize_auto(bytes, Validate::Yes)?;
+        Self::check_verifying_key(&vk)?;
+        Ok(vk)
+    }
+
+    // 不做任何校验地反序列化验证密钥（自动识别格式），仅用于本机生成且未经他人之手的可信文件
+    fn deserialize_verifying_key_unchecked(bytes: &[u8]) -> Result<Self::VerifyingKey, VoteError> {
+        Ok(deserialize_auto(bytes, Validate::No)?.0)
+    }
+}
+
+// 通用可信设置的证明系统
+// 一份SRS支持规模不超过上限的任意电路，每个电路只需确定性地索引即可得到密钥，
+// 调整候选人数量等电路改动不再需要新的仪式
+pub trait UniversalProofSystem<F: PrimeField>: ProofSystem<F> {
+    type UniversalSrs: Send + Sync + 'static;
+
+    // 生成支持至多 max_constraints 个约束的通用SRS
+    fn universal_setup<R: RngCore + CryptoRng>(max_constraints: usize, rng: &mut R) -> Result<Self::UniversalSrs, VoteError>;
+
+    // 用通用SRS索引电路，得到该电路的证明密钥和验证密钥（确定性过程）
+    fn index<C: ConstraintSynthesizer<F>>(
+        srs: &Self::UniversalSrs,
+        circuit: C,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>;
+}
+
+// Groth16 后端
+mod groth16_backend {
+    use super::*;
+    use ark_crypto_primitives::snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_ec::pairing::Pairing;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::{UniformRand, Zero};
+    use ark_groth16::Groth16;
+
+    // 检查证明中的点：不能是单位元，且必须位于素数阶子群中（r*P = O）
+    fn check_point<A: AffineRepr>(point: &A, name: &str) -> Result<(), VoteError> {
+        if point.is_zero() {
+            return Err(VoteError::InvalidProofEncoding(format!("proof element {} is the identity", name)));
+        }
+        if !point.mul_bigint(<A::ScalarField as PrimeField>::MODULUS).is_zero() {
+            return Err(VoteError::InvalidProofEncoding(format!(
+                "proof element {} is not in the prime-order subgroup",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    impl<E: Pairing> ProofSystem<E::ScalarField> for Groth16<E> {
+        type ProvingKey = ark_groth16::ProvingKey<E>;
+        type VerifyingKey = ark_groth16::VerifyingKey<E>;
+        type Proof = ark_groth16::Proof<E>;
+
+        const NAME: &'static str = "groth16";
+
+        fn setup<C, R>(circuit: C, rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+        where
+            C: ConstraintSynthesizer<E::ScalarField> + Clone,
+            R: RngCore + CryptoRng,
+        {
+            <Groth16<E> as CircuitSpecificSetupSNARK<E::ScalarField>>::setup(circuit, rng)
+                .map_err(|e| VoteError::Setup(Box::new(e)))
+        }
+
+        fn prove<C, R>(pk: &Self::ProvingKey, circuit: C, rng: &mut R) -> Result<Self::Proof, VoteError>
+        where
+            C: ConstraintSynthesizer<E::ScalarField>,
+            R: RngCore + CryptoRng,
+        {
+            <Groth16<E> as SNARK<E::ScalarField>>::prove(pk, circuit, rng)
+                .map_err(|e| VoteError::Proving(Box::new(e)))
+        }
+
+        fn verify(
+            vk: &Self::VerifyingKey,
+            public_inputs: &[E::ScalarField],
+            proof: &Self::Proof,
+        ) -> Result<bool, VoteError> {
+            <Groth16<E> as SNARK<E::ScalarField>>::verify(vk, public_inputs, proof)
+                .map_err(|e| VoteError::Verification(Box::new(e)))
+        }
+
+        // 诚实证明者生成的 A、B、C 均为随机化的子群元素，为单位元的概率可忽略
+        fn check_proof(proof: &Self::Proof) -> Result<(), VoteError> {
+            check_point(&proof.a, "A")?;
+            check_point(&proof.b, "B")?;
+            check_point(&proof.c, "C")
+        }
+
+        // gamma_abc_g1 的第0项对应常数1
+        fn num_public_inputs(vk: &Self::VerifyingKey) -> Option<usize> {
+            vk.gamma_abc_g1.len().checked_sub(1)
+        }
+
+        fn embedded_verifying_key(pk: &Self::ProvingKey) -> Option<Self::VerifyingKey> {
+            Some(pk.vk.clone())
+        }
+
+        // alpha、beta、gamma、delta 为单位元时配对方程退化，任意证明都可能通过验证
+        fn check_verifying_key(vk: &Self::VerifyingKey) -> Result<(), VoteError> {
+            for (name, is_identity) in [
+                ("alpha_g1", vk.alpha_g1.is_zero()),
+                ("beta_g2", vk.beta_g2.is_zero()),
+                ("gamma_g2", vk.gamma_g2.is_zero()),
+                ("delta_g2", vk.delta_g2.is_zero()),
+            ] {
+                if is_identity {
+                    return Err(VoteError::KeyMismatch(format!("verifying key element {} is the identity", name)));
+                }
+            }
+            Ok(())
+        }
+
+        // 证明密钥中的 beta_g1、delta_g1 与内嵌验证密钥中的 beta_g2、delta_g2 由同一陷门生成，
+        // 检查 e(beta_g1, G2) = e(G1, beta_g2) 和 e(delta_g1, G2) = e(G1, delta_g2)
+        fn check_proving_key(pk: &Self::ProvingKey) -> Result<(), VoteError> {
+            Self::check_verifying_key(&pk.vk)?;
+            // 可信设置使用随机生成元而非标准生成元，因此以 B 查询中同一多项式在
+            // G1/G2 上的一对取值 (g1^b, g2^b) 作为跨群锚点：
+            // e(x_g1, g2^b) = e(g1^b, x_g2) 当且仅当两侧的 x 相同
+            let (b_g1, b_g2) = pk
+                .b_g1_query
+                .iter()
+                .zip(pk.b_g2_query.iter())
+                .find(|(b_g1, b_g2)| !b_g1.is_zero() && !b_g2.is_zero())
+                .ok_or_else(|| VoteError::KeyMismatch("proving key has an empty B query".to_string()))?;
+            for (name, x_g1, x_g2) in [
+                ("beta", pk.beta_g1, pk.vk.beta_g2),
+                ("delta", pk.delta_g1, pk.vk.delta_g2),
+            ] {
+                if E::pairing(x_g1, *b_g2) != E::pairing(*b_g1, x_g2) {
+                    return Err(VoteError::KeyMismatch(format!(
+                        "{}_g1 does not match the embedded verifying key's {}_g2",
+                        name, name
+                    )));
+                }
+            }
+            Ok(())
+        }
+
+        // 随机线性组合批量验证：对每个证明取随机系数 r_i，检查
+        // prod e(r_i*A_i, B_i) = e(sum(r_i)*alpha, beta) * e(sum(r_i*vk_x_i), gamma) * e(sum(r_i*C_i), delta)
+        // 整批通过则全部有效，否则退回逐个验证以定位无效证明
+        fn verify_batch(
+            vk: &Self::VerifyingKey,
+            batch: &[(Vec<E::ScalarField>, Self::Proof)],
+        ) -> Vec<Result<bool, VoteError>> {
+            let pvk = ark_groth16::prepare_verifying_key(vk);
+            let mut rng = rand::thread_rng();
+
+            let mut results: Vec<Option<Result<bool, VoteError>>> = Vec::with_capacity(batch.len());
+            let mut g1 = Vec::with_capacity(batch.len() + 3);
+            let mut g2 = Vec::with_capacity(batch.len() + 3);
+            let mut r_sum = E::ScalarField::zero();
+            let mut vk_x_sum = E::G1::zero();
+            let mut c_sum = E::G1::zero();
+
+            for (public_inputs, proof) in batch {
+                // 公开输入数量不符的证明单独报错，不参与合并检查
+                match Groth16::<E>::prepare_inputs(&pvk, public_inputs) {
+                    Ok(vk_x) => {
+                        let r = E::ScalarField::rand(&mut rng);
+                        g1.push((proof.a * r).into_affine());
+                        g2.push(proof.b);
+                        r_sum += r;
+                        vk_x_sum += vk_x * r;
+                        c_sum += proof.c * r;
+                        results.push(None);
+                    }
+                    Err(e) => results.push(Some(Err(VoteError::Verification(Box::new(e))))),
+                }
+            }
+
+            if !g1.is_empty() {
+                g1.push((vk.alpha_g1 * -r_sum).into_affine());
+                g2.push(vk.beta_g2);
+                g1.push((-vk_x_sum).into_affine());
+                g2.push(vk.gamma_g2);
+                g1.push((-c_sum).into_affine());
+                g2.push(vk.delta_g2);
+
+                if E::multi_pairing(g1, g2).is_zero() {
+                    return results.into_iter().map(|r| r.unwrap_or(Ok(true))).collect();
+                }
+            }
+
+            results
+                .into_iter()
+                .zip(batch)
+                .map(|(r, (public_inputs, proof))| r.unwrap_or_else(|| <Self as ProofSystem<E::ScalarField>>::verify(vk, public_inputs, proof)))
+                .collect()
+        }
+    }
+}
+
+// 测试用的模拟后端（test-mock 特性，本 crate 的单元测试总是可用）
+// 警告：不提供任何安全性。"证明"只是密钥标识与公开输入的摘要，任何人都能为任意公开输入伪造；
+// prove 仍会合成电路并检查约束，违反约束的选票照样被拒绝。
+// 密钥生成和证明都是常数级耗时，供 Web 处理、计票、防重标识等逻辑测试使用
+// 发布构建中启用 test-mock 须同时显式启用 insecure-mock-release，避免模拟后端被误带上线
+#[cfg(all(feature = "test-mock", not(debug_assertions), not(feature = "insecure-mock-release")))]
+compile_error!("the `test-mock` feature is insecure and disabled in release builds; enable `insecure-mock-release` to opt in explicitly");
+
+#[cfg(any(test, feature = "test-mock"))]
+pub mod mock_backend {
+    use super::*;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisMode};
+
+    // 证明摘要的域分隔标签
+    const MOCK_PROOF_CONTEXT: &str = "zkp-vote 2024 mock snark proof";
+
+    // 模拟后端
+    pub struct MockSNARK;
+
+    // 模拟密钥（证明密钥与验证密钥相同）
+    #[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+    pub struct MockKey {
+        pub key_id: [u8; 32],       // setup 时随机生成，区分不同的设置
+        pub num_public_inputs: u64, // 电路的公开输入数量
+    }
+
+    // 模拟证明
+    #[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+    pub struct MockProof {
+        pub digest: [u8; 32],
+    }
+
+    // 密钥标识与公开输入的摘要
+    fn proof_digest<F: PrimeField>(key: &MockKey, public_inputs: &[F]) -> Result<[u8; 32], VoteError> {
+        let mut bytes = key.key_id.to_vec();
+        for input in public_inputs {
+            input.serialize_compressed(&mut bytes)?;
+        }
+        Ok(blake3::derive_key(MOCK_PROOF_CONTEXT, &bytes))
+    }
+
+    impl<F: PrimeField> ProofSystem<F> for MockSNARK {
+        type ProvingKey = MockKey;
+        type VerifyingKey = MockKey;
+        type Proof = MockProof;
+
+        const NAME: &'static str = "mock";
+
+        fn setup<C, R>(circuit: C, rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+        where
+            C: ConstraintSynthesizer<F> + Clone,
+            R: RngCore + CryptoRng,
+        {
+            let cs = ConstraintSystem::<F>::new_ref();
+            cs.set_mode(SynthesisMode::Setup);
+            circuit.generate_constraints(cs.clone())?;
+            let mut key_id = [0u8; 32];
+            rng.fill_bytes(&mut key_id);
+            // 实例变量的第0项为常数1
+            let key = MockKey { key_id, num_public_inputs: cs.num_instance_variables() as u64 - 1 };
+            Ok((key.clone(), key))
+        }
+
+        fn prove<C, R>(pk: &Self::ProvingKey, circuit: C, _rng: &mut R) -> Result<Self::Proof, VoteError>
+        where
+            C: ConstraintSynthesizer<F>,
+            R: RngCore + CryptoRng,
+        {
+            let cs = ConstraintSystem::<F>::new_ref();
+            circuit.generate_constraints(cs.clone())?;
+            if !cs.is_satisfied()? {
+                return Err(VoteError::Proving("circuit is not satisfied".into()));
+            }
+            let public_inputs = cs
+                .borrow()
+                .ok_or_else(|| VoteError::Internal("constraint system is not available".to_string()))?
+                .instance_assignment[1..]
+                .to_vec();
+            Ok(MockProof { digest: proof_digest(pk, &public_inputs)? })
+        }
+
+        fn verify(vk: &Self::VerifyingKey, public_inputs: &[F], proof: &Self::Proof) -> Result<bool, VoteError> {
+            if public_inputs.len() as u64 != vk.num_public_inputs {
+                return Err(VoteError::Verification(
+                    format!("expected {} public inputs, got {}", vk.num_public_inputs, public_inputs.len()).into(),
+                ));
+            }
+            Ok(proof_digest(vk, public_inputs)? == proof.digest)
+        }
+
+        fn num_public_inputs(vk: &Self::VerifyingKey) -> Option<usize> {
+            Some(vk.num_public_inputs as usize)
+        }
+
+        fn embedded_verifying_key(pk: &Self::ProvingKey) -> Option<Self::VerifyingKey> {
+            Some(pk.clone())
+        }
+    }
+}
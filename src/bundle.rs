@@ -0,0 +1,241 @@
+// 选举结果导出包
+//
+// 选举结束后计票方导出验证密钥、全部选票的公开输入和证明、计票结果及计票证明，
+// 任何第三方都可以用 verify_bundle 独立复核：重新验证每张选票证明、按与计票相同的重新投票规则重新去重防重标识，
+// 并用计票证明核对各选项票数确实来自计入选票的承诺（承诺取自各选票证明的公开输入，导出方无法替换）。
+// 导出包用 blake3 摘要保护完整性（防止传输中损坏或被部分篡改），摘要不是签名，
+// 导出包的来源需通过公布验证密钥指纹等渠道另行确认。
+// 计票电路密钥须来自独立于导出方的可信设置（按计入选票的数量生成并公布，见 tally::setup_tally）：
+// 持有陷门的一方可以为任意票数伪造计票证明，因此导出方只用公布的证明密钥生成证明，
+// 复核方用公布的计票验证密钥复核，不采用导出包中携带的验证密钥。
+
+use crate::tally::{check_tally_keys, keep_latest_ballots, prove_tally, verify_tally, TallyProof, NUM_OPTIONS};
+use crate::{
+    field_to_decimal, keys, vk_fingerprint, BallotOpening, BallotPublicInputs, ProofEnvelope, ProofEnvelopeJson, ProofSystem,
+    TallyResult, VerificationOutcome, VoteError, VoteSystem,
+};
+use ark_ff::PrimeField;
+use ark_std::rand::{CryptoRng, RngCore};
+use tracing::{error, info, info_span};
+
+// 摘要的域分隔标签
+const BUNDLE_DIGEST_CONTEXT: &str = "zkp-vote 2024 election bundle";
+
+// 导出包的当前格式版本（主版本号），见 ElectionBundle::version
+pub const BUNDLE_VERSION: u32 = 1;
+
+// 导出包中的单张选票
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BundledBallot {
+    pub envelope: ProofEnvelopeJson, // 带格式版本的证明信封（含公开输入和验证密钥指纹）
+}
+
+// 选举结果导出包
+//
+// 格式版本：version 是主版本号，只在不兼容的改动时递增（删除字段、改变已有字段的含义或编码、改变摘要规则），
+// verify_bundle 拒绝高于 BUNDLE_VERSION 的版本，避免旧的复核方误读新格式。
+// 新增字段属于兼容改动，不递增版本：旧的复核方把不认识的字段保留在 extensions 中，摘要照常覆盖，复核结果不受影响。
+//
+// 迁移说明：
+// 0：引入版本号之前导出的包（没有 version 字段），摘要按字段顺序序列化计算，仍可复核
+// 1：增加 version 字段；摘要改为对键按字典序排列的 JSON 计算（包括不认识的字段），与字段在结构体中的位置无关
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ElectionBundle {
+    #[serde(default, skip_serializing_if = "is_unversioned")]
+    pub version: u32,                // 格式主版本号，没有该字段的旧导出包为 0
+    pub backend: String,             // 证明系统后端名称
+    pub verifying_key: String,       // 十六进制编码的选票验证密钥
+    pub vk_fingerprint: String,      // 十六进制编码的选票验证密钥指纹
+    pub ballots: Vec<BundledBallot>, // 全部选票（含未计入的）
+    pub tally: TallyResult,          // 计票结果
+    pub tally_verifying_key: String, // 十六进制编码的计票电路验证密钥（公布的可信设置产出，复核时须与之相同）
+    pub tally_proof: String,         // 十六进制编码的计票证明
+    pub digest: String,              // 以上内容的十六进制 blake3 摘要
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>, // 同一主版本中后来新增、本版本不认识的字段
+}
+
+fn is_unversioned(version: &u32) -> bool {
+    *version == 0
+}
+
+impl ElectionBundle {
+    // 计算除摘要字段外全部内容的摘要（规则随版本而定，见 ElectionBundle）
+    pub fn compute_digest(&self) -> String {
+        let mut content = self.clone();
+        content.digest = String::new();
+        let bytes = if self.version == 0 {
+            serde_json::to_vec(&content)
+        } else {
+            serde_json::to_value(&content).and_then(|value| serde_json::to_vec(&canonical_json(value)))
+        }
+        .expect("serializing a bundle cannot fail");
+        hex::encode(blake3::derive_key(BUNDLE_DIGEST_CONTEXT, &bytes))
+    }
+}
+
+// 各层对象的键按字典序排列
+fn canonical_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().map(|(key, value)| (key, canonical_json(value))).collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(entries.into_iter().collect())
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(values.into_iter().map(canonical_json).collect()),
+        value => value,
+    }
+}
+
+// 计票并导出选举结果
+// 计票电路的规模取决于计入的选票数量，tally_pk、tally_vk 须是为该数量公布的计票密钥，数量不符时返回 KeyMismatch
+pub fn export_bundle<F, P, R>(
+    system: &VoteSystem<F, P>,
+    vk: &P::VerifyingKey,
+    tally_pk: &P::ProvingKey,
+    tally_vk: &P::VerifyingKey,
+    ballots: Vec<(BallotPublicInputs<F>, P::Proof, BallotOpening<F>)>,
+    rng: &mut R,
+) -> Result<ElectionBundle, VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+    R: RngCore + CryptoRng,
+{
+    let _enter = info_span!("export_bundle", num_ballots = ballots.len()).entered();
+
+    let bundled = ballots
+        .iter()
+        .map(|(public_inputs, proof, _)| {
+            let envelope = ProofEnvelope::<F, P>::new(vk, public_inputs.clone(), proof.clone())?;
+            Ok(BundledBallot { envelope: envelope.to_json()? })
+        })
+        .collect::<Result<Vec<_>, VoteError>>()?;
+    let openings: Vec<BallotOpening<F>> = ballots.iter().map(|(_, _, opening)| opening.clone()).collect();
+    let tally = system.tally(vk, ballots)?;
+
+    let accepted: Vec<BallotOpening<F>> = tally.accepted.iter().map(|&i| openings[i].clone()).collect();
+    keys::check_consistency::<F, P>(tally_pk, tally_vk)?;
+    check_tally_keys::<F, P>(tally_vk, accepted.len())?;
+    let tally_proof = prove_tally::<F, P, _>(tally_pk, &accepted, &tally.counts, rng)?;
+
+    let mut bundle = ElectionBundle {
+        version: BUNDLE_VERSION,
+        backend: P::NAME.to_string(),
+        verifying_key: hex::encode(P::serialize_verifying_key(vk)?),
+        vk_fingerprint: hex::encode(vk_fingerprint::<F, P>(vk)),
+        ballots: bundled,
+        tally,
+        tally_verifying_key: hex::encode(P::serialize_verifying_key(tally_vk)?),
+        tally_proof: hex::encode(P::serialize_proof(&tally_proof.proof)?),
+        digest: String::new(),
+        extensions: serde_json::Map::new(),
+    };
+    bundle.digest = bundle.compute_digest();
+    info!("Exported election bundle with {} ballots", bundle.ballots.len());
+    Ok(bundle)
+}
+
+// 独立复核导出包，返回重新计算的计票结果
+// 选票的投票值不公开，各选项票数由计票证明担保；计入/拒绝的选票和防重标识则完全重新计算
+// tally_vk 为从可信来源取得的、为计入选票数量公布的计票验证密钥，导出包携带的验证密钥与之不同时返回 KeyMismatch
+pub fn verify_bundle<F, P>(bundle: &ElectionBundle, tally_vk: &P::VerifyingKey) -> Result<TallyResult, VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+{
+    let _enter = info_span!("verify_bundle", num_ballots = bundle.ballots.len()).entered();
+
+    // 新的主版本可能改变摘要规则和字段含义，先于其他检查拒绝
+    if bundle.version > BUNDLE_VERSION {
+        error!("Rejected election bundle with version {}", bundle.version);
+        return Err(VoteError::UnsupportedBundleVersion(bundle.version));
+    }
+    if !bundle.extensions.is_empty() {
+        let fields: Vec<&str> = bundle.extensions.keys().map(String::as_str).collect();
+        info!("Election bundle carries fields this version does not know: {}", fields.join(", "));
+    }
+    if bundle.compute_digest() != bundle.digest {
+        error!("Election bundle digest mismatch");
+        return Err(VoteError::BundleIntegrity("digest does not match the bundle content".to_string()));
+    }
+    if bundle.backend != P::NAME {
+        return Err(VoteError::KeyMismatch(format!(
+            "bundle was produced by backend {}, expected {}",
+            bundle.backend,
+            P::NAME
+        )));
+    }
+    let vk = P::deserialize_verifying_key(&decode_hex(&bundle.verifying_key)?)?;
+    if hex::encode(vk_fingerprint::<F, P>(&vk)) != bundle.vk_fingerprint {
+        return Err(VoteError::KeyMismatch("verifying key does not match its fingerprint".to_string()));
+    }
+
+    // 与 VoteSystem::tally 相同的规则：证明无效的选票拒绝，同一防重标识只计入序号最大的有效选票（序号相同时计入第一张）
+    let mut result = TallyResult::new();
+    let mut valid = Vec::new();
+    for (index, ballot) in bundle.ballots.iter().enumerate() {
+        let envelope = ProofEnvelope::<F, P>::from_json(&ballot.envelope)
+            .and_then(|envelope| envelope.check_verifying_key(&vk).map(|()| envelope));
+        let ProofEnvelope { public_inputs, proof, .. } = match envelope {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                result.reject_error(index, &e);
+                continue;
+            }
+        };
+        match VoteSystem::<F, P>::check_ballot(&vk, &public_inputs, &proof) {
+            Ok(VerificationOutcome::Valid) => {}
+            Ok(VerificationOutcome::Invalid { reason }) => {
+                result.reject_invalid(index, reason.code());
+                continue;
+            }
+            Err(e) => {
+                result.reject_error(index, &e);
+                continue;
+            }
+        }
+        valid.push((index, public_inputs, ()));
+    }
+    let mut commitments = Vec::new();
+    for (index, public_inputs, ()) in keep_latest_ballots(&mut result, valid) {
+        result.accepted.push(index);
+        result.consumed_nullifiers.push(field_to_decimal(public_inputs.nullifier));
+        result.commitments.push(field_to_decimal(public_inputs.commitment));
+        commitments.push(public_inputs.commitment);
+    }
+
+    if result.accepted != bundle.tally.accepted
+        || result.rejected != bundle.tally.rejected
+        || result.consumed_nullifiers != bundle.tally.consumed_nullifiers
+        || result.commitments != bundle.tally.commitments
+    {
+        return Err(VoteError::BundleIntegrity("accepted ballots do not match the published tally".to_string()));
+    }
+    if bundle.tally.counts.len() != NUM_OPTIONS
+        || bundle.tally.counts.iter().sum::<u64>() != result.accepted.len() as u64
+    {
+        return Err(VoteError::BundleIntegrity("option counts do not add up to the accepted ballots".to_string()));
+    }
+
+    // 计票证明担保各选项票数由计入选票的承诺开启得到，承诺取自选票证明的公开输入而不是导出方公布的计票结果
+    // 只用可信来源的计票验证密钥复核：导出方换用自己生成的密钥（持有陷门）时，即使证明在该密钥下成立也拒绝
+    if decode_hex(&bundle.tally_verifying_key)? != P::serialize_verifying_key(tally_vk)? {
+        error!("Election bundle carries a tally verifying key other than the published one");
+        return Err(VoteError::KeyMismatch("bundle's tally verifying key is not the published one".to_string()));
+    }
+    check_tally_keys::<F, P>(tally_vk, result.accepted.len())?;
+    let (proof, _) = P::deserialize_proof_auto(&decode_hex(&bundle.tally_proof)?)?;
+    let tally_proof = TallyProof::<F, P> { proof, num_ballots: result.accepted.len() };
+    if !verify_tally(tally_vk, &tally_proof, &commitments, &bundle.tally.counts)? {
+        return Err(VoteError::Verification("tally proof is invalid".into()));
+    }
+    result.counts = bundle.tally.counts.clone();
+
+    info!("Verified election bundle: {} accepted, {} rejected", result.accepted.len(), result.rejected.len());
+    Ok(result)
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, VoteError> {
+    hex::decode(s).map_err(|e| VoteError::BundleIntegrity(format!("invalid hex encoding: {}", e)))
+}
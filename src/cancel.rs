@@ -0,0 +1,50 @@
+// 证明任务的取消标志
+//
+// 等待证明结果的异步任务被丢弃（客户端断开、超时）时置位，阻塞线程在证明各阶段之间检查，
+// 尚未开始的阶段不再执行。已开始的阶段（约束合成、证明计算）无法中途打断，只能跑完后丢弃结果。
+
+use crate::VoteError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::debug;
+
+// 取消标志，克隆后共享同一状态
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    #[cfg(feature = "server")]
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    // 已取消时返回错误，用于在证明阶段之间提前退出（该错误不会被等待方看到）
+    pub(crate) fn check(&self, phase: &str) -> Result<(), VoteError> {
+        if self.is_cancelled() {
+            debug!("Proof generation cancelled before {}", phase);
+            return Err(VoteError::Proving(format!("proof generation cancelled before {}", phase).into()));
+        }
+        Ok(())
+    }
+
+    // 释放时取消的守卫，由等待结果的一方持有
+    #[cfg(feature = "server")]
+    pub(crate) fn drop_guard(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+// 释放时取消证明任务（正常完成后释放也会置位，此时任务已结束，没有影响）
+#[cfg(feature = "server")]
+pub(crate) struct CancelOnDrop(CancelToken);
+
+#[cfg(feature = "server")]
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
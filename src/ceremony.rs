@@ -0,0 +1,404 @@
+// Groth16 第二阶段（电路相关）多方可信设置
+//
+// 单方执行 setup 时，运营方知道全部"有毒废料"，可以伪造证明。
+// 多方仪式中每位参与者依次对 δ 做随机缩放，只要有一位参与者诚实地销毁了
+// 自己的随机数，最终密钥就是安全的。
+//
+// 流程：
+// 1. 从磁盘加载第一阶段的 powers-of-tau 参数（PowersOfTau::load）
+// 2. 由电路和第一阶段参数计算初始参数（initial_parameters，δ = 1）
+// 3. 参与者依次调用 Contribution::new 追加贡献
+// 4. 任何人都可以调用 verify_transcript 验证整条贡献链并得到最终密钥
+
+use crate::backend::deserialize_exact;
+use crate::progress::{ProgressSink, TracingProgress};
+use crate::VoteError;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ff::{Field, One, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use ark_std::rand::RngCore;
+use ark_std::UniformRand;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+// 第一阶段参数（powers of tau）
+// 文件格式为 arkworks 的压缩序列化格式
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTau<E: Pairing> {
+    pub tau_g1: Vec<E::G1Affine>,       // [τ^i]_1，i ∈ [0, 2m-1)
+    pub tau_g2: Vec<E::G2Affine>,       // [τ^i]_2，i ∈ [0, m)
+    pub alpha_tau_g1: Vec<E::G1Affine>, // [α·τ^i]_1，i ∈ [0, m)
+    pub beta_tau_g1: Vec<E::G1Affine>,  // [β·τ^i]_1，i ∈ [0, m)
+    pub beta_g2: E::G2Affine,           // [β]_2
+}
+
+impl<E: Pairing> PowersOfTau<E> {
+    // 从磁盘加载第一阶段参数
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, VoteError> {
+        let bytes = std::fs::read(path.as_ref()).map_err(|e| {
+            VoteError::Ceremony(format!("Failed to read powers of tau: {}", e))
+        })?;
+        deserialize_exact(&bytes, Compress::Yes, Validate::Yes)
+    }
+
+    // 保存第一阶段参数到磁盘
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), VoteError> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)?;
+        std::fs::write(path.as_ref(), bytes).map_err(|e| {
+            VoteError::Ceremony(format!("Failed to write powers of tau: {}", e))
+        })
+    }
+
+    // 由已知秘密直接生成第一阶段参数（仅用于测试，秘密即有毒废料）
+    pub fn from_secrets_insecure(
+        tau: E::ScalarField,
+        alpha: E::ScalarField,
+        beta: E::ScalarField,
+        domain_size: usize,
+    ) -> Self {
+        warn!("Generating powers of tau from known secrets; this is NOT secure");
+
+        let g1 = E::G1::generator();
+        let g2 = E::G2::generator();
+        let powers: Vec<E::ScalarField> = std::iter::successors(Some(E::ScalarField::one()), |p| Some(*p * tau))
+            .take(2 * domain_size - 1)
+            .collect();
+
+        let scale_g1 = |k: E::ScalarField, n: usize| -> Vec<E::G1Affine> {
+            E::G1::normalize_batch(&powers[..n].iter().map(|p| g1 * (*p * k)).collect::<Vec<_>>())
+        };
+
+        Self {
+            tau_g1: scale_g1(E::ScalarField::one(), 2 * domain_size - 1),
+            tau_g2: E::G2::normalize_batch(&powers[..domain_size].iter().map(|p| g2 * *p).collect::<Vec<_>>()),
+            alpha_tau_g1: scale_g1(alpha, domain_size),
+            beta_tau_g1: scale_g1(beta, domain_size),
+            beta_g2: (g2 * beta).into_affine(),
+        }
+    }
+}
+
+// 最终密钥
+#[derive(Clone, Debug)]
+pub struct FinalKeys<E: Pairing> {
+    pub proving_key: ProvingKey<E>,
+    pub verifying_key: VerifyingKey<E>,
+}
+
+// 由电路和第一阶段参数计算第二阶段初始参数（γ = δ = 1）
+// R1CS 到 QAP 的约化方式与 ark-groth16 的 LibsnarkReduction 保持一致
+pub fn initial_parameters<E, C>(
+    circuit: C,
+    powers: &PowersOfTau<E>,
+) -> Result<ProvingKey<E>, VoteError>
+where
+    E: Pairing,
+    C: ConstraintSynthesizer<E::ScalarField>,
+{
+    info!("Computing initial phase-2 parameters");
+
+    // 以与证明方相同的方式综合电路
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone())?;
+    cs.finalize();
+    let matrices = cs
+        .to_matrices()
+        .ok_or_else(|| VoteError::Ceremony("Failed to extract constraint matrices".to_string()))?;
+
+    let num_instance = matrices.num_instance_variables;
+    let num_witness = matrices.num_witness_variables;
+    let num_constraints = matrices.num_constraints;
+
+    let domain = GeneralEvaluationDomain::<E::ScalarField>::new(num_constraints + num_instance)
+        .ok_or_else(|| VoteError::Ceremony("Circuit too large for evaluation domain".to_string()))?;
+    let m = domain.size();
+
+    if powers.tau_g1.len() < 2 * m - 1
+        || powers.tau_g2.len() < m
+        || powers.alpha_tau_g1.len() < m
+        || powers.beta_tau_g1.len() < m
+    {
+        return Err(VoteError::Ceremony(format!(
+            "Powers of tau too small: circuit requires domain size {}",
+            m
+        )));
+    }
+    debug!("Phase-2 domain size: {}", m);
+
+    // 通过逆FFT得到拉格朗日基在 τ 处的取值
+    let lagrange = |points: &[E::G1Affine]| -> Vec<E::G1> {
+        let mut v: Vec<E::G1> = points[..m].iter().map(|p| p.into_group()).collect();
+        domain.ifft_in_place(&mut v);
+        v
+    };
+    let lag_g1 = lagrange(&powers.tau_g1);
+    let alpha_lag_g1 = lagrange(&powers.alpha_tau_g1);
+    let beta_lag_g1 = lagrange(&powers.beta_tau_g1);
+    let lag_g2 = {
+        let mut v: Vec<E::G2> = powers.tau_g2[..m].iter().map(|p| p.into_group()).collect();
+        domain.ifft_in_place(&mut v);
+        v
+    };
+
+    let num_vars = num_instance + num_witness;
+    let mut a_g1 = vec![E::G1::zero(); num_vars];
+    let mut b_g1 = vec![E::G1::zero(); num_vars];
+    let mut b_g2 = vec![E::G2::zero(); num_vars];
+    let mut abc_g1 = vec![E::G1::zero(); num_vars]; // [β·a_k(τ) + α·b_k(τ) + c_k(τ)]_1
+
+    for (row, terms) in matrices.a.iter().enumerate() {
+        for (coeff, k) in terms {
+            a_g1[*k] += lag_g1[row] * coeff;
+            abc_g1[*k] += beta_lag_g1[row] * coeff;
+        }
+    }
+    for (row, terms) in matrices.b.iter().enumerate() {
+        for (coeff, k) in terms {
+            b_g1[*k] += lag_g1[row] * coeff;
+            b_g2[*k] += lag_g2[row] * coeff;
+            abc_g1[*k] += alpha_lag_g1[row] * coeff;
+        }
+    }
+    for (row, terms) in matrices.c.iter().enumerate() {
+        for (coeff, k) in terms {
+            abc_g1[*k] += lag_g1[row] * coeff;
+        }
+    }
+    // 公开输入的额外约束行
+    for i in 0..num_instance {
+        let row = num_constraints + i;
+        a_g1[i] += lag_g1[row];
+        abc_g1[i] += beta_lag_g1[row];
+    }
+
+    // [τ^i·Z(τ)]_1 = [τ^(i+m)]_1 - [τ^i]_1
+    let h_query: Vec<E::G1> = (0..m - 1)
+        .map(|i| powers.tau_g1[i + m].into_group() - powers.tau_g1[i])
+        .collect();
+
+    let vk = VerifyingKey {
+        alpha_g1: powers.alpha_tau_g1[0],
+        beta_g2: powers.beta_g2,
+        gamma_g2: E::G2Affine::generator(),
+        delta_g2: E::G2Affine::generator(),
+        gamma_abc_g1: E::G1::normalize_batch(&abc_g1[..num_instance]),
+    };
+
+    Ok(ProvingKey {
+        vk,
+        beta_g1: powers.beta_tau_g1[0],
+        delta_g1: E::G1Affine::generator(),
+        a_query: E::G1::normalize_batch(&a_g1),
+        b_g1_query: E::G1::normalize_batch(&b_g1),
+        b_g2_query: E::G2::normalize_batch(&b_g2),
+        h_query: E::G1::normalize_batch(&h_query),
+        l_query: E::G1::normalize_batch(&abc_g1[num_instance..]),
+    })
+}
+
+// 计算参数的哈希
+fn params_hash<E: Pairing>(params: &ProvingKey<E>) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    params
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    *blake3::hash(&bytes).as_bytes()
+}
+
+// 由上一轮参数哈希和 (s, s·δ') 派生 G2 上的挑战点 r
+// 使用随机采样（而非标量乘生成元）保证 r 的离散对数未知
+fn derive_r<E: Pairing>(previous_hash: &[u8; 32], s_g1: &E::G1Affine, s_delta_g1: &E::G1Affine) -> E::G2Affine {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"zkp-vote/ceremony/r");
+    hasher.update(previous_hash);
+    let mut bytes = Vec::new();
+    s_g1.serialize_compressed(&mut bytes).expect("serializing into a Vec cannot fail");
+    s_delta_g1.serialize_compressed(&mut bytes).expect("serializing into a Vec cannot fail");
+    hasher.update(&bytes);
+
+    let mut rng = ChaCha20Rng::from_seed(*hasher.finalize().as_bytes());
+    E::G2::rand(&mut rng).into_affine()
+}
+
+// 检查 e(a1, b2) == e(a2, b1)，即 a2/a1 与 b2/b1 的比值相同
+fn same_ratio<E: Pairing>(
+    g1: (E::G1Affine, E::G1Affine),
+    g2: (E::G2Affine, E::G2Affine),
+) -> bool {
+    E::pairing(g1.0, g2.1) == E::pairing(g1.1, g2.0)
+}
+
+// 用随机线性组合将两个等长向量压缩为一对点
+fn merge_pairs<E: Pairing>(v1: &[E::G1Affine], v2: &[E::G1Affine]) -> (E::G1Affine, E::G1Affine) {
+    let mut rng = rand::thread_rng();
+    let mut s = E::G1::zero();
+    let mut t = E::G1::zero();
+    for (a, b) in v1.iter().zip(v2) {
+        let rho = E::ScalarField::rand(&mut rng);
+        s += *a * rho;
+        t += *b * rho;
+    }
+    (s.into_affine(), t.into_affine())
+}
+
+// 一位参与者的贡献
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution<E: Pairing> {
+    pub params: ProvingKey<E>,    // 贡献后的参数
+    pub s_g1: E::G1Affine,        // 随机点 s
+    pub s_delta_g1: E::G1Affine,  // s·δ'
+    pub r_delta_g2: E::G2Affine,  // r·δ'
+}
+
+impl<E: Pairing> Contribution<E> {
+    // 在上一轮参数基础上追加贡献
+    // entropy 与系统随机数混合后作为本轮随机数种子，δ' 用完即丢弃
+    pub fn new(previous_params: &ProvingKey<E>, entropy: &[u8]) -> Self {
+        info!("Computing phase-2 contribution");
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"zkp-vote/ceremony/entropy");
+        hasher.update(entropy);
+        let mut os_entropy = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut os_entropy);
+        hasher.update(&os_entropy);
+        let mut rng = ChaCha20Rng::from_seed(*hasher.finalize().as_bytes());
+
+        let delta = loop {
+            let d = E::ScalarField::rand(&mut rng);
+            if !d.is_zero() {
+                break d;
+            }
+        };
+        let delta_inv = delta.inverse().expect("delta is non-zero");
+
+        // 知识证明
+        let s_g1 = E::G1::rand(&mut rng).into_affine();
+        let s_delta_g1 = (s_g1 * delta).into_affine();
+        let r = derive_r::<E>(&params_hash(previous_params), &s_g1, &s_delta_g1);
+        let r_delta_g2 = (r * delta).into_affine();
+
+        // δ 乘以 δ'，h_query 和 l_query 除以 δ'
+        let mut params = previous_params.clone();
+        params.delta_g1 = (params.delta_g1 * delta).into_affine();
+        params.vk.delta_g2 = (params.vk.delta_g2 * delta).into_affine();
+        params.h_query = E::G1::normalize_batch(
+            &params.h_query.iter().map(|p| *p * delta_inv).collect::<Vec<_>>(),
+        );
+        params.l_query = E::G1::normalize_batch(
+            &params.l_query.iter().map(|p| *p * delta_inv).collect::<Vec<_>>(),
+        );
+
+        Self { params, s_g1, s_delta_g1, r_delta_g2 }
+    }
+
+    // 本次贡献的哈希，供参与者公开确认自己的贡献已被纳入
+    pub fn hash(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        *blake3::hash(&bytes).as_bytes()
+    }
+
+    // 验证本次贡献是对 previous 的合法更新
+    fn verify(&self, previous: &ProvingKey<E>) -> Result<(), String> {
+        let next = &self.params;
+
+        // 除 δ 相关部分外，参数不得改变
+        if next.vk.alpha_g1 != previous.vk.alpha_g1
+            || next.vk.beta_g2 != previous.vk.beta_g2
+            || next.vk.gamma_g2 != previous.vk.gamma_g2
+            || next.vk.gamma_abc_g1 != previous.vk.gamma_abc_g1
+            || next.beta_g1 != previous.beta_g1
+            || next.a_query != previous.a_query
+            || next.b_g1_query != previous.b_g1_query
+            || next.b_g2_query != previous.b_g2_query
+        {
+            return Err("non-delta parameters were modified".to_string());
+        }
+        if next.h_query.len() != previous.h_query.len() || next.l_query.len() != previous.l_query.len() {
+            return Err("query lengths changed".to_string());
+        }
+        if self.s_g1.is_zero() || self.s_delta_g1.is_zero() || next.delta_g1.is_zero() {
+            return Err("degenerate contribution".to_string());
+        }
+
+        // 知识证明：s·δ' / s == r·δ' / r
+        let r = derive_r::<E>(&params_hash(previous), &self.s_g1, &self.s_delta_g1);
+        if !same_ratio::<E>((self.s_g1, self.s_delta_g1), (r, self.r_delta_g2)) {
+            return Err("invalid proof of knowledge".to_string());
+        }
+
+        // δ_1 按相同的 δ' 更新
+        if !same_ratio::<E>((previous.delta_g1, next.delta_g1), (r, self.r_delta_g2)) {
+            return Err("delta_g1 not updated consistently".to_string());
+        }
+
+        // δ_1 与 δ_2 一致
+        if !same_ratio::<E>(
+            (E::G1Affine::generator(), next.delta_g1),
+            (E::G2Affine::generator(), next.vk.delta_g2),
+        ) {
+            return Err("delta_g1 and delta_g2 disagree".to_string());
+        }
+
+        // h_query 和 l_query 除以相同的 δ'
+        let (h_prev, h_next) = merge_pairs::<E>(&previous.h_query, &next.h_query);
+        if !same_ratio::<E>((h_next, h_prev), (previous.vk.delta_g2, next.vk.delta_g2)) {
+            return Err("h_query not updated consistently".to_string());
+        }
+        let (l_prev, l_next) = merge_pairs::<E>(&previous.l_query, &next.l_query);
+        if !same_ratio::<E>((l_next, l_prev), (previous.vk.delta_g2, next.vk.delta_g2)) {
+            return Err("l_query not updated consistently".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+// 验证完整的贡献链并返回最终密钥
+// initial 应由验证者自行通过 initial_parameters 从电路和第一阶段参数计算；进度以 tracing 事件输出
+pub fn verify_transcript<E: Pairing>(
+    initial: &ProvingKey<E>,
+    chain: &[Contribution<E>],
+) -> Result<FinalKeys<E>, VoteError> {
+    verify_transcript_with_progress(initial, chain, &TracingProgress)
+}
+
+// 同 verify_transcript，每验证完一份贡献向 progress 报告一次进度（"verify_contribution"）
+pub fn verify_transcript_with_progress<E: Pairing>(
+    initial: &ProvingKey<E>,
+    chain: &[Contribution<E>],
+    progress: &dyn ProgressSink,
+) -> Result<FinalKeys<E>, VoteError> {
+    info!("Verifying ceremony transcript with {} contributions", chain.len());
+
+    if chain.is_empty() {
+        return Err(VoteError::Ceremony("Transcript has no contributions".to_string()));
+    }
+
+    let mut previous = initial;
+    progress.report("verify_contribution", 0.0);
+    for (i, contribution) in chain.iter().enumerate() {
+        contribution.verify(previous).map_err(|e| {
+            warn!("Contribution {} rejected: {}", i, e);
+            VoteError::Ceremony(format!("Contribution {} rejected: {}", i, e))
+        })?;
+        debug!("Contribution {} verified", i);
+        progress.report("verify_contribution", (i + 1) as f64 / chain.len() as f64);
+        previous = &contribution.params;
+    }
+
+    let proving_key = previous.clone();
+    let verifying_key = proving_key.vk.clone();
+    Ok(FinalKeys { proving_key, verifying_key })
+}
@@ -0,0 +1,112 @@
+// 投票选择
+//
+// 电路中的投票值是选项槽位的编号：候选人占用 [0, num_candidates)，
+// 允许弃权的配置在候选人之后额外占用一个槽位 num_candidates，允许废票的配置在最后再占用一个槽位。
+// 二选一选票用 No/Yes（0 为反对，1 为赞成），多候选人选票用 Candidate(i)；
+// Candidate(i) 在二选一选票上也接受（兼容以数字提交的旧客户端），转换回来时规范为 No/Yes。
+// 弃权是投票人的一种表态，废票（Spoiled）只证明投票人参与了投票（同样占用防重标识），
+// 两者都不计入任何候选人的票数，但都计入投票率。
+//
+// JSON 表示：No/Yes/Abstain/Spoiled 为字符串 "no"/"yes"/"abstain"/"spoiled"，Candidate(i) 为数字 i。
+
+use crate::{CircuitConfig, VoteError};
+use ark_ff::{BigInteger, PrimeField};
+use tracing::warn;
+
+// 投票选择
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VoteChoice {
+    No,
+    Yes,
+    Candidate(u16),
+    Abstain,
+    Spoiled,
+}
+
+impl VoteChoice {
+    // 按电路配置映射为选项槽位，配置不允许的选择一律拒绝
+    pub fn slot(self, config: &CircuitConfig) -> Result<usize, VoteError> {
+        let binary = config.num_candidates == crate::tally::NUM_OPTIONS;
+        let slot = match self {
+            VoteChoice::No if binary => Some(0),
+            VoteChoice::Yes if binary => Some(1),
+            VoteChoice::No | VoteChoice::Yes => None,
+            VoteChoice::Candidate(i) => Some(usize::from(i)).filter(|&i| i < config.num_candidates),
+            VoteChoice::Abstain => config.allow_abstain.then_some(config.num_candidates),
+            VoteChoice::Spoiled => config.allow_spoiled.then_some(config.num_slots() - 1),
+        };
+        slot.ok_or_else(|| {
+            warn!("Invalid vote choice: {:?}", self);
+            VoteError::InvalidBallot(match self {
+                VoteChoice::No | VoteChoice::Yes => "yes/no choices require a two-candidate ballot".to_string(),
+                VoteChoice::Abstain => "this ballot does not allow abstention".to_string(),
+                VoteChoice::Spoiled => "this ballot does not allow spoiled ballots".to_string(),
+                VoteChoice::Candidate(_) => format!(
+                    "Invalid vote choice. Must be between 0 and {}.",
+                    config.num_candidates - 1
+                ),
+            })
+        })
+    }
+
+    // 按电路配置映射为电路中的投票值
+    pub fn to_field<F: PrimeField>(self, config: &CircuitConfig) -> Result<F, VoteError> {
+        Ok(F::from(self.slot(config)? as u64))
+    }
+
+    // 由选项槽位还原投票选择
+    pub fn from_slot(slot: usize, config: &CircuitConfig) -> Result<Self, VoteError> {
+        let binary = config.num_candidates == crate::tally::NUM_OPTIONS;
+        match slot {
+            0 if binary => Ok(VoteChoice::No),
+            1 if binary => Ok(VoteChoice::Yes),
+            i if i < config.num_candidates => Ok(VoteChoice::Candidate(i as u16)),
+            i if i == config.num_candidates && config.allow_abstain => Ok(VoteChoice::Abstain),
+            i if i + 1 == config.num_slots() && config.allow_spoiled => Ok(VoteChoice::Spoiled),
+            i => Err(VoteError::InvalidBallot(format!("vote value {} is not a valid choice", i))),
+        }
+    }
+
+    // 由电路中的投票值还原投票选择
+    pub fn from_field<F: PrimeField>(value: F, config: &CircuitConfig) -> Result<Self, VoteError> {
+        let bigint = value.into_bigint();
+        let slot = (bigint.num_bits() <= 16)
+            .then(|| bigint.as_ref()[0] as usize)
+            .ok_or_else(|| VoteError::InvalidBallot("vote value is not a valid choice".to_string()))?;
+        Self::from_slot(slot, config)
+    }
+}
+
+impl serde::Serialize for VoteChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            VoteChoice::No => serializer.serialize_str("no"),
+            VoteChoice::Yes => serializer.serialize_str("yes"),
+            VoteChoice::Abstain => serializer.serialize_str("abstain"),
+            VoteChoice::Spoiled => serializer.serialize_str("spoiled"),
+            VoteChoice::Candidate(i) => serializer.serialize_u16(*i),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for VoteChoice {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Index(u16),
+            Name(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Index(i) => Ok(VoteChoice::Candidate(i)),
+            Repr::Name(name) => match name.as_str() {
+                "no" => Ok(VoteChoice::No),
+                "yes" => Ok(VoteChoice::Yes),
+                "abstain" => Ok(VoteChoice::Abstain),
+                "spoiled" => Ok(VoteChoice::Spoiled),
+                other => Err(serde::de::Error::custom(format!("unknown vote choice {:?}", other))),
+            },
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// 客户端证明与验证
+//
+// 浏览器（wasm 模块）和自助终端（ffi 模块）在客户端生成选票证明，服务端看不到投票人秘密和投票值。
+// 这里不做密钥生成：证明密钥和验证密钥由服务端下发，客户端只负责证明和验证。
+// 选票JSON中的字段元素为十进制字符串，与 HttpProver::request_body 的格式一致：
+//   {"vote": "1", "secret": "...", "randomness": "...", "election_id": "...", "sequence": "0", "epoch": "..."}
+
+use crate::curve::{DefaultField as Fr, DefaultPairing};
+use crate::fingerprint::decode_key;
+use crate::keys::check_consistency;
+use crate::{field_to_decimal, parse_field_element, BallotPublicInputs, ProofSystem, VoteCircuit, VoteError, VoteSystem};
+use ark_groth16::Groth16;
+use ark_std::rand::rngs::OsRng;
+use zeroize::Zeroize;
+
+// 客户端使用默认曲线上的 Groth16
+pub type ClientBackend = Groth16<DefaultPairing>;
+
+// 客户端证明密钥
+pub type ClientProvingKey = <ClientBackend as ProofSystem<Fr>>::ProvingKey;
+
+// 客户端验证密钥
+pub type ClientVerifyingKey = <ClientBackend as ProofSystem<Fr>>::VerifyingKey;
+
+// 选票JSON（私有输入在解析为电路后清零）
+#[derive(serde::Deserialize)]
+struct BallotJson {
+    vote: String,
+    secret: String,
+    randomness: String,
+    election_id: String,
+    sequence: String,
+    epoch: String,
+}
+
+impl Drop for BallotJson {
+    fn drop(&mut self) {
+        self.vote.zeroize();
+        self.secret.zeroize();
+        self.randomness.zeroize();
+    }
+}
+
+// 解析选票JSON为投票电路
+fn parse_ballot(ballot_json: &str) -> Result<VoteCircuit<Fr>, VoteError> {
+    let ballot: BallotJson = serde_json::from_str(ballot_json)
+        .map_err(|e| VoteError::InvalidBallot(format!("invalid ballot json: {}", e)))?;
+    Ok(VoteCircuit {
+        vote: parse_field_element(&ballot.vote)?,
+        secret: parse_field_element(&ballot.secret)?,
+        randomness: parse_field_element(&ballot.randomness)?,
+        election_id: parse_field_element(&ballot.election_id)?,
+        sequence: parse_field_element(&ballot.sequence)?,
+        epoch: parse_field_element(&ballot.epoch)?,
+    })
+}
+
+// 解码密钥文件格式（见 fingerprint::encode_key）的证明密钥
+pub fn decode_proving_key(pk_bytes: &[u8]) -> Result<ClientProvingKey, VoteError> {
+    let pk = decode_key::<Fr, ClientProvingKey>(pk_bytes)?;
+    ClientBackend::check_proving_key(&pk)?;
+    Ok(pk)
+}
+
+// 解码 /vk 返回的序列化格式的验证密钥
+pub fn decode_verifying_key(vk_bytes: &[u8]) -> Result<ClientVerifyingKey, VoteError> {
+    ClientBackend::deserialize_verifying_key(vk_bytes)
+}
+
+// 检查分别下发的证明密钥与验证密钥是否配对（见 keys::check_consistency）
+pub fn check_key_pair(pk: &ClientProvingKey, vk: &ClientVerifyingKey) -> Result<(), VoteError> {
+    check_consistency::<Fr, ClientBackend>(pk, vk)
+}
+
+// 为选票生成证明，返回压缩格式的证明
+pub fn prove_ballot(pk: &ClientProvingKey, ballot_json: &str) -> Result<Vec<u8>, VoteError> {
+    let circuit = parse_ballot(ballot_json)?;
+    let proof = VoteSystem::<Fr, ClientBackend>::new().vote(pk, circuit, &mut OsRng)?;
+    ClientBackend::serialize_proof(&proof)
+}
+
+// 选票的公开输入（十进制字符串数组，按电路顺序），随证明一起提交给服务端
+pub fn ballot_public_inputs(ballot_json: &str) -> Result<String, VoteError> {
+    let inputs: Vec<String> = parse_ballot(ballot_json)?
+        .public_inputs()
+        .to_vec()
+        .into_iter()
+        .map(field_to_decimal)
+        .collect();
+    Ok(serde_json::to_string(&inputs).expect("serializing strings cannot fail"))
+}
+
+// 验证证明，公开输入为十进制字符串数组
+pub fn verify_ballot(vk: &ClientVerifyingKey, inputs_json: &str, proof_bytes: &[u8]) -> Result<bool, VoteError> {
+    let inputs: Vec<String> = serde_json::from_str(inputs_json)
+        .map_err(|e| VoteError::InvalidBallot(format!("invalid public inputs json: {}", e)))?;
+    let inputs = inputs
+        .iter()
+        .map(|s| parse_field_element::<Fr>(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let public_inputs = BallotPublicInputs::from_slice(&inputs)?;
+    let (proof, _) = ClientBackend::deserialize_proof_auto(proof_bytes)?;
+    let outcome = VoteSystem::<Fr, ClientBackend>::new().verify_ballot(vk, &public_inputs, &proof)?;
+    Ok(outcome.is_valid())
+}
@@ -0,0 +1,117 @@
+// 曲线选择
+//
+// 通过 cargo 特性选择配对曲线：`curve-bn254`（默认）与 `curve-bls12-381`，两者可同时启用。
+// 电路对标量域是泛型的，这里集中放置各曲线的哈希参数、序列化头部使用的曲线标识，
+// 以及下游代码直接使用的类型别名。Web层和防重标识存储使用默认曲线（同时启用时为BN254）。
+
+use crate::{VoteError, VoteSystem};
+use ark_ec::pairing::Pairing;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::Groth16;
+
+#[cfg(not(any(feature = "curve-bn254", feature = "curve-bls12-381")))]
+compile_error!("at least one of the `curve-bn254` and `curve-bls12-381` features must be enabled");
+
+// BN254 上的投票系统
+#[cfg(feature = "curve-bn254")]
+pub type Bn254VoteSystem = VoteSystem<ark_bn254::Fr, Groth16<ark_bn254::Bn254>>;
+
+// BLS12-381 上的投票系统
+#[cfg(feature = "curve-bls12-381")]
+pub type Bls12VoteSystem = VoteSystem<ark_bls12_381::Fr, Groth16<ark_bls12_381::Bls12_381>>;
+
+// 默认曲线
+#[cfg(feature = "curve-bn254")]
+pub type DefaultPairing = ark_bn254::Bn254;
+#[cfg(all(feature = "curve-bls12-381", not(feature = "curve-bn254")))]
+pub type DefaultPairing = ark_bls12_381::Bls12_381;
+
+// 默认曲线的标量域
+pub type DefaultField = <DefaultPairing as Pairing>::ScalarField;
+
+// 曲线标识（写入密钥文件和证明信封的头部）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveId {
+    Bn254 = 1,
+    Bls12_381 = 2,
+}
+
+impl CurveId {
+    // 由标量域确定曲线，未知的域返回 None
+    pub fn of<F: PrimeField>() -> Option<Self> {
+        let modulus = F::MODULUS.to_bytes_le();
+        #[cfg(feature = "curve-bn254")]
+        if modulus == ark_bn254::Fr::MODULUS.to_bytes_le() {
+            return Some(CurveId::Bn254);
+        }
+        #[cfg(feature = "curve-bls12-381")]
+        if modulus == ark_bls12_381::Fr::MODULUS.to_bytes_le() {
+            return Some(CurveId::Bls12_381);
+        }
+        let _ = modulus;
+        None
+    }
+
+    // 由标量域确定曲线，未知的域返回错误
+    pub fn require<F: PrimeField>() -> Result<Self, VoteError> {
+        Self::of::<F>().ok_or_else(|| VoteError::KeyMismatch("scalar field does not belong to a supported curve".to_string()))
+    }
+
+    // 头部中的编码
+    pub fn to_u32(self) -> u32 {
+        self as u32
+    }
+
+    // 解析头部中的编码（不要求对应的特性已启用）
+    pub fn from_u32(id: u32) -> Option<Self> {
+        match id {
+            1 => Some(CurveId::Bn254),
+            2 => Some(CurveId::Bls12_381),
+            _ => None,
+        }
+    }
+
+    // 校验头部记录的曲线与当前标量域一致
+    pub fn check<F: PrimeField>(id: u32) -> Result<(), VoteError> {
+        let expected = Self::require::<F>()?;
+        match Self::from_u32(id) {
+            Some(actual) if actual == expected => Ok(()),
+            Some(actual) => Err(VoteError::KeyMismatch(format!(
+                "data was produced on curve {:?}, expected {:?}",
+                actual, expected
+            ))),
+            None => Err(VoteError::KeyMismatch(format!("unknown curve identifier {}", id))),
+        }
+    }
+}
+
+// 各曲线的哈希参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurveParams {
+    pub poseidon_full_rounds: usize,    // Poseidon 完整轮数（前后各一半）
+    pub poseidon_partial_rounds: usize, // Poseidon 部分轮数
+    pub mimc_rounds: usize,             // 选票承诺的 MiMC-5 轮数
+}
+
+// BN254 参数（保持原有取值，已发布的密钥依赖这些常数）
+const BN254_PARAMS: CurveParams = CurveParams {
+    poseidon_full_rounds: 8,
+    poseidon_partial_rounds: 57,
+    mimc_rounds: 91,
+};
+
+// BLS12-381 参数：宽度3、x^5 的 Poseidon 取 R_F = 8、R_P = 57；MiMC-5 取 ceil(log5(r)) = 110 轮
+const BLS12_381_PARAMS: CurveParams = CurveParams {
+    poseidon_full_rounds: 8,
+    poseidon_partial_rounds: 57,
+    mimc_rounds: 110,
+};
+
+// 标量域对应的哈希参数，未知的域使用BN254参数
+pub fn params<F: PrimeField>() -> CurveParams {
+    match CurveId::of::<F>() {
+        Some(CurveId::Bls12_381) => BLS12_381_PARAMS,
+        Some(CurveId::Bn254) | None => BN254_PARAMS,
+    }
+}
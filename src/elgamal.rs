@@ -0,0 +1,340 @@
+// 选票加密与门限解密
+//
+// 选票以指数ElGamal加密到受托人共享的公钥上（Baby Jubjub曲线，其基域即BN254的标量域，
+// 因此可在投票电路所在的域内直接做椭圆曲线运算）：
+//   C1 = r·G，C2 = v·G + r·PK
+// EncryptedVoteCircuit 证明密文格式正确且加密的是 0 或 1；密文可逐点相加得到加密的总票数，
+// 开票时任意 threshold 名受托人各自给出解密份额，combine_shares 合并份额并恢复总票数。
+//
+// 密钥份额目前由可信分发者按Shamir秘密共享生成，分布式密钥生成不在本模块范围内。
+
+use crate::VoteError;
+use ark_bn254::Fr;
+use ark_ec::twisted_edwards::TECurveConfig;
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ed_on_bn254::{EdwardsAffine, EdwardsConfig, EdwardsProjective, Fr as Scalar};
+use ark_ff::{BigInteger, Field, PrimeField, UniformRand, Zero};
+use ark_relations::lc;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+use ark_std::rand::{CryptoRng, RngCore};
+use std::collections::HashSet;
+use std::ops::Add;
+use tracing::debug;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// 单位元 (0, 1)
+fn identity() -> EdwardsAffine {
+    <EdwardsAffine as AffineRepr>::zero()
+}
+
+// 密文
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub c1: EdwardsAffine,
+    pub c2: EdwardsAffine,
+}
+
+impl Ciphertext {
+    // 加密0且随机值为0的密文，作为同态求和的起点
+    pub fn zero() -> Self {
+        Self { c1: identity(), c2: identity() }
+    }
+}
+
+// 同态加法：两个密文相加得到明文之和的密文
+impl Add for Ciphertext {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            c1: (self.c1 + other.c1).into_affine(),
+            c2: (self.c2 + other.c2).into_affine(),
+        }
+    }
+}
+
+// 受托人持有的私钥份额
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+    pub index: u64, // 受托人编号（从1开始）
+    pub secret: Scalar,
+}
+
+impl Zeroize for KeyShare {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for KeyShare {}
+
+// 受托人对某个密文给出的解密份额 secret_i·C1
+#[derive(Clone, Debug)]
+pub struct DecryptionShare {
+    pub index: u64,
+    pub point: EdwardsAffine,
+}
+
+// 生成共享公钥及 num_trustees 份私钥份额，任意 threshold 份即可解密
+pub fn generate_threshold_keys<R: RngCore + CryptoRng>(
+    threshold: usize,
+    num_trustees: usize,
+    rng: &mut R,
+) -> Result<(EdwardsAffine, Vec<KeyShare>), VoteError> {
+    if threshold == 0 || threshold > num_trustees {
+        return Err(VoteError::Decryption(format!(
+            "invalid threshold {} of {} trustees",
+            threshold, num_trustees
+        )));
+    }
+
+    // 随机多项式 f(x) = s + a_1·x + ... + a_{t-1}·x^{t-1}，私钥为 s = f(0)
+    let coefficients: Vec<Scalar> = (0..threshold).map(|_| Scalar::rand(rng)).collect();
+    let shares = (1..=num_trustees as u64)
+        .map(|index| {
+            let x = Scalar::from(index);
+            let secret = coefficients.iter().rev().fold(Scalar::zero(), |acc, c| acc * x + c);
+            KeyShare { index, secret }
+        })
+        .collect();
+    let public_key = (EdwardsAffine::generator() * coefficients[0]).into_affine();
+
+    Ok((public_key, shares))
+}
+
+// 加密投票值
+pub fn encrypt(public_key: &EdwardsAffine, vote: u64, randomness: Scalar) -> Ciphertext {
+    let g = EdwardsAffine::generator();
+    Ciphertext {
+        c1: (g * randomness).into_affine(),
+        c2: (g * Scalar::from(vote) + *public_key * randomness).into_affine(),
+    }
+}
+
+// 计算受托人的解密份额
+pub fn decryption_share(share: &KeyShare, ciphertext: &Ciphertext) -> DecryptionShare {
+    DecryptionShare {
+        index: share.index,
+        point: (ciphertext.c1 * share.secret).into_affine(),
+    }
+}
+
+// 合并解密份额并恢复明文，明文须位于 [0, max_value] 范围内
+// 份额数量不足门限时拉格朗日插值得到的不是真实私钥，此时在范围内找不到明文而报错
+pub fn combine_shares(
+    ciphertext: &Ciphertext,
+    shares: &[DecryptionShare],
+    max_value: u64,
+) -> Result<u64, VoteError> {
+    debug!("Combining {} decryption shares", shares.len());
+
+    let mut seen = HashSet::new();
+    if shares.is_empty() || !shares.iter().all(|s| s.index != 0 && seen.insert(s.index)) {
+        return Err(VoteError::Decryption("decryption shares must have distinct non-zero indices".to_string()));
+    }
+
+    // 在 x = 0 处做拉格朗日插值：s·C1 = Σ λ_i·(s_i·C1)
+    let mut blinding = EdwardsProjective::zero();
+    for share in shares {
+        let xi = Scalar::from(share.index);
+        let mut lambda = Scalar::from(1u64);
+        for other in shares.iter().filter(|o| o.index != share.index) {
+            let xj = Scalar::from(other.index);
+            lambda *= xj * (xj - xi).inverse().unwrap();
+        }
+        blinding += share.point * lambda;
+    }
+
+    // m·G = C2 - s·C1，在允许范围内求离散对数
+    let target = ciphertext.c2.into_group() - blinding;
+    let g = EdwardsAffine::generator();
+    let mut candidate = EdwardsProjective::zero();
+    for m in 0..=max_value {
+        if candidate == target {
+            return Ok(m);
+        }
+        candidate += g;
+    }
+    Err(VoteError::Decryption(format!("plaintext not found in [0, {}]", max_value)))
+}
+
+// 电路内的曲线点：坐标的线性组合及其值
+#[derive(Clone)]
+struct PointVar {
+    x: LinearCombination<Fr>,
+    y: LinearCombination<Fr>,
+    value: EdwardsAffine,
+}
+
+impl PointVar {
+    // 以公开输入变量表示的点
+    fn input(cs: &ConstraintSystemRef<Fr>, value: EdwardsAffine) -> Result<Self, SynthesisError> {
+        let x = cs.new_input_variable(|| Ok(value.x))?;
+        let y = cs.new_input_variable(|| Ok(value.y))?;
+        Ok(Self { x: lc!() + x, y: lc!() + y, value })
+    }
+
+    // 常量点
+    fn constant(value: EdwardsAffine) -> Self {
+        Self {
+            x: lc!() + (value.x, Variable::One),
+            y: lc!() + (value.y, Variable::One),
+            value,
+        }
+    }
+
+    // 比特为1时取常量点 p，否则取单位元 (0, 1)（坐标对比特是线性的，无需约束）
+    fn select_constant(bit: Variable, bit_val: bool, p: EdwardsAffine) -> Self {
+        Self {
+            x: lc!() + (p.x, bit),
+            y: lc!() + Variable::One + (p.y - Fr::from(1u64), bit),
+            value: if bit_val { p } else { identity() },
+        }
+    }
+
+    // 比特为1时取 self，否则取单位元
+    fn select(&self, cs: &ConstraintSystemRef<Fr>, bit: Variable, bit_val: bool) -> Result<Self, SynthesisError> {
+        let value = if bit_val { self.value } else { identity() };
+        let x = cs.new_witness_variable(|| Ok(value.x))?;
+        cs.enforce_constraint(lc!() + bit, self.x.clone(), lc!() + x)?;
+        // y = 1 + bit·(self.y - 1)
+        let y = cs.new_witness_variable(|| Ok(value.y))?;
+        cs.enforce_constraint(
+            lc!() + bit,
+            self.y.clone() - Variable::One,
+            lc!() + y - Variable::One,
+        )?;
+        Ok(Self { x: lc!() + x, y: lc!() + y, value })
+    }
+
+    // 扭曲爱德华兹曲线上的完全加法公式：
+    // x3 = (x1·y2 + y1·x2) / (1 + d·x1·x2·y1·y2)，y3 = (y1·y2 - a·x1·x2) / (1 - d·x1·x2·y1·y2)
+    fn add(&self, cs: &ConstraintSystemRef<Fr>, other: &Self) -> Result<Self, SynthesisError> {
+        let (x1, y1) = (self.value.x, self.value.y);
+        let (x2, y2) = (other.value.x, other.value.y);
+        let a = EdwardsConfig::COEFF_A;
+        let d = EdwardsConfig::COEFF_D;
+
+        let product = |l: &LinearCombination<Fr>, r: &LinearCombination<Fr>, v: Fr| {
+            let var = cs.new_witness_variable(|| Ok(v))?;
+            cs.enforce_constraint(l.clone(), r.clone(), lc!() + var)?;
+            Ok::<_, SynthesisError>(var)
+        };
+        let x1y2 = product(&self.x, &other.y, x1 * y2)?;
+        let y1x2 = product(&self.y, &other.x, y1 * x2)?;
+        let x1x2 = product(&self.x, &other.x, x1 * x2)?;
+        let y1y2 = product(&self.y, &other.y, y1 * y2)?;
+        let t = product(&(lc!() + x1x2), &(lc!() + y1y2), x1 * x2 * y1 * y2)?;
+
+        let value = (self.value + other.value).into_affine();
+        let x3 = cs.new_witness_variable(|| Ok(value.x))?;
+        cs.enforce_constraint(lc!() + x3, lc!() + Variable::One + (d, t), lc!() + x1y2 + y1x2)?;
+        let y3 = cs.new_witness_variable(|| Ok(value.y))?;
+        cs.enforce_constraint(lc!() + y3, lc!() + Variable::One + (-d, t), lc!() + y1y2 + (-a, x1x2))?;
+
+        Ok(Self { x: lc!() + x3, y: lc!() + y3, value })
+    }
+
+    // 约束两个点相等
+    fn enforce_equal(&self, cs: &ConstraintSystemRef<Fr>, other: &Self) -> Result<(), SynthesisError> {
+        cs.enforce_constraint(self.x.clone(), lc!() + Variable::One, other.x.clone())?;
+        cs.enforce_constraint(self.y.clone(), lc!() + Variable::One, other.y.clone())
+    }
+}
+
+// 选票加密电路
+// 公开输入顺序：[PK.x, PK.y, C1.x, C1.y, C2.x, C2.y]
+#[derive(Clone, Debug)]
+pub struct EncryptedVoteCircuit {
+    pub public_key: EdwardsAffine,
+    pub ciphertext: Ciphertext,
+    pub vote: Fr,           // 投票选择（私有输入）
+    pub randomness: Scalar, // 加密随机值（私有输入）
+}
+
+impl EncryptedVoteCircuit {
+    // 加密投票值并构造对应电路
+    pub fn new(public_key: EdwardsAffine, vote: u64, randomness: Scalar) -> Self {
+        Self {
+            public_key,
+            ciphertext: encrypt(&public_key, vote, randomness),
+            vote: Fr::from(vote),
+            randomness,
+        }
+    }
+
+    // 构造仅用于密钥生成的空电路
+    pub fn blank() -> Self {
+        Self::new(EdwardsAffine::generator(), 0, Scalar::zero())
+    }
+
+    // 按电路分配顺序输出公开输入
+    pub fn public_inputs(&self) -> Vec<Fr> {
+        let (pk, c) = (self.public_key, self.ciphertext);
+        vec![pk.x, pk.y, c.c1.x, c.c1.y, c.c2.x, c.c2.y]
+    }
+}
+
+impl Zeroize for EncryptedVoteCircuit {
+    fn zeroize(&mut self) {
+        self.vote.zeroize();
+        self.randomness.zeroize();
+    }
+}
+
+impl Drop for EncryptedVoteCircuit {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for EncryptedVoteCircuit {}
+
+impl ConstraintSynthesizer<Fr> for EncryptedVoteCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let public_key = PointVar::input(&cs, self.public_key)?;
+        let c1 = PointVar::input(&cs, self.ciphertext.c1)?;
+        let c2 = PointVar::input(&cs, self.ciphertext.c2)?;
+
+        // vote ∈ {0, 1}
+        let vote_val = self.vote == Fr::from(1u64);
+        let vote = cs.new_witness_variable(|| Ok(self.vote))?;
+        cs.enforce_constraint(lc!() + vote, lc!() + vote - Variable::One, lc!())?;
+
+        // 随机值按比特分解，同一组比特同时用于 r·G 和 r·PK
+        let g = EdwardsAffine::generator();
+        let mut g_power = g.into_group();
+        let mut pk_power = public_key.clone();
+        let mut r_g = PointVar::constant(identity());
+        let mut r_pk = PointVar::constant(identity());
+        let bits = self.randomness.into_bigint().to_bits_le();
+        for (i, bit_val) in bits.into_iter().take(Scalar::MODULUS_BIT_SIZE as usize).enumerate() {
+            let bit = cs.new_witness_variable(|| Ok(Fr::from(bit_val)))?;
+            cs.enforce_constraint(lc!() + bit, lc!() + bit - Variable::One, lc!())?;
+
+            r_g = r_g.add(&cs, &PointVar::select_constant(bit, bit_val, g_power.into_affine()))?;
+            r_pk = r_pk.add(&cs, &pk_power.select(&cs, bit, bit_val)?)?;
+
+            if i + 1 < Scalar::MODULUS_BIT_SIZE as usize {
+                g_power.double_in_place();
+                pk_power = pk_power.add(&cs, &pk_power)?;
+            }
+        }
+
+        // C1 = r·G，C2 = v·G + r·PK
+        r_g.enforce_equal(&cs, &c1)?;
+        PointVar::select_constant(vote, vote_val, g)
+            .add(&cs, &r_pk)?
+            .enforce_equal(&cs, &c2)?;
+
+        Ok(())
+    }
+}
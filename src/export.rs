@@ -0,0 +1,470 @@
+// 链上验证导出
+//
+// 为BN254上的Groth16验证密钥生成Solidity验证合约（使用EVM的0x06/0x07/0x08预编译），
+// 并按合约 verifyProof 函数的ABI布局编码调用数据；
+// 另提供与snarkjs互通的证明、公开信号和验证密钥JSON格式，以及供第三方发布和比对的可读验证密钥JSON。
+//
+// snarkjs 的 bn128 即 arkworks 的 BN254（alt_bn128），两者的基域、标量域和生成元一致，
+// 因此 circom/snarkjs 生成的 Groth16 证明可直接用 VoteSystem::verify 验证（反之亦然）。
+// 坐标和公开信号均为标准形式（非Montgomery形式）的大端十进制字符串。
+
+use crate::{check_public_input_count, field_to_decimal, fingerprint, parse_field_element, KeyFingerprint, ProofSystem, VoteError, VoteSystem};
+use ark_bn254::{Bn254, Fq, Fq2, Fq6, Fr, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use ark_ff::{One, PrimeField, Zero};
+use ark_groth16::{Groth16, Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use num_bigint::BigUint;
+use serde_json::{json, Value};
+
+// 生成的合约名
+pub const VERIFIER_CONTRACT_NAME: &str = "BallotVerifier";
+
+// 基域元素的十进制表示
+fn fq_decimal(x: Fq) -> String {
+    BigUint::from(x.into_bigint()).to_string()
+}
+
+// G1点的EVM坐标 (x, y)，无穷远点编码为 (0, 0)
+fn g1_coords(p: &G1Affine) -> [Fq; 2] {
+    p.xy().map(|(x, y)| [*x, *y]).unwrap_or([Fq::zero(); 2])
+}
+
+// G2点的EVM坐标，扩域元素按预编译约定先虚部后实部：(x.c1, x.c0, y.c1, y.c0)
+fn g2_coords(p: &G2Affine) -> [Fq; 4] {
+    let (x, y) = p.xy().map(|(x, y)| (*x, *y)).unwrap_or((Fq2::zero(), Fq2::zero()));
+    [x.c1, x.c0, y.c1, y.c0]
+}
+
+// verifyProof 的函数签名（公开输入个数决定定长数组长度）
+fn verify_signature(num_inputs: usize) -> String {
+    format!("verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[{}])", num_inputs)
+}
+
+// 生成Groth16验证合约
+pub fn solidity_verifier(vk: &VerifyingKey<Bn254>) -> String {
+    let num_inputs = vk.gamma_abc_g1.len() - 1;
+    let mut out = String::new();
+
+    let mut line = |s: String| {
+        out.push_str(&s);
+        out.push('\n');
+    };
+
+    line("// SPDX-License-Identifier: MIT".to_string());
+    line("// Generated by zkp-vote. Do not edit.".to_string());
+    line("pragma solidity ^0.8.0;".to_string());
+    line(String::new());
+    line(format!("contract {} {{", VERIFIER_CONTRACT_NAME));
+    line(format!(
+        "    uint256 constant SNARK_SCALAR_FIELD = {};",
+        BigUint::from(Fr::MODULUS)
+    ));
+    line(format!("    uint256 constant PRIME_Q = {};", BigUint::from(Fq::MODULUS)));
+    line(String::new());
+
+    let [ax, ay] = g1_coords(&vk.alpha_g1);
+    line(format!("    uint256 constant ALPHA_X = {};", fq_decimal(ax)));
+    line(format!("    uint256 constant ALPHA_Y = {};", fq_decimal(ay)));
+    for (name, point) in [("BETA", &vk.beta_g2), ("GAMMA", &vk.gamma_g2), ("DELTA", &vk.delta_g2)] {
+        let [x1, x0, y1, y0] = g2_coords(point);
+        line(format!("    uint256 constant {}_X1 = {};", name, fq_decimal(x1)));
+        line(format!("    uint256 constant {}_X0 = {};", name, fq_decimal(x0)));
+        line(format!("    uint256 constant {}_Y1 = {};", name, fq_decimal(y1)));
+        line(format!("    uint256 constant {}_Y0 = {};", name, fq_decimal(y0)));
+    }
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        let [x, y] = g1_coords(point);
+        line(format!("    uint256 constant IC{}_X = {};", i, fq_decimal(x)));
+        line(format!("    uint256 constant IC{}_Y = {};", i, fq_decimal(y)));
+    }
+    line(String::new());
+
+    line("    function verifyProof(".to_string());
+    line("        uint256[2] calldata a,".to_string());
+    line("        uint256[2][2] calldata b,".to_string());
+    line("        uint256[2] calldata c,".to_string());
+    line(format!("        uint256[{}] calldata input", num_inputs));
+    line("    ) external view returns (bool) {".to_string());
+    line(format!("        for (uint256 i = 0; i < {}; i++) {{", num_inputs));
+    line("            require(input[i] < SNARK_SCALAR_FIELD, \"input out of range\");".to_string());
+    line("        }".to_string());
+    line(String::new());
+    line("        // vk_x = IC0 + sum(input[i] * IC{i+1})".to_string());
+    line("        uint256[2] memory vkX = [IC0_X, IC0_Y];".to_string());
+    for i in 0..num_inputs {
+        line(format!(
+            "        vkX = ecAdd(vkX, ecMul([IC{}_X, IC{}_Y], input[{}]));",
+            i + 1,
+            i + 1,
+            i
+        ));
+    }
+    line(String::new());
+    line("        // e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1".to_string());
+    line("        uint256[24] memory p;".to_string());
+    line("        p[0] = a[0];".to_string());
+    line("        p[1] = (PRIME_Q - (a[1] % PRIME_Q)) % PRIME_Q;".to_string());
+    line("        p[2] = b[0][0];".to_string());
+    line("        p[3] = b[0][1];".to_string());
+    line("        p[4] = b[1][0];".to_string());
+    line("        p[5] = b[1][1];".to_string());
+    line("        p[6] = ALPHA_X;".to_string());
+    line("        p[7] = ALPHA_Y;".to_string());
+    line("        p[8] = BETA_X1;".to_string());
+    line("        p[9] = BETA_X0;".to_string());
+    line("        p[10] = BETA_Y1;".to_string());
+    line("        p[11] = BETA_Y0;".to_string());
+    line("        p[12] = vkX[0];".to_string());
+    line("        p[13] = vkX[1];".to_string());
+    line("        p[14] = GAMMA_X1;".to_string());
+    line("        p[15] = GAMMA_X0;".to_string());
+    line("        p[16] = GAMMA_Y1;".to_string());
+    line("        p[17] = GAMMA_Y0;".to_string());
+    line("        p[18] = c[0];".to_string());
+    line("        p[19] = c[1];".to_string());
+    line("        p[20] = DELTA_X1;".to_string());
+    line("        p[21] = DELTA_X0;".to_string());
+    line("        p[22] = DELTA_Y1;".to_string());
+    line("        p[23] = DELTA_Y0;".to_string());
+    line(String::new());
+    line("        uint256[1] memory result;".to_string());
+    line("        bool success;".to_string());
+    line("        assembly {".to_string());
+    line("            success := staticcall(gas(), 0x08, p, 768, result, 0x20)".to_string());
+    line("        }".to_string());
+    line("        return success && result[0] == 1;".to_string());
+    line("    }".to_string());
+    line(String::new());
+    line("    function ecAdd(uint256[2] memory p1, uint256[2] memory p2) internal view returns (uint256[2] memory r) {".to_string());
+    line("        uint256[4] memory inp = [p1[0], p1[1], p2[0], p2[1]];".to_string());
+    line("        bool success;".to_string());
+    line("        assembly {".to_string());
+    line("            success := staticcall(gas(), 0x06, inp, 0x80, r, 0x40)".to_string());
+    line("        }".to_string());
+    line("        require(success, \"ecAdd failed\");".to_string());
+    line("    }".to_string());
+    line(String::new());
+    line("    function ecMul(uint256[2] memory p1, uint256 s) internal view returns (uint256[2] memory r) {".to_string());
+    line("        uint256[3] memory inp = [p1[0], p1[1], s];".to_string());
+    line("        bool success;".to_string());
+    line("        assembly {".to_string());
+    line("            success := staticcall(gas(), 0x07, inp, 0x60, r, 0x40)".to_string());
+    line("        }".to_string());
+    line("        require(success, \"ecMul failed\");".to_string());
+    line("    }".to_string());
+    line("}".to_string());
+
+    out
+}
+
+// 32字节大端编码（ABI中uint256的编码方式）
+fn abi_word<F: PrimeField>(x: F) -> [u8; 32] {
+    let bytes = Into::<BigUint>::into(x.into_bigint()).to_bytes_be();
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
+
+// 按合约 verifyProof 的ABI布局编码调用数据：
+// 4字节函数选择器，随后依次为 a[2]、b[2][2]、c[2]、input[n]，每项32字节大端
+pub fn evm_calldata(public_inputs: &[Fr], proof: &Proof<Bn254>) -> Vec<u8> {
+    let selector = ethers::utils::keccak256(verify_signature(public_inputs.len()).as_bytes());
+
+    let mut calldata = Vec::with_capacity(4 + 32 * (8 + public_inputs.len()));
+    calldata.extend_from_slice(&selector[..4]);
+    for x in g1_coords(&proof.a) {
+        calldata.extend_from_slice(&abi_word(x));
+    }
+    for x in g2_coords(&proof.b) {
+        calldata.extend_from_slice(&abi_word(x));
+    }
+    for x in g1_coords(&proof.c) {
+        calldata.extend_from_slice(&abi_word(x));
+    }
+    for x in public_inputs {
+        calldata.extend_from_slice(&abi_word(*x));
+    }
+    calldata
+}
+
+// snarkjs 使用的协议与曲线标识
+const SNARKJS_PROTOCOL: &str = "groth16";
+const SNARKJS_CURVE: &str = "bn128";
+
+// 解析失败统一视为数据格式错误
+fn invalid_data() -> VoteError {
+    VoteError::Serialization(SerializationError::InvalidData)
+}
+
+// G1点的snarkjs表示：射影坐标 [x, y, z] 的十进制字符串，无穷远点为 ["0", "1", "0"]
+// 注意必须先 into_bigint() 转为标准表示，直接取内部值得到的是Montgomery形式
+fn g1_to_snarkjs(p: &G1Affine) -> Value {
+    match p.xy() {
+        Some((x, y)) => json!([fq_decimal(*x), fq_decimal(*y), "1"]),
+        None => json!(["0", "1", "0"]),
+    }
+}
+
+// G2点的snarkjs表示：扩域元素按 [c0, c1] 顺序（与EVM预编译的先虚部后实部相反）
+fn g2_to_snarkjs(p: &G2Affine) -> Value {
+    let fq2 = |x: &Fq2| json!([fq_decimal(x.c0), fq_decimal(x.c1)]);
+    match p.xy() {
+        Some((x, y)) => json!([fq2(x), fq2(y), ["1", "0"]]),
+        None => json!([["0", "0"], ["1", "0"], ["0", "0"]]),
+    }
+}
+
+// 解析十进制字符串表示的基域元素
+fn fq_from_snarkjs(v: &Value) -> Result<Fq, VoteError> {
+    parse_field_element(v.as_str().ok_or_else(invalid_data)?)
+}
+
+// 解析 [c0, c1] 形式的扩域元素
+fn fq2_from_snarkjs(v: &Value) -> Result<Fq2, VoteError> {
+    match v.as_array().map(Vec::as_slice) {
+        Some([c0, c1]) => Ok(Fq2::new(fq_from_snarkjs(c0)?, fq_from_snarkjs(c1)?)),
+        _ => Err(invalid_data()),
+    }
+}
+
+// 解析G1点并校验其在曲线和正确子群上
+fn g1_from_snarkjs(v: &Value) -> Result<G1Affine, VoteError> {
+    let [x, y, z] = match v.as_array().map(Vec::as_slice) {
+        Some([x, y, z]) => [fq_from_snarkjs(x)?, fq_from_snarkjs(y)?, fq_from_snarkjs(z)?],
+        _ => return Err(invalid_data()),
+    };
+    if z.is_zero() {
+        return Ok(G1Affine::identity());
+    }
+    if !z.is_one() {
+        return Err(invalid_data());
+    }
+    let p = G1Affine::new_unchecked(x, y);
+    if !p.is_on_curve() || !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(invalid_data());
+    }
+    Ok(p)
+}
+
+// 解析G2点并校验其在曲线和正确子群上
+fn g2_from_snarkjs(v: &Value) -> Result<G2Affine, VoteError> {
+    let [x, y, z] = match v.as_array().map(Vec::as_slice) {
+        Some([x, y, z]) => [fq2_from_snarkjs(x)?, fq2_from_snarkjs(y)?, fq2_from_snarkjs(z)?],
+        _ => return Err(invalid_data()),
+    };
+    if z.is_zero() {
+        return Ok(G2Affine::identity());
+    }
+    if !z.is_one() {
+        return Err(invalid_data());
+    }
+    let p = G2Affine::new_unchecked(x, y);
+    if !p.is_on_curve() || !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(invalid_data());
+    }
+    Ok(p)
+}
+
+// 校验协议和曲线标识（缺省时视为兼容）
+fn check_snarkjs_header(v: &Value) -> Result<(), VoteError> {
+    for (key, expected) in [("protocol", SNARKJS_PROTOCOL), ("curve", SNARKJS_CURVE)] {
+        if let Some(actual) = v.get(key) {
+            if actual.as_str() != Some(expected) {
+                return Err(invalid_data());
+            }
+        }
+    }
+    Ok(())
+}
+
+// 导出为snarkjs格式的证明（proof.json）
+pub fn to_snarkjs_proof(proof: &Proof<Bn254>) -> Value {
+    json!({
+        "pi_a": g1_to_snarkjs(&proof.a),
+        "pi_b": g2_to_snarkjs(&proof.b),
+        "pi_c": g1_to_snarkjs(&proof.c),
+        "protocol": SNARKJS_PROTOCOL,
+        "curve": SNARKJS_CURVE,
+    })
+}
+
+// 解析snarkjs格式的证明（与二进制格式一样拒绝含单位元的证明）
+pub fn from_snarkjs_proof(v: &Value) -> Result<Proof<Bn254>, VoteError> {
+    check_snarkjs_header(v)?;
+    let field = |key: &str| v.get(key).ok_or_else(invalid_data);
+    let proof = Proof {
+        a: g1_from_snarkjs(field("pi_a")?)?,
+        b: g2_from_snarkjs(field("pi_b")?)?,
+        c: g1_from_snarkjs(field("pi_c")?)?,
+    };
+    <Groth16<Bn254> as ProofSystem<Fr>>::check_proof(&proof)?;
+    Ok(proof)
+}
+
+// 导出为snarkjs格式的验证密钥（verification_key.json）
+// vk_alphabeta_12 为 e(alpha, beta)，旧版snarkjs验证时会用到
+pub fn to_snarkjs_vkey(vk: &VerifyingKey<Bn254>) -> Value {
+    let alphabeta = Bn254::pairing(vk.alpha_g1, vk.beta_g2).0;
+    let fq2 = |x: &Fq2| json!([fq_decimal(x.c0), fq_decimal(x.c1)]);
+    let fq6 = |x: &Fq6| json!([fq2(&x.c0), fq2(&x.c1), fq2(&x.c2)]);
+
+    json!({
+        "protocol": SNARKJS_PROTOCOL,
+        "curve": SNARKJS_CURVE,
+        "nPublic": vk.gamma_abc_g1.len() - 1,
+        "vk_alpha_1": g1_to_snarkjs(&vk.alpha_g1),
+        "vk_beta_2": g2_to_snarkjs(&vk.beta_g2),
+        "vk_gamma_2": g2_to_snarkjs(&vk.gamma_g2),
+        "vk_delta_2": g2_to_snarkjs(&vk.delta_g2),
+        "vk_alphabeta_12": [fq6(&alphabeta.c0), fq6(&alphabeta.c1)],
+        "IC": vk.gamma_abc_g1.iter().map(g1_to_snarkjs).collect::<Vec<_>>(),
+    })
+}
+
+// 解析snarkjs格式的验证密钥（vk_alphabeta_12 可由其他字段推出，不做解析）
+pub fn from_snarkjs_vkey(v: &Value) -> Result<VerifyingKey<Bn254>, VoteError> {
+    check_snarkjs_header(v)?;
+    let field = |key: &str| v.get(key).ok_or_else(invalid_data);
+
+    let gamma_abc_g1 = field("IC")?
+        .as_array()
+        .ok_or_else(invalid_data)?
+        .iter()
+        .map(g1_from_snarkjs)
+        .collect::<Result<Vec<_>, _>>()?;
+    if gamma_abc_g1.is_empty() {
+        return Err(invalid_data());
+    }
+    if let Some(n_public) = v.get("nPublic") {
+        if n_public.as_u64() != Some(gamma_abc_g1.len() as u64 - 1) {
+            return Err(invalid_data());
+        }
+    }
+
+    let vk = VerifyingKey {
+        alpha_g1: g1_from_snarkjs(field("vk_alpha_1")?)?,
+        beta_g2: g2_from_snarkjs(field("vk_beta_2")?)?,
+        gamma_g2: g2_from_snarkjs(field("vk_gamma_2")?)?,
+        delta_g2: g2_from_snarkjs(field("vk_delta_2")?)?,
+        gamma_abc_g1,
+    };
+    <Groth16<Bn254> as ProofSystem<Fr>>::check_verifying_key(&vk)?;
+    Ok(vk)
+}
+
+// 导出为snarkjs格式的公开信号（public.json），顺序与 PublicInputs::to_vec() 一致
+pub fn to_snarkjs_public_signals(public_inputs: &[Fr]) -> Value {
+    json!(public_inputs.iter().map(|x| field_to_decimal(*x)).collect::<Vec<_>>())
+}
+
+// 解析snarkjs格式的公开信号：十进制字符串数组，每个值须在标量域范围内（超出时返回 FieldRangeExceeded）
+pub fn from_snarkjs_public_signals(v: &Value) -> Result<Vec<Fr>, VoteError> {
+    v.as_array()
+        .ok_or_else(invalid_data)?
+        .iter()
+        .map(|signal| parse_field_element(signal.as_str().ok_or_else(invalid_data)?))
+        .collect()
+}
+
+// 验证snarkjs格式的证明和公开信号
+// 公开信号数量与验证密钥不符时返回 KeyMismatch，而不是让验证静默失败
+pub fn verify_snarkjs(
+    system: &VoteSystem<Fr, Groth16<Bn254>>,
+    vk: &VerifyingKey<Bn254>,
+    proof: &Value,
+    public_signals: &Value,
+) -> Result<bool, VoteError> {
+    let proof = from_snarkjs_proof(proof)?;
+    let public_inputs = from_snarkjs_public_signals(public_signals)?;
+    check_public_input_count::<Fr, Groth16<Bn254>>(vk, public_inputs.len())?;
+    system.verify(vk, &public_inputs, &proof)
+}
+
+// 可读验证密钥JSON的格式标识和版本
+pub const VK_JSON_FORMAT: &str = "zkp-vote/verifying-key";
+pub const VK_JSON_VERSION: u64 = 1;
+
+// 点的十六进制表示：arkworks 的规范压缩编码（x 坐标小端序，最高字节的高位为 y 的符号和无穷远点标志）
+fn point_to_hex<T: CanonicalSerialize>(p: &T) -> Result<String, VoteError> {
+    let mut bytes = Vec::with_capacity(p.compressed_size());
+    p.serialize_compressed(&mut bytes)?;
+    Ok(hex::encode(bytes))
+}
+
+// 解析十六进制压缩编码的点，并校验其在曲线和正确子群上
+fn point_from_hex<T: CanonicalDeserialize>(v: &Value) -> Result<T, VoteError> {
+    let bytes = hex::decode(v.as_str().ok_or_else(invalid_data)?).map_err(|_| invalid_data())?;
+    let mut reader = bytes.as_slice();
+    let p = T::deserialize_compressed(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(invalid_data());
+    }
+    Ok(p)
+}
+
+// 导出为可读JSON格式的验证密钥：
+// {
+//   "format": "zkp-vote/verifying-key", "version": 1, "protocol": "groth16", "curve": "bn254",
+//   "fingerprint": 验证密钥指纹（十六进制，见 fingerprint::fingerprint，与 /vk 等接口返回的一致）,
+//   "alpha_g1", "beta_g2", "gamma_g2", "delta_g2": 各点的十六进制压缩编码,
+//   "ic": 公开输入对应的G1点（第0项为常数项，其后按 PublicInputs::to_vec() 的顺序）
+// }
+pub fn vk_to_json(vk: &VerifyingKey<Bn254>) -> Result<Value, VoteError> {
+    Ok(json!({
+        "format": VK_JSON_FORMAT,
+        "version": VK_JSON_VERSION,
+        "protocol": SNARKJS_PROTOCOL,
+        "curve": "bn254",
+        "fingerprint": fingerprint::<Fr, _>(vk)?.to_string(),
+        "alpha_g1": point_to_hex(&vk.alpha_g1)?,
+        "beta_g2": point_to_hex(&vk.beta_g2)?,
+        "gamma_g2": point_to_hex(&vk.gamma_g2)?,
+        "delta_g2": point_to_hex(&vk.delta_g2)?,
+        "ic": vk.gamma_abc_g1.iter().map(point_to_hex).collect::<Result<Vec<_>, _>>()?,
+    }))
+}
+
+// 解析可读JSON格式的验证密钥，重新计算指纹并与 JSON 中记录的比对，不一致时返回 KeyMismatch
+pub fn vk_from_json(v: &Value) -> Result<VerifyingKey<Bn254>, VoteError> {
+    let field = |key: &str| v.get(key).ok_or_else(invalid_data);
+    if field("format")?.as_str() != Some(VK_JSON_FORMAT) {
+        return Err(invalid_data());
+    }
+    let version = field("version")?.as_u64().ok_or_else(invalid_data)?;
+    if version != VK_JSON_VERSION {
+        return Err(VoteError::UnsupportedVersion(format!(
+            "verifying key JSON version {} is not supported (latest is {})",
+            version, VK_JSON_VERSION
+        )));
+    }
+    if field("protocol")?.as_str() != Some(SNARKJS_PROTOCOL) || field("curve")?.as_str() != Some("bn254") {
+        return Err(invalid_data());
+    }
+    let expected = KeyFingerprint::from_hex(field("fingerprint")?.as_str().ok_or_else(invalid_data)?)?;
+
+    let gamma_abc_g1 = field("ic")?
+        .as_array()
+        .ok_or_else(invalid_data)?
+        .iter()
+        .map(point_from_hex)
+        .collect::<Result<Vec<G1Affine>, _>>()?;
+    let vk = VerifyingKey {
+        alpha_g1: point_from_hex(field("alpha_g1")?)?,
+        beta_g2: point_from_hex(field("beta_g2")?)?,
+        gamma_g2: point_from_hex(field("gamma_g2")?)?,
+        delta_g2: point_from_hex(field("delta_g2")?)?,
+        gamma_abc_g1,
+    };
+    <Groth16<Bn254> as ProofSystem<Fr>>::check_verifying_key(&vk)?;
+
+    let actual = fingerprint::<Fr, _>(&vk)?;
+    if actual != expected {
+        return Err(VoteError::KeyMismatch(format!(
+            "verifying key JSON records fingerprint {}, but its points hash to {}",
+            expected, actual
+        )));
+    }
+    Ok(vk)
+}
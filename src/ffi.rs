@@ -0,0 +1,226 @@
+// C 接口（供自助终端等 C/C++ 客户端调用）
+//
+// 头文件由 cbindgen 在构建时生成到 include/zkp_vote.h（见 build.rs）。
+// 内存所有权约定：
+//   - zkp_vote_load_keys 返回的密钥句柄归调用方所有，必须用 zkp_vote_free_keys 释放；
+//   - 其余函数的输入缓冲区只在调用期间借用，输出写入调用方分配的缓冲区，库不会保留任何调用方指针；
+//   - 输出缓冲区不足时返回 ZKP_VOTE_BUFFER_TOO_SMALL，并在 out_len 中写入所需长度。
+// 选票JSON和密钥格式见 client 模块。出错时可用 zkp_vote_last_error 取得本线程最近一次的错误信息。
+
+use crate::client::{self, ClientProvingKey, ClientVerifyingKey};
+use crate::VoteError;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// 调用成功
+pub const ZKP_VOTE_OK: i32 = 0;
+// 调用失败，详情见 zkp_vote_last_error
+pub const ZKP_VOTE_ERROR: i32 = -1;
+// 输出缓冲区不足
+pub const ZKP_VOTE_BUFFER_TOO_SMALL: i32 = -2;
+// 必需的指针参数为空
+pub const ZKP_VOTE_NULL_POINTER: i32 = -3;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+// 记录本线程最近一次的错误（错误码在前，便于调用方解析）
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).expect("nul bytes were replaced");
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+fn set_vote_error(e: &VoteError) {
+    set_last_error(format!("{}: {}", e.code(), e));
+}
+
+// 执行调用并捕获 panic，panic 不能跨越 C ABI 边界
+fn guard(f: impl FnOnce() -> i32) -> i32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(status) => status,
+        Err(_) => {
+            set_last_error("internal_error: panic in zkp-vote".to_string());
+            ZKP_VOTE_ERROR
+        }
+    }
+}
+
+// 借用调用方的字节缓冲区，长度为0时允许空指针
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(ptr, len))
+    }
+}
+
+// 借用调用方的 NUL 结尾字符串
+unsafe fn c_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>, VoteError> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|e| VoteError::InvalidBallot(format!("input is not valid UTF-8: {}", e)))
+}
+
+// 已加载的密钥（对 C 调用方不透明）
+pub struct ZkpVoteKeys {
+    proving_key: Option<ClientProvingKey>,
+    verifying_key: Option<ClientVerifyingKey>,
+}
+
+// 加载密钥：证明密钥为密钥文件格式，验证密钥为 /vk 返回的序列化格式；
+// 只做验证时 pk 可为空（pk_len 为 0），只做证明时 vk 同理；两者都提供时检查是否配对。失败时返回空指针
+/// # Safety
+/// `pk`/`vk` 必须指向至少 `pk_len`/`vk_len` 字节的可读内存（长度为 0 时可为空）。
+#[no_mangle]
+pub unsafe extern "C" fn zkp_vote_load_keys(
+    pk: *const u8,
+    pk_len: usize,
+    vk: *const u8,
+    vk_len: usize,
+) -> *mut ZkpVoteKeys {
+    let mut keys = std::ptr::null_mut();
+    guard(|| {
+        let (Some(pk), Some(vk)) = (bytes(pk, pk_len), bytes(vk, vk_len)) else {
+            set_last_error("null_pointer: key buffer is null".to_string());
+            return ZKP_VOTE_NULL_POINTER;
+        };
+        let loaded = (|| {
+            let keys = ZkpVoteKeys {
+                proving_key: (!pk.is_empty()).then(|| client::decode_proving_key(pk)).transpose()?,
+                verifying_key: (!vk.is_empty()).then(|| client::decode_verifying_key(vk)).transpose()?,
+            };
+            // 两把密钥都提供时检查是否配对
+            if let (Some(pk), Some(vk)) = (&keys.proving_key, &keys.verifying_key) {
+                client::check_key_pair(pk, vk)?;
+            }
+            Ok::<_, VoteError>(keys)
+        })();
+        match loaded {
+            Ok(loaded) => {
+                keys = Box::into_raw(Box::new(loaded));
+                ZKP_VOTE_OK
+            }
+            Err(e) => {
+                set_vote_error(&e);
+                ZKP_VOTE_ERROR
+            }
+        }
+    });
+    keys
+}
+
+// 释放密钥句柄，传入空指针时不做任何事
+/// # Safety
+/// `keys` 必须是 zkp_vote_load_keys 返回且尚未释放的句柄。
+#[no_mangle]
+pub unsafe extern "C" fn zkp_vote_free_keys(keys: *mut ZkpVoteKeys) {
+    if !keys.is_null() {
+        drop(Box::from_raw(keys));
+    }
+}
+
+// 为选票生成证明，压缩格式的证明写入 out，实际长度写入 out_len
+/// # Safety
+/// `keys` 必须是有效句柄，`ballot_json` 必须是 NUL 结尾的字符串，
+/// `out` 必须指向至少 `out_cap` 字节的可写内存，`out_len` 必须可写。
+#[no_mangle]
+pub unsafe extern "C" fn zkp_vote_prove(
+    keys: *const ZkpVoteKeys,
+    ballot_json: *const c_char,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        if keys.is_null() || out_len.is_null() {
+            set_last_error("null_pointer: keys and out_len must not be null".to_string());
+            return ZKP_VOTE_NULL_POINTER;
+        }
+        let result = c_str(ballot_json).and_then(|ballot| {
+            let ballot = ballot.ok_or_else(|| VoteError::InvalidBallot("ballot json is null".to_string()))?;
+            let pk = (*keys).proving_key.as_ref().ok_or_else(|| {
+                VoteError::KeyMismatch("no proving key was loaded".to_string())
+            })?;
+            client::prove_ballot(pk, ballot)
+        });
+        let proof = match result {
+            Ok(proof) => proof,
+            Err(e) => {
+                set_vote_error(&e);
+                return ZKP_VOTE_ERROR;
+            }
+        };
+        *out_len = proof.len();
+        if proof.len() > out_cap || out.is_null() {
+            set_last_error(format!("buffer_too_small: proof needs {} bytes", proof.len()));
+            return ZKP_VOTE_BUFFER_TOO_SMALL;
+        }
+        std::ptr::copy_nonoverlapping(proof.as_ptr(), out, proof.len());
+        ZKP_VOTE_OK
+    })
+}
+
+// 验证证明，公开输入为十进制字符串数组的JSON；有效返回 1，无效返回 0，出错返回负数
+/// # Safety
+/// `keys` 必须是有效句柄，`inputs_json` 必须是 NUL 结尾的字符串，
+/// `proof` 必须指向至少 `proof_len` 字节的可读内存。
+#[no_mangle]
+pub unsafe extern "C" fn zkp_vote_verify(
+    keys: *const ZkpVoteKeys,
+    inputs_json: *const c_char,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    guard(|| {
+        let proof = match bytes(proof, proof_len) {
+            Some(proof) if !keys.is_null() => proof,
+            _ => {
+                set_last_error("null_pointer: keys and proof must not be null".to_string());
+                return ZKP_VOTE_NULL_POINTER;
+            }
+        };
+        let result = c_str(inputs_json).and_then(|inputs| {
+            let inputs = inputs.ok_or_else(|| VoteError::InvalidBallot("public inputs json is null".to_string()))?;
+            let vk = (*keys).verifying_key.as_ref().ok_or_else(|| {
+                VoteError::KeyMismatch("no verifying key was loaded".to_string())
+            })?;
+            client::verify_ballot(vk, inputs, proof)
+        });
+        match result {
+            Ok(valid) => valid as i32,
+            Err(e) => {
+                set_vote_error(&e);
+                ZKP_VOTE_ERROR
+            }
+        }
+    })
+}
+
+// 将本线程最近一次的错误信息（NUL 结尾）复制到 buf，返回包含结尾 NUL 的完整长度；
+// 没有错误时返回 0。返回值大于 buf_cap 时信息被截断，可按返回值重新分配后再取
+/// # Safety
+/// `buf` 必须指向至少 `buf_cap` 字节的可写内存（buf_cap 为 0 时可为空）。
+#[no_mangle]
+pub unsafe extern "C" fn zkp_vote_last_error(buf: *mut c_char, buf_cap: usize) -> usize {
+    LAST_ERROR.with(|e| {
+        let e = e.borrow();
+        let Some(message) = e.as_ref() else {
+            return 0;
+        };
+        let message = message.as_bytes_with_nul();
+        if !buf.is_null() && buf_cap > 0 {
+            let n = message.len().min(buf_cap);
+            std::ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buf, n);
+            *buf.add(n - 1) = 0;
+        }
+        message.len()
+    })
+}
@@ -0,0 +1,58 @@
+// 字段元素的编码转换
+//
+// 客户端以十进制、十六进制或小端字节提交公开输入，这里是各编码的唯一实现。
+// 解析时不在 [0, F::MODULUS) 范围内的值一律拒绝（FieldRangeExceeded），而不是静默取模，
+// 否则同一字段元素会有多种合法编码（如 x 与 x + p），影响证明摘要去重和防重标识比较。
+
+use crate::VoteError;
+use ark_ff::{BigInteger, PrimeField};
+use num_bigint::BigUint;
+use std::str::FromStr;
+
+// 检查数值在字段范围内，original 为错误信息中显示的原始输入
+fn fr_from_biguint<F: PrimeField>(value: BigUint, original: &str) -> Result<F, VoteError> {
+    let modulus: BigUint = F::MODULUS.into();
+    if value >= modulus {
+        return Err(VoteError::FieldRangeExceeded(original.to_string()));
+    }
+    Ok(F::from(value))
+}
+
+// 解析十进制字符串表示的字段元素
+pub fn fr_from_decimal<F: PrimeField>(s: &str) -> Result<F, VoteError> {
+    let value = BigUint::from_str(s)
+        .map_err(|e| VoteError::InvalidBallot(format!("invalid field element {:?}: {}", s, e)))?;
+    fr_from_biguint(value, s)
+}
+
+// 字段元素的十进制字符串表示（与 fr_from_decimal 互逆）
+pub fn fr_to_decimal<F: PrimeField>(x: F) -> String {
+    let value: BigUint = x.into_bigint().into();
+    value.to_string()
+}
+
+// 解析十六进制字符串表示的字段元素（0x 前缀可选）
+pub fn fr_from_hex<F: PrimeField>(s: &str) -> Result<F, VoteError> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    let value = BigUint::parse_bytes(digits.as_bytes(), 16)
+        .ok_or_else(|| VoteError::InvalidBallot(format!("invalid hex field element {:?}", s)))?;
+    fr_from_biguint(value, s)
+}
+
+// 字段元素的十六进制字符串表示，带 0x 前缀、不补零（与 fr_from_hex 互逆）
+pub fn fr_to_hex<F: PrimeField>(x: F) -> String {
+    let value: BigUint = x.into_bigint().into();
+    format!("0x{:x}", value)
+}
+
+// 解析小端字节表示的字段元素，长度不限（高位可补零），数值须在字段范围内
+pub fn fr_from_le_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, VoteError> {
+    fr_from_biguint(BigUint::from_bytes_le(bytes), &format!("le:{}", hex::encode(bytes)))
+}
+
+// 字段元素的小端字节表示，长度固定为字段的字节数（与 fr_from_le_bytes 互逆）
+pub fn fr_to_le_bytes<F: PrimeField>(x: F) -> Vec<u8> {
+    let mut bytes = x.into_bigint().to_bytes_le();
+    bytes.resize((F::MODULUS_BIT_SIZE as usize).div_ceil(8), 0);
+    bytes
+}
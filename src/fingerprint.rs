@@ -0,0 +1,418 @@
+// 密钥指纹与完整性校验
+//
+// 密钥指纹 = BLAKE3(电路版本 || 曲线标识 || 密钥的规范序列化)。
+// 密钥文件以带指纹的头部开头，加载时重新计算指纹并与头部比对，截断或损坏的密钥直接拒绝；
+// 导出的证明信封携带验证密钥指纹，验证方可据此确认证明与所用验证密钥匹配；
+// 信封还记录格式版本，无法识别的未来版本直接报 UnsupportedVersion。
+// 密钥文件和信封都可选压缩或非压缩的点编码（见 Compression），所用格式记录在头部，解码时自动识别；
+// 指纹始终按压缩格式计算，与文件所用格式无关。
+// 密钥文件头部和证明信封都记录曲线标识，用另一条曲线加载时直接报 KeyMismatch 而不是反序列化失败。
+// 按电路配置生成的密钥可在头部额外记录配置摘要（见 encode_key_for_config），加载方据此确认密钥与所用配置一致。
+
+use crate::backend::serialize_with;
+use crate::curve::CurveId;
+use crate::{BallotPublicInputs, CircuitConfig, Compression, ProofSystem, VoteError, CIRCUIT_VERSION};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError, Validate};
+use std::fmt;
+use tracing::error;
+
+// 密钥文件头部的魔数，区分密钥本体的点编码格式
+const KEY_MAGIC: &[u8; 4] = b"ZKVK";
+const KEY_MAGIC_UNCOMPRESSED: &[u8; 4] = b"ZKVU";
+
+// 头部记录了电路配置摘要的密钥文件的魔数
+const KEY_MAGIC_CONFIG: &[u8; 4] = b"ZKCK";
+const KEY_MAGIC_CONFIG_UNCOMPRESSED: &[u8; 4] = b"ZKCU";
+
+// 密钥文件头部长度：魔数 + 电路版本 + 曲线标识 + 指纹 [+ 电路配置摘要]
+const KEY_HEADER_LEN: usize = 4 + 4 + 4 + 32;
+const KEY_HEADER_LEN_CONFIG: usize = KEY_HEADER_LEN + 32;
+
+// 密钥文件头部
+pub(crate) struct KeyHeader {
+    pub fingerprint: KeyFingerprint,
+    pub compression: Compression,
+    pub config_digest: Option<[u8; 32]>, // 生成密钥所用电路配置的摘要（见 CircuitConfig::digest），旧格式的密钥文件没有
+    pub len: usize,                      // 头部字节数，之后为密钥本体
+}
+
+// 密钥指纹
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyFingerprint(pub [u8; 32]);
+
+impl KeyFingerprint {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    // 解析十六进制表示的指纹
+    pub fn from_hex(s: &str) -> Result<Self, VoteError> {
+        let bytes = hex::decode(s)
+            .map_err(|e| VoteError::InvalidBallot(format!("invalid key fingerprint: {}", e)))?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| VoteError::InvalidBallot("key fingerprint must be 32 bytes".to_string()))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for KeyFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+// 计算证明密钥或验证密钥的指纹（曲线由密钥所在的标量域确定）
+pub fn fingerprint<F: PrimeField, K: CanonicalSerialize>(key: &K) -> Result<KeyFingerprint, VoteError> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"zkp-vote/key-fingerprint");
+    hasher.update(&CIRCUIT_VERSION.to_le_bytes());
+    hasher.update(&F::MODULUS.to_bytes_le());
+    let mut bytes = Vec::with_capacity(key.compressed_size());
+    key.serialize_compressed(&mut bytes)?;
+    hasher.update(&bytes);
+    Ok(KeyFingerprint(*hasher.finalize().as_bytes()))
+}
+
+// 验证密钥指纹
+// 客户端将其与证明元数据中的指纹比对，可区分"验证密钥不匹配"与"证明本身无效"
+pub fn vk_fingerprint<F: PrimeField, P: ProofSystem<F>>(vk: &P::VerifyingKey) -> [u8; 32] {
+    fingerprint::<F, _>(vk).expect("serializing a key into a Vec cannot fail").0
+}
+
+// 编码带指纹头部的密钥文件（压缩格式）
+pub fn encode_key<F: PrimeField, K: CanonicalSerialize>(key: &K) -> Result<Vec<u8>, VoteError> {
+    encode_key_with::<F, K>(key, Compression::Compressed)
+}
+
+// 按指定格式编码带指纹头部的密钥文件，格式由头部的魔数记录
+pub fn encode_key_with<F: PrimeField, K: CanonicalSerialize>(key: &K, compression: Compression) -> Result<Vec<u8>, VoteError> {
+    encode_key_header::<F, K>(key, compression, None)
+}
+
+// 编码头部记录电路配置摘要的密钥文件（压缩格式），密钥须按该配置生成
+pub fn encode_key_for_config<F: PrimeField, K: CanonicalSerialize>(
+    key: &K,
+    config: &CircuitConfig,
+) -> Result<Vec<u8>, VoteError> {
+    encode_key_header::<F, K>(key, Compression::Compressed, Some(config.digest()))
+}
+
+fn encode_key_header<F: PrimeField, K: CanonicalSerialize>(
+    key: &K,
+    compression: Compression,
+    config_digest: Option<[u8; 32]>,
+) -> Result<Vec<u8>, VoteError> {
+    let body = serialize_with(key, compression)?;
+    let mut bytes = Vec::with_capacity(KEY_HEADER_LEN_CONFIG + body.len());
+    bytes.extend_from_slice(match (compression, config_digest.is_some()) {
+        (Compression::Compressed, false) => KEY_MAGIC,
+        (Compression::Uncompressed, false) => KEY_MAGIC_UNCOMPRESSED,
+        (Compression::Compressed, true) => KEY_MAGIC_CONFIG,
+        (Compression::Uncompressed, true) => KEY_MAGIC_CONFIG_UNCOMPRESSED,
+    });
+    bytes.extend_from_slice(&CIRCUIT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&CurveId::require::<F>()?.to_u32().to_le_bytes());
+    bytes.extend_from_slice(fingerprint::<F, K>(key)?.as_bytes());
+    if let Some(digest) = config_digest {
+        bytes.extend_from_slice(&digest);
+    }
+    bytes.extend(body);
+    Ok(bytes)
+}
+
+// 密钥文件头部记录的电路配置摘要，旧格式的密钥文件返回 None
+pub fn key_config_digest<F: PrimeField>(bytes: &[u8]) -> Result<Option<[u8; 32]>, VoteError> {
+    Ok(parse_key_header::<F>(bytes)?.config_digest)
+}
+
+// 校验密钥文件头部（魔数、电路版本和曲线），返回头部记录的指纹、电路配置摘要和密钥本体的格式
+pub(crate) fn parse_key_header<F: PrimeField>(bytes: &[u8]) -> Result<KeyHeader, VoteError> {
+    let (compression, len) = match bytes.get(..4) {
+        Some(magic) if bytes.len() >= KEY_HEADER_LEN && magic == KEY_MAGIC => (Compression::Compressed, KEY_HEADER_LEN),
+        Some(magic) if bytes.len() >= KEY_HEADER_LEN && magic == KEY_MAGIC_UNCOMPRESSED => {
+            (Compression::Uncompressed, KEY_HEADER_LEN)
+        }
+        Some(magic) if bytes.len() >= KEY_HEADER_LEN_CONFIG && magic == KEY_MAGIC_CONFIG => {
+            (Compression::Compressed, KEY_HEADER_LEN_CONFIG)
+        }
+        Some(magic) if bytes.len() >= KEY_HEADER_LEN_CONFIG && magic == KEY_MAGIC_CONFIG_UNCOMPRESSED => {
+            (Compression::Uncompressed, KEY_HEADER_LEN_CONFIG)
+        }
+        _ => {
+            error!("Key file is missing its fingerprint header");
+            return Err(VoteError::Serialization(SerializationError::InvalidData));
+        }
+    };
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != CIRCUIT_VERSION {
+        error!("Key file was generated for circuit version {}, expected {}", version, CIRCUIT_VERSION);
+        return Err(VoteError::KeyMismatch(format!(
+            "key was generated for circuit version {}, expected {}",
+            version, CIRCUIT_VERSION
+        )));
+    }
+    CurveId::check::<F>(u32::from_le_bytes(bytes[8..12].try_into().unwrap())).map_err(|e| {
+        error!("Rejected key file: {}", e);
+        e
+    })?;
+    Ok(KeyHeader {
+        fingerprint: KeyFingerprint(bytes[12..KEY_HEADER_LEN].try_into().unwrap()),
+        compression,
+        config_digest: (len == KEY_HEADER_LEN_CONFIG).then(|| bytes[KEY_HEADER_LEN..len].try_into().unwrap()),
+        len,
+    })
+}
+
+// 解码密钥文件，重新计算指纹并与头部比对
+pub fn decode_key<F, K>(bytes: &[u8]) -> Result<K, VoteError>
+where
+    F: PrimeField,
+    K: CanonicalSerialize + CanonicalDeserialize,
+{
+    let header = parse_key_header::<F>(bytes)?;
+    let stored = header.fingerprint;
+
+    let mut body = &bytes[header.len..];
+    let key = K::deserialize_with_mode(&mut body, header.compression.into(), Validate::Yes).map_err(|e| {
+        error!("Failed to deserialize key with fingerprint {}: {}", stored, e);
+        e
+    })?;
+    let actual = fingerprint::<F, K>(&key)?;
+    if !body.is_empty() || actual != stored {
+        error!("Key fingerprint mismatch: header {}, recomputed {}", stored, actual);
+        return Err(VoteError::KeyMismatch(format!(
+            "key fingerprint mismatch: header {}, recomputed {}",
+            stored, actual
+        )));
+    }
+    Ok(key)
+}
+
+// 证明信封的当前格式版本
+// 信封长期保存（公告板、导出文件），格式变化时递增版本号，旧版本的信封须保持可解码
+// 2：头部记录证明的点编码格式（版本 1 的证明均为压缩格式）
+pub const PROOF_ENVELOPE_VERSION: u32 = 2;
+
+// 证明信封二进制编码的魔数
+const ENVELOPE_MAGIC: &[u8; 4] = b"ZKPE";
+
+// 证明信封二进制编码的最短头部长度（版本 1）：魔数 + 格式版本 + 曲线标识 + 电路版本 + 指纹
+const ENVELOPE_HEADER_LEN: usize = 4 + 4 + 4 + 4 + 32;
+
+// 导出的证明信封：证明、公开输入及生成时所用验证密钥的指纹
+// 除原始证明外还记录格式版本、曲线和电路版本，升级后仍能判断旧证明该用哪把验证密钥验证
+pub struct ProofEnvelope<F: PrimeField, P: ProofSystem<F>> {
+    pub format_version: u32,
+    pub curve_id: CurveId,
+    pub circuit_version: u32, // 生成证明时的电路版本（与验证密钥指纹一起确定所需的验证密钥）
+    pub compression: Compression, // 编码证明所用的点格式（版本 1 只支持压缩格式）
+    pub vk_fingerprint: KeyFingerprint,
+    pub public_inputs: BallotPublicInputs<F>,
+    pub proof: P::Proof,
+}
+
+// 证明信封的 JSON 编码（Web 响应和导出包中使用）
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProofEnvelopeJson {
+    pub format_version: u32,
+    pub curve_id: u32,
+    pub circuit_version: u32,
+    #[serde(default)]
+    pub compression: Compression,   // 证明的点编码格式（版本 1 的信封没有该字段，为压缩格式）
+    pub vk_fingerprint: String,     // 十六进制编码的验证密钥指纹
+    pub public_inputs: Vec<String>, // 十进制字符串表示的公开输入（按电路顺序）
+    pub proof: String,              // 十六进制编码的证明
+}
+
+// 拒绝当前实现无法解码的格式版本
+fn check_envelope_version(version: u32) -> Result<(), VoteError> {
+    if version == 0 || version > PROOF_ENVELOPE_VERSION {
+        error!("Rejected proof envelope with format version {}", version);
+        return Err(VoteError::UnsupportedVersion(format!(
+            "proof envelope format version {} is not supported (latest is {})",
+            version, PROOF_ENVELOPE_VERSION
+        )));
+    }
+    Ok(())
+}
+
+// 版本 1 的信封只能记录压缩格式的证明
+fn check_envelope_compression(version: u32, compression: Compression) -> Result<(), VoteError> {
+    if version == 1 && compression != Compression::Compressed {
+        return Err(VoteError::UnsupportedVersion(
+            "proof envelope format version 1 only supports compressed proofs".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// 解析信封中记录的曲线，须与当前标量域一致
+fn envelope_curve<F: PrimeField>(id: u32) -> Result<CurveId, VoteError> {
+    CurveId::check::<F>(id)?;
+    CurveId::require::<F>()
+}
+
+// 从二进制编码中读取小端序 u32
+fn read_u32(reader: &mut &[u8]) -> Result<u32, VoteError> {
+    if reader.len() < 4 {
+        return Err(VoteError::Serialization(SerializationError::InvalidData));
+    }
+    let (head, rest) = reader.split_at(4);
+    *reader = rest;
+    Ok(u32::from_le_bytes(head.try_into().unwrap()))
+}
+
+impl<F: PrimeField, P: ProofSystem<F>> ProofEnvelope<F, P> {
+    // 以验证密钥的指纹封装证明
+    pub fn new(vk: &P::VerifyingKey, public_inputs: BallotPublicInputs<F>, proof: P::Proof) -> Result<Self, VoteError> {
+        Ok(Self {
+            format_version: PROOF_ENVELOPE_VERSION,
+            curve_id: CurveId::require::<F>()?,
+            circuit_version: CIRCUIT_VERSION,
+            compression: Compression::Compressed,
+            vk_fingerprint: fingerprint::<F, _>(vk)?,
+            public_inputs,
+            proof,
+        })
+    }
+
+    // 改用指定的点格式编码证明
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    // 二进制编码（整数均为小端序）：
+    // 魔数 || 格式版本 || 曲线标识 || 电路版本 || [点格式（版本 2 起）] || 指纹 || 公开输入个数 || 公开输入 || 证明长度 || 证明
+    pub fn to_bytes(&self) -> Result<Vec<u8>, VoteError> {
+        check_envelope_version(self.format_version)?;
+        check_envelope_compression(self.format_version, self.compression)?;
+        let inputs = self.public_inputs.to_vec();
+        let proof = P::serialize_proof_with(&self.proof, self.compression)?;
+        let mut bytes = Vec::with_capacity(ENVELOPE_HEADER_LEN + 12 + proof.len());
+        bytes.extend_from_slice(ENVELOPE_MAGIC);
+        bytes.extend_from_slice(&self.format_version.to_le_bytes());
+        bytes.extend_from_slice(&self.curve_id.to_u32().to_le_bytes());
+        bytes.extend_from_slice(&self.circuit_version.to_le_bytes());
+        if self.format_version >= 2 {
+            bytes.extend_from_slice(&u32::from(self.compression.to_u8()).to_le_bytes());
+        }
+        bytes.extend_from_slice(self.vk_fingerprint.as_bytes());
+        bytes.extend_from_slice(&(inputs.len() as u32).to_le_bytes());
+        for x in inputs {
+            x.serialize_compressed(&mut bytes)?;
+        }
+        bytes.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+        bytes.extend(proof);
+        Ok(bytes)
+    }
+
+    // 解码二进制编码，先检查格式版本，未来版本的信封报 UnsupportedVersion
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VoteError> {
+        if bytes.len() < ENVELOPE_HEADER_LEN || &bytes[..4] != ENVELOPE_MAGIC {
+            return Err(VoteError::Serialization(SerializationError::InvalidData));
+        }
+        let mut reader = &bytes[4..];
+        let format_version = read_u32(&mut reader)?;
+        check_envelope_version(format_version)?;
+        let curve_id = envelope_curve::<F>(read_u32(&mut reader)?)?;
+        let circuit_version = read_u32(&mut reader)?;
+        let compression = if format_version >= 2 {
+            let value = u8::try_from(read_u32(&mut reader)?)
+                .map_err(|_| VoteError::Serialization(SerializationError::InvalidData))?;
+            Compression::from_u8(value)?
+        } else {
+            Compression::Compressed
+        };
+        if reader.len() < 32 {
+            return Err(VoteError::Serialization(SerializationError::InvalidData));
+        }
+        let vk_fingerprint = KeyFingerprint(reader[..32].try_into().unwrap());
+        reader = &reader[32..];
+
+        let num_inputs = read_u32(&mut reader)? as usize;
+        if num_inputs != crate::NUM_PUBLIC_INPUTS {
+            return Err(VoteError::Serialization(SerializationError::InvalidData));
+        }
+        let mut inputs = Vec::with_capacity(num_inputs);
+        for _ in 0..num_inputs {
+            inputs.push(F::deserialize_compressed(&mut reader)?);
+        }
+        let proof_len = read_u32(&mut reader)? as usize;
+        if reader.len() != proof_len {
+            return Err(VoteError::Serialization(SerializationError::InvalidData));
+        }
+        Ok(Self {
+            format_version,
+            curve_id,
+            circuit_version,
+            compression,
+            vk_fingerprint,
+            public_inputs: BallotPublicInputs::from_slice(&inputs)?,
+            proof: P::deserialize_proof_with_mode(reader, compression.into())?,
+        })
+    }
+
+    // JSON 编码
+    pub fn to_json(&self) -> Result<ProofEnvelopeJson, VoteError> {
+        check_envelope_version(self.format_version)?;
+        check_envelope_compression(self.format_version, self.compression)?;
+        Ok(ProofEnvelopeJson {
+            format_version: self.format_version,
+            curve_id: self.curve_id.to_u32(),
+            circuit_version: self.circuit_version,
+            compression: self.compression,
+            vk_fingerprint: self.vk_fingerprint.to_string(),
+            public_inputs: self.public_inputs.to_vec().into_iter().map(crate::field_to_decimal).collect(),
+            proof: hex::encode(P::serialize_proof_with(&self.proof, self.compression)?),
+        })
+    }
+
+    // 解码 JSON 编码
+    pub fn from_json(json: &ProofEnvelopeJson) -> Result<Self, VoteError> {
+        check_envelope_version(json.format_version)?;
+        check_envelope_compression(json.format_version, json.compression)?;
+        let inputs = json
+            .public_inputs
+            .iter()
+            .map(|s| crate::parse_field_element::<F>(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = hex::decode(&json.proof)
+            .map_err(|e| VoteError::InvalidProofEncoding(format!("invalid hex encoding: {}", e)))?;
+        Ok(Self {
+            format_version: json.format_version,
+            curve_id: envelope_curve::<F>(json.curve_id)?,
+            circuit_version: json.circuit_version,
+            compression: json.compression,
+            vk_fingerprint: KeyFingerprint::from_hex(&json.vk_fingerprint)?,
+            public_inputs: BallotPublicInputs::from_slice(&inputs)?,
+            proof: P::deserialize_proof_with_mode(&proof, json.compression.into())?,
+        })
+    }
+
+    // 解析 JSON 文本
+    // 先只读取格式版本：未来版本的字段布局可能不同，应报 UnsupportedVersion 而不是字段缺失
+    pub fn from_json_str(s: &str) -> Result<Self, VoteError> {
+        #[derive(serde::Deserialize)]
+        struct VersionOnly {
+            format_version: u32,
+        }
+        let invalid = |e: serde_json::Error| VoteError::InvalidBallot(format!("invalid proof envelope: {}", e));
+        check_envelope_version(serde_json::from_str::<VersionOnly>(s).map_err(invalid)?.format_version)?;
+        Self::from_json(&serde_json::from_str(s).map_err(invalid)?)
+    }
+
+    // 检查信封中的指纹是否与给定验证密钥一致
+    pub fn check_verifying_key(&self, vk: &P::VerifyingKey) -> Result<(), VoteError> {
+        let actual = fingerprint::<F, _>(vk)?;
+        if actual != self.vk_fingerprint {
+            return Err(VoteError::KeyMismatch(format!(
+                "proof was generated for verifying key {}, got {}",
+                self.vk_fingerprint, actual
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+// 异步证明任务
+//
+// 服务端生成证明需要数秒，负载均衡器后面的客户端等不到同步响应。启用任务队列后 POST /vote 只登记任务并立即返回 202，
+// 证明在有界的工作池中生成，客户端凭任务标识轮询 GET /vote/status/<任务标识>。
+// 已结束任务的结果保留 ttl 时间，过期后查询返回 UnknownJob；轮询只读取任务状态，多个客户端可同时轮询同一任务。
+// 未结束的任务数有上限，队列已满时新任务以 AtCapacity（503）拒绝；任务崩溃时状态记为 Failed，不会一直停在 Proving。
+
+use crate::fingerprint::ProofEnvelopeJson;
+use crate::VoteError;
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, error, info, warn};
+
+// 已结束任务的默认保留时间
+pub const DEFAULT_JOB_TTL: Duration = Duration::from_secs(600);
+
+// 默认的未结束任务数上限（排队和正在证明的任务合计）
+pub const DEFAULT_MAX_PENDING_JOBS: usize = 1024;
+
+// 任务状态
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,  // 等待空闲的工作者
+    Proving, // 正在生成证明
+    Done {
+        ballot_id: Option<String>,                  // 选票标识，见 VoteResponse::ballot_id
+        proof_envelope: Option<ProofEnvelopeJson>, // 选票的证明信封
+    },
+    Failed {
+        code: String, // 机器可读错误码，见 VoteError::code
+        error: String,
+    },
+}
+
+impl JobStatus {
+    // 任务是否已结束
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Done { .. } | JobStatus::Failed { .. })
+    }
+}
+
+struct Job {
+    status: JobStatus,
+    finished_at: Option<Instant>,
+}
+
+// 证明任务队列：同时运行的任务不超过 workers 个，其余排队等待，未结束的任务不超过 max_pending 个
+pub struct JobQueue {
+    jobs: RwLock<HashMap<String, Job>>,
+    workers: Arc<Semaphore>,
+    ttl: Duration,
+    max_pending: usize,
+    pending: AtomicUsize, // 未结束的任务数
+}
+
+impl JobQueue {
+    pub fn new(workers: usize, ttl: Duration) -> Self {
+        Self {
+            jobs: RwLock::new(HashMap::new()),
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            ttl,
+            max_pending: DEFAULT_MAX_PENDING_JOBS,
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    // 设置未结束任务数上限（默认 DEFAULT_MAX_PENDING_JOBS），达到上限时新任务被拒绝
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    // 已结束任务的保留时间
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // 未结束的任务数
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    // 登记任务并在后台运行，任务成功时返回 (选票标识, 证明信封)
+    // 未结束的任务已达上限时不登记，返回 AtCapacity；登记时顺带清除已过期的任务
+    // 任务崩溃时状态记为 Failed（internal_error），调用方须自行在任务内释放占用的资源
+    pub async fn spawn<F>(self: &Arc<Self>, job_id: String, job: F) -> Result<(), VoteError>
+    where
+        F: Future<Output = Result<(Option<String>, Option<ProofEnvelopeJson>), VoteError>> + Send + 'static,
+    {
+        let reserved = self
+            .pending
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < self.max_pending).then_some(n + 1));
+        if reserved.is_err() {
+            warn!("Rejected proving job: {} jobs are already pending", self.max_pending);
+            return Err(VoteError::AtCapacity);
+        }
+
+        let mut jobs = self.jobs.write().await;
+        let before = jobs.len();
+        jobs.retain(|_, job| !self.expired(job));
+        if jobs.len() < before {
+            debug!("Dropped {} expired proving jobs", before - jobs.len());
+        }
+        jobs.insert(job_id.clone(), Job { status: JobStatus::Queued, finished_at: None });
+        drop(jobs);
+
+        let queue = self.clone();
+        tokio::spawn(async move {
+            let status = match queue.workers.clone().acquire_owned().await {
+                Ok(_permit) => {
+                    queue.set(&job_id, JobStatus::Proving).await;
+                    match AssertUnwindSafe(job).catch_unwind().await {
+                        Ok(Ok((ballot_id, proof_envelope))) => {
+                            info!("Proving job {} finished", job_id);
+                            JobStatus::Done { ballot_id, proof_envelope }
+                        }
+                        Ok(Err(e)) => {
+                            warn!("Proving job {} failed: {}", job_id, e);
+                            JobStatus::Failed { code: e.code().to_string(), error: e.to_string() }
+                        }
+                        Err(_) => {
+                            error!("Proving job {} panicked", job_id);
+                            let e = VoteError::Internal("proving job panicked".to_string());
+                            JobStatus::Failed { code: e.code().to_string(), error: e.to_string() }
+                        }
+                    }
+                }
+                Err(_) => {
+                    let e = VoteError::Internal("proving job queue closed".to_string());
+                    JobStatus::Failed { code: e.code().to_string(), error: e.to_string() }
+                }
+            };
+            queue.set(&job_id, status).await;
+            queue.pending.fetch_sub(1, Ordering::SeqCst);
+        });
+        Ok(())
+    }
+
+    // 任务的当前状态，未知或已过期的任务返回 UnknownJob
+    pub async fn status(&self, job_id: &str) -> Result<JobStatus, VoteError> {
+        match self.jobs.read().await.get(job_id) {
+            Some(job) if !self.expired(job) => Ok(job.status.clone()),
+            _ => Err(VoteError::UnknownJob(job_id.to_string())),
+        }
+    }
+
+    async fn set(&self, job_id: &str, status: JobStatus) {
+        let finished_at = status.is_finished().then(Instant::now);
+        if let Some(job) = self.jobs.write().await.get_mut(job_id) {
+            job.status = status;
+            job.finished_at = finished_at;
+        }
+    }
+
+    fn expired(&self, job: &Job) -> bool {
+        job.finished_at.is_some_and(|finished_at| finished_at.elapsed() > self.ttl)
+    }
+}
@@ -0,0 +1,91 @@
+// 内存映射的证明密钥
+//
+// 多选项电路的证明密钥可达数百MB。ProvingKeyHandle 打开时只映射密钥文件并校验头部，
+// 密钥在第一次生成证明时才反序列化，空闲选举的密钥不占用常驻内存；
+// 反序列化时重新计算指纹，文件内容损坏或被替换时拒绝加载。
+
+use crate::fingerprint::{decode_key, parse_key_header};
+use crate::{KeyFingerprint, ProofSystem, VoteError};
+use ark_ff::PrimeField;
+use memmap2::Mmap;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, info_span};
+
+// 证明密钥句柄（密钥文件格式见 fingerprint::encode_key）
+pub struct ProvingKeyHandle<F: PrimeField, P: ProofSystem<F>> {
+    path: PathBuf,
+    mmap: Mmap,
+    fingerprint: KeyFingerprint,
+    key: Mutex<Option<Arc<P::ProvingKey>>>, // 首次使用时反序列化
+    _field: PhantomData<fn() -> F>,
+}
+
+impl<F: PrimeField, P: ProofSystem<F>> ProvingKeyHandle<F, P> {
+    // 映射密钥文件并校验头部，密钥本体延迟到首次使用时加载
+    pub fn open_mmap(path: impl AsRef<Path>) -> Result<Self, VoteError> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path).map_err(|e| {
+            error!("Failed to open proving key {}: {}", path.display(), e);
+            VoteError::Storage(e.into())
+        })?;
+        // SAFETY: 映射期间密钥文件不应被修改；即使被修改，加载时的指纹校验也会拒绝不一致的内容
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| {
+            error!("Failed to map proving key {}: {}", path.display(), e);
+            VoteError::Storage(e.into())
+        })?;
+        let fingerprint = parse_key_header::<F>(&mmap)?.fingerprint;
+        info!("Mapped proving key {} with fingerprint {}", path.display(), fingerprint);
+
+        Ok(Self {
+            path,
+            mmap,
+            fingerprint,
+            key: Mutex::new(None),
+            _field: PhantomData,
+        })
+    }
+
+    // 密钥文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 头部记录的密钥指纹
+    pub fn fingerprint(&self) -> KeyFingerprint {
+        self.fingerprint
+    }
+
+    // 密钥是否已反序列化到内存
+    pub fn is_loaded(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    // 获取证明密钥，首次调用时反序列化并校验指纹
+    pub fn get(&self) -> Result<Arc<P::ProvingKey>, VoteError> {
+        let mut key = self.lock();
+        if let Some(key) = key.as_ref() {
+            return Ok(key.clone());
+        }
+
+        let _enter = info_span!("load_proving_key", path = %self.path.display()).entered();
+        let decoded = decode_key::<F, P::ProvingKey>(&self.mmap).and_then(|pk| {
+            P::check_proving_key(&pk)?;
+            Ok(pk)
+        });
+        let loaded = Arc::new(decoded.map_err(|e| {
+            error!("Failed to load proving key {}: {}", self.path.display(), e);
+            e
+        })?);
+        info!("Loaded proving key {}", self.path.display());
+        *key = Some(loaded.clone());
+        Ok(loaded)
+    }
+
+    // 加载失败不会留下半成品，因此忽略锁中毒
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Arc<P::ProvingKey>>> {
+        self.key.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
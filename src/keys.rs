@@ -0,0 +1,58 @@
+// 证明密钥与验证密钥的一致性检查
+//
+// 分别导入的证明密钥和验证密钥可能来自不同的设置或仪式，也可能其中一方已损坏；
+// 不配对的密钥生成的证明全部无法通过验证，而这要到第一张选票提交时才会暴露。
+// 检查分两步：先确认证明密钥自身一致，再将验证密钥与证明密钥内嵌的验证密钥比对，
+// 错误信息指明可疑的一方。
+
+use crate::fingerprint::fingerprint;
+use crate::progress::{ProgressSink, TracingProgress};
+use crate::{ProofSystem, VoteError};
+use ark_ff::PrimeField;
+use tracing::error;
+
+// 检查证明密钥与验证密钥是否出自同一次设置，不一致时返回 KeyMismatch
+// 证明密钥不携带验证密钥的后端只做第一步检查；进度以 tracing 事件输出
+pub fn check_consistency<F: PrimeField, P: ProofSystem<F>>(
+    pk: &P::ProvingKey,
+    vk: &P::VerifyingKey,
+) -> Result<(), VoteError> {
+    check_consistency_with_progress::<F, P>(pk, vk, &TracingProgress)
+}
+
+// 同 check_consistency，并向 progress 报告进度（"check_proving_key" → "compare_verifying_key"）
+pub fn check_consistency_with_progress<F: PrimeField, P: ProofSystem<F>>(
+    pk: &P::ProvingKey,
+    vk: &P::VerifyingKey,
+    progress: &dyn ProgressSink,
+) -> Result<(), VoteError> {
+    progress.report("check_proving_key", 0.0);
+    P::check_proving_key(pk).map_err(|e| match e {
+        VoteError::KeyMismatch(reason) => {
+            error!("Proving key is internally inconsistent: {}", reason);
+            VoteError::KeyMismatch(format!(
+                "proving key is internally inconsistent ({}); the proving key is suspect",
+                reason
+            ))
+        }
+        e => e,
+    })?;
+
+    progress.report("compare_verifying_key", 0.9);
+    let Some(embedded) = P::embedded_verifying_key(pk) else {
+        progress.report("compare_verifying_key", 1.0);
+        return Ok(());
+    };
+    let expected = fingerprint::<F, _>(&embedded)?;
+    let actual = fingerprint::<F, _>(vk)?;
+    if expected != actual {
+        error!("Verifying key {} does not match proving key (expects {})", actual, expected);
+        return Err(VoteError::KeyMismatch(format!(
+            "verifying key {} does not match the verifying key {} embedded in the proving key; \
+             the verifying key is suspect",
+            actual, expected
+        )));
+    }
+    progress.report("compare_verifying_key", 1.0);
+    Ok(())
+}
@@ -1,252 +1,1739 @@
 // 匿名投票系统主逻辑
 
-use ark_crypto_primitives::snark::constraints::SNARKGadget;
-use ark_crypto_primitives::snark::{SNARK, TestSNARK};
-use ark_ff::PrimeField;
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use ark_std::rand::RngCore;
-use std::future::Future;
-use std::pin::Pin;
+use ark_ec::pairing::Pairing;
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, Variable};
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_groth16::Groth16;
+use chrono::{DateTime, Utc};
+use std::marker::PhantomData;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::task::JoinSet;
-use log::{debug, error, info, trace, warn};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+use tracing::{debug, error, info, info_span, warn};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub use aggregate::{AggregateProof, AggregationSrs, AggregationVerifierKey};
+pub use audit::{AuditEntry, AuditEvent, AuditLog};
+pub use backend::{Compression, ProofSystem, UniversalProofSystem};
+#[cfg(any(test, feature = "test-mock"))]
+pub use backend::mock_backend::MockSNARK;
+pub use bundle::{export_bundle, verify_bundle, ElectionBundle, BUNDLE_VERSION};
+pub use choice::VoteChoice;
+#[cfg(feature = "curve-bls12-381")]
+pub use curve::Bls12VoteSystem;
+#[cfg(feature = "curve-bn254")]
+pub use curve::Bn254VoteSystem;
+pub use curve::CurveId;
+// 字段元素编码转换的原有名称（实现见 field 模块）
+pub use field::{
+    fr_from_decimal as parse_field_element, fr_from_hex as parse_hex_field, fr_to_decimal as field_to_decimal,
+    fr_to_hex as field_to_hex,
+};
+pub use fingerprint::{fingerprint, vk_fingerprint, KeyFingerprint, ProofEnvelope, ProofEnvelopeJson, PROOF_ENVELOPE_VERSION};
+#[cfg(feature = "server")]
+pub use jobs::{JobQueue, JobStatus};
+pub use keyfile::ProvingKeyHandle;
+pub use merkle::{IncrementalMerkleTree, VoterRoll, VOTER_TREE_DEPTH};
+pub use nullifier::expected_nullifier;
+pub use prf::{PrfBinding, PrfKey};
+#[cfg(feature = "server")]
+pub use prover::{AsyncProver, HttpProver, LocalProver, ProverPool};
+pub use progress::{ProgressSink, TracingProgress};
+pub use report::{ProofReport, VerifyReport};
+#[cfg(feature = "server")]
+pub use sink::{FsSink, ProofSink, RetryPolicy, S3Sink, SinkError};
+pub use store::{DuplicateNullifier, NullifierSet, NullifierStore};
+pub use stream::{BallotVerdict, EnvelopeStream};
+use nullifier::{enforce_poseidon_hash2, poseidon_hash2};
+pub use tally::{BallotOpening, TallyResult};
+
+pub mod accumulator;
+pub mod audit;
+pub mod aggregate;
+pub mod backend;
+pub mod bundle;
+mod cancel;
+pub mod ceremony;
+pub mod choice;
+pub mod client;
+pub mod curve;
+#[cfg(feature = "curve-bn254")]
+pub mod elgamal;
+#[cfg(feature = "curve-bn254")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod field;
+pub mod fingerprint;
+#[cfg(feature = "server")]
+pub mod jobs;
+pub mod keys;
+pub mod keyfile;
+pub mod merkle;
+pub mod nullifier;
+pub mod pedersen;
+pub mod prf;
+#[cfg(feature = "server")]
+pub mod prover;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod report;
+#[cfg(feature = "server")]
+pub mod sink;
+pub mod store;
+pub mod stream;
+pub mod tally;
+#[cfg(test)]
+mod test;
+pub mod testvectors;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "server")]
+pub mod web;
+
+// 选票序号的位宽（电路约束序号位于 [0, 2^64) 范围内）
+pub const SEQUENCE_BITS: usize = 64;
+
+// 投票电路版本，电路约束变化时递增（计入密钥指纹，旧密钥加载时被拒绝）
+// 2：选举标识作为电路常量绑定到密钥
+// 3：选票承诺作为公开输入，计票和复核按公开输入中的承诺核对开启值
+pub const CIRCUIT_VERSION: u32 = 3;
+
+// 用户ID的最大字节数
+pub const MAX_USER_ID_LEN: usize = 256;
+
+// 投票时段的长度（秒），证明绑定生成时所在的时段，防止提前很久预先生成
+pub const EPOCH_SECONDS: i64 = 3600;
+
+// 投票电路的公开输入
+// 电路按 to_vec() 的顺序分配公开输入变量，验证时必须使用相同顺序，
+// 否则验证会直接返回false而不会报错
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputs<F: PrimeField> {
+    pub nullifier: F,   // 防重标识
+    pub election_id: F, // 选举标识
+    pub sequence: F,    // 选票序号（同一投票人后投的选票覆盖先投的）
+    pub epoch: F,       // 生成证明时所在的时段
+    pub commitment: F,  // 选票承诺，见 tally::commit_ballot（电路证明其由投票值和随机值计算）
+}
+
+impl<F: PrimeField> PublicInputs<F> {
+    // 各公开输入的名称，与 to_vec() 的顺序和结构体字段名一致
+    pub const LABELS: [&'static str; NUM_PUBLIC_INPUTS] = ["nullifier", "election_id", "sequence", "epoch", "commitment"];
+
+    // 按电路分配顺序输出公开输入
+    pub fn to_vec(&self) -> Vec<F> {
+        vec![self.nullifier, self.election_id, self.sequence, self.epoch, self.commitment]
+    }
+
+    // 带名称的公开输入（按电路分配顺序），供排查验证失败时查看
+    pub fn labeled(&self) -> Vec<(&'static str, F)> {
+        Self::LABELS.into_iter().zip(self.to_vec()).collect()
+    }
+
+    // 按电路分配顺序解析公开输入，数量必须为 NUM_PUBLIC_INPUTS
+    pub fn from_slice(values: &[F]) -> Result<Self, VoteError> {
+        match values {
+            [nullifier, election_id, sequence, epoch, commitment] => Ok(Self {
+                nullifier: *nullifier,
+                election_id: *election_id,
+                sequence: *sequence,
+                epoch: *epoch,
+                commitment: *commitment,
+            }),
+            _ => Err(VoteError::InvalidBallot(format!(
+                "expected {} public inputs, got {}",
+                NUM_PUBLIC_INPUTS,
+                values.len()
+            ))),
+        }
+    }
+}
+
+// 投票电路的公开输入数量
+pub const NUM_PUBLIC_INPUTS: usize = 5;
+
+// 电路版本 3 之前没有选票承诺这一公开输入
+pub(crate) const LEGACY_NUM_PUBLIC_INPUTS: usize = 4;
+
+// 从 /vote、/vote/batch、/reveal 的响应，/verify 的请求或证明信封的 JSON 中取出客户端声称的公开输入
+// 约定这些 JSON 都带有按电路顺序排列的十进制字符串数组 public_inputs（Groth16 证明本身不含公开输入）；
+// 解码不需要验证密钥，返回按 PublicInputs 字段命名的值
+pub fn decode_public_inputs<F: PrimeField>(bundle: &serde_json::Value) -> Result<Vec<(&'static str, F)>, VoteError> {
+    let values = bundle
+        .get("public_inputs")
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| VoteError::InvalidBallot("bundle has no public_inputs array".to_string()))?
+        .iter()
+        .map(|v| {
+            let s = v
+                .as_str()
+                .ok_or_else(|| VoteError::InvalidBallot(format!("public input {} is not a decimal string", v)))?;
+            parse_field_element(s)
+        })
+        .collect::<Result<Vec<F>, _>>()?;
+    Ok(PublicInputs::from_slice(&values)?.labeled())
+}
+
+// 多候选人电路支持的最大候选人数量（投票选择以 u8 提交）
+pub const MAX_CANDIDATES: usize = 256;
+
+// 选票证明的公开输入
+pub type BallotPublicInputs<F> = PublicInputs<F>;
+
+// 选票验证结果
+// 证明被拒绝（投票人作弊或公开输入被篡改）为 Invalid；密钥损坏、证明无法解码、
+// 公开输入数量不符等运行错误以 Err(VoteError) 返回，审计时两者需区分报告
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationOutcome {
+    Valid,
+    Invalid { reason: InvalidReason },
+}
+
+// 证明无效的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidReason {
+    // 证明未通过验证方程：证明是伪造的，或与所给公开输入不对应
+    ProofRejected,
+}
+
+impl InvalidReason {
+    // 稳定的机器可读原因码
+    pub fn code(self) -> &'static str {
+        match self {
+            InvalidReason::ProofRejected => "proof_rejected",
+        }
+    }
+}
+
+impl VerificationOutcome {
+    pub fn is_valid(self) -> bool {
+        self == VerificationOutcome::Valid
+    }
+    
+    // 无效时的原因
+    pub fn invalid_reason(self) -> Option<InvalidReason> {
+        match self {
+            VerificationOutcome::Valid => None,
+            VerificationOutcome::Invalid { reason } => Some(reason),
+        }
+    }
+}
+
+impl From<bool> for VerificationOutcome {
+    fn from(valid: bool) -> Self {
+        if valid {
+            VerificationOutcome::Valid
+        } else {
+            VerificationOutcome::Invalid { reason: InvalidReason::ProofRejected }
+        }
+    }
+}
+
+// 把任意随机数发生器当作密码学安全的随机数发生器使用（仅供已弃用的旧接口过渡）
+struct AssumeCryptoRng<'a, R: RngCore>(&'a mut R);
+
+impl<R: RngCore> RngCore for AssumeCryptoRng<'_, R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl<R: RngCore> CryptoRng for AssumeCryptoRng<'_, R> {}
+
+// 检查公开输入数量与验证密钥是否一致
+// 数量不符时后端要么报出难以理解的错误，要么按错位的输入验证，因此提前以 KeyMismatch 拒绝
+pub(crate) fn check_public_input_count<F: PrimeField, P: ProofSystem<F>>(
+    vk: &P::VerifyingKey,
+    count: usize,
+) -> Result<(), VoteError> {
+    match P::num_public_inputs(vk) {
+        Some(expected) if expected != count => Err(VoteError::KeyMismatch(format!(
+            "verifying key expects {} public inputs, got {}",
+            expected, count
+        ))),
+        _ => Ok(()),
+    }
+}
 
 // 定义投票电路
+// 防重标识不单独提供，由电路按 Poseidon(投票人秘密, 选举标识) 派生（见 nullifier::expected_nullifier）
+// 私有输入在电路被丢弃时清零（证明后端消费电路后即丢弃）
 #[derive(Clone, Debug)]
 pub struct VoteCircuit<F: PrimeField> {
     pub vote: F,        // 投票选择（私有输入）
-    pub nullifier: F,   // 防重标识（私有输入）
-    pub randomness: F,  // 额外随机值（私有输入），用于增加混淆
+    pub secret: F,      // 投票人秘密（私有输入），见 nullifier::user_secret_to_field
+    pub randomness: F,  // 选票承诺的随机值（私有输入），见 tally::commit_ballot
+    pub election_id: F, // 选举标识（公开输入）
+    pub sequence: F,    // 选票序号（公开输入），用于抗胁迫的重新投票
+    pub epoch: F,       // 时段（公开输入），见 ballot_epoch
+}
+
+impl<F: PrimeField> VoteCircuit<F> {
+    // 构造仅用于为指定选举生成密钥的空电路
+    pub fn blank(election_id: F) -> Self {
+        Self {
+            vote: F::zero(),
+            secret: F::zero(),
+            randomness: F::zero(),
+            election_id,
+            sequence: F::zero(),
+            epoch: F::zero(),
+        }
+    }
+    
+    // 该电路实例对应的公开输入，防重标识由投票人秘密和选举标识派生，选票承诺由投票值和随机值计算
+    pub fn public_inputs(&self) -> PublicInputs<F> {
+        PublicInputs {
+            nullifier: poseidon_hash2(self.secret, self.election_id),
+            election_id: self.election_id,
+            sequence: self.sequence,
+            epoch: self.epoch,
+            commitment: tally::commit_ballot(self.vote, self.randomness),
+        }
+    }
+}
+
+impl<F: PrimeField> Zeroize for VoteCircuit<F> {
+    fn zeroize(&mut self) {
+        self.vote.zeroize();
+        self.secret.zeroize();
+        self.randomness.zeroize();
+    }
+}
+
+impl<F: PrimeField> Drop for VoteCircuit<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: PrimeField> ZeroizeOnDrop for VoteCircuit<F> {}
+
+// 清零约束系统中的见证赋值（约束检查后不再需要私有输入的取值）
+pub(crate) fn zeroize_witness<F: PrimeField>(cs: &ConstraintSystemRef<F>) {
+    if let Some(mut cs) = cs.borrow_mut() {
+        cs.witness_assignment.zeroize();
+    }
 }
 
 impl<F: PrimeField> ConstraintSynthesizer<F> for VoteCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.synthesize(cs, tally::NUM_OPTIONS, None, None)
+    }
+}
+
+impl<F: PrimeField> VoteCircuit<F> {
+    // 生成约束，投票选择约束在 [0, num_candidates) 范围内；params_digest 为绑定到密钥的选举参数摘要，
+    // prf 存在时另外约束投票人秘密由承诺对应的 PRF 密钥派生
+    fn synthesize(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        num_candidates: usize,
+        params_digest: Option<F>,
+        prf: Option<&PrfBinding<F>>,
+    ) -> Result<(), SynthesisError> {
+        // 按PublicInputs::to_vec()的顺序分配公开输入
+        let mut input_vars = Vec::new();
+        for value in self.public_inputs().to_vec() {
+            input_vars.push(cs.new_input_variable(|| Ok(value))?);
+        }
+        let sequence_var = input_vars[2];
+        
+        // 选举标识同时作为电路常量（密钥的域分隔）：电路结构随选举而不同，
+        // 为某场选举生成的密钥既不能证明也不能验证其他选举的选票
+        cs.enforce_constraint(lc!() + input_vars[1], lc!() + Variable::One, lc!() + (self.election_id, Variable::One))?;
+        
+        // 选举参数摘要同样作为电路常量，参数不同（如弃权槽位与候选人数量之和相同）的配置得到不同的密钥
+        if let Some(digest) = params_digest {
+            cs.enforce_constraint(lc!() + (digest, Variable::One), lc!() + Variable::One, lc!() + (digest, Variable::One))?;
+        }
+        
+        // 防重标识 = Poseidon(投票人秘密, 选举标识)，与 NullifierCircuit 的派生相同
+        let secret_var = cs.new_witness_variable(|| Ok(self.secret))?;
+        let (hash, _) = enforce_poseidon_hash2(&cs, (secret_var, self.secret), (input_vars[1], self.election_id))?;
+        cs.enforce_constraint(lc!() + hash, lc!() + Variable::One, lc!() + input_vars[0])?;
+        if let Some(prf) = prf {
+            prf.enforce(&cs, secret_var)?;
+        }
+        
         // 添加约束条件
-        // 验证投票选择有效性 (0 或 1)
+        // 验证投票选择有效性 (0 到 num_candidates - 1)
         let vote_var = cs.new_witness_variable(|| Ok(self.vote))?;
-        let zero = cs.new_constant_variable(F::zero())?;
-        let one = cs.new_constant_variable(F::one())?;
         
-        // 约束 vote * (vote - 1) = 0，确保vote只能是0或1
-        cs.enforce_constraint(
-            || "vote * (vote - 1) = 0",
-            |lc| lc + vote_var,
-            |lc| lc + vote_var - one,
-            |lc| lc + zero,
-        )?;
+        // 约束 vote * (vote - 1) * ... * (vote - (n - 1)) = 0，确保vote只能是0到n-1之一
+        // 逐个因子累乘，每步一个中间见证；两个候选人时只有 vote * (vote - 1) = 0 一个约束
+        let mut product = vote_var;
+        let mut product_value = self.vote;
+        for i in 1..num_candidates.saturating_sub(1) {
+            let offset = F::from(i as u64);
+            let next_value = product_value * (self.vote - offset);
+            let next = cs.new_witness_variable(|| Ok(next_value))?;
+            cs.enforce_constraint(lc!() + product, lc!() + vote_var - (offset, Variable::One), lc!() + next)?;
+            product = next;
+            product_value = next_value;
+        }
+        product_value.zeroize();
+        let last = F::from(num_candidates.saturating_sub(1) as u64);
+        cs.enforce_constraint(lc!() + product, lc!() + vote_var - (last, Variable::One), lc!())?;
+        
+        // 选票承诺 = MiMC(投票值, 随机值)，与公开输入中的承诺相等：计票方持有的开启值必须与证明对应
+        let randomness_var = cs.new_witness_variable(|| Ok(self.randomness))?;
+        let (commitment, _) = tally::enforce_commit_ballot(&cs, lc!() + vote_var, self.vote, randomness_var, self.randomness)?;
+        cs.enforce_constraint(commitment, lc!() + Variable::One, lc!() + input_vars[4])?;
         
-        // 添加nullifier变量（私有输入）
-        let _nullifier_var = cs.new_witness_variable(|| Ok(self.nullifier))?;
+        // 约束序号位于 [0, 2^64) 范围内，使序号之间的大小比较有意义
+        // 序号与防重标识同为本证明的公开输入，证明只对由投票人秘密派生的防重标识和这一序号的组合成立：
+        // 不能把证明改挂到其他序号或其他防重标识下，计票据此对同一防重标识只计入序号最大的选票
+        let sequence_bits = self.sequence.into_bigint().to_bits_le();
+        let mut bit_vars = Vec::with_capacity(SEQUENCE_BITS);
+        for &set in sequence_bits.iter().take(SEQUENCE_BITS) {
+            let bit = if set { F::one() } else { F::zero() };
+            let bit_var = cs.new_witness_variable(|| Ok(bit))?;
+            
+            // 约束 bit * (bit - 1) = 0
+            cs.enforce_constraint(lc!() + bit_var, lc!() + bit_var - Variable::One, lc!())?;
+            bit_vars.push(bit_var);
+        }
         
-        // 添加随机值变量（私有输入），用于增加混淆
-        let _randomness_var = cs.new_witness_variable(|| Ok(self.randomness))?;
+        // 约束 sum(bit_i * 2^i) = sequence
+        let mut packed = lc!();
+        let mut coeff = F::one();
+        for bit_var in &bit_vars {
+            packed += (coeff, *bit_var);
+            coeff.double_in_place();
+        }
+        cs.enforce_constraint(packed, lc!() + Variable::One, lc!() + sequence_var)?;
         
         Ok(())
     }
 }
 
+// 多候选人投票电路：与 VoteCircuit 相同，但投票选择约束在 [0, num_candidates) 范围内
+// 候选人数量、选举标识和选举参数摘要决定电路结构，密钥须以相同参数的空电路生成（见 VoteSystem::setup_with_candidates）；
+// 两个候选人且不绑定选举参数时约束系统与 VoteCircuit 完全相同，可使用同一套密钥
+#[derive(Clone, Debug)]
+pub struct CandidateVoteCircuit<F: PrimeField> {
+    pub ballot: VoteCircuit<F>,
+    pub num_candidates: usize,
+    pub params_digest: Option<F>, // 选举参数摘要（电路常量），见 ElectionParams::digest_field；None 时不绑定
+    pub prf: Option<PrfBinding<F>>, // PRF 密钥承诺（电路常量）及派生见证，见 prf 模块；None 时不绑定
+}
+
+impl<F: PrimeField> CandidateVoteCircuit<F> {
+    // 构造仅用于为指定选举生成密钥的空电路
+    pub fn blank(num_candidates: usize, election_id: F) -> Self {
+        Self { ballot: VoteCircuit::blank(election_id), num_candidates, params_digest: None, prf: None }
+    }
+    
+    // 构造按选举参数生成密钥的空电路，参数摘要绑定到密钥
+    pub fn for_params(params: &ElectionParams, election_id: F) -> Self {
+        Self {
+            ballot: VoteCircuit::blank(election_id),
+            num_candidates: params.config().num_slots(),
+            params_digest: Some(params.digest_field()),
+            prf: None,
+        }
+    }
+    
+    // 把 PRF 密钥承诺绑定到空电路，生成的密钥只接受由该 PRF 密钥派生投票人秘密的证明
+    pub fn with_prf_commitment(mut self, commitment: F) -> Self {
+        self.prf = Some(PrfBinding::blank(commitment));
+        self
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for CandidateVoteCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.ballot.synthesize(cs, self.num_candidates, self.params_digest, self.prf.as_ref())
+    }
+}
+
+// 电路配置：决定电路结构的参数，按配置生成的密钥只能用于同一配置的电路
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct CircuitConfig {
+    pub num_candidates: usize,
+    #[serde(default)]
+    pub allow_abstain: bool, // 是否在候选人之后额外设置一个弃权槽位，见 VoteChoice
+    #[serde(default)]
+    pub allow_spoiled: bool, // 是否在最后额外设置一个废票槽位（只计入投票率，不计入任何候选人），见 VoteChoice
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self { num_candidates: tally::NUM_OPTIONS, allow_abstain: false, allow_spoiled: false }
+    }
+}
+
+impl CircuitConfig {
+    // 指定候选人数量、不允许弃权的配置
+    pub fn with_candidates(num_candidates: usize) -> Self {
+        Self { num_candidates, ..Self::default() }
+    }
+    
+    // 电路中的选项槽位数量（候选人加上可选的弃权槽位和废票槽位）
+    pub fn num_slots(&self) -> usize {
+        self.num_candidates + usize::from(self.allow_abstain) + usize::from(self.allow_spoiled)
+    }
+    
+    // 校验候选人数量和槽位数量的范围
+    pub fn validate(&self) -> Result<(), VoteError> {
+        if self.num_candidates < 2 || self.num_slots() > MAX_CANDIDATES {
+            return Err(VoteError::InvalidBallot(format!(
+                "number of candidates must be between 2 and {}, got {}",
+                MAX_CANDIDATES - usize::from(self.allow_abstain) - usize::from(self.allow_spoiled),
+                self.num_candidates
+            )));
+        }
+        Ok(())
+    }
+    
+    // 配置摘要 = BLAKE3(电路版本 || 候选人数量 [|| 弃权标记] [|| 废票标记])，电路约束变化后同一配置得到不同的摘要
+    // 弃权和废票标记只在允许时计入，不允许弃权和废票的配置摘要保持不变
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"zkp-vote/circuit-config");
+        hasher.update(&CIRCUIT_VERSION.to_le_bytes());
+        hasher.update(&(self.num_candidates as u64).to_le_bytes());
+        if self.allow_abstain {
+            hasher.update(b"abstain");
+        }
+        if self.allow_spoiled {
+            hasher.update(b"spoiled");
+        }
+        *hasher.finalize().as_bytes()
+    }
+}
+
+// 选举参数中的哈希方案（防重标识等电路内哈希）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashScheme {
+    #[default]
+    Poseidon,
+}
+
+impl HashScheme {
+    // 计入参数摘要的方案标签
+    fn tag(&self) -> u8 {
+        match self {
+            HashScheme::Poseidon => 1,
+        }
+    }
+}
+
+fn default_circuit_version() -> u32 {
+    CIRCUIT_VERSION
+}
+
+// 选举参数：一场选举的全部电路配置，由 VoteSystem::setup_with_params 生成绑定这些参数的密钥
+// merkle_depth 为投票人名册 Merkle 树的深度，当前电路不包含名册成员证明，只接受 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ElectionParams {
+    pub num_candidates: usize,
+    #[serde(default)]
+    pub merkle_depth: u32,
+    #[serde(default)]
+    pub hash_scheme: HashScheme,
+    #[serde(default)]
+    pub allow_abstain: bool,
+    #[serde(default)]
+    pub allow_spoiled: bool,
+    #[serde(default = "default_circuit_version")]
+    pub circuit_version: u32,
+}
+
+impl Default for ElectionParams {
+    fn default() -> Self {
+        Self::from(CircuitConfig::default())
+    }
+}
+
+impl From<CircuitConfig> for ElectionParams {
+    fn from(config: CircuitConfig) -> Self {
+        Self {
+            num_candidates: config.num_candidates,
+            merkle_depth: 0,
+            hash_scheme: HashScheme::default(),
+            allow_abstain: config.allow_abstain,
+            allow_spoiled: config.allow_spoiled,
+            circuit_version: CIRCUIT_VERSION,
+        }
+    }
+}
+
+impl ElectionParams {
+    // 参数对应的电路配置
+    pub fn config(&self) -> CircuitConfig {
+        CircuitConfig {
+            num_candidates: self.num_candidates,
+            allow_abstain: self.allow_abstain,
+            allow_spoiled: self.allow_spoiled,
+        }
+    }
+    
+    // 校验参数：电路版本须为当前版本，名册深度只能为 0，候选人数量范围同 CircuitConfig::validate
+    pub fn validate(&self) -> Result<(), VoteError> {
+        if self.circuit_version != CIRCUIT_VERSION {
+            return Err(VoteError::UnsupportedVersion(format!(
+                "election params are for circuit version {}, expected {}",
+                self.circuit_version, CIRCUIT_VERSION
+            )));
+        }
+        if self.merkle_depth != 0 {
+            return Err(VoteError::InvalidBallot(format!(
+                "voter roll membership proofs are not supported, merkle depth must be 0, got {}",
+                self.merkle_depth
+            )));
+        }
+        self.config().validate()
+    }
+    
+    // 规范参数摘要 = BLAKE3(电路版本 || 候选人数量 || 弃权标记 || 名册深度 || 哈希方案 [|| 废票标记])，各字段定长编码
+    // 废票标记只在允许废票时计入，之前的参数摘要保持不变
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"zkp-vote/election-params");
+        hasher.update(&self.circuit_version.to_le_bytes());
+        hasher.update(&(self.num_candidates as u64).to_le_bytes());
+        hasher.update(&[u8::from(self.allow_abstain)]);
+        hasher.update(&self.merkle_depth.to_le_bytes());
+        hasher.update(&[self.hash_scheme.tag()]);
+        if self.allow_spoiled {
+            hasher.update(b"spoiled");
+        }
+        *hasher.finalize().as_bytes()
+    }
+    
+    // 参数摘要对应的字段元素（按小端取模），作为电路常量绑定到密钥
+    pub fn digest_field<F: PrimeField>(&self) -> F {
+        F::from_le_bytes_mod_order(&self.digest())
+    }
+}
+
+// 投票电路构造器
+// 投票选择按电路配置映射为槽位编号，缺少投票选择、投票人秘密或随机值时拒绝构造
+pub struct VoteCircuitBuilder<F: PrimeField> {
+    config: CircuitConfig,
+    params_digest: Option<F>,
+    prf: Option<PrfBinding<F>>,
+    choice: Option<VoteChoice>,
+    secret: Option<F>,
+    randomness: Option<F>,
+    election_id: F,
+    sequence: u64,
+    epoch: u64,
+}
+
+impl<F: PrimeField> VoteCircuitBuilder<F> {
+    pub fn new(config: CircuitConfig) -> Self {
+        Self {
+            config,
+            params_digest: None,
+            prf: None,
+            choice: None,
+            secret: None,
+            randomness: None,
+            election_id: F::zero(),
+            sequence: 0,
+            epoch: 0,
+        }
+    }
+    
+    // 按选举参数构造，电路绑定参数摘要，与 VoteSystem::setup_with_params 生成的密钥配套
+    pub fn from_params(params: &ElectionParams) -> Self {
+        let mut builder = Self::new(params.config());
+        builder.params_digest = Some(params.digest_field());
+        builder
+    }
+    
+    pub fn choice(mut self, choice: VoteChoice) -> Self {
+        self.choice = Some(choice);
+        self
+    }
+    
+    pub fn secret(mut self, secret: F) -> Self {
+        self.secret = Some(secret);
+        self
+    }
+    
+    // 由投票人秘密的原始字节派生，见 nullifier::user_secret_to_field
+    pub fn user_secret(self, user_secret: &[u8]) -> Self {
+        self.secret(nullifier::user_secret_to_field(user_secret))
+    }
+    
+    // 由服务端 PRF 密钥和用户ID派生投票人秘密，电路证明派生关系（密钥须以 with_prf_commitment 的空电路生成）
+    pub fn prf_key(mut self, key: &PrfKey<F>, user_id: &str) -> Self {
+        self.secret = Some(key.voter_secret(user_id));
+        self.prf = Some(key.binding(user_id));
+        self
+    }
+    
+    pub fn randomness(mut self, randomness: F) -> Self {
+        self.randomness = Some(randomness);
+        self
+    }
+    
+    pub fn election_id(mut self, election_id: F) -> Self {
+        self.election_id = election_id;
+        self
+    }
+    
+    pub fn sequence(mut self, sequence: u64) -> Self {
+        self.sequence = sequence;
+        self
+    }
+    
+    pub fn epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+    
+    // 构造按配置槽位数量约束投票值的电路
+    pub fn build(mut self) -> Result<CandidateVoteCircuit<F>, VoteError> {
+        let missing = |what: &str| VoteError::InvalidBallot(format!("missing {}", what));
+        let vote = self.choice.ok_or_else(|| missing("vote choice"))?.to_field(&self.config)?;
+        let secret = self.secret.take().ok_or_else(|| missing("voter secret"))?;
+        let randomness = self.randomness.take().ok_or_else(|| missing("randomness"))?;
+        Ok(CandidateVoteCircuit {
+            ballot: VoteCircuit {
+                vote,
+                secret,
+                randomness,
+                election_id: self.election_id,
+                sequence: F::from(self.sequence),
+                epoch: F::from(self.epoch),
+            },
+            num_candidates: self.config.num_slots(),
+            params_digest: self.params_digest,
+            prf: self.prf.take(),
+        })
+    }
+}
+
+impl<F: PrimeField> Drop for VoteCircuitBuilder<F> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+        self.randomness.zeroize();
+    }
+}
+
+// 错误中携带的底层错误
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 // 定义错误类型
 #[derive(Debug, thiserror::Error)]
-pub enum VoteSystemError {
-    #[error("SNARK error: {0}")]
-    SnarkError(String),
+pub enum VoteError {
+    #[error("Invalid ballot: {0}")]
+    InvalidBallot(String),
+    #[error("Unsatisfied constraint: {0}")]
+    UnsatisfiedConstraint(String),
     #[error("Circuit synthesis error: {0}")]
-    SynthesisError(#[from] SynthesisError),
+    Synthesis(#[from] SynthesisError),
+    #[error("Setup error: {0}")]
+    Setup(#[source] BoxError),
     #[error("Proof generation error: {0}")]
-    ProofGenerationError(String),
+    Proving(#[source] BoxError),
     #[error("Proof verification error: {0}")]
-    ProofVerificationError(String),
-    #[error("Invalid vote: {0}")]
-    InvalidVote(String),
-    #[error("Invalid nullifier: {0}")]
-    InvalidNullifier(String),
+    Verification(#[source] BoxError),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] ark_serialize::SerializationError),
+    #[error("Key mismatch: {0}")]
+    KeyMismatch(String),
+    #[error("Field element out of range: {0}")]
+    FieldRangeExceeded(String),
+    #[error("Nullifier has already been used")]
+    DuplicateNullifier,
+    #[error("Storage error: {0}")]
+    Storage(#[from] store::StoreError),
+    #[error("Aggregation error: {0}")]
+    Aggregation(String),
+    #[error("Decryption error: {0}")]
+    Decryption(String),
+    #[error("Ceremony error: {0}")]
+    Ceremony(String),
+    #[error("Wrong election phase: {0}")]
+    WrongPhase(String),
+    #[error("Revealed ballot does not match the commitment")]
+    CommitmentMismatch,
+    #[error("Election bundle integrity check failed: {0}")]
+    BundleIntegrity(String),
+    #[error("Invalid user id: {0}")]
+    InvalidUserId(String),
+    #[error("Invalid proof encoding: {0}")]
+    InvalidProofEncoding(String),
+    #[error("Proof has already been submitted")]
+    ReplayedProof,
+    #[error("Unsupported version: {0}")]
+    UnsupportedVersion(String),
+    #[error("Audit log integrity check failed: {0}")]
+    AuditIntegrity(String),
+    #[error("Proof generation timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("Server at capacity")]
+    AtCapacity,
+    #[error("No keys for election {0}")]
+    UnknownElection(String),
+    #[error("Circuit config mismatch: {0}")]
+    ConfigMismatch(String),
+    #[error("Unknown or expired proving job {0}")]
+    UnknownJob(String),
+    #[error("Unsupported election bundle version {0}")]
+    UnsupportedBundleVersion(u32),
+    #[error("Credential commitment is already registered: {0}")]
+    DuplicateCommitment(String),
     #[error("Internal error: {0}")]
-    InternalError(String),
+    Internal(String),
 }
 
-// 投票系统结构体
-pub struct VoteSystem<F: PrimeField, S: SNARK<F>> {
-    pub snark: S,
-    worker_pool: Arc<WorkerPool>,
-}
-
-// 工作线程池
-struct WorkerPool {
-    sender: mpsc::Sender<WorkerTask>,
-}
-
-// 工作任务
-enum WorkerTask {
-    GenerateProof,
-    VerifyProof,
-}
-
-impl WorkerPool {
-    fn new(size: usize) -> Self {
-        let (sender, mut receiver) = mpsc::channel(100);
-        
-        // 启动工作线程
-        let mut set = JoinSet::new();
-        for i in 0..size {
-            let mut receiver = receiver.clone();
-            set.spawn(async move {
-                while let Some(task) = receiver.recv().await {
-                    match task {
-                        WorkerTask::GenerateProof => {
-                            debug!("Worker {}: Processing proof generation task", i);
-                        }
-                        WorkerTask::VerifyProof => {
-                            debug!("Worker {}: Processing proof verification task", i);
-                        }
-                    }
-                }
-            });
+impl From<store::DuplicateNullifier> for VoteError {
+    fn from(_: store::DuplicateNullifier) -> Self {
+        VoteError::DuplicateNullifier
+    }
+}
+
+impl From<store::NullifierSetError> for VoteError {
+    fn from(e: store::NullifierSetError) -> Self {
+        match e {
+            store::NullifierSetError::Duplicate(e) => e.into(),
+            store::NullifierSetError::Store(e) => e.into(),
         }
+    }
+}
+
+impl VoteError {
+    // 稳定的机器可读错误码（供Web层和客户端使用，不随错误信息文本变化）
+    pub fn code(&self) -> &'static str {
+        match self {
+            VoteError::InvalidBallot(_) => "invalid_ballot",
+            VoteError::UnsatisfiedConstraint(_) => "unsatisfied_constraint",
+            VoteError::Synthesis(_) => "synthesis_failed",
+            VoteError::Setup(_) => "setup_failed",
+            VoteError::Proving(_) => "proving_failed",
+            VoteError::Verification(_) => "verification_failed",
+            VoteError::Serialization(_) => "serialization_failed",
+            VoteError::KeyMismatch(_) => "key_mismatch",
+            VoteError::FieldRangeExceeded(_) => "field_range_exceeded",
+            VoteError::DuplicateNullifier => "duplicate_nullifier",
+            VoteError::Storage(_) => "storage_failed",
+            VoteError::Aggregation(_) => "aggregation_failed",
+            VoteError::Decryption(_) => "decryption_failed",
+            VoteError::Ceremony(_) => "ceremony_failed",
+            VoteError::WrongPhase(_) => "wrong_phase",
+            VoteError::CommitmentMismatch => "commitment_mismatch",
+            VoteError::BundleIntegrity(_) => "bundle_integrity",
+            VoteError::InvalidUserId(_) => "invalid_user_id",
+            VoteError::InvalidProofEncoding(_) => "invalid_proof_encoding",
+            VoteError::ReplayedProof => "replayed_proof",
+            VoteError::UnsupportedVersion(_) => "unsupported_version",
+            VoteError::AuditIntegrity(_) => "audit_integrity",
+            VoteError::Timeout(_) => "timeout",
+            VoteError::AtCapacity => "at_capacity",
+            VoteError::UnknownElection(_) => "unknown_election",
+            VoteError::ConfigMismatch(_) => "config_mismatch",
+            VoteError::UnknownJob(_) => "unknown_job",
+            VoteError::UnsupportedBundleVersion(_) => "unsupported_bundle_version",
+            VoteError::DuplicateCommitment(_) => "duplicate_commitment",
+            VoteError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+// 投票系统结构体
+// 验证只读取传入的验证密钥，不修改系统状态也不经过任何锁，同一实例可被多个线程共享（&self 或 Arc）并发验证；
+// 只有证明生成的排队与并发上限（ProverPool）是有状态的，由其自身管理
+pub struct VoteSystem<F: PrimeField, P: ProofSystem<F>> {
+    _backend: PhantomData<fn() -> (F, P)>,
+    #[cfg(feature = "server")]
+    prover: Arc<dyn AsyncProver<F, P>>, // vote_async 使用的证明后端
+    #[cfg(feature = "server")]
+    prover_pool: Arc<ProverPool>, // 本地异步证明的排队与并发上限
+    max_parallelism: usize, // 批量证明时的最大并行度
+    proving_pool: Option<Arc<rayon::ThreadPool>>, // 单个证明内部（MSM、FFT）使用的线程池，见 with_threads
+}
+
+impl<F: PrimeField, P: ProofSystem<F>> VoteSystem<F, P> {
+    // 创建不含密钥的系统实例
+    fn new() -> Self {
+        // 默认批量并行度为可用CPU核数
+        let max_parallelism = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
         
-        // 后台管理工作线程
-        tokio::spawn(async move {
-            while set.join_next().await.is_some() {
-                // 处理工作线程的退出
-            }
-        });
+        // 本地异步证明默认最多同时进行 max_parallelism 个
+        #[cfg(feature = "server")]
+        let prover_pool = Arc::new(ProverPool::new(max_parallelism, 0));
         
-        Self { sender }
+        Self {
+            _backend: PhantomData,
+            #[cfg(feature = "server")]
+            prover: Arc::new(LocalProver::with_pool(prover_pool.clone())),
+            #[cfg(feature = "server")]
+            prover_pool,
+            max_parallelism,
+            proving_pool: None,
+        }
     }
     
-    async fn submit_task(&self, task: WorkerTask) -> Result<(), VoteSystemError> {
-        self.sender.send(task).await.map_err(|e| {
-            error!("Failed to submit task: {}", e);
-            VoteSystemError::InternalError(format!("Failed to submit task: {}", e))
-        })
+    // 为指定选举初始化系统
+    // 选举标识是电路常量，生成的密钥只能用于该选举的选票；进度以 tracing 事件输出，见 setup_with_progress
+    pub fn setup<R: RngCore + CryptoRng>(
+        election_id: F,
+        rng: &mut R,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        Self::setup_with_progress(election_id, rng, &TracingProgress)
     }
-}
-
-impl<F: PrimeField, S: SNARK<F>> VoteSystem<F, S> {
-    // 初始化系统
-    pub fn setup<R: RngCore>(rng: &mut R) -> Result<(Self, S::ProvingKey, S::VerifyingKey), VoteSystemError> {
-        info!("Setting up vote system");
+    
+    // 为指定选举初始化系统，并向 progress 报告进度（"synthesize" → "generate_keys"）
+    pub fn setup_with_progress<R: RngCore + CryptoRng>(
+        election_id: F,
+        rng: &mut R,
+        progress: &dyn ProgressSink,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        let span = info_span!("setup", duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        info!("Setting up vote system with {} backend", P::NAME);
         
-        let snark = S::setup(rng).map_err(|e| {
-            error!("Failed to setup SNARK: {:?}", e);
-            VoteSystemError::SnarkError(format!("{:?}", e))
-        })?;
+        let (pk, vk) = Self::generate_keys(VoteCircuit::<F>::blank(election_id), rng, progress)?;
+        
+        let system = Self::new();
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        info!("Vote system setup completed successfully");
+        Ok((system, pk, vk))
+    }
+    
+    // 生成电路密钥并报告进度；先合成一次电路，约束数量决定了密钥生成的耗时
+    fn generate_keys<C: ConstraintSynthesizer<F> + Clone, R: RngCore + CryptoRng>(
+        circuit: C,
+        rng: &mut R,
+        progress: &dyn ProgressSink,
+    ) -> Result<(P::ProvingKey, P::VerifyingKey), VoteError> {
+        progress.report("synthesize", 0.0);
+        let cs = ConstraintSystem::<F>::new_ref();
+        cs.set_mode(ark_relations::r1cs::SynthesisMode::Setup);
+        circuit.clone().generate_constraints(cs.clone())?;
+        info!("Generating keys for {} constraints", cs.num_constraints());
         
-        let (pk, vk) = snark.keygen().map_err(|e| {
+        progress.report("generate_keys", 0.05);
+        let keys = P::setup(circuit, rng).map_err(|e| {
             error!("Failed to generate keys: {:?}", e);
-            VoteSystemError::SnarkError(format!("{:?}", e))
+            e
         })?;
+        progress.report("generate_keys", 1.0);
+        Ok(keys)
+    }
+    
+    // 以多候选人电路初始化系统，证明须使用相同候选人数量的 CandidateVoteCircuit
+    pub fn setup_with_candidates<R: RngCore + CryptoRng>(
+        num_candidates: usize,
+        election_id: F,
+        rng: &mut R,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        Self::setup_with_candidates_and_progress(num_candidates, election_id, rng, &TracingProgress)
+    }
+    
+    // 以多候选人电路初始化系统，并向 progress 报告进度
+    pub fn setup_with_candidates_and_progress<R: RngCore + CryptoRng>(
+        num_candidates: usize,
+        election_id: F,
+        rng: &mut R,
+        progress: &dyn ProgressSink,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        if !(2..=MAX_CANDIDATES).contains(&num_candidates) {
+            return Err(VoteError::Setup(
+                format!("number of candidates must be between 2 and {}, got {}", MAX_CANDIDATES, num_candidates).into(),
+            ));
+        }
+        let span = info_span!("setup", num_candidates, duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        info!("Setting up vote system for {} candidates with {} backend", num_candidates, P::NAME);
         
-        // 创建工作线程池
-        let worker_pool = Arc::new(WorkerPool::new(4)); // 4个工作线程
+        let (pk, vk) = Self::generate_keys(CandidateVoteCircuit::<F>::blank(num_candidates, election_id), rng, progress)?;
         
-        let system = Self { snark, worker_pool };
-        info!("Vote system setup completed successfully");
-        Ok((system, pk, vk))
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok((Self::new(), pk, vk))
+    }
+    
+    // 按电路配置初始化系统
+    pub fn setup_with_config<R: RngCore + CryptoRng>(
+        config: &CircuitConfig,
+        election_id: F,
+        rng: &mut R,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        config.validate().map_err(|e| VoteError::Setup(e.to_string().into()))?;
+        Self::setup_with_candidates(config.num_slots(), election_id, rng)
+    }
+    
+    // 按选举参数初始化系统，参数摘要作为电路常量绑定到密钥，
+    // 按某组参数生成的证明不能在其他参数的验证密钥下通过验证（证明须由 VoteCircuitBuilder::from_params 构造）
+    pub fn setup_with_params<R: RngCore + CryptoRng>(
+        params: &ElectionParams,
+        election_id: F,
+        rng: &mut R,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        params.validate().map_err(|e| VoteError::Setup(e.to_string().into()))?;
+        let span = info_span!("setup", num_candidates = params.num_candidates, duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        info!("Setting up vote system for election params {} with {} backend", hex::encode(params.digest()), P::NAME);
+        
+        let (pk, vk) = Self::generate_keys(CandidateVoteCircuit::<F>::for_params(params, election_id), rng, &TracingProgress)?;
+        
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok((Self::new(), pk, vk))
+    }
+    
+    // 以给定的空电路生成密钥，电路常量（选举标识、槽位数量、选举参数摘要、PRF 密钥承诺）都由空电路确定
+    // 证明须使用常量相同的 CandidateVoteCircuit（如 VoteCircuitBuilder::from_params(..).prf_key(..) 构造）
+    pub fn setup_for_circuit<R: RngCore + CryptoRng>(
+        blank: CandidateVoteCircuit<F>,
+        rng: &mut R,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        if !(2..=MAX_CANDIDATES).contains(&blank.num_candidates) {
+            return Err(VoteError::Setup(
+                format!("number of candidates must be between 2 and {}, got {}", MAX_CANDIDATES, blank.num_candidates).into(),
+            ));
+        }
+        let span = info_span!("setup", num_candidates = blank.num_candidates, duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        info!("Setting up vote system for {} candidates with {} backend", blank.num_candidates, P::NAME);
+        
+        let (pk, vk) = Self::generate_keys(blank, rng, &TracingProgress)?;
+        
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok((Self::new(), pk, vk))
+    }
+    
+    // 由固定种子确定性地生成密钥（仅用于测试和开发）
+    // 警告：种子即为全部"有毒废料"，任何知道种子的人都能伪造证明，
+    // 这不是可信设置，绝不能用于生产环境
+    #[cfg(any(test, feature = "dev-setup"))]
+    pub fn setup_deterministic(
+        election_id: F,
+        seed: [u8; 32],
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+        
+        warn!("Using deterministic setup; this is NOT a trusted setup and must only be used for testing");
+        let mut rng = ChaCha20Rng::from_seed(seed);
+        Self::setup(election_id, &mut rng)
     }
     
     // 生成投票证明（同步版本）
-    pub fn vote<R: RngCore>(
+    pub fn vote<R: RngCore + CryptoRng>(
         &self,
-        pk: &S::ProvingKey,
+        pk: &P::ProvingKey,
         circuit: VoteCircuit<F>,
         rng: &mut R,
-    ) -> Result<S::Proof, VoteSystemError> {
+    ) -> Result<P::Proof, VoteError> {
+        // 注意：span中不记录投票值及任何私有输入
+        let span = info_span!("prove", duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
         debug!("Generating proof synchronously");
         
-        self.snark.prove(pk, circuit, rng).map_err(|e| {
+        Self::check_constraints(circuit.clone())?;
+        let result = self.prove_in_pool(pk, circuit, rng).map_err(|e| {
             error!("Failed to generate proof: {:?}", e);
-            VoteSystemError::ProofGenerationError(format!("{:?}", e))
+            e
+        });
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+
+    // 由固定种子确定性地生成投票证明（仅用于复现问题报告）
+    // 证明的全部随机性都来自以 seed 初始化的 ChaCha20，相同的密钥、电路和种子总是得到相同的证明字节，
+    // 与是否配置证明线程池无关。
+    // 警告：知道种子即可把证明与种子关联起来，种子泄露或重复使用会破坏证明的零知识性，
+    // 生产环境必须使用 vote 并传入 OsRng 等 CryptoRng
+    pub fn vote_seeded(
+        &self,
+        pk: &P::ProvingKey,
+        circuit: VoteCircuit<F>,
+        seed: [u8; 32],
+    ) -> Result<P::Proof, VoteError> {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        warn!("Generating a seeded proof; this links the proof to its seed and must only be used for debugging");
+        Self::check_constraints(circuit.clone())?;
+        let mut rng = ChaCha20Rng::from_seed(seed);
+        // 不经 prove_in_pool 重新派生种子，保证有无线程池时结果一致
+        let result = match &self.proving_pool {
+            Some(pool) => pool.install(move || P::prove(pk, circuit, &mut rng)),
+            None => P::prove(pk, circuit, &mut rng),
+        };
+        result.map_err(|e| {
+            error!("Failed to generate seeded proof: {:?}", e);
+            e
         })
     }
+
+    // 旧版 setup：接受任意 RngCore，下一个版本移除
+    // 非密码学随机数发生器生成的密钥可能被伪造证明，请改用 setup 并传入 OsRng 等 CryptoRng
+    #[deprecated(since = "0.1.0", note = "use `setup` with an `RngCore + CryptoRng` such as `OsRng`")]
+    pub fn setup_with_any_rng<R: RngCore>(
+        election_id: F,
+        rng: &mut R,
+    ) -> Result<(Self, P::ProvingKey, P::VerifyingKey), VoteError> {
+        Self::setup(election_id, &mut AssumeCryptoRng(rng))
+    }
     
-    // 生成投票证明（异步版本）
-    pub async fn vote_async<R: RngCore + Send + 'static>(
+    // 旧版 vote：接受任意 RngCore，下一个版本移除
+    // 非密码学随机数发生器会削弱证明的零知识性，请改用 vote 并传入 OsRng 等 CryptoRng
+    #[deprecated(since = "0.1.0", note = "use `vote` with an `RngCore + CryptoRng` such as `OsRng`")]
+    pub fn vote_with_any_rng<R: RngCore>(
         &self,
-        pk: S::ProvingKey,
+        pk: &P::ProvingKey,
         circuit: VoteCircuit<F>,
-        mut rng: R,
-    ) -> Result<S::Proof, VoteSystemError> {
+        rng: &mut R,
+    ) -> Result<P::Proof, VoteError> {
+        self.vote(pk, circuit, &mut AssumeCryptoRng(rng))
+    }
+    
+    // 以内存映射的证明密钥生成投票证明，密钥在首次使用时加载
+    pub fn vote_with_handle<R: RngCore + CryptoRng>(
+        &self,
+        handle: &ProvingKeyHandle<F, P>,
+        circuit: VoteCircuit<F>,
+        rng: &mut R,
+    ) -> Result<P::Proof, VoteError> {
+        let pk = handle.get()?;
+        self.vote(&pk, circuit, rng)
+    }
+    
+    // 生成投票证明并返回证明报告（耗时、证明大小、约束数量、内存估计）
+    pub fn vote_with_report<C: ConstraintSynthesizer<F> + Clone + Send, R: RngCore + CryptoRng>(
+        &self,
+        pk: &P::ProvingKey,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(P::Proof, ProofReport), VoteError> {
+        self.vote_with_report_cancellable(pk, circuit, rng, &cancel::CancelToken::default())
+    }
+    
+    // 同 vote_with_report，约束检查前和证明计算前检查取消标志
+    fn vote_with_report_cancellable<C: ConstraintSynthesizer<F> + Clone + Send, R: RngCore + CryptoRng>(
+        &self,
+        pk: &P::ProvingKey,
+        circuit: C,
+        rng: &mut R,
+        cancel: &cancel::CancelToken,
+    ) -> Result<(P::Proof, ProofReport), VoteError> {
+        let span = info_span!("prove", duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        debug!("Generating proof with report");
+        
+        cancel.check("synthesis")?;
+        let cs = Self::check_constraints(circuit.clone())?;
+        let num_constraints = cs.num_constraints();
+        let estimated_memory_bytes = report::estimate_prover_memory(&cs);
+        drop(cs);
+        let synthesis_time = start.elapsed();
+        
+        cancel.check("proving")?;
+        let proving_start = Instant::now();
+        let proof = self.prove_in_pool(pk, circuit, rng).map_err(|e| {
+            error!("Failed to generate proof: {:?}", e);
+            e
+        })?;
+        let proving_time = proving_start.elapsed();
+        
+        let report = ProofReport {
+            synthesis_time,
+            proving_time,
+            proof_size: P::serialize_proof(&proof)?.len(),
+            num_constraints,
+            estimated_memory_bytes,
+        };
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok((proof, report))
+    }
+    
+    // 先检查约束，区分"选票格式错误"与"证明生成失败"
+    // 返回的约束系统中见证赋值已清零，只保留约束结构
+    fn check_constraints<C: ConstraintSynthesizer<F>>(circuit: C) -> Result<ConstraintSystemRef<F>, VoteError> {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone())?;
+        let unsatisfied = cs.which_is_unsatisfied();
+        zeroize_witness(&cs);
+        if let Some(name) = unsatisfied? {
+            warn!("Ballot does not satisfy circuit constraints");
+            return Err(VoteError::UnsatisfiedConstraint(name));
+        }
+        Ok(cs)
+    }
+    
+    // 仅检查电路约束是否满足（不需要密钥，也不生成证明）
+    // 可在提交耗时的证明任务前预先校验输入
+    pub fn check_circuit<C: ConstraintSynthesizer<F>>(circuit: C) -> Result<bool, SynthesisError> {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone())?;
+        let satisfied = cs.is_satisfied();
+        zeroize_witness(&cs);
+        satisfied
+    }
+    
+    // 限定单个证明内部使用的线程数
+    // arkworks 启用 parallel 特性（本 crate 的 parallel 特性）后，证明中的多标量乘法和 FFT
+    // 在当前 rayon 线程池中并行计算；这里为证明单独建一个 threads 个线程的线程池，不占用全局线程池。
+    // 未启用 parallel 特性时证明始终是单线程的，设置不影响结果。批量证明时各张选票共用该线程池
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|i| format!("zkp-vote-prover-{}", i))
+            .build();
+        match pool {
+            Ok(pool) => self.proving_pool = Some(Arc::new(pool)),
+            Err(e) => warn!("Failed to build proving thread pool, using the global pool: {}", e),
+        }
+        self
+    }
+    
+    // 单个证明可使用的线程数（未调用 with_threads 时为全局 rayon 线程池的大小）
+    pub fn proving_threads(&self) -> usize {
+        self.proving_pool.as_ref().map_or_else(rayon::current_num_threads, |pool| pool.current_num_threads())
+    }
+    
+    // 在 with_threads 配置的线程池中生成证明，未配置时直接在调用线程中生成
+    fn prove_in_pool<C, R>(&self, pk: &P::ProvingKey, circuit: C, rng: &mut R) -> Result<P::Proof, VoteError>
+    where
+        C: ConstraintSynthesizer<F> + Send,
+        R: RngCore + CryptoRng,
+    {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+        
+        let Some(pool) = &self.proving_pool else {
+            return P::prove(pk, circuit, rng);
+        };
+        // 调用方的随机数发生器不一定能跨线程传递（如 ThreadRng），由它派生种子交给线程池
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let mut pool_rng = ChaCha20Rng::from_seed(seed);
+        seed.zeroize();
+        pool.install(move || P::prove(pk, circuit, &mut pool_rng))
+    }
+    
+    // 设置批量证明的最大并行度（与Web服务共用机器时可调低）
+    pub fn set_max_parallelism(&mut self, max_parallelism: usize) {
+        self.max_parallelism = max_parallelism.max(1);
+    }
+    
+    // 设置 vote_async 使用的证明后端（如远程证明服务）
+    #[cfg(feature = "server")]
+    pub fn set_prover(&mut self, prover: Arc<dyn AsyncProver<F, P>>) {
+        self.prover = prover;
+    }
+    
+    // 设置本地异步证明使用的证明池，vote_async 改用经该池排队的 LocalProver
+    // 多个 VoteSystem 共用一个证明池时，合计的并发证明数受同一上限约束
+    #[cfg(feature = "server")]
+    pub fn set_prover_pool(&mut self, pool: Arc<ProverPool>) {
+        self.prover = Arc::new(LocalProver::with_pool(pool.clone()));
+        self.prover_pool = pool;
+    }
+    
+    // 本地异步证明使用的证明池（可查询排队深度）
+    #[cfg(feature = "server")]
+    pub fn prover_pool(&self) -> &Arc<ProverPool> {
+        &self.prover_pool
+    }
+    
+    // 由固定种子批量并行生成投票证明（用于基准测试和复现问题报告），结果按输入顺序返回
+    // 每张选票的证明随机性来自以 BLAKE3(seed, 序号) 初始化的 ChaCha20，相同的种子总是得到相同的证明。
+    // 警告：与 vote_seeded 相同，知道种子即可把证明与种子关联起来，种子泄露或重复使用会破坏证明的零知识性，
+    // 生产环境必须逐张调用 vote 并传入 OsRng 等 CryptoRng。
+    // 同时证明的选票不超过 set_max_parallelism 设置的并行度；配置了 with_threads 时在其线程池中计算，否则使用全局 rayon 线程池
+    pub fn prove_batch(
+        &self,
+        pk: &P::ProvingKey,
+        circuits: Vec<VoteCircuit<F>>,
+        seed: [u8; 32],
+    ) -> Result<Vec<P::Proof>, VoteError> {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+        use rayon::prelude::*;
+        
+        warn!("Generating a seeded proof batch; this links the proofs to their seed and must not be used for real ballots");
+        debug!("Generating {} proofs in parallel (max parallelism: {})", circuits.len(), self.max_parallelism);
+        
+        // 每个并行任务至少处理 chunk 张选票，任务数不超过 max_parallelism
+        let chunk = circuits.len().div_ceil(self.max_parallelism).max(1);
+        let prove_all = || {
+            circuits
+                .into_par_iter()
+                .enumerate()
+                .with_min_len(chunk)
+                .map(|(index, circuit)| {
+                    // 派生每张选票独立的随机数种子
+                    let mut hasher = blake3::Hasher::new();
+                    hasher.update(&seed);
+                    hasher.update(&(index as u64).to_le_bytes());
+                    let mut rng = ChaCha20Rng::from_seed(*hasher.finalize().as_bytes());
+                    
+                    self.vote(pk, circuit, &mut rng)
+                })
+                .collect()
+        };
+        match &self.proving_pool {
+            Some(pool) => pool.install(prove_all),
+            None => prove_all(),
+        }
+    }
+    
+    // 生成投票证明（异步版本）
+    // 证明由注入的 AsyncProver 生成，默认的 LocalProver 经证明池排队后在tokio阻塞线程池中计算，
+    // 不会阻塞异步执行器；返回的 future 被丢弃时 LocalProver 不再开始新的证明阶段
+    #[cfg(feature = "server")]
+    pub async fn vote_async(
+        self: Arc<Self>,
+        pk: Arc<P::ProvingKey>,
+        circuit: VoteCircuit<F>,
+    ) -> Result<P::Proof, VoteError> {
         debug!("Generating proof asynchronously");
         
-        // 提交任务到工作线程池
-        self.worker_pool.submit_task(WorkerTask::GenerateProof).await?;
+        self.prover.prove(circuit, pk).await.map_err(|e| {
+            error!("Failed to generate proof: {:?}", e);
+            e
+        })
+    }
+    
+    // 生成投票证明（异步版本，带超时）
+    // 超时后放弃等待并返回 Timeout；本地证明任务在下一个阶段开始前退出，已开始的阶段跑完后丢弃结果
+    #[cfg(feature = "server")]
+    pub async fn vote_async_with_timeout(
+        self: Arc<Self>,
+        pk: Arc<P::ProvingKey>,
+        circuit: VoteCircuit<F>,
+        timeout: std::time::Duration,
+    ) -> Result<P::Proof, VoteError> {
+        tokio::time::timeout(timeout, self.vote_async(pk, circuit)).await.map_err(|_| {
+            warn!("Proof generation timed out after {:?}", timeout);
+            VoteError::Timeout(timeout)
+        })?
+    }
+    
+    // 生成投票证明并返回证明报告（异步版本）
+    // 报告中的耗时只在本进程内测量，因此总是在本地证明（经证明池排队），不经过注入的 AsyncProver
+    #[cfg(feature = "server")]
+    pub async fn vote_with_report_async<C, R>(
+        self: Arc<Self>,
+        pk: Arc<P::ProvingKey>,
+        circuit: C,
+        mut rng: R,
+    ) -> Result<(P::Proof, ProofReport), VoteError>
+    where
+        C: ConstraintSynthesizer<F> + Clone + Send + 'static,
+        R: RngCore + CryptoRng + Send + 'static,
+    {
+        debug!("Generating proof with report asynchronously");
         
-        // 在后台线程中生成证明
-        tokio::task::spawn_blocking(move || {
-            // 模拟一些计算延迟
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            
-            // 生成证明
-            let snark = S::setup(&mut rng).map_err(|e| {
-                VoteSystemError::SnarkError(format!("{:?}", e))
-            })?;
-            
-            snark.prove(&pk, circuit, &mut rng).map_err(|e| {
-                VoteSystemError::ProofGenerationError(format!("{:?}", e))
-            })
-        }).await.map_err(|e| {
-            error!("Failed to spawn proof generation task: {:?}", e);
-            VoteSystemError::InternalError(format!("{:?}", e))
+        // 返回的 future 被丢弃（如客户端断开）时取消尚未开始的证明阶段
+        let pool = self.prover_pool.clone();
+        pool.run(move |cancel| self.vote_with_report_cancellable(&pk, circuit, &mut rng, cancel)).await
+    }
+    
+    // 生成投票证明并返回证明报告（异步版本，带超时，语义同 vote_async_with_timeout）
+    #[cfg(feature = "server")]
+    pub async fn vote_with_report_async_with_timeout<C, R>(
+        self: Arc<Self>,
+        pk: Arc<P::ProvingKey>,
+        circuit: C,
+        rng: R,
+        timeout: std::time::Duration,
+    ) -> Result<(P::Proof, ProofReport), VoteError>
+    where
+        C: ConstraintSynthesizer<F> + Clone + Send + 'static,
+        R: RngCore + CryptoRng + Send + 'static,
+    {
+        tokio::time::timeout(timeout, self.vote_with_report_async(pk, circuit, rng)).await.map_err(|_| {
+            warn!("Proof generation timed out after {:?}", timeout);
+            VoteError::Timeout(timeout)
         })?
     }
     
     // 验证投票证明（不直接暴露投票值）
     pub fn verify(
         &self,
-        vk: &S::VerifyingKey,
-        public_inputs: &[F], // 公开输入（不包含投票值）
-        proof: &S::Proof,
-    ) -> Result<bool, VoteSystemError> {
+        vk: &P::VerifyingKey,
+        public_inputs: &[F], // 公开输入（不包含投票值），须按PublicInputs::to_vec()的顺序
+        proof: &P::Proof,
+    ) -> Result<bool, VoteError> {
+        let span = info_span!("verify", duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
         debug!("Verifying proof synchronously");
         
-        self.snark.verify(vk, public_inputs, proof).map_err(|e| {
+        let result = P::verify(vk, public_inputs, proof).map_err(|e| {
             error!("Failed to verify proof: {:?}", e);
-            VoteSystemError::ProofVerificationError(format!("{:?}", e))
-        })
+            e
+        });
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+    
+    // 验证投票证明并返回验证报告
+    pub fn verify_with_report(
+        &self,
+        vk: &P::VerifyingKey,
+        public_inputs: &[F],
+        proof: &P::Proof,
+    ) -> Result<(bool, VerifyReport), VoteError> {
+        let span = info_span!("verify", duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        debug!("Verifying proof with report");
+        
+        let valid = P::verify(vk, public_inputs, proof).map_err(|e| {
+            error!("Failed to verify proof: {:?}", e);
+            e
+        })?;
+        let report = VerifyReport {
+            verification_time: start.elapsed(),
+            num_public_inputs: public_inputs.len(),
+            proof_size: P::serialize_proof(proof)?.len(),
+        };
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok((valid, report))
+    }
+    
+    // 以类型化的公开输入验证选票证明
+    // 先按电路顺序序列化公开输入并与验证密钥核对数量，不一致时返回 KeyMismatch
+    pub fn verify_ballot(
+        &self,
+        vk: &P::VerifyingKey,
+        public_inputs: &BallotPublicInputs<F>,
+        proof: &P::Proof,
+    ) -> Result<VerificationOutcome, VoteError> {
+        let span = info_span!("verify_ballot", duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        debug!("Verifying ballot proof");
+        
+        let result = Self::check_ballot(vk, public_inputs, proof).map_err(|e| {
+            error!("Failed to verify ballot: {:?}", e);
+            e
+        });
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+    
+    // 验证证明信封：先确认信封中的验证密钥指纹与 vk 一致，再验证证明
+    pub fn verify_envelope(
+        &self,
+        vk: &P::VerifyingKey,
+        envelope: &ProofEnvelope<F, P>,
+    ) -> Result<VerificationOutcome, VoteError> {
+        envelope.check_verifying_key(vk).map_err(|e| {
+            error!("Rejected proof envelope: {}", e);
+            e
+        })?;
+        self.verify_ballot(vk, &envelope.public_inputs, &envelope.proof)
+    }
+    
+    // 逐条验证长度前缀的信封流（格式见 stream 模块），不把整个流读入内存
+    // 每条记录产出一个带字节偏移的 BallotVerdict，单条记录损坏不影响之后的记录
+    pub fn verify_stream<'a, R: std::io::BufRead + 'a>(
+        &'a self,
+        vk: &'a P::VerifyingKey,
+        reader: R,
+    ) -> EnvelopeStream<'a, F, P, R> {
+        EnvelopeStream::new(self, vk, reader)
+    }
+    
+    fn check_ballot(
+        vk: &P::VerifyingKey,
+        public_inputs: &BallotPublicInputs<F>,
+        proof: &P::Proof,
+    ) -> Result<VerificationOutcome, VoteError> {
+        let inputs = public_inputs.to_vec();
+        check_public_input_count::<F, P>(vk, inputs.len())?;
+        Ok(P::verify(vk, &inputs, proof)?.into())
+    }
+    
+    // 计票：逐张验证选票证明，同一防重标识只计入序号最大的有效选票（序号相同时计入第一张），并按开启值累计各选项票数
+    // 选票的投票值不出现在公开输入中，计票方需持有各选票承诺的开启值（见 tally::BallotOpening），
+    // 开启值与证明公开输入中的承诺不符的选票以 commitment_mismatch 拒绝
+    pub fn tally<I>(&self, vk: &P::VerifyingKey, ballots: I) -> Result<TallyResult, VoteError>
+    where
+        I: IntoIterator<Item = (BallotPublicInputs<F>, P::Proof, BallotOpening<F>)>,
+    {
+        self.tally_with_config(vk, &CircuitConfig::default(), ballots)
+    }
+    
+    // 按电路配置计票：候选人票计入 counts，弃权票和废票分别计入 abstained / spoiled，三者都计入投票率
+    // 重新投票后被更大序号取代的选票以 superseded_ballot 拒绝
+    // 计票证明只覆盖二选一配置（见 tally::TallyCircuit）
+    pub fn tally_with_config<I>(
+        &self,
+        vk: &P::VerifyingKey,
+        config: &CircuitConfig,
+        ballots: I,
+    ) -> Result<TallyResult, VoteError>
+    where
+        I: IntoIterator<Item = (BallotPublicInputs<F>, P::Proof, BallotOpening<F>)>,
+    {
+        let span = info_span!("tally", accepted = tracing::field::Empty, rejected = tracing::field::Empty, duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        
+        // 验证密钥不匹配时所有选票都会失败，直接报错而不是全部拒绝
+        check_public_input_count::<F, P>(vk, NUM_PUBLIC_INPUTS)?;
+        
+        // 先逐张验证，有效选票再按防重标识筛选序号最大的一张
+        let mut result = TallyResult::with_candidates(config.num_candidates);
+        let mut valid = Vec::new();
+        for (index, (public_inputs, proof, opening)) in ballots.into_iter().enumerate() {
+            match Self::check_ballot(vk, &public_inputs, &proof) {
+                Ok(VerificationOutcome::Valid) => {}
+                Ok(VerificationOutcome::Invalid { reason }) => {
+                    result.reject_invalid(index, reason.code());
+                    continue;
+                }
+                Err(e) => {
+                    result.reject_error(index, &e);
+                    continue;
+                }
+            }
+            // 证明只担保公开输入中的承诺，开启值与之不符时不能按开启值计票
+            if opening.commitment() != public_inputs.commitment {
+                result.reject_invalid(index, VoteError::CommitmentMismatch.code());
+                continue;
+            }
+            let choice = match VoteChoice::from_field(opening.vote, config) {
+                Ok(choice) => choice,
+                Err(_) => {
+                    result.reject_invalid(index, "vote_out_of_range");
+                    continue;
+                }
+            };
+            valid.push((index, public_inputs, (choice, opening)));
+        }
+        
+        for (index, public_inputs, (choice, opening)) in tally::keep_latest_ballots(&mut result, valid) {
+            match choice {
+                VoteChoice::Abstain => result.abstained += 1,
+                VoteChoice::Spoiled => result.spoiled += 1,
+                choice => result.counts[choice.slot(config)?] += 1,
+            }
+            result.accepted.push(index);
+            result.consumed_nullifiers.push(field_to_decimal(public_inputs.nullifier));
+            result.commitments.push(field_to_decimal(opening.commitment()));
+        }
+        
+        span.record("accepted", result.accepted.len());
+        span.record("rejected", result.rejected.len());
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        info!("Tallied {} ballots, rejected {}", result.accepted.len(), result.rejected.len());
+        Ok(result)
+    }
+    
+    // 批量验证投票证明，返回与输入对齐的逐条结果
+    pub fn verify_batch(
+        &self,
+        vk: &P::VerifyingKey,
+        batch: &[(Vec<F>, P::Proof)],
+    ) -> Vec<Result<bool, VoteError>> {
+        let span = info_span!("verify_batch", batch_size = batch.len(), duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        debug!("Verifying {} proofs in batch", batch.len());
+        
+        let results = P::verify_batch(vk, batch);
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        results
     }
     
     // 验证投票证明（异步版本）
+    // 验证密钥和公开输入以 Arc 传入，并发验证时共享同一份而不必逐个请求克隆
+    #[cfg(feature = "server")]
     pub async fn verify_async(
         &self,
-        vk: S::VerifyingKey,
-        public_inputs: Vec<F>, // 公开输入（不包含投票值）
-        proof: S::Proof,
-    ) -> Result<bool, VoteSystemError> {
+        vk: Arc<P::VerifyingKey>,
+        public_inputs: Arc<[F]>, // 公开输入（不包含投票值）
+        proof: P::Proof,
+    ) -> Result<bool, VoteError> {
         debug!("Verifying proof asynchronously");
         
-        // 提交任务到工作线程池
-        self.worker_pool.submit_task(WorkerTask::VerifyProof).await?;
-        
-        // 在后台线程中验证证明
+        // 在阻塞线程池中验证证明，不经过任何队列或锁
         tokio::task::spawn_blocking(move || {
-            // 模拟一些计算延迟
-            std::thread::sleep(std::time::Duration::from_millis(50));
-            
-            // 验证证明
-            let snark = S::setup(&mut rand::thread_rng()).map_err(|e| {
-                VoteSystemError::SnarkError(format!("{:?}", e))
-            })?;
-            
-            snark.verify(&vk, &public_inputs, &proof).map_err(|e| {
-                VoteSystemError::ProofVerificationError(format!("{:?}", e))
-            })
+            let _enter = info_span!("verify").entered();
+            P::verify(&vk, &public_inputs, &proof)
         }).await.map_err(|e| {
             error!("Failed to spawn proof verification task: {:?}", e);
-            VoteSystemError::InternalError(format!("{:?}", e))
+            VoteError::Internal(format!("{:?}", e))
         })?
     }
+    
+    // 异步验证选票证明（语义同 verify_ballot）
+    #[cfg(feature = "server")]
+    pub async fn verify_ballot_async(
+        &self,
+        vk: Arc<P::VerifyingKey>,
+        public_inputs: BallotPublicInputs<F>,
+        proof: P::Proof,
+    ) -> Result<VerificationOutcome, VoteError> {
+        debug!("Verifying ballot proof asynchronously");
+        
+        tokio::task::spawn_blocking(move || {
+            let _enter = info_span!("verify_ballot").entered();
+            Self::check_ballot(&vk, &public_inputs, &proof)
+        }).await.map_err(|e| {
+            error!("Failed to spawn ballot verification task: {:?}", e);
+            VoteError::Internal(format!("{:?}", e))
+        })?
+    }
+}
+
+impl<F: PrimeField, P: UniversalProofSystem<F>> VoteSystem<F, P> {
+    // 以通用SRS初始化系统，之后每场选举只需调用 index_circuit 获取密钥，无需新的仪式
+    pub fn setup_universal<R: RngCore + CryptoRng>(
+        max_constraints: usize,
+        rng: &mut R,
+    ) -> Result<(Self, P::UniversalSrs), VoteError> {
+        let span = info_span!("setup_universal", max_constraints, duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        info!("Generating universal SRS with {} backend", P::NAME);
+        
+        let srs = P::universal_setup(max_constraints, rng).map_err(|e| {
+            error!("Failed to generate universal SRS: {:?}", e);
+            e
+        })?;
+        
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok((Self::new(), srs))
+    }
+    
+    // 用通用SRS索引投票电路（VoteCircuit 或 CandidateVoteCircuit 的空电路），得到证明密钥和验证密钥
+    pub fn index_circuit<C: ConstraintSynthesizer<F>>(
+        &self,
+        srs: &P::UniversalSrs,
+        circuit: C,
+    ) -> Result<(P::ProvingKey, P::VerifyingKey), VoteError> {
+        let _enter = info_span!("index_circuit").entered();
+        P::index(srs, circuit).map_err(|e| {
+            error!("Failed to index circuit: {:?}", e);
+            e
+        })
+    }
 }
 
-// 生成安全的防重标识
-pub fn generate_nullifier<F: PrimeField, R: RngCore>(user_id: &str, rng: &mut R) -> F {
+impl<E: Pairing> VoteSystem<E::ScalarField, Groth16<E>> {
+    // 重新随机化证明，得到同一陈述的另一个有效证明（需要验证密钥中的 delta）
+    // 公布前重新随机化，使公布的证明无法与投票人自留的证明逐字节对应（无收据性）
+    pub fn rerandomize<R: RngCore + CryptoRng>(
+        &self,
+        vk: &ark_groth16::VerifyingKey<E>,
+        proof: &ark_groth16::Proof<E>,
+        rng: &mut R,
+    ) -> ark_groth16::Proof<E> {
+        Groth16::<E>::rerandomize_proof(vk, proof, rng)
+    }
+    
+    // 将多个投票证明聚合为一个证明，public_inputs 与 proofs 一一对应
+    pub fn aggregate(
+        &self,
+        srs: &AggregationSrs<E>,
+        proofs: &[ark_groth16::Proof<E>],
+        public_inputs: &[Vec<E::ScalarField>],
+    ) -> Result<AggregateProof<E>, VoteError> {
+        let span = info_span!("aggregate", num_proofs = proofs.len(), duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        
+        let result = aggregate::aggregate_proofs(srs, proofs, public_inputs).map_err(|e| {
+            error!("Failed to aggregate proofs: {:?}", e);
+            e
+        });
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+    
+    // 验证聚合证明
+    pub fn verify_aggregate(
+        &self,
+        vk: &ark_groth16::VerifyingKey<E>,
+        srs_vk: &AggregationVerifierKey<E>,
+        public_inputs: &[Vec<E::ScalarField>],
+        proof: &AggregateProof<E>,
+    ) -> Result<bool, VoteError> {
+        let span = info_span!("verify_aggregate", num_proofs = proof.num_proofs, duration_ms = tracing::field::Empty);
+        let _enter = span.enter();
+        let start = Instant::now();
+        
+        let result = aggregate::verify_aggregate_proof(vk, srs_vk, public_inputs, proof);
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+}
+
+// 计算时间戳所在的粗粒度时段编号
+pub fn ballot_epoch(timestamp: DateTime<Utc>) -> u64 {
+    timestamp.timestamp().div_euclid(EPOCH_SECONDS).max(0) as u64
+}
+
+// 将选举标识字符串映射为字段元素
+pub fn election_id_to_field<F: PrimeField>(election_id: &str) -> F {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"zkp-vote/election-id");
+    hasher.update(election_id.as_bytes());
+    F::from_le_bytes_mod_order(hasher.finalize().as_bytes())
+}
+
+// 校验用户ID长度（1..=MAX_USER_ID_LEN 字节）
+// 空ID会让所有匿名请求共享同一登记键，超长ID则被无意义地整体哈希，两者都直接拒绝
+pub fn validate_user_id(user_id: &str) -> Result<(), VoteError> {
+    if user_id.is_empty() {
+        return Err(VoteError::InvalidUserId("user id must not be empty".to_string()));
+    }
+    if user_id.len() > MAX_USER_ID_LEN {
+        return Err(VoteError::InvalidUserId(format!(
+            "user id is {} bytes, at most {} allowed",
+            user_id.len(),
+            MAX_USER_ID_LEN
+        )));
+    }
+    Ok(())
+}
+
+// 生成安全的防重标识，用户ID不合法时返回 InvalidUserId
+pub fn generate_nullifier<F: PrimeField, R: RngCore + CryptoRng>(user_id: &str, rng: &mut R) -> Result<F, VoteError> {
     use blake3::Hasher;
     
-    // 使用Blake3哈希生成防重标识
+    validate_user_id(user_id)?;
+    
+    // 使用Blake3哈希生成防重标识，随机性全部来自调用方提供的随机数发生器
+    let mut entropy = [0u8; 32];
+    rng.fill_bytes(&mut entropy);
     let mut hasher = Hasher::new();
     hasher.update(user_id.as_bytes());
-    hasher.update(&rng.next_u64().to_le_bytes());
-    hasher.update(&std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs().to_le_bytes());
+    hasher.update(&entropy);
+    entropy.zeroize();
     
     let hash = hasher.finalize();
     let hash_bytes = hash.as_bytes();
@@ -255,10 +1742,11 @@ pub fn generate_nullifier<F: PrimeField, R: RngCore>(user_id: &str, rng: &mut R)
     let mut buffer = [0u8; 32];
     buffer.copy_from_slice(&hash_bytes[..32]);
     
-    F::from_le_bytes_mod_order(&buffer)
+    Ok(F::from_le_bytes_mod_order(&buffer))
 }
 
 // 初始化日志
 pub fn init_logger() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // 若已安装tracing订阅器则跳过
+    let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).try_init();
 }
\ No newline at end of file
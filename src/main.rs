@@ -1,76 +1,145 @@
-// 匿名投票系统主逻辑
+// 匿名投票系统演示与离线复核工具
+//
+// 电路和投票系统只在库中定义，不带参数运行时演示完整的投票→验证流程。
+// 验证方只拿到公开输入（防重标识、选举标识、选票序号、时段、选票承诺），看不到投票值。
+//
+// 子命令：
+//   tally --bundle <导出包.json> --tally-vk <计票验证密钥>
+//       用 verify_bundle 复核选举导出包并输出各选项票数。计票验证密钥为可信设置公布的十六进制编码密钥，
+//       不采用导出包中携带的密钥；导出包未通过复核或有选票证明不成立时以非零状态退出
 
-use ark_crypto_primitives::snark::constraints::SNARKGadget;
-use ark_crypto_primitives::snark::{SNARK, TestSNARK};
-use ark_ff::PrimeField;
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use ark_std::rand::RngCore;
+use ark_ff::{One, Zero};
+use ark_groth16::Groth16;
+use ark_std::rand::thread_rng;
+use std::process::ExitCode;
+use zkp_vote::curve::{DefaultField as Fr, DefaultPairing};
+use zkp_vote::field::fr_to_hex;
+use zkp_vote::nullifier::user_secret_to_field;
+use zkp_vote::{
+    ballot_epoch, election_id_to_field, expected_nullifier, verify_bundle, ElectionBundle, ProofEnvelope, ProofSystem,
+    VoteCircuit, VoteError, VoteSystem,
+};
 
-// 定义投票电路
-#[derive(Clone)]
-pub struct VoteCircuit<F: PrimeField> {
-    pub vote: F,        // 投票选择
-    pub nullifier: F,   // 防重标识
-}
+const USAGE: &str = "usage: zkp-vote [tally --bundle <bundle.json> --tally-vk <tally_vk.hex>]";
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for VoteCircuit<F> {
-    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        // 添加约束条件
-        // 这里简化处理，实际应用中需要更复杂的约束
-        
-        // 验证投票选择有效性 (0 或 1)
-        let vote_var = cs.new_witness_variable(|| Ok(self.vote))?;
-        let zero = cs.new_constant_variable(F::zero())?;
-        let one = cs.new_constant_variable(F::one())?;
-        
-        // 约束 vote * (vote - 1) = 0，确保vote只能是0或1
-        cs.enforce_constraint(
-            || "vote * (vote - 1) = 0",
-            |lc| lc + vote_var,
-            |lc| lc + vote_var - one,
-            |lc| lc + zero,
-        )?;
-        
-        // 添加nullifier变量
-        let _nullifier_var = cs.new_witness_variable(|| Ok(self.nullifier))?;
-        
-        Ok(())
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => {
+            demo();
+            ExitCode::SUCCESS
+        }
+        Some("tally") => match tally_args(&args[1..]) {
+            Some((bundle_path, tally_vk_path)) => tally(bundle_path, tally_vk_path),
+            None => {
+                eprintln!("{}", USAGE);
+                ExitCode::from(2)
+            }
+        },
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
     }
 }
 
-// 投票系统结构体
-pub struct VoteSystem<F: PrimeField, S: SNARK<F>> {
-    pub snark: S,
+// 解析 tally 子命令的参数，返回导出包路径和计票验证密钥路径
+fn tally_args(args: &[String]) -> Option<(&str, &str)> {
+    let mut bundle_path = None;
+    let mut tally_vk_path = None;
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, path] if flag == "--bundle" && bundle_path.is_none() => bundle_path = Some(path.as_str()),
+            [flag, path] if flag == "--tally-vk" && tally_vk_path.is_none() => tally_vk_path = Some(path.as_str()),
+            _ => return None,
+        }
+    }
+    Some((bundle_path?, tally_vk_path?))
 }
 
-impl<F: PrimeField, S: SNARK<F>> VoteSystem<F, S> {
-    // 初始化系统
-    pub fn setup<R: RngCore>(rng: &mut R) -> Result<(Self, S::ProvingKey, S::VerifyingKey), S::Error> {
-        let snark = S::setup(rng)?;
-        let (pk, vk) = snark.keygen()?;
-        let system = Self { snark };
-        Ok((system, pk, vk))
+// 复核导出包并输出计票结果
+// 重复投票和被重新投票取代的选票被拒绝属于正常的计票规则，其余原因被拒绝的选票（证明不成立、无法解码等）视为复核失败
+fn tally(path: &str, tally_vk_path: &str) -> ExitCode {
+    let tally_vk = match std::fs::read_to_string(tally_vk_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| hex::decode(text.trim()).map_err(|e| e.to_string()))
+        .and_then(|bytes| Groth16::<DefaultPairing>::deserialize_verifying_key(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(vk) => vk,
+        Err(e) => {
+            eprintln!("failed to read tally verifying key {}: {}", tally_vk_path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let bundle: ElectionBundle = match std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            eprintln!("failed to read bundle {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match verify_bundle::<Fr, Groth16<DefaultPairing>>(&bundle, &tally_vk) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("bundle verification failed: {} ({})", e, e.code());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "ballots: {} (accepted {}, rejected {})",
+        bundle.ballots.len(),
+        result.accepted.len(),
+        result.rejected.len()
+    );
+    for (option, count) in result.counts.iter().enumerate() {
+        println!("option {}: {}", option, count);
     }
-    
-    // 生成投票证明
-    pub fn vote<R: RngCore>(
-        &self,
-        pk: &S::ProvingKey,
-        circuit: VoteCircuit<F>,
-        rng: &mut R,
-    ) -> Result<S::Proof, S::Error> {
-        self.snark.prove(pk, circuit, rng)
+    let mut failed = false;
+    for rejected in &result.rejected {
+        println!("rejected ballot {}: {}", rejected.index, rejected.reason);
+        failed |= rejected.reason != VoteError::DuplicateNullifier.code() && rejected.reason != "superseded_ballot";
     }
-    
-    // 验证投票证明
-    pub fn verify(
-        &self,
-        vk: &S::VerifyingKey,
-        vote: F,
-        nullifier: F,
-        proof: &S::Proof,
-    ) -> Result<bool, S::Error> {
-        let circuit = VoteCircuit { vote, nullifier };
-        self.snark.verify(vk, &circuit, proof)
+    if failed {
+        eprintln!("some ballot proofs failed verification");
+        return ExitCode::FAILURE;
     }
-}
\ No newline at end of file
+    ExitCode::SUCCESS
+}
+
+fn demo() {
+    let mut rng = thread_rng();
+    // 大电路的密钥生成较慢，逐阶段输出进度
+    let progress = |phase: &str, fraction: f64| eprintln!("setup: {} {:.0}%", phase, fraction * 100.0);
+    // 密钥只能用于生成时指定的选举
+    let election_id = election_id_to_field("demo-election");
+    let (system, pk, vk) = VoteSystem::<Fr, Groth16<DefaultPairing>>::setup_with_progress(election_id, &mut rng, &progress)
+        .expect("Failed to setup vote system");
+
+    let circuit = VoteCircuit {
+        vote: Fr::one(),
+        secret: user_secret_to_field(b"demo-voter-secret"),
+        randomness: Fr::from(67890u64),
+        election_id,
+        sequence: Fr::zero(),
+        epoch: Fr::from(ballot_epoch(chrono::Utc::now())),
+    };
+    let public_inputs = circuit.public_inputs();
+    assert_eq!(public_inputs.nullifier, expected_nullifier(b"demo-voter-secret", election_id));
+
+    let proof = system.vote(&pk, circuit, &mut rng).expect("Failed to generate proof");
+    let outcome = system
+        .verify_ballot(&vk, &public_inputs, &proof)
+        .expect("Failed to verify proof");
+    println!("vote proof valid: {}", outcome.is_valid());
+    println!("nullifier: {}", fr_to_hex(public_inputs.nullifier));
+
+    // 证明以带格式版本的信封输出，保存后可用同一验证密钥离线复核
+    let envelope = ProofEnvelope::<Fr, Groth16<DefaultPairing>>::new(&vk, public_inputs, proof)
+        .and_then(|envelope| envelope.to_json())
+        .expect("Failed to build proof envelope");
+    println!("{}", serde_json::to_string_pretty(&envelope).expect("Failed to encode proof envelope"));
+}
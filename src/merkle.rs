@@ -0,0 +1,179 @@
+// 投票人名册的增量 Merkle 树
+//
+// 叶子是投票人的凭证承诺，按登记顺序从左到右插入，根只由名册（承诺序列）决定，任何人都可以用 compute_root 从公布的名册重新计算。
+// 内部节点 = Poseidon(左, 右)（见 nullifier::poseidon_hash2），空位按层取空子树的根（叶子层为0）。
+// 树只保存每层最右侧待配对的左子节点，插入和求根都只需 depth 次哈希。
+// 电路中的资格证明尚未加入（ElectionParams::merkle_depth 目前只接受 0），加入后须使用同一哈希、空位取值和深度。
+
+use crate::nullifier::poseidon_hash2;
+use crate::VoteError;
+use ark_ff::PrimeField;
+use std::collections::HashSet;
+
+// 名册树的深度，最多容纳 2^20 名投票人
+pub const VOTER_TREE_DEPTH: usize = 20;
+
+// 增量 Merkle 树
+#[derive(Clone, Debug)]
+pub struct IncrementalMerkleTree<F: PrimeField> {
+    depth: usize,
+    size: u64,
+    frontier: Vec<F>, // 各层最右侧待配对的左子节点
+    zeros: Vec<F>,    // 各层空子树的根，zeros[depth] 为空树的根
+    root: F,
+}
+
+impl<F: PrimeField> IncrementalMerkleTree<F> {
+    // 创建指定深度（1..=32）的空树
+    pub fn new(depth: usize) -> Result<Self, VoteError> {
+        if !(1..=32).contains(&depth) {
+            return Err(VoteError::InvalidBallot(format!("merkle tree depth must be between 1 and 32, got {}", depth)));
+        }
+        let zeros = empty_roots::<F>(depth);
+        Ok(Self { depth, size: 0, frontier: vec![F::zero(); depth], root: zeros[depth], zeros })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // 已插入的叶子数
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn root(&self) -> F {
+        self.root
+    }
+
+    // 在最右侧插入叶子，返回叶子的位置；树已满时返回 InvalidBallot
+    pub fn insert(&mut self, leaf: F) -> Result<u64, VoteError> {
+        if self.size >= 1u64 << self.depth {
+            return Err(VoteError::InvalidBallot(format!("merkle tree of depth {} is full", self.depth)));
+        }
+        let index = self.size;
+        let mut node = leaf;
+        let mut position = index;
+        for level in 0..self.depth {
+            node = if position.is_multiple_of(2) {
+                self.frontier[level] = node;
+                poseidon_hash2(node, self.zeros[level])
+            } else {
+                poseidon_hash2(self.frontier[level], node)
+            };
+            position /= 2;
+        }
+        self.root = node;
+        self.size += 1;
+        Ok(index)
+    }
+}
+
+// 各层空子树的根
+fn empty_roots<F: PrimeField>(depth: usize) -> Vec<F> {
+    let mut zeros = vec![F::zero()];
+    for level in 0..depth {
+        zeros.push(poseidon_hash2(zeros[level], zeros[level]));
+    }
+    zeros
+}
+
+// 检查叶子数能放入指定深度的树
+fn check_fits(depth: usize, num_leaves: usize) -> Result<(), VoteError> {
+    if !(1..=32).contains(&depth) || num_leaves as u64 > 1u64 << depth {
+        return Err(VoteError::InvalidBallot(format!("{} leaves do not fit a merkle tree of depth {}", num_leaves, depth)));
+    }
+    Ok(())
+}
+
+// 由完整的叶子序列逐层重新计算根（与依次插入 IncrementalMerkleTree 的结果相同），供第三方从公布的名册复核
+pub fn compute_root<F: PrimeField>(depth: usize, leaves: &[F]) -> Result<F, VoteError> {
+    check_fits(depth, leaves.len())?;
+    let zeros = empty_roots::<F>(depth);
+    let mut level = leaves.to_vec();
+    for zero in &zeros[..depth] {
+        level = level
+            .chunks(2)
+            .map(|pair| poseidon_hash2(pair[0], pair.get(1).copied().unwrap_or(*zero)))
+            .collect();
+    }
+    Ok(level.first().copied().unwrap_or(zeros[depth]))
+}
+
+// 第 index 个叶子的成员路径：从叶子层向上各层的兄弟节点，空位取空子树的根
+pub fn merkle_path<F: PrimeField>(depth: usize, leaves: &[F], index: u64) -> Result<Vec<F>, VoteError> {
+    check_fits(depth, leaves.len())?;
+    if index >= leaves.len() as u64 {
+        return Err(VoteError::InvalidBallot(format!("leaf {} is not in a roll of {}", index, leaves.len())));
+    }
+    let zeros = empty_roots::<F>(depth);
+    let mut level = leaves.to_vec();
+    let mut position = index as usize;
+    let mut path = Vec::with_capacity(depth);
+    for zero in &zeros[..depth] {
+        path.push(level.get(position ^ 1).copied().unwrap_or(*zero));
+        level = level
+            .chunks(2)
+            .map(|pair| poseidon_hash2(pair[0], pair.get(1).copied().unwrap_or(*zero)))
+            .collect();
+        position /= 2;
+    }
+    Ok(path)
+}
+
+// 由叶子、位置和成员路径重新计算根，与名册树根相同即证明该叶子在名册中
+pub fn path_root<F: PrimeField>(leaf: F, index: u64, path: &[F]) -> F {
+    path.iter().enumerate().fold(leaf, |node, (level, sibling)| {
+        if (index >> level) & 1 == 0 {
+            poseidon_hash2(node, *sibling)
+        } else {
+            poseidon_hash2(*sibling, node)
+        }
+    })
+}
+
+// 投票人名册：凭证承诺的 Merkle 树及已登记承诺的集合
+#[derive(Clone, Debug)]
+pub struct VoterRoll<F: PrimeField> {
+    tree: IncrementalMerkleTree<F>,
+    members: HashSet<F>,
+}
+
+impl<F: PrimeField> VoterRoll<F> {
+    pub fn new(depth: usize) -> Result<Self, VoteError> {
+        Ok(Self { tree: IncrementalMerkleTree::new(depth)?, members: HashSet::new() })
+    }
+
+    pub fn tree(&self) -> &IncrementalMerkleTree<F> {
+        &self.tree
+    }
+
+    // 按给定顺序登记一批承诺，返回第一个承诺的位置
+    // 批内重复或已登记的承诺返回 DuplicateCommitment，容量不足返回 InvalidBallot，两种情况下整批都不登记
+    pub fn register(&mut self, commitments: &[F]) -> Result<u64, VoteError> {
+        let mut batch = HashSet::with_capacity(commitments.len());
+        for commitment in commitments {
+            if self.members.contains(commitment) || !batch.insert(*commitment) {
+                return Err(VoteError::DuplicateCommitment(crate::field_to_decimal(*commitment)));
+            }
+        }
+        let capacity = (1u64 << self.tree.depth()) - self.tree.len();
+        if commitments.len() as u64 > capacity {
+            return Err(VoteError::InvalidBallot(format!(
+                "cannot register {} voters, the voter roll has room for {}",
+                commitments.len(),
+                capacity
+            )));
+        }
+        let first = self.tree.len();
+        for commitment in commitments {
+            self.tree.insert(*commitment)?;
+        }
+        self.members.extend(batch);
+        Ok(first)
+    }
+}
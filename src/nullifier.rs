@@ -0,0 +1,196 @@
+// 由用户秘密派生防重标识
+//
+// 防重标识 = Poseidon(用户秘密, 选举标识)。投票电路（VoteCircuit）和 NullifierCircuit 在电路内完成派生并公开防重标识，
+// 服务端用 expected_nullifier 独立计算同一值并与证明的公开输入比对。
+// 原生计算与电路共用同一组 Poseidon 参数，修改参数时两者同步变化；轮数按曲线选取（见 curve::params）。
+
+use crate::curve::{params, CurveParams};
+use ark_ff::PrimeField;
+use ark_relations::lc;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// Poseidon 参数：宽度3（速率2、容量1），S盒 x^5
+const POSEIDON_WIDTH: usize = 3;
+
+// 轮常数（由固定域分隔串派生）
+fn round_constants<F: PrimeField>() -> Vec<[F; POSEIDON_WIDTH]> {
+    let p = params::<F>();
+    (0..p.poseidon_full_rounds + p.poseidon_partial_rounds)
+        .map(|r| {
+            let mut constants = [F::zero(); POSEIDON_WIDTH];
+            for (i, c) in constants.iter_mut().enumerate() {
+                let mut hasher = blake3::Hasher::new();
+                hasher.update(b"zkp-vote/nullifier/poseidon");
+                hasher.update(&((r * POSEIDON_WIDTH + i) as u64).to_le_bytes());
+                *c = F::from_le_bytes_mod_order(hasher.finalize().as_bytes());
+            }
+            constants
+        })
+        .collect()
+}
+
+// Cauchy 构造的 MDS 矩阵：M[i][j] = 1 / (i + WIDTH + j)
+fn mds_matrix<F: PrimeField>() -> [[F; POSEIDON_WIDTH]; POSEIDON_WIDTH] {
+    let mut m = [[F::zero(); POSEIDON_WIDTH]; POSEIDON_WIDTH];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = F::from((i + POSEIDON_WIDTH + j) as u64).inverse().unwrap();
+        }
+    }
+    m
+}
+
+// 当前轮是否为完整轮（前后各一半完整轮，中间为部分轮）
+fn is_full_round(p: &CurveParams, r: usize) -> bool {
+    r < p.poseidon_full_rounds / 2 || r >= p.poseidon_full_rounds / 2 + p.poseidon_partial_rounds
+}
+
+// 双输入 Poseidon 哈希：状态初始化为 [0, a, b]，输出置换后的第0个元素
+pub fn poseidon_hash2<F: PrimeField>(a: F, b: F) -> F {
+    let p = params::<F>();
+    let mds = mds_matrix::<F>();
+    let mut state = [F::zero(), a, b];
+    for (r, constants) in round_constants::<F>().iter().enumerate() {
+        for (s, c) in state.iter_mut().zip(constants) {
+            *s += c;
+        }
+        let sbox_width = if is_full_round(&p, r) { POSEIDON_WIDTH } else { 1 };
+        for s in &mut state[..sbox_width] {
+            *s = s.square().square() * *s;
+        }
+        let mut next = [F::zero(); POSEIDON_WIDTH];
+        for (n, row) in next.iter_mut().zip(&mds) {
+            *n = row.iter().zip(&state).map(|(m, s)| *m * s).sum();
+        }
+        state = next;
+    }
+    state[0]
+}
+
+// 在电路内计算双输入 Poseidon 哈希，返回结果变量及其值
+pub(crate) fn enforce_poseidon_hash2<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    a: (Variable, F),
+    b: (Variable, F),
+) -> Result<(Variable, F), SynthesisError> {
+    let p = params::<F>();
+    let mds = mds_matrix::<F>();
+    let mut state_vals = [F::zero(), a.1, b.1];
+    let mut state: [LinearCombination<F>; POSEIDON_WIDTH] = [lc!(), lc!() + a.0, lc!() + b.0];
+
+    for (r, constants) in round_constants::<F>().iter().enumerate() {
+        for i in 0..POSEIDON_WIDTH {
+            state_vals[i] += constants[i];
+            state[i] = state[i].clone() + (constants[i], Variable::One);
+        }
+
+        let sbox_width = if is_full_round(&p, r) { POSEIDON_WIDTH } else { 1 };
+        for i in 0..sbox_width {
+            let x_val = state_vals[i];
+            let x2_val = x_val.square();
+            let x2_var = cs.new_witness_variable(|| Ok(x2_val))?;
+            cs.enforce_constraint(state[i].clone(), state[i].clone(), lc!() + x2_var)?;
+
+            let x4_val = x2_val.square();
+            let x4_var = cs.new_witness_variable(|| Ok(x4_val))?;
+            cs.enforce_constraint(lc!() + x2_var, lc!() + x2_var, lc!() + x4_var)?;
+
+            state_vals[i] = x4_val * x_val;
+            let x5_var = cs.new_witness_variable(|| Ok(state_vals[i]))?;
+            cs.enforce_constraint(lc!() + x4_var, state[i].clone(), lc!() + x5_var)?;
+            state[i] = lc!() + x5_var;
+        }
+
+        // 线性层结果落到新变量上，避免线性组合在部分轮中不断膨胀
+        let mut next_vals = [F::zero(); POSEIDON_WIDTH];
+        let mut next: [LinearCombination<F>; POSEIDON_WIDTH] = [lc!(), lc!(), lc!()];
+        for i in 0..POSEIDON_WIDTH {
+            let mut mixed: LinearCombination<F> = lc!();
+            for j in 0..POSEIDON_WIDTH {
+                next_vals[i] += mds[i][j] * state_vals[j];
+                mixed = mixed + (mds[i][j], &state[j]);
+            }
+            let var = cs.new_witness_variable(|| Ok(next_vals[i]))?;
+            cs.enforce_constraint(mixed, lc!() + Variable::One, lc!() + var)?;
+            next[i] = lc!() + var;
+        }
+        state = next;
+        state_vals = next_vals;
+    }
+
+    let out = cs.new_witness_variable(|| Ok(state_vals[0]))?;
+    cs.enforce_constraint(state[0].clone(), lc!() + Variable::One, lc!() + out)?;
+    Ok((out, state_vals[0]))
+}
+
+// 将任意长度的用户秘密映射为字段元素
+pub fn user_secret_to_field<F: PrimeField>(user_secret: &[u8]) -> F {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"zkp-vote/user-secret");
+    hasher.update(user_secret);
+    F::from_le_bytes_mod_order(hasher.finalize().as_bytes())
+}
+
+// 计算服务端预期的防重标识，与 NullifierCircuit 公开的防重标识一致
+pub fn expected_nullifier<F: PrimeField>(user_secret: &[u8], election_id: F) -> F {
+    poseidon_hash2(user_secret_to_field(user_secret), election_id)
+}
+
+// 防重标识派生电路
+// 公开输入顺序：[防重标识, 选举标识]（与 PublicInputs::to_vec() 的前两项一致）
+#[derive(Clone, Debug)]
+pub struct NullifierCircuit<F: PrimeField> {
+    pub secret: F,      // 用户秘密（私有输入）
+    pub election_id: F, // 选举标识
+}
+
+impl<F: PrimeField> NullifierCircuit<F> {
+    // 由用户秘密字节构造电路
+    pub fn new(user_secret: &[u8], election_id: F) -> Self {
+        Self { secret: user_secret_to_field(user_secret), election_id }
+    }
+
+    // 该电路实例公开的防重标识
+    pub fn nullifier(&self) -> F {
+        poseidon_hash2(self.secret, self.election_id)
+    }
+
+    // 按电路分配顺序输出公开输入
+    pub fn public_inputs(&self) -> Vec<F> {
+        vec![self.nullifier(), self.election_id]
+    }
+}
+
+impl<F: PrimeField> Zeroize for NullifierCircuit<F> {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<F: PrimeField> Drop for NullifierCircuit<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: PrimeField> ZeroizeOnDrop for NullifierCircuit<F> {}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for NullifierCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let nullifier_var = cs.new_input_variable(|| Ok(self.nullifier()))?;
+        let election_id_var = cs.new_input_variable(|| Ok(self.election_id))?;
+        let secret_var = cs.new_witness_variable(|| Ok(self.secret))?;
+
+        let (hash, _) = enforce_poseidon_hash2(
+            &cs,
+            (secret_var, self.secret),
+            (election_id_var, self.election_id),
+        )?;
+        cs.enforce_constraint(lc!() + hash, lc!() + Variable::One, lc!() + nullifier_var)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+// 按候选人累加的 Pedersen 承诺（运行计票）
+//
+// 承诺 C = v·G + r·H，G 为曲线群的生成元，H 由固定的域分隔串哈希到曲线得到，
+// 没有人知道 H 关于 G 的离散对数，因此承诺是绑定的。承诺满足加法同态：
+//   Σ C_i = (Σ v_i)·G + (Σ r_i)·H
+// RunningTally 为每个候选人维护承诺之和、随机值之和与票数，不保存逐张选票即可随时得到计票结果。
+// finalize 公开各候选人的票数 n 与承诺之和 C，并附 Schnorr 证明：知道 R 使 C − n·G = R·H，
+// 即 C 确实打开为 n，同时不公开随机值之和。审计方可将公布的逐票承诺求和，与 C 核对。
+
+use crate::tally::NUM_OPTIONS;
+use crate::VoteError;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{PrimeField, UniformRand, Zero};
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::{CryptoRng, RngCore};
+use tracing::debug;
+use zeroize::Zeroize;
+
+// 派生生成元 H 的域分隔串
+const GENERATOR_H_CONTEXT: &str = "zkp-vote 2024-06 pedersen generator H";
+
+// 打开证明挑战值的域分隔串
+const OPENING_CHALLENGE_CONTEXT: &str = "zkp-vote 2024-06 running tally opening challenge";
+
+// Pedersen 承诺参数
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PedersenParams<G: CurveGroup> {
+    pub g: G::Affine,
+    pub h: G::Affine,
+}
+
+impl<G: CurveGroup> PedersenParams<G> {
+    // 标准参数：G 为群生成元，H 由域分隔串按"尝试-递增"哈希到曲线并清除余因子
+    pub fn new() -> Self {
+        let len = G::Affine::generator().compressed_size();
+        let mut bytes = vec![0u8; len];
+        let h = (0u64..)
+            .find_map(|counter| {
+                let mut hasher = blake3::Hasher::new_derive_key(GENERATOR_H_CONTEXT);
+                hasher.update(&counter.to_le_bytes());
+                hasher.finalize_xof().fill(&mut bytes);
+                G::Affine::from_random_bytes(&bytes)
+                    .map(|point| point.clear_cofactor())
+                    .filter(|point| !point.is_zero())
+            })
+            .expect("hash-to-curve eventually finds a point");
+        Self { g: G::Affine::generator(), h }
+    }
+
+    // 计算承诺 value·G + randomness·H
+    pub fn commit(&self, value: G::ScalarField, randomness: G::ScalarField) -> G::Affine {
+        (self.g * value + self.h * randomness).into_affine()
+    }
+}
+
+impl<G: CurveGroup> Default for PedersenParams<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 一票的承诺（承诺值为1），随机值由计票方保存，释放时清零
+#[derive(Clone, Debug)]
+pub struct VoteCommitment<G: CurveGroup> {
+    pub point: G::Affine,
+    pub randomness: G::ScalarField,
+}
+
+impl<G: CurveGroup> VoteCommitment<G> {
+    // 以新的随机值生成一票的承诺
+    pub fn new<R: RngCore + CryptoRng>(params: &PedersenParams<G>, rng: &mut R) -> Self {
+        let randomness = G::ScalarField::rand(rng);
+        Self { point: params.commit(G::ScalarField::from(1u64), randomness), randomness }
+    }
+
+    // 检查承诺确实打开为1
+    pub fn check(&self, params: &PedersenParams<G>) -> bool {
+        params.commit(G::ScalarField::from(1u64), self.randomness) == self.point
+    }
+}
+
+impl<G: CurveGroup> Drop for VoteCommitment<G> {
+    fn drop(&mut self) {
+        self.randomness.zeroize();
+    }
+}
+
+// 承诺打开的 Schnorr 证明：知道 R 使 C − n·G = R·H
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpeningProof<G: CurveGroup> {
+    pub t: G::Affine,      // k·H
+    pub s: G::ScalarField, // k + c·R
+}
+
+// 单个候选人的最终结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CandidateTotal<G: CurveGroup> {
+    pub count: u64,            // 票数
+    pub commitment: G::Affine, // 该候选人全部承诺之和
+    pub proof: OpeningProof<G>,
+}
+
+// 运行计票的最终结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinalTally<G: CurveGroup> {
+    pub totals: Vec<CandidateTotal<G>>,
+}
+
+impl<G: CurveGroup> FinalTally<G> {
+    // 各候选人票数
+    pub fn counts(&self) -> Vec<u64> {
+        self.totals.iter().map(|t| t.count).collect()
+    }
+
+    // 验证每个候选人的承诺之和确实打开为公布的票数
+    pub fn verify(&self, params: &PedersenParams<G>) -> Result<(), VoteError> {
+        for (candidate, total) in self.totals.iter().enumerate() {
+            if !verify_opening(params, total) {
+                return Err(VoteError::Verification(
+                    format!("opening proof for candidate {} is invalid", candidate).into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 打开证明的挑战值，绑定参数、承诺、票数和 T
+fn opening_challenge<G: CurveGroup>(
+    params: &PedersenParams<G>,
+    commitment: &G::Affine,
+    count: u64,
+    t: &G::Affine,
+) -> G::ScalarField {
+    let mut bytes = Vec::new();
+    (params.g, params.h, *commitment, *t)
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    bytes.extend_from_slice(&count.to_le_bytes());
+    G::ScalarField::from_le_bytes_mod_order(blake3::derive_key(OPENING_CHALLENGE_CONTEXT, &bytes).as_slice())
+}
+
+// 检查 s·H = T + c·(C − n·G)
+fn verify_opening<G: CurveGroup>(params: &PedersenParams<G>, total: &CandidateTotal<G>) -> bool {
+    let c = opening_challenge(params, &total.commitment, total.count, &total.proof.t);
+    let blinding = total.commitment.into_group() - params.g * G::ScalarField::from(total.count);
+    params.h * total.proof.s == total.proof.t.into_group() + blinding * c
+}
+
+// 按候选人累加承诺的运行计票
+#[derive(Clone, Debug)]
+pub struct RunningTally<G: CurveGroup> {
+    params: PedersenParams<G>,
+    sums: Vec<G>,                    // 各候选人的承诺之和
+    randomness: Vec<G::ScalarField>, // 各候选人的随机值之和
+    counts: Vec<u64>,                // 各候选人票数
+}
+
+impl<G: CurveGroup> RunningTally<G> {
+    // 创建指定候选人数量的运行计票
+    pub fn new(num_candidates: usize) -> Self {
+        Self {
+            params: PedersenParams::new(),
+            sums: vec![G::zero(); num_candidates],
+            randomness: vec![G::ScalarField::zero(); num_candidates],
+            counts: vec![0; num_candidates],
+        }
+    }
+
+    // 承诺参数
+    pub fn params(&self) -> &PedersenParams<G> {
+        &self.params
+    }
+
+    // 将一票的承诺累加到候选人名下
+    pub fn add(&mut self, commitment: &VoteCommitment<G>, candidate: usize) -> Result<(), VoteError> {
+        if candidate >= self.counts.len() {
+            return Err(VoteError::InvalidBallot(format!(
+                "candidate {} out of range, {} candidates",
+                candidate,
+                self.counts.len()
+            )));
+        }
+        if !commitment.check(&self.params) {
+            return Err(VoteError::CommitmentMismatch);
+        }
+        self.sums[candidate] += commitment.point;
+        self.randomness[candidate] += commitment.randomness;
+        self.counts[candidate] += 1;
+        debug!("Added vote commitment for candidate {}", candidate);
+        Ok(())
+    }
+
+    // 撤回之前累加到候选人名下的一票（重新投票时替换旧选票）
+    pub fn remove(&mut self, commitment: &VoteCommitment<G>, candidate: usize) -> Result<(), VoteError> {
+        if self.counts.get(candidate).copied().unwrap_or(0) == 0 {
+            return Err(VoteError::InvalidBallot(format!("no vote recorded for candidate {}", candidate)));
+        }
+        if !commitment.check(&self.params) {
+            return Err(VoteError::CommitmentMismatch);
+        }
+        self.sums[candidate] -= commitment.point;
+        self.randomness[candidate] -= commitment.randomness;
+        self.counts[candidate] -= 1;
+        debug!("Removed vote commitment for candidate {}", candidate);
+        Ok(())
+    }
+
+    // 当前各候选人票数
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    // 候选人当前的承诺之和
+    pub fn commitment(&self, candidate: usize) -> Option<G::Affine> {
+        self.sums.get(candidate).map(|sum| sum.into_affine())
+    }
+
+    // 公开各候选人票数与承诺之和，并为每个候选人生成打开证明
+    pub fn finalize<R: RngCore + CryptoRng>(&self, rng: &mut R) -> FinalTally<G> {
+        let totals = (0..self.counts.len())
+            .map(|candidate| {
+                let count = self.counts[candidate];
+                let commitment = self.sums[candidate].into_affine();
+                let mut k = G::ScalarField::rand(rng);
+                let t = (self.params.h * k).into_affine();
+                let c = opening_challenge(&self.params, &commitment, count, &t);
+                let s = k + c * self.randomness[candidate];
+                k.zeroize();
+                CandidateTotal { count, commitment, proof: OpeningProof { t, s } }
+            })
+            .collect();
+        FinalTally { totals }
+    }
+}
+
+impl<G: CurveGroup> Default for RunningTally<G> {
+    fn default() -> Self {
+        Self::new(NUM_OPTIONS)
+    }
+}
+
+impl<G: CurveGroup> Drop for RunningTally<G> {
+    fn drop(&mut self) {
+        self.randomness.zeroize();
+    }
+}
@@ -0,0 +1,157 @@
+// 服务端 PRF 密钥
+//
+// 投票人秘密只由用户ID决定时，知道用户ID取值范围的观察者可以逐个算出防重标识，把公开的选票与用户对应起来。
+// 设置 PRF 密钥后，投票人秘密 = Poseidon(PRF密钥, 用户ID)，防重标识 = Poseidon(投票人秘密, 选举标识)，
+// 即 PRF_密钥(用户ID, 选举标识)；同一用户在同一选举中总是得到同一防重标识，不持有密钥则无法由用户ID计算。
+// 密钥承诺 = Poseidon(PRF密钥, 1) 与选举参数摘要一样作为电路常量绑定到密钥，
+// 证明同时说明投票人秘密由承诺对应的 PRF 密钥派生（见 PrfBinding）；承诺不是公开输入，
+// 验证方从 GET /vk 响应的 prf_key_commitment 得知验证密钥绑定的是哪个承诺。
+// 密钥由环境变量 ZKP_VOTE_PRF_KEY 或配置读入，Debug 输出只显示承诺，释放时清零；密钥本身不得写入日志。
+
+use crate::nullifier::{enforce_poseidon_hash2, poseidon_hash2, user_secret_to_field};
+use crate::VoteError;
+use ark_ff::PrimeField;
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError, Variable};
+use ark_std::rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// 读取 PRF 密钥的环境变量（32字节的十六进制编码）
+pub const PRF_KEY_ENV: &str = "ZKP_VOTE_PRF_KEY";
+
+// 服务端持有的 PRF 密钥
+pub struct PrfKey<F: PrimeField> {
+    key: F,
+}
+
+impl<F: PrimeField> PrfKey<F> {
+    // 由32字节密钥材料构造（按小端取模）
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        Self { key: F::from_le_bytes_mod_order(bytes) }
+    }
+
+    // 解析十六进制编码的32字节密钥，错误信息中不包含密钥内容
+    pub fn from_hex(s: &str) -> Result<Self, VoteError> {
+        let mut bytes = [0u8; 32];
+        let decoded = hex::decode_to_slice(s.trim(), &mut bytes);
+        let key = decoded.map(|()| Self::from_bytes(&bytes));
+        bytes.zeroize();
+        key.map_err(|_| VoteError::Setup("PRF key must be 32 bytes encoded as 64 hex digits".into()))
+    }
+
+    // 从环境变量 ZKP_VOTE_PRF_KEY 读取，未设置时返回 None
+    pub fn from_env() -> Result<Option<Self>, VoteError> {
+        match std::env::var(PRF_KEY_ENV) {
+            Ok(mut value) => {
+                let key = Self::from_hex(&value);
+                value.zeroize();
+                key.map(Some)
+            }
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(std::env::VarError::NotUnicode(_)) => {
+                Err(VoteError::Setup(format!("{} is not valid unicode", PRF_KEY_ENV).into()))
+            }
+        }
+    }
+
+    // 随机生成密钥
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self { key: F::rand(rng) }
+    }
+
+    // 密钥承诺，作为电路常量绑定到证明密钥，可以公开
+    pub fn commitment(&self) -> F {
+        prf_commitment(self.key)
+    }
+
+    // 由用户ID派生投票人秘密
+    pub fn voter_secret(&self, user_id: &str) -> F {
+        poseidon_hash2(self.key, user_secret_to_field(user_id.as_bytes()))
+    }
+
+    // 用户在指定选举中的防重标识
+    pub fn nullifier(&self, user_id: &str, election_id: F) -> F {
+        poseidon_hash2(self.voter_secret(user_id), election_id)
+    }
+
+    // 电路中证明派生关系所需的见证
+    pub fn binding(&self, user_id: &str) -> PrfBinding<F> {
+        PrfBinding {
+            commitment: self.commitment(),
+            key: self.key,
+            user: user_secret_to_field(user_id.as_bytes()),
+        }
+    }
+}
+
+impl<F: PrimeField> std::fmt::Debug for PrfKey<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrfKey").field("commitment", &self.commitment().to_string()).finish_non_exhaustive()
+    }
+}
+
+impl<F: PrimeField> Zeroize for PrfKey<F> {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl<F: PrimeField> Drop for PrfKey<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: PrimeField> ZeroizeOnDrop for PrfKey<F> {}
+
+fn prf_commitment<F: PrimeField>(key: F) -> F {
+    poseidon_hash2(key, F::one())
+}
+
+// 投票电路中的 PRF 派生关系：承诺为电路常量，PRF 密钥和用户ID为私有输入
+// 约束 Poseidon(密钥, 1) = 承诺、Poseidon(密钥, 用户ID) = 投票人秘密
+#[derive(Clone)]
+pub struct PrfBinding<F: PrimeField> {
+    pub commitment: F, // PRF 密钥承诺（电路常量）
+    key: F,            // PRF 密钥（私有输入）
+    user: F,           // 用户ID映射的字段元素（私有输入）
+}
+
+impl<F: PrimeField> PrfBinding<F> {
+    // 仅用于生成密钥的空见证，只确定承诺常量
+    pub fn blank(commitment: F) -> Self {
+        Self { commitment, key: F::zero(), user: F::zero() }
+    }
+
+    // 生成约束，secret 为投票电路中投票人秘密的见证变量
+    pub(crate) fn enforce(&self, cs: &ConstraintSystemRef<F>, secret: Variable) -> Result<(), SynthesisError> {
+        let key_var = cs.new_witness_variable(|| Ok(self.key))?;
+        let user_var = cs.new_witness_variable(|| Ok(self.user))?;
+        let (commitment, _) = enforce_poseidon_hash2(cs, (key_var, self.key), (Variable::One, F::one()))?;
+        cs.enforce_constraint(lc!() + commitment, lc!() + Variable::One, lc!() + (self.commitment, Variable::One))?;
+        let (derived, _) = enforce_poseidon_hash2(cs, (key_var, self.key), (user_var, self.user))?;
+        cs.enforce_constraint(lc!() + derived, lc!() + Variable::One, lc!() + secret)?;
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> std::fmt::Debug for PrfBinding<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrfBinding").field("commitment", &self.commitment.to_string()).finish_non_exhaustive()
+    }
+}
+
+impl<F: PrimeField> Zeroize for PrfBinding<F> {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.user.zeroize();
+    }
+}
+
+impl<F: PrimeField> Drop for PrfBinding<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: PrimeField> ZeroizeOnDrop for PrfBinding<F> {}
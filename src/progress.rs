@@ -0,0 +1,31 @@
+// 长时间操作的进度报告
+//
+// 大电路的密钥生成可能要几分钟，导入密钥的一致性检查和仪式转录验证也要做大量配对运算，
+// 运营方需要区分"仍在进行"和"卡住"。这些操作按阶段向 ProgressSink 报告进度：
+// 阶段名为稳定的标识（如 "generate_keys"），完成比例在 [0, 1] 内，同一操作中单调不减，
+// 操作成功结束时最后一次报告为 1.0。
+// 底层密钥生成本身不可细分，进度只在阶段之间推进。
+
+use tracing::info;
+
+// 进度回调
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, phase: &str, fraction: f64);
+}
+
+// 以 tracing 事件输出进度（setup 等不带进度参数的接口默认使用）
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingProgress;
+
+impl ProgressSink for TracingProgress {
+    fn report(&self, phase: &str, fraction: f64) {
+        info!(phase, percent = (fraction * 100.0).round() as u32, "Progress: {} {:.0}%", phase, fraction * 100.0);
+    }
+}
+
+// 闭包也可以直接作为进度回调
+impl<T: Fn(&str, f64) + Send + Sync> ProgressSink for T {
+    fn report(&self, phase: &str, fraction: f64) {
+        self(phase, fraction)
+    }
+}
@@ -0,0 +1,270 @@
+// 异步证明后端
+//
+// VoteSystem::vote_async 把证明生成委托给注入的 AsyncProver，证明可以在本进程内完成，
+// 也可以交给远程证明服务（如GPU服务器）。默认使用 LocalProver，证明任务经 ProverPool 排队，
+// 限制同时进行的证明数量（每个证明都要占用与电路规模成正比的内存）。
+
+use crate::cancel::CancelToken;
+use crate::{ProofSystem, VoteCircuit, VoteError, VoteSystem};
+use ark_ff::PrimeField;
+use ark_std::rand::rngs::OsRng;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info_span, warn};
+
+// 异步证明后端
+#[async_trait::async_trait]
+pub trait AsyncProver<F: PrimeField, P: ProofSystem<F>>: Send + Sync + 'static {
+    // 为投票电路生成证明
+    async fn prove(&self, circuit: VoteCircuit<F>, pk: Arc<P::ProvingKey>) -> Result<P::Proof, VoteError>;
+}
+
+// 有界并发的证明池
+// 证明任务按提交顺序排队（tokio 信号量是公平的），同时进行的证明不超过 max_concurrent 个。
+// threads_per_proof 大于0时每个并发槽位有自己的 rayon 线程池，单个证明内部（MSM、FFT）
+// 最多使用这么多线程；为0时使用全局线程池。
+// 等待方被丢弃时排队中的任务直接出队，进行中的任务在下一个证明阶段前退出，
+// 槽位在证明线程真正结束后才释放，放弃等待不会让实际占用的内存超出上限。
+// 设置了排队上限（with_max_queued）时，排队已满的新任务立即以 AtCapacity 拒绝，而不是无限排队
+pub struct ProverPool {
+    max_concurrent: usize,
+    max_queued: Option<usize>,
+    permits: Arc<Semaphore>,
+    thread_pools: Arc<Mutex<Vec<rayon::ThreadPool>>>, // 空闲槽位的线程池
+    queued: Arc<AtomicUsize>,
+    running: Arc<AtomicUsize>,
+}
+
+impl ProverPool {
+    pub fn new(max_concurrent: usize, threads_per_proof: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        let thread_pools = if threads_per_proof == 0 {
+            Vec::new()
+        } else {
+            (0..max_concurrent)
+                .map_while(|slot| {
+                    rayon::ThreadPoolBuilder::new()
+                        .num_threads(threads_per_proof)
+                        .thread_name(move |i| format!("zkp-vote-pool-{}-{}", slot, i))
+                        .build()
+                        .map_err(|e| warn!("Failed to build prover pool threads, using the global pool: {}", e))
+                        .ok()
+                })
+                .collect()
+        };
+        Self {
+            max_concurrent,
+            max_queued: None,
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            thread_pools: Arc::new(Mutex::new(thread_pools)),
+            queued: Arc::new(AtomicUsize::new(0)),
+            running: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+    
+    // 设置排队上限：所有槽位都被占用且已有这么多任务在排队时，新任务立即被拒绝（0 表示不排队）
+    pub fn with_max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = Some(max_queued);
+        self
+    }
+    
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+    
+    // 排队上限，未设置时不限
+    pub fn max_queued(&self) -> Option<usize> {
+        self.max_queued
+    }
+    
+    // 排队等待的证明任务数
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+    
+    // 正在进行的证明任务数
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+    
+    // 排队并在阻塞线程池中生成证明
+    pub async fn prove<F: PrimeField, P: ProofSystem<F>>(
+        &self,
+        pk: Arc<P::ProvingKey>,
+        circuit: VoteCircuit<F>,
+    ) -> Result<P::Proof, VoteError> {
+        self.run(move |cancel| {
+            let _enter = info_span!("prove").entered();
+            cancel.check("synthesis")?;
+            VoteSystem::<F, P>::check_constraints(circuit.clone())?;
+            cancel.check("proving")?;
+            P::prove(&pk, circuit, &mut OsRng)
+        })
+        .await
+    }
+    
+    // 排队执行证明任务，任务在阻塞线程池中运行，在其证明阶段之间检查取消标志
+    pub(crate) async fn run<T, J>(&self, job: J) -> Result<T, VoteError>
+    where
+        T: Send + 'static,
+        J: FnOnce(&CancelToken) -> Result<T, VoteError> + Send + 'static,
+    {
+        let cancel = CancelToken::default();
+        let _guard = cancel.drop_guard();
+        
+        // 有空闲槽位时直接开始，否则排队（排队已满时拒绝）
+        let permit = match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let waiting = match self.max_queued {
+                    Some(limit) => Counted::bounded(self.queued.clone(), limit).ok_or_else(|| {
+                        warn!("Prover pool at capacity: {} running, {} queued", self.running(), self.queue_depth());
+                        VoteError::AtCapacity
+                    })?,
+                    None => Counted::new(self.queued.clone()),
+                };
+                let permit = self.permits.clone().acquire_owned().await.map_err(|e| {
+                    error!("Prover pool is closed: {}", e);
+                    VoteError::Internal(format!("Prover pool is closed: {}", e))
+                })?;
+                drop(waiting);
+                permit
+            }
+        };
+        let running = Counted::new(self.running.clone());
+        
+        let thread_pool = self.thread_pools.lock().expect("prover pool lock poisoned").pop();
+        let thread_pools = self.thread_pools.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = match &thread_pool {
+                Some(pool) => pool.install(|| job(&cancel)),
+                None => job(&cancel),
+            };
+            // 先归还线程池再释放槽位，拿到槽位的任务总能拿到线程池
+            if let Some(pool) = thread_pool {
+                thread_pools.lock().expect("prover pool lock poisoned").push(pool);
+            }
+            drop(running);
+            drop(permit);
+            result
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to spawn proof generation task: {:?}", e);
+            VoteError::Internal(format!("{:?}", e))
+        })?
+    }
+}
+
+impl Default for ProverPool {
+    // 默认同时进行的证明数为可用CPU核数，单个证明使用全局线程池
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self::new(cores, 0)
+    }
+}
+
+// 计数守卫：创建时加一，释放时减一
+struct Counted(Arc<AtomicUsize>);
+
+impl Counted {
+    fn new(counter: Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+    
+    // 计数未达上限时加一，否则返回 None
+    fn bounded(counter: Arc<AtomicUsize>, limit: usize) -> Option<Self> {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < limit).then_some(n + 1))
+            .ok()?;
+        Some(Self(counter))
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// 本进程内的证明后端，证明任务经 ProverPool 排队后在tokio阻塞线程池中执行，
+// 等待方被丢弃时在证明阶段之间退出
+pub struct LocalProver<F: PrimeField, P: ProofSystem<F>> {
+    pool: Arc<ProverPool>,
+    _backend: PhantomData<fn() -> (F, P)>,
+}
+
+impl<F: PrimeField, P: ProofSystem<F>> LocalProver<F, P> {
+    pub fn new() -> Self {
+        Self::with_pool(Arc::new(ProverPool::default()))
+    }
+    
+    // 使用指定的证明池（可与其他 LocalProver 共用，合计并发受同一上限约束）
+    pub fn with_pool(pool: Arc<ProverPool>) -> Self {
+        Self { pool, _backend: PhantomData }
+    }
+}
+
+impl<F: PrimeField, P: ProofSystem<F>> Default for LocalProver<F, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: PrimeField, P: ProofSystem<F>> AsyncProver<F, P> for LocalProver<F, P> {
+    async fn prove(&self, circuit: VoteCircuit<F>, pk: Arc<P::ProvingKey>) -> Result<P::Proof, VoteError> {
+        self.pool.prove::<F, P>(pk, circuit).await
+    }
+}
+
+// 远程证明服务（占位实现）
+// 远程服务持有同一份证明密钥，请求中只携带证明密钥指纹以便服务端选择密钥；
+// 注意：请求体包含投票人秘密等私有输入，远程服务必须是受信任的
+pub struct HttpProver {
+    endpoint: String,
+}
+
+impl HttpProver {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into() }
+    }
+
+    // 远程证明服务地址
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    // 构造发送给远程证明服务的请求体（字段元素为十进制字符串）
+    pub fn request_body<F: PrimeField, K: ark_serialize::CanonicalSerialize>(
+        circuit: &VoteCircuit<F>,
+        pk: &K,
+    ) -> Result<serde_json::Value, VoteError> {
+        use crate::field_to_decimal;
+
+        Ok(serde_json::json!({
+            "pk_fingerprint": crate::fingerprint::<F, _>(pk)?.to_string(),
+            "vote": field_to_decimal(circuit.vote),
+            "secret": field_to_decimal(circuit.secret),
+            "randomness": field_to_decimal(circuit.randomness),
+            "election_id": field_to_decimal(circuit.election_id),
+            "sequence": field_to_decimal(circuit.sequence),
+            "epoch": field_to_decimal(circuit.epoch),
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: PrimeField, P: ProofSystem<F>> AsyncProver<F, P> for HttpProver {
+    async fn prove(&self, circuit: VoteCircuit<F>, pk: Arc<P::ProvingKey>) -> Result<P::Proof, VoteError> {
+        let _body = Self::request_body(&circuit, &*pk)?;
+        debug!("Delegating proof generation to {}", self.endpoint);
+        // 尚未接入HTTP客户端
+        Err(VoteError::Proving(
+            format!("remote prover at {} is not available", self.endpoint).into(),
+        ))
+    }
+}
@@ -0,0 +1,278 @@
+// Python 接口（pyo3，供选举管理脚本调用）
+//
+// 构建：maturin develop --features python（模块名 zkp_vote_py，见 pyproject.toml）
+// 测试：pytest python/tests
+//
+// 字段元素在 Python 侧统一为 0x 前缀的十六进制字符串；密钥与证明为 bytes，
+// 证明密钥为密钥文件格式（见 fingerprint::encode_key），验证密钥为 /vk 返回的序列化格式。
+// 错误映射为以 VoteError 变体命名的 Python 异常，均继承自 zkp_vote_py.VoteError。
+
+use crate::client::{self, ClientBackend, ClientProvingKey, ClientVerifyingKey};
+use crate::curve::DefaultField as Fr;
+use crate::fingerprint::encode_key;
+use crate::tally::BallotOpening;
+use crate::{field_to_decimal, field_to_hex, parse_field_element, parse_hex_field, BallotPublicInputs, ProofSystem, VoteCircuit, VoteSystem};
+use ark_std::rand::rngs::OsRng;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+// 以 VoteError 变体命名的异常类型
+macro_rules! vote_exceptions {
+    ($($variant:ident),* $(,)?) => {
+        mod exceptions {
+            pyo3::create_exception!(zkp_vote_py, VoteError, pyo3::exceptions::PyException);
+            $(pyo3::create_exception!(zkp_vote_py, $variant, VoteError);)*
+        }
+
+        // 错误转换为对应的 Python 异常，异常信息带错误码
+        fn to_py_err(e: crate::VoteError) -> PyErr {
+            let message = format!("{}: {}", e.code(), e);
+            match e {
+                $(crate::VoteError::$variant { .. } => exceptions::$variant::new_err(message),)*
+            }
+        }
+
+        fn add_exceptions(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+            m.add("VoteError", py.get_type::<exceptions::VoteError>())?;
+            $(m.add(stringify!($variant), py.get_type::<exceptions::$variant>())?;)*
+            Ok(())
+        }
+    };
+}
+
+vote_exceptions!(
+    InvalidBallot,
+    UnsatisfiedConstraint,
+    Synthesis,
+    Setup,
+    Proving,
+    Verification,
+    Serialization,
+    KeyMismatch,
+    FieldRangeExceeded,
+    DuplicateNullifier,
+    Storage,
+    Aggregation,
+    Decryption,
+    Ceremony,
+    WrongPhase,
+    CommitmentMismatch,
+    BundleIntegrity,
+    InvalidUserId,
+    InvalidProofEncoding,
+    ReplayedProof,
+    UnsupportedVersion,
+    AuditIntegrity,
+    Timeout,
+    AtCapacity,
+    UnknownElection,
+    ConfigMismatch,
+    UnknownJob,
+    UnsupportedBundleVersion,
+    DuplicateCommitment,
+    Internal,
+);
+
+// 十进制字符串（TallyResult 中的格式）转为十六进制
+fn decimal_to_hex(s: &str) -> PyResult<String> {
+    parse_field_element::<Fr>(s).map(field_to_hex).map_err(to_py_err)
+}
+
+// 从字典中取出十六进制字段元素
+fn dict_field(dict: &PyDict, key: &str) -> PyResult<Fr> {
+    let value: String = dict
+        .get_item(key)?
+        .ok_or_else(|| to_py_err(crate::VoteError::InvalidBallot(format!("missing field {:?}", key))))?
+        .extract()?;
+    parse_hex_field(&value).map_err(to_py_err)
+}
+
+fn parse_public_inputs(inputs: &[String]) -> PyResult<BallotPublicInputs<Fr>> {
+    let values = inputs
+        .iter()
+        .map(|s| parse_hex_field::<Fr>(s))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_py_err)?;
+    BallotPublicInputs::from_slice(&values).map_err(to_py_err)
+}
+
+fn decode_proving_key(pk: &[u8]) -> PyResult<ClientProvingKey> {
+    client::decode_proving_key(pk).map_err(to_py_err)
+}
+
+fn decode_verifying_key(vk: &[u8]) -> PyResult<ClientVerifyingKey> {
+    client::decode_verifying_key(vk).map_err(to_py_err)
+}
+
+// 为十六进制选举标识生成新的证明密钥和验证密钥，返回 (pk, vk)，密钥只能用于该选举的选票
+// 这是单方可信设置，正式选举应使用多方仪式（见 ceremony 模块）生成的密钥
+#[pyfunction]
+fn setup(py: Python<'_>, election_id: &str) -> PyResult<(PyObject, PyObject)> {
+    let election_id: Fr = parse_hex_field(election_id).map_err(to_py_err)?;
+    let (_, pk, vk) = py
+        .allow_threads(|| VoteSystem::<Fr, ClientBackend>::setup(election_id, &mut OsRng))
+        .map_err(to_py_err)?;
+    let pk = encode_key::<Fr, _>(&pk).map_err(to_py_err)?;
+    let vk = ClientBackend::serialize_verifying_key(&vk).map_err(to_py_err)?;
+    Ok((PyBytes::new(py, &pk).into(), PyBytes::new(py, &vk).into()))
+}
+
+// 为选票生成证明；ballot 为包含 vote/secret/randomness/election_id/sequence/epoch 的字典，
+// 返回 {"proof": bytes, "public_inputs": [hex, ...]}
+#[pyfunction]
+fn prove_ballot(py: Python<'_>, pk: &[u8], ballot: &PyDict) -> PyResult<PyObject> {
+    let pk = decode_proving_key(pk)?;
+    let circuit = VoteCircuit {
+        vote: dict_field(ballot, "vote")?,
+        secret: dict_field(ballot, "secret")?,
+        randomness: dict_field(ballot, "randomness")?,
+        election_id: dict_field(ballot, "election_id")?,
+        sequence: dict_field(ballot, "sequence")?,
+        epoch: dict_field(ballot, "epoch")?,
+    };
+    let public_inputs: Vec<String> = circuit.public_inputs().to_vec().into_iter().map(field_to_hex).collect();
+    let proof = py
+        .allow_threads(|| VoteSystem::<Fr, ClientBackend>::new().vote(&pk, circuit, &mut OsRng))
+        .map_err(to_py_err)?;
+    let proof = ClientBackend::serialize_proof(&proof).map_err(to_py_err)?;
+
+    let result = PyDict::new(py);
+    result.set_item("proof", PyBytes::new(py, &proof))?;
+    result.set_item("public_inputs", public_inputs)?;
+    Ok(result.into())
+}
+
+// 验证证明，公开输入为十六进制字符串列表
+#[pyfunction]
+fn verify_ballot(py: Python<'_>, vk: &[u8], public_inputs: Vec<String>, proof: &[u8]) -> PyResult<bool> {
+    let vk = decode_verifying_key(vk)?;
+    let public_inputs = parse_public_inputs(&public_inputs)?;
+    let (proof, _) = ClientBackend::deserialize_proof_auto(proof).map_err(to_py_err)?;
+    let outcome = py
+        .allow_threads(|| VoteSystem::<Fr, ClientBackend>::new().verify_ballot(&vk, &public_inputs, &proof))
+        .map_err(to_py_err)?;
+    Ok(outcome.is_valid())
+}
+
+// 计票；ballots 中每项为 prove_ballot 的返回值加上开启值 vote/randomness，
+// 返回 {"counts", "accepted", "rejected": [(index, reason)], "consumed_nullifiers", "commitments"}
+#[pyfunction]
+fn tally(py: Python<'_>, vk: &[u8], ballots: &PyList) -> PyResult<PyObject> {
+    let vk = decode_verifying_key(vk)?;
+    let mut parsed = Vec::with_capacity(ballots.len());
+    for ballot in ballots {
+        let ballot: &PyDict = ballot.downcast()?;
+        let public_inputs: Vec<String> = ballot
+            .get_item("public_inputs")?
+            .ok_or_else(|| to_py_err(crate::VoteError::InvalidBallot("missing field \"public_inputs\"".to_string())))?
+            .extract()?;
+        let proof: &[u8] = ballot
+            .get_item("proof")?
+            .ok_or_else(|| to_py_err(crate::VoteError::InvalidBallot("missing field \"proof\"".to_string())))?
+            .extract()?;
+        let (proof, _) = ClientBackend::deserialize_proof_auto(proof).map_err(to_py_err)?;
+        let opening = BallotOpening { vote: dict_field(ballot, "vote")?, randomness: dict_field(ballot, "randomness")? };
+        parsed.push((parse_public_inputs(&public_inputs)?, proof, opening));
+    }
+    let result = py
+        .allow_threads(|| VoteSystem::<Fr, ClientBackend>::new().tally(&vk, parsed))
+        .map_err(to_py_err)?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("counts", &result.counts)?;
+    dict.set_item("accepted", &result.accepted)?;
+    let rejected: Vec<(usize, String)> = result.rejected.iter().map(|r| (r.index, r.reason.clone())).collect();
+    dict.set_item("rejected", rejected)?;
+    let nullifiers = result.consumed_nullifiers.iter().map(|s| decimal_to_hex(s)).collect::<PyResult<Vec<_>>>()?;
+    dict.set_item("consumed_nullifiers", nullifiers)?;
+    let commitments = result.commitments.iter().map(|s| decimal_to_hex(s)).collect::<PyResult<Vec<_>>>()?;
+    dict.set_item("commitments", commitments)?;
+    Ok(dict.into())
+}
+
+// 选票承诺（十六进制），与计票结果中的 commitments 对应
+#[pyfunction]
+fn ballot_commitment(vote: &str, randomness: &str) -> PyResult<String> {
+    let opening = BallotOpening {
+        vote: parse_hex_field::<Fr>(vote).map_err(to_py_err)?,
+        randomness: parse_hex_field::<Fr>(randomness).map_err(to_py_err)?,
+    };
+    Ok(field_to_hex(opening.commitment()))
+}
+
+// 将证明密钥写入文件（写入前校验格式）
+#[pyfunction]
+fn export_proving_key(pk: &[u8], path: &str) -> PyResult<()> {
+    decode_proving_key(pk)?;
+    std::fs::write(path, pk).map_err(|e| to_py_err(crate::VoteError::Storage(crate::store::StoreError::Io(e))))
+}
+
+// 从文件读取证明密钥（校验格式与曲线后返回原始字节）
+#[pyfunction]
+fn import_proving_key(py: Python<'_>, path: &str) -> PyResult<PyObject> {
+    let pk = std::fs::read(path).map_err(|e| to_py_err(crate::VoteError::Storage(crate::store::StoreError::Io(e))))?;
+    decode_proving_key(&pk)?;
+    Ok(PyBytes::new(py, &pk).into())
+}
+
+// 将验证密钥写入文件（写入前校验格式）
+#[pyfunction]
+fn export_verifying_key(vk: &[u8], path: &str) -> PyResult<()> {
+    decode_verifying_key(vk)?;
+    std::fs::write(path, vk).map_err(|e| to_py_err(crate::VoteError::Storage(crate::store::StoreError::Io(e))))
+}
+
+// 从文件读取验证密钥（校验格式后返回原始字节）
+#[pyfunction]
+fn import_verifying_key(py: Python<'_>, path: &str) -> PyResult<PyObject> {
+    let vk = std::fs::read(path).map_err(|e| to_py_err(crate::VoteError::Storage(crate::store::StoreError::Io(e))))?;
+    decode_verifying_key(&vk)?;
+    Ok(PyBytes::new(py, &vk).into())
+}
+
+// 由证明密钥导出对应的验证密钥
+#[pyfunction]
+fn verifying_key_from_proving_key(py: Python<'_>, pk: &[u8]) -> PyResult<PyObject> {
+    let pk = decode_proving_key(pk)?;
+    let vk = ClientBackend::serialize_verifying_key(&pk.vk).map_err(to_py_err)?;
+    Ok(PyBytes::new(py, &vk).into())
+}
+
+// 检查证明密钥与验证密钥是否配对，不配对时抛出 KeyMismatch（信息指明可疑的一方）
+#[pyfunction]
+fn check_key_pair(pk: &[u8], vk: &[u8]) -> PyResult<()> {
+    let pk = decode_proving_key(pk)?;
+    let vk = decode_verifying_key(vk)?;
+    client::check_key_pair(&pk, &vk).map_err(to_py_err)
+}
+
+// 十进制字符串转为十六进制字段元素（便于与 HTTP 接口返回的十进制格式互转）
+#[pyfunction]
+fn hex_from_decimal(value: &str) -> PyResult<String> {
+    decimal_to_hex(value)
+}
+
+// 十六进制字段元素转为十进制字符串
+#[pyfunction]
+fn decimal_from_hex(value: &str) -> PyResult<String> {
+    parse_hex_field::<Fr>(value).map(field_to_decimal).map_err(to_py_err)
+}
+
+#[pymodule]
+fn zkp_vote_py(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    add_exceptions(py, m)?;
+    m.add_function(wrap_pyfunction!(setup, m)?)?;
+    m.add_function(wrap_pyfunction!(prove_ballot, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_ballot, m)?)?;
+    m.add_function(wrap_pyfunction!(tally, m)?)?;
+    m.add_function(wrap_pyfunction!(ballot_commitment, m)?)?;
+    m.add_function(wrap_pyfunction!(export_proving_key, m)?)?;
+    m.add_function(wrap_pyfunction!(import_proving_key, m)?)?;
+    m.add_function(wrap_pyfunction!(export_verifying_key, m)?)?;
+    m.add_function(wrap_pyfunction!(import_verifying_key, m)?)?;
+    m.add_function(wrap_pyfunction!(verifying_key_from_proving_key, m)?)?;
+    m.add_function(wrap_pyfunction!(check_key_pair, m)?)?;
+    m.add_function(wrap_pyfunction!(hex_from_decimal, m)?)?;
+    m.add_function(wrap_pyfunction!(decimal_from_hex, m)?)?;
+    Ok(())
+}
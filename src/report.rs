@@ -0,0 +1,42 @@
+// 证明生成与验证报告
+//
+// 供运维观察不同电路配置下的证明耗时、证明体积和约束规模。
+// 报告中不包含任何私有输入。
+
+use ark_ff::PrimeField;
+use ark_relations::r1cs::ConstraintSystemRef;
+use std::time::Duration;
+
+// 证明生成报告
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ProofReport {
+    pub synthesis_time: Duration,      // 电路综合与约束检查耗时
+    pub proving_time: Duration,        // 后端生成证明耗时
+    pub proof_size: usize,             // 证明序列化后的字节数（压缩格式）
+    pub num_constraints: usize,        // 约束数量
+    pub estimated_memory_bytes: usize, // 证明过程峰值内存的粗略估计
+}
+
+// 验证报告
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct VerifyReport {
+    pub verification_time: Duration, // 后端验证耗时
+    pub num_public_inputs: usize,    // 公开输入数量
+    pub proof_size: usize,           // 证明序列化后的字节数（压缩格式）
+}
+
+// 粗略估计证明过程的峰值内存
+// 计入约束矩阵的非零项、变量赋值，以及 QAP 计算中三组求值域向量；不含证明密钥本身
+// 会内联约束系统中的线性组合，调用后不应再向其添加约束
+pub(crate) fn estimate_prover_memory<F: PrimeField>(cs: &ConstraintSystemRef<F>) -> usize {
+    cs.finalize();
+    let field_size = std::mem::size_of::<F>();
+    let num_variables = cs.num_instance_variables() + cs.num_witness_variables();
+    let domain_size = (cs.num_constraints() + cs.num_instance_variables()).next_power_of_two();
+    let non_zero = cs
+        .to_matrices()
+        .map(|m| m.a_num_non_zero + m.b_num_non_zero + m.c_num_non_zero)
+        .unwrap_or(0);
+
+    non_zero * (field_size + std::mem::size_of::<usize>()) + num_variables * field_size + 3 * domain_size * field_size
+}
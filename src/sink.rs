@@ -0,0 +1,149 @@
+// 已受理证明的归档
+//
+// 服务端可选地把每张已受理选票的证明推送到外部存储归档。归档在后台进行，失败时按退避间隔重试，
+// 重试用尽后只记录错误日志，不影响投票响应。内置文件系统实现；S3 兼容的对象存储只有接口骨架，尚未接入HTTP客户端。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+// 归档失败
+#[derive(Debug, thiserror::Error)]
+pub enum SinkError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid object id: {0}")]
+    InvalidId(String),
+    #[error("Sink unavailable: {0}")]
+    Unavailable(String),
+}
+
+// 证明归档目标
+#[async_trait::async_trait]
+pub trait ProofSink: Send + Sync + 'static {
+    // 以 id 为键写入证明的规范编码，同一 id 重复写入时覆盖
+    async fn put(&self, id: &str, proof: &[u8]) -> Result<(), SinkError>;
+}
+
+// 对象键只允许十六进制等安全字符，避免路径穿越
+fn check_id(id: &str) -> Result<(), SinkError> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+        return Err(SinkError::InvalidId(id.to_string()));
+    }
+    Ok(())
+}
+
+// 文件系统归档：每个证明写入 <目录>/<id>.proof
+// 先写临时文件再改名，读取方不会看到写了一半的文件
+pub struct FsSink {
+    dir: PathBuf,
+}
+
+impl FsSink {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // 证明在归档目录中的路径
+    pub fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.proof", id))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[async_trait::async_trait]
+impl ProofSink for FsSink {
+    async fn put(&self, id: &str, proof: &[u8]) -> Result<(), SinkError> {
+        check_id(id)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let tmp = self.dir.join(format!(".{}.proof.tmp", id));
+        tokio::fs::write(&tmp, proof).await?;
+        tokio::fs::rename(&tmp, self.path(id)).await?;
+        Ok(())
+    }
+}
+
+// S3 兼容对象存储归档：证明以 PUT <endpoint>/<bucket>/<prefix><id>.proof 上传
+pub struct S3Sink {
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Sink {
+    pub fn new(endpoint: impl Into<String>, bucket: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), bucket: bucket.into(), prefix: String::new() }
+    }
+
+    // 对象键前缀（如 "election-2024/"）
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    // 证明对象的URL
+    pub fn object_url(&self, id: &str) -> String {
+        format!("{}/{}/{}{}.proof", self.endpoint.trim_end_matches('/'), self.bucket, self.prefix, id)
+    }
+}
+
+#[async_trait::async_trait]
+impl ProofSink for S3Sink {
+    async fn put(&self, id: &str, proof: &[u8]) -> Result<(), SinkError> {
+        check_id(id)?;
+        debug!("Uploading {} byte proof to {}", proof.len(), self.object_url(id));
+        // 尚未接入HTTP客户端
+        Err(SinkError::Unavailable(format!("object storage at {} is not available", self.endpoint)))
+    }
+}
+
+// 归档重试策略：首次失败后等待 initial_backoff，之后每次加倍，最多尝试 max_attempts 次
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 5, initial_backoff: Duration::from_millis(200) }
+    }
+}
+
+// 在后台归档证明，不等待结果
+pub fn spawn_archive(sink: Arc<dyn ProofSink>, policy: RetryPolicy, id: String, proof: Vec<u8>) {
+    tokio::spawn(async move {
+        archive(&*sink, policy, &id, &proof).await;
+    });
+}
+
+// 按重试策略归档证明，返回是否成功
+pub async fn archive(sink: &dyn ProofSink, policy: RetryPolicy, id: &str, proof: &[u8]) -> bool {
+    let mut backoff = policy.initial_backoff;
+    for attempt in 1..=policy.max_attempts.max(1) {
+        match sink.put(id, proof).await {
+            Ok(()) => {
+                debug!("Archived proof {} (attempt {})", id, attempt);
+                return true;
+            }
+            // 对象键无效时重试也不会成功
+            Err(e @ SinkError::InvalidId(_)) => {
+                error!("Failed to archive proof {}: {}", id, e);
+                return false;
+            }
+            Err(e) if attempt < policy.max_attempts => {
+                warn!("Failed to archive proof {} (attempt {}): {}, retrying in {:?}", id, attempt, e, backoff);
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+            Err(e) => {
+                error!("Failed to archive proof {} after {} attempts: {}", id, attempt, e);
+            }
+        }
+    }
+    false
+}
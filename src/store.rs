@@ -0,0 +1,354 @@
+// 防重标识存储
+//
+// Web层通过 NullifierBackend 记录已使用的防重标识及各防重标识已接受的最大选票序号，不同部署可按持久化需求选择后端；
+// 内置内存后端和文件后端，Redis/SQL等后端只需实现该trait即可接入。
+// NullifierSet 在此之上提供可并发插入、重复时报错的防重标识集合，持久化由 NullifierStore 完成。
+
+use crate::curve::DefaultField as Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+// 存储错误
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Corrupt nullifier store: {0}")]
+    Corrupt(String),
+}
+
+// 防重标识已被使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Nullifier has already been used")]
+pub struct DuplicateNullifier;
+
+// 插入防重标识集合失败
+#[derive(Debug, thiserror::Error)]
+pub enum NullifierSetError {
+    #[error(transparent)]
+    Duplicate(#[from] DuplicateNullifier),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+// 防重标识存储后端
+// 重新投票时同一防重标识的选票序号必须严格递增，后端须与防重标识一起记录已接受的最大序号，
+// 重启后仍能拒绝序号不大于已接受序号的选票
+pub trait NullifierBackend: Send + Sync + 'static {
+    // 该防重标识已接受的最大选票序号，未记录时为 None
+    fn sequence(&self, n: &Fr) -> Option<u64>;
+
+    // 记录防重标识及其已接受的选票序号（已记录更大或相同的序号时不变）
+    fn record(&mut self, n: Fr, sequence: u64) -> Result<(), StoreError>;
+
+    // 是否已记录该防重标识
+    fn contains(&self, n: &Fr) -> bool {
+        self.sequence(n).is_some()
+    }
+
+    // 以序号0记录防重标识
+    fn insert(&mut self, n: Fr) -> Result<(), StoreError> {
+        self.record(n, 0)
+    }
+
+    // 打开某场选举的防重标识存储：与本后端同一种持久化方式，按选举标识与其他选举分开存放，
+    // 同一选举标识重新打开时载入之前记录的防重标识
+    fn open_election(&self, election_id: &str) -> Result<Self, StoreError>
+    where
+        Self: Sized;
+}
+
+// 内存后端（进程重启后丢失）
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    nullifiers: HashMap<Fr, u64>,
+}
+
+impl NullifierBackend for MemoryBackend {
+    fn sequence(&self, n: &Fr) -> Option<u64> {
+        self.nullifiers.get(n).copied()
+    }
+
+    fn record(&mut self, n: Fr, sequence: u64) -> Result<(), StoreError> {
+        let last = self.nullifiers.entry(n).or_insert(sequence);
+        *last = (*last).max(sequence);
+        Ok(())
+    }
+
+    fn open_election(&self, _election_id: &str) -> Result<Self, StoreError> {
+        Ok(Self::default())
+    }
+}
+
+// 文件后端
+// 每行一条记录：十六进制编码的防重标识、空格、十进制的选票序号（以换行结束的行才是完整记录），
+// 只追加写入，打开时全部载入内存，同一防重标识取最大的序号；没有序号的旧格式记录按序号0载入
+// 写入中途断电可能在文件末尾留下不完整或无法解析的记录：打开时丢弃这段尾部并截断文件，
+// 之前的完整记录全部保留；无法解析的记录之后仍有完整记录时视为文件损坏
+#[derive(Debug)]
+pub struct FileBackend {
+    path: PathBuf,
+    file: File,
+    nullifiers: HashMap<Fr, u64>,
+}
+
+impl FileBackend {
+    // 打开（不存在时创建）存储文件并载入已有记录
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+
+        let mut nullifiers = HashMap::<Fr, u64>::new();
+        let mut valid_len = 0; // 最后一条完整记录之后的偏移
+        let mut bad_record = None; // 第一条无法解析的记录（行号及原因）
+        let mut offset = 0;
+        for (i, line) in contents.split_inclusive(|&b| b == b'\n').enumerate() {
+            offset += line.len();
+            let Some(record) = line.strip_suffix(b"\n") else {
+                // 没有换行结束的最后一行是未写完的记录
+                bad_record.get_or_insert((i + 1, "record is not newline-terminated".to_string()));
+                break;
+            };
+            if record.is_empty() {
+                continue;
+            }
+            match Self::parse_record(record) {
+                Ok((n, sequence)) => {
+                    if let Some((line, reason)) = bad_record {
+                        return Err(StoreError::Corrupt(format!("line {}: {}", line, reason)));
+                    }
+                    let last = nullifiers.entry(n).or_insert(sequence);
+                    *last = (*last).max(sequence);
+                    valid_len = offset;
+                }
+                Err(reason) => {
+                    bad_record.get_or_insert((i + 1, reason));
+                }
+            }
+        }
+
+        if let Some((line, reason)) = bad_record {
+            warn!(
+                "Dropping {} bytes of incomplete records at the end of nullifier store {} (line {}: {}); recovered {} nullifiers",
+                contents.len() - valid_len,
+                path.display(),
+                line,
+                reason,
+                nullifiers.len()
+            );
+            // 截断尾部，之后追加的记录从完整记录之后开始
+            file.set_len(valid_len as u64)?;
+            file.sync_data()?;
+        }
+
+        Ok(Self { path, file, nullifiers })
+    }
+
+    // 解析一行记录（不含换行），返回防重标识及选票序号
+    fn parse_record(record: &[u8]) -> Result<(Fr, u64), String> {
+        let (nullifier, sequence) = match record.iter().position(|&b| b == b' ') {
+            Some(i) => (&record[..i], Some(&record[i + 1..])),
+            None => (record, None),
+        };
+        let bytes = hex::decode(nullifier).map_err(|e| e.to_string())?;
+        let n = Fr::deserialize_compressed(bytes.as_slice()).map_err(|e| e.to_string())?;
+        let sequence = match sequence {
+            Some(digits) => std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| digits.parse().ok())
+                .ok_or_else(|| "invalid ballot sequence".to_string())?,
+            None => 0,
+        };
+        Ok((n, sequence))
+    }
+
+    // 存储文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 某场选举的存储文件路径：与本文件同目录，文件名加上选举标识的 BLAKE3 摘要（如 nullifiers-<摘要>.log），
+    // 选举标识可以是任意字符串，摘要保证文件名合法且不同选举互不冲突
+    pub fn election_path(&self, election_id: &str) -> PathBuf {
+        let digest = blake3::hash(election_id.as_bytes()).to_hex();
+        let stem = self.path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+        let file_name = match self.path.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, digest, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, digest),
+        };
+        self.path.with_file_name(file_name)
+    }
+}
+
+impl NullifierBackend for FileBackend {
+    fn sequence(&self, n: &Fr) -> Option<u64> {
+        self.nullifiers.get(n).copied()
+    }
+
+    fn record(&mut self, n: Fr, sequence: u64) -> Result<(), StoreError> {
+        if self.nullifiers.get(&n).is_some_and(|&last| last >= sequence) {
+            return Ok(());
+        }
+        let mut bytes = Vec::new();
+        n.serialize_compressed(&mut bytes)
+            .map_err(|e| StoreError::Corrupt(e.to_string()))?;
+        writeln!(self.file, "{} {}", hex::encode(bytes), sequence)?;
+        // 落盘后再更新内存，保证重启后不会丢失已确认的记录
+        self.file.sync_data()?;
+        self.nullifiers.insert(n, sequence);
+        Ok(())
+    }
+
+    fn open_election(&self, election_id: &str) -> Result<Self, StoreError> {
+        Self::open(self.election_path(election_id))
+    }
+}
+
+// 防重标识集合的持久化后端
+pub trait NullifierStore: Send + 'static {
+    // 载入已持久化的防重标识
+    fn load(&mut self) -> Result<Vec<Fr>, StoreError>;
+
+    // 持久化新插入的防重标识（集合保证不会重复调用）
+    fn append(&mut self, n: &Fr) -> Result<(), StoreError>;
+
+    // 打开某场选举的持久化后端，见 NullifierBackend::open_election
+    fn open_election(&self, election_id: &str) -> Result<Self, StoreError>
+    where
+        Self: Sized;
+}
+
+// 不做持久化的默认后端（进程重启后丢失）
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryStore;
+
+impl NullifierStore for MemoryStore {
+    fn load(&mut self) -> Result<Vec<Fr>, StoreError> {
+        Ok(Vec::new())
+    }
+
+    fn append(&mut self, _n: &Fr) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    fn open_election(&self, _election_id: &str) -> Result<Self, StoreError> {
+        Ok(MemoryStore)
+    }
+}
+
+impl NullifierStore for FileBackend {
+    fn load(&mut self) -> Result<Vec<Fr>, StoreError> {
+        Ok(self.nullifiers.keys().copied().collect())
+    }
+
+    fn append(&mut self, n: &Fr) -> Result<(), StoreError> {
+        NullifierBackend::insert(self, *n)
+    }
+
+    fn open_election(&self, election_id: &str) -> Result<Self, StoreError> {
+        NullifierBackend::open_election(self, election_id)
+    }
+}
+
+// NullifierSet 可直接作为 Web 层的防重标识存储后端（AppState::with_nullifier_backend）
+// 后端接口的记录是幂等的（重新投票沿用已记录的防重标识），重复检查由 Web 层在同一把写锁内完成；
+// NullifierStore 只持久化防重标识，选票序号只保存在内存中，重启后载入的防重标识按序号0处理
+impl<S: NullifierStore> NullifierBackend for NullifierSet<S> {
+    fn sequence(&self, n: &Fr) -> Option<u64> {
+        self.lock().0.get(&canonical_key(n)).copied()
+    }
+
+    fn record(&mut self, n: Fr, sequence: u64) -> Result<(), StoreError> {
+        let key = canonical_key(&n);
+        let mut inner = self.lock();
+        let (keys, store) = &mut *inner;
+        match keys.get_mut(&key) {
+            Some(last) => *last = (*last).max(sequence),
+            None => {
+                store.append(&n)?;
+                keys.insert(key, sequence);
+            }
+        }
+        Ok(())
+    }
+
+    fn open_election(&self, election_id: &str) -> Result<Self, StoreError> {
+        let store = self.lock().1.open_election(election_id)?;
+        Self::with_store(store)
+    }
+}
+
+// 防重标识的规范编码：约化后整数的小端字节
+// 同一字段元素只有一种编码，避免以不同表示绕过重复检查
+fn canonical_key(n: &Fr) -> Vec<u8> {
+    n.into_bigint().to_bytes_le()
+}
+
+// 已使用的防重标识集合
+// 检查、持久化和记录在同一把锁内完成，多个处理器并发插入同一防重标识时只有一个成功
+#[derive(Debug)]
+pub struct NullifierSet<S: NullifierStore = MemoryStore> {
+    inner: Mutex<(HashMap<Vec<u8>, u64>, S)>, // 规范编码 -> 已接受的最大选票序号
+}
+
+impl NullifierSet<MemoryStore> {
+    // 创建空的内存集合
+    pub fn new() -> Self {
+        Self { inner: Mutex::new((HashMap::new(), MemoryStore)) }
+    }
+}
+
+impl Default for NullifierSet<MemoryStore> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: NullifierStore> NullifierSet<S> {
+    // 以持久化后端创建集合，并载入后端中已有的防重标识
+    pub fn with_store(mut store: S) -> Result<Self, StoreError> {
+        let keys = store.load()?.iter().map(|n| (canonical_key(n), 0)).collect();
+        Ok(Self { inner: Mutex::new((keys, store)) })
+    }
+
+    // 记录防重标识，已存在时返回 DuplicateNullifier
+    pub fn insert(&self, n: Fr) -> Result<(), NullifierSetError> {
+        let key = canonical_key(&n);
+        let mut inner = self.lock();
+        let (keys, store) = &mut *inner;
+        if keys.contains_key(&key) {
+            return Err(DuplicateNullifier.into());
+        }
+        // 持久化成功后再记录，保证内存与后端一致
+        store.append(&n)?;
+        keys.insert(key, 0);
+        Ok(())
+    }
+
+    // 是否已记录该防重标识
+    pub fn contains(&self, n: &Fr) -> bool {
+        self.lock().0.contains_key(&canonical_key(n))
+    }
+
+    // 已记录的防重标识数量
+    pub fn len(&self) -> usize {
+        self.lock().0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 其他线程持锁时崩溃不会破坏集合（插入只在持久化成功后才修改内存），因此忽略锁中毒
+    fn lock(&self) -> std::sync::MutexGuard<'_, (HashMap<Vec<u8>, u64>, S)> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
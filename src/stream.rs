@@ -0,0 +1,147 @@
+// 证明信封流的逐条验证
+//
+// 审计方拿到的导出文件可能有数GB，不能整体读入内存。流由若干条记录首尾相接组成，
+// 每条记录为 长度（u32，小端序） || 信封的二进制编码（见 ProofEnvelope::to_bytes）。
+// 长度前缀完好时，单条记录损坏不影响之后的记录；长度前缀本身不完整或超出上限时无法再定位下一条记录，流就此结束。
+
+use crate::{BallotPublicInputs, KeyFingerprint, ProofEnvelope, ProofSystem, VerificationOutcome, VoteError, VoteSystem};
+use ark_ff::PrimeField;
+use ark_serialize::SerializationError;
+use std::io::{BufRead, Write};
+use tracing::{error, warn};
+
+// 单条记录的最大字节数，超出时视为长度前缀损坏（正常信封不足 1KB）
+pub const MAX_ENVELOPE_RECORD_LEN: usize = 1 << 20;
+
+// 单张选票的验证结论
+#[derive(Debug)]
+pub struct BallotVerdict<F: PrimeField> {
+    pub index: u64,  // 记录序号（从0开始）
+    pub offset: u64, // 记录（含长度前缀）在流中的起始字节偏移，用于定位出错的记录
+    pub public_inputs: Option<BallotPublicInputs<F>>, // 记录无法解码时为 None
+    pub result: Result<VerificationOutcome, VoteError>, // 验证结果；信封损坏或验证密钥不符时为错误
+}
+
+impl<F: PrimeField> BallotVerdict<F> {
+    // 记录解码成功且证明通过验证
+    pub fn is_valid(&self) -> bool {
+        matches!(self.result, Ok(VerificationOutcome::Valid))
+    }
+}
+
+// 向流中追加一条记录
+pub fn write_envelope<F: PrimeField, P: ProofSystem<F>, W: Write>(
+    writer: &mut W,
+    envelope: &ProofEnvelope<F, P>,
+) -> Result<(), VoteError> {
+    let bytes = envelope.to_bytes()?;
+    writer
+        .write_all(&(bytes.len() as u32).to_le_bytes())
+        .and_then(|_| writer.write_all(&bytes))
+        .map_err(|e| VoteError::Serialization(SerializationError::IoError(e)))
+}
+
+// 逐条读取并验证信封的迭代器（见 VoteSystem::verify_stream）
+// 每条记录产出一个 BallotVerdict；读取失败（I/O 错误、记录被截断、长度前缀超出上限）时产出 Err 并结束
+pub struct EnvelopeStream<'a, F: PrimeField, P: ProofSystem<F>, R: BufRead> {
+    system: &'a VoteSystem<F, P>,
+    vk: &'a P::VerifyingKey,
+    vk_fingerprint: Result<KeyFingerprint, Option<VoteError>>, // 计算指纹失败时，错误在第一次迭代时产出
+    reader: R,
+    offset: u64,
+    index: u64,
+    done: bool,
+}
+
+impl<'a, F: PrimeField, P: ProofSystem<F>, R: BufRead> EnvelopeStream<'a, F, P, R> {
+    pub(crate) fn new(system: &'a VoteSystem<F, P>, vk: &'a P::VerifyingKey, reader: R) -> Self {
+        Self {
+            system,
+            vk,
+            // 验证密钥的指纹只算一次，不随每条记录重新序列化验证密钥
+            vk_fingerprint: crate::fingerprint::<F, _>(vk).map_err(Some),
+            reader,
+            offset: 0,
+            index: 0,
+            done: false,
+        }
+    }
+
+    // 读取下一条记录，流在记录边界处结束时返回 None
+    fn read_record(&mut self) -> Result<Option<Vec<u8>>, VoteError> {
+        let io = |e| VoteError::Serialization(SerializationError::IoError(e));
+        if self.reader.fill_buf().map_err(io)?.is_empty() {
+            return Ok(None);
+        }
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len).map_err(io)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_ENVELOPE_RECORD_LEN {
+            return Err(VoteError::Serialization(SerializationError::InvalidData));
+        }
+        let mut record = vec![0u8; len];
+        self.reader.read_exact(&mut record).map_err(io)?;
+        self.offset += 4 + len as u64;
+        Ok(Some(record))
+    }
+
+    // 解码并验证一条记录
+    fn verify_record(
+        &self,
+        record: &[u8],
+        expected: KeyFingerprint,
+    ) -> (Option<BallotPublicInputs<F>>, Result<VerificationOutcome, VoteError>) {
+        let envelope = match ProofEnvelope::<F, P>::from_bytes(record) {
+            Ok(envelope) => envelope,
+            Err(e) => return (None, Err(e)),
+        };
+        let result = if envelope.vk_fingerprint != expected {
+            Err(VoteError::KeyMismatch(format!(
+                "proof was generated for verifying key {}, got {}",
+                envelope.vk_fingerprint, expected
+            )))
+        } else {
+            self.system.verify_ballot(self.vk, &envelope.public_inputs, &envelope.proof)
+        };
+        (Some(envelope.public_inputs), result)
+    }
+}
+
+impl<F: PrimeField, P: ProofSystem<F>, R: BufRead> Iterator for EnvelopeStream<'_, F, P, R> {
+    type Item = Result<BallotVerdict<F>, VoteError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let expected = match &mut self.vk_fingerprint {
+            Ok(fingerprint) => *fingerprint,
+            Err(e) => {
+                self.done = true;
+                return e.take().map(Err);
+            }
+        };
+
+        let offset = self.offset;
+        let record = match self.read_record() {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => {
+                error!("Proof envelope stream unreadable at byte offset {}: {}", offset, e);
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        let index = self.index;
+        self.index += 1;
+
+        let (public_inputs, result) = self.verify_record(&record, expected);
+        if let Err(e) = &result {
+            warn!("Proof envelope {} at byte offset {} rejected: {}", index, offset, e);
+        }
+        Some(Ok(BallotVerdict { index, offset, public_inputs, result }))
+    }
+}
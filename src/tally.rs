@@ -0,0 +1,403 @@
+// 计票正确性证明
+//
+// 开票后由计票方公布各选项的总票数，并给出一个零知识证明：
+// 公布的总票数恰好等于全部选票承诺所对应投票值之和，且不泄露任何单张选票。
+
+use crate::{check_public_input_count, ProofSystem, PublicInputs, VoteError};
+use ark_ff::PrimeField;
+use std::collections::HashMap;
+use ark_relations::lc;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+use ark_std::rand::{CryptoRng, RngCore};
+use tracing::{debug, error, info, warn};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// 选项数量（当前电路只支持 0 / 1 两个选项）
+pub const NUM_OPTIONS: usize = 2;
+
+// 选票开启值（计票方持有，不公开）
+#[derive(Clone, Debug)]
+pub struct BallotOpening<F: PrimeField> {
+    pub vote: F,       // 投票选择
+    pub randomness: F, // 承诺随机值
+}
+
+impl<F: PrimeField> BallotOpening<F> {
+    // 计算该选票的公开承诺
+    pub fn commitment(&self) -> F {
+        commit_ballot(self.vote, self.randomness)
+    }
+}
+
+impl<F: PrimeField> Zeroize for BallotOpening<F> {
+    fn zeroize(&mut self) {
+        self.vote.zeroize();
+        self.randomness.zeroize();
+    }
+}
+
+impl<F: PrimeField> Drop for BallotOpening<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: PrimeField> ZeroizeOnDrop for BallotOpening<F> {}
+
+// 生成 MiMC 轮常数（由固定域分隔串派生，保证证明方和验证方一致）
+fn mimc_constants<F: PrimeField>() -> Vec<F> {
+    (0..crate::curve::params::<F>().mimc_rounds)
+        .map(|i| {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(b"zkp-vote/tally/mimc");
+            hasher.update(&(i as u64).to_le_bytes());
+            F::from_le_bytes_mod_order(hasher.finalize().as_bytes())
+        })
+        .collect()
+}
+
+// 计算选票承诺：以随机值为密钥、对投票值做 MiMC-5 加密
+pub fn commit_ballot<F: PrimeField>(vote: F, randomness: F) -> F {
+    let mut x = vote;
+    for c in mimc_constants::<F>() {
+        let t = x + randomness + c;
+        let t2 = t.square();
+        x = t2.square() * t;
+    }
+    x + randomness
+}
+
+// 在电路内计算选票承诺，返回承诺的线性组合及其值
+pub(crate) fn enforce_commit_ballot<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    vote: LinearCombination<F>,
+    vote_val: F,
+    randomness: Variable,
+    randomness_val: F,
+) -> Result<(LinearCombination<F>, F), SynthesisError> {
+    let mut x_val = vote_val;
+    let mut x = vote;
+    for c in mimc_constants::<F>() {
+        let t_val = x_val + randomness_val + c;
+        let t = x.clone() + randomness + (c, Variable::One);
+
+        let t2_val = t_val.square();
+        let t2_var = cs.new_witness_variable(|| Ok(t2_val))?;
+        cs.enforce_constraint(t.clone(), t.clone(), lc!() + t2_var)?;
+
+        let t4_val = t2_val.square();
+        let t4_var = cs.new_witness_variable(|| Ok(t4_val))?;
+        cs.enforce_constraint(lc!() + t2_var, lc!() + t2_var, lc!() + t4_var)?;
+
+        x_val = t4_val * t_val;
+        let x_var = cs.new_witness_variable(|| Ok(x_val))?;
+        cs.enforce_constraint(lc!() + t4_var, t, lc!() + x_var)?;
+        x = lc!() + x_var;
+    }
+    Ok((x + randomness, x_val + randomness_val))
+}
+
+// 计票电路
+// 公开输入顺序：[承诺_0, ..., 承诺_{n-1}, 选项0总数, 选项1总数]
+#[derive(Clone, Debug)]
+pub struct TallyCircuit<F: PrimeField> {
+    pub ballots: Vec<BallotOpening<F>>, // 选票开启值（私有输入）
+    pub commitments: Vec<F>,            // 选票承诺（公开输入）
+    pub totals: [F; NUM_OPTIONS],       // 各选项总票数（公开输入）
+}
+
+impl<F: PrimeField> TallyCircuit<F> {
+    // 构造仅用于密钥生成的空电路
+    fn blank(num_ballots: usize) -> Self {
+        let ballot = BallotOpening { vote: F::zero(), randomness: F::zero() };
+        Self {
+            commitments: vec![ballot.commitment(); num_ballots],
+            ballots: vec![ballot; num_ballots],
+            totals: [F::from(num_ballots as u64), F::zero()],
+        }
+    }
+}
+
+// 选票开启值在各自被丢弃时清零
+impl<F: PrimeField> ZeroizeOnDrop for TallyCircuit<F> {}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for TallyCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.ballots.len() != self.commitments.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // 先分配公开输入，保证顺序与 tally_public_inputs 一致
+        let mut commitment_vars = Vec::with_capacity(self.commitments.len());
+        for c in &self.commitments {
+            commitment_vars.push(cs.new_input_variable(|| Ok(*c))?);
+        }
+        let total0_var = cs.new_input_variable(|| Ok(self.totals[0]))?;
+        let total1_var = cs.new_input_variable(|| Ok(self.totals[1]))?;
+
+        let mut vote_sum: LinearCombination<F> = lc!();
+
+        for (ballot, commitment_var) in self.ballots.iter().zip(commitment_vars) {
+            let vote_var = cs.new_witness_variable(|| Ok(ballot.vote))?;
+            let randomness_var = cs.new_witness_variable(|| Ok(ballot.randomness))?;
+
+            // 约束 vote * (vote - 1) = 0
+            cs.enforce_constraint(
+                lc!() + vote_var,
+                lc!() + vote_var - Variable::One,
+                lc!(),
+            )?;
+
+            // 在电路内重新计算承诺并与公开承诺比对
+            let (commitment, _) = enforce_commit_ballot(
+                &cs,
+                lc!() + vote_var,
+                ballot.vote,
+                randomness_var,
+                ballot.randomness,
+            )?;
+            cs.enforce_constraint(commitment, lc!() + Variable::One, lc!() + commitment_var)?;
+
+            vote_sum = vote_sum + vote_var;
+        }
+
+        // 约束 sum(vote) = 选项1总数
+        cs.enforce_constraint(vote_sum.clone(), lc!() + Variable::One, lc!() + total1_var)?;
+
+        // 约束 n - sum(vote) = 选项0总数
+        let n = F::from(self.ballots.len() as u64);
+        cs.enforce_constraint(
+            lc!() + (n, Variable::One) - vote_sum,
+            lc!() + Variable::One,
+            lc!() + total0_var,
+        )?;
+
+        Ok(())
+    }
+}
+
+// 可重新投票场景下筛选计票选票：同一防重标识只保留序号最大的选票
+// 输入为已验证选票的公开输入及其附带数据，输出保持各投票人首次出现的顺序
+pub fn select_latest_ballots<F: PrimeField, T>(ballots: Vec<(PublicInputs<F>, T)>) -> Vec<T> {
+    let mut order: Vec<F> = Vec::new();
+    let mut latest: HashMap<F, (F, T)> = HashMap::new();
+
+    for (inputs, ballot) in ballots {
+        match latest.get(&inputs.nullifier) {
+            Some((sequence, _)) if sequence.into_bigint() >= inputs.sequence.into_bigint() => {
+                debug!("Discarding superseded ballot");
+            }
+            existing => {
+                if existing.is_none() {
+                    order.push(inputs.nullifier);
+                }
+                latest.insert(inputs.nullifier, (inputs.sequence, ballot));
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|nullifier| latest.remove(&nullifier).map(|(_, ballot)| ballot))
+        .collect()
+}
+
+// 计票和复核共用的重新投票规则：已验证选票（下标、公开输入、附带数据）中同一防重标识只保留序号最大的一张，
+// 返回按下标排序的保留选票；其余选票记入拒绝列表，序号与保留的选票相同时为 duplicate_nullifier，否则为 superseded_ballot
+pub(crate) fn keep_latest_ballots<F: PrimeField, T>(
+    result: &mut TallyResult,
+    valid: Vec<(usize, PublicInputs<F>, T)>,
+) -> Vec<(usize, PublicInputs<F>, T)> {
+    let keys: Vec<(usize, F, F)> = valid.iter().map(|(index, inputs, _)| (*index, inputs.nullifier, inputs.sequence)).collect();
+    let mut latest =
+        select_latest_ballots(valid.into_iter().map(|(index, inputs, ballot)| (inputs.clone(), (index, inputs, ballot))).collect());
+    latest.sort_by_key(|(index, ..)| *index);
+    let kept: HashMap<F, (usize, F)> =
+        latest.iter().map(|(index, inputs, _)| (inputs.nullifier, (*index, inputs.sequence))).collect();
+    for (index, nullifier, sequence) in keys {
+        match kept.get(&nullifier) {
+            Some(&(kept_index, _)) if kept_index == index => {}
+            Some(&(_, kept_sequence)) if kept_sequence == sequence => {
+                result.reject_invalid(index, VoteError::DuplicateNullifier.code());
+            }
+            _ => result.reject_invalid(index, "superseded_ballot"),
+        }
+    }
+    result.rejected.sort_by_key(|rejected| rejected.index);
+    latest
+}
+
+// 选票被拒绝的类别
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionKind {
+    Invalid, // 选票无效：证明不成立、重复投票等（投票人一方的问题）
+    Error,   // 验证出错：证明无法解码、密钥不匹配等（数据损坏或计票方一方的问题）
+}
+
+// 被拒绝的选票、类别及原因（无效时为 InvalidReason 或计票规则的原因码，出错时为 VoteError::code() 的错误码）
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RejectedBallot {
+    pub index: usize,
+    pub kind: RejectionKind,
+    pub reason: String,
+}
+
+// 计票结果（字段元素以十进制字符串表示，可直接公布）
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TallyResult {
+    pub counts: Vec<u64>,                 // 各选项票数
+    pub accepted: Vec<usize>,             // 计入的选票下标
+    pub rejected: Vec<RejectedBallot>,    // 未计入的选票下标及原因
+    pub consumed_nullifiers: Vec<String>, // 计入选票使用的防重标识
+    pub commitments: Vec<String>,         // 计入选票的承诺，与 counts 一起作为计票证明的公开输入
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub abstained: u64, // 弃权票数（只计入投票率）
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub spoiled: u64, // 废票数（只计入投票率）
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl TallyResult {
+    pub(crate) fn new() -> Self {
+        Self::with_candidates(NUM_OPTIONS)
+    }
+
+    pub(crate) fn with_candidates(num_candidates: usize) -> Self {
+        Self { counts: vec![0; num_candidates], ..Default::default() }
+    }
+
+    // 投票率：计入的选票总数，包括弃权票和废票
+    pub fn turnout(&self) -> u64 {
+        self.accepted.len() as u64
+    }
+
+    pub(crate) fn reject_invalid(&mut self, index: usize, reason: &str) {
+        debug!("Rejected invalid ballot {}: {}", index, reason);
+        self.rejected.push(RejectedBallot { index, kind: RejectionKind::Invalid, reason: reason.to_string() });
+    }
+
+    pub(crate) fn reject_error(&mut self, index: usize, e: &VoteError) {
+        warn!("Failed to verify ballot {}: {}", index, e);
+        self.rejected.push(RejectedBallot { index, kind: RejectionKind::Error, reason: e.code().to_string() });
+    }
+}
+
+// 计票证明
+#[derive(Clone, Debug)]
+pub struct TallyProof<F: PrimeField, P: ProofSystem<F>> {
+    pub proof: P::Proof,
+    pub num_ballots: usize,
+}
+
+// 按电路约定的顺序组装公开输入
+pub fn tally_public_inputs<F: PrimeField>(
+    commitments: &[F],
+    totals: &[u64],
+) -> Result<Vec<F>, VoteError> {
+    if totals.len() != NUM_OPTIONS {
+        return Err(VoteError::InvalidBallot(format!(
+            "expected {} totals, got {}",
+            NUM_OPTIONS,
+            totals.len()
+        )));
+    }
+    let mut inputs = commitments.to_vec();
+    inputs.extend(totals.iter().map(|t| F::from(*t)));
+    Ok(inputs)
+}
+
+// 为固定选票数量生成计票电路的密钥
+// 持有该次设置随机数的一方可以伪造计票证明，应由独立于计票方的可信设置执行并公布（见 bundle 模块）
+pub fn setup_tally<F, P, R>(
+    num_ballots: usize,
+    rng: &mut R,
+) -> Result<(P::ProvingKey, P::VerifyingKey), VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+    R: RngCore + CryptoRng,
+{
+    info!("Setting up tally circuit for {} ballots", num_ballots);
+
+    P::setup(TallyCircuit::<F>::blank(num_ballots), rng).map_err(|e| {
+        error!("Failed to setup tally circuit: {:?}", e);
+        e
+    })
+}
+
+// 检查计票密钥是否为 num_ballots 张计入选票生成，数量不符时返回 KeyMismatch
+pub fn check_tally_keys<F, P>(vk: &P::VerifyingKey, num_ballots: usize) -> Result<(), VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+{
+    check_public_input_count::<F, P>(vk, num_ballots + NUM_OPTIONS)
+        .map_err(|_| VoteError::KeyMismatch(format!("tally keys were not generated for {} ballots", num_ballots)))
+}
+
+// 生成计票证明
+pub fn prove_tally<F, P, R>(
+    pk: &P::ProvingKey,
+    ballots: &[BallotOpening<F>],
+    totals: &[u64],
+    rng: &mut R,
+) -> Result<TallyProof<F, P>, VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+    R: RngCore + CryptoRng,
+{
+    debug!("Generating tally proof for {} ballots", ballots.len());
+
+    if totals.len() != NUM_OPTIONS {
+        return Err(VoteError::InvalidBallot(format!(
+            "expected {} totals, got {}",
+            NUM_OPTIONS,
+            totals.len()
+        )));
+    }
+
+    let circuit = TallyCircuit {
+        commitments: ballots.iter().map(BallotOpening::commitment).collect(),
+        ballots: ballots.to_vec(),
+        totals: [F::from(totals[0]), F::from(totals[1])],
+    };
+
+    let proof = P::prove(pk, circuit, rng).map_err(|e| {
+        error!("Failed to generate tally proof: {:?}", e);
+        e
+    })?;
+
+    Ok(TallyProof { proof, num_ballots: ballots.len() })
+}
+
+// 验证计票证明
+pub fn verify_tally<F, P>(
+    vk: &P::VerifyingKey,
+    proof: &TallyProof<F, P>,
+    commitments: &[F],
+    totals: &[u64],
+) -> Result<bool, VoteError>
+where
+    F: PrimeField,
+    P: ProofSystem<F>,
+{
+    debug!("Verifying tally proof for {} commitments", commitments.len());
+
+    if proof.num_ballots != commitments.len() {
+        return Ok(false);
+    }
+
+    let public_inputs = tally_public_inputs(commitments, totals)?;
+    check_public_input_count::<F, P>(vk, public_inputs.len())?;
+    P::verify(vk, &public_inputs, &proof.proof).map_err(|e| {
+        error!("Failed to verify tally proof: {:?}", e);
+        e
+    })
+}
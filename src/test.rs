@@ -2,9 +2,9 @@
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use ark_bn254::{Bn254, Fr};
-    use ark_crypto_primitives::snark::SNARK;
+    use crate::*;
+    use ark_ff::{One, Zero};
+    use crate::curve::{DefaultField as Fr, DefaultPairing as E};
     use ark_groth16::Groth16;
     use ark_std::rand::thread_rng;
     use ark_relations::r1cs::ConstraintSystem;
@@ -13,20 +13,21 @@ mod tests {
     fn test_vote_system() {
         // 初始化系统
         let mut rng = thread_rng();
-        let (system, pk, vk) = VoteSystem::<Fr, Groth16<Bn254>>::setup(&mut rng).unwrap();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
         
         // 创建投票电路实例
         let vote = Fr::one();  // 投票给选项1
         let nullifier = Fr::from(12345u64);  // 防重标识
         let randomness = Fr::from(67890u64); // 额外随机值
-        let circuit = VoteCircuit { vote, nullifier, randomness };
+        let election_id = Fr::from(2024u64); // 选举标识
+        let circuit = VoteCircuit { vote, secret: nullifier, randomness, election_id, sequence: Fr::zero(), epoch: Fr::zero() };
+        
+        let public_inputs = circuit.public_inputs().to_vec();
         
         // 生成证明
         let proof = system.vote(&pk, circuit, &mut rng).unwrap();
         
         // 验证证明（不直接暴露投票值）
-        // 在实际应用中，可能需要提供一些公开输入（如nullifier的哈希值等）
-        let public_inputs = vec![]; // 简化处理，实际应用中可能需要一些公开输入
         let is_valid = system.verify(&vk, &public_inputs, &proof).unwrap();
         
         // 断言验证结果为真
@@ -37,19 +38,20 @@ mod tests {
     fn test_invalid_vote() {
         // 初始化系统
         let mut rng = thread_rng();
-        let (system, pk, vk) = VoteSystem::<Fr, Groth16<Bn254>>::setup(&mut rng).unwrap();
+        let (system, pk, _vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
         
         // 创建投票电路实例（无效投票）
         let vote = Fr::from(2u64);  // 无效投票选择
         let nullifier = Fr::from(12345u64);  // 防重标识
         let randomness = Fr::from(67890u64); // 额外随机值
-        let circuit = VoteCircuit { vote, nullifier, randomness };
+        let election_id = Fr::from(2024u64); // 选举标识
+        let circuit = VoteCircuit { vote, secret: nullifier, randomness, election_id, sequence: Fr::zero(), epoch: Fr::zero() };
         
         // 生成证明（应该失败）
         let proof_result = system.vote(&pk, circuit, &mut rng);
         
-        // 断言生成证明失败
-        assert!(proof_result.is_err());
+        // 断言生成证明失败，且错误类型为约束不满足
+        assert!(matches!(proof_result, Err(VoteError::UnsatisfiedConstraint(_))));
     }
     
     #[test]
@@ -61,7 +63,8 @@ mod tests {
         let vote = Fr::one();
         let nullifier = Fr::from(12345u64);
         let randomness = Fr::from(67890u64);
-        let circuit = VoteCircuit { vote, nullifier, randomness };
+        let election_id = Fr::from(2024u64);
+        let circuit = VoteCircuit { vote, secret: nullifier, randomness, election_id, sequence: Fr::zero(), epoch: Fr::zero() };
         
         circuit.generate_constraints(cs.clone()).unwrap();
         assert!(cs.is_satisfied().unwrap());
@@ -70,13 +73,5888 @@ mod tests {
     #[test]
     fn test_nullifier_calculation() {
         // 测试防重标识计算
+        let mut rng = thread_rng();
         let user_id1 = "user1";
         let user_id2 = "user2";
         
-        let nullifier1 = super::super::calculate_nullifier(user_id1);
-        let nullifier2 = super::super::calculate_nullifier(user_id2);
+        let nullifier1: Fr = generate_nullifier(user_id1, &mut rng).unwrap();
+        let nullifier2: Fr = generate_nullifier(user_id2, &mut rng).unwrap();
         
         // 确保不同用户ID产生不同的防重标识
         assert_ne!(nullifier1, nullifier2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_user_id_length_validated() {
+        let mut rng = thread_rng();
+
+        // 空ID不能映射到共享的防重标识
+        assert!(matches!(generate_nullifier::<Fr, _>("", &mut rng), Err(VoteError::InvalidUserId(_))));
+
+        // 恰好为上限的ID被接受，多一个字节即拒绝
+        let maximal = "u".repeat(MAX_USER_ID_LEN);
+        assert!(generate_nullifier::<Fr, _>(&maximal, &mut rng).is_ok());
+        let oversized = "u".repeat(MAX_USER_ID_LEN + 1);
+        assert!(matches!(generate_nullifier::<Fr, _>(&oversized, &mut rng), Err(VoteError::InvalidUserId(_))));
+
+        // 上限按字节计算
+        assert!(validate_user_id(&"é".repeat(MAX_USER_ID_LEN / 2)).is_ok());
+        assert!(validate_user_id(&"é".repeat(MAX_USER_ID_LEN / 2 + 1)).is_err());
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_rejects_invalid_user_id() {
+        use crate::web::{vote_route, AppState};
+
+        let state = AppState::new_mock();
+        let route = vote_route(state);
+        for user_id in [String::new(), "u".repeat(MAX_USER_ID_LEN + 1)] {
+            let body = serde_json::json!({ "vote": 1, "user_id": user_id, "chain": "localhost", "gas_option": "relayer" });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 400);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["code"], "invalid_user_id");
+        }
+
+        let body = serde_json::json!({ "vote": 1, "user_id": "u".repeat(MAX_USER_ID_LEN), "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[test]
+    fn test_tally_proof() {
+        use crate::tally::{prove_tally, setup_tally, verify_tally, BallotOpening};
+        
+        let mut rng = thread_rng();
+        
+        // 构造4张选票：3票投给选项1，1票投给选项0
+        let ballots: Vec<BallotOpening<Fr>> = [1u64, 0, 1, 1]
+            .iter()
+            .enumerate()
+            .map(|(i, v)| BallotOpening {
+                vote: Fr::from(*v),
+                randomness: Fr::from(1000u64 + i as u64),
+            })
+            .collect();
+        let commitments: Vec<Fr> = ballots.iter().map(BallotOpening::commitment).collect();
+        
+        let (pk, vk) = setup_tally::<Fr, Groth16<E>, _>(ballots.len(), &mut rng).unwrap();
+        let proof = prove_tally::<Fr, Groth16<E>, _>(&pk, &ballots, &[1, 3], &mut rng).unwrap();
+        
+        // 正确的总票数验证通过
+        assert!(verify_tally(&vk, &proof, &commitments, &[1, 3]).unwrap());
+        
+        // 虚增的总票数验证失败
+        assert!(!verify_tally(&vk, &proof, &commitments, &[1, 4]).unwrap());
+    }
+    
+    #[test]
+    fn test_prove_batch() {
+        // 初始化系统并限制并行度
+        let mut rng = thread_rng();
+        let (mut system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        system.set_max_parallelism(4);
+        
+        // 创建64张选票
+        let circuits: Vec<VoteCircuit<Fr>> = (0..64u64)
+            .map(|i| VoteCircuit {
+                vote: Fr::from(i % 2),
+                secret: Fr::from(10000u64 + i),
+                randomness: Fr::from(20000u64 + i),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            })
+            .collect();
+        
+        let public_inputs: Vec<Vec<Fr>> = circuits.iter().map(|c| c.public_inputs().to_vec()).collect();
+        
+        // 并行生成证明
+        let proofs = system.prove_batch(&pk, circuits, [42u8; 32]).unwrap();
+        assert_eq!(proofs.len(), 64);
+        
+        // 所有证明均应按输入顺序验证通过
+        for (proof, inputs) in proofs.iter().zip(&public_inputs) {
+            assert!(system.verify(&vk, inputs, proof).unwrap());
+        }
+    }
+    
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_prove_span_emitted() {
+        let mut rng = thread_rng();
+        let (system, pk, _vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        // 证明过程应处于prove span中
+        assert!(logs_contain("prove"));
+        assert!(logs_contain("Generating proof synchronously"));
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_vote_async_does_not_block_executor() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let system = Arc::new(system);
+        let pk = Arc::new(pk);
+        
+        // 并发提交4个异步证明任务
+        let completed = Arc::new(AtomicUsize::new(0));
+        let mut handles = Vec::new();
+        for i in 0..4u64 {
+            let circuit = VoteCircuit {
+                vote: Fr::from(i % 2),
+                secret: Fr::from(12345u64 + i),
+                randomness: Fr::from(67890u64 + i),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            };
+            let inputs = circuit.public_inputs().to_vec();
+            let system = system.clone();
+            let pk = pk.clone();
+            let completed = completed.clone();
+            handles.push(tokio::spawn(async move {
+                let proof = system.vote_async(pk, circuit).await;
+                completed.fetch_add(1, Ordering::SeqCst);
+                proof.map(|p| (p, inputs))
+            }));
+        }
+        
+        // 轻量异步任务：在证明未完成期间计数
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = {
+            let ticks = ticks.clone();
+            let completed = completed.clone();
+            tokio::spawn(async move {
+                while completed.load(Ordering::SeqCst) < 4 {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                }
+            })
+        };
+        
+        for handle in handles {
+            let (proof, inputs) = handle.await.unwrap().unwrap();
+            assert!(system.verify(&vk, &inputs, &proof).unwrap());
+        }
+        ticker.await.unwrap();
+        
+        // 执行器未被阻塞，轻量任务应在证明期间多次运行
+        assert!(ticks.load(Ordering::SeqCst) > 1);
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_async_delegates_to_injected_prover() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        
+        // 返回预先生成的证明并记录调用次数的模拟证明后端
+        struct CannedProver {
+            proof: ark_groth16::Proof<E>,
+            calls: AtomicUsize,
+        }
+        
+        #[async_trait::async_trait]
+        impl AsyncProver<Fr, Groth16<E>> for CannedProver {
+            async fn prove(
+                &self,
+                _circuit: VoteCircuit<Fr>,
+                _pk: Arc<ark_groth16::ProvingKey<E>>,
+            ) -> Result<ark_groth16::Proof<E>, VoteError> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Ok(self.proof.clone())
+            }
+        }
+        
+        let mut rng = thread_rng();
+        let (mut system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        let canned = system.vote(&pk, circuit.clone(), &mut rng).unwrap();
+        
+        let prover = Arc::new(CannedProver { proof: canned.clone(), calls: AtomicUsize::new(0) });
+        system.set_prover(prover.clone());
+        let system = Arc::new(system);
+        
+        let proof = system.clone().vote_async(Arc::new(pk), circuit).await.unwrap();
+        assert_eq!(proof, canned);
+        assert_eq!(prover.calls.load(Ordering::SeqCst), 1);
+        assert!(system.verify(&vk, &public_inputs, &proof).unwrap());
+        
+        // 远程证明服务的占位实现返回证明错误
+        let mut rng = thread_rng();
+        let (mut system, pk, _) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        system.set_prover(Arc::new(HttpProver::new("http://127.0.0.1:1/prove")));
+        let result = Arc::new(system).vote_async(Arc::new(pk), VoteCircuit::blank(Fr::from(2024u64))).await;
+        assert!(matches!(result, Err(VoteError::Proving(_))));
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_async_with_timeout() {
+        use std::sync::Arc;
+        use std::time::Duration;
+        
+        // 永不完成的模拟证明后端
+        struct StuckProver;
+        
+        #[async_trait::async_trait]
+        impl AsyncProver<Fr, Groth16<E>> for StuckProver {
+            async fn prove(
+                &self,
+                _circuit: VoteCircuit<Fr>,
+                _pk: Arc<ark_groth16::ProvingKey<E>>,
+            ) -> Result<ark_groth16::Proof<E>, VoteError> {
+                std::future::pending().await
+            }
+        }
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let pk = Arc::new(pk);
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        
+        // 超时时间足够时与 vote_async 相同
+        let system = Arc::new(system);
+        let proof = system
+            .clone()
+            .vote_async_with_timeout(pk.clone(), circuit.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(system.verify(&vk, &public_inputs, &proof).unwrap());
+        
+        // 卡住的证明在超时后返回 Timeout
+        let mut stuck = VoteSystem::<Fr, Groth16<E>>::new();
+        stuck.set_prover(Arc::new(StuckProver));
+        let timeout = Duration::from_millis(50);
+        let result = Arc::new(stuck).vote_async_with_timeout(pk.clone(), circuit.clone(), timeout).await;
+        assert!(matches!(result, Err(VoteError::Timeout(t)) if t == timeout));
+        assert_eq!(VoteError::Timeout(timeout).code(), "timeout");
+        
+        // 带报告的版本同样超时
+        let result = system
+            .clone()
+            .vote_with_report_async_with_timeout(pk.clone(), circuit.clone(), ark_std::rand::rngs::OsRng, Duration::ZERO)
+            .await;
+        assert!(matches!(result, Err(VoteError::Timeout(_))));
+        
+        // 已取消的证明任务不再开始证明阶段
+        let cancel = crate::cancel::CancelToken::default();
+        drop(cancel.drop_guard());
+        assert!(cancel.is_cancelled());
+        let result = system.vote_with_report_cancellable(&pk, circuit, &mut rng, &cancel);
+        assert!(matches!(result, Err(VoteError::Proving(_))));
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_prover_pool_bounds_concurrency() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+        use std::time::{Duration, Instant};
+        
+        let mut rng = thread_rng();
+        let (mut system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let pool = Arc::new(ProverPool::new(2, 1));
+        system.set_prover_pool(pool.clone());
+        let system = Arc::new(system);
+        let pk = Arc::new(pk);
+        
+        // 采样证明池状态，直到全部任务完成
+        let done = Arc::new(AtomicBool::new(false));
+        let peak_running = Arc::new(AtomicUsize::new(0));
+        let peak_queued = Arc::new(AtomicUsize::new(0));
+        let monitor = {
+            let (pool, done) = (pool.clone(), done.clone());
+            let (peak_running, peak_queued) = (peak_running.clone(), peak_queued.clone());
+            tokio::spawn(async move {
+                while !done.load(Ordering::SeqCst) {
+                    peak_running.fetch_max(pool.running(), Ordering::SeqCst);
+                    peak_queued.fetch_max(pool.queue_depth(), Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            })
+        };
+        
+        // 同时提交10个证明任务
+        let circuits: Vec<VoteCircuit<Fr>> = (0..10u64)
+            .map(|i| VoteCircuit {
+                vote: Fr::from(i % 2),
+                secret: Fr::from(12345u64 + i),
+                randomness: Fr::from(67890u64 + i),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            })
+            .collect();
+        let proofs = futures::future::join_all(
+            circuits.iter().map(|circuit| system.clone().vote_async(pk.clone(), circuit.clone())),
+        )
+        .await;
+        done.store(true, Ordering::SeqCst);
+        monitor.await.unwrap();
+        
+        for (circuit, proof) in circuits.iter().zip(proofs) {
+            assert!(system.verify(&vk, &circuit.public_inputs().to_vec(), &proof.unwrap()).unwrap());
+        }
+        assert!(peak_running.load(Ordering::SeqCst) <= 2);
+        assert!(peak_queued.load(Ordering::SeqCst) >= 1);
+        assert_eq!(pool.queue_depth(), 0);
+        assert_eq!(pool.running(), 0);
+        
+        // 任务按提交顺序开始，同时最多2个，6个各20毫秒的任务至少需要3轮
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let start = Instant::now();
+        let results = futures::future::join_all((0..6usize).map(|i| {
+            let (started, active, peak) = (started.clone(), active.clone(), peak.clone());
+            pool.run(move |_| {
+                started.lock().unwrap().push(i);
+                peak.fetch_max(active.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok(i)
+            })
+        }))
+        .await;
+        assert!(start.elapsed() >= Duration::from_millis(60));
+        assert_eq!(results.into_iter().map(Result::unwrap).collect::<Vec<_>>(), (0..6).collect::<Vec<_>>());
+        let started = started.lock().unwrap().clone();
+        // 同一轮的两个任务在不同线程上开始，顺序可能互换，轮次之间保持提交顺序
+        let rounds: Vec<Vec<usize>> = started
+            .chunks(2)
+            .map(|round| {
+                let mut round = round.to_vec();
+                round.sort();
+                round
+            })
+            .collect();
+        assert_eq!(rounds, vec![vec![0, 1], vec![2, 3], vec![4, 5]]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+    
+    #[test]
+    fn test_setup_reports_monotonic_progress() {
+        use std::sync::Mutex;
+        
+        let reports = Mutex::new(Vec::<(String, f64)>::new());
+        let record = |phase: &str, fraction: f64| reports.lock().unwrap().push((phase.to_string(), fraction));
+        let check = |reports: &Mutex<Vec<(String, f64)>>| {
+            let reports = std::mem::take(&mut *reports.lock().unwrap());
+            assert!(reports.len() >= 2);
+            assert_eq!(reports.first().unwrap().1, 0.0);
+            assert_eq!(reports.last().unwrap().1, 1.0);
+            assert!(reports.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            reports.into_iter().map(|(phase, _)| phase).collect::<Vec<_>>()
+        };
+        
+        let mut rng = thread_rng();
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup_with_progress(Fr::zero(), &mut rng, &record).unwrap();
+        let phases = check(&reports);
+        assert!(phases.contains(&"synthesize".to_string()));
+        assert!(phases.contains(&"generate_keys".to_string()));
+        
+        VoteSystem::<Fr, Groth16<E>>::setup_with_candidates_and_progress(3, Fr::zero(), &mut rng, &record).unwrap();
+        check(&reports);
+        
+        // 导入密钥时的一致性检查
+        crate::keys::check_consistency_with_progress::<Fr, Groth16<E>>(&pk, &vk, &record).unwrap();
+        let phases = check(&reports);
+        assert_eq!(phases.first().unwrap(), "check_proving_key");
+        
+        // 默认实现输出 tracing 事件，不影响结果
+        TracingProgress.report("setup", 0.5);
+    }
+    
+    #[test]
+    fn test_check_circuit() {
+        // 有效投票满足约束
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        assert!(VoteSystem::<Fr, Groth16<E>>::check_circuit(circuit).unwrap());
+        
+        // vote = 2 不满足约束
+        let circuit = VoteCircuit {
+            vote: Fr::from(2u64),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        assert!(!VoteSystem::<Fr, Groth16<E>>::check_circuit(circuit).unwrap());
+    }
+    
+    #[test]
+    fn test_vote_with_report() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone()).unwrap();
+        
+        let (proof, report) = system.vote_with_report(&pk, circuit, &mut rng).unwrap();
+        assert_eq!(report.num_constraints, cs.num_constraints());
+        assert_eq!(report.proof_size, 128);
+        assert!(report.estimated_memory_bytes > report.num_constraints * std::mem::size_of::<Fr>());
+        
+        let (valid, report) = system.verify_with_report(&vk, &public_inputs, &proof).unwrap();
+        assert!(valid);
+        assert_eq!(report.num_public_inputs, NUM_PUBLIC_INPUTS);
+        assert_eq!(report.proof_size, 128);
+        
+        // 不满足约束的选票不生成报告
+        let circuit = VoteCircuit { vote: Fr::from(2u64), ..VoteCircuit::blank(Fr::from(2024u64)) };
+        assert!(matches!(
+            system.vote_with_report(&pk, circuit, &mut rng),
+            Err(VoteError::UnsatisfiedConstraint(_))
+        ));
+    }
+    
+    #[test]
+    fn test_proving_thread_count_does_not_change_proofs() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+        
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut thread_rng()).unwrap();
+        let single = system.with_threads(1);
+        let multi = VoteSystem::<Fr, Groth16<E>>::new().with_threads(4);
+        assert_eq!(single.proving_threads(), 1);
+        assert_eq!(multi.proving_threads(), 4);
+        
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        
+        // 相同随机数下单线程与多线程的多标量乘法结果完全一致，只有耗时不同（耗时见 benches/vote.rs 的 vote_threads）
+        let (single_proof, single_report) =
+            single.vote_with_report(&pk, circuit.clone(), &mut ChaCha20Rng::from_seed([7u8; 32])).unwrap();
+        let (multi_proof, multi_report) =
+            multi.vote_with_report(&pk, circuit, &mut ChaCha20Rng::from_seed([7u8; 32])).unwrap();
+        assert_eq!(single_proof, multi_proof);
+        assert_eq!(single_report.num_constraints, multi_report.num_constraints);
+        assert!(single.verify(&vk, &public_inputs, &single_proof).unwrap());
+        assert!(multi.verify(&vk, &public_inputs, &multi_proof).unwrap());
+    }
+
+    #[test]
+    fn test_vote_seeded_is_reproducible() {
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut thread_rng()).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        let serialize = |proof| <Groth16<E> as ProofSystem<Fr>>::serialize_proof(proof).unwrap();
+
+        // 相同种子得到逐字节相同的证明，配置线程池也不影响结果
+        let first = system.vote_seeded(&pk, circuit.clone(), [1u8; 32]).unwrap();
+        let again = system.vote_seeded(&pk, circuit.clone(), [1u8; 32]).unwrap();
+        assert_eq!(serialize(&first), serialize(&again));
+        let pooled = VoteSystem::<Fr, Groth16<E>>::new().with_threads(2);
+        let pooled_proof = pooled.vote_seeded(&pk, circuit.clone(), [1u8; 32]).unwrap();
+        assert_eq!(serialize(&first), serialize(&pooled_proof));
+
+        // 不同种子得到不同的证明，两者都能通过验证
+        let other = system.vote_seeded(&pk, circuit, [2u8; 32]).unwrap();
+        assert_ne!(serialize(&first), serialize(&other));
+        assert!(system.verify(&vk, &public_inputs, &first).unwrap());
+        assert!(system.verify(&vk, &public_inputs, &other).unwrap());
+    }
+
+    #[test]
+    fn test_circuit_witness_zeroized_on_drop() {
+        use std::mem::ManuallyDrop;
+        
+        let mut circuit = ManuallyDrop::new(VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::from(3u64),
+            epoch: Fr::zero(),
+        });
+        // SAFETY: 字段均为不含析构逻辑的Copy类型，运行Drop后内存仍由ManuallyDrop持有，读取字段是安全的
+        unsafe { std::ptr::drop_in_place(&mut *circuit) };
+        assert!(circuit.vote.is_zero());
+        assert!(circuit.secret.is_zero());
+        assert!(circuit.randomness.is_zero());
+        // 公开输入不清零
+        assert_eq!(circuit.election_id, Fr::from(2024u64));
+        assert_eq!(circuit.sequence, Fr::from(3u64));
+        
+        // 约束检查后见证赋值被清零
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        crate::zeroize_witness(&cs);
+        assert!(cs.borrow().unwrap().witness_assignment.iter().all(|w| w.is_zero()));
+    }
+    
+    #[test]
+    fn test_witness_structs_zeroize_on_drop() {
+        use zeroize::ZeroizeOnDrop;
+        
+        // 新增携带私有输入的电路或结构体时须加入此列表
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        
+        assert_zeroize_on_drop::<VoteCircuit<Fr>>();
+        assert_zeroize_on_drop::<crate::nullifier::NullifierCircuit<Fr>>();
+        assert_zeroize_on_drop::<BallotOpening<Fr>>();
+        assert_zeroize_on_drop::<crate::tally::TallyCircuit<Fr>>();
+        #[cfg(feature = "curve-bn254")]
+        assert_zeroize_on_drop::<crate::elgamal::EncryptedVoteCircuit>();
+        #[cfg(feature = "curve-bn254")]
+        assert_zeroize_on_drop::<crate::elgamal::KeyShare>();
+    }
+    
+    // 计算验证密钥序列化结果的摘要
+    fn vk_digest(vk: &<Groth16<E> as ProofSystem<Fr>>::VerifyingKey) -> String {
+        use ark_serialize::CanonicalSerialize;
+        
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes).unwrap();
+        blake3::hash(&bytes).to_hex().to_string()
+    }
+    
+    #[test]
+    fn test_setup_deterministic_is_reproducible() {
+        let (_, _, vk1) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::zero(), [7u8; 32]).unwrap();
+        let (_, _, vk2) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::zero(), [7u8; 32]).unwrap();
+        let (_, _, vk3) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::zero(), [8u8; 32]).unwrap();
+        
+        // 相同种子产生相同密钥，不同种子产生不同密钥
+        assert_eq!(vk_digest(&vk1), vk_digest(&vk2));
+        assert_ne!(vk_digest(&vk1), vk_digest(&vk3));
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_setup_deterministic_golden_vk() {
+        // 固定种子的验证密钥摘要与金标文件比对，用于发现意外的电路改动
+        // 金标文件随仓库提交，缺少时测试失败；设置 UPDATE_GOLDEN=1 重新生成
+        let golden_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/vk_seed_zero.blake3");
+        let (_, _, vk) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::zero(), [0u8; 32]).unwrap();
+        let digest = vk_digest(&vk);
+        
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(golden_path, format!("{}\n", digest)).unwrap();
+            return;
+        }
+        
+        let golden = std::fs::read_to_string(golden_path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}; rerun with UPDATE_GOLDEN=1 to create it", golden_path, e));
+        assert_eq!(
+            golden.trim(),
+            digest,
+            "verifying key changed for fixed seed; if the circuit change is intentional, rerun with UPDATE_GOLDEN=1"
+        );
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_golden_test_vectors() {
+        use crate::testvectors::{load_vectors, regenerate_vk_digest, standard_vectors, verify_vector, write_vectors};
+        
+        // 随仓库提交的向量（含旧版本生成的）必须仍被当前代码接受；设置 UPDATE_GOLDEN=1 重新生成标准向量
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/vectors");
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            write_vectors(dir, &standard_vectors::<Fr, Groth16<E>>().unwrap()).unwrap();
+            return;
+        }
+        
+        let vectors = load_vectors(dir)
+            .unwrap_or_else(|e| panic!("cannot load {}: {}; rerun with UPDATE_GOLDEN=1 to create it", dir, e));
+        // 电路版本 1 的向量没有版本后缀
+        for name in [
+            "boolean_ballot",
+            "candidate_ballot",
+            "boolean_ballot_v2",
+            "candidate_ballot_v2",
+            "boolean_ballot_v3",
+            "candidate_ballot_v3",
+        ] {
+            assert!(vectors.iter().any(|v| v.name == name), "missing test vector {}", name);
+        }
+        for vector in &vectors {
+            verify_vector::<Fr, Groth16<E>>(vector).unwrap_or_else(|e| panic!("{}: {}", vector.name, e));
+            if vector.circuit_version == CIRCUIT_VERSION {
+                assert_eq!(
+                    regenerate_vk_digest::<Fr, Groth16<E>>(vector).unwrap(),
+                    vector.vk_digest,
+                    "{}: verifying key changed for fixed seed; if the circuit change is intentional, bump CIRCUIT_VERSION and add new vectors",
+                    vector.name
+                );
+            }
+        }
+        
+        // 篡改后的向量被拒绝
+        let mut tampered = vectors[0].clone();
+        tampered.expected_nullifier = "1".to_string();
+        assert!(matches!(verify_vector::<Fr, Groth16<E>>(&tampered), Err(VoteError::InvalidBallot(_))));
+        let mut tampered = vectors[0].clone();
+        tampered.witness.vote = 7;
+        assert!(matches!(verify_vector::<Fr, Groth16<E>>(&tampered), Err(VoteError::InvalidBallot(_))));
+        let mut tampered = vectors[0].clone();
+        tampered.witness.sequence = 1;
+        tampered.public_inputs[2] = "1".to_string();
+        assert!(matches!(verify_vector::<Fr, Groth16<E>>(&tampered), Err(VoteError::Verification(_))));
+        let mut tampered = vectors[0].clone();
+        tampered.format = crate::testvectors::TEST_VECTOR_FORMAT + 1;
+        assert!(matches!(verify_vector::<Fr, Groth16<E>>(&tampered), Err(VoteError::UnsupportedVersion(_))));
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_golden_merkle_vector() {
+        use crate::testvectors::{load_merkle_vector, merkle_vector, verify_merkle_vector, write_merkle_vector};
+        
+        // 名册树的哈希、空位取值或叶子顺序改变时向量不再通过；设置 UPDATE_GOLDEN=1 重新生成
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/vectors/merkle");
+        let path = format!("{}/voter_roll_depth{}.json", dir, crate::VOTER_TREE_DEPTH);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            write_merkle_vector(dir, &merkle_vector::<Fr>().unwrap()).unwrap();
+            return;
+        }
+        
+        let vector = load_merkle_vector(&path)
+            .unwrap_or_else(|e| panic!("cannot load {}: {}; rerun with UPDATE_GOLDEN=1 to create it", path, e));
+        verify_merkle_vector::<Fr>(&vector).unwrap();
+        assert_eq!(vector.paths.len(), 3);
+        assert_eq!(vector.paths[0].siblings.len(), vector.depth);
+        
+        // 篡改树根、叶子或路径都被拒绝
+        let mut tampered = vector.clone();
+        tampered.root = "1".to_string();
+        assert!(matches!(verify_merkle_vector::<Fr>(&tampered), Err(VoteError::InvalidBallot(_))));
+        let mut tampered = vector.clone();
+        tampered.leaves.swap(0, 1);
+        assert!(matches!(verify_merkle_vector::<Fr>(&tampered), Err(VoteError::InvalidBallot(_))));
+        let mut tampered = vector.clone();
+        tampered.paths[1].siblings[3] = "1".to_string();
+        assert!(matches!(verify_merkle_vector::<Fr>(&tampered), Err(VoteError::InvalidBallot(_))));
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_solidity_verifier_golden() {
+        // 固定种子生成的验证合约与随仓库提交的金标文件比对，缺少时测试失败；设置 UPDATE_GOLDEN=1 重新生成
+        let golden_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/verifier_seed_zero.sol");
+        let (_, _, vk) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::zero(), [0u8; 32]).unwrap();
+        let contract = crate::export::solidity_verifier(&vk);
+        
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(golden_path, &contract).unwrap();
+            return;
+        }
+        
+        let golden = std::fs::read_to_string(golden_path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}; rerun with UPDATE_GOLDEN=1 to create it", golden_path, e));
+        assert_eq!(
+            golden,
+            contract,
+            "generated verifier changed for fixed seed; if intentional, rerun with UPDATE_GOLDEN=1"
+        );
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_evm_calldata_layout() {
+        use ark_bn254::{G1Affine, G2Affine};
+        use ark_ec::AffineRepr;
+        use num_bigint::BigUint;
+        use std::str::FromStr;
+        
+        fn word(decimal: &str) -> Vec<u8> {
+            let bytes = BigUint::from_str(decimal).unwrap().to_bytes_be();
+            let mut word = vec![0u8; 32 - bytes.len()];
+            word.extend(bytes);
+            word
+        }
+        
+        // 使用生成元构造证明，其坐标为公开已知值
+        let proof = ark_groth16::Proof::<E> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::generator(),
+        };
+        let calldata = crate::export::evm_calldata(&[Fr::from(7u64), Fr::from(9u64)], &proof);
+        
+        let selector = ethers::utils::keccak256(b"verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[2])");
+        let mut expected = selector[..4].to_vec();
+        for decimal in [
+            "1",
+            "2",
+            "11559732032986387107991004021392285783925812861821192530917403151452391805634",
+            "10857046999023057135944570762232829481370756359578518086990519993285655852781",
+            "4082367875863433681332203403145435568316851327593401208105741076214120093531",
+            "8495653923123431417604973247489272438418190587263600148770280649306958101930",
+            "1",
+            "2",
+            "7",
+            "9",
+        ] {
+            expected.extend(word(decimal));
+        }
+        assert_eq!(calldata, expected);
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_vk_json_round_trip() {
+        use crate::export::{vk_from_json, vk_to_json};
+        use ark_serialize::CanonicalSerialize;
+        
+        // 固定种子的验证密钥JSON随仓库提交，供第三方比对格式；设置 UPDATE_GOLDEN=1 重新生成
+        let golden_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/vk_seed_zero.json");
+        let (_, _, vk) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::zero(), [0u8; 32]).unwrap();
+        let json = vk_to_json(&vk).unwrap();
+        
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(golden_path, serde_json::to_string_pretty(&json).unwrap() + "\n").unwrap();
+            return;
+        }
+        
+        let golden = std::fs::read_to_string(golden_path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}; rerun with UPDATE_GOLDEN=1 to create it", golden_path, e));
+        let golden: serde_json::Value = serde_json::from_str(&golden).unwrap();
+        assert_eq!(golden, json, "verifying key changed for fixed seed; if the circuit change is intentional, rerun with UPDATE_GOLDEN=1");
+        assert_eq!(golden["fingerprint"], fingerprint::<Fr, _>(&vk).unwrap().to_string());
+        assert_eq!(golden["ic"].as_array().unwrap().len(), NUM_PUBLIC_INPUTS + 1);
+        
+        // 解析后的规范编码与原验证密钥逐字节相同
+        let parsed = vk_from_json(&golden).unwrap();
+        let encode = |vk: &ark_groth16::VerifyingKey<E>| {
+            let mut bytes = Vec::new();
+            vk.serialize_compressed(&mut bytes).unwrap();
+            bytes
+        };
+        assert_eq!(encode(&parsed), encode(&vk));
+        assert_eq!(vk_to_json(&parsed).unwrap(), golden);
+        
+        // 点被替换后指纹对不上：KeyMismatch
+        let mut swapped = golden.clone();
+        swapped["gamma_g2"] = golden["delta_g2"].clone();
+        assert!(matches!(vk_from_json(&swapped), Err(VoteError::KeyMismatch(_))));
+        
+        // 不在曲线上的点、未知版本和其他曲线被拒绝
+        let mut corrupted = golden.clone();
+        let mut alpha = hex::decode(golden["alpha_g1"].as_str().unwrap()).unwrap();
+        alpha[0] ^= 1;
+        corrupted["alpha_g1"] = serde_json::json!(hex::encode(alpha));
+        assert!(vk_from_json(&corrupted).is_err());
+        let mut future = golden.clone();
+        future["version"] = serde_json::json!(2);
+        assert!(matches!(vk_from_json(&future), Err(VoteError::UnsupportedVersion(_))));
+        let mut other_curve = golden;
+        other_curve["curve"] = serde_json::json!("bls12_381");
+        assert!(vk_from_json(&other_curve).is_err());
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_snarkjs_round_trip() {
+        use crate::export::{from_snarkjs_proof, from_snarkjs_vkey, to_snarkjs_proof, to_snarkjs_vkey};
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        let proof_json = to_snarkjs_proof(&proof);
+        let vk_json = to_snarkjs_vkey(&vk);
+        assert_eq!(vk_json["nPublic"], NUM_PUBLIC_INPUTS);
+        
+        let parsed_proof = from_snarkjs_proof(&proof_json).unwrap();
+        let parsed_vk = from_snarkjs_vkey(&vk_json).unwrap();
+        assert_eq!(parsed_proof, proof);
+        assert_eq!(parsed_vk, vk);
+        assert!(system.verify(&parsed_vk, &public_inputs, &parsed_proof).unwrap());
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_snarkjs_fixture_matches_generators() {
+        use crate::export::{from_snarkjs_proof, to_snarkjs_proof};
+        use ark_bn254::{G1Affine, G2Affine};
+        use ark_ec::AffineRepr;
+        
+        // 以生成元为坐标的snarkjs格式证明，坐标为bn128曲线的公开常量
+        let fixture: serde_json::Value = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/testdata/snarkjs_proof_generators.json"
+        )))
+        .unwrap();
+        let proof = ark_groth16::Proof::<E> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::generator(),
+        };
+        
+        assert_eq!(to_snarkjs_proof(&proof), fixture);
+        assert_eq!(from_snarkjs_proof(&fixture).unwrap(), proof);
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_snarkjs_uses_standard_not_montgomery_form() {
+        use crate::export::{from_snarkjs_proof, to_snarkjs_proof};
+        use ark_bn254::{Fq, G1Affine, G2Affine};
+        use ark_ec::AffineRepr;
+        use ark_ff::BigInt;
+        
+        // 内部存储为Montgomery形式，直接取内部值会得到错误的十进制串
+        let one = Fq::one();
+        assert_ne!(one.0, BigInt::from(1u64));
+        assert_eq!(one.into_bigint(), BigInt::from(1u64));
+        
+        let proof = ark_groth16::Proof::<E> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: (G1Affine::generator() * Fr::from(2u64)).into(),
+        };
+        let json = to_snarkjs_proof(&proof);
+        assert_eq!(json["pi_a"], serde_json::json!(["1", "2", "1"]));
+        
+        // 把Montgomery形式的内部值当作坐标写入，解析后不再是曲线上的点，必须被拒绝
+        let mut montgomery = json.clone();
+        montgomery["pi_a"] = serde_json::json!([one.0.to_string(), (one + one).0.to_string(), "1"]);
+        assert!(from_snarkjs_proof(&montgomery).is_err());
+        
+        // 超出基域模数的坐标同样被拒绝
+        let mut out_of_range = json;
+        out_of_range["pi_a"][0] = serde_json::json!(num_bigint::BigUint::from(Fq::MODULUS).to_string());
+        assert!(from_snarkjs_proof(&out_of_range).is_err());
+    }
+    
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_snarkjs_proof_and_public_signals_fixtures() {
+        use crate::export::{
+            from_snarkjs_public_signals, from_snarkjs_vkey, to_snarkjs_proof, to_snarkjs_public_signals,
+            to_snarkjs_vkey, verify_snarkjs,
+        };
+        
+        // snarkjs 的三个文件（verification_key.json / proof.json / public.json）随仓库提交；设置 UPDATE_GOLDEN=1 重新生成
+        let vk_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/snarkjs/verification_key.json");
+        let proof_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/snarkjs/proof.json");
+        let public_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/snarkjs/public.json");
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::from(2024u64), [0u8; 32]).unwrap();
+        
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let circuit = VoteCircuit {
+                vote: Fr::one(),
+                secret: Fr::from(12345u64),
+                randomness: Fr::from(67890u64),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            };
+            let public_inputs = circuit.public_inputs().to_vec();
+            let proof = system.vote_seeded(&pk, circuit, [7u8; 32]).unwrap();
+            let write = |path: &str, value: serde_json::Value| {
+                std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap()).unwrap();
+                std::fs::write(path, serde_json::to_string_pretty(&value).unwrap() + "\n").unwrap();
+            };
+            write(vk_path, to_snarkjs_vkey(&vk));
+            write(proof_path, to_snarkjs_proof(&proof));
+            write(public_path, to_snarkjs_public_signals(&public_inputs));
+            return;
+        }
+        
+        let read = |path: &str| -> serde_json::Value {
+            let text = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("cannot read {}: {}; rerun with UPDATE_GOLDEN=1 to create it", path, e));
+            serde_json::from_str(&text).unwrap()
+        };
+        let vk_json = read(vk_path);
+        let proof_json = read(proof_path);
+        let public_json = read(public_path);
+        
+        // 按snarkjs文件验证，不依赖当前电路生成的密钥
+        let fixture_vk = from_snarkjs_vkey(&vk_json).unwrap();
+        assert!(verify_snarkjs(&system, &fixture_vk, &proof_json, &public_json).unwrap());
+        
+        // 公开信号为标准形式的十进制字符串，顺序与 PublicInputs::to_vec() 一致
+        let signals = from_snarkjs_public_signals(&public_json).unwrap();
+        let public_inputs = PublicInputs::from_slice(&signals).unwrap();
+        assert_eq!(public_inputs.election_id, Fr::from(2024u64));
+        assert_eq!(public_inputs.sequence, Fr::zero());
+        assert_eq!(to_snarkjs_public_signals(&signals), public_json);
+        
+        // 篡改任一公开信号后验证失败
+        let mut tampered = public_json.clone();
+        tampered[2] = serde_json::json!("1");
+        assert!(!verify_snarkjs(&system, &fixture_vk, &proof_json, &tampered).unwrap());
+        
+        // 信号数量与验证密钥不符时报错，而不是静默失败
+        let mut short = public_json.clone();
+        short.as_array_mut().unwrap().pop();
+        assert!(matches!(
+            verify_snarkjs(&system, &fixture_vk, &proof_json, &short),
+            Err(VoteError::KeyMismatch(_))
+        ));
+        
+        // 超出标量域的信号和非字符串信号被拒绝
+        let mut out_of_range = public_json.clone();
+        out_of_range[0] = serde_json::json!(num_bigint::BigUint::from(Fr::MODULUS).to_string());
+        assert!(matches!(from_snarkjs_public_signals(&out_of_range), Err(VoteError::FieldRangeExceeded(_))));
+        let mut not_string = public_json;
+        not_string[0] = serde_json::json!(1);
+        assert!(from_snarkjs_public_signals(&not_string).is_err());
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_decode_public_inputs_from_vote_response() {
+        use crate::web::{vote_route, AppState};
+        
+        let state = AppState::new_mock();
+        state.setup_election("e1").await.unwrap();
+        let route = vote_route(state);
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "election_id": "e1", "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        
+        // 不需要验证密钥即可按字段名查看公开输入
+        let labeled = decode_public_inputs::<Fr>(&reply).unwrap();
+        let labels: Vec<&str> = labeled.iter().map(|(label, _)| *label).collect();
+        assert_eq!(labels, PublicInputs::<Fr>::LABELS);
+        assert_eq!(labels, ["nullifier", "election_id", "sequence", "epoch", "commitment"]);
+        let public_inputs = PublicInputs::from_slice(&labeled.iter().map(|(_, x)| *x).collect::<Vec<_>>()).unwrap();
+        assert_eq!(public_inputs.election_id, election_id_to_field::<Fr>("e1"));
+        assert_eq!(public_inputs.sequence, Fr::zero());
+        assert_eq!(public_inputs.epoch, Fr::from(ballot_epoch(chrono::Utc::now())));
+        assert_eq!(public_inputs.labeled(), labeled);
+        
+        // /verify 请求使用同一约定
+        let verify = serde_json::json!({ "proof": "00", "public_inputs": reply["public_inputs"], "election_id": "e1" });
+        assert_eq!(decode_public_inputs::<Fr>(&verify).unwrap(), labeled);
+        
+        // 缺少公开输入、个数不符或不是十进制字符串时报错
+        assert!(decode_public_inputs::<Fr>(&serde_json::json!({ "proof": "00" })).is_err());
+        assert!(decode_public_inputs::<Fr>(&serde_json::json!({ "public_inputs": ["1", "2"] })).is_err());
+        assert!(decode_public_inputs::<Fr>(&serde_json::json!({ "public_inputs": [1, 2, 3, 4] })).is_err());
+    }
+    
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_vote_response_serde_round_trip() {
+        use crate::web::VoteResponse;
+        
+        let response = VoteResponse {
+            success: true,
+            message: "Vote submitted successfully and transaction sent to blockchain.".to_string(),
+            gas_cost: Some("21000".to_string()),
+            transaction_hash: None,
+            vk_fingerprint: Some("ab".repeat(32)),
+            public_inputs: Some(vec!["1".to_string(), "2".to_string(), "0".to_string(), "3".to_string()]),
+            ballot_id: Some("cd".repeat(32)),
+            nullifier: Some("0x01".to_string()),
+            commitment: Some("42".to_string()),
+            envelope: Some(ProofEnvelopeJson {
+                format_version: 2,
+                curve_id: 1,
+                circuit_version: 1,
+                compression: Compression::Compressed,
+                vk_fingerprint: "ab".repeat(32),
+                public_inputs: vec!["1".to_string(), "2".to_string(), "0".to_string(), "3".to_string()],
+                proof: "00".to_string(),
+            }),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["ballot_id"], "cd".repeat(32));
+        assert_eq!(json["envelope"]["proof"], "00");
+        assert_eq!(serde_json::from_value::<VoteResponse>(json).unwrap(), response);
+        
+        // 旧版响应中没有新增字段，解析为 None
+        let legacy = serde_json::json!({ "success": false, "message": "Duplicate vote", "gas_cost": null, "transaction_hash": null });
+        let legacy: VoteResponse = serde_json::from_value(legacy).unwrap();
+        assert_eq!((legacy.ballot_id, legacy.envelope), (None, None));
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_response_envelope_verifies_offline() {
+        use crate::web::{vote_route, AppState, VoteResponse};
+        
+        let state = AppState::new_mock();
+        let vk = state.setup_election("e1").await.unwrap();
+        let route = vote_route(state.clone());
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "election_id": "e1", "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: VoteResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert!(reply.success);
+        
+        // 投票人只凭响应中的信封和公布的验证密钥复核自己的选票
+        let envelope_json = reply.envelope.clone().unwrap();
+        let envelope = ProofEnvelope::<Fr, MockSNARK>::from_json(&envelope_json).unwrap();
+        let (system, _, _) = VoteSystem::<Fr, MockSNARK>::setup(election_id_to_field("e1"), &mut thread_rng()).unwrap();
+        assert!(system.verify_envelope(&vk, &envelope).unwrap().is_valid());
+        assert_eq!(reply.nullifier, Some(crate::field::fr_to_hex(envelope.public_inputs.nullifier)));
+        assert_eq!(reply.public_inputs, Some(envelope_json.public_inputs.clone()));
+        assert_eq!(reply.vk_fingerprint, Some(envelope_json.vk_fingerprint.clone()));
+        
+        // 选票标识是证明的摘要，响应前信封已保存
+        let ballot_id = reply.ballot_id.unwrap();
+        let digest = blake3::hash(&<MockSNARK as ProofSystem<Fr>>::serialize_proof(&envelope.proof).unwrap());
+        assert_eq!(ballot_id, digest.to_hex().to_string());
+        assert_eq!(state.recorded_envelope(&ballot_id).await, Some(envelope_json));
+        assert_eq!(state.recorded_envelope("unknown").await, None);
+        
+        // 选票承诺以十进制字符串给出
+        let commitment = reply.commitment.unwrap();
+        assert!(crate::field::fr_from_decimal::<Fr>(&commitment).is_ok());
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_election_tally_endpoint_counts_and_caches() {
+        use crate::web::{tally_route, vote_route, AppState, TallyResponse};
+        use warp::Filter;
+
+        // PRF 密钥下同一用户的防重标识固定，重新投票后较早的选票在计票时被取代
+        let state = AppState::<MockSNARK>::try_with_prf_key(PrfKey::generate(&mut thread_rng())).unwrap();
+        state.setup_election("e1").await.unwrap();
+        let route = vote_route(state.clone()).or(tally_route(state.clone()));
+        let ballots = [("alice", 1, 0), ("bob", 1, 0), ("carol", 1, 0), ("dave", 0, 0), ("erin", 0, 0), ("alice", 0, 1)];
+        for (user_id, vote, sequence) in ballots {
+            let body = serde_json::json!({
+                "vote": vote, "user_id": user_id, "election_id": "e1", "sequence": sequence,
+                "chain": "localhost", "gas_option": "relayer",
+            });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        }
+
+        // 选举结束前不公布
+        let resp = warp::test::request().method("GET").path("/elections/e1/tally").reply(&route).await;
+        assert_eq!(resp.status(), 403);
+        let resp = warp::test::request().method("GET").path("/elections/unknown/tally").reply(&route).await;
+        assert_eq!(resp.status(), 404);
+
+        state.advance_phase("e1").await.unwrap();
+        state.advance_phase("e1").await.unwrap();
+        let resp = warp::test::request().method("GET").path("/elections/e1/tally").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let tally: TallyResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(tally.counts, vec![3, 2]);
+        assert_eq!((tally.accepted, tally.duplicate_nullifiers, tally.superseded, tally.rejected), (5, 0, 1, 1));
+
+        // 选票存储未变时返回缓存的结果（计票时间不变）
+        let resp = warp::test::request().method("GET").path("/elections/e1/tally").reply(&route).await;
+        assert_eq!(serde_json::from_slice::<TallyResponse>(resp.body()).unwrap(), tally);
+
+        // 允许查询中间结果时选举进行中也可计票
+        let interim = AppState::new_mock().with_interim_results(true);
+        interim.setup_election("e2").await.unwrap();
+        let route = tally_route(interim);
+        let resp = warp::test::request().method("GET").path("/elections/e2/tally").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let tally: TallyResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!((tally.counts, tally.accepted), (vec![0, 0], 0));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_spoiled_ballot_counts_toward_turnout_only() {
+        use crate::web::{tally_route, vote_route, AppState, TallyResponse};
+        use warp::Filter;
+
+        let config = CircuitConfig { num_candidates: 3, allow_abstain: true, allow_spoiled: true };
+        assert_eq!(VoteChoice::Spoiled.slot(&config).unwrap(), 4);
+        assert_eq!(VoteChoice::from_slot(4, &config).unwrap(), VoteChoice::Spoiled);
+        assert!(VoteChoice::Spoiled.slot(&CircuitConfig::default()).is_err());
+        assert_eq!(serde_json::to_value(VoteChoice::Spoiled).unwrap(), "spoiled");
+
+        let state = AppState::new_mock().with_interim_results(true);
+        state.setup_election_with_params("e1", ElectionParams::from(config)).await.unwrap();
+        let route = vote_route(state.clone()).or(tally_route(state.clone()));
+        let ballots = [
+            ("alice", serde_json::json!(2)),
+            ("bob", serde_json::json!(0)),
+            ("carol", serde_json::json!("abstain")),
+            ("dave", serde_json::json!("spoiled")),
+            ("erin", serde_json::json!("spoiled")),
+        ];
+        for (user_id, vote) in ballots {
+            let body = serde_json::json!({
+                "vote": vote, "user_id": user_id, "election_id": "e1", "chain": "localhost", "gas_option": "relayer",
+            });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        }
+
+        // 废票计入投票率，但不计入任何候选人，也不算弃权
+        let resp = warp::test::request().method("GET").path("/elections/e1/tally").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let tally: TallyResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(tally.counts, vec![1, 0, 1]);
+        assert_eq!((tally.abstained, tally.spoiled, tally.turnout), (1, 2, 5));
+        assert_eq!((tally.accepted, tally.rejected), (5, 0));
+
+        // 同一用户的废票同样占用防重标识
+        let body = serde_json::json!({
+            "vote": "spoiled", "user_id": "dave", "election_id": "e1", "chain": "localhost", "gas_option": "relayer",
+        });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_election_state_transitions() {
+        use crate::web::ElectionState::{self, *};
+
+        assert_eq!(Created.open().unwrap(), Open);
+        assert_eq!(Open.open().unwrap(), Open);
+        assert_eq!(Open.close().unwrap(), Closed);
+        assert_eq!(Closed.tallied().unwrap(), Tallied);
+        // 结束投票可重复调用
+        assert_eq!(Closed.close().unwrap(), Closed);
+        assert_eq!(Tallied.close().unwrap(), Tallied);
+        assert_eq!(Tallied.tallied().unwrap(), Tallied);
+
+        // 不能跳过或回退状态
+        for result in [Created.close(), Created.tallied(), Open.tallied(), Closed.open(), Tallied.open()] {
+            assert!(matches!(result, Err(VoteError::WrongPhase(_))));
+        }
+        let accepting: Vec<ElectionState> = [Created, Open, Closed, Tallied].into_iter().filter(|s| s.accepts_votes()).collect();
+        assert_eq!(accepting, vec![Open]);
+        assert!(!Open.is_closed() && Closed.is_closed() && Tallied.is_closed());
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_election_lifecycle_end_to_end() {
+        use crate::web::{
+            close_election_route, create_election_route, open_election_route, tally_route, vote_route, AppState,
+            ElectionState, TallyResponse,
+        };
+        use warp::Filter;
+
+        let state = AppState::new_mock().with_authority_token("secret");
+        let route = create_election_route(state.clone())
+            .or(open_election_route(state.clone()))
+            .or(close_election_route(state.clone()))
+            .or(vote_route(state.clone()))
+            .or(tally_route(state.clone()));
+        let post = |path: &str| warp::test::request().method("POST").path(path).header("authorization", "Bearer secret");
+        let vote = |user_id: &str, vote: u64| {
+            serde_json::json!({ "vote": vote, "user_id": user_id, "election_id": "e1", "chain": "localhost", "gas_option": "relayer" })
+        };
+        let state_of = |body: &[u8]| serde_json::from_slice::<serde_json::Value>(body).unwrap()["state"].clone();
+
+        // 创建与状态变更都需要计票方令牌
+        let create = serde_json::json!({ "election_id": "e1" });
+        let resp = warp::test::request().method("POST").path("/elections").json(&create).reply(&route).await;
+        assert_eq!(resp.status(), 401);
+        let resp = post("/elections").json(&create).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(state_of(resp.body()), "created");
+        assert_eq!(post("/elections").json(&create).reply(&route).await.status(), 403);
+        assert_eq!(state.election_state("e1").await, Some(ElectionState::Created));
+
+        // 开放前不接受选票，也不能结束
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote("alice", 1)).reply(&route).await;
+        assert_eq!(resp.status(), 403);
+        assert_eq!(post("/elections/e1/close").reply(&route).await.status(), 403);
+
+        let resp = post("/elections/e1/open").reply(&route).await;
+        assert_eq!((resp.status().as_u16(), state_of(resp.body())), (200, "open".into()));
+        for (user_id, choice) in [("alice", 1), ("bob", 0), ("carol", 1)] {
+            let resp = warp::test::request().method("POST").path("/vote").json(&vote(user_id, choice)).reply(&route).await;
+            assert_eq!(resp.status(), 200);
+        }
+        let resp = warp::test::request().method("GET").path("/elections/e1/tally").reply(&route).await;
+        assert_eq!(resp.status(), 403);
+
+        // 结束投票可重复调用，结束后不再接受选票
+        for _ in 0..2 {
+            let resp = post("/elections/e1/close").reply(&route).await;
+            assert_eq!((resp.status().as_u16(), state_of(resp.body())), (200, "closed".into()));
+        }
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote("dave", 0)).reply(&route).await;
+        assert_eq!(resp.status(), 403);
+        assert_eq!(post("/elections/e1/open").reply(&route).await.status(), 403);
+
+        // 计票后进入 Tallied 状态
+        let resp = warp::test::request().method("GET").path("/elections/e1/tally").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let tally: TallyResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!((tally.counts, tally.accepted), (vec![1, 2], 3));
+        assert_eq!(state.election_state("e1").await, Some(ElectionState::Tallied));
+        let resp = post("/elections/e1/close").reply(&route).await;
+        assert_eq!((resp.status().as_u16(), state_of(resp.body())), (200, "tallied".into()));
+
+        assert_eq!(post("/elections/unknown/open").reply(&route).await.status(), 404);
+    }
+
+    #[test]
+    fn test_incremental_merkle_tree_matches_offline_root() {
+        use crate::merkle::{compute_root, IncrementalMerkleTree, VoterRoll};
+
+        // 每个长度下依次插入得到的根与整体重算一致，空树的根为空子树的根
+        let leaves: Vec<Fr> = (1..=8u64).map(Fr::from).collect();
+        let mut tree = IncrementalMerkleTree::<Fr>::new(3).unwrap();
+        assert_eq!(tree.root(), compute_root::<Fr>(3, &[]).unwrap());
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(tree.insert(*leaf).unwrap(), i as u64);
+            assert_eq!(tree.root(), compute_root(3, &leaves[..=i]).unwrap());
+        }
+        assert!(tree.insert(Fr::from(9u64)).is_err());
+        assert!(compute_root(3, &[leaves.clone(), vec![Fr::from(9u64)]].concat()).is_err());
+        assert!(IncrementalMerkleTree::<Fr>::new(0).is_err());
+
+        // 插入顺序决定树根
+        let reversed: Vec<Fr> = leaves.iter().rev().copied().collect();
+        assert_ne!(compute_root(3, &leaves).unwrap(), compute_root(3, &reversed).unwrap());
+
+        // 含重复承诺的批次整批不登记
+        let mut roll = VoterRoll::<Fr>::new(3).unwrap();
+        assert_eq!(roll.register(&leaves[..2]).unwrap(), 0);
+        let root = roll.tree().root();
+        for batch in [vec![leaves[2], leaves[2]], vec![leaves[2], leaves[0]]] {
+            assert!(matches!(roll.register(&batch), Err(VoteError::DuplicateCommitment(_))));
+            assert_eq!((roll.tree().len(), roll.tree().root()), (2, root));
+        }
+
+        // 名册满后拒绝登记
+        assert_eq!(roll.register(&leaves[2..]).unwrap(), 2);
+        assert_eq!(roll.tree().root(), compute_root(3, &leaves).unwrap());
+        assert!(matches!(roll.register(&[Fr::from(9u64)]), Err(VoteError::InvalidBallot(_))));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_create_election_admits_one() {
+        use crate::web::{AppState, ElectionState};
+        
+        // 同一选举的并发创建请求恰好一个成功，其余返回 WrongPhase
+        let state = AppState::new_mock();
+        let requests: Vec<_> = (0..4)
+            .map(|_| {
+                let state = state.clone();
+                tokio::spawn(async move { state.create_election("e1", ElectionParams::default()).await })
+            })
+            .collect();
+        let mut created = 0;
+        for request in requests {
+            match request.await.unwrap() {
+                Ok(_) => created += 1,
+                Err(e) => assert!(matches!(e, VoteError::WrongPhase(_)), "{}", e),
+            }
+        }
+        assert_eq!(created, 1);
+        assert_eq!(state.election_state("e1").await, Some(ElectionState::Created));
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_register_voters_builds_eligibility_tree() {
+        use crate::merkle::{compute_root, VOTER_TREE_DEPTH};
+        use crate::nullifier::poseidon_hash2;
+        use crate::web::{
+            create_election_route, merkle_root_route, open_election_route, register_voters_route, AppState,
+            MerkleRootResponse,
+        };
+        use warp::Filter;
+
+        let state = AppState::new_mock().with_authority_token("secret");
+        let route = create_election_route(state.clone())
+            .or(open_election_route(state.clone()))
+            .or(register_voters_route(state.clone()))
+            .or(merkle_root_route(state.clone()));
+        let post = |path: &str| warp::test::request().method("POST").path(path).header("authorization", "Bearer secret");
+        let body = |commitments: &[Fr]| {
+            serde_json::json!({ "commitments": commitments.iter().map(|c| field_to_decimal(*c)).collect::<Vec<_>>() })
+        };
+        let merkle_root = || async {
+            let resp = warp::test::request().method("GET").path("/elections/e1/merkle-root").reply(&route).await;
+            assert_eq!(resp.status(), 200);
+            serde_json::from_slice::<MerkleRootResponse>(resp.body()).unwrap()
+        };
+
+        let resp = post("/elections").json(&serde_json::json!({ "election_id": "e1" })).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let empty = merkle_root().await;
+        assert_eq!((empty.size, empty.depth), (0, VOTER_TREE_DEPTH));
+        assert_eq!(empty.root, field_to_decimal(compute_root::<Fr>(VOTER_TREE_DEPTH, &[]).unwrap()));
+
+        // 登记需要计票方令牌
+        let commitments: Vec<Fr> = (0..1000u64).map(|i| poseidon_hash2(Fr::from(i), Fr::from(42u64))).collect();
+        let resp =
+            warp::test::request().method("POST").path("/elections/e1/voters").json(&body(&commitments[..1])).reply(&route).await;
+        assert_eq!(resp.status(), 401);
+
+        // 分批登记 1000 名投票人，树根与离线按同一顺序重算的结果一致
+        for (i, batch) in commitments.chunks(250).enumerate() {
+            let resp = post("/elections/e1/voters").json(&body(batch)).reply(&route).await;
+            assert_eq!(resp.status(), 200);
+            let registered: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(registered["first_index"], i * 250);
+            assert_eq!(registered["size"], (i + 1) * 250);
+        }
+        let expected = field_to_decimal(compute_root(VOTER_TREE_DEPTH, &commitments).unwrap());
+        let roll = merkle_root().await;
+        assert_eq!((roll.root.as_str(), roll.size), (expected.as_str(), 1000));
+
+        // 重复的承诺整批拒绝，名册不变
+        let batch = [poseidon_hash2(Fr::from(1000u64), Fr::from(42u64)), commitments[7]];
+        let resp = post("/elections/e1/voters").json(&body(&batch)).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(resp.body()).unwrap()["code"], "duplicate_commitment");
+        assert_eq!(merkle_root().await.root, expected);
+
+        // 选举开放后名册固定
+        assert_eq!(post("/elections/e1/open").reply(&route).await.status(), 200);
+        let resp = post("/elections/e1/voters").json(&body(&batch[..1])).reply(&route).await;
+        assert_eq!(resp.status(), 403);
+        assert_eq!(merkle_root().await.size, 1000);
+
+        assert_eq!(post("/elections/unknown/voters").json(&body(&batch[..1])).reply(&route).await.status(), 404);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_elections_hosted_side_by_side_with_isolated_keys() {
+        use crate::web::{
+            election_verify_route, election_vk_route, election_vote_route, handle_rejection, AppState, VoteResponse,
+        };
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new();
+        state.setup_election("a").await.unwrap();
+        state.setup_election("b").await.unwrap();
+        assert_eq!(state.election_ids().await, vec!["a".to_string(), "b".to_string()]);
+        let route = election_vote_route(state.clone())
+            .or(election_verify_route(state.clone()))
+            .or(election_vk_route(state.clone()))
+            .recover(handle_rejection);
+        let vote = |election_id: &str| {
+            let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+            warp::test::request().method("POST").path(&format!("/elections/{}/vote", election_id)).json(&body)
+        };
+        let vk = |election_id: &str| warp::test::request().method("GET").path(&format!("/elections/{}/vk", election_id));
+
+        // 同一用户在两场选举中各投一票，防重标识按选举分别记录
+        let resp = vote("a").reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        let envelope = serde_json::from_slice::<VoteResponse>(resp.body()).unwrap().envelope.unwrap();
+        assert_eq!(vote("b").reply(&route).await.status(), 200);
+
+        // 选举 a 的证明只在 a 的验证密钥下成立
+        let body = serde_json::json!({ "proof": envelope.proof, "public_inputs": envelope.public_inputs });
+        for (election_id, valid) in [("a", true), ("b", false)] {
+            let path = format!("/elections/{}/verify", election_id);
+            let resp = warp::test::request().method("POST").path(&path).json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 200);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["valid"], valid, "election {}", election_id);
+        }
+        let (vk_a, vk_b) = (vk("a").reply(&route).await, vk("b").reply(&route).await);
+        let fingerprint = |body: &[u8]| serde_json::from_slice::<serde_json::Value>(body).unwrap()["vk_fingerprint"].clone();
+        assert_eq!(fingerprint(vk_a.body()), envelope.vk_fingerprint.as_str());
+        assert_ne!(fingerprint(vk_a.body()), fingerprint(vk_b.body()));
+
+        // 请求体中的选举标识须与路径一致
+        let body = serde_json::json!({
+            "vote": 1, "user_id": "bob", "election_id": "b", "chain": "localhost", "gas_option": "relayer",
+        });
+        let resp = warp::test::request().method("POST").path("/elections/a/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 400);
+
+        // 未知选举和未知路由返回 404 及错误响应体
+        for resp in [vote("c").reply(&route).await, vk("c").reply(&route).await] {
+            assert_eq!(resp.status(), 404);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["code"], "unknown_election");
+            assert!(reply["message"].as_str().unwrap().contains('c'));
+        }
+        let resp = warp::test::request().method("GET").path("/elections/a/unknown").reply(&route).await;
+        assert_eq!(resp.status(), 404);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "not_found");
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_proof_issued_before_restart_verifies_after() {
+        use crate::store::FileBackend;
+        use crate::web::{verify_route, vote_route, AppState, VoteResponse, NULLIFIER_STORE_FILE, PROVING_KEY_FILE, VERIFYING_KEY_FILE, VOTER_SECRET_KEY_FILE};
+        use warp::Filter;
+
+        type State = AppState<Groth16<E>, FileBackend>;
+        let dir = std::env::temp_dir().join(format!("zkp-vote-keys-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let verify = |state: &State, body: serde_json::Value| {
+            let route = verify_route(state.clone());
+            async move {
+                let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&route).await;
+                serde_json::from_slice::<serde_json::Value>(resp.body()).unwrap()
+            }
+        };
+
+        // 首次启动生成密钥并写入目录
+        let state = State::load_or_init(&dir).unwrap();
+        assert!(dir.join(PROVING_KEY_FILE).exists() && dir.join(VERIFYING_KEY_FILE).exists());
+        assert!(dir.join(NULLIFIER_STORE_FILE).exists() && dir.join(VOTER_SECRET_KEY_FILE).exists());
+        let route = vote_route(state.clone()).or(verify_route(state.clone()));
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        let envelope = serde_json::from_slice::<VoteResponse>(resp.body()).unwrap().envelope.unwrap();
+        drop((route, state));
+
+        // 重启后加载同一套密钥，重启前签发的证明仍然有效，已投票的用户不能再投
+        let state = State::load_or_init(&dir).unwrap();
+        let reply = verify(&state, serde_json::json!({ "envelope": envelope })).await;
+        assert_eq!(reply["valid"], true, "{}", reply);
+        assert_eq!(reply["vk_fingerprint"], envelope.vk_fingerprint.as_str());
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&vote_route(state.clone())).await;
+        assert_eq!(resp.status(), 409, "{}", String::from_utf8_lossy(resp.body()));
+        drop(state);
+
+        // 只剩一个密钥文件时拒绝启动
+        let vk_file = std::fs::read(dir.join(VERIFYING_KEY_FILE)).unwrap();
+        std::fs::remove_file(dir.join(VERIFYING_KEY_FILE)).unwrap();
+        assert!(matches!(State::load_or_init(&dir).err(), Some(VoteError::KeyMismatch(_))));
+        // 损坏的密钥文件同样拒绝启动
+        let mut corrupted = vk_file.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        std::fs::write(dir.join(VERIFYING_KEY_FILE), corrupted).unwrap();
+        assert!(State::load_or_init(&dir).is_err());
+
+        // 强制换新密钥后覆盖密钥文件，旧证明不再有效
+        let state = State::load_or_init_with(&dir, true).unwrap();
+        assert_ne!(std::fs::read(dir.join(VERIFYING_KEY_FILE)).unwrap(), vk_file);
+        let reply = verify(&state, serde_json::json!({ "proof": envelope.proof, "public_inputs": envelope.public_inputs })).await;
+        assert_eq!(reply["valid"], false, "{}", reply);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_prf_key_with_key_dir() {
+        use crate::store::FileBackend;
+        use crate::web::{vote_route, AppState, PRF_COMMITMENT_FILE};
+
+        type State = AppState<Groth16<E>, FileBackend>;
+        let dir = std::env::temp_dir().join(format!("zkp-vote-prf-keys-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let prf_key = || PrfKey::from_bytes(&[9u8; 32]);
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+
+        // 生成的默认密钥绑定 PRF 密钥承诺，承诺记录在密钥目录中
+        let state = State::load_or_init_with_prf_key(&dir, false, prf_key()).unwrap();
+        assert_eq!(state.prf_key_commitment(), Some(prf_key().commitment()));
+        assert!(dir.join(PRF_COMMITMENT_FILE).exists());
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&vote_route(state)).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+
+        // 重启时提供同一 PRF 密钥：加载同一套密钥，已投票的用户不能再投
+        let state = State::load_or_init_with_prf_key(&dir, false, prf_key()).unwrap();
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&vote_route(state)).await;
+        assert_eq!(resp.status(), 409, "{}", String::from_utf8_lossy(resp.body()));
+
+        // 不提供或提供不同的 PRF 密钥时拒绝启动
+        assert!(matches!(State::load_or_init(&dir).err(), Some(VoteError::KeyMismatch(_))));
+        let other = PrfKey::from_bytes(&[8u8; 32]);
+        assert!(matches!(State::load_or_init_with_prf_key(&dir, false, other).err(), Some(VoteError::KeyMismatch(_))));
+
+        // 记录的承诺被改成别的值（或不是合法十六进制）时，即使提供原 PRF 密钥也拒绝启动
+        let mismatched = crate::field::fr_to_hex(PrfKey::<Fr>::from_bytes(&[8u8; 32]).commitment());
+        std::fs::write(dir.join(PRF_COMMITMENT_FILE), mismatched).unwrap();
+        assert!(matches!(State::load_or_init_with_prf_key(&dir, false, prf_key()).err(), Some(VoteError::KeyMismatch(_))));
+        std::fs::write(dir.join(PRF_COMMITMENT_FILE), "not hex").unwrap();
+        assert!(matches!(State::load_or_init_with_prf_key(&dir, false, prf_key()).err(), Some(VoteError::KeyMismatch(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_rejected_after_restart() {
+        use crate::store::FileBackend;
+        use crate::web::{vote_route, AppState};
+        
+        let path = std::env::temp_dir().join(format!("zkp-vote-restart-nullifiers-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::zero(), &mut thread_rng()).unwrap();
+        // 模拟一次启动：同一套密钥、同一派生密钥，防重标识及序号从文件载入
+        let start = || {
+            let state = AppState::<Groth16<E>, FileBackend>::from_keys(pk.clone(), vk.clone(), FileBackend::open(&path).unwrap())
+                .unwrap()
+                .with_voter_secret_key(PrfKey::from_bytes(&[7u8; 32]));
+            vote_route(state)
+        };
+        let vote = |sequence: u64| serde_json::json!({
+            "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer", "sequence": sequence
+        });
+        
+        let route = start();
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(0)).reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        drop(route);
+        
+        // 重启后同一用户得到同一防重标识，再次投票被拒绝；以更大的序号重新投票仍然允许
+        let route = start();
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(0)).reply(&route).await;
+        assert_eq!(resp.status(), 409, "{}", String::from_utf8_lossy(resp.body()));
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(1)).reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        drop(route);
+        
+        // 重新投票的序号同样持久化，重启后不能重放
+        let route = start();
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(1)).reply(&route).await;
+        assert_eq!(resp.status(), 409, "{}", String::from_utf8_lossy(resp.body()));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_election_nullifiers_persist_across_restart() {
+        use crate::store::FileBackend;
+        use crate::web::{vote_route, AppState};
+        
+        let path = std::env::temp_dir().join(format!("zkp-vote-election-nullifiers-{}.log", std::process::id()));
+        let election_path = FileBackend::open(&path).unwrap().election_path("e1");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&election_path);
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::zero(), &mut thread_rng()).unwrap();
+        // 模拟一次启动：选举密钥重新生成，选举的防重标识从该选举自己的文件载入
+        let start = || async {
+            let state = AppState::<Groth16<E>, FileBackend>::from_keys(pk.clone(), vk.clone(), FileBackend::open(&path).unwrap())
+                .unwrap()
+                .with_voter_secret_key(PrfKey::from_bytes(&[7u8; 32]));
+            state.setup_election("e1").await.unwrap();
+            vote_route(state)
+        };
+        let vote = |election_id: Option<&str>| serde_json::json!({
+            "vote": 1, "user_id": "alice", "election_id": election_id, "chain": "localhost", "gas_option": "relayer"
+        });
+        
+        let route = start().await;
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(Some("e1"))).reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        drop(route);
+        assert_eq!(std::fs::read_to_string(&election_path).unwrap().lines().count(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 0);
+        
+        // 重启后同一用户在该选举中不能再投，默认选举的防重标识不受影响
+        let route = start().await;
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(Some("e1"))).reply(&route).await;
+        assert_eq!(resp.status(), 409, "{}", String::from_utf8_lossy(resp.body()));
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(None)).reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&election_path).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_rotated_keys_accepted_until_retirement() {
+        use crate::web::{rotate_vk_route, verify_route, vk_route, vote_route, AppState, VoteResponse};
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new().with_authority_token("authority-secret");
+        let route = vote_route(state.clone())
+            .or(verify_route(state.clone()))
+            .or(vk_route(state.clone()))
+            .or(rotate_vk_route(state.clone()));
+        let vote = |user_id: &str| {
+            let body = serde_json::json!({ "vote": 1, "user_id": user_id, "chain": "localhost", "gas_option": "relayer" });
+            warp::test::request().method("POST").path("/vote").json(&body)
+        };
+        let verify = |body: serde_json::Value| warp::test::request().method("POST").path("/verify").json(&body);
+        let rotate = |authorization: &str| {
+            warp::test::request()
+                .method("POST")
+                .path("/vk/rotate")
+                .header("authorization", authorization)
+                .json(&serde_json::json!({ "transition_secs": 5 }))
+        };
+
+        // 轮换前用旧密钥证明的选票
+        let resp = vote("alice").reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        let old = serde_json::from_slice::<VoteResponse>(resp.body()).unwrap().envelope.unwrap();
+
+        // 轮换需要计票方令牌
+        assert_eq!(rotate("Bearer wrong").reply(&route).await.status(), 401);
+        let resp = rotate("Bearer authority-secret").reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        let rotated: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        let retire_at: chrono::DateTime<chrono::Utc> = serde_json::from_value(rotated["retire_at"].clone()).unwrap();
+        assert_ne!(rotated["vk_fingerprint"], old.vk_fingerprint.as_str());
+        let resp = warp::test::request().method("GET").path("/vk").reply(&route).await;
+        let vk: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(vk["vk_fingerprint"], rotated["vk_fingerprint"]);
+        assert_eq!(state.active_verifying_keys().await.len(), 2);
+
+        // 过渡期内旧证明仍然有效，响应记录验证通过所用的旧密钥
+        let old_proof = serde_json::json!({ "proof": old.proof, "public_inputs": old.public_inputs });
+        let reply: serde_json::Value = serde_json::from_slice(verify(old_proof.clone()).reply(&route).await.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+        assert_eq!(reply["vk_fingerprint"], old.vk_fingerprint.as_str());
+        let reply: serde_json::Value =
+            serde_json::from_slice(verify(serde_json::json!({ "envelope": old })).reply(&route).await.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+
+        // 新选票用新密钥证明
+        let resp = vote("bob").reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        let new = serde_json::from_slice::<VoteResponse>(resp.body()).unwrap().envelope.unwrap();
+        assert_eq!(rotated["vk_fingerprint"], new.vk_fingerprint.as_str());
+        let new_proof = serde_json::json!({ "proof": new.proof, "public_inputs": new.public_inputs });
+        let reply: serde_json::Value = serde_json::from_slice(verify(new_proof.clone()).reply(&route).await.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+        assert_eq!(reply["vk_fingerprint"], new.vk_fingerprint.as_str());
+
+        // 停用后只接受新密钥
+        if let Ok(remaining) = (retire_at - chrono::Utc::now()).to_std() {
+            tokio::time::sleep(remaining + std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(state.active_verifying_keys().await.len(), 1);
+        let reply: serde_json::Value = serde_json::from_slice(verify(old_proof).reply(&route).await.body()).unwrap();
+        assert_eq!(reply["valid"], false);
+        assert!(reply.get("vk_fingerprint").is_none());
+        let resp = verify(serde_json::json!({ "envelope": old })).reply(&route).await;
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "key_mismatch");
+        let reply: serde_json::Value = serde_json::from_slice(verify(new_proof).reply(&route).await.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_accepted_proof_archived_to_fs_sink() {
+        use crate::sink::{archive, FsSink, RetryPolicy, S3Sink};
+        use crate::web::{vote_route, AppState, VoteResponse};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dir = std::env::temp_dir().join(format!("zkp-vote-sink-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let sink = Arc::new(FsSink::new(&dir));
+        let policy = RetryPolicy { max_attempts: 3, initial_backoff: Duration::from_millis(1) };
+        let state = AppState::new_mock().with_proof_sink(sink.clone(), policy);
+        let route = vote_route(state);
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: VoteResponse = serde_json::from_slice(resp.body()).unwrap();
+
+        // 归档在后台进行，文件名为选票标识，内容为证明的规范编码
+        let ballot_id = reply.ballot_id.unwrap();
+        let path = sink.path(&ballot_id);
+        for _ in 0..200 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let archived = std::fs::read(&path).unwrap();
+        assert_eq!(blake3::hash(&archived).to_hex().to_string(), ballot_id);
+        let envelope = ProofEnvelope::<Fr, MockSNARK>::from_json(&reply.envelope.unwrap()).unwrap();
+        assert_eq!(archived, <MockSNARK as ProofSystem<Fr>>::serialize_proof(&envelope.proof).unwrap());
+
+        // 不可用的归档目标重试用尽后放弃，非法的对象键不写入
+        assert!(!archive(&S3Sink::new("http://localhost:9000", "proofs"), policy, &ballot_id, &archived).await);
+        assert!(!archive(&*sink, policy, "../escape", &archived).await);
+        assert!(!dir.parent().unwrap().join("escape.proof").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // 向进程内运行的服务发送一个 JSON 请求（HTTP/1.1，Connection: close），返回状态码和响应体
+    #[cfg(feature = "server")]
+    async fn post_json(addr: std::net::SocketAddr, path: &str, body: &serde_json::Value) -> (u16, serde_json::Value) {
+        let body = body.to_string();
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            addr,
+            body.len(),
+            body
+        );
+        exchange(addr, &request).await
+    }
+    
+    // 向进程内运行的服务发送一个 GET 请求，返回状态码和响应体
+    #[cfg(feature = "server")]
+    async fn get_json(addr: std::net::SocketAddr, path: &str) -> (u16, serde_json::Value) {
+        exchange(addr, &format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, addr)).await
+    }
+    
+    #[cfg(feature = "server")]
+    async fn exchange(addr: std::net::SocketAddr, request: &str) -> (u16, serde_json::Value) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_duplicate_votes_rejected_with_409() {
+        use crate::web::{vote_route, AppState};
+        
+        let nullifiers = NullifierSet::new();
+        let state = AppState::<MockSNARK, NullifierSet>::with_nullifier_backend(nullifiers);
+        let (addr, server) = warp::serve(vote_route(state)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        
+        // 同一用户的多个请求同时到达：恰好一个被接受，其余返回 409
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        let requests: Vec<_> = (0..8)
+            .map(|_| {
+                let body = body.clone();
+                tokio::spawn(async move { post_json(addr, "/vote", &body).await })
+            })
+            .collect();
+        let mut accepted = 0;
+        for request in requests {
+            let (status, reply) = request.await.unwrap();
+            match status {
+                200 => {
+                    assert_eq!(reply["success"], true);
+                    accepted += 1;
+                }
+                409 => {
+                    assert_eq!(reply["success"], false);
+                    assert_eq!(reply["code"], "duplicate_nullifier");
+                }
+                _ => panic!("unexpected status {}: {}", status, reply),
+            }
+        }
+        assert_eq!(accepted, 1);
+        
+        // 之后再投同样被拒绝，其他用户不受影响
+        let (status, reply) = post_json(addr, "/vote", &body).await;
+        assert_eq!((status, reply["code"].as_str()), (409, Some("duplicate_nullifier")));
+        let bob = serde_json::json!({ "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" });
+        assert_eq!(post_json(addr, "/vote", &bob).await.0, 200);
+    }
+
+    #[test]
+    fn test_verification_types_are_send_sync() {
+        // 验证路径上跨线程共享的类型，新增共享状态时须加入此列表
+        fn assert_send_sync<T: Send + Sync>() {}
+        
+        assert_send_sync::<VoteSystem<Fr, Groth16<E>>>();
+        assert_send_sync::<<Groth16<E> as ProofSystem<Fr>>::VerifyingKey>();
+        assert_send_sync::<<Groth16<E> as ProofSystem<Fr>>::Proof>();
+        assert_send_sync::<BallotPublicInputs<Fr>>();
+        assert_send_sync::<ProofEnvelope<Fr, Groth16<E>>>();
+        #[cfg(feature = "server")]
+        {
+            assert_send_sync::<crate::web::AppState<Groth16<E>>>();
+            assert_send_sync::<JobQueue>();
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_parallel_verifies_share_one_vote_system() {
+        use std::sync::Arc;
+        
+        let mut rng = thread_rng();
+        let election_id = Fr::from(2024u64);
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(election_id, &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id,
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        let mut forged = public_inputs.clone();
+        forged.nullifier += Fr::one();
+        
+        // 多个线程同时借用同一实例验证，不需要任何锁
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let inputs = if i % 2 == 0 { &public_inputs } else { &forged };
+                    let (system, vk, proof) = (&system, &vk, &proof);
+                    scope.spawn(move || system.verify_ballot(vk, inputs, proof).unwrap().is_valid() == (i % 2 == 0))
+                })
+                .collect();
+            assert!(handles.into_iter().all(|handle| handle.join().unwrap()));
+        });
+        
+        // 并发的异步验证共享同一实例和验证密钥，结果与逐个验证一致
+        let (system, vk) = (Arc::new(system), Arc::new(vk));
+        let tasks: Vec<_> = (0..64)
+            .map(|i| {
+                let inputs = if i % 4 == 3 { forged.clone() } else { public_inputs.clone() };
+                let (system, vk, proof) = (system.clone(), vk.clone(), proof.clone());
+                tokio::spawn(async move { system.verify_ballot_async(vk, inputs, proof).await.unwrap().is_valid() == (i % 4 != 3) })
+            })
+            .collect();
+        for task in tasks {
+            assert!(task.await.unwrap());
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_votes_tally_matches_accepted_ballots() {
+        use crate::web::{vote_route, AppState};
+
+        let state = AppState::new_mock();
+        let (addr, server) = warp::serve(vote_route(state.clone())).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        // 32 个用户同时投票，其中前 8 个各重复提交一次
+        let requests: Vec<_> = (0..32)
+            .chain(0..8)
+            .map(|i| {
+                let body = serde_json::json!({
+                    "vote": i % 2, "user_id": format!("voter-{}", i), "chain": "localhost", "gas_option": "relayer",
+                });
+                tokio::spawn(async move { post_json(addr, "/vote", &body).await.0 })
+            })
+            .collect();
+        let mut accepted = 0;
+        for request in requests {
+            match request.await.unwrap() {
+                200 => accepted += 1,
+                409 => {}
+                status => panic!("unexpected status {}", status),
+            }
+        }
+
+        // 运行计票恰好计入每张被接受的选票
+        assert_eq!(accepted, 32);
+        let counts = state.running_counts().await;
+        assert_eq!(counts.iter().sum::<u64>(), accepted);
+        assert_eq!(counts, vec![16, 16]);
+        let params = state.running_tally_params().await;
+        state.finalize_running_tally().await.verify(&params).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_vote_job_queue_polled_until_done() {
+        use crate::web::{vote_route, vote_status_route, AppState, JobStatusResponse};
+        use warp::Filter;
+        use std::time::Duration;
+
+        let state = AppState::new_mock().with_job_queue(2, Duration::from_millis(500));
+        let routes = vote_route(state.clone()).or(vote_status_route(state.clone()));
+        let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let poll = move |job_id: String| async move { get_json(addr, &format!("/vote/status/{}", job_id)).await };
+
+        // 投票立即返回 202 及任务标识，重复投票仍当即返回 409
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        let (status, reply) = post_json(addr, "/vote", &body).await;
+        assert_eq!(status, 202);
+        let queued: JobStatusResponse = serde_json::from_value(reply).unwrap();
+        assert_eq!(queued.status, JobStatus::Queued);
+        assert_eq!(post_json(addr, "/vote", &body).await.0, 409);
+
+        // 多个客户端同时轮询同一任务直到完成
+        let pollers: Vec<_> = (0..4)
+            .map(|_| {
+                let job_id = queued.job_id.clone();
+                tokio::spawn(async move {
+                    loop {
+                        let (status, reply) = poll(job_id.clone()).await;
+                        assert_eq!(status, 200);
+                        let reply: JobStatusResponse = serde_json::from_value(reply).unwrap();
+                        if reply.status.is_finished() {
+                            return reply.status;
+                        }
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                })
+            })
+            .collect();
+        for poller in pollers {
+            let JobStatus::Done { ballot_id, proof_envelope } = poller.await.unwrap() else {
+                panic!("proving job failed");
+            };
+            let envelope = proof_envelope.unwrap();
+            assert_eq!(state.recorded_envelope(&ballot_id.unwrap()).await, Some(envelope));
+        }
+        assert_eq!(state.running_counts().await, vec![0, 1]);
+
+        // 证明失败的任务报告错误码，投票资格被释放
+        let invalid = serde_json::json!({ "vote": 7, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" });
+        let (status, reply) = post_json(addr, "/vote", &invalid).await;
+        assert_eq!(status, 202);
+        let job_id = reply["job_id"].as_str().unwrap().to_string();
+        let failed = loop {
+            let (_, reply) = poll(job_id.clone()).await;
+            if reply["status"] == "failed" {
+                break reply;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(failed["code"], "invalid_ballot");
+        let bob = serde_json::json!({ "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" });
+        assert_eq!(post_json(addr, "/vote", &bob).await.0, 202);
+
+        // 结果保留期过后及未知任务返回 404
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        for job_id in [queued.job_id, "unknown".to_string()] {
+            let (status, reply) = poll(job_id).await;
+            assert_eq!((status, reply["code"].as_str()), (404, Some("unknown_job")));
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_job_queue_bounded_and_panicking_job_fails() {
+        use crate::fingerprint::ProofEnvelopeJson;
+        use crate::jobs::JobQueue;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        async fn panicking_job() -> Result<(Option<String>, Option<ProofEnvelopeJson>), VoteError> {
+            panic!("prover crashed")
+        }
+        let finished = |queue: Arc<JobQueue>, job_id: &'static str| async move {
+            loop {
+                let status = queue.status(job_id).await.unwrap();
+                if status.is_finished() {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+
+        // 未结束的任务达到上限时新任务被拒绝，不登记
+        let queue = Arc::new(JobQueue::new(1, Duration::from_secs(60)).with_max_pending(1));
+        let (release, blocked) = tokio::sync::oneshot::channel::<()>();
+        queue.spawn("a".to_string(), async move {
+            let _ = blocked.await;
+            Ok((None, None))
+        }).await.unwrap();
+        assert!(matches!(queue.spawn("b".to_string(), async { Ok((None, None)) }).await, Err(VoteError::AtCapacity)));
+        assert!(matches!(queue.status("b").await, Err(VoteError::UnknownJob(_))));
+        release.send(()).unwrap();
+        assert!(matches!(finished(queue.clone(), "a").await, JobStatus::Done { .. }));
+
+        // 任务崩溃时记为失败并释放名额，不会一直停在 proving
+        queue.spawn("c".to_string(), panicking_job()).await.unwrap();
+        match finished(queue.clone(), "c").await {
+            JobStatus::Failed { code, .. } => assert_eq!(code, "internal_error"),
+            status => panic!("unexpected status {:?}", status),
+        }
+        assert_eq!(queue.pending(), 0);
+        queue.spawn("d".to_string(), async { Ok((None, None)) }).await.unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_rejected_with_503_when_job_queue_full() {
+        use crate::web::{vote_route, AppState};
+        use std::time::Duration;
+
+        // 队列已满时返回 503，投票资格随即释放，稍后重试不会被当作重复投票
+        let state = AppState::new_mock().with_bounded_job_queue(1, 0, Duration::from_secs(60));
+        let route = vote_route(state);
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        for _ in 0..2 {
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 503);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["code"], "at_capacity");
+        }
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_batch_rejects_duplicate_user() {
+        use crate::web::{vote_batch_route, AppState};
+        
+        let state = AppState::new_mock();
+        let route = vote_batch_route(state);
+        
+        // 批次中alice出现两次
+        let body = serde_json::json!([
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/vote/batch")
+            .json(&body)
+            .reply(&route)
+            .await;
+        assert_eq!(resp.status(), 200);
+        
+        let results: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(results.len(), 3);
+        
+        // bob的选票被接受
+        assert_eq!(results[1]["success"], true);
+        assert!(results[1]["proof"].is_string());
+        
+        // alice的两张选票中恰好一张被拒绝
+        let alice_accepted = [&results[0], &results[2]]
+            .iter()
+            .filter(|r| r["success"] == true)
+            .count();
+        assert_eq!(alice_accepted, 1);
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_verify_batch_returns_aligned_results() {
+        use crate::web::{audit_route, verify_batch_route, vote_batch_route, AppState};
+        use warp::Filter;
+        
+        let state = AppState::new_mock();
+        let route = vote_batch_route(state.clone())
+            .or(verify_batch_route(state.clone()))
+            .or(audit_route(state));
+        
+        // 通过批量投票接口获得两份有效证明（防重标识由服务端派生）
+        let body = serde_json::json!([
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        let alice_proof = votes[0]["proof"].as_str().unwrap();
+        let bob_proof = votes[1]["proof"].as_str().unwrap();
+        let alice_inputs = votes[0]["public_inputs"].clone();
+        let bob_inputs = votes[1]["public_inputs"].clone();
+        
+        // 公开输入顺序：[防重标识, 选举标识, 选票序号, 时段]
+        assert_ne!(alice_inputs[0], bob_inputs[0]);
+        let mut forged_inputs = bob_inputs.clone();
+        forged_inputs[0] = serde_json::json!("999");
+        let body = serde_json::json!([
+            { "proof": alice_proof, "public_inputs": alice_inputs },
+            { "proof": bob_proof, "public_inputs": forged_inputs },
+            { "proof": "not hex", "public_inputs": alice_inputs },
+            { "proof": bob_proof, "public_inputs": bob_inputs },
+            { "proof": alice_proof, "public_inputs": ["111", "0"] },
+        ]);
+        let resp = warp::test::request().method("POST").path("/verify/batch").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        
+        let results: Vec<Option<bool>> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(results, vec![Some(true), Some(false), None, Some(true), None]);
+        
+        // 每个条目都记入审计日志，外部取回后可复核哈希链
+        let resp = warp::test::request().method("GET").path("/audit").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        let entries: Vec<AuditEntry> = serde_json::from_value(reply["entries"].clone()).unwrap();
+        crate::audit::verify_chain(&entries).unwrap();
+        assert_eq!(reply["head"], entries.last().unwrap().hash);
+        let outcomes: Vec<&str> = entries.iter().map(|entry| entry.event.outcome.as_str()).collect();
+        assert_eq!(outcomes, ["valid", "proof_rejected", "invalid_ballot", "valid", "verification_failed"]);
+        assert_eq!(entries[0].event.nullifier.as_ref(), alice_inputs[0].as_str().map(str::to_string).as_ref());
+        assert_eq!(entries[2].event.nullifier, None);
+    }
+    
+    #[test]
+    fn test_audit_log_hash_chain() {
+        use crate::audit::{verify_chain, AuditAction};
+        
+        let mut log = AuditLog::new();
+        assert!(log.is_empty());
+        assert_eq!(log.head(), hex::encode([0u8; 32]));
+        for (i, outcome) in ["valid", "proof_rejected", "duplicate_nullifier", "valid"].into_iter().enumerate() {
+            let entry = log.append(AuditEvent {
+                action: AuditAction::Verify,
+                election_id: Some("2024".to_string()),
+                nullifier: Some(format!("{}", 1000 + i)),
+                outcome: outcome.to_string(),
+            });
+            assert_eq!(entry.index, i as u64);
+        }
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.head(), log.entries()[3].hash);
+        verify_chain(log.entries()).unwrap();
+        
+        // 每条记录链接到上一条
+        for pair in log.entries().windows(2) {
+            assert_eq!(pair[1].prev_hash, pair[0].hash);
+        }
+        
+        // 篡改记录内容：摘要不再一致
+        let mut entries = log.entries().to_vec();
+        entries[1].event.outcome = "valid".to_string();
+        let Err(err) = verify_chain(&entries) else { panic!("tampered outcome must be detected") };
+        assert_eq!(err.code(), "audit_integrity");
+        assert!(err.to_string().contains("entry 1"));
+        
+        // 篡改后重新计算摘要：下一条记录的链接断开
+        entries[1].hash = entries[1].compute_hash();
+        let Err(err) = verify_chain(&entries) else { panic!("recomputed hash must break the chain") };
+        assert!(err.to_string().contains("entry 2"));
+        
+        // 删除记录：序号不连续
+        let mut entries = log.entries().to_vec();
+        entries.remove(1);
+        assert!(verify_chain(&entries).is_err());
+        
+        // 截断末尾的记录无法由链本身发现，需对照公布的链头摘要
+        verify_chain(&log.entries()[..2]).unwrap();
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_rejected_after_deadline() {
+        use crate::web::{vote_route, AppState};
+        use chrono::{Duration, Utc};
+        
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        
+        // 截止时间已过：拒绝
+        let state = AppState::new_mock().with_election_end(Utc::now() - Duration::hours(1));
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&vote_route(state)).await;
+        assert_eq!(resp.status(), 403);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["message"], "Voting closed");
+        
+        // 截止时间未到：接受
+        let state = AppState::new_mock().with_election_end(Utc::now() + Duration::hours(1));
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&vote_route(state)).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], true);
+    }
+    
+    #[test]
+    fn test_vote_choice_boundaries() {
+        let binary = CircuitConfig::default();
+        assert_eq!(VoteChoice::No.to_field::<Fr>(&binary).unwrap(), Fr::zero());
+        assert_eq!(VoteChoice::Yes.to_field::<Fr>(&binary).unwrap(), Fr::one());
+        // 以数字提交的旧客户端选票
+        assert_eq!(VoteChoice::Candidate(1).to_field::<Fr>(&binary).unwrap(), Fr::one());
+        for vote in [2u16, 3, 127, 128, 254, 255, u16::MAX] {
+            assert!(matches!(VoteChoice::Candidate(vote).to_field::<Fr>(&binary), Err(VoteError::InvalidBallot(_))));
+        }
+        assert!(matches!(VoteChoice::Abstain.to_field::<Fr>(&binary), Err(VoteError::InvalidBallot(_))));
+        
+        // 多候选人：[0, n) 之内接受，No/Yes 没有意义
+        let three = CircuitConfig::with_candidates(3);
+        assert_eq!(VoteChoice::Candidate(2).to_field::<Fr>(&three).unwrap(), Fr::from(2u64));
+        assert!(matches!(VoteChoice::Candidate(3).to_field::<Fr>(&three), Err(VoteError::InvalidBallot(_))));
+        assert!(matches!(VoteChoice::Yes.to_field::<Fr>(&three), Err(VoteError::InvalidBallot(_))));
+        let max = CircuitConfig::with_candidates(MAX_CANDIDATES);
+        assert_eq!(VoteChoice::Candidate(255).to_field::<Fr>(&max).unwrap(), Fr::from(255u64));
+        
+        // 弃权占用候选人之后的槽位
+        let abstain = CircuitConfig { num_candidates: 3, allow_abstain: true, allow_spoiled: false };
+        assert_eq!(VoteChoice::Abstain.to_field::<Fr>(&abstain).unwrap(), Fr::from(3u64));
+        assert!(CircuitConfig { num_candidates: MAX_CANDIDATES, allow_abstain: true, allow_spoiled: false }.validate().is_err());
+        assert_ne!(abstain.digest(), three.digest());
+    }
+    
+    #[test]
+    fn test_vote_choice_field_round_trip() {
+        let binary = CircuitConfig::default();
+        let candidates = CircuitConfig::with_candidates(5);
+        let abstain = CircuitConfig { num_candidates: 2, allow_abstain: true, allow_spoiled: false };
+        let cases = [
+            (VoteChoice::No, binary),
+            (VoteChoice::Yes, binary),
+            (VoteChoice::Candidate(0), candidates),
+            (VoteChoice::Candidate(4), candidates),
+            (VoteChoice::Abstain, abstain),
+            (VoteChoice::Yes, abstain),
+        ];
+        for (choice, config) in cases {
+            let value = choice.to_field::<Fr>(&config).unwrap();
+            assert_eq!(VoteChoice::from_field(value, &config).unwrap(), choice, "{:?}", choice);
+        }
+        // 二选一选票上的候选人编号规范为 No/Yes
+        let value = VoteChoice::Candidate(0).to_field::<Fr>(&binary).unwrap();
+        assert_eq!(VoteChoice::from_field(value, &binary).unwrap(), VoteChoice::No);
+        // 超出槽位范围的值无法还原
+        for value in [Fr::from(5u64), Fr::from(u64::MAX), -Fr::one()] {
+            assert!(matches!(VoteChoice::from_field(value, &candidates), Err(VoteError::InvalidBallot(_))));
+        }
+        
+        // JSON 表示
+        for (choice, json) in [
+            (VoteChoice::No, r#""no""#),
+            (VoteChoice::Yes, r#""yes""#),
+            (VoteChoice::Abstain, r#""abstain""#),
+            (VoteChoice::Candidate(3), "3"),
+        ] {
+            assert_eq!(serde_json::to_string(&choice).unwrap(), json);
+            assert_eq!(serde_json::from_str::<VoteChoice>(json).unwrap(), choice);
+        }
+        for json in [r#""maybe""#, "-1", "65536", "1.5", "null"] {
+            assert!(serde_json::from_str::<VoteChoice>(json).is_err(), "{}", json);
+        }
+    }
+    
+    #[test]
+    fn test_vote_circuit_builder() {
+        let config = CircuitConfig { num_candidates: 3, allow_abstain: true, allow_spoiled: false };
+        let build = |choice| {
+            VoteCircuitBuilder::<Fr>::new(config)
+                .choice(choice)
+                .user_secret(b"builder-secret")
+                .randomness(Fr::from(67890u64))
+                .election_id(election_id_to_field("builder-election"))
+                .sequence(2)
+                .build()
+        };
+        
+        let circuit = build(VoteChoice::Abstain).unwrap();
+        assert_eq!(circuit.num_candidates, 4);
+        assert_eq!(circuit.ballot.vote, Fr::from(3u64));
+        assert_eq!(circuit.ballot.sequence, Fr::from(2u64));
+        assert_eq!(
+            circuit.ballot.public_inputs().nullifier,
+            expected_nullifier(b"builder-secret", election_id_to_field::<Fr>("builder-election"))
+        );
+        assert!(VoteSystem::<Fr, Groth16<E>>::check_circuit(circuit).unwrap());
+        assert!(VoteSystem::<Fr, Groth16<E>>::check_circuit(build(VoteChoice::Candidate(2)).unwrap()).unwrap());
+        
+        // 配置不允许的选择和缺少的私有输入都在构造时拒绝
+        assert!(matches!(build(VoteChoice::Yes), Err(VoteError::InvalidBallot(_))));
+        assert!(matches!(build(VoteChoice::Candidate(3)), Err(VoteError::InvalidBallot(_))));
+        assert!(matches!(
+            VoteCircuitBuilder::<Fr>::new(config).choice(VoteChoice::Candidate(0)).randomness(Fr::one()).build(),
+            Err(VoteError::InvalidBallot(_))
+        ));
+    }
+    
+    #[test]
+    fn test_candidate_circuit_range() {
+        let ballot = |vote: u64| VoteCircuit {
+            vote: Fr::from(vote),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        for num_candidates in [2usize, 3, 5] {
+            for vote in 0..num_candidates as u64 + 2 {
+                let circuit = CandidateVoteCircuit { ballot: ballot(vote), num_candidates, params_digest: None, prf: None };
+                assert_eq!(
+                    VoteSystem::<Fr, Groth16<E>>::check_circuit(circuit).unwrap(),
+                    vote < num_candidates as u64
+                );
+            }
+        }
+        
+        // 两个候选人时与 VoteCircuit 的约束系统相同
+        let constraints = |circuit: &dyn Fn(ConstraintSystemRef<Fr>)| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit(cs.clone());
+            (cs.num_constraints(), cs.num_witness_variables())
+        };
+        assert_eq!(
+            constraints(&|cs| VoteCircuit::<Fr>::blank(Fr::zero()).generate_constraints(cs).unwrap()),
+            constraints(&|cs| CandidateVoteCircuit::<Fr>::blank(2, Fr::zero()).generate_constraints(cs).unwrap())
+        );
+        
+        assert!(matches!(
+            VoteSystem::<Fr, Groth16<E>>::setup_with_candidates(1, Fr::zero(), &mut thread_rng()),
+            Err(VoteError::Setup(_))
+        ));
+    }
+    
+    #[test]
+    fn test_key_consistency_names_suspect_side() {
+        use crate::keys::check_consistency;
+        
+        let mut rng = thread_rng();
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let (_, other_pk, other_vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        check_consistency::<Fr, Groth16<E>>(&pk, &vk).unwrap();
+        
+        // 其他设置、其他选举或其他候选人数量的验证密钥：验证密钥可疑
+        let (_, _, election_vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2025u64), &mut rng).unwrap();
+        let (_, _, candidates_vk) =
+            VoteSystem::<Fr, Groth16<E>>::setup_with_candidates(3, Fr::from(2024u64), &mut rng).unwrap();
+        for wrong_vk in [&other_vk, &election_vk, &candidates_vk] {
+            match check_consistency::<Fr, Groth16<E>>(&pk, wrong_vk) {
+                Err(VoteError::KeyMismatch(msg)) => assert!(msg.contains("the verifying key is suspect"), "{}", msg),
+                other => panic!("expected KeyMismatch, got {:?}", other.map(|_| ())),
+            }
+        }
+        
+        // 证明密钥的群元素与内嵌验证密钥不配：证明密钥可疑
+        let mut spliced = pk.clone();
+        spliced.delta_g1 = other_pk.delta_g1;
+        match check_consistency::<Fr, Groth16<E>>(&spliced, &vk) {
+            Err(VoteError::KeyMismatch(msg)) => {
+                assert!(msg.contains("delta_g1"), "{}", msg);
+                assert!(msg.contains("the proving key is suspect"), "{}", msg);
+            }
+            other => panic!("expected KeyMismatch, got {:?}", other.map(|_| ())),
+        }
+        
+        assert!(crate::client::check_key_pair(&pk, &vk).is_ok());
+        assert!(matches!(crate::client::check_key_pair(&pk, &other_vk), Err(VoteError::KeyMismatch(_))));
+        
+        #[cfg(feature = "server")]
+        {
+            use crate::store::MemoryBackend;
+            use crate::web::AppState;
+            assert!(matches!(
+                AppState::<Groth16<E>>::from_keys(pk, other_vk, MemoryBackend::default()),
+                Err(VoteError::KeyMismatch(_))
+            ));
+        }
+    }
+    
+    #[test]
+    fn test_keys_are_bound_to_election() {
+        let mut rng = thread_rng();
+        let (system, pk_a, vk_a) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let (_, _, vk_b) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2025u64), &mut rng).unwrap();
+        let ballot = |election_id: u64| VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(election_id),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        
+        // 选举 A 的密钥生成的证明只能用选举 A 的验证密钥通过验证
+        let inputs = ballot(2024).public_inputs().to_vec();
+        let proof = system.vote(&pk_a, ballot(2024), &mut rng).unwrap();
+        assert!(system.verify(&vk_a, &inputs, &proof).unwrap());
+        assert!(!system.verify(&vk_b, &inputs, &proof).unwrap());
+        
+        // 用选举 A 的证明密钥证明选举 B 的选票：两个验证密钥都不接受
+        let inputs = ballot(2025).public_inputs().to_vec();
+        let proof = system.vote(&pk_a, ballot(2025), &mut rng).unwrap();
+        assert!(!system.verify(&vk_a, &inputs, &proof).unwrap());
+        assert!(!system.verify(&vk_b, &inputs, &proof).unwrap());
+    }
+    
+    #[test]
+    fn test_election_params_bind_keys() {
+        // 缺省字段取默认值，摘要只由参数内容决定
+        let abstain: ElectionParams = serde_json::from_str(r#"{"num_candidates": 3, "allow_abstain": true}"#).unwrap();
+        assert_eq!(abstain, ElectionParams::from(CircuitConfig { num_candidates: 3, allow_abstain: true, allow_spoiled: false }));
+        assert_eq!(abstain.hash_scheme, HashScheme::Poseidon);
+        let json = serde_json::to_string(&abstain).unwrap();
+        assert_eq!(serde_json::from_str::<ElectionParams>(&json).unwrap().digest(), abstain.digest());
+        let four = ElectionParams::from(CircuitConfig::with_candidates(4));
+        assert_eq!(abstain.config().num_slots(), four.config().num_slots());
+        assert_ne!(abstain.digest(), four.digest());
+        assert_ne!(ElectionParams { merkle_depth: 20, ..four }.digest(), four.digest());
+        
+        // 不支持的参数在生成密钥前拒绝
+        assert!(matches!(ElectionParams { circuit_version: 1, ..four }.validate(), Err(VoteError::UnsupportedVersion(_))));
+        assert!(matches!(ElectionParams { merkle_depth: 20, ..four }.validate(), Err(VoteError::InvalidBallot(_))));
+        let mut rng = thread_rng();
+        let election_id = Fr::from(2024u64);
+        assert!(matches!(
+            VoteSystem::<Fr, Groth16<E>>::setup_with_params(&ElectionParams { merkle_depth: 20, ..four }, election_id, &mut rng),
+            Err(VoteError::Setup(_))
+        ));
+        
+        // 槽位数量相同的两组参数得到不同的密钥，证明只能在生成时所用参数的验证密钥下通过验证
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup_with_params(&abstain, election_id, &mut rng).unwrap();
+        let (_, _, vk_four) = VoteSystem::<Fr, Groth16<E>>::setup_with_params(&four, election_id, &mut rng).unwrap();
+        let (_, _, vk_config) =
+            VoteSystem::<Fr, Groth16<E>>::setup_with_config(&abstain.config(), election_id, &mut rng).unwrap();
+        assert_ne!(vk_fingerprint::<Fr, Groth16<E>>(&vk), vk_fingerprint::<Fr, Groth16<E>>(&vk_four));
+        assert_ne!(vk_fingerprint::<Fr, Groth16<E>>(&vk), vk_fingerprint::<Fr, Groth16<E>>(&vk_config));
+        
+        let circuit = VoteCircuitBuilder::<Fr>::from_params(&abstain)
+            .choice(VoteChoice::Abstain)
+            .user_secret(b"params-secret")
+            .randomness(Fr::from(67890u64))
+            .election_id(election_id)
+            .build()
+            .unwrap();
+        assert_eq!(circuit.params_digest, Some(abstain.digest_field()));
+        let inputs = circuit.ballot.public_inputs().to_vec();
+        let proof = <Groth16<E> as ProofSystem<Fr>>::prove(&pk, circuit, &mut rng).unwrap();
+        assert!(system.verify(&vk, &inputs, &proof).unwrap());
+        assert!(!system.verify(&vk_four, &inputs, &proof).unwrap());
+        assert!(!system.verify(&vk_config, &inputs, &proof).unwrap());
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_zero_accepted_and_invalid_choices_rejected() {
+        use crate::web::{vote_route, AppState};
+        
+        let state = AppState::new_mock();
+        let route = vote_route(state);
+        
+        // vote = 0 是有效的反对票
+        let body = serde_json::json!({ "vote": 0, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], true);
+        
+        // 超出 {0, 1} 的选择被拒绝，而不是被当作 0
+        for (user, vote) in [("bob", 2), ("carol", 255)] {
+            let body = serde_json::json!({ "vote": vote, "user_id": user, "chain": "localhost", "gas_option": "relayer" });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 400);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["code"], "invalid_ballot");
+        }
+        
+        // 超出u8范围的值在反序列化时被拒绝
+        let body = serde_json::json!({ "vote": 256, "user_id": "dave", "chain": "localhost", "gas_option": "relayer" });
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 400);
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_failed_revote_keeps_previous_sequence() {
+        use crate::web::{vote_route, AppState};
+        
+        let state = AppState::new_mock();
+        let route = vote_route(state);
+        let vote = |vote: u8, sequence: u64| {
+            serde_json::json!({ "vote": vote, "user_id": "alice", "sequence": sequence, "chain": "localhost", "gas_option": "relayer" })
+        };
+        
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(1, 0)).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        
+        // 以序号1重新投票但选择无效：失败后恢复为序号0，而不是删除登记
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(2, 1)).reply(&route).await;
+        assert_eq!(resp.status(), 400);
+        
+        // 序号0不能再次使用，序号1仍可用于重新投票
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(0, 0)).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote(0, 1)).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+    }
+    
+    #[test]
+    fn test_tally_counts_no_vote_separately_from_abstention() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let randomness = Fr::from(67890u64);
+        let circuit = VoteCircuit {
+            vote: Fr::zero(),
+            secret: Fr::from(12345u64),
+            randomness,
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        assert!(system.verify_ballot(&vk, &public_inputs, &proof).unwrap().is_valid());
+        
+        // 反对票计入选项0
+        let opening = BallotOpening { vote: Fr::zero(), randomness };
+        let result = system.tally(&vk, vec![(public_inputs, proof, opening)]).unwrap();
+        assert_eq!(result.counts, vec![1, 0]);
+        assert_eq!(result.accepted, vec![0]);
+        
+        // 弃权（未投票）不计入任何选项
+        let no_ballots: Vec<(PublicInputs<Fr>, ark_groth16::Proof<E>, BallotOpening<Fr>)> = Vec::new();
+        let result = system.tally(&vk, no_ballots).unwrap();
+        assert_eq!(result.counts, vec![0, 0]);
+        assert!(result.accepted.is_empty());
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_commit_then_reveal() {
+        use crate::tally::commit_ballot;
+        use crate::web::{commit_route, reveal_route, vote_route, AppState, ElectionPhase};
+        use warp::Filter;
+        
+        let state = AppState::new_mock();
+        state.setup_election("e1").await.unwrap();
+        let route = commit_route(state.clone()).or(reveal_route(state.clone())).or(vote_route(state.clone()));
+        let randomness = Fr::from(424242u64);
+        let commitment = field_to_decimal(commit_ballot(Fr::one(), randomness));
+        let reveal = serde_json::json!({
+            "election_id": "e1", "user_id": "alice", "vote": 1, "sequence": 2,
+            "randomness": field_to_decimal(randomness), "chain": "localhost", "gas_option": "relayer",
+        });
+        
+        // 没有密钥的选举无法揭示，承诺时即返回 404
+        let unknown = serde_json::json!({ "election_id": "e9", "user_id": "alice", "commitment": commitment });
+        let resp = warp::test::request().method("POST").path("/commit").json(&unknown).reply(&route).await;
+        assert_eq!(resp.status(), 404);
+        
+        // 承诺阶段：接受承诺，拒绝揭示
+        let body = serde_json::json!({ "election_id": "e1", "user_id": "alice", "commitment": commitment });
+        let resp = warp::test::request().method("POST").path("/commit").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let resp = warp::test::request().method("POST").path("/reveal").json(&reveal).reply(&route).await;
+        assert_eq!(resp.status(), 403);
+        
+        // bob 承诺后又直接投票
+        let bob = serde_json::json!({ "election_id": "e1", "user_id": "bob", "commitment": commitment });
+        let resp = warp::test::request().method("POST").path("/commit").json(&bob).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let vote = serde_json::json!({
+            "vote": 1, "user_id": "bob", "election_id": "e1", "chain": "localhost", "gas_option": "relayer",
+        });
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        
+        // 揭示阶段：拒绝新承诺，开启值与承诺一致时生成证明
+        assert_eq!(state.advance_phase("e1").await.unwrap(), ElectionPhase::Reveal);
+        let resp = warp::test::request().method("POST").path("/commit").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 403);
+        let resp = warp::test::request().method("POST").path("/reveal").json(&reveal).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], true);
+        assert!(reply["proof"].is_string());
+        assert_eq!(reply["public_inputs"][1], field_to_decimal(election_id_to_field::<Fr>("e1")));
+        // 证明沿用揭示的序号和随机值：证明中的选票承诺就是投票人提交的承诺
+        assert_eq!(reply["public_inputs"][2], "2");
+        assert_eq!(reply["public_inputs"][4], commitment);
+        
+        // 同一承诺不能揭示两次，揭示后也不能再以序号0投票
+        let resp = warp::test::request().method("POST").path("/reveal").json(&reveal).reply(&route).await;
+        assert_eq!(resp.status(), 400);
+        let vote = serde_json::json!({
+            "vote": 0, "user_id": "alice", "election_id": "e1", "chain": "localhost", "gas_option": "relayer",
+        });
+        let resp = warp::test::request().method("POST").path("/vote").json(&vote).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        
+        // 已经投票的投票人不能以相同序号再揭示
+        let mut bob_reveal = reveal.clone();
+        bob_reveal["user_id"] = serde_json::json!("bob");
+        bob_reveal["sequence"] = serde_json::json!(0);
+        let resp = warp::test::request().method("POST").path("/reveal").json(&bob_reveal).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        
+        assert_eq!(state.advance_phase("e1").await.unwrap(), ElectionPhase::Closed);
+        assert!(matches!(state.advance_phase("e1").await, Err(VoteError::WrongPhase(_))));
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_reveal_rejected_when_commitment_does_not_match() {
+        use crate::tally::commit_ballot;
+        use crate::web::{commit_route, reveal_route, AppState};
+        use warp::Filter;
+        
+        let state = AppState::new_mock();
+        state.setup_election("e2").await.unwrap();
+        let route = commit_route(state.clone()).or(reveal_route(state.clone()));
+        let randomness = Fr::from(424242u64);
+        
+        // 承诺投给选项0
+        let body = serde_json::json!({
+            "election_id": "e2", "user_id": "bob",
+            "commitment": field_to_decimal(commit_ballot(Fr::zero(), randomness)),
+        });
+        let resp = warp::test::request().method("POST").path("/commit").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        state.advance_phase("e2").await.unwrap();
+        
+        // 揭示时改投选项1，或使用不同的随机值
+        for (vote, randomness) in [(1, randomness), (0, randomness + Fr::one())] {
+            let reveal = serde_json::json!({
+                "election_id": "e2", "user_id": "bob", "vote": vote,
+                "randomness": field_to_decimal(randomness), "chain": "localhost", "gas_option": "relayer",
+            });
+            let resp = warp::test::request().method("POST").path("/reveal").json(&reveal).reply(&route).await;
+            assert_eq!(resp.status(), 400);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["code"], "commitment_mismatch");
+        }
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_reveal_uses_election_config() {
+        use crate::tally::commit_ballot;
+        use crate::web::{commit_route, reveal_route, AppState};
+        use warp::Filter;
+        
+        // 选举有三名候选人，服务默认配置只有两个选项
+        let state = AppState::new_mock();
+        let config = CircuitConfig { num_candidates: 3, allow_abstain: false, allow_spoiled: false };
+        state.setup_election_with_params("e3", ElectionParams::from(config)).await.unwrap();
+        let route = commit_route(state.clone()).or(reveal_route(state.clone()));
+        let randomness = Fr::from(424242u64);
+        let commitment = field_to_decimal(commit_ballot(Fr::from(2u64), randomness));
+        let body = serde_json::json!({ "election_id": "e3", "user_id": "carol", "commitment": commitment });
+        let resp = warp::test::request().method("POST").path("/commit").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        state.advance_phase("e3").await.unwrap();
+        
+        // 按选举的电路配置开启承诺：投给第三名候选人的选票可以揭示
+        let reveal = serde_json::json!({
+            "election_id": "e3", "user_id": "carol", "vote": 2,
+            "randomness": field_to_decimal(randomness), "chain": "localhost", "gas_option": "relayer",
+        });
+        let resp = warp::test::request().method("POST").path("/reveal").json(&reveal).reply(&route).await;
+        assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["public_inputs"][4], commitment);
+    }
+    
+    #[test]
+    fn test_three_party_ceremony() {
+        use crate::ceremony::{initial_parameters, verify_transcript, Contribution, PowersOfTau};
+        use ark_std::UniformRand;
+        
+        let mut rng = thread_rng();
+        // 仪式为选举 2024 生成密钥
+        let circuit = VoteCircuit::<Fr>::blank(Fr::from(2024u64));
+        
+        // 第一阶段参数写入磁盘后再加载；域须容纳投票电路的约束和公开输入（防重标识派生和序号位分解后约 600 个）
+        let powers = PowersOfTau::<E>::from_secrets_insecure(
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            1024,
+        );
+        let ptau_path = std::env::temp_dir().join(format!("zkp-vote-ptau-{}.bin", std::process::id()));
+        powers.save(&ptau_path).unwrap();
+        let powers = PowersOfTau::<E>::load(&ptau_path).unwrap();
+        std::fs::remove_file(&ptau_path).unwrap();
+        
+        // 三方依次贡献
+        let initial = initial_parameters(circuit, &powers).unwrap();
+        let c1 = Contribution::new(&initial, b"alice");
+        let c2 = Contribution::new(&c1.params, b"bob");
+        let c3 = Contribution::new(&c2.params, b"carol");
+        let chain = vec![c1, c2, c3];
+        
+        let keys = verify_transcript(&initial, &chain).unwrap();
+        
+        // 带进度的验证每验证完一份贡献报告一次
+        let reports = std::sync::Mutex::new(Vec::new());
+        let record = |phase: &str, fraction: f64| reports.lock().unwrap().push((phase.to_string(), fraction));
+        crate::ceremony::verify_transcript_with_progress(&initial, &chain, &record).unwrap();
+        let fractions: Vec<f64> = reports.lock().unwrap().iter().map(|(_, fraction)| *fraction).collect();
+        assert_eq!(fractions, vec![0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]);
+        
+        // 最终密钥可以正常证明和验证选票
+        let ballot = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let inputs = ballot.public_inputs().to_vec();
+        let proof = <Groth16<E> as ProofSystem<Fr>>::prove(&keys.proving_key, ballot, &mut rng).unwrap();
+        assert!(<Groth16<E> as ProofSystem<Fr>>::verify(&keys.verifying_key, &inputs, &proof).unwrap());
+        
+        // 篡改中间贡献的参数后验证失败
+        let mut tampered = chain.clone();
+        tampered[1].params.l_query[0] = (tampered[1].params.l_query[0] * Fr::from(2u64)).into();
+        assert!(verify_transcript(&initial, &tampered).is_err());
+        
+        // 篡改中间贡献的知识证明后验证失败
+        let mut tampered = chain;
+        tampered[1].s_delta_g1 = (tampered[1].s_delta_g1 * Fr::from(2u64)).into();
+        assert!(verify_transcript(&initial, &tampered).is_err());
+    }
+    
+    #[test]
+    fn test_parse_field_element_rejects_out_of_range() {
+        use num_bigint::BigUint;
+        
+        let modulus: BigUint = Fr::MODULUS.into();
+        let below = (&modulus - 1u32).to_string();
+        let above = (&modulus + 1u32).to_string();
+        
+        assert_eq!(parse_field_element::<Fr>(&below).unwrap(), -Fr::one());
+        assert!(matches!(
+            parse_field_element::<Fr>(&modulus.to_string()),
+            Err(VoteError::FieldRangeExceeded(_))
+        ));
+        assert!(matches!(
+            parse_field_element::<Fr>(&above),
+            Err(VoteError::FieldRangeExceeded(_))
+        ));
+        assert!(matches!(parse_field_element::<Fr>("12a"), Err(VoteError::InvalidBallot(_))));
+    }
+    
+    #[test]
+    fn test_field_encodings_round_trip() {
+        use crate::field::{fr_from_decimal, fr_from_hex, fr_from_le_bytes, fr_to_decimal, fr_to_hex, fr_to_le_bytes};
+        use ark_std::UniformRand;
+        use num_bigint::BigUint;
+        
+        let mut rng = thread_rng();
+        let samples = [Fr::zero(), Fr::one(), Fr::from(255u64), -Fr::one(), Fr::rand(&mut rng), Fr::rand(&mut rng)];
+        for x in samples {
+            // 每种编码各自互逆
+            assert_eq!(fr_from_decimal::<Fr>(&fr_to_decimal(x)).unwrap(), x);
+            assert_eq!(fr_from_hex::<Fr>(&fr_to_hex(x)).unwrap(), x);
+            assert_eq!(fr_from_le_bytes::<Fr>(&fr_to_le_bytes(x)).unwrap(), x);
+            
+            // 跨编码转换：十进制 -> 十六进制 -> 字节 -> 十进制
+            let hex = fr_to_hex(fr_from_decimal::<Fr>(&fr_to_decimal(x)).unwrap());
+            let bytes = fr_to_le_bytes(fr_from_hex::<Fr>(&hex).unwrap());
+            assert_eq!(fr_to_decimal(fr_from_le_bytes::<Fr>(&bytes).unwrap()), fr_to_decimal(x));
+            assert_eq!(bytes.len(), 32);
+        }
+        
+        // 十六进制的 0x 前缀可选，字节的高位补零不影响结果
+        assert_eq!(fr_from_hex::<Fr>("ff").unwrap(), Fr::from(255u64));
+        assert_eq!(fr_from_hex::<Fr>("0x00ff").unwrap(), Fr::from(255u64));
+        assert_eq!(fr_from_le_bytes::<Fr>(&[0xff, 0, 0]).unwrap(), Fr::from(255u64));
+        assert_eq!(fr_from_le_bytes::<Fr>(&[]).unwrap(), Fr::zero());
+        
+        // 模数及以上的值在每种编码下都拒绝，不静默取模
+        let modulus: BigUint = Fr::MODULUS.into();
+        for value in [modulus.clone(), &modulus + 1u32, BigUint::from(1u32) << 256] {
+            let is_range_error = |r: Result<Fr, VoteError>| matches!(r, Err(VoteError::FieldRangeExceeded(_)));
+            assert!(is_range_error(fr_from_decimal(&value.to_string())));
+            assert!(is_range_error(fr_from_hex(&format!("0x{:x}", value))));
+            assert!(is_range_error(fr_from_le_bytes(&value.to_bytes_le())));
+        }
+        
+        // 格式错误的字符串
+        assert!(matches!(fr_from_hex::<Fr>("0xzz"), Err(VoteError::InvalidBallot(_))));
+        assert!(matches!(fr_from_decimal::<Fr>("-1"), Err(VoteError::InvalidBallot(_))));
+    }
+    
+    #[test]
+    fn test_memory_nullifier_backend() {
+        use crate::store::{MemoryBackend, NullifierBackend};
+        
+        let mut backend = MemoryBackend::default();
+        assert!(!backend.contains(&Fr::from(1u64)));
+        backend.insert(Fr::from(1u64)).unwrap();
+        assert!(backend.contains(&Fr::from(1u64)));
+        assert!(!backend.contains(&Fr::from(2u64)));
+    }
+    
+    #[test]
+    fn test_file_nullifier_backend_persists_across_restart() {
+        use crate::store::{FileBackend, NullifierBackend};
+        
+        let path = std::env::temp_dir().join(format!("zkp-vote-nullifiers-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        
+        {
+            let mut backend = FileBackend::open(&path).unwrap();
+            backend.insert(Fr::from(1u64)).unwrap();
+            backend.insert(-Fr::one()).unwrap();
+            backend.insert(Fr::from(1u64)).unwrap();
+            assert!(backend.contains(&Fr::from(1u64)));
+        }
+        
+        // 模拟重启：重新打开同一文件
+        let mut backend = FileBackend::open(&path).unwrap();
+        assert!(backend.contains(&Fr::from(1u64)));
+        assert!(backend.contains(&-Fr::one()));
+        assert!(!backend.contains(&Fr::from(2u64)));
+        backend.insert(Fr::from(2u64)).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+        
+        // 选票序号随防重标识持久化，只记录更大的序号
+        backend.record(Fr::from(2u64), 3).unwrap();
+        backend.record(Fr::from(2u64), 1).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+        drop(backend);
+        // 没有序号的旧格式记录按序号0载入
+        let mut bytes = Vec::new();
+        ark_serialize::CanonicalSerialize::serialize_compressed(&Fr::from(5u64), &mut bytes).unwrap();
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        contents.push_str(&format!("{}\n", hex::encode(bytes)));
+        std::fs::write(&path, contents).unwrap();
+        let backend = FileBackend::open(&path).unwrap();
+        assert_eq!(backend.sequence(&Fr::from(2u64)), Some(3));
+        assert_eq!(backend.sequence(&Fr::from(1u64)), Some(0));
+        assert_eq!(backend.sequence(&Fr::from(5u64)), Some(0));
+        assert_eq!(backend.sequence(&Fr::from(6u64)), None);
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_file_nullifier_backend_recovers_truncated_tail() {
+        use crate::store::{FileBackend, NullifierBackend, StoreError};
+        
+        let path = std::env::temp_dir().join(format!("zkp-vote-nullifiers-truncated-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let nullifiers = [Fr::from(1u64), Fr::from(2u64), -Fr::one()];
+        {
+            let mut backend = FileBackend::open(&path).unwrap();
+            for n in nullifiers {
+                backend.insert(n).unwrap();
+            }
+        }
+        let complete = std::fs::read(&path).unwrap();
+        
+        // 最后一条记录写到一半时断电：完整记录全部载入，残缺的尾部被丢弃
+        let record = complete.split(|&b| b == b'\n').next().unwrap().to_vec();
+        let mut truncated = complete.clone();
+        truncated.extend_from_slice(&record[..record.len() / 2]);
+        std::fs::write(&path, &truncated).unwrap();
+        {
+            let mut backend = FileBackend::open(&path).unwrap();
+            for n in nullifiers {
+                assert!(backend.contains(&n));
+            }
+            assert_eq!(std::fs::read(&path).unwrap(), complete);
+            // 截断后追加的记录在下次打开时仍能载入
+            backend.insert(Fr::from(3u64)).unwrap();
+        }
+        let backend = FileBackend::open(&path).unwrap();
+        assert!(backend.contains(&Fr::from(3u64)));
+        
+        // 尾部的无法解析的内容（含零字节）同样被丢弃
+        let mut garbage = std::fs::read(&path).unwrap();
+        garbage.extend_from_slice(b"not-hex\n\0\0\0\0");
+        std::fs::write(&path, &garbage).unwrap();
+        let backend = FileBackend::open(&path).unwrap();
+        assert!(nullifiers.iter().all(|n| backend.contains(n)));
+        assert!(backend.contains(&Fr::from(3u64)));
+        
+        // 损坏的记录之后仍有完整记录时不是断电造成的，拒绝打开
+        let mut corrupt = b"not-hex\n".to_vec();
+        corrupt.extend_from_slice(&complete);
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(matches!(FileBackend::open(&path), Err(StoreError::Corrupt(_))));
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_nullifier_set_rejects_duplicates() {
+        use crate::store::{FileBackend, NullifierSetError};
+        use num_bigint::BigUint;
+
+        let set = NullifierSet::new();
+        set.insert(Fr::from(1u64)).unwrap();
+        assert!(matches!(set.insert(Fr::from(1u64)), Err(NullifierSetError::Duplicate(DuplicateNullifier))));
+        assert!(set.contains(&Fr::from(1u64)));
+        assert_eq!(set.len(), 1);
+
+        // p-1 与 2p-1 表示同一字段元素
+        set.insert(-Fr::one()).unwrap();
+        let modulus: BigUint = Fr::MODULUS.into();
+        let alias = Fr::from(&modulus * 2u32 - 1u32);
+        assert!(set.insert(alias).is_err());
+        assert_eq!(set.len(), 2);
+
+        // 文件后端中的记录在重新打开后载入集合
+        let path = std::env::temp_dir().join(format!("zkp-vote-nullifier-set-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let set = NullifierSet::with_store(FileBackend::open(&path).unwrap()).unwrap();
+            set.insert(Fr::from(7u64)).unwrap();
+        }
+        let set = NullifierSet::with_store(FileBackend::open(&path).unwrap()).unwrap();
+        assert!(set.contains(&Fr::from(7u64)));
+        assert!(set.insert(Fr::from(7u64)).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_public_inputs_ordering() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        // 规范顺序验证通过
+        assert!(system.verify(&vk, &public_inputs.to_vec(), &proof).unwrap());
+        
+        // 顺序打乱后验证失败
+        let mut shuffled = public_inputs.to_vec();
+        shuffled.reverse();
+        assert!(!system.verify(&vk, &shuffled, &proof).unwrap());
+    }
+    
+    // 在指定后端上执行完整的投票→验证流程
+    fn run_vote_flow<P: ProofSystem<Fr>>() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, P>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        // 证明和验证密钥经过序列化往返后仍能验证
+        let proof = P::deserialize_proof(&P::serialize_proof(&proof).unwrap()).unwrap();
+        let vk = P::deserialize_verifying_key(&P::serialize_verifying_key(&vk).unwrap()).unwrap();
+        assert!(system.verify(&vk, &public_inputs, &proof).unwrap());
+        
+        // 错误的公开输入验证失败
+        let wrong_inputs = vec![Fr::from(1u64), Fr::from(2u64), Fr::from(3u64), Fr::from(4u64), Fr::from(5u64)];
+        assert!(!system.verify(&vk, &wrong_inputs, &proof).unwrap());
+    }
+    
+    #[test]
+    fn test_groth16_backend_flow() {
+        run_vote_flow::<Groth16<E>>();
+    }
+    
+    #[test]
+    fn test_mock_backend_flow() {
+        run_vote_flow::<MockSNARK>();
+        
+        // 模拟后端仍检查约束：越界的投票值无法生成证明
+        let mut rng = thread_rng();
+        let (system, pk, _) = VoteSystem::<Fr, MockSNARK>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit { vote: Fr::from(2u64), ..VoteCircuit::blank(Fr::from(2024u64)) };
+        assert!(system.vote(&pk, circuit, &mut rng).is_err());
+        
+        // 不同设置的密钥互不通用
+        let (_, _, other_vk) = VoteSystem::<Fr, MockSNARK>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit { vote: Fr::one(), ..VoteCircuit::blank(Fr::from(2024u64)) };
+        let inputs = circuit.public_inputs().to_vec();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        assert!(!system.verify(&other_vk, &inputs, &proof).unwrap());
+    }
+    
+    // 以模拟后端跑完整的Web流程：批量投票、运行计票、批量验证、审计日志，不做任何真实的密钥生成和证明
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_mock_backend_web_flow() {
+        use crate::web::{audit_route, verify_batch_route, vote_batch_route, AppState};
+        use warp::Filter;
+        
+        let state = AppState::new_mock();
+        let route = vote_batch_route(state.clone())
+            .or(verify_batch_route(state.clone()))
+            .or(audit_route(state.clone()));
+        let body = serde_json::json!([
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 1, "user_id": "carol", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        let accepted: Vec<bool> = votes.iter().map(|v| v["success"].as_bool().unwrap()).collect();
+        assert_eq!(accepted, vec![true, true, true, false]);
+        assert_eq!(state.running_counts().await, vec![1, 2]);
+        
+        // 生成的证明通过验证，改动公开输入后不通过
+        let mut forged_inputs = votes[1]["public_inputs"].clone();
+        forged_inputs[0] = serde_json::json!("999");
+        let body = serde_json::json!([
+            { "proof": votes[0]["proof"], "public_inputs": votes[0]["public_inputs"] },
+            { "proof": votes[1]["proof"], "public_inputs": forged_inputs },
+            { "proof": votes[2]["proof"], "public_inputs": votes[2]["public_inputs"] },
+        ]);
+        let resp = warp::test::request().method("POST").path("/verify/batch").json(&body).reply(&route).await;
+        let results: Vec<Option<bool>> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(results, vec![Some(true), Some(false), Some(true)]);
+        
+        let resp = warp::test::request().method("GET").path("/audit").reply(&route).await;
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["entries"].as_array().unwrap().len(), 3);
+    }
+    
+    #[test]
+    fn test_aggregate_256_ballots() {
+        use crate::aggregate::{aggregate_chunked, verify_chunked_aggregate, AggregateProof, AggregationSrs};
+        use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuits: Vec<VoteCircuit<Fr>> = (0..256u64)
+            .map(|i| VoteCircuit {
+                vote: Fr::from(i % 2),
+                secret: Fr::from(10000u64 + i),
+                randomness: Fr::from(20000u64 + i),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            })
+            .collect();
+        let public_inputs: Vec<Vec<Fr>> = circuits.iter().map(|c| c.public_inputs().to_vec()).collect();
+        let mut proofs = system.prove_batch(&pk, circuits, [7u8; 32]).unwrap();
+        
+        let srs = AggregationSrs::<E>::setup_insecure(256, &mut rng);
+        let srs_vk = srs.verifier_key();
+        
+        // 聚合证明验证通过，且序列化往返后仍然有效
+        let aggregate = system.aggregate(&srs, &proofs, &public_inputs).unwrap();
+        assert!(system.verify_aggregate(&vk, &srs_vk, &public_inputs, &aggregate).unwrap());
+        let mut bytes = Vec::new();
+        aggregate.serialize_compressed(&mut bytes).unwrap();
+        let decoded = AggregateProof::<E>::deserialize_compressed(&bytes[..]).unwrap();
+        assert!(system.verify_aggregate(&vk, &srs_vk, &public_inputs, &decoded).unwrap());
+        
+        // 分块聚合（块大小不整除总数）同样验证通过
+        let chunked = aggregate_chunked(&srs, &proofs, &public_inputs, 100).unwrap();
+        assert_eq!(chunked.chunks.len(), 3);
+        assert!(verify_chunked_aggregate(&vk, &srs_vk, &public_inputs, &chunked).unwrap());
+        
+        // 篡改其中一张选票的公开输入，聚合验证失败
+        let mut tampered_inputs = public_inputs.clone();
+        tampered_inputs[137][0] += Fr::one();
+        assert!(!system.verify_aggregate(&vk, &srs_vk, &tampered_inputs, &aggregate).unwrap());
+        
+        // 篡改其中一个证明后重新聚合，聚合验证失败
+        proofs[42].c = (proofs[42].c * Fr::from(2u64)).into();
+        let aggregate = system.aggregate(&srs, &proofs, &public_inputs).unwrap();
+        assert!(!system.verify_aggregate(&vk, &srs_vk, &public_inputs, &aggregate).unwrap());
+    }
+    
+    #[test]
+    fn test_proof_serialization_modes() {
+        use ark_serialize::Compress;
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        type G = Groth16<E>;
+        let compressed = <G as ProofSystem<Fr>>::serialize_proof_compressed(&proof).unwrap();
+        let uncompressed = <G as ProofSystem<Fr>>::serialize_proof_uncompressed(&proof).unwrap();
+        assert_eq!(compressed.len(), 128);
+        assert_eq!(uncompressed.len(), 256);
+        
+        // 指定格式往返
+        let decoded = <G as ProofSystem<Fr>>::deserialize_proof_with_mode(&compressed, Compress::Yes).unwrap();
+        assert!(system.verify(&vk, &public_inputs, &decoded).unwrap());
+        let decoded = <G as ProofSystem<Fr>>::deserialize_proof_with_mode(&uncompressed, Compress::No).unwrap();
+        assert!(system.verify(&vk, &public_inputs, &decoded).unwrap());
+        
+        // 格式不匹配时失败
+        assert!(<G as ProofSystem<Fr>>::deserialize_proof_with_mode(&uncompressed, Compress::Yes).is_err());
+        
+        // 自动识别格式
+        let (decoded, mode) = <G as ProofSystem<Fr>>::deserialize_proof_auto(&compressed).unwrap();
+        assert!(mode == Compress::Yes);
+        assert_eq!(decoded, proof);
+        let (decoded, mode) = <G as ProofSystem<Fr>>::deserialize_proof_auto(&uncompressed).unwrap();
+        assert!(mode == Compress::No);
+        assert_eq!(decoded, proof);
+    }
+    
+    #[test]
+    fn test_compression_modes_round_trip() {
+        use crate::fingerprint::{decode_key, encode_key, encode_key_with, ProofEnvelope};
+        use ark_serialize::{CanonicalDeserialize, Validate};
+        
+        type G = Groth16<E>;
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, G>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        // 两种格式的体积：非压缩格式体积翻倍，解码省去开方（解码耗时见 benches/vote.rs 的 decode_proof）
+        for (compression, size) in [(Compression::Compressed, 128), (Compression::Uncompressed, 256)] {
+            let bytes = <G as ProofSystem<Fr>>::serialize_proof_with(&proof, compression).unwrap();
+            assert_eq!(bytes.len(), size);
+            assert_eq!(<G as ProofSystem<Fr>>::deserialize_proof(&bytes).unwrap(), proof);
+            let decoded = ark_groth16::Proof::<E>::deserialize_with_mode(&bytes[..], compression.into(), Validate::No).unwrap();
+            assert_eq!(decoded, proof);
+        }
+        
+        // 验证密钥：两种格式都能自动识别
+        for compression in [Compression::Compressed, Compression::Uncompressed] {
+            let bytes = <G as ProofSystem<Fr>>::serialize_verifying_key_with(&vk, compression).unwrap();
+            assert_eq!(<G as ProofSystem<Fr>>::deserialize_verifying_key(&bytes).unwrap(), vk);
+            assert_eq!(<G as ProofSystem<Fr>>::deserialize_verifying_key_unchecked(&bytes).unwrap(), vk);
+        }
+        let compressed_vk = <G as ProofSystem<Fr>>::serialize_verifying_key(&vk).unwrap();
+        let uncompressed_vk = <G as ProofSystem<Fr>>::serialize_verifying_key_with(&vk, Compression::Uncompressed).unwrap();
+        assert!(uncompressed_vk.len() > compressed_vk.len());
+        
+        // 密钥文件：头部记录格式，指纹与格式无关
+        let compressed_file = encode_key::<Fr, _>(&pk).unwrap();
+        let uncompressed_file = encode_key_with::<Fr, _>(&pk, Compression::Uncompressed).unwrap();
+        assert_eq!(&uncompressed_file[..4], b"ZKVU");
+        assert_eq!(compressed_file[12..44], uncompressed_file[12..44]);
+        assert!(uncompressed_file.len() > compressed_file.len());
+        for file in [&compressed_file, &uncompressed_file] {
+            assert_eq!(decode_key::<Fr, ark_groth16::ProvingKey<E>>(file).unwrap(), pk);
+        }
+        // 声明的格式与本体不符时拒绝
+        let mut mislabeled = compressed_file.clone();
+        mislabeled[..4].copy_from_slice(b"ZKVU");
+        assert!(decode_key::<Fr, ark_groth16::ProvingKey<E>>(&mislabeled).is_err());
+        
+        // 证明信封：两种格式混合往返，二进制和 JSON 编码都记录格式
+        let compressed = ProofEnvelope::<Fr, G>::new(&vk, public_inputs.clone(), proof.clone()).unwrap();
+        let uncompressed = ProofEnvelope::<Fr, G>::new(&vk, public_inputs, proof.clone())
+            .unwrap()
+            .with_compression(Compression::Uncompressed);
+        let compressed_bytes = compressed.to_bytes().unwrap();
+        let uncompressed_bytes = uncompressed.to_bytes().unwrap();
+        assert_eq!(uncompressed_bytes.len(), compressed_bytes.len() + 128);
+        for (envelope, bytes) in [(&compressed, &compressed_bytes), (&uncompressed, &uncompressed_bytes)] {
+            let from_bin = ProofEnvelope::<Fr, G>::from_bytes(bytes).unwrap();
+            assert_eq!(from_bin.compression, envelope.compression);
+            assert_eq!(&from_bin.to_bytes().unwrap(), bytes);
+            assert!(system.verify_envelope(&vk, &from_bin).unwrap().is_valid());
+            
+            let json = serde_json::to_string(&envelope.to_json().unwrap()).unwrap();
+            let from_json = ProofEnvelope::<Fr, G>::from_json_str(&json).unwrap();
+            assert_eq!(from_json.compression, envelope.compression);
+            assert_eq!(from_json.proof, proof);
+        }
+        
+        // 头部的格式与证明本体不符时拒绝，版本 1 不能记录非压缩格式
+        let mut mislabeled = compressed_bytes.clone();
+        mislabeled[16] = Compression::Uncompressed.to_u8();
+        assert!(ProofEnvelope::<Fr, G>::from_bytes(&mislabeled).is_err());
+        let mut v1 = uncompressed;
+        v1.format_version = 1;
+        assert!(matches!(v1.to_bytes(), Err(VoteError::UnsupportedVersion(_))));
+    }
+    
+    #[test]
+    fn test_identity_proof_rejected() {
+        use ark_ec::{pairing::Pairing, AffineRepr};
+        use ark_serialize::{CanonicalSerialize, Compress};
+        
+        type G = Groth16<E>;
+        let identity = ark_groth16::Proof::<E> {
+            a: <E as Pairing>::G1Affine::zero(),
+            b: <E as Pairing>::G2Affine::zero(),
+            c: <E as Pairing>::G1Affine::zero(),
+        };
+        let mut compressed = Vec::new();
+        identity.serialize_compressed(&mut compressed).unwrap();
+        let mut uncompressed = Vec::new();
+        identity.serialize_uncompressed(&mut uncompressed).unwrap();
+        
+        assert!(matches!(
+            <G as ProofSystem<Fr>>::deserialize_proof(&compressed),
+            Err(VoteError::InvalidProofEncoding(_))
+        ));
+        assert!(matches!(
+            <G as ProofSystem<Fr>>::deserialize_proof_with_mode(&uncompressed, Compress::No),
+            Err(VoteError::InvalidProofEncoding(_))
+        ));
+        for bytes in [&compressed, &uncompressed] {
+            let Err(err) = <G as ProofSystem<Fr>>::deserialize_proof_auto(bytes) else {
+                panic!("identity proof was accepted");
+            };
+            assert_eq!(err.code(), "invalid_proof_encoding");
+        }
+        
+        // 只有一个点为单位元同样拒绝
+        let mut rng = thread_rng();
+        let (system, pk, _) = VoteSystem::<Fr, G>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::from(1u64),
+            secret: Fr::from(11u64),
+            randomness: Fr::from(12u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let mut proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        proof.c = <E as Pairing>::G1Affine::zero();
+        let bytes = <G as ProofSystem<Fr>>::serialize_proof(&proof).unwrap();
+        assert!(matches!(
+            <G as ProofSystem<Fr>>::deserialize_proof(&bytes),
+            Err(VoteError::InvalidProofEncoding(_))
+        ));
+    }
+    
+    #[test]
+    fn test_deserializers_reject_adversarial_encodings() {
+        use crate::ceremony::PowersOfTau;
+        use crate::fingerprint::{decode_key, encode_key, ProofEnvelope};
+        use ark_ec::{pairing::Pairing, AffineRepr};
+        use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+        
+        type G = Groth16<E>;
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, G>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(31u64),
+            randomness: Fr::from(37u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let inputs_json = serde_json::to_string(
+            &public_inputs.to_vec().into_iter().map(field_to_decimal).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        // 少一个字节、多一个字节和空输入
+        let wrong_lengths = |bytes: &[u8]| {
+            let mut extended = bytes.to_vec();
+            extended.push(0);
+            [bytes[..bytes.len() - 1].to_vec(), extended, Vec::new()]
+        };
+        
+        // 证明：长度错误
+        let compressed = <G as ProofSystem<Fr>>::serialize_proof_compressed(&proof).unwrap();
+        let uncompressed = <G as ProofSystem<Fr>>::serialize_proof_uncompressed(&proof).unwrap();
+        for bytes in wrong_lengths(&compressed) {
+            assert!(<G as ProofSystem<Fr>>::deserialize_proof(&bytes).is_err());
+            assert!(<G as ProofSystem<Fr>>::deserialize_proof_with_mode(&bytes, Compress::Yes).is_err());
+            assert!(<G as ProofSystem<Fr>>::deserialize_proof_auto(&bytes).is_err());
+            assert!(crate::client::verify_ballot(&vk, &inputs_json, &bytes).is_err());
+        }
+        for bytes in wrong_lengths(&uncompressed) {
+            assert!(<G as ProofSystem<Fr>>::deserialize_proof_with_mode(&bytes, Compress::No).is_err());
+            assert!(<G as ProofSystem<Fr>>::deserialize_proof_auto(&bytes).is_err());
+        }
+        
+        // 证明：非压缩编码中篡改 A 的 y 坐标，点离开曲线
+        let mut off_curve = uncompressed.clone();
+        off_curve[proof.a.uncompressed_size() / 2 + 1] ^= 1;
+        assert!(<G as ProofSystem<Fr>>::deserialize_proof_with_mode(&off_curve, Compress::No).is_err());
+        assert!(<G as ProofSystem<Fr>>::deserialize_proof_auto(&off_curve).is_err());
+        assert!(crate::client::verify_ballot(&vk, &inputs_json, &off_curve).is_err());
+        
+        // 证明：压缩编码中篡改 B 的 x 坐标；落在曲线上但不在素数阶子群中的点，
+        // 不校验时能解码，校验时必须拒绝
+        let b_offset = proof.a.compressed_size() + 1;
+        let mut wrong_subgroup = 0;
+        for k in 0..=255u8 {
+            let mut bytes = compressed.clone();
+            bytes[b_offset] = k;
+            let unchecked = ark_groth16::Proof::<E>::deserialize_with_mode(&bytes[..], Compress::Yes, Validate::No);
+            let checked = <G as ProofSystem<Fr>>::deserialize_proof(&bytes);
+            match checked {
+                Ok(decoded) => assert!(decoded.b.mul_bigint(Fr::MODULUS).is_zero()),
+                Err(_) => {
+                    if let Ok(unchecked) = unchecked {
+                        if !unchecked.b.mul_bigint(Fr::MODULUS).is_zero() {
+                            wrong_subgroup += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert!(wrong_subgroup > 0);
+        
+        // 证明信封：长度错误与单位元
+        let envelope = ProofEnvelope::<Fr, G>::new(&vk, public_inputs.clone(), proof.clone()).unwrap();
+        for bytes in wrong_lengths(&envelope.to_bytes().unwrap()) {
+            assert!(ProofEnvelope::<Fr, G>::from_bytes(&bytes).is_err());
+        }
+        let mut identity = proof.clone();
+        identity.a = <E as Pairing>::G1Affine::zero();
+        let identity_envelope = ProofEnvelope::<Fr, G> { proof: identity, ..envelope };
+        assert!(matches!(
+            ProofEnvelope::<Fr, G>::from_bytes(&identity_envelope.to_bytes().unwrap()),
+            Err(VoteError::InvalidProofEncoding(_))
+        ));
+        
+        // 验证密钥：长度错误、离开曲线和单位元
+        let vk_bytes = <G as ProofSystem<Fr>>::serialize_verifying_key(&vk).unwrap();
+        for bytes in wrong_lengths(&vk_bytes) {
+            assert!(<G as ProofSystem<Fr>>::deserialize_verifying_key(&bytes).is_err());
+            assert!(crate::client::decode_verifying_key(&bytes).is_err());
+        }
+        let mut vk_uncompressed = Vec::new();
+        vk.serialize_uncompressed(&mut vk_uncompressed).unwrap();
+        vk_uncompressed[vk.alpha_g1.uncompressed_size() / 2 + 1] ^= 1;
+        assert!(ark_groth16::VerifyingKey::<E>::deserialize_uncompressed(&vk_uncompressed[..]).is_err());
+        let mut degenerate_vk = vk.clone();
+        degenerate_vk.delta_g2 = <E as Pairing>::G2Affine::zero();
+        let degenerate_vk_bytes = <G as ProofSystem<Fr>>::serialize_verifying_key(&degenerate_vk).unwrap();
+        for result in [
+            <G as ProofSystem<Fr>>::deserialize_verifying_key(&degenerate_vk_bytes),
+            crate::client::decode_verifying_key(&degenerate_vk_bytes),
+        ] {
+            match result {
+                Err(VoteError::KeyMismatch(msg)) => assert!(msg.contains("delta_g2"), "{}", msg),
+                other => panic!("expected KeyMismatch, got {:?}", other.map(|_| ())),
+            }
+        }
+        // 不校验的变体只用于可信文件，对退化密钥不做检查
+        assert!(<G as ProofSystem<Fr>>::deserialize_verifying_key_unchecked(&degenerate_vk_bytes).is_ok());
+        
+        // 证明密钥：长度错误与单位元（两侧同时置为单位元，配对检查本身无法发现）
+        let pk_bytes = <G as ProofSystem<Fr>>::serialize_proving_key(&pk).unwrap();
+        assert_eq!(<G as ProofSystem<Fr>>::deserialize_proving_key(&pk_bytes).unwrap(), pk);
+        for bytes in wrong_lengths(&pk_bytes) {
+            assert!(<G as ProofSystem<Fr>>::deserialize_proving_key(&bytes).is_err());
+        }
+        let key_file = encode_key::<Fr, _>(&pk).unwrap();
+        for bytes in wrong_lengths(&key_file) {
+            assert!(decode_key::<Fr, ark_groth16::ProvingKey<E>>(&bytes).is_err());
+            assert!(crate::client::decode_proving_key(&bytes).is_err());
+        }
+        let mut degenerate_pk = pk.clone();
+        degenerate_pk.delta_g1 = <E as Pairing>::G1Affine::zero();
+        degenerate_pk.vk.delta_g2 = <E as Pairing>::G2Affine::zero();
+        let degenerate_pk_bytes = <G as ProofSystem<Fr>>::serialize_proving_key(&degenerate_pk).unwrap();
+        assert!(matches!(
+            <G as ProofSystem<Fr>>::deserialize_proving_key(&degenerate_pk_bytes),
+            Err(VoteError::KeyMismatch(_))
+        ));
+        assert!(matches!(
+            crate::client::decode_proving_key(&encode_key::<Fr, _>(&degenerate_pk).unwrap()),
+            Err(VoteError::KeyMismatch(_))
+        ));
+        
+        // 第一阶段参数文件：长度错误
+        let powers = PowersOfTau::<E>::from_secrets_insecure(Fr::from(2u64), Fr::from(3u64), Fr::from(5u64), 4);
+        let path = std::env::temp_dir().join(format!("zkp-vote-adversarial-ptau-{}.bin", std::process::id()));
+        powers.save(&path).unwrap();
+        let ptau_bytes = std::fs::read(&path).unwrap();
+        for bytes in wrong_lengths(&ptau_bytes) {
+            std::fs::write(&path, bytes).unwrap();
+            assert!(PowersOfTau::<E>::load(&path).is_err());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_rerandomized_proof_verifies_same_statement() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(424242u64),
+            randomness: Fr::from(171717u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        
+        let rerandomized = system.rerandomize(&vk, &proof, &mut rng);
+        let original_bytes = <Groth16<E> as ProofSystem<Fr>>::serialize_proof(&proof).unwrap();
+        let rerandomized_bytes = <Groth16<E> as ProofSystem<Fr>>::serialize_proof(&rerandomized).unwrap();
+        assert_ne!(original_bytes, rerandomized_bytes);
+        assert!(system.verify_ballot(&vk, &public_inputs, &rerandomized).unwrap().is_valid());
+        
+        // 对不同的公开输入验证失败
+        let mut other = public_inputs.clone();
+        other.election_id = Fr::from(2025u64);
+        assert!(!system.verify_ballot(&vk, &other, &rerandomized).unwrap().is_valid());
+    }
+    
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_any_rng_shims() {
+        use rand::rngs::mock::StepRng;
+        
+        // 旧接口仍可传入非密码学随机数发生器
+        let mut rng = StepRng::new(1, 0x9E37_79B9_7F4A_7C15);
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup_with_any_rng(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(31337u64),
+            randomness: Fr::from(4242u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote_with_any_rng(&pk, circuit, &mut rng).unwrap();
+        assert!(system.verify_ballot(&vk, &public_inputs, &proof).unwrap().is_valid());
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_app_state_uses_injected_rng() {
+        use crate::store::MemoryBackend;
+        use crate::web::{vote_batch_route, AppState};
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+        
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::zero(), &mut thread_rng()).unwrap();
+        let body = serde_json::json!([{ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" }]);
+        let mut nullifiers = Vec::new();
+        for seed in [[1u8; 32], [1u8; 32], [2u8; 32]] {
+            let state = AppState::<Groth16<E>>::from_keys(pk.clone(), vk.clone(), MemoryBackend::default())
+                .unwrap()
+                .with_rng(ChaCha20Rng::from_seed(seed));
+            let resp = warp::test::request()
+                .method("POST")
+                .path("/vote/batch")
+                .json(&body)
+                .reply(&vote_batch_route(state))
+                .await;
+            assert_eq!(resp.status(), 200);
+            let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+            nullifiers.push(votes[0]["public_inputs"][0].clone());
+        }
+        
+        // 投票人秘密完全由注入的随机数源决定
+        assert_eq!(nullifiers[0], nullifiers[1]);
+        assert_ne!(nullifiers[0], nullifiers[2]);
+    }
+    
+    #[test]
+    fn test_hex_field_roundtrip() {
+        use crate::{field_to_hex, parse_hex_field};
+        use num_bigint::BigUint;
+        
+        let x = Fr::from(0xdead_beefu64);
+        assert_eq!(field_to_hex(x), "0xdeadbeef");
+        assert_eq!(parse_hex_field::<Fr>("0xdeadbeef").unwrap(), x);
+        assert_eq!(parse_hex_field::<Fr>("deadbeef").unwrap(), x);
+        assert_eq!(parse_hex_field::<Fr>(&field_to_hex(-Fr::one())).unwrap(), -Fr::one());
+        
+        // 模数本身超出范围，非十六进制字符拒绝
+        let modulus: BigUint = Fr::MODULUS.into();
+        assert!(matches!(
+            parse_hex_field::<Fr>(&format!("0x{:x}", modulus)),
+            Err(VoteError::FieldRangeExceeded(_))
+        ));
+        assert!(matches!(parse_hex_field::<Fr>("0xzz"), Err(VoteError::InvalidBallot(_))));
+    }
+    
+    #[test]
+    fn test_revote_keeps_latest_ballot() {
+        use crate::tally::select_latest_ballots;
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        
+        // 投票人A先投0（序号0），后改投1（序号1）；投票人B投0
+        let ballots = vec![
+            (Fr::from(111u64), 0u64, 0u64),
+            (Fr::from(111u64), 1, 1),
+            (Fr::from(222u64), 0, 0),
+        ];
+        
+        let mut accepted = Vec::new();
+        for (nullifier, vote, sequence) in ballots {
+            let circuit = VoteCircuit {
+                vote: Fr::from(vote),
+                secret: nullifier,
+                randomness: Fr::from(67890u64),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::from(sequence),
+                epoch: Fr::zero(),
+            };
+            let public_inputs = circuit.public_inputs();
+            let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+            assert!(system.verify(&vk, &public_inputs.to_vec(), &proof).unwrap());
+            accepted.push((public_inputs, vote));
+        }
+        
+        // 每个投票人只计入序号最大的选票
+        let tallied = select_latest_ballots(accepted);
+        assert_eq!(tallied, vec![1, 0]);
+    }
+    
+    #[test]
+    fn test_tally_counts_latest_revote_only() {
+        use ark_std::UniformRand;
+        
+        let mut rng = thread_rng();
+        let election_id = Fr::from(2024u64);
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(election_id, &mut rng).unwrap();
+        
+        // 投票人A先投0（序号0），后改投1（序号1），再以相同序号重复提交；投票人B投0
+        let ballots: Vec<_> = [(111u64, 0u64, 0u64), (111, 1, 1), (222, 0, 0), (111, 1, 1)]
+            .into_iter()
+            .map(|(secret, vote, sequence)| {
+                let randomness = Fr::rand(&mut rng);
+                let circuit = VoteCircuit {
+                    vote: Fr::from(vote),
+                    secret: Fr::from(secret),
+                    randomness,
+                    election_id,
+                    sequence: Fr::from(sequence),
+                    epoch: Fr::zero(),
+                };
+                let public_inputs = circuit.public_inputs();
+                let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+                (public_inputs, proof, BallotOpening { vote: Fr::from(vote), randomness })
+            })
+            .collect();
+        
+        // 只计入A序号最大的选票，A的第一张被取代，序号相同的重复提交按防重标识重复拒绝
+        let result = system.tally(&vk, ballots).unwrap();
+        assert_eq!(result.counts, vec![1, 1]);
+        assert_eq!(result.accepted, vec![1, 2]);
+        let rejected: Vec<_> = result.rejected.iter().map(|r| (r.index, r.reason.as_str())).collect();
+        assert_eq!(rejected, vec![(0, "superseded_ballot"), (3, "duplicate_nullifier")]);
+    }
+    
+    #[test]
+    fn test_sequence_bound_to_nullifier() {
+        let mut rng = thread_rng();
+        let election_id = Fr::from(2024u64);
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(election_id, &mut rng).unwrap();
+        let prove = |secret: u64, sequence: u64, rng: &mut _| {
+            let circuit = VoteCircuit {
+                vote: Fr::one(),
+                secret: Fr::from(secret),
+                randomness: Fr::from(67890u64),
+                election_id,
+                sequence: Fr::from(sequence),
+                epoch: Fr::zero(),
+            };
+            let public_inputs = circuit.public_inputs();
+            (public_inputs, system.vote(&pk, circuit, rng).unwrap())
+        };
+        let (inputs, proof) = prove(111, 1, &mut rng);
+        let (other, _) = prove(222, 2, &mut rng);
+        assert!(system.verify_ballot(&vk, &inputs, &proof).unwrap().is_valid());
+        
+        // 证明不能改挂到更大的序号，也不能挂到其他投票人的防重标识下
+        let mut raised = inputs.clone();
+        raised.sequence = other.sequence;
+        assert!(!system.verify_ballot(&vk, &raised, &proof).unwrap().is_valid());
+        let mut moved = inputs.clone();
+        moved.nullifier = other.nullifier;
+        assert!(!system.verify_ballot(&vk, &moved, &proof).unwrap().is_valid());
+    }
+    
+    #[test]
+    fn test_sequence_out_of_range_rejected() {
+        // 超出64位的序号不满足约束
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: -Fr::one(),
+            epoch: Fr::zero(),
+        };
+        assert!(!VoteSystem::<Fr, Groth16<E>>::check_circuit(circuit).unwrap());
+    }
+
+    #[test]
+    fn test_nullifier_batch_circuit_satisfied() {
+        use crate::accumulator::{NullifierBatchCircuit, NULLIFIER_BATCH_SIZE};
+        use ark_relations::r1cs::ConstraintSynthesizer;
+
+        // 满批：64个严格递增的防重标识
+        let nullifiers: Vec<Fr> = (1..=NULLIFIER_BATCH_SIZE as u64).map(|i| Fr::from(i * 7)).collect();
+        let circuit = NullifierBatchCircuit {
+            nullifiers: nullifiers.clone(),
+            count: NULLIFIER_BATCH_SIZE,
+            acc_in: Fr::zero(),
+            prev_last: Fr::zero(),
+        };
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // 乱序时不满足
+        let mut unordered = nullifiers;
+        unordered.swap(3, 4);
+        let circuit = NullifierBatchCircuit {
+            nullifiers: unordered,
+            count: NULLIFIER_BATCH_SIZE,
+            acc_in: Fr::zero(),
+            prev_last: Fr::zero(),
+        };
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_nullifier_uniqueness_chain() {
+        use crate::accumulator::{
+            prove_nullifier_uniqueness, setup_nullifier_batch, verify_nullifier_uniqueness,
+        };
+
+        let mut rng = thread_rng();
+        let (pk, vk) = setup_nullifier_batch::<Fr, Groth16<E>, _>(4, &mut rng).unwrap();
+
+        // 10个防重标识分成3批，最后一批含填充
+        let nullifiers: Vec<Fr> = (0..10u64).map(|i| Fr::from(1000 - i * 13)).collect();
+        let proofs =
+            prove_nullifier_uniqueness::<Fr, Groth16<E>, _>(&pk, 4, &nullifiers, &mut rng).unwrap();
+        assert_eq!(proofs.len(), 3);
+        assert_eq!(proofs[2].inputs.count, Fr::from(2u64));
+        assert!(verify_nullifier_uniqueness(&vk, &proofs).unwrap().is_some());
+
+        // 批次缺失或顺序被打乱时链接检查失败
+        let mut reordered = proofs.clone();
+        reordered.swap(0, 1);
+        assert!(verify_nullifier_uniqueness(&vk, &reordered).unwrap().is_none());
+        assert!(verify_nullifier_uniqueness(&vk, &proofs[1..]).unwrap().is_none());
+
+        // 重复的防重标识无法生成证明
+        let mut duplicated = nullifiers;
+        duplicated.push(Fr::from(1000u64));
+        assert!(matches!(
+            prove_nullifier_uniqueness::<Fr, Groth16<E>, _>(&pk, 4, &duplicated, &mut rng),
+            Err(VoteError::DuplicateNullifier)
+        ));
+    }
+
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_nullifier_hash_to_field_fixed_vectors() {
+        use crate::nullifier::user_secret_to_field;
+
+        // 选举标识和用户秘密都以带域分隔标签的 BLAKE3 完整摘要按小端模域映射为字段元素，防重标识 = Poseidon(秘密, 选举标识)；
+        // 映射一旦改变，已签发的防重标识全部对不上，这里写死几组数值（测试向量文件可以重新生成，不能代替）
+        let election_id = election_id_to_field::<Fr>("election-2025");
+        assert_eq!(field_to_decimal(election_id), "5135135320025180420825248317189858089355303607165957920521501468835514229841");
+        assert_eq!(field_to_decimal(user_secret_to_field::<Fr>(b"alice-secret")), "13246697028274937749685650469017045476925537372915975127167288056008426328523");
+        assert_eq!(field_to_decimal(expected_nullifier::<Fr>(b"alice-secret", election_id)), "14370904691026726043651736692612191833090647127911328782886284222993378167526");
+
+        // 同一秘密在不同选举中的防重标识互不相同
+        assert_ne!(
+            expected_nullifier::<Fr>(b"alice-secret", election_id),
+            expected_nullifier(b"alice-secret", election_id_to_field::<Fr>("election-2026"))
+        );
+    }
+
+    #[test]
+    fn test_expected_nullifier_matches_proof() {
+        use crate::nullifier::{user_secret_to_field, NullifierCircuit};
+
+        let mut rng = thread_rng();
+        let election_id = election_id_to_field::<Fr>("election-2024");
+        let circuit = NullifierCircuit::new(b"alice-secret", election_id);
+        let (pk, vk) = Groth16::<E>::setup(circuit.clone(), &mut rng).unwrap();
+        let public_inputs = circuit.public_inputs();
+        let proof = Groth16::<E>::prove(&pk, circuit, &mut rng).unwrap();
+        assert!(Groth16::<E>::verify(&vk, &public_inputs, &proof).unwrap());
+
+        // 服务端独立计算的防重标识与证明公开的一致
+        assert_eq!(expected_nullifier(b"alice-secret", election_id), public_inputs[0]);
+        assert_ne!(expected_nullifier(b"bob-secret", election_id), public_inputs[0]);
+        assert_ne!(
+            expected_nullifier(b"alice-secret", election_id_to_field::<Fr>("election-2025")),
+            public_inputs[0]
+        );
+
+        // 投票电路在电路内做同样的派生：公开的防重标识只能是投票人秘密对应的那一个
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(election_id, &mut rng).unwrap();
+        let ballot = VoteCircuit {
+            vote: Fr::one(),
+            secret: user_secret_to_field(b"alice-secret"),
+            randomness: Fr::from(67890u64),
+            election_id,
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let ballot_inputs = ballot.public_inputs();
+        assert_eq!(ballot_inputs.nullifier, public_inputs[0]);
+        let proof = system.vote(&pk, ballot, &mut rng).unwrap();
+        assert!(system.verify(&vk, &ballot_inputs.to_vec(), &proof).unwrap());
+        let other = PublicInputs { nullifier: expected_nullifier(b"bob-secret", election_id), ..ballot_inputs };
+        assert!(!system.verify(&vk, &other.to_vec(), &proof).unwrap());
+    }
+
+    #[test]
+    fn test_prf_key_nullifiers_differ_per_key() {
+        let mut rng = thread_rng();
+        let election_id = election_id_to_field::<Fr>("election-2024");
+        let (key, other_key) = (PrfKey::<Fr>::generate(&mut rng), PrfKey::<Fr>::generate(&mut rng));
+        
+        // 同一密钥下同一用户的防重标识固定，不同密钥下不同；不持有密钥时无法由用户ID算出
+        let nullifier = key.nullifier("alice", election_id);
+        assert_eq!(nullifier, key.nullifier("alice", election_id));
+        assert_ne!(nullifier, other_key.nullifier("alice", election_id));
+        assert_ne!(nullifier, key.nullifier("bob", election_id));
+        assert_ne!(nullifier, key.nullifier("alice", election_id_to_field::<Fr>("election-2025")));
+        assert_ne!(nullifier, expected_nullifier(b"alice", election_id));
+        
+        // 十六进制读入；Debug 输出和错误信息不含密钥
+        let key_hex = "5a".repeat(32);
+        let parsed = PrfKey::<Fr>::from_hex(&key_hex).unwrap();
+        assert_eq!(parsed.commitment(), PrfKey::<Fr>::from_bytes(&[0x5a; 32]).commitment());
+        assert_eq!(format!("{:?}", parsed), format!("PrfKey {{ commitment: {:?}, .. }}", parsed.commitment().to_string()));
+        let err = PrfKey::<Fr>::from_hex(&key_hex[2..]).unwrap_err();
+        assert!(!err.to_string().contains("5a5a"));
+        
+        // 电路证明投票人秘密由承诺对应的 PRF 密钥派生
+        let blank = CandidateVoteCircuit::blank(2, election_id).with_prf_commitment(key.commitment());
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup_for_circuit(blank, &mut rng).unwrap();
+        let ballot = |prf_key: &PrfKey<Fr>| {
+            VoteCircuitBuilder::new(CircuitConfig::default())
+                .choice(VoteChoice::Yes)
+                .prf_key(prf_key, "alice")
+                .randomness(Fr::from(67890u64))
+                .election_id(election_id)
+                .build()
+                .unwrap()
+        };
+        let circuit = ballot(&key);
+        let public_inputs = circuit.ballot.public_inputs();
+        assert_eq!(public_inputs.nullifier, nullifier);
+        let (proof, _) = system.vote_with_report(&pk, circuit, &mut rng).unwrap();
+        assert!(system.verify_ballot(&vk, &public_inputs, &proof).unwrap().is_valid());
+        
+        // 其他密钥派生的投票人秘密与绑定的承诺不符
+        let mut forged = ballot(&other_key);
+        assert!(VoteSystem::<Fr, Groth16<E>>::check_circuit(forged.clone()).unwrap());
+        forged.prf.as_mut().unwrap().commitment = key.commitment();
+        assert!(!VoteSystem::<Fr, Groth16<E>>::check_circuit(forged).unwrap());
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_nullifier_derived_from_server_prf_key() {
+        use crate::web::{vk_route, vote_route, AppState, VoteResponse};
+        
+        let vote = |state: AppState<MockSNARK>| async move {
+            let route = vote_route(state);
+            let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 200);
+            let reply: VoteResponse = serde_json::from_slice(resp.body()).unwrap();
+            assert!(reply.success);
+            crate::field::fr_from_hex::<Fr>(&reply.nullifier.unwrap()).unwrap()
+        };
+        
+        // 同一用户在两个持有不同 PRF 密钥的服务上得到不同的防重标识，均等于 PRF_密钥(用户ID, 选举标识)
+        let key_hex = ["01".repeat(32), "02".repeat(32)];
+        let mut nullifiers = Vec::new();
+        for key_hex in &key_hex {
+            let state = AppState::<MockSNARK>::try_with_prf_key(PrfKey::from_hex(key_hex).unwrap()).unwrap();
+            let expected = PrfKey::<Fr>::from_hex(key_hex).unwrap();
+            assert_eq!(state.prf_key_commitment(), Some(expected.commitment()));
+            // 承诺随验证密钥公布，验证方据此得知证明绑定的 PRF 密钥
+            let resp = warp::test::request().method("GET").path("/vk").reply(&vk_route(state.clone())).await;
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["prf_key_commitment"], crate::field::fr_to_hex(expected.commitment()));
+            let nullifier = vote(state).await;
+            assert_eq!(nullifier, expected.nullifier("alice", Fr::zero()));
+            nullifiers.push(nullifier);
+        }
+        assert_ne!(nullifiers[0], nullifiers[1]);
+    }
+    
+    #[test]
+    fn test_verify_ballot_checks_input_count() {
+        use crate::nullifier::NullifierCircuit;
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        assert_eq!(
+            system.verify_ballot(&vk, &public_inputs, &proof).unwrap(),
+            VerificationOutcome::Valid
+        );
+
+        let mut tampered = public_inputs.clone();
+        tampered.sequence = Fr::one();
+        assert_eq!(
+            system.verify_ballot(&vk, &tampered, &proof).unwrap(),
+            VerificationOutcome::Invalid { reason: InvalidReason::ProofRejected }
+        );
+
+        // 其他电路的验证密钥公开输入数量不同，直接报 KeyMismatch
+        let (_, other_vk) =
+            Groth16::<E>::setup(NullifierCircuit::new(b"secret", Fr::zero()), &mut rng).unwrap();
+        assert!(matches!(
+            system.verify_ballot(&other_vk, &public_inputs, &proof),
+            Err(VoteError::KeyMismatch(_))
+        ));
+    }
+
+    // 密钥生成总是失败的模拟证明系统
+    struct FailingSetup;
+
+    impl ProofSystem<Fr> for FailingSetup {
+        type ProvingKey = ark_groth16::ProvingKey<E>;
+        type VerifyingKey = ark_groth16::VerifyingKey<E>;
+        type Proof = ark_groth16::Proof<E>;
+
+        const NAME: &'static str = "failing";
+
+        fn setup<C, R>(_circuit: C, _rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+        where
+            C: ConstraintSynthesizer<Fr> + Clone,
+            R: RngCore + CryptoRng,
+        {
+            Err(VoteError::Setup("injected setup failure".into()))
+        }
+
+        fn prove<C, R>(_pk: &Self::ProvingKey, _circuit: C, _rng: &mut R) -> Result<Self::Proof, VoteError>
+        where
+            C: ConstraintSynthesizer<Fr>,
+            R: RngCore + CryptoRng,
+        {
+            unreachable!()
+        }
+
+        fn verify(_vk: &Self::VerifyingKey, _public_inputs: &[Fr], _proof: &Self::Proof) -> Result<bool, VoteError> {
+            unreachable!()
+        }
+    }
+
+    // 记录验证密钥被克隆次数的验证密钥包装
+    static COUNTING_VK_CLONES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+    #[derive(CanonicalSerialize, CanonicalDeserialize)]
+    struct CountingVk(ark_groth16::VerifyingKey<E>);
+
+    impl Clone for CountingVk {
+        fn clone(&self) -> Self {
+            COUNTING_VK_CLONES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Self(self.0.clone())
+        }
+    }
+
+    // 以 CountingVk 为验证密钥的 Groth16
+    struct CountingGroth16;
+
+    impl ProofSystem<Fr> for CountingGroth16 {
+        type ProvingKey = ark_groth16::ProvingKey<E>;
+        type VerifyingKey = CountingVk;
+        type Proof = ark_groth16::Proof<E>;
+
+        const NAME: &'static str = "counting-groth16";
+
+        fn setup<C, R>(circuit: C, rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+        where
+            C: ConstraintSynthesizer<Fr> + Clone,
+            R: RngCore + CryptoRng,
+        {
+            let (pk, vk) = <Groth16<E> as ProofSystem<Fr>>::setup(circuit, rng)?;
+            Ok((pk, CountingVk(vk)))
+        }
+
+        fn prove<C, R>(pk: &Self::ProvingKey, circuit: C, rng: &mut R) -> Result<Self::Proof, VoteError>
+        where
+            C: ConstraintSynthesizer<Fr>,
+            R: RngCore + CryptoRng,
+        {
+            <Groth16<E> as ProofSystem<Fr>>::prove(pk, circuit, rng)
+        }
+
+        fn verify(vk: &Self::VerifyingKey, public_inputs: &[Fr], proof: &Self::Proof) -> Result<bool, VoteError> {
+            <Groth16<E> as ProofSystem<Fr>>::verify(&vk.0, public_inputs, proof)
+        }
+
+        fn num_public_inputs(vk: &Self::VerifyingKey) -> Option<usize> {
+            <Groth16<E> as ProofSystem<Fr>>::num_public_inputs(&vk.0)
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_concurrent_verification_shares_verifying_key() {
+        use std::sync::atomic::Ordering;
+        use std::sync::Arc;
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, CountingGroth16>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(2718u64),
+            randomness: Fr::from(3141u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+
+        let system = Arc::new(system);
+        let vk = Arc::new(vk);
+        let inputs: Arc<[Fr]> = public_inputs.to_vec().into();
+        let clones_before = COUNTING_VK_CLONES.load(Ordering::SeqCst);
+
+        let mut handles = Vec::new();
+        for i in 0..100 {
+            let system = system.clone();
+            let vk = vk.clone();
+            let inputs = inputs.clone();
+            let public_inputs = public_inputs.clone();
+            let proof = proof.clone();
+            handles.push(tokio::spawn(async move {
+                if i % 2 == 0 {
+                    system.verify_async(vk, inputs, proof).await
+                } else {
+                    system.verify_ballot_async(vk, public_inputs, proof).await.map(|o| o.is_valid())
+                }
+            }));
+        }
+        for handle in handles {
+            assert!(handle.await.unwrap().unwrap());
+        }
+
+        assert_eq!(COUNTING_VK_CLONES.load(Ordering::SeqCst), clones_before);
+    }
+
+    // 证明固定耗时 300ms 的模拟证明系统（其余同 MockSNARK）
+    struct SlowMockSNARK;
+
+    impl ProofSystem<Fr> for SlowMockSNARK {
+        type ProvingKey = crate::backend::mock_backend::MockKey;
+        type VerifyingKey = crate::backend::mock_backend::MockKey;
+        type Proof = crate::backend::mock_backend::MockProof;
+
+        const NAME: &'static str = "slow-mock";
+
+        fn setup<C, R>(circuit: C, rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+        where
+            C: ConstraintSynthesizer<Fr> + Clone,
+            R: RngCore + CryptoRng,
+        {
+            <MockSNARK as ProofSystem<Fr>>::setup(circuit, rng)
+        }
+
+        fn prove<C, R>(pk: &Self::ProvingKey, circuit: C, rng: &mut R) -> Result<Self::Proof, VoteError>
+        where
+            C: ConstraintSynthesizer<Fr>,
+            R: RngCore + CryptoRng,
+        {
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            <MockSNARK as ProofSystem<Fr>>::prove(pk, circuit, rng)
+        }
+
+        fn verify(vk: &Self::VerifyingKey, public_inputs: &[Fr], proof: &Self::Proof) -> Result<bool, VoteError> {
+            <MockSNARK as ProofSystem<Fr>>::verify(vk, public_inputs, proof)
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_proving_timeout_returns_504() {
+        use crate::web::{vote_route, AppState};
+        use std::time::Duration;
+
+        let (system, pk, vk) = VoteSystem::<Fr, SlowMockSNARK>::setup(Fr::zero(), &mut thread_rng()).unwrap();
+        let pool = system.prover_pool().clone();
+        let state = AppState::from_parts(system, pk, vk, crate::store::MemoryBackend::default(), Default::default())
+            .with_proving_timeout(Duration::from_millis(50));
+        let route = vote_route(state.clone());
+        let body = serde_json::json!({ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" });
+
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 504);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "timeout");
+
+        // 超时后不再等待，阻塞线程上的证明阶段跑完后释放槽位
+        for _ in 0..100 {
+            if pool.running() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(pool.running(), 0);
+        assert_eq!(pool.queue_depth(), 0);
+
+        // 超时的选票不占用防重标识，超时时间足够时同一用户可以重新投票
+        let route = vote_route(state.with_proving_timeout(Duration::from_secs(60)));
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vote_rejected_when_prover_pool_saturated() {
+        use crate::web::{vote_route, AppState};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        // 一个证明槽位、最多一个排队
+        let pool = Arc::new(ProverPool::new(1, 0).with_max_queued(1));
+        let (system, pk, vk) = VoteSystem::<Fr, SlowMockSNARK>::setup(Fr::zero(), &mut thread_rng()).unwrap();
+        let state = AppState::from_parts(system, pk, vk, crate::store::MemoryBackend::default(), Default::default())
+            .with_prover_pool(pool.clone());
+        let route = vote_route(state);
+        let vote = |user: &str| {
+            let route = route.clone();
+            let body = serde_json::json!({ "vote": 1, "user_id": user, "chain": "localhost", "gas_option": "relayer" });
+            async move { warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await }
+        };
+
+        // 第一张选票占用槽位，第二张排队
+        let first = tokio::spawn(vote("alice"));
+        while pool.running() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let second = tokio::spawn(vote("bob"));
+        while pool.queue_depth() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // 槽位和排队都已满：立即返回 503，不等待
+        let resp = vote("carol").await;
+        assert_eq!(resp.status(), 503);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "at_capacity");
+        assert_eq!(reply["message"], "Server at capacity");
+
+        // 排队的选票在槽位释放后完成
+        assert_eq!(first.await.unwrap().status(), 200);
+        assert_eq!(second.await.unwrap().status(), 200);
+        assert_eq!(pool.queue_depth(), 0);
+
+        // 被拒绝的投票人未被登记，空闲时可以重新投票
+        assert_eq!(vote("carol").await.status(), 200);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_app_state_try_new_propagates_setup_failure() {
+        use crate::web::AppState;
+
+        let result = AppState::<FailingSetup>::try_new();
+        assert!(matches!(result, Err(VoteError::Setup(_))));
+    }
+
+    #[test]
+    fn test_key_fingerprint_integrity() {
+        use crate::fingerprint::{decode_key, encode_key};
+
+        let mut rng = thread_rng();
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let bytes = encode_key::<Fr, _>(&pk).unwrap();
+        let decoded: ark_groth16::ProvingKey<E> = decode_key::<Fr, _>(&bytes).unwrap();
+        assert_eq!(fingerprint::<Fr, _>(&decoded).unwrap(), fingerprint::<Fr, _>(&pk).unwrap());
+        assert_ne!(fingerprint::<Fr, _>(&pk).unwrap(), fingerprint::<Fr, _>(&vk).unwrap());
+
+        // 截断的证明密钥加载失败
+        let truncated = &bytes[..bytes.len() - 64];
+        assert!(decode_key::<Fr, ark_groth16::ProvingKey<E>>(truncated).is_err());
+
+        // 头部指纹被篡改时加载失败
+        let mut tampered = bytes.clone();
+        tampered[12] ^= 1;
+        assert!(matches!(
+            decode_key::<Fr, ark_groth16::ProvingKey<E>>(&tampered),
+            Err(VoteError::KeyMismatch(_))
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_key_files_rejected_for_mismatched_config() {
+        use crate::fingerprint::{encode_key, encode_key_for_config, key_config_digest};
+        use crate::store::MemoryBackend;
+        use crate::web::AppState;
+
+        // 按3个候选人生成的密钥
+        let three = CircuitConfig::with_candidates(3);
+        let (_, pk, vk) = VoteSystem::<Fr, MockSNARK>::setup_with_candidates(3, Fr::zero(), &mut thread_rng()).unwrap();
+        let pk_file = encode_key_for_config::<Fr, _>(&pk, &three).unwrap();
+        let vk_file = encode_key_for_config::<Fr, _>(&vk, &three).unwrap();
+        assert_eq!(key_config_digest::<Fr>(&pk_file).unwrap(), Some(three.digest()));
+        assert_eq!(key_config_digest::<Fr>(&encode_key::<Fr, _>(&pk).unwrap()).unwrap(), None);
+
+        let state = AppState::<MockSNARK>::from_key_files(&pk_file, &vk_file, three, MemoryBackend::default()).unwrap();
+        assert_eq!((state.num_candidates(), state.config_digest()), (3, three.digest()));
+
+        // 服务端配置为2个候选人时拒绝启动
+        let two = CircuitConfig::with_candidates(2);
+        match AppState::<MockSNARK>::from_key_files(&pk_file, &vk_file, two, MemoryBackend::default()) {
+            Err(e @ VoteError::ConfigMismatch(_)) => {
+                assert_eq!(e.code(), "config_mismatch");
+                assert!(e.to_string().contains("proving key"), "{}", e);
+            }
+            other => panic!("expected ConfigMismatch, got {:?}", other.map(|_| ())),
+        }
+
+        // 两个密钥文件记录的配置不一致，或密钥文件没有记录配置时同样拒绝
+        let (_, _, vk_two) = VoteSystem::<Fr, MockSNARK>::setup_with_candidates(2, Fr::zero(), &mut thread_rng()).unwrap();
+        let vk_two_file = encode_key_for_config::<Fr, _>(&vk_two, &two).unwrap();
+        assert!(matches!(
+            AppState::<MockSNARK>::from_key_files(&pk_file, &vk_two_file, three, MemoryBackend::default()),
+            Err(VoteError::ConfigMismatch(_))
+        ));
+        let legacy = encode_key::<Fr, _>(&pk).unwrap();
+        assert!(matches!(
+            AppState::<MockSNARK>::from_key_files(&legacy, &vk_file, three, MemoryBackend::default()),
+            Err(VoteError::ConfigMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_proving_key_handle_loads_lazily() {
+        use crate::fingerprint::encode_key;
+        
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let bytes = encode_key::<Fr, _>(&pk).unwrap();
+        let dir = std::env::temp_dir();
+        let path_a = dir.join(format!("zkp-vote-pk-a-{}.key", std::process::id()));
+        let path_b = dir.join(format!("zkp-vote-pk-b-{}.key", std::process::id()));
+        std::fs::write(&path_a, &bytes).unwrap();
+        std::fs::write(&path_b, &bytes).unwrap();
+        
+        // 打开两场选举的密钥都不会立即加载
+        let handle_a = ProvingKeyHandle::<Fr, Groth16<E>>::open_mmap(&path_a).unwrap();
+        let handle_b = ProvingKeyHandle::<Fr, Groth16<E>>::open_mmap(&path_b).unwrap();
+        assert_eq!(handle_a.fingerprint(), fingerprint::<Fr, _>(&pk).unwrap());
+        assert!(!handle_a.is_loaded());
+        assert!(!handle_b.is_loaded());
+        
+        // 首次生成证明时才加载，且只加载被使用的那一份
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs().to_vec();
+        let proof = system.vote_with_handle(&handle_a, circuit, &mut rng).unwrap();
+        assert!(system.verify(&vk, &public_inputs, &proof).unwrap());
+        assert!(handle_a.is_loaded());
+        assert!(!handle_b.is_loaded());
+        
+        // 密钥本体中翻转一个字节：头部校验通过，加载时检测到损坏
+        let mut corrupted = bytes.clone();
+        let index = corrupted.len() - 100;
+        corrupted[index] ^= 1;
+        std::fs::write(&path_b, &corrupted).unwrap();
+        let handle_b = ProvingKeyHandle::<Fr, Groth16<E>>::open_mmap(&path_b).unwrap();
+        assert!(handle_b.get().is_err());
+        assert!(!handle_b.is_loaded());
+        
+        // 头部损坏时打开即失败
+        corrupted[0] ^= 1;
+        std::fs::write(&path_b, &corrupted).unwrap();
+        assert!(ProvingKeyHandle::<Fr, Groth16<E>>::open_mmap(&path_b).is_err());
+        
+        drop(handle_a);
+        drop(handle_b);
+        std::fs::remove_file(&path_a).unwrap();
+        std::fs::remove_file(&path_b).unwrap();
+    }
+    
+    #[test]
+    fn test_proof_envelope_checks_vk_fingerprint() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let (_, _, other_vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+
+        let envelope = ProofEnvelope::<Fr, Groth16<E>>::new(&vk, public_inputs, proof).unwrap();
+        let envelope = ProofEnvelope::<Fr, Groth16<E>>::from_bytes(&envelope.to_bytes().unwrap()).unwrap();
+        assert_eq!(envelope.vk_fingerprint, fingerprint::<Fr, _>(&vk).unwrap());
+        assert!(system.verify_envelope(&vk, &envelope).unwrap().is_valid());
+
+        // 用其他验证密钥验证时直接报 KeyMismatch
+        assert!(matches!(
+            system.verify_envelope(&other_vk, &envelope),
+            Err(VoteError::KeyMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_stream_skips_corrupted_record() {
+        use crate::stream::write_envelope;
+
+        // 以模拟后端生成若干张选票，循环写入一万条记录
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, MockSNARK>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let envelopes: Vec<_> = (0..8u64)
+            .map(|i| {
+                let circuit = VoteCircuit {
+                    vote: Fr::from(i % 2),
+                    secret: Fr::from(1000 + i),
+                    randomness: Fr::from(2000 + i),
+                    election_id: Fr::from(2024u64),
+                    sequence: Fr::zero(),
+                    epoch: Fr::zero(),
+                };
+                let public_inputs = circuit.public_inputs();
+                let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+                ProofEnvelope::<Fr, MockSNARK>::new(&vk, public_inputs, proof).unwrap()
+            })
+            .collect();
+
+        const RECORDS: usize = 10_000;
+        const CORRUPTED: usize = RECORDS / 2;
+        let mut stream = Vec::new();
+        let mut offsets = Vec::with_capacity(RECORDS);
+        for i in 0..RECORDS {
+            offsets.push(stream.len() as u64);
+            write_envelope(&mut stream, &envelopes[i % envelopes.len()]).unwrap();
+        }
+        // 破坏中间一条记录的魔数（长度前缀完好）
+        stream[offsets[CORRUPTED] as usize + 4] ^= 0xff;
+
+        let verdicts: Vec<_> = system
+            .verify_stream(&vk, std::io::BufReader::new(stream.as_slice()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(verdicts.len(), RECORDS);
+        for (i, verdict) in verdicts.iter().enumerate() {
+            assert_eq!(verdict.index, i as u64);
+            assert_eq!(verdict.offset, offsets[i]);
+            if i == CORRUPTED {
+                assert!(matches!(verdict.result, Err(VoteError::Serialization(_))));
+                assert!(verdict.public_inputs.is_none());
+            } else {
+                assert!(verdict.is_valid(), "record {} should verify", i);
+                assert_eq!(verdict.public_inputs.as_ref(), Some(&envelopes[i % envelopes.len()].public_inputs));
+            }
+        }
+
+        // 公开输入被篡改的记录验证不通过，其他验证密钥的记录报 KeyMismatch
+        let mut forged_inputs = envelopes[0].public_inputs.clone();
+        forged_inputs.sequence = Fr::one();
+        let forged = ProofEnvelope::<Fr, MockSNARK>::new(&vk, forged_inputs, envelopes[0].proof.clone()).unwrap();
+        let (_, _, other_vk) = VoteSystem::<Fr, MockSNARK>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let other =
+            ProofEnvelope::<Fr, MockSNARK>::new(&other_vk, envelopes[1].public_inputs.clone(), envelopes[1].proof.clone()).unwrap();
+        let mut stream = Vec::new();
+        for envelope in [&forged, &other, &envelopes[2]] {
+            write_envelope(&mut stream, envelope).unwrap();
+        }
+        // 末尾被截断的记录：产出错误后结束
+        stream.extend_from_slice(&[0x10, 0x00]);
+        let results: Vec<_> = system.verify_stream(&vk, stream.as_slice()).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results[0].as_ref().unwrap().result.as_ref().unwrap().invalid_reason(),
+            Some(InvalidReason::ProofRejected)
+        );
+        assert!(matches!(results[1].as_ref().unwrap().result, Err(VoteError::KeyMismatch(_))));
+        assert!(results[2].as_ref().unwrap().is_valid());
+        assert!(matches!(results[3], Err(VoteError::Serialization(_))));
+
+        // 长度前缀超出上限时无法定位下一条记录，流就此结束
+        let mut stream = u32::MAX.to_le_bytes().to_vec();
+        write_envelope(&mut stream, &envelopes[0]).unwrap();
+        let results: Vec<_> = system.verify_stream(&vk, stream.as_slice()).collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_proof_envelope_v1_fixtures() {
+        // 版本 1 信封的金标文件随仓库提交，升级后必须仍能解码并通过验证；设置 UPDATE_GOLDEN=1 重新生成
+        let bin_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/proof_envelope_v1.bin");
+        let json_path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/proof_envelope_v1.json");
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::from(2024u64), [0u8; 32]).unwrap();
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let circuit = VoteCircuit {
+                vote: Fr::one(),
+                secret: Fr::from(12345u64),
+                randomness: Fr::from(67890u64),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            };
+            let public_inputs = circuit.public_inputs();
+            let proof = system.vote(&pk, circuit, &mut thread_rng()).unwrap();
+            // 按版本 1 的布局编码：头部和 JSON 都没有点格式字段
+            let mut envelope = ProofEnvelope::<Fr, Groth16<E>>::new(&vk, public_inputs, proof).unwrap();
+            envelope.format_version = 1;
+            let json = serde_json::to_string_pretty(&envelope.to_json().unwrap()).unwrap();
+            let json: Vec<&str> = json.lines().filter(|line| !line.trim_start().starts_with("\"compression\"")).collect();
+            std::fs::write(bin_path, envelope.to_bytes().unwrap()).unwrap();
+            std::fs::write(json_path, json.join("\n") + "\n").unwrap();
+            return;
+        }
+
+        let read = |path: &str| {
+            std::fs::read(path).unwrap_or_else(|e| panic!("cannot read {}: {}; rerun with UPDATE_GOLDEN=1 to create it", path, e))
+        };
+        let bin = read(bin_path);
+        let json = String::from_utf8(read(json_path)).unwrap();
+
+        let from_bin = ProofEnvelope::<Fr, Groth16<E>>::from_bytes(&bin).unwrap();
+        let from_json = ProofEnvelope::<Fr, Groth16<E>>::from_json_str(&json).unwrap();
+        assert_eq!(from_bin.format_version, 1);
+        assert_eq!(from_bin.curve_id, CurveId::Bn254);
+        assert_eq!(from_bin.circuit_version, CIRCUIT_VERSION);
+        assert_eq!(from_bin.vk_fingerprint, fingerprint::<Fr, _>(&vk).unwrap());
+        assert_eq!(from_bin.public_inputs, from_json.public_inputs);
+        assert_eq!(from_bin.proof, from_json.proof);
+        assert!(system.verify_envelope(&vk, &from_bin).unwrap().is_valid());
+
+        // 两种编码都能无损重现
+        assert_eq!(from_bin.to_bytes().unwrap(), bin);
+        assert_eq!(from_json.to_json().unwrap(), serde_json::from_str::<ProofEnvelopeJson>(&json).unwrap());
+
+        // 未来版本（以及不存在的版本 0）报 UnsupportedVersion，而不是当作损坏数据
+        for version in [0u32, PROOF_ENVELOPE_VERSION + 1] {
+            let mut future = bin.clone();
+            future[4..8].copy_from_slice(&version.to_le_bytes());
+            assert!(matches!(
+                ProofEnvelope::<Fr, Groth16<E>>::from_bytes(&future),
+                Err(VoteError::UnsupportedVersion(_))
+            ));
+        }
+        // 未来版本的 JSON 字段布局可能完全不同
+        let future = serde_json::json!({ "format_version": PROOF_ENVELOPE_VERSION + 1, "payload": "opaque" }).to_string();
+        let err = ProofEnvelope::<Fr, Groth16<E>>::from_json_str(&future).err().unwrap();
+        assert_eq!(err.code(), "unsupported_version");
+    }
+
+    #[cfg(feature = "curve-bls12-381")]
+    #[test]
+    fn test_bls12_vote_flow() {
+        use ark_bls12_381::Fr as BlsFr;
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = Bls12VoteSystem::setup(election_id_to_field("bls-election"), &mut rng).unwrap();
+        let circuit = VoteCircuit {
+            vote: BlsFr::one(),
+            secret: BlsFr::from(12345u64),
+            randomness: BlsFr::from(67890u64),
+            election_id: election_id_to_field("bls-election"),
+            sequence: BlsFr::zero(),
+            epoch: BlsFr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(CurveId::of::<BlsFr>(), Some(CurveId::Bls12_381));
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        assert!(system.verify_ballot(&vk, &public_inputs, &proof).unwrap().is_valid());
+    }
+
+    #[cfg(all(feature = "curve-bn254", feature = "curve-bls12-381"))]
+    #[test]
+    fn test_cross_curve_serialization_rejected() {
+        use crate::fingerprint::{decode_key, encode_key};
+        use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = Bn254VoteSystem::setup(Fr::from(2024u64), &mut rng).unwrap();
+
+        // BN254 密钥文件不能作为 BLS12-381 密钥加载
+        let bytes = encode_key::<Fr, _>(&pk).unwrap();
+        assert!(matches!(
+            decode_key::<BlsFr, ark_groth16::ProvingKey<Bls12_381>>(&bytes),
+            Err(VoteError::KeyMismatch(_))
+        ));
+
+        // BN254 证明信封不能在 BLS12-381 上解析
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret: Fr::from(12345u64),
+            randomness: Fr::from(67890u64),
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        let envelope = ProofEnvelope::<Fr, Groth16<E>>::new(&vk, public_inputs, proof).unwrap();
+        assert!(matches!(
+            ProofEnvelope::<BlsFr, Groth16<Bls12_381>>::from_bytes(&envelope.to_bytes().unwrap()),
+            Err(VoteError::KeyMismatch(_))
+        ));
+
+        // 两条曲线使用各自的承诺参数
+        assert_ne!(crate::curve::params::<Fr>(), crate::curve::params::<BlsFr>());
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_vk_fingerprint_distinguishes_key_mismatch() {
+        use crate::web::{verify_route, vk_route, vote_batch_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new();
+        let other = AppState::<Groth16<E>>::new();
+        let route = vote_batch_route(state.clone()).or(verify_route(state.clone())).or(vk_route(state));
+
+        let body = serde_json::json!([{ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" }]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        let proof = votes[0]["proof"].clone();
+        let public_inputs = votes[0]["public_inputs"].clone();
+
+        // 证明元数据中的指纹与 /vk 公布的指纹一致
+        let resp = warp::test::request().method("GET").path("/vk").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let vk: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(votes[0]["vk_fingerprint"], vk["vk_fingerprint"]);
+
+        // 另一次密钥生成的验证密钥：报 key_mismatch，而不是验证失败
+        let resp = warp::test::request().method("GET").path("/vk").reply(&vk_route(other.clone())).await;
+        let other_vk: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_ne!(other_vk["vk_fingerprint"], vk["vk_fingerprint"]);
+        let body = serde_json::json!({
+            "proof": proof,
+            "public_inputs": public_inputs,
+            "vk_fingerprint": votes[0]["vk_fingerprint"],
+        });
+        let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&verify_route(other)).await;
+        assert_eq!(resp.status(), 409);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "key_mismatch");
+
+        // 验证密钥匹配但公开输入被篡改：正常返回无效
+        let mut forged_inputs = public_inputs.clone();
+        forged_inputs[2] = serde_json::json!("1");
+        let body = serde_json::json!({
+            "proof": proof,
+            "public_inputs": forged_inputs,
+            "vk_fingerprint": votes[0]["vk_fingerprint"],
+        });
+        let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["valid"], false);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_verify_endpoint_accepts_envelope() {
+        use crate::web::{verify_route, vote_batch_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::new_mock();
+        let route = vote_batch_route(state.clone()).or(verify_route(state));
+        let body = serde_json::json!([
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        let alice = votes[0]["envelope"].clone();
+        let bob = votes[1]["envelope"].clone();
+        let verify = |body: serde_json::Value| {
+            let route = route.clone();
+            async move {
+                let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&route).await;
+                (resp.status(), serde_json::from_slice::<serde_json::Value>(resp.body()).unwrap())
+            }
+        };
+
+        // 有效信封：公开输入取自信封
+        let (status, reply) = verify(serde_json::json!({ "envelope": alice })).await;
+        assert_eq!(status, 200);
+        assert_eq!(reply["valid"], true);
+        let (_, reply) = verify(serde_json::json!({ "envelope": alice, "public_inputs": alice["public_inputs"] })).await;
+        assert_eq!(reply["valid"], true);
+
+        // 证明被篡改：200、valid=false
+        let mut tampered = alice.clone();
+        tampered["proof"] = bob["proof"].clone();
+        let (status, reply) = verify(serde_json::json!({ "envelope": tampered })).await;
+        assert_eq!(status, 200);
+        assert_eq!(reply["valid"], false);
+        assert_eq!(reply["reason"], "proof_rejected");
+
+        // 冒用他人的防重标识重放证明：200、valid=false
+        let mut claimed = alice["public_inputs"].clone();
+        claimed[0] = bob["public_inputs"][0].clone();
+        let (status, reply) = verify(serde_json::json!({ "envelope": alice, "public_inputs": claimed })).await;
+        assert_eq!(status, 200);
+        assert_eq!(reply["valid"], false);
+
+        // 信封格式错误，或同时提交证明和信封：400
+        let mut malformed = alice.clone();
+        malformed["proof"] = serde_json::json!("not hex");
+        let (status, reply) = verify(serde_json::json!({ "envelope": malformed })).await;
+        assert_eq!(status, 400);
+        assert_eq!(reply["success"], false);
+        let (status, _) = verify(serde_json::json!({ "envelope": alice, "proof": votes[0]["proof"] })).await;
+        assert_eq!(status, 400);
+        let (status, _) = verify(serde_json::json!({ "public_inputs": alice["public_inputs"] })).await;
+        assert_eq!(status, 400);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_verify_distinguishes_false_proof_from_internal_error() {
+        use crate::store::MemoryBackend;
+        use crate::web::{verify_route, vote_batch_route, AppState};
+        use warp::Filter;
+
+        let mut rng = thread_rng();
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::zero(), &mut rng).unwrap();
+        let state = AppState::<Groth16<E>>::from_keys(pk.clone(), vk.clone(), MemoryBackend::default()).unwrap();
+        let route = vote_batch_route(state.clone()).or(verify_route(state));
+
+        let body = serde_json::json!([{ "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" }]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        let proof = votes[0]["proof"].clone();
+        let public_inputs = votes[0]["public_inputs"].clone();
+
+        // 有效证明：200、valid=true
+        let body = serde_json::json!({ "proof": proof, "public_inputs": public_inputs });
+        let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+
+        // 格式正确但验证不通过的证明：200、valid=false
+        let mut forged_inputs = public_inputs.clone();
+        forged_inputs[0] = serde_json::json!("12345");
+        let forged = serde_json::json!({ "proof": proof, "public_inputs": forged_inputs });
+        let resp = warp::test::request().method("POST").path("/verify").json(&forged).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], true);
+        assert_eq!(reply["valid"], false);
+        assert_eq!(reply["reason"], "proof_rejected");
+
+        // 服务端验证密钥损坏：500（from_keys 会拒绝不配对的密钥，这里绕过检查直接构造）
+        let mut corrupted = vk;
+        corrupted.gamma_abc_g1.truncate(2);
+        let broken = AppState::<Groth16<E>>::from_parts(
+            VoteSystem::new(),
+            pk,
+            corrupted,
+            MemoryBackend::default(),
+            crate::web::SharedRng::default(),
+        );
+        let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&verify_route(broken)).await;
+        assert_eq!(resp.status(), 500);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], false);
+        assert_eq!(reply["code"], "internal_error");
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_app_state_keys_per_election() {
+        use crate::web::{verify_batch_route, verify_route, vk_route, vote_batch_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new();
+        let route = vote_batch_route(state.clone()).or(verify_route(state.clone())).or(vk_route(state.clone()));
+        let vote = |election_id: &str| {
+            serde_json::json!([{
+                "vote": 1, "user_id": "alice", "election_id": election_id,
+                "chain": "localhost", "gas_option": "relayer",
+            }])
+        };
+
+        // 没有密钥的选举：拒绝投票，查询验证密钥返回 404
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&vote("e-a")).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(votes[0]["success"], false);
+        assert_eq!(votes[0]["error_code"], "unknown_election");
+        let resp = warp::test::request().method("GET").path("/vk?election_id=e-a").reply(&route).await;
+        assert_eq!(resp.status(), 404);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "unknown_election");
+
+        // 为两场选举分别生成密钥，重复生成返回已有的验证密钥
+        let vk_a = state.setup_election("e-a").await.unwrap();
+        let vk_b = state.setup_election("e-b").await.unwrap();
+        assert!(Arc::ptr_eq(&vk_a, &state.setup_election("e-a").await.unwrap()));
+        assert_ne!(vk_digest(&vk_a), vk_digest(&vk_b));
+
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&vote("e-a")).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(votes[0]["success"], true);
+        let verify = |election_id: &str| {
+            serde_json::json!({
+                "proof": votes[0]["proof"], "public_inputs": votes[0]["public_inputs"], "election_id": election_id,
+            })
+        };
+
+        // 按选举查找验证密钥：选举 A 的选票不能用选举 B 的密钥通过验证
+        let resp = warp::test::request().method("POST").path("/verify").json(&verify("e-a")).reply(&route).await;
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+        let resp = warp::test::request().method("POST").path("/verify").json(&verify("e-b")).reply(&route).await;
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["valid"], false);
+        let resp = warp::test::request().method("POST").path("/verify").json(&verify("e-c")).reply(&route).await;
+        assert_eq!(resp.status(), 404);
+
+        // 批量验证同样逐条按选举标识和验证密钥指纹查找验证密钥，同一批中的条目可属于不同选举
+        let route = route.or(verify_batch_route(state.clone()));
+        let entry = |election_id: &str| {
+            serde_json::json!({
+                "proof": votes[0]["proof"], "public_inputs": votes[0]["public_inputs"], "election_id": election_id,
+            })
+        };
+        let mut pinned_to_b = entry("e-b");
+        pinned_to_b["vk_fingerprint"] = votes[0]["vk_fingerprint"].clone();
+        let mut pinned_to_a = entry("e-a");
+        pinned_to_a["vk_fingerprint"] = votes[0]["vk_fingerprint"].clone();
+        let unscoped = serde_json::json!({ "proof": votes[0]["proof"], "public_inputs": votes[0]["public_inputs"] });
+        let body = serde_json::json!([entry("e-a"), entry("e-b"), entry("e-c"), pinned_to_b, pinned_to_a, unscoped]);
+        let resp = warp::test::request().method("POST").path("/verify/batch").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let results: Vec<Option<bool>> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(results, vec![Some(true), Some(false), None, None, Some(true), Some(false)]);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_app_state_election_params() {
+        use crate::web::{election_params_route, verify_route, vote_batch_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new();
+        let route = vote_batch_route(state.clone())
+            .or(verify_route(state.clone()))
+            .or(election_params_route(state.clone()));
+        let params = ElectionParams::from(CircuitConfig { num_candidates: 3, allow_abstain: true, allow_spoiled: false });
+
+        // 未按参数生成密钥的选举查询参数返回 404
+        let resp = warp::test::request().method("GET").path("/elections/e-p/params").reply(&route).await;
+        assert_eq!(resp.status(), 404);
+
+        // 按参数生成密钥，相同参数重复生成返回已有的验证密钥，不同参数返回 KeyMismatch
+        let vk = state.setup_election_with_params("e-p", params).await.unwrap();
+        assert!(Arc::ptr_eq(&vk, &state.setup_election_with_params("e-p", params).await.unwrap()));
+        let four = ElectionParams::from(CircuitConfig::with_candidates(4));
+        assert!(matches!(state.setup_election_with_params("e-p", four).await, Err(VoteError::KeyMismatch(_))));
+
+        let resp = warp::test::request().method("GET").path("/elections/e-p/params").reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(serde_json::from_value::<ElectionParams>(reply["params"].clone()).unwrap(), params);
+        assert_eq!(reply["params_hash"], hex::encode(params.digest()));
+
+        // 服务端按选举参数构造电路，弃权票可以证明并通过验证
+        let body = serde_json::json!([{
+            "vote": "abstain", "user_id": "alice", "election_id": "e-p",
+            "chain": "localhost", "gas_option": "relayer",
+        }]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(votes[0]["success"], true, "{}", votes[0]);
+        let body = serde_json::json!({
+            "proof": votes[0]["proof"], "public_inputs": votes[0]["public_inputs"], "election_id": "e-p",
+        });
+        let resp = warp::test::request().method("POST").path("/verify").json(&body).reply(&route).await;
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["valid"], true);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_client_proved_ballot_records_nullifier() {
+        use crate::nullifier::poseidon_hash2;
+        use crate::web::{vote_route, AppState};
+        use ark_std::UniformRand;
+
+        let mut rng = thread_rng();
+        let election_id = election_id_to_field::<Fr>("client-election");
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(election_id, &mut rng).unwrap();
+        let state = AppState::<Groth16<E>>::new();
+        state.add_election_keys("client-election", pk.clone(), vk).await.unwrap();
+        let route = vote_route(state.clone());
+
+        // 客户端自行选择秘密、派生防重标识并生成证明，服务端只看到防重标识、选票承诺和证明
+        let secret = Fr::rand(&mut rng);
+        let circuit = VoteCircuit {
+            vote: Fr::one(),
+            secret,
+            randomness: Fr::rand(&mut rng),
+            election_id,
+            sequence: Fr::zero(),
+            epoch: Fr::from(ballot_epoch(chrono::Utc::now())),
+        };
+        let nullifier = field_to_hex(poseidon_hash2(secret, election_id));
+        let commitment = field_to_hex(circuit.public_inputs().commitment);
+        let proof = hex::encode(<Groth16<E> as ProofSystem<Fr>>::serialize_proof(&system.vote(&pk, circuit, &mut rng).unwrap()).unwrap());
+        let body = serde_json::json!({
+            "nullifier": nullifier,
+            "commitment": commitment,
+            "proof": proof,
+            "election_id": "client-election",
+            "chain": "localhost",
+            "gas_option": "relayer",
+        });
+
+        // 防重标识与证明不符：验证失败，不记录
+        let mut forged = body.clone();
+        forged["nullifier"] = serde_json::json!("0x3039");
+        let resp = warp::test::request().method("POST").path("/vote").json(&forged).reply(&route).await;
+        assert_eq!(resp.status(), 422);
+
+        // 选票承诺与证明不符：同样验证失败
+        let mut forged = body.clone();
+        forged["commitment"] = serde_json::json!("0x3039");
+        let resp = warp::test::request().method("POST").path("/vote").json(&forged).reply(&route).await;
+        assert_eq!(resp.status(), 422);
+
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], true);
+        // 服务端不知道投票选择，不计入运行计票
+        assert_eq!(state.running_counts().await.iter().sum::<u64>(), 0);
+
+        // 原样重放已接受的证明被拒绝
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "replayed_proof");
+
+        // 防重标识已记录：同一秘密重新生成的证明同样被拒绝
+        let circuit = VoteCircuit {
+            vote: Fr::zero(),
+            secret,
+            randomness: Fr::rand(&mut rng),
+            election_id,
+            sequence: Fr::zero(),
+            epoch: Fr::from(ballot_epoch(chrono::Utc::now())),
+        };
+        let mut again = body.clone();
+        again["commitment"] = serde_json::json!(field_to_hex(circuit.public_inputs().commitment));
+        again["proof"] = serde_json::json!(hex::encode(
+            <Groth16<E> as ProofSystem<Fr>>::serialize_proof(&system.vote(&pk, circuit, &mut rng).unwrap()).unwrap()
+        ));
+        let resp = warp::test::request().method("POST").path("/vote").json(&again).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "duplicate_nullifier");
+
+        // 只有防重标识没有证明：拒绝
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/vote")
+            .json(&serde_json::json!({ "nullifier": "0x1", "chain": "localhost", "gas_option": "relayer" }))
+            .reply(&route)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_replayed_client_proof_rejected() {
+        use crate::nullifier::poseidon_hash2;
+        use crate::web::{vote_route, AppState};
+        use ark_serialize::CanonicalSerialize;
+        use ark_std::UniformRand;
+
+        let mut rng = thread_rng();
+        let election_id = election_id_to_field::<Fr>("replay-election");
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(election_id, &mut rng).unwrap();
+        let state = AppState::<Groth16<E>>::new();
+        state.add_election_keys("replay-election", pk.clone(), vk).await.unwrap();
+        let route = vote_route(state);
+
+        // 原样重放先按证明摘要识别，其余选票按防重标识和序号识别
+        let secret = Fr::rand(&mut rng);
+        let circuit = |sequence: u64, randomness: Fr| VoteCircuit {
+            vote: Fr::one(),
+            secret,
+            randomness,
+            election_id,
+            sequence: Fr::from(sequence),
+            epoch: Fr::from(ballot_epoch(chrono::Utc::now())),
+        };
+        let randomness = Fr::rand(&mut rng);
+        let proof = system.vote(&pk, circuit(1, randomness), &mut rng).unwrap();
+        let body = serde_json::json!({
+            "nullifier": field_to_hex(poseidon_hash2(secret, election_id)),
+            "commitment": field_to_hex(circuit(1, randomness).public_inputs().commitment),
+            "proof": hex::encode(<Groth16<E> as ProofSystem<Fr>>::serialize_proof(&proof).unwrap()),
+            "election_id": "replay-election",
+            "sequence": 1,
+            "chain": "localhost",
+            "gas_option": "relayer",
+        });
+
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+
+        // 原样重放：409
+        let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["success"], false);
+        assert_eq!(reply["code"], "replayed_proof");
+
+        // 换用非压缩编码重放同一证明：同样拒绝
+        let mut uncompressed = Vec::new();
+        proof.serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut reencoded = body.clone();
+        reencoded["proof"] = serde_json::json!(hex::encode(uncompressed));
+        let resp = warp::test::request().method("POST").path("/vote").json(&reencoded).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+
+        // 同一序号的新证明：防重标识已以该序号使用，拒绝
+        let mut fresh = body.clone();
+        let randomness = Fr::rand(&mut rng);
+        let proof = system.vote(&pk, circuit(1, randomness), &mut rng).unwrap();
+        fresh["proof"] = serde_json::json!(hex::encode(<Groth16<E> as ProofSystem<Fr>>::serialize_proof(&proof).unwrap()));
+        fresh["commitment"] = serde_json::json!(field_to_hex(circuit(1, randomness).public_inputs().commitment));
+        let resp = warp::test::request().method("POST").path("/vote").json(&fresh).reply(&route).await;
+        assert_eq!(resp.status(), 409);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "duplicate_nullifier");
+
+        // 更大的序号仍可重新投票
+        let randomness = Fr::rand(&mut rng);
+        let proof = system.vote(&pk, circuit(2, randomness), &mut rng).unwrap();
+        fresh["proof"] = serde_json::json!(hex::encode(<Groth16<E> as ProofSystem<Fr>>::serialize_proof(&proof).unwrap()));
+        fresh["commitment"] = serde_json::json!(field_to_hex(circuit(2, randomness).public_inputs().commitment));
+        fresh["sequence"] = serde_json::json!(2);
+        let resp = warp::test::request().method("POST").path("/vote").json(&fresh).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_setup_endpoint_generates_keys_per_config() {
+        use crate::web::{setup_route, vote_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new().with_authority_token("authority-secret");
+        let route = setup_route(state.clone()).or(vote_route(state));
+        let setup = |config: serde_json::Value, token: &'static str| {
+            warp::test::request()
+                .method("POST")
+                .path("/setup")
+                .header("authorization", format!("Bearer {}", token))
+                .json(&config)
+        };
+
+        // 未携带正确令牌：401
+        let resp = setup(serde_json::json!({ "num_candidates": 3 }), "wrong").reply(&route).await;
+        assert_eq!(resp.status(), 401);
+
+        // 两个配置得到不同的句柄和验证密钥
+        let mut replies = Vec::new();
+        for num_candidates in [3, 4] {
+            let resp = setup(serde_json::json!({ "num_candidates": num_candidates }), "authority-secret")
+                .reply(&route)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(reply["config"]["num_candidates"], num_candidates);
+            replies.push(reply);
+        }
+        assert_ne!(replies[0]["key_handle"], replies[1]["key_handle"]);
+        assert_ne!(replies[0]["verifying_key"], replies[1]["verifying_key"]);
+        assert_eq!(
+            replies[0]["key_handle"],
+            hex::encode(CircuitConfig::with_candidates(3).digest())
+        );
+
+        // 同一配置再次请求返回已保存的密钥
+        let resp = setup(serde_json::json!({ "num_candidates": 3 }), "authority-secret").reply(&route).await;
+        let again: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(again["key_handle"], replies[0]["key_handle"]);
+        assert_eq!(again["verifying_key"], replies[0]["verifying_key"]);
+
+        // 超出范围的配置：400
+        let resp = setup(serde_json::json!({ "num_candidates": 1 }), "authority-secret").reply(&route).await;
+        assert_eq!(resp.status(), 400);
+
+        // 投票引用句柄：按三个候选人的电路证明，指纹为该配置的验证密钥
+        let handle = replies[0]["key_handle"].clone();
+        let vote = |vote: u8, user: &str, handle: serde_json::Value| {
+            warp::test::request().method("POST").path("/vote").json(&serde_json::json!({
+                "vote": vote,
+                "user_id": user,
+                "key_handle": handle,
+                "chain": "localhost",
+                "gas_option": "relayer",
+            }))
+        };
+        let resp = vote(2, "alice", handle.clone()).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["vk_fingerprint"], replies[0]["vk_fingerprint"]);
+        let resp = vote(3, "bob", handle).reply(&route).await;
+        assert_eq!(resp.status(), 400);
+        let resp = vote(1, "carol", serde_json::json!("00")).reply(&route).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[cfg(all(feature = "server", feature = "curve-bn254"))]
+    #[test]
+    fn test_fuzz_seed_corpus() {
+        use crate::web::check_vote_request;
+        type G = Groth16<E>;
+
+        let corpus = |target: &str, name: &str| {
+            std::fs::read(format!("{}/fuzz/corpus/{}/{}", env!("CARGO_MANIFEST_DIR"), target, name)).unwrap()
+        };
+
+        // 合法输入
+        for name in ["server_vote.json", "server_revote.json", "client_ballot.json", "client_ballot_uncompressed.json"] {
+            check_vote_request::<G>(&corpus("vote_request", name)).unwrap();
+        }
+        assert!(<G as ProofSystem<Fr>>::deserialize_proof(&corpus("proof_codec", "generators_compressed.bin")).is_ok());
+        assert!(<G as ProofSystem<Fr>>::deserialize_proof_auto(&corpus("proof_codec", "generators_uncompressed.bin")).is_ok());
+
+        // 边界输入：返回错误而不是 panic
+        assert!(matches!(
+            check_vote_request::<G>(&corpus("vote_request", "nullifier_equals_modulus.json")),
+            Err(VoteError::FieldRangeExceeded(_))
+        ));
+        for name in ["nullifier_without_proof.json", "vote_out_of_range.json", "odd_length_proof.json", "not_an_object.json"] {
+            assert!(matches!(check_vote_request::<G>(&corpus("vote_request", name)), Err(VoteError::InvalidBallot(_))));
+        }
+        assert!(matches!(
+            check_vote_request::<G>(&corpus("vote_request", "user_id_too_long.json")),
+            Err(VoteError::InvalidUserId(_))
+        ));
+        assert!(matches!(
+            <G as ProofSystem<Fr>>::deserialize_proof(&corpus("proof_codec", "identity_compressed.bin")),
+            Err(VoteError::InvalidProofEncoding(_))
+        ));
+        for name in ["truncated.bin", "trailing_byte.bin", "x_equals_modulus.bin", "empty.bin"] {
+            assert!(<G as ProofSystem<Fr>>::deserialize_proof_auto(&corpus("proof_codec", name)).is_err());
+        }
+    }
+
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_encrypted_tally_threshold_decryption() {
+        use crate::elgamal::{
+            combine_shares, decryption_share, generate_threshold_keys, Ciphertext, EncryptedVoteCircuit,
+        };
+        use ark_ed_on_bn254::Fr as Scalar;
+        use ark_relations::r1cs::ConstraintSynthesizer;
+        use ark_std::UniformRand;
+
+        let mut rng = thread_rng();
+        let (public_key, key_shares) = generate_threshold_keys(2, 3, &mut rng).unwrap();
+
+        // 5张选票，每张附带密文格式正确的电路检查
+        let votes = [1u64, 0, 1, 1, 0];
+        let mut total = Ciphertext::zero();
+        for vote in votes {
+            let circuit = EncryptedVoteCircuit::new(public_key, vote, Scalar::rand(&mut rng));
+            total = total + circuit.ciphertext;
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        // 任意2名受托人即可解密总票数
+        let shares: Vec<_> = [&key_shares[0], &key_shares[2]]
+            .iter()
+            .map(|share| decryption_share(share, &total))
+            .collect();
+        assert_eq!(combine_shares(&total, &shares, votes.len() as u64).unwrap(), 3);
+
+        // 单个份额不足门限
+        assert!(combine_shares(&total, &shares[..1], votes.len() as u64).is_err());
+    }
+
+    #[cfg(feature = "curve-bn254")]
+    #[test]
+    fn test_encrypted_vote_rejects_invalid_ballot() {
+        use crate::elgamal::{encrypt, generate_threshold_keys, EncryptedVoteCircuit};
+        use ark_ed_on_bn254::Fr as Scalar;
+        use ark_relations::r1cs::ConstraintSynthesizer;
+
+        let mut rng = thread_rng();
+        let (public_key, _) = generate_threshold_keys(2, 3, &mut rng).unwrap();
+
+        // 加密值为2的选票不满足电路约束
+        let r = Scalar::from(42u64);
+        let circuit = EncryptedVoteCircuit {
+            public_key,
+            ciphertext: encrypt(&public_key, 2, r),
+            vote: Fr::from(2u64),
+            randomness: r,
+        };
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        // 密文与证明中的投票值不一致
+        let mut circuit = EncryptedVoteCircuit::new(public_key, 1, r);
+        circuit.ciphertext = encrypt(&public_key, 0, r);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_tally_deduplicates_nullifiers() {
+        use crate::tally::{RejectedBallot, RejectionKind};
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let mut ballot = |nullifier: u64, vote: u64| {
+            let randomness = Fr::from(1000u64 + nullifier);
+            let circuit = VoteCircuit {
+                vote: Fr::from(vote),
+                secret: Fr::from(nullifier),
+                randomness,
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            };
+            let public_inputs = circuit.public_inputs();
+            let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+            (public_inputs, proof, BallotOpening { vote: Fr::from(vote), randomness })
+        };
+
+        let first = ballot(1, 1);
+        let duplicate = ballot(1, 0);
+        let mut forged = ballot(2, 1);
+        forged.0.sequence = Fr::one();
+        let last = ballot(3, 0);
+        let expected_nullifiers = vec![field_to_decimal(first.0.nullifier), field_to_decimal(last.0.nullifier)];
+
+        let result = system.tally(&vk, vec![first, duplicate, forged, last]).unwrap();
+        assert_eq!(result.counts, vec![1, 1]);
+        assert_eq!(result.accepted, vec![0, 3]);
+        assert_eq!(
+            result.rejected,
+            vec![
+                RejectedBallot { index: 1, kind: RejectionKind::Invalid, reason: "duplicate_nullifier".to_string() },
+                RejectedBallot { index: 2, kind: RejectionKind::Invalid, reason: "proof_rejected".to_string() },
+            ]
+        );
+        assert_eq!(result.consumed_nullifiers, expected_nullifiers);
+
+        // 结果可序列化公布
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(serde_json::from_str::<TallyResult>(&json).unwrap(), result);
+    }
+
+    #[test]
+    fn test_tally_rejects_opening_not_matching_proof() {
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let randomness = Fr::from(1234u64);
+        let circuit = VoteCircuit {
+            vote: Fr::zero(),
+            secret: Fr::from(7u64),
+            randomness,
+            election_id: Fr::from(2024u64),
+            sequence: Fr::zero(),
+            epoch: Fr::zero(),
+        };
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(public_inputs.commitment, BallotOpening { vote: Fr::zero(), randomness }.commitment());
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+
+        // 投的是0，计票方换成投1的开启值：证明公开的承诺对不上，不计入
+        let swapped = BallotOpening { vote: Fr::one(), randomness };
+        let result = system.tally(&vk, vec![(public_inputs.clone(), proof.clone(), swapped)]).unwrap();
+        assert_eq!(result.counts, vec![0, 0]);
+        assert!(result.accepted.is_empty());
+        assert_eq!(result.rejected[0].reason, "commitment_mismatch");
+
+        // 篡改公开输入中的承诺使其与假开启值一致：证明不成立
+        let swapped = BallotOpening { vote: Fr::one(), randomness };
+        let forged = BallotPublicInputs { commitment: swapped.commitment(), ..public_inputs };
+        let result = system.tally(&vk, vec![(forged, proof, swapped)]).unwrap();
+        assert_eq!(result.rejected[0].reason, "proof_rejected");
+    }
+
+    // 时段为 u64::MAX 的选票验证时出错（模拟证明文件损坏等运行错误）的 Groth16
+    struct FaultyGroth16;
+
+    impl ProofSystem<Fr> for FaultyGroth16 {
+        type ProvingKey = ark_groth16::ProvingKey<E>;
+        type VerifyingKey = ark_groth16::VerifyingKey<E>;
+        type Proof = ark_groth16::Proof<E>;
+
+        const NAME: &'static str = "faulty-groth16";
+
+        fn setup<C, R>(circuit: C, rng: &mut R) -> Result<(Self::ProvingKey, Self::VerifyingKey), VoteError>
+        where
+            C: ConstraintSynthesizer<Fr> + Clone,
+            R: RngCore + CryptoRng,
+        {
+            <Groth16<E> as ProofSystem<Fr>>::setup(circuit, rng)
+        }
+
+        fn prove<C, R>(pk: &Self::ProvingKey, circuit: C, rng: &mut R) -> Result<Self::Proof, VoteError>
+        where
+            C: ConstraintSynthesizer<Fr>,
+            R: RngCore + CryptoRng,
+        {
+            <Groth16<E> as ProofSystem<Fr>>::prove(pk, circuit, rng)
+        }
+
+        fn verify(vk: &Self::VerifyingKey, public_inputs: &[Fr], proof: &Self::Proof) -> Result<bool, VoteError> {
+            // 公开输入的第4项为时段（其后为选票承诺）
+            if public_inputs.get(3) == Some(&Fr::from(u64::MAX)) {
+                return Err(VoteError::Serialization(ark_serialize::SerializationError::InvalidData));
+            }
+            <Groth16<E> as ProofSystem<Fr>>::verify(vk, public_inputs, proof)
+        }
+
+        fn num_public_inputs(vk: &Self::VerifyingKey) -> Option<usize> {
+            <Groth16<E> as ProofSystem<Fr>>::num_public_inputs(vk)
+        }
+    }
+
+    #[test]
+    fn test_tally_distinguishes_invalid_from_errored_ballots() {
+        use crate::tally::{RejectedBallot, RejectionKind};
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, FaultyGroth16>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let mut ballot = |secret: u64, epoch: u64| {
+            let randomness = Fr::from(500u64 + secret);
+            let circuit = VoteCircuit {
+                vote: Fr::one(),
+                secret: Fr::from(secret),
+                randomness,
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::from(epoch),
+            };
+            let public_inputs = circuit.public_inputs();
+            let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+            (public_inputs, proof, BallotOpening { vote: Fr::one(), randomness })
+        };
+
+        let valid = ballot(1, 0);
+        let mut cheating = ballot(2, 0);
+        cheating.0.election_id = Fr::from(2025u64);
+        let corrupted = ballot(3, u64::MAX);
+
+        // 单张验证：有效、无效（附原因）、出错三种结果互不混淆
+        assert_eq!(system.verify_ballot(&vk, &valid.0, &valid.1).unwrap(), VerificationOutcome::Valid);
+        assert_eq!(
+            system.verify_ballot(&vk, &cheating.0, &cheating.1).unwrap().invalid_reason(),
+            Some(InvalidReason::ProofRejected)
+        );
+        assert!(matches!(
+            system.verify_ballot(&vk, &corrupted.0, &corrupted.1),
+            Err(VoteError::Serialization(_))
+        ));
+
+        let result = system.tally(&vk, vec![valid, cheating, corrupted]).unwrap();
+        assert_eq!(result.counts, vec![0, 1]);
+        assert_eq!(result.accepted, vec![0]);
+        assert_eq!(
+            result.rejected,
+            vec![
+                RejectedBallot { index: 1, kind: RejectionKind::Invalid, reason: "proof_rejected".to_string() },
+                RejectedBallot { index: 2, kind: RejectionKind::Error, reason: "serialization_failed".to_string() },
+            ]
+        );
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["rejected"][0]["kind"], "invalid");
+        assert_eq!(json["rejected"][1]["kind"], "error");
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_export_bundle_independently_verified() {
+        use crate::tally::setup_tally;
+        use crate::web::{export_route, vote_batch_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::<Groth16<E>>::new().with_authority_token("authority-secret");
+        let route = vote_batch_route(state.clone()).or(export_route(state.clone()));
+        let body = serde_json::json!([
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 1, "user_id": "carol", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+
+        // 尚未登记公布的计票密钥时拒绝导出，导出方不自行生成计票密钥
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/export")
+            .header("authorization", "Bearer authority-secret")
+            .reply(&route)
+            .await;
+        assert_eq!(resp.status(), 409);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "key_mismatch");
+
+        // 登记独立可信设置为3张选票公布的计票密钥；为其他数量生成的密钥被拒绝
+        let (tally_pk, tally_vk) = setup_tally::<Fr, Groth16<E>, _>(3, &mut thread_rng()).unwrap();
+        let (other_pk, other_vk) = setup_tally::<Fr, Groth16<E>, _>(2, &mut thread_rng()).unwrap();
+        assert!(matches!(state.add_tally_keys(3, other_pk, other_vk).await, Err(VoteError::KeyMismatch(_))));
+        state.add_tally_keys(3, tally_pk, tally_vk.clone()).await.unwrap();
+
+        // 未携带或携带错误令牌时拒绝导出
+        let resp = warp::test::request().method("POST").path("/export").reply(&route).await;
+        assert_eq!(resp.status(), 401);
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/export")
+            .header("authorization", "Bearer wrong")
+            .reply(&route)
+            .await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/export")
+            .header("authorization", "Bearer authority-secret")
+            .reply(&route)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let bundle: ElectionBundle = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(bundle.ballots.len(), 3);
+
+        // 第三方凭导出包和公布的计票验证密钥复核
+        let result = verify_bundle::<Fr, Groth16<E>>(&bundle, &tally_vk).unwrap();
+        assert_eq!(result.counts, vec![1, 2]);
+        assert_eq!(result.accepted, vec![0, 1, 2]);
+        assert_eq!(result, bundle.tally);
+
+        // 篡改票数后摘要不再匹配
+        let mut tampered = bundle.clone();
+        tampered.tally.counts = vec![0, 3];
+        assert!(matches!(verify_bundle::<Fr, Groth16<E>>(&tampered, &tally_vk), Err(VoteError::BundleIntegrity(_))));
+
+        // 重新计算摘要也无法通过计票证明
+        tampered.digest = tampered.compute_digest();
+        assert!(matches!(verify_bundle::<Fr, Groth16<E>>(&tampered, &tally_vk), Err(VoteError::Verification(_))));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_export_bundle_with_revote_verifies() {
+        use crate::tally::setup_tally;
+        use crate::web::{export_route, vote_route, AppState};
+        use warp::Filter;
+
+        let state = AppState::new_mock().with_authority_token("authority-secret");
+        let route = vote_route(state.clone()).or(export_route(state.clone()));
+        let (tally_pk, tally_vk) = setup_tally::<Fr, MockSNARK, _>(2, &mut thread_rng()).unwrap();
+        state.add_tally_keys(2, tally_pk, tally_vk.clone()).await.unwrap();
+
+        // alice 先投1再以序号1改投0，bob 投1
+        for (user_id, vote, sequence) in [("alice", 1, 0), ("bob", 1, 0), ("alice", 0, 1)] {
+            let body = serde_json::json!({
+                "vote": vote, "user_id": user_id, "sequence": sequence, "chain": "localhost", "gas_option": "relayer",
+            });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        }
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/export")
+            .header("authorization", "Bearer authority-secret")
+            .reply(&route)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let bundle: ElectionBundle = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(bundle.ballots.len(), 3);
+
+        // 复核按与计票相同的规则只计入 alice 的最新选票，导出包通过自身的复核
+        let result = verify_bundle::<Fr, MockSNARK>(&bundle, &tally_vk).unwrap();
+        assert_eq!(result, bundle.tally);
+        assert_eq!(result.counts, vec![1, 1]);
+        assert_eq!(result.accepted, vec![1, 2]);
+        let rejected: Vec<_> = result.rejected.iter().map(|r| (r.index, r.reason.as_str())).collect();
+        assert_eq!(rejected, vec![(0, "superseded_ballot")]);
+    }
+
+    #[test]
+    fn test_bundle_rejects_swapped_openings() {
+        use crate::tally::{prove_tally, setup_tally};
+
+        let mut rng = thread_rng();
+        let (system, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut rng).unwrap();
+        let mut ballot = |secret: u64, vote: u64| {
+            let randomness = Fr::from(300u64 + secret);
+            let circuit = VoteCircuit {
+                vote: Fr::from(vote),
+                secret: Fr::from(secret),
+                randomness,
+                election_id: Fr::from(2024u64),
+                sequence: Fr::zero(),
+                epoch: Fr::zero(),
+            };
+            let public_inputs = circuit.public_inputs();
+            let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+            (public_inputs, proof, BallotOpening { vote: Fr::from(vote), randomness })
+        };
+        let ballots = vec![ballot(1, 1), ballot(2, 0)];
+        let (tally_pk, tally_vk) = setup_tally::<Fr, Groth16<E>, _>(2, &mut rng).unwrap();
+        let bundle = export_bundle(&system, &vk, &tally_pk, &tally_vk, ballots, &mut rng).unwrap();
+        assert_eq!(verify_bundle::<Fr, Groth16<E>>(&bundle, &tally_vk).unwrap().counts, vec![1, 1]);
+
+        // 导出方用公布的计票证明密钥，换用另一组开启值（两票都投0）重新生成计票证明
+        let swapped = vec![
+            BallotOpening { vote: Fr::zero(), randomness: Fr::from(1u64) },
+            BallotOpening { vote: Fr::zero(), randomness: Fr::from(2u64) },
+        ];
+        let tally_proof = prove_tally::<Fr, Groth16<E>, _>(&tally_pk, &swapped, &[2, 0], &mut rng).unwrap();
+        let mut forged = bundle.clone();
+        forged.tally.counts = vec![2, 0];
+        forged.tally_proof = hex::encode(<Groth16<E> as ProofSystem<Fr>>::serialize_proof(&tally_proof.proof).unwrap());
+
+        // 承诺取自选票证明：沿用原承诺时计票证明不成立
+        forged.digest = forged.compute_digest();
+        assert!(matches!(verify_bundle::<Fr, Groth16<E>>(&forged, &tally_vk), Err(VoteError::Verification(_))));
+
+        // 改写公布的承诺与假开启值一致：与选票证明中的承诺对不上
+        forged.tally.commitments = swapped.iter().map(|opening| field_to_decimal(opening.commitment())).collect();
+        forged.digest = forged.compute_digest();
+        assert!(matches!(verify_bundle::<Fr, Groth16<E>>(&forged, &tally_vk), Err(VoteError::BundleIntegrity(_))));
+    }
+
+    #[test]
+    fn test_bundle_rejects_regenerated_tally_key() {
+        use crate::tally::setup_tally;
+
+        let mut rng = thread_rng();
+        let election_id = Fr::from(2024u64);
+        let (system, pk, vk) = VoteSystem::<Fr, MockSNARK>::setup(election_id, &mut rng).unwrap();
+        let ballots: Vec<_> = (0..2u64)
+            .map(|i| {
+                let randomness = Fr::from(200 + i);
+                let circuit = VoteCircuit {
+                    vote: Fr::one(),
+                    secret: Fr::from(20 + i),
+                    randomness,
+                    election_id,
+                    sequence: Fr::zero(),
+                    epoch: Fr::zero(),
+                };
+                let public_inputs = circuit.public_inputs();
+                let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+                (public_inputs, proof, BallotOpening { vote: Fr::one(), randomness })
+            })
+            .collect();
+        let (tally_pk, tally_vk) = setup_tally::<Fr, MockSNARK, _>(2, &mut rng).unwrap();
+        let bundle = export_bundle(&system, &vk, &tally_pk, &tally_vk, ballots.clone(), &mut rng).unwrap();
+        assert_eq!(verify_bundle::<Fr, MockSNARK>(&bundle, &tally_vk).unwrap().counts, vec![0, 2]);
+
+        // 导出方自行重新生成计票密钥：导出包自洽（用包内密钥可以复核），但不是公布的密钥
+        let (forged_pk, forged_vk) = setup_tally::<Fr, MockSNARK, _>(2, &mut rng).unwrap();
+        let forged = export_bundle(&system, &vk, &forged_pk, &forged_vk, ballots, &mut rng).unwrap();
+        assert!(verify_bundle::<Fr, MockSNARK>(&forged, &forged_vk).is_ok());
+        assert!(matches!(verify_bundle::<Fr, MockSNARK>(&forged, &tally_vk), Err(VoteError::KeyMismatch(_))));
+
+        // 篡改导出包换入重新生成的验证密钥并重算摘要，同样被拒绝
+        let mut tampered = bundle.clone();
+        tampered.tally_verifying_key = forged.tally_verifying_key.clone();
+        tampered.tally_proof = forged.tally_proof.clone();
+        tampered.digest = tampered.compute_digest();
+        assert!(matches!(verify_bundle::<Fr, MockSNARK>(&tampered, &tally_vk), Err(VoteError::KeyMismatch(_))));
+
+        // 公布的密钥不是为该数量的选票生成时拒绝
+        let (_, wrong_vk) = setup_tally::<Fr, MockSNARK, _>(3, &mut rng).unwrap();
+        assert!(matches!(verify_bundle::<Fr, MockSNARK>(&bundle, &wrong_vk), Err(VoteError::KeyMismatch(_))));
+    }
+
+    #[test]
+    fn test_bundle_version_gates_verification() {
+        use crate::tally::setup_tally;
+
+        let mut rng = thread_rng();
+        let election_id = Fr::from(2024u64);
+        let (system, pk, vk) = VoteSystem::<Fr, MockSNARK>::setup(election_id, &mut rng).unwrap();
+        let ballots = (0..2u64)
+            .map(|i| {
+                let randomness = Fr::from(100 + i);
+                let circuit = VoteCircuit {
+                    vote: Fr::from(i),
+                    secret: Fr::from(10 + i),
+                    randomness,
+                    election_id,
+                    sequence: Fr::zero(),
+                    epoch: Fr::zero(),
+                };
+                let public_inputs = circuit.public_inputs();
+                let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+                (public_inputs, proof, BallotOpening { vote: Fr::from(i), randomness })
+            })
+            .collect();
+        let (tally_pk, tally_vk) = setup_tally::<Fr, MockSNARK, _>(2, &mut rng).unwrap();
+        let bundle = export_bundle(&system, &vk, &tally_pk, &tally_vk, ballots, &mut rng).unwrap();
+        let reparse = |bundle: &ElectionBundle| -> ElectionBundle {
+            serde_json::from_str(&serde_json::to_string(bundle).unwrap()).unwrap()
+        };
+
+        // 当前版本（v1）的导出包带版本号，解析后复核通过
+        let json = serde_json::to_value(&bundle).unwrap();
+        assert_eq!(json["version"], BUNDLE_VERSION);
+        let parsed: ElectionBundle = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(verify_bundle::<Fr, MockSNARK>(&parsed, &tally_vk).unwrap().counts, vec![1, 1]);
+
+        // 同一主版本中新增的字段：不认识该字段的复核方原样保留并照常复核，摘要覆盖该字段
+        let mut extended = json.clone();
+        extended["published_at"] = serde_json::json!("2026-10-17T00:00:00Z");
+        let mut newer: ElectionBundle = serde_json::from_value(extended).unwrap();
+        newer.digest = newer.compute_digest();
+        let newer = reparse(&newer);
+        assert_eq!(newer.extensions["published_at"], "2026-10-17T00:00:00Z");
+        assert!(verify_bundle::<Fr, MockSNARK>(&newer, &tally_vk).is_ok());
+        let mut tampered = newer.clone();
+        tampered.extensions.insert("published_at".to_string(), serde_json::json!("2026-10-18T00:00:00Z"));
+        assert!(matches!(verify_bundle::<Fr, MockSNARK>(&tampered, &tally_vk), Err(VoteError::BundleIntegrity(_))));
+
+        // 未知的主版本一律拒绝，即使摘要匹配
+        let mut future = bundle.clone();
+        future.version = 999;
+        future.digest = future.compute_digest();
+        let future = reparse(&future);
+        assert!(matches!(verify_bundle::<Fr, MockSNARK>(&future, &tally_vk), Err(VoteError::UnsupportedBundleVersion(999))));
+        assert_eq!(VoteError::UnsupportedBundleVersion(999).code(), "unsupported_bundle_version");
+
+        // 引入版本号之前导出的包没有 version 字段，按原来的摘要规则复核
+        let mut legacy = bundle.clone();
+        legacy.version = 0;
+        legacy.digest = legacy.compute_digest();
+        assert!(serde_json::to_value(&legacy).unwrap().get("version").is_none());
+        assert!(verify_bundle::<Fr, MockSNARK>(&reparse(&legacy), &tally_vk).is_ok());
+    }
+
+    #[test]
+    fn test_running_tally_opens_to_counts() {
+        use crate::pedersen::{PedersenParams, RunningTally, VoteCommitment};
+        use crate::tally::NUM_OPTIONS;
+        use ark_ec::{pairing::Pairing, CurveGroup};
+        use ark_std::rand::Rng;
+        
+        type G = <E as Pairing>::G1;
+        let mut rng = thread_rng();
+        let mut tally = RunningTally::<G>::default();
+        let params = PedersenParams::<G>::new();
+        assert_eq!(tally.params(), &params);
+        
+        // 逐票累加，同时记下公布的逐票承诺
+        let mut expected = vec![0u64; NUM_OPTIONS];
+        let mut published = vec![G::zero(); NUM_OPTIONS];
+        for _ in 0..200 {
+            let candidate = rng.gen_range(0..NUM_OPTIONS);
+            let commitment = VoteCommitment::new(&params, &mut rng);
+            tally.add(&commitment, candidate).unwrap();
+            expected[candidate] += 1;
+            published[candidate] += commitment.point;
+        }
+        assert_eq!(tally.counts(), expected.as_slice());
+        
+        let result = tally.finalize(&mut rng);
+        assert_eq!(result.counts(), expected);
+        result.verify(&params).unwrap();
+        for (total, sum) in result.totals.iter().zip(&published) {
+            assert_eq!(total.commitment, sum.into_affine());
+        }
+        
+        // 篡改票数后打开证明不再成立
+        let mut tampered = result.clone();
+        tampered.totals[0].count += 1;
+        assert!(matches!(tampered.verify(&params), Err(VoteError::Verification(_))));
+        
+        // 打开值不是1的承诺和越界的候选人被拒绝
+        let mut forged = VoteCommitment::new(&params, &mut rng);
+        forged.point = params.commit(Fr::from(2u64), forged.randomness);
+        assert!(matches!(tally.add(&forged, 0), Err(VoteError::CommitmentMismatch)));
+        let commitment = VoteCommitment::new(&params, &mut rng);
+        assert!(matches!(tally.add(&commitment, NUM_OPTIONS), Err(VoteError::InvalidBallot(_))));
+        assert_eq!(tally.counts(), expected.as_slice());
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_app_state_running_tally() {
+        use crate::web::{vote_batch_route, AppState};
+        
+        let state = AppState::new_mock();
+        let route = vote_batch_route(state.clone());
+        let body = serde_json::json!([
+            { "vote": 1, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 1, "user_id": "carol", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        assert_eq!(resp.status(), 200);
+        
+        assert_eq!(state.running_counts().await, vec![1, 2]);
+        let result = state.finalize_running_tally().await;
+        assert_eq!(result.counts(), vec![1, 2]);
+        result.verify(&state.running_tally_params().await).unwrap();
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_running_tally_replaces_revote() {
+        use crate::web::{vote_route, AppState};
+        
+        let state = AppState::new_mock();
+        let route = vote_route(state.clone());
+        
+        // alice 先投1再以序号1改投0，bob 投1：运行计票只计入 alice 的最新选票
+        for (user_id, vote, sequence) in [("alice", 1, 0), ("bob", 1, 0), ("alice", 0, 1)] {
+            let body = serde_json::json!({
+                "vote": vote, "user_id": user_id, "sequence": sequence, "chain": "localhost", "gas_option": "relayer",
+            });
+            let resp = warp::test::request().method("POST").path("/vote").json(&body).reply(&route).await;
+            assert_eq!(resp.status(), 200, "{}", String::from_utf8_lossy(resp.body()));
+        }
+        
+        assert_eq!(state.running_counts().await, vec![1, 1]);
+        let result = state.finalize_running_tally().await;
+        assert_eq!(result.counts(), vec![1, 1]);
+        result.verify(&state.running_tally_params().await).unwrap();
+    }
+    
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_results_for_three_candidates_after_close() {
+        use crate::web::{results_route, vote_batch_route, AppState};
+        use chrono::{Duration, Utc};
+        
+        let state = AppState::<MockSNARK>::try_with_candidates(3).unwrap();
+        assert_eq!(state.num_candidates(), 3);
+        let route = vote_batch_route(state.clone());
+        let body = serde_json::json!([
+            { "vote": 2, "user_id": "alice", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 0, "user_id": "bob", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 1, "user_id": "carol", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 2, "user_id": "dave", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 3, "user_id": "erin", "chain": "localhost", "gas_option": "relayer" },
+            { "vote": 2, "user_id": "frank", "chain": "localhost", "gas_option": "relayer" },
+        ]);
+        let resp = warp::test::request().method("POST").path("/vote/batch").json(&body).reply(&route).await;
+        let votes: Vec<serde_json::Value> = serde_json::from_slice(resp.body()).unwrap();
+        let accepted: Vec<bool> = votes.iter().map(|v| v["success"] == true).collect();
+        assert_eq!(accepted, vec![true, true, true, true, false, true]);
+        // 超出候选人范围的选择被拒绝
+        assert_eq!(votes[4]["error_code"], "invalid_ballot");
+        
+        // 投票截止前不公布结果
+        let resp = warp::test::request().method("GET").path("/results").reply(&results_route(state.clone())).await;
+        assert_eq!(resp.status(), 403);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["code"], "wrong_phase");
+        
+        // 截止后公布各候选人票数
+        let closed = state.with_election_end(Utc::now() - Duration::seconds(1));
+        let resp = warp::test::request().method("GET").path("/results").reply(&results_route(closed)).await;
+        assert_eq!(resp.status(), 200);
+        let reply: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(reply["num_candidates"], 3);
+        assert_eq!(reply["counts"], serde_json::json!([1, 1, 3]));
+        assert_eq!(reply["total_votes"], 5);
+    }
+    
+    // 客户端证明并验证一张选票（密钥由服务端生成后以字节下发）
+    fn run_client_ballot_flow() {
+        use crate::client::{ballot_public_inputs, decode_proving_key, decode_verifying_key, prove_ballot, verify_ballot};
+        use crate::fingerprint::encode_key;
+
+        let (_, pk, vk) = VoteSystem::<Fr, Groth16<E>>::setup(Fr::from(2024u64), &mut ark_std::rand::rngs::OsRng).unwrap();
+        let pk = decode_proving_key(&encode_key::<Fr, _>(&pk).unwrap()).unwrap();
+        let vk = decode_verifying_key(&<Groth16<E> as ProofSystem<Fr>>::serialize_verifying_key(&vk).unwrap()).unwrap();
+
+        let ballot = serde_json::json!({
+            "vote": "1", "secret": "12345", "randomness": "67890",
+            "election_id": "2024", "sequence": "0", "epoch": "42",
+        })
+        .to_string();
+        let proof = prove_ballot(&pk, &ballot).unwrap();
+        let inputs = ballot_public_inputs(&ballot).unwrap();
+        assert!(verify_ballot(&vk, &inputs, &proof).unwrap());
+
+        // 篡改公开输入后验证失败
+        let mut tampered: Vec<String> = serde_json::from_str(&inputs).unwrap();
+        tampered[3] = "43".to_string();
+        assert!(!verify_ballot(&vk, &serde_json::to_string(&tampered).unwrap(), &proof).unwrap());
+
+        // 非法投票值在客户端即被拒绝
+        let invalid = ballot.replace("\"vote\":\"1\"", "\"vote\":\"2\"");
+        assert!(matches!(prove_ballot(&pk, &invalid), Err(VoteError::UnsatisfiedConstraint(_))));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_client_ballot_flow() {
+        run_client_ballot_flow();
+    }
+
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn test_client_ballot_flow_in_browser() {
+        run_client_ballot_flow();
+    }
+
+    // 随机字段元素，边界值 0、1、p-1 及其邻近值以较高概率出现
+    #[cfg(not(target_arch = "wasm32"))]
+    fn arb_field() -> impl proptest::strategy::Strategy<Value = Fr> {
+        use proptest::prelude::*;
+
+        prop_oneof![
+            1 => Just(Fr::zero()),
+            1 => Just(Fr::one()),
+            1 => Just(-Fr::one()),
+            1 => Just(Fr::from(2u64)),
+            1 => Just(-Fr::from(2u64)),
+            2 => any::<u64>().prop_map(Fr::from),
+            4 => any::<[u8; 32]>().prop_map(|bytes| Fr::from_le_bytes_mod_order(&bytes)),
+        ]
+    }
+
+    // 非零字段元素，用于篡改公开输入
+    #[cfg(not(target_arch = "wasm32"))]
+    fn arb_nonzero_field() -> impl proptest::strategy::Strategy<Value = Fr> {
+        use proptest::strategy::Strategy;
+
+        arb_field().prop_filter("delta must be non-zero", |x| !x.is_zero())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    type ProptestFixture = (
+        VoteSystem<Fr, Groth16<E>>,
+        <Groth16<E> as ProofSystem<Fr>>::VerifyingKey,
+        PublicInputs<Fr>,
+        <Groth16<E> as ProofSystem<Fr>>::Proof,
+    );
+
+    // 各属性测试共用的密钥和证明（Groth16 密钥生成较慢，只生成一次）
+    #[cfg(not(target_arch = "wasm32"))]
+    fn proptest_fixture() -> &'static ProptestFixture {
+        static FIXTURE: std::sync::OnceLock<ProptestFixture> = std::sync::OnceLock::new();
+        FIXTURE.get_or_init(|| {
+            let (system, pk, vk) =
+                VoteSystem::<Fr, Groth16<E>>::setup_deterministic(Fr::from(2024u64), [0u8; 32]).unwrap();
+            let circuit = VoteCircuit {
+                vote: Fr::one(),
+                secret: Fr::from(12345u64),
+                randomness: Fr::from(67890u64),
+                election_id: Fr::from(2024u64),
+                sequence: Fr::from(7u64),
+                epoch: Fr::from(42u64),
+            };
+            let public_inputs = circuit.public_inputs();
+            let proof = system.vote(&pk, circuit, &mut thread_rng()).unwrap();
+            (system, vk, public_inputs, proof)
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            // 投票值为0或1时电路满足约束，其他任意值都不满足
+            #[test]
+            fn prop_vote_satisfies_iff_binary(
+                vote in arb_field(),
+                secret in arb_field(),
+                randomness in arb_field(),
+                election_id in arb_field(),
+                sequence in any::<u64>(),
+                epoch in any::<u64>(),
+            ) {
+                let circuit = VoteCircuit {
+                    vote,
+                    secret,
+                    randomness,
+                    election_id,
+                    sequence: Fr::from(sequence),
+                    epoch: Fr::from(epoch),
+                };
+                let binary = vote.is_zero() || vote.is_one();
+                prop_assert_eq!(VoteSystem::<Fr, Groth16<E>>::check_circuit(circuit).unwrap(), binary);
+            }
+
+            // 公开的防重标识总是等于 Poseidon(秘密, 选举标识)，替换为其他值后约束不满足
+            #[test]
+            fn prop_nullifier_binds_secret(
+                secret in arb_field(),
+                election_id in arb_field(),
+                delta in arb_nonzero_field(),
+            ) {
+                let circuit = VoteCircuit {
+                    vote: Fr::one(),
+                    secret,
+                    randomness: Fr::zero(),
+                    election_id,
+                    sequence: Fr::zero(),
+                    epoch: Fr::zero(),
+                };
+                let public_inputs = circuit.public_inputs();
+                prop_assert_eq!(public_inputs.nullifier, crate::nullifier::poseidon_hash2(secret, election_id));
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                circuit.generate_constraints(cs.clone()).unwrap();
+                prop_assert!(cs.is_satisfied().unwrap());
+
+                // 实例赋值第0项为常量1，防重标识位于第1项
+                cs.borrow_mut().unwrap().instance_assignment[1] += delta;
+                prop_assert!(!cs.is_satisfied().unwrap());
+            }
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(32))]
+
+            // 16个线程并发插入（含重复值），每个不同的防重标识恰好插入成功一次
+            #[test]
+            fn prop_nullifier_set_concurrent_insert(
+                values in proptest::collection::vec(0u64..64, 1..256),
+            ) {
+                use std::collections::HashSet;
+                use std::sync::atomic::{AtomicUsize, Ordering};
+
+                let set = NullifierSet::new();
+                let inserted = AtomicUsize::new(0);
+                std::thread::scope(|scope| {
+                    for thread in 0..16 {
+                        let (set, inserted, values) = (&set, &inserted, &values);
+                        scope.spawn(move || {
+                            for value in values.iter().skip(thread).step_by(16) {
+                                if set.insert(Fr::from(*value)).is_ok() {
+                                    inserted.fetch_add(1, Ordering::SeqCst);
+                                }
+                            }
+                            // 所有线程都尝试插入全部值，制造同一值的竞争
+                            for value in values {
+                                if set.insert(Fr::from(*value)).is_ok() {
+                                    inserted.fetch_add(1, Ordering::SeqCst);
+                                }
+                            }
+                        });
+                    }
+                });
+
+                let distinct: HashSet<u64> = values.iter().copied().collect();
+                prop_assert_eq!(inserted.load(Ordering::SeqCst), distinct.len());
+                prop_assert_eq!(set.len(), distinct.len());
+                for value in &distinct {
+                    prop_assert!(set.contains(&Fr::from(*value)));
+                }
+            }
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(16))]
+
+            // 篡改任意一个公开输入后证明验证失败
+            #[test]
+            fn prop_verify_rejects_mutated_public_inputs(
+                index in 0..NUM_PUBLIC_INPUTS,
+                delta in arb_nonzero_field(),
+            ) {
+                let (system, vk, public_inputs, proof) = proptest_fixture();
+                let mut inputs = public_inputs.to_vec();
+                prop_assert!(system.verify(vk, &inputs, proof).unwrap());
+
+                inputs[index] += delta;
+                prop_assert!(!system.verify(vk, &inputs, proof).unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,423 @@
+// 跨版本、跨实现的测试向量
+//
+// 每个向量由固定种子确定性生成：验证密钥及其摘要、选票见证、预期防重标识、预期公开输入，
+// 以及一个能通过验证的证明。向量以 JSON 文件随仓库提交（testdata/vectors/），
+// 后续版本用 verify_vector 检查当前代码仍接受旧版本生成的向量：由见证重新计算的防重标识和
+// 公开输入不变，旧证明仍能用旧验证密钥通过验证。电路版本相同的向量还可用 regenerate_vk_digest
+// 从种子重新生成验证密钥，核对电路结构没有意外改动。
+// 其他实现（如浏览器端、snarkjs 电路）可以读取同一批文件，核对各自计算的防重标识和公开输入。
+//
+// 目前覆盖二选一选票和多候选人选票，以及投票人名册的 Merkle 树（MerkleVector：名册叶子、树根和成员路径，
+// 固定 merkle 模块的哈希、空位取值和叶子顺序）。选票电路尚不包含名册成员证明（ElectionParams::merkle_depth 只接受 0），
+// 成员证明加入电路后，带资格证明的选票向量随新的电路版本补充，其树根和路径须与 MerkleVector 一致。
+// 警告：向量的密钥由公开种子生成，任何人都能伪造证明，只能用于测试。
+
+use crate::curve::CurveId;
+use crate::merkle::{compute_root, merkle_path, path_root, IncrementalMerkleTree, VOTER_TREE_DEPTH};
+use crate::nullifier::{expected_nullifier, user_secret_to_field};
+use crate::store::StoreError;
+use crate::{
+    check_public_input_count, election_id_to_field, field_to_decimal, parse_field_element, ProofSystem, VoteCircuit,
+    VoteError, VoteSystem, LEGACY_NUM_PUBLIC_INPUTS, MAX_CANDIDATES,
+};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use std::path::Path;
+use tracing::error;
+
+// 向量文件的格式版本
+pub const TEST_VECTOR_FORMAT: u32 = 1;
+
+// 选票类型（决定使用哪个电路）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BallotKind {
+    Boolean,   // 二选一选票（VoteCircuit）
+    Candidate, // 多候选人选票（CandidateVoteCircuit）
+}
+
+// 选票见证（私有输入及其来源）
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BallotWitness {
+    pub vote: u64,
+    pub user_secret: String, // 投票人秘密，见 nullifier::user_secret_to_field
+    pub randomness: String,  // 十进制字符串表示的承诺随机值
+    pub election_id: String, // 选举标识字符串，见 election_id_to_field
+    pub sequence: u64,
+    pub epoch: u64,
+}
+
+impl BallotWitness {
+    // 构造投票电路
+    pub fn circuit<F: PrimeField>(&self) -> Result<VoteCircuit<F>, VoteError> {
+        Ok(VoteCircuit {
+            vote: F::from(self.vote),
+            secret: user_secret_to_field(self.user_secret.as_bytes()),
+            randomness: parse_field_element(&self.randomness)?,
+            election_id: election_id_to_field(&self.election_id),
+            sequence: F::from(self.sequence),
+            epoch: F::from(self.epoch),
+        })
+    }
+}
+
+// 测试向量
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TestVector {
+    pub format: u32,                // 向量文件格式版本
+    pub name: String,               // 向量名称（即文件名）
+    pub generator_version: String,  // 生成向量的 crate 版本
+    pub circuit_version: u32,       // 生成向量时的电路版本
+    pub backend: String,            // 证明系统后端名称
+    pub curve_id: u32,              // 曲线标识，见 CurveId
+    pub seed: String,               // 十六进制编码的 32 字节种子（密钥和证明随机数均由其派生）
+    pub kind: BallotKind,
+    pub num_candidates: usize,
+    pub vk_digest: String,          // 验证密钥压缩序列化的十六进制 blake3 摘要
+    pub verifying_key: String,      // 十六进制编码的验证密钥
+    pub witness: BallotWitness,
+    pub expected_nullifier: String, // 十进制字符串表示的预期防重标识
+    pub public_inputs: Vec<String>, // 十进制字符串表示的预期公开输入（按电路顺序）
+    pub proof: String,              // 十六进制编码的证明
+}
+
+// 名册 Merkle 树向量：按登记顺序的叶子、树根及部分叶子的成员路径
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MerkleVector {
+    pub format: u32,               // 向量文件格式版本
+    pub name: String,              // 向量名称（即文件名）
+    pub generator_version: String, // 生成向量的 crate 版本
+    pub curve_id: u32,             // 曲线标识，见 CurveId
+    pub depth: usize,              // 树深度
+    pub leaves: Vec<String>,       // 十进制字符串表示的叶子（投票人凭证承诺），按登记顺序
+    pub root: String,              // 十进制字符串表示的树根
+    pub paths: Vec<MerklePathVector>,
+}
+
+// 一个叶子的成员路径
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MerklePathVector {
+    pub index: u64,            // 叶子位置
+    pub siblings: Vec<String>, // 从叶子层向上各层的兄弟节点（十进制字符串）
+}
+
+// 验证密钥摘要
+pub fn vk_digest<F: PrimeField, P: ProofSystem<F>>(vk: &P::VerifyingKey) -> Result<String, VoteError> {
+    let mut bytes = Vec::with_capacity(vk.compressed_size());
+    vk.serialize_compressed(&mut bytes)?;
+    Ok(blake3::hash(&bytes).to_hex().to_string())
+}
+
+// 由种子生成向量的密钥（绑定见证中的选举）和证明
+// 密钥生成后继续使用同一个随机数发生器生成证明，整个向量由种子唯一确定
+#[cfg(any(test, feature = "dev-setup"))]
+fn setup_and_prove<F: PrimeField, P: ProofSystem<F>>(
+    seed: [u8; 32],
+    kind: BallotKind,
+    num_candidates: usize,
+    witness: &BallotWitness,
+    prove: bool,
+) -> Result<(P::VerifyingKey, Option<P::Proof>), VoteError> {
+    use crate::CandidateVoteCircuit;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    let election_id = election_id_to_field::<F>(&witness.election_id);
+    let (system, pk, vk) = match kind {
+        BallotKind::Boolean => VoteSystem::<F, P>::setup(election_id, &mut rng)?,
+        BallotKind::Candidate => VoteSystem::<F, P>::setup_with_candidates(num_candidates, election_id, &mut rng)?,
+    };
+    let proof = match prove {
+        false => None,
+        true => {
+            let circuit = witness.circuit::<F>()?;
+            Some(match kind {
+                BallotKind::Boolean => system.vote(&pk, circuit, &mut rng)?,
+                BallotKind::Candidate => {
+                    let circuit = CandidateVoteCircuit { ballot: circuit, num_candidates, params_digest: None, prf: None };
+                    system.vote_with_report(&pk, circuit, &mut rng)?.0
+                }
+            })
+        }
+    };
+    Ok((vk, proof))
+}
+
+// 由固定种子生成测试向量（仅用于测试和开发）
+#[cfg(any(test, feature = "dev-setup"))]
+pub fn generate<F: PrimeField, P: ProofSystem<F>>(
+    name: &str,
+    seed: [u8; 32],
+    kind: BallotKind,
+    num_candidates: usize,
+    witness: BallotWitness,
+) -> Result<TestVector, VoteError> {
+    use crate::CIRCUIT_VERSION;
+    use tracing::info;
+
+    let circuit = witness.circuit::<F>()?;
+    let public_inputs = circuit.public_inputs();
+    let (vk, proof) = setup_and_prove::<F, P>(seed, kind, num_candidates, &witness, true)?;
+    let proof = proof.expect("a witness was given");
+    info!("Generated test vector {}", name);
+    Ok(TestVector {
+        format: TEST_VECTOR_FORMAT,
+        name: name.to_string(),
+        generator_version: env!("CARGO_PKG_VERSION").to_string(),
+        circuit_version: CIRCUIT_VERSION,
+        backend: P::NAME.to_string(),
+        curve_id: CurveId::require::<F>()?.to_u32(),
+        seed: hex::encode(seed),
+        kind,
+        num_candidates,
+        vk_digest: vk_digest::<F, P>(&vk)?,
+        verifying_key: hex::encode(P::serialize_verifying_key(&vk)?),
+        expected_nullifier: field_to_decimal(expected_nullifier::<F>(
+            witness.user_secret.as_bytes(),
+            election_id_to_field(&witness.election_id),
+        )),
+        witness,
+        public_inputs: public_inputs.to_vec().into_iter().map(field_to_decimal).collect(),
+        proof: hex::encode(P::serialize_proof(&proof)?),
+    })
+}
+
+// 随仓库提交的标准向量集，名称带电路版本，电路版本递增后旧版本的向量继续保留
+#[cfg(any(test, feature = "dev-setup"))]
+pub fn standard_vectors<F: PrimeField, P: ProofSystem<F>>() -> Result<Vec<TestVector>, VoteError> {
+    let witness = |vote: u64| BallotWitness {
+        vote,
+        user_secret: "test-vector-voter".to_string(),
+        randomness: "67890".to_string(),
+        election_id: "test-vector-election".to_string(),
+        sequence: 0,
+        epoch: 0,
+    };
+    let version = crate::CIRCUIT_VERSION;
+    Ok(vec![
+        generate::<F, P>(
+            &format!("boolean_ballot_v{}", version),
+            [1u8; 32],
+            BallotKind::Boolean,
+            crate::tally::NUM_OPTIONS,
+            witness(1),
+        )?,
+        generate::<F, P>(&format!("candidate_ballot_v{}", version), [2u8; 32], BallotKind::Candidate, 5, witness(3))?,
+    ])
+}
+
+// 从种子重新生成验证密钥并返回其摘要（仅用于测试和开发）
+// 与向量中记录的摘要不同说明电路结构或密钥生成过程发生了变化
+#[cfg(any(test, feature = "dev-setup"))]
+pub fn regenerate_vk_digest<F: PrimeField, P: ProofSystem<F>>(vector: &TestVector) -> Result<String, VoteError> {
+    let (vk, _) = setup_and_prove::<F, P>(parse_seed(&vector.seed)?, vector.kind, vector.num_candidates, &vector.witness, false)?;
+    vk_digest::<F, P>(&vk)
+}
+
+// 检查当前代码仍接受向量：见证满足电路约束，由见证重新计算的防重标识和公开输入与记录一致，
+// 证明能用记录的验证密钥通过验证
+pub fn verify_vector<F: PrimeField, P: ProofSystem<F>>(vector: &TestVector) -> Result<(), VoteError> {
+    let mismatch = |what: &str| VoteError::InvalidBallot(format!("test vector {}: {}", vector.name, what));
+
+    if vector.format == 0 || vector.format > TEST_VECTOR_FORMAT {
+        return Err(VoteError::UnsupportedVersion(format!(
+            "test vector format {} is not supported (latest is {})",
+            vector.format, TEST_VECTOR_FORMAT
+        )));
+    }
+    if vector.backend != P::NAME {
+        return Err(VoteError::KeyMismatch(format!(
+            "test vector {} was produced by backend {}, expected {}",
+            vector.name,
+            vector.backend,
+            P::NAME
+        )));
+    }
+    CurveId::check::<F>(vector.curve_id)?;
+    if vector.kind == BallotKind::Candidate && !(2..=MAX_CANDIDATES).contains(&vector.num_candidates) {
+        return Err(mismatch("number of candidates is out of range"));
+    }
+
+    // 见证满足电路约束
+    let circuit = vector.witness.circuit::<F>()?;
+    let satisfied = match vector.kind {
+        BallotKind::Boolean => VoteSystem::<F, P>::check_circuit(circuit.clone())?,
+        BallotKind::Candidate => VoteSystem::<F, P>::check_circuit(crate::CandidateVoteCircuit {
+            ballot: circuit.clone(),
+            num_candidates: vector.num_candidates,
+            params_digest: None,
+            prf: None,
+        })?,
+    };
+    if !satisfied {
+        return Err(mismatch("witness does not satisfy the circuit"));
+    }
+
+    // 防重标识和公开输入
+    let public_inputs = circuit.public_inputs();
+    let nullifier = expected_nullifier::<F>(vector.witness.user_secret.as_bytes(), circuit.election_id);
+    if field_to_decimal(nullifier) != vector.expected_nullifier {
+        return Err(mismatch("nullifier derived from the witness does not match"));
+    }
+    if public_inputs.nullifier != nullifier {
+        return Err(mismatch("circuit nullifier does not match the expected nullifier"));
+    }
+    let recorded = vector
+        .public_inputs
+        .iter()
+        .map(|s| parse_field_element::<F>(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    // 电路版本 3 之前的向量没有选票承诺这一公开输入，其余公开输入的顺序不变
+    let mut expected = public_inputs.to_vec();
+    if vector.circuit_version < 3 {
+        expected.truncate(LEGACY_NUM_PUBLIC_INPUTS);
+    }
+    if recorded != expected {
+        return Err(mismatch("public inputs derived from the witness do not match"));
+    }
+
+    // 验证密钥和证明
+    let vk = P::deserialize_verifying_key(&decode_hex(&vector.verifying_key)?)?;
+    if vk_digest::<F, P>(&vk)? != vector.vk_digest {
+        return Err(VoteError::KeyMismatch(format!(
+            "test vector {}: verifying key does not match its digest",
+            vector.name
+        )));
+    }
+    let proof = P::deserialize_proof(&decode_hex(&vector.proof)?)?;
+    check_public_input_count::<F, P>(&vk, expected.len())?;
+    if !P::verify(&vk, &expected, &proof)? {
+        error!("Test vector {} no longer verifies", vector.name);
+        return Err(VoteError::Verification(format!("test vector {}: proof was rejected", vector.name).into()));
+    }
+    Ok(())
+}
+
+// 随仓库提交的名册向量：名册深度下的 5 个叶子（末尾有空位），覆盖首个、奇数位置和最后一个叶子的路径
+pub fn merkle_vector<F: PrimeField>() -> Result<MerkleVector, VoteError> {
+    let leaves: Vec<F> = (0..5)
+        .map(|i| user_secret_to_field(format!("test-vector-voter-{}", i).as_bytes()))
+        .collect();
+    let paths = [0u64, 3, 4]
+        .into_iter()
+        .map(|index| {
+            let siblings = merkle_path(VOTER_TREE_DEPTH, &leaves, index)?;
+            Ok(MerklePathVector { index, siblings: siblings.into_iter().map(field_to_decimal).collect() })
+        })
+        .collect::<Result<Vec<_>, VoteError>>()?;
+    Ok(MerkleVector {
+        format: TEST_VECTOR_FORMAT,
+        name: format!("voter_roll_depth{}", VOTER_TREE_DEPTH),
+        generator_version: env!("CARGO_PKG_VERSION").to_string(),
+        curve_id: CurveId::require::<F>()?.to_u32(),
+        depth: VOTER_TREE_DEPTH,
+        root: field_to_decimal(compute_root(VOTER_TREE_DEPTH, &leaves)?),
+        leaves: leaves.into_iter().map(field_to_decimal).collect(),
+        paths,
+    })
+}
+
+// 检查当前代码仍接受名册向量：逐个插入和整体重算得到记录的树根，记录的成员路径与重新计算的一致且能还原树根
+pub fn verify_merkle_vector<F: PrimeField>(vector: &MerkleVector) -> Result<(), VoteError> {
+    let mismatch = |what: String| VoteError::InvalidBallot(format!("merkle vector {}: {}", vector.name, what));
+
+    if vector.format == 0 || vector.format > TEST_VECTOR_FORMAT {
+        return Err(VoteError::UnsupportedVersion(format!(
+            "test vector format {} is not supported (latest is {})",
+            vector.format, TEST_VECTOR_FORMAT
+        )));
+    }
+    CurveId::check::<F>(vector.curve_id)?;
+
+    let leaves = vector
+        .leaves
+        .iter()
+        .map(|s| parse_field_element::<F>(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let root = parse_field_element::<F>(&vector.root)?;
+    if compute_root(vector.depth, &leaves)? != root {
+        return Err(mismatch("root recomputed from the leaves does not match".to_string()));
+    }
+    let mut tree = IncrementalMerkleTree::<F>::new(vector.depth)?;
+    for leaf in &leaves {
+        tree.insert(*leaf)?;
+    }
+    if tree.root() != root {
+        return Err(mismatch("incremental tree root does not match".to_string()));
+    }
+
+    for path in &vector.paths {
+        let siblings = path
+            .siblings
+            .iter()
+            .map(|s| parse_field_element::<F>(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        if siblings != merkle_path(vector.depth, &leaves, path.index)? {
+            return Err(mismatch(format!("path of leaf {} does not match", path.index)));
+        }
+        if path_root(leaves[path.index as usize], path.index, &siblings) != root {
+            return Err(mismatch(format!("path of leaf {} does not lead to the root", path.index)));
+        }
+    }
+    Ok(())
+}
+
+// 写入名册向量（<name>.json）
+pub fn write_merkle_vector(dir: impl AsRef<Path>, vector: &MerkleVector) -> Result<(), VoteError> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir).map_err(|e| VoteError::Storage(StoreError::Io(e)))?;
+    let json = serde_json::to_string_pretty(vector).map_err(|e| VoteError::Internal(e.to_string()))?;
+    std::fs::write(dir.join(format!("{}.json", vector.name)), json + "\n").map_err(|e| VoteError::Storage(StoreError::Io(e)))
+}
+
+// 读取名册向量文件
+pub fn load_merkle_vector(path: impl AsRef<Path>) -> Result<MerkleVector, VoteError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|e| VoteError::Storage(StoreError::Io(e)))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| VoteError::InvalidBallot(format!("invalid merkle vector {}: {}", path.display(), e)))
+}
+
+// 把向量写入目录，每个向量一个 <name>.json 文件
+pub fn write_vectors(dir: impl AsRef<Path>, vectors: &[TestVector]) -> Result<(), VoteError> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir).map_err(|e| VoteError::Storage(StoreError::Io(e)))?;
+    for vector in vectors {
+        let json = serde_json::to_string_pretty(vector).map_err(|e| VoteError::Internal(e.to_string()))?;
+        std::fs::write(dir.join(format!("{}.json", vector.name)), json + "\n")
+            .map_err(|e| VoteError::Storage(StoreError::Io(e)))?;
+    }
+    Ok(())
+}
+
+// 读取目录下全部 *.json 向量，按文件名排序
+pub fn load_vectors(dir: impl AsRef<Path>) -> Result<Vec<TestVector>, VoteError> {
+    let mut paths = std::fs::read_dir(dir.as_ref())
+        .map_err(|e| VoteError::Storage(StoreError::Io(e)))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| VoteError::Storage(StoreError::Io(e)))?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path).map_err(|e| VoteError::Storage(StoreError::Io(e)))?;
+            serde_json::from_slice(&bytes).map_err(|e| {
+                VoteError::InvalidBallot(format!("invalid test vector {}: {}", path.display(), e))
+            })
+        })
+        .collect()
+}
+
+#[cfg(any(test, feature = "dev-setup"))]
+fn parse_seed(s: &str) -> Result<[u8; 32], VoteError> {
+    decode_hex(s)?
+        .try_into()
+        .map_err(|_| VoteError::InvalidBallot("test vector seed must be 32 bytes".to_string()))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, VoteError> {
+    hex::decode(s).map_err(|e| VoteError::InvalidBallot(format!("invalid hex encoding: {}", e)))
+}
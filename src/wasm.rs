@@ -0,0 +1,32 @@
+// 浏览器端证明与验证（wasm-bindgen 接口）
+//
+// 选票JSON和密钥格式见 client 模块。
+
+use crate::client;
+use crate::VoteError;
+use wasm_bindgen::prelude::*;
+
+// 错误转换为带错误码的JS异常
+fn to_js_error(e: VoteError) -> JsError {
+    JsError::new(&format!("{}: {}", e.code(), e))
+}
+
+// 以密钥文件格式的证明密钥为选票生成证明，返回压缩格式的证明
+#[wasm_bindgen]
+pub fn prove_ballot(pk_bytes: &[u8], ballot_json: &str) -> Result<Vec<u8>, JsError> {
+    let pk = client::decode_proving_key(pk_bytes).map_err(to_js_error)?;
+    client::prove_ballot(&pk, ballot_json).map_err(to_js_error)
+}
+
+// 选票的公开输入（十进制字符串数组的JSON）
+#[wasm_bindgen]
+pub fn ballot_public_inputs(ballot_json: &str) -> Result<String, JsError> {
+    client::ballot_public_inputs(ballot_json).map_err(to_js_error)
+}
+
+// 以 /vk 返回的验证密钥验证证明
+#[wasm_bindgen]
+pub fn verify_ballot(vk_bytes: &[u8], inputs_json: &str, proof_bytes: &[u8]) -> Result<bool, JsError> {
+    let vk = client::decode_verifying_key(vk_bytes).map_err(to_js_error)?;
+    client::verify_ballot(&vk, inputs_json, proof_bytes).map_err(to_js_error)
+}
@@ -1,313 +1,3591 @@
 // 异步Web服务示例
 
-use crate::{VoteCircuit, VoteSystem, generate_nullifier, init_logger, VoteSystemError};
-use ark_bn254::{Bn254, Fr};
+use crate::audit::{AuditAction, AuditEntry, AuditEvent, AuditLog};
+use crate::field::{fr_from_decimal, fr_from_hex, fr_to_decimal, fr_to_hex};
+use crate::keys;
+use crate::merkle::{VoterRoll, VOTER_TREE_DEPTH};
+use crate::nullifier::poseidon_hash2;
+use crate::store::{FileBackend, MemoryBackend, NullifierBackend};
+use crate::{export_bundle, fingerprint, CircuitConfig, CandidateVoteCircuit, ElectionParams, ProofEnvelope, VoteChoice, VoteCircuitBuilder, ProofEnvelopeJson, vk_fingerprint, BallotOpening, BallotPublicInputs, KeyFingerprint, VoteCircuit, VoteSystem, ballot_epoch, election_id_to_field, InvalidReason, VerificationOutcome, init_logger, validate_user_id, PublicInputs, ProofSystem, UniversalProofSystem, ProverPool, PrfKey, VoteError};
+use crate::curve::{DefaultField as Fr, DefaultPairing};
+use crate::sink::{self, ProofSink, RetryPolicy};
+use crate::jobs::{JobQueue, JobStatus, DEFAULT_JOB_TTL, DEFAULT_MAX_PENDING_JOBS};
+use crate::pedersen::{FinalTally, PedersenParams, RunningTally, VoteCommitment};
+use crate::tally::{check_tally_keys, NUM_OPTIONS};
+use ark_ec::pairing::Pairing;
 use ark_groth16::Groth16;
-use ark_std::rand::thread_rng;
+use ark_ff::UniformRand;
+use ark_std::rand::rngs::OsRng;
+use ark_std::rand::{CryptoRng, Rng, RngCore};
+use chrono::{DateTime, Utc};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use warp::Filter;
-use log::{debug, error, info, trace, warn};
-use ethers::{prelude::*, providers::{Provider, Http}, types::Address};
+use warp::http::StatusCode;
+use warp::{Filter, Reply};
+use tracing::{debug, error, field, info, info_span, warn, Instrument};
+use ethers::{providers::{Provider, Http}, types::Address};
 
 // 投票请求
-#[derive(serde::Deserialize, Debug)]
+#[derive(serde::Deserialize, Debug, Clone)]
 struct VoteRequest {
-    vote: u8,      // 投票选择 (0 或 1)
-    user_id: String, // 用户ID（用于内部处理，不会暴露在证明中）
-    nullifier: Option<String>, // 防重标识（可选，前端可生成）
+    #[serde(default)]
+    vote: Option<VoteChoice>, // 投票选择（"yes"/"no"/"abstain"/"spoiled" 或候选人编号），客户端自行生成证明时不提交
+    #[serde(default)]
+    user_id: String, // 用户ID（用于内部处理，不会暴露在证明中），客户端自行生成证明时不提交
+    #[serde(default)]
+    nullifier: Option<String>, // 客户端派生的防重标识（十六进制），须与 proof 一起提交
+    #[serde(default)]
+    proof: Option<String>, // 客户端生成的证明（十六进制），此时服务端只验证证明并记录防重标识
+    #[serde(default)]
+    commitment: Option<String>, // 客户端证明中的选票承诺（十六进制），须与 proof 一起提交
+    #[serde(default)]
+    epoch: Option<u64>, // 客户端证明所用的时段，缺省为当前时段
     chain: String, // 区块链网络选择
     gas_option: String, // Gas费用支付方式
+    #[serde(default)]
+    election_id: Option<String>, // 选举标识（可选）
+    #[serde(default)]
+    sequence: u64, // 选票序号（重新投票时递增，后投的选票覆盖先投的）
+    #[serde(default)]
+    key_handle: Option<String>, // /setup 返回的密钥句柄（可选），按该句柄对应的电路配置生成或验证证明
+}
+
+// 投票响应
+// 投票人保存信封后可随时用同一验证密钥离线复核自己的选票，或凭选票标识向服务端查询（见 AppState::recorded_envelope）
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VoteResponse {
+    pub success: bool,
+    pub message: String,
+    pub gas_cost: Option<String>,
+    pub transaction_hash: Option<String>,
+    pub vk_fingerprint: Option<String>, // 生成证明所用证明密钥对应的验证密钥指纹
+    pub public_inputs: Option<Vec<String>>, // 十进制字符串表示的公开输入（按电路顺序），排查验证失败时用 decode_public_inputs 解读
+    pub ballot_id: Option<String>, // 选票标识：证明规范编码的十六进制 BLAKE3 摘要
+    pub nullifier: Option<String>, // 十六进制编码的防重标识
+    pub commitment: Option<String>, // 十进制字符串表示的选票承诺（见 BallotOpening::commitment），客户端生成证明的选票为 None
+    pub envelope: Option<ProofEnvelopeJson>, // 带格式版本的证明信封，供长期保存和离线验证
+}
+
+// 批量投票中单张选票的处理结果
+#[derive(serde::Serialize, Debug)]
+struct BatchVoteResult {
+    success: bool,
+    proof: Option<String>, // 十六进制编码的证明
+    transaction_hash: Option<String>,
+    public_inputs: Option<Vec<String>>, // 十进制字符串表示的公开输入（按电路顺序）
+    vk_fingerprint: Option<String>, // 生成证明所用证明密钥对应的验证密钥指纹
+    envelope: Option<ProofEnvelopeJson>, // 带格式版本的证明信封，供长期保存和离线验证
+    error: Option<String>,
+    error_code: Option<&'static str>, // 机器可读错误码
+}
+
+// 证明任务状态响应（/vote 启用任务队列时的 202 响应与 /vote/status/<任务标识> 的响应）
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JobStatusResponse {
+    pub success: bool,
+    pub job_id: String,
+    #[serde(flatten)]
+    pub status: JobStatus,
+}
+
+// 密钥轮换请求
+#[derive(serde::Deserialize, Debug)]
+struct RotateKeyRequest {
+    #[serde(default = "default_transition_secs")]
+    transition_secs: u64, // 旧验证密钥的过渡期（秒），默认见 DEFAULT_KEY_TRANSITION
+}
+
+fn default_transition_secs() -> u64 {
+    DEFAULT_KEY_TRANSITION.as_secs()
+}
+
+// 密钥轮换响应
+#[derive(serde::Serialize, Debug)]
+struct RotateKeyResponse {
+    success: bool,
+    verifying_key: String,  // 新验证密钥（十六进制）
+    vk_fingerprint: String, // 新验证密钥指纹
+    retire_at: DateTime<Utc>, // 旧验证密钥的停用时间
+}
+
+// 错误响应
+#[derive(serde::Serialize, Debug)]
+struct ErrorResponse {
+    success: bool,
+    code: &'static str, // 机器可读错误码，见VoteError::code
+    message: String,
+}
+
+// 验证请求
+#[derive(serde::Deserialize, Debug)]
+struct VerifyRequest {
+    #[serde(default)]
+    proof: Option<String>, // 十六进制编码的证明（压缩或非压缩格式），提交 envelope 时省略
+    #[serde(default)]
+    envelope: Option<ProofEnvelopeJson>, // 投票响应中的证明信封，证明和验证密钥指纹取自信封
+    #[serde(default)]
+    public_inputs: Vec<String>, // 声称的公开输入（十进制字符串，按电路顺序），提交信封时可省略而使用信封中记录的
+    #[serde(default)]
+    election_id: Option<String>, // 选举标识（用于选择该选举的验证密钥）
+    #[serde(default)]
+    vk_fingerprint: Option<String>, // 证明信封中的验证密钥指纹（可选，提供时与所用验证密钥比对）
+}
+
+// 验证响应
+#[derive(serde::Serialize, Debug)]
+struct VerifyResponse {
+    success: bool,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<InvalidReason>, // 证明无效的原因（验证出错时返回错误响应，不在此处）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vk_fingerprint: Option<String>, // 验证通过所用的验证密钥指纹（密钥轮换的过渡期内可能是旧密钥）
+}
+
+// 批量验证中的单条证明
+#[derive(serde::Deserialize, Debug)]
+struct BatchVerifyEntry {
+    proof: String,              // 十六进制编码的证明
+    public_inputs: Vec<String>, // 十进制字符串表示的公开输入
+    #[serde(default)]
+    election_id: Option<String>, // 选举标识（用于选择该选举的验证密钥）
+    #[serde(default)]
+    vk_fingerprint: Option<String>, // 证明信封中的验证密钥指纹（可选，按指纹在该选举的有效验证密钥中选择）
+}
+
+// 承诺请求（两阶段投票的承诺阶段）
+#[derive(serde::Deserialize, Debug)]
+struct CommitRequest {
+    election_id: String,
+    user_id: String,
+    commitment: String, // 十进制字符串表示的选票承诺，见 tally::commit_ballot
+}
+
+// 承诺响应
+#[derive(serde::Serialize, Debug)]
+struct CommitResponse {
+    success: bool,
+    phase: ElectionPhase,
+}
+
+// 揭示请求（两阶段投票的揭示阶段）
+#[derive(serde::Deserialize, Debug)]
+struct RevealRequest {
+    election_id: String,
+    user_id: String,
+    vote: VoteChoice,   // 投票选择，见 VoteChoice
+    randomness: String, // 十进制字符串表示的承诺随机值
+    chain: String,
+    gas_option: String,
+    #[serde(default)]
+    sequence: u64, // 选票序号，见 VoteRequest::sequence
+}
+
+// 揭示响应
+#[derive(serde::Serialize, Debug)]
+struct RevealResponse {
+    success: bool,
+    proof: String,              // 十六进制编码的证明
+    public_inputs: Vec<String>, // 十进制字符串表示的公开输入（按电路顺序）
+    vk_fingerprint: String,
+    envelope: ProofEnvelopeJson, // 带格式版本的证明信封，供长期保存和离线验证
+}
+
+// 两阶段投票中选举所处的阶段，只能按 Commit → Reveal → Closed 的顺序推进
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElectionPhase {
+    Commit, // 接受选票承诺
+    Reveal, // 接受承诺的开启值并生成证明
+    Closed, // 不再接受任何提交
+}
+
+impl ElectionPhase {
+    // 下一阶段
+    pub fn next(self) -> Option<Self> {
+        match self {
+            ElectionPhase::Commit => Some(ElectionPhase::Reveal),
+            ElectionPhase::Reveal => Some(ElectionPhase::Closed),
+            ElectionPhase::Closed => None,
+        }
+    }
+}
+
+// 选举的生命周期，只能按 Created → Open → Closed → Tallied 的顺序推进
+// 只有经 POST /elections 创建的选举纳入生命周期管理，这些选举只在 Open 状态下接受选票
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElectionState {
+    Created, // 已生成密钥，尚未开放投票
+    Open,    // 接受选票
+    Closed,  // 不再接受选票，可以计票
+    Tallied, // 已公布计票结果
+}
+
+impl ElectionState {
+    // 开放投票，已开放的选举保持不变
+    pub fn open(self) -> Result<Self, VoteError> {
+        match self {
+            ElectionState::Created | ElectionState::Open => Ok(ElectionState::Open),
+            state => Err(VoteError::WrongPhase(format!("cannot open an election in the {:?} state", state))),
+        }
+    }
+    
+    // 结束投票；可重复调用，已结束或已计票的选举保持不变
+    pub fn close(self) -> Result<Self, VoteError> {
+        match self {
+            ElectionState::Created => Err(VoteError::WrongPhase("cannot close an election that was never opened".to_string())),
+            ElectionState::Open => Ok(ElectionState::Closed),
+            state => Ok(state),
+        }
+    }
+    
+    // 记录计票结果已公布，只有已结束的选举可以计票
+    pub fn tallied(self) -> Result<Self, VoteError> {
+        match self {
+            ElectionState::Closed | ElectionState::Tallied => Ok(ElectionState::Tallied),
+            state => Err(VoteError::WrongPhase(format!("cannot tally an election in the {:?} state", state))),
+        }
+    }
+    
+    // 是否接受选票
+    pub fn accepts_votes(self) -> bool {
+        self == ElectionState::Open
+    }
+    
+    // 投票是否已结束
+    pub fn is_closed(self) -> bool {
+        matches!(self, ElectionState::Closed | ElectionState::Tallied)
+    }
+}
+
+// 选举状态响应
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ElectionStateResponse {
+    pub success: bool,
+    pub election_id: String,
+    pub state: ElectionState,
+}
+
+// 验证密钥查询参数
+#[derive(serde::Deserialize, Debug)]
+struct VerifyingKeyQuery {
+    election_id: Option<String>,
+}
+
+// 验证密钥响应
+#[derive(serde::Serialize, Debug)]
+struct VerifyingKeyResponse {
+    success: bool,
+    verifying_key: String,  // 十六进制编码的验证密钥
+    vk_fingerprint: String, // 十六进制编码的验证密钥指纹
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prf_key_commitment: Option<String>, // 验证密钥绑定的 PRF 密钥承诺（十六进制，未设置 PRF 密钥时省略）
+}
+
+// 审计日志响应
+#[derive(serde::Serialize, Debug)]
+struct AuditResponse<'a> {
+    success: bool,
+    head: String,              // 链头摘要（十六进制）
+    entries: &'a [AuditEntry], // 全部审计记录，可用 audit::verify_chain 复核
+}
+
+// 创建选举请求
+#[derive(serde::Deserialize, Debug)]
+struct CreateElectionRequest {
+    election_id: String, // 选举标识
+    #[serde(default)]
+    params: ElectionParams, // 选举参数（缺省为默认电路配置），参数摘要绑定到该选举的密钥
+}
+
+// 创建选举响应
+#[derive(serde::Serialize, Debug)]
+struct CreateElectionResponse {
+    success: bool,
+    election_id: String,
+    params: ElectionParams,
+    params_hash: String,   // 十六进制编码的选举参数摘要
+    verifying_key: String, // 十六进制编码的验证密钥
+    state: ElectionState,  // 新建的选举处于 Created 状态，须开放后才接受选票
+}
+
+// 选举参数响应
+#[derive(serde::Serialize, Debug)]
+struct ElectionParamsResponse {
+    success: bool,
+    election_id: String,
+    params: ElectionParams,
+    params_hash: String, // 十六进制编码的选举参数摘要
+}
+
+// 投票人登记请求
+#[derive(serde::Deserialize, Debug)]
+struct RegisterVotersRequest {
+    commitments: Vec<String>, // 十进制编码的凭证承诺，按数组顺序插入名册树
+}
+
+// 投票人登记响应
+#[derive(serde::Serialize, Debug)]
+struct RegisterVotersResponse {
+    success: bool,
+    election_id: String,
+    registered: usize, // 本批登记的人数
+    first_index: u64,  // 本批第一个承诺在名册树中的位置
+    root: String,      // 登记后的十进制名册树根
+    size: u64,
+}
+
+// 名册树根响应
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct MerkleRootResponse {
+    pub success: bool,
+    pub election_id: String,
+    pub root: String, // 十进制编码的名册树根
+    pub size: u64,    // 已登记的投票人数
+    pub depth: usize,
+}
+
+// 按电路配置生成密钥的响应
+#[derive(serde::Serialize, Debug)]
+struct SetupResponse {
+    success: bool,
+    key_handle: String, // 密钥句柄（配置摘要的十六进制），投票时通过 key_handle 引用
+    config: CircuitConfig,
+    verifying_key: String,  // 十六进制编码的验证密钥
+    vk_fingerprint: String, // 十六进制编码的验证密钥指纹
+}
+
+// 错误对应的HTTP状态码
+fn error_status(e: &VoteError) -> StatusCode {
+    match e {
+        VoteError::InvalidBallot(_)
+        | VoteError::UnsatisfiedConstraint(_)
+        | VoteError::Serialization(_)
+        | VoteError::FieldRangeExceeded(_)
+        | VoteError::Aggregation(_)
+        | VoteError::Decryption(_)
+        | VoteError::CommitmentMismatch
+        | VoteError::BundleIntegrity(_)
+        | VoteError::InvalidUserId(_)
+        | VoteError::InvalidProofEncoding(_)
+        | VoteError::UnsupportedVersion(_)
+        | VoteError::UnsupportedBundleVersion(_) => StatusCode::BAD_REQUEST,
+        VoteError::DuplicateNullifier | VoteError::ReplayedProof | VoteError::DuplicateCommitment(_) => StatusCode::CONFLICT,
+        VoteError::WrongPhase(_) => StatusCode::FORBIDDEN,
+        VoteError::Verification(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        VoteError::KeyMismatch(_) | VoteError::ConfigMismatch(_) => StatusCode::CONFLICT,
+        VoteError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        VoteError::AtCapacity => StatusCode::SERVICE_UNAVAILABLE,
+        VoteError::UnknownElection(_) | VoteError::UnknownJob(_) => StatusCode::NOT_FOUND,
+        VoteError::Synthesis(_)
+        | VoteError::Setup(_)
+        | VoteError::Proving(_)
+        | VoteError::Ceremony(_)
+        | VoteError::Storage(_)
+        | VoteError::AuditIntegrity(_)
+        | VoteError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// 投票截止后的响应
+fn voting_closed_reply() -> warp::reply::Response {
+    let body = ErrorResponse {
+        success: false,
+        code: "voting_closed",
+        message: "Voting closed".to_string(),
+    };
+    warp::reply::with_status(warp::reply::json(&body), StatusCode::FORBIDDEN).into_response()
+}
+
+// 将错误转换为带状态码的JSON响应
+fn error_reply(e: &VoteError) -> warp::reply::Response {
+    let body = ErrorResponse {
+        success: false,
+        code: e.code(),
+        message: e.to_string(),
+    };
+    warp::reply::with_status(warp::reply::json(&body), error_status(e)).into_response()
+}
+
+// 未匹配任何路由的请求返回 404 及错误响应体，其他拒绝交给 warp 默认处理
+pub async fn handle_rejection(rejection: warp::Rejection) -> Result<warp::reply::Response, warp::Rejection> {
+    if rejection.is_not_found() {
+        let body = ErrorResponse {
+            success: false,
+            code: "not_found",
+            message: "No such endpoint".to_string(),
+        };
+        return Ok(warp::reply::with_status(warp::reply::json(&body), StatusCode::NOT_FOUND).into_response());
+    }
+    Err(rejection)
+}
+
+// 可放入共享随机数源的密码学安全随机数发生器
+pub trait SecureRng: RngCore + CryptoRng + Send {}
+
+impl<R: RngCore + CryptoRng + Send> SecureRng for R {}
+
+// 注入到应用状态的共享随机数源，默认由 OsRng 提供
+// 投票人秘密、承诺随机值、证明随机性等所有随机值都从这里取，克隆得到的是同一个随机数源
+#[derive(Clone)]
+pub struct SharedRng(Arc<std::sync::Mutex<Box<dyn SecureRng>>>);
+
+impl SharedRng {
+    pub fn new(rng: impl SecureRng + 'static) -> Self {
+        Self(Arc::new(std::sync::Mutex::new(Box::new(rng))))
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut dyn SecureRng) -> T) -> T {
+        let mut rng = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        f(rng.as_mut())
+    }
+}
+
+impl Default for SharedRng {
+    fn default() -> Self {
+        Self::new(OsRng)
+    }
+}
+
+impl RngCore for SharedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.with(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.with(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.with(|rng| rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.with(|rng| rng.try_fill_bytes(dest))
+    }
+}
+
+impl CryptoRng for SharedRng {}
+
+// 应用状态（证明系统后端默认为默认曲线上的Groth16，防重标识默认存储在内存中）
+pub struct AppState<P: ProofSystem<Fr> = Groth16<DefaultPairing>, N: NullifierBackend = MemoryBackend> {
+    vote_system: Arc<VoteSystem<Fr, P>>,
+    default_keys: Arc<RwLock<DefaultKeys<P>>>, // 默认选举的当前密钥，及轮换后过渡期内仍接受的旧验证密钥
+    blockchain_providers: Arc<RwLock<HashMap<String, Arc<Provider<Http>>>>>,
+    vote_contracts: Arc<RwLock<HashMap<String, Address>>>,
+    voted_users: Arc<RwLock<HashMap<(String, String), u64>>>, // 已投票用户（(选举标识, 投票人登记键) -> 最新选票序号），默认选举的选举标识为空串
+    elections: Arc<RwLock<HashMap<ElectionId, ElectionHandle<P, N>>>>, // 各选举的密钥、参数、状态及防重标识（setup_election 生成或由通用SRS索引得到）
+    config_keys: Arc<RwLock<HashMap<String, ConfigKeys<P>>>>, // 由 /setup 按电路配置生成的密钥（密钥句柄 -> 配置及密钥）
+    tally_keys: Arc<RwLock<HashMap<usize, TallyKeys<P>>>>, // 独立可信设置公布的计票电路密钥（计入选票数量 -> 密钥），见 add_tally_keys
+    nullifiers: Arc<RwLock<N>>, // 默认选举已使用的防重标识及各防重标识已接受的最大选票序号
+    accepted_proofs: Arc<RwLock<HashSet<[u8; 32]>>>, // 已接受的客户端证明摘要（BLAKE3），拒绝原样重放的证明
+    phases: Arc<RwLock<HashMap<String, ElectionPhase>>>, // 两阶段投票中各选举的阶段（未记录的选举处于承诺阶段）
+    commitments: Arc<RwLock<HashMap<(String, String), Fr>>>, // 选票承诺（(选举标识, 投票人登记键) -> 承诺）
+    ballots: Arc<RwLock<BallotStore<P>>>, // 已生成证明的选票，供导出选举结果和按选举计票
+    tally_cache: Arc<RwLock<HashMap<String, (u64, TallyResponse)>>>, // 各选举的计票结果（选举标识 -> (选票存储版本, 结果)）
+    interim_results: bool, // 是否允许在选举结束前查询计票结果
+    envelopes: Arc<RwLock<HashMap<String, ProofEnvelopeJson>>>, // 已受理选票的证明信封（选票标识 -> 信封）
+    running_tally: Arc<RwLock<RunningTally<TallyGroup>>>, // 按候选人累加的选票承诺，计票结果随时可用
+    running_votes: Arc<RwLock<RunningVotes>>, // 运行计票中各投票人当前计入的一票，重新投票时替换
+    num_candidates: usize, // 候选人数量，默认密钥按该数量的多候选人电路生成
+    authority_token: Option<Arc<str>>, // 计票方令牌，未设置时导出接口一律拒绝
+    rng: SharedRng, // 所有随机值的来源
+    election_end: DateTime<Utc>, // 投票截止时间，之后提交的选票一律拒绝
+    proving_timeout: Duration, // 单张选票证明生成的超时时间，超时返回 Timeout
+    audit_log: Arc<RwLock<AuditLog>>, // 验证事件的哈希链审计日志
+    prf_key: Option<Arc<PrfKey<Fr>>>, // 服务端 PRF 密钥，设置后投票人秘密由密钥和用户ID派生（见 prf 模块）
+    secret_key: Arc<PrfKey<Fr>>, // 未设置 PRF 密钥时派生投票人秘密的密钥（不绑定电路），同一密钥下每个投票人的防重标识固定
+    secret_key_pinned: bool, // secret_key 由配置给定或从密钥目录加载（with_rng 不再重新生成），否则由随机数源生成，重启后改变
+    proof_sink: Option<(Arc<dyn ProofSink>, RetryPolicy)>, // 已受理证明的归档目标及重试策略，未设置时不归档
+    jobs: Option<Arc<JobQueue>>, // 异步证明任务队列，设置后 /vote 返回 202 及任务标识，未设置时同步等待证明
+    key_dir: Option<Arc<Path>>, // 默认密钥文件所在目录（见 load_or_init），设置后轮换的密钥同时写入
+    circuit_indexer: Option<CircuitIndexer<P>>, // 通用可信设置的状态为新选举索引电路（见 with_universal_srs），未设置时按电路单独生成密钥
+}
+
+// 默认的证明生成超时时间
+pub const DEFAULT_PROVING_TIMEOUT: Duration = Duration::from_secs(120);
+
+// 用通用SRS索引空电路，得到新选举的密钥
+type CircuitIndexer<P> = Arc<
+    dyn Fn(CandidateVoteCircuit<Fr>) -> Result<(<P as ProofSystem<Fr>>::ProvingKey, <P as ProofSystem<Fr>>::VerifyingKey), VoteError>
+        + Send
+        + Sync,
+>;
+
+// 运行计票中各投票人当前计入的一票（防重标识 -> (序号, 承诺, 候选人)）
+type RunningVotes = HashMap<Fr, (u64, VoteCommitment<TallyGroup>, usize)>;
+
+// 已生成证明的选票（公开输入、证明及计票所需的承诺开启值）
+type RecordedBallot<P> = (PublicInputs<Fr>, <P as ProofSystem<Fr>>::Proof, BallotOpening<Fr>);
+
+// 按选举分组的已生成证明的选票，每记录一张选票版本号加一，计票缓存按版本号失效
+// 计票电路只支持两个选项，其他电路配置下的选票不记录
+struct BallotStore<P: ProofSystem<Fr>> {
+    default: Vec<RecordedBallot<P>>, // 默认密钥下的选票，导出包只覆盖这些选票
+    elections: HashMap<String, Vec<RecordedBallot<P>>>, // 各选举密钥下的选票（选举标识 -> 选票）
+    version: u64,
+}
+
+impl<P: ProofSystem<Fr>> Default for BallotStore<P> {
+    fn default() -> Self {
+        Self { default: Vec::new(), elections: HashMap::new(), version: 0 }
+    }
+}
+
+impl<P: ProofSystem<Fr>> BallotStore<P> {
+    // 记录一张选票，election_id 为 None 时记入默认密钥下的选票
+    fn push(&mut self, election_id: Option<&str>, ballot: RecordedBallot<P>) {
+        match election_id {
+            Some(id) => self.elections.entry(id.to_string()).or_default().push(ballot),
+            None => self.default.push(ballot),
+        }
+        self.version += 1;
+    }
+}
+
+// 运行计票的承诺所在的群（默认曲线的G1，标量域即电路所在的域）
+pub type TallyGroup = <DefaultPairing as Pairing>::G1;
+
+// 单场选举的电路配置及密钥（选举标识是电路常量，密钥不能用于其他选举）
+type ElectionKeys<P> = (CircuitConfig, Arc<<P as ProofSystem<Fr>>::ProvingKey>, Arc<<P as ProofSystem<Fr>>::VerifyingKey>);
+
+// 选举标识（路由路径中的 <选举标识>）
+pub type ElectionId = String;
+
+// 单场选举：电路配置及密钥、密钥绑定的选举参数、生命周期状态和已使用的防重标识
+// 各选举的防重标识互不影响：由 AppState 的后端按选举标识打开各自的存储（见 NullifierBackend::open_election），
+// 持久化后端下重启后为同一选举生成或登记密钥时载入之前记录的防重标识
+struct ElectionHandle<P: ProofSystem<Fr>, N: NullifierBackend> {
+    config: CircuitConfig,
+    proving_key: Arc<P::ProvingKey>,
+    verifying_key: Arc<P::VerifyingKey>,
+    params: Option<ElectionParams>, // 密钥绑定的选举参数（导入已有密钥的选举为 None）
+    state: Option<ElectionState>,   // 生命周期状态（未纳入生命周期管理的选举为 None）
+    nullifiers: N, // 已使用的防重标识及各防重标识已接受的最大选票序号
+    voters: VoterRoll<Fr>, // 投票人名册（凭证承诺的 Merkle 树），只在 Created 状态下接受登记
+}
+
+impl<P: ProofSystem<Fr>, N: NullifierBackend> ElectionHandle<P, N> {
+    fn new(
+        config: CircuitConfig,
+        proving_key: P::ProvingKey,
+        verifying_key: Arc<P::VerifyingKey>,
+        params: Option<ElectionParams>,
+        nullifiers: N,
+    ) -> Self {
+        Self {
+            config,
+            proving_key: Arc::new(proving_key),
+            verifying_key,
+            params,
+            state: None,
+            nullifiers,
+            voters: VoterRoll::new(VOTER_TREE_DEPTH).expect("VOTER_TREE_DEPTH is a valid tree depth"),
+        }
+    }
+
+    fn keys(&self) -> ElectionKeys<P> {
+        (self.config, self.proving_key.clone(), self.verifying_key.clone())
+    }
+}
+
+// 默认选举的密钥：新选票用当前密钥证明，验证时当前密钥和未到停用时间的旧验证密钥都接受（见 AppState::rotate_keys）
+struct DefaultKeys<P: ProofSystem<Fr>> {
+    proving_key: Arc<P::ProvingKey>,
+    verifying_key: Arc<P::VerifyingKey>,
+    retiring: Vec<(Arc<P::VerifyingKey>, DateTime<Utc>)>, // 轮换下来的旧验证密钥及其停用时间
+}
+
+impl<P: ProofSystem<Fr>> DefaultKeys<P> {
+    // 此刻仍接受的验证密钥，当前密钥在前
+    fn active(&self, now: DateTime<Utc>) -> Vec<Arc<P::VerifyingKey>> {
+        std::iter::once(self.verifying_key.clone())
+            .chain(self.retiring.iter().filter(|(_, retire_at)| *retire_at > now).map(|(vk, _)| vk.clone()))
+            .collect()
+    }
+}
+
+// 密钥轮换后旧验证密钥的默认过渡期
+pub const DEFAULT_KEY_TRANSITION: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 默认密钥目录中的证明密钥和验证密钥文件名（见 AppState::load_or_init）
+pub const PROVING_KEY_FILE: &str = "proving_key.bin";
+pub const VERIFYING_KEY_FILE: &str = "verifying_key.bin";
+// 默认密钥目录中派生投票人秘密的密钥文件名（32字节的十六进制编码，未设置 PRF 密钥时使用）
+pub const VOTER_SECRET_KEY_FILE: &str = "voter_secret.key";
+
+// serve 从该环境变量读取默认密钥目录，未设置时每次启动都重新生成密钥
+pub const KEY_DIR_ENV: &str = "ZKP_VOTE_KEY_DIR";
+// 设置为 1 时 serve 无视密钥目录中已有的密钥重新生成（之前签发的证明全部失效）
+pub const FORCE_NEW_KEYS_ENV: &str = "ZKP_VOTE_FORCE_NEW_KEYS";
+// serve 从该环境变量读取计票方令牌（见 AppState::with_authority_token）
+pub const AUTHORITY_TOKEN_ENV: &str = "ZKP_VOTE_AUTHORITY_TOKEN";
+// serve 从该环境变量读取投票截止时间（RFC 3339，见 AppState::with_election_end），未设置时投票不截止，/results 不公布
+pub const ELECTION_END_ENV: &str = "ZKP_VOTE_ELECTION_END";
+
+// 将默认密钥写入 key_dir，头部记录指纹和电路配置摘要（见 fingerprint::encode_key_for_config）
+// 先写临时文件再改名，中途失败不会留下写了一半的密钥文件
+fn write_default_keys<P: ProofSystem<Fr>>(
+    key_dir: &Path,
+    config: &CircuitConfig,
+    proving_key: &P::ProvingKey,
+    verifying_key: &P::VerifyingKey,
+) -> Result<(), VoteError> {
+    let files = [
+        (PROVING_KEY_FILE, crate::fingerprint::encode_key_for_config::<Fr, _>(proving_key, config)?),
+        (VERIFYING_KEY_FILE, crate::fingerprint::encode_key_for_config::<Fr, _>(verifying_key, config)?),
+    ];
+    std::fs::create_dir_all(key_dir).map_err(|e| VoteError::Storage(e.into()))?;
+    for (name, bytes) in files {
+        let tmp = key_dir.join(format!(".{}.tmp", name));
+        std::fs::write(&tmp, bytes)
+            .and_then(|()| std::fs::rename(&tmp, key_dir.join(name)))
+            .map_err(|e| {
+                error!("Failed to write {} to {}: {}", name, key_dir.display(), e);
+                VoteError::Storage(e.into())
+            })?;
+    }
+    info!(
+        "Wrote default keys with verifying key {} to {}",
+        hex::encode(vk_fingerprint::<Fr, P>(verifying_key)),
+        key_dir.display()
+    );
+    Ok(())
+}
+
+// 默认密钥目录中的防重标识存储文件名（见 FileBackend），重启后已投票的用户不能再投
+pub const NULLIFIER_STORE_FILE: &str = "nullifiers.log";
+// 默认密钥目录中默认密钥绑定的 PRF 密钥承诺（十六进制），默认密钥不绑定 PRF 密钥时没有该文件
+pub const PRF_COMMITMENT_FILE: &str = "prf_commitment.hex";
+
+// 记录新生成的默认密钥绑定的 PRF 密钥承诺，不绑定时删除旧的记录
+fn write_prf_commitment(key_dir: &Path, commitment: Option<Fr>) -> Result<(), VoteError> {
+    let path = key_dir.join(PRF_COMMITMENT_FILE);
+    let written = match commitment {
+        Some(commitment) => {
+            let tmp = key_dir.join(format!(".{}.tmp", PRF_COMMITMENT_FILE));
+            std::fs::write(&tmp, fr_to_hex(commitment)).and_then(|()| std::fs::rename(&tmp, &path))
+        }
+        None => match std::fs::remove_file(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        },
+    };
+    written.map_err(|e| {
+        error!("Failed to update {} in {}: {}", PRF_COMMITMENT_FILE, key_dir.display(), e);
+        VoteError::Storage(e.into())
+    })
+}
+
+// 核对目录中的默认密钥绑定的 PRF 密钥承诺与本次启动提供的 PRF 密钥一致（都没有也算一致）
+fn check_prf_commitment(key_dir: &Path, commitment: Option<Fr>) -> Result<(), VoteError> {
+    let path = key_dir.join(PRF_COMMITMENT_FILE);
+    let recorded = if path.exists() {
+        let contents = String::from_utf8(read_key_file(&path)?)
+            .map_err(|_| VoteError::KeyMismatch(format!("{} is not valid hex", path.display())))?;
+        Some(fr_from_hex::<Fr>(contents.trim()).map_err(|_| VoteError::KeyMismatch(format!("{} is not valid hex", path.display())))?)
+    } else {
+        None
+    };
+    match (recorded, commitment) {
+        (Some(recorded), Some(commitment)) if recorded == commitment => Ok(()),
+        (None, None) => Ok(()),
+        (recorded, commitment) => {
+            error!("Default keys in {} are not bound to the configured PRF key", key_dir.display());
+            Err(VoteError::KeyMismatch(format!(
+                "default keys in {} are bound to PRF key commitment {}, but the server is configured with {}",
+                key_dir.display(),
+                recorded.map(fr_to_hex).unwrap_or_else(|| "none".to_string()),
+                commitment.map(fr_to_hex).unwrap_or_else(|| "none".to_string())
+            )))
+        }
+    }
+}
+
+// 从 key_dir 读取派生投票人秘密的密钥，文件不存在时随机生成并写入（先写临时文件再改名，仅所有者可读写）
+// 重新生成默认密钥时保留该文件：换了密钥后同一用户的防重标识仍然不变，已投票的用户不能再投
+fn load_or_init_voter_secret_key(key_dir: &Path, rng: &mut SharedRng) -> Result<PrfKey<Fr>, VoteError> {
+    let path = key_dir.join(VOTER_SECRET_KEY_FILE);
+    if path.exists() {
+        let mut contents = String::from_utf8(read_key_file(&path)?)
+            .map_err(|_| VoteError::Setup(format!("{} is not valid hex", path.display()).into()))?;
+        let key = PrfKey::from_hex(&contents);
+        contents.zeroize();
+        return key;
+    }
+    let mut bytes = [0u8; 32];
+    rng.fill_bytes(&mut bytes);
+    let key = PrfKey::from_bytes(&bytes);
+    let mut contents = hex::encode(bytes);
+    bytes.zeroize();
+    std::fs::create_dir_all(key_dir).map_err(|e| VoteError::Storage(e.into()))?;
+    let tmp = key_dir.join(format!(".{}.tmp", VOTER_SECRET_KEY_FILE));
+    let written = write_private_file(&tmp, contents.as_bytes()).and_then(|()| std::fs::rename(&tmp, &path));
+    contents.zeroize();
+    written.map_err(|e| {
+        error!("Failed to write {} to {}: {}", VOTER_SECRET_KEY_FILE, key_dir.display(), e);
+        VoteError::Storage(e.into())
+    })?;
+    info!("Wrote a new voter secret key to {}", key_dir.display());
+    Ok(key)
+}
+
+// 创建仅所有者可读写的文件并写入
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    std::io::Write::write_all(&mut file, contents)?;
+    file.sync_all()
+}
+
+// 读取密钥文件
+fn read_key_file(path: &Path) -> Result<Vec<u8>, VoteError> {
+    std::fs::read(path).map_err(|e| {
+        error!("Failed to read key file {}: {}", path.display(), e);
+        VoteError::Storage(e.into())
+    })
+}
+
+// 按电路配置生成的密钥
+type ConfigKeys<P> = (CircuitConfig, Arc<<P as ProofSystem<Fr>>::ProvingKey>, Arc<<P as ProofSystem<Fr>>::VerifyingKey>);
+
+// 计票电路的证明密钥和验证密钥
+type TallyKeys<P> = (Arc<<P as ProofSystem<Fr>>::ProvingKey>, Arc<<P as ProofSystem<Fr>>::VerifyingKey>);
+
+// 手动实现Clone，避免要求后端类型本身实现Clone
+impl<P: ProofSystem<Fr>, N: NullifierBackend> Clone for AppState<P, N> {
+    fn clone(&self) -> Self {
+        Self {
+            vote_system: self.vote_system.clone(),
+            default_keys: self.default_keys.clone(),
+            blockchain_providers: self.blockchain_providers.clone(),
+            vote_contracts: self.vote_contracts.clone(),
+            voted_users: self.voted_users.clone(),
+            elections: self.elections.clone(),
+            config_keys: self.config_keys.clone(),
+            tally_keys: self.tally_keys.clone(),
+            nullifiers: self.nullifiers.clone(),
+            accepted_proofs: self.accepted_proofs.clone(),
+            phases: self.phases.clone(),
+            commitments: self.commitments.clone(),
+            ballots: self.ballots.clone(),
+            tally_cache: self.tally_cache.clone(),
+            interim_results: self.interim_results,
+            envelopes: self.envelopes.clone(),
+            running_tally: self.running_tally.clone(),
+            running_votes: self.running_votes.clone(),
+            num_candidates: self.num_candidates,
+            authority_token: self.authority_token.clone(),
+            rng: self.rng.clone(),
+            election_end: self.election_end,
+            proving_timeout: self.proving_timeout,
+            audit_log: self.audit_log.clone(),
+            prf_key: self.prf_key.clone(),
+            secret_key: self.secret_key.clone(),
+            secret_key_pinned: self.secret_key_pinned,
+            proof_sink: self.proof_sink.clone(),
+            jobs: self.jobs.clone(),
+            key_dir: self.key_dir.clone(),
+            circuit_indexer: self.circuit_indexer.clone(),
+        }
+    }
+}
+
+impl<P: ProofSystem<Fr>> Default for AppState<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: ProofSystem<Fr>> AppState<P> {
+    // 初始化应用状态，密钥生成失败时返回错误
+    pub fn try_new() -> Result<Self, VoteError> {
+        Self::try_with_nullifier_backend(MemoryBackend::default())
+    }
+    
+    // 初始化应用状态，密钥生成失败时panic（仅用于示例和测试）
+    pub fn new() -> Self {
+        Self::try_new().expect("Failed to setup vote system")
+    }
+    
+    // 初始化指定候选人数量的应用状态，按该数量的多候选人电路为默认选举生成密钥
+    pub fn try_with_candidates(num_candidates: usize) -> Result<Self, VoteError> {
+        init_logger();
+        info!("Initializing application state for {} candidates", num_candidates);
+        
+        let mut rng = SharedRng::default();
+        let (vote_system, proving_key, verifying_key) =
+            VoteSystem::<Fr, P>::setup_with_candidates(num_candidates, default_election_id(), &mut rng)?;
+        
+        let mut state = Self::from_parts(vote_system, proving_key, verifying_key, MemoryBackend::default(), rng);
+        state.num_candidates = num_candidates;
+        state.running_tally = Arc::new(RwLock::new(RunningTally::new(num_candidates)));
+        Ok(state)
+    }
+    
+    // 使用服务端 PRF 密钥初始化应用状态，防重标识 = PRF_密钥(用户ID, 选举标识)
+    // 密钥承诺绑定到默认密钥和之后由本状态生成的各选举、各配置的密钥；
+    // 用 add_election_keys 导入的密钥须以同一承诺的空电路生成（见 CandidateVoteCircuit::with_prf_commitment）
+    pub fn try_with_prf_key(prf_key: PrfKey<Fr>) -> Result<Self, VoteError> {
+        init_logger();
+        info!("Initializing application state with a server-side PRF key");
+        
+        let mut rng = SharedRng::default();
+        let blank = CandidateVoteCircuit::blank(NUM_OPTIONS, default_election_id()).with_prf_commitment(prf_key.commitment());
+        let (vote_system, proving_key, verifying_key) = VoteSystem::<Fr, P>::setup_for_circuit(blank, &mut rng)?;
+        
+        let mut state = Self::from_parts(vote_system, proving_key, verifying_key, MemoryBackend::default(), rng);
+        state.prf_key = Some(Arc::new(prf_key));
+        Ok(state)
+    }
+}
+
+impl<P: ProofSystem<Fr>> AppState<P, FileBackend> {
+    // 从 key_dir 加载默认密钥，目录中没有密钥文件时生成密钥并写入（文件名见 PROVING_KEY_FILE、VERIFYING_KEY_FILE）
+    // 防重标识及其选票序号记录在同一目录的 NULLIFIER_STORE_FILE 中，派生投票人秘密的密钥同样从该目录加载或生成
+    // （见 VOTER_SECRET_KEY_FILE），重启后同一用户的防重标识不变，已投票的用户不能再投；
+    // 每次启动都重新生成密钥会使之前签发的证明全部失效，重启后应加载同一套密钥；
+    // 加载时重新校验指纹、电路配置和密钥配对（见 from_key_files），不符或只有一个密钥文件时拒绝启动
+    pub fn load_or_init(key_dir: &Path) -> Result<Self, VoteError> {
+        Self::load_or_init_with(key_dir, false)
+    }
+
+    // 同 load_or_init，force_new_keys 为 true 时无视已有的密钥文件重新生成并覆盖（之前签发的证明全部失效，已记录的防重标识保留）
+    pub fn load_or_init_with(key_dir: &Path, force_new_keys: bool) -> Result<Self, VoteError> {
+        Self::load_or_init_in(key_dir, force_new_keys, None)
+    }
+
+    // 同 load_or_init_with，默认密钥绑定服务端 PRF 密钥的承诺（见 try_with_prf_key）
+    // 密钥本身不写入目录，重启时须提供同一 PRF 密钥；目录中记录的承诺不符时拒绝启动
+    pub fn load_or_init_with_prf_key(key_dir: &Path, force_new_keys: bool, prf_key: PrfKey<Fr>) -> Result<Self, VoteError> {
+        Self::load_or_init_in(key_dir, force_new_keys, Some(prf_key))
+    }
+
+    fn load_or_init_in(key_dir: &Path, force_new_keys: bool, prf_key: Option<PrfKey<Fr>>) -> Result<Self, VoteError> {
+        std::fs::create_dir_all(key_dir).map_err(|e| VoteError::Storage(e.into()))?;
+        let nullifiers = FileBackend::open(key_dir.join(NULLIFIER_STORE_FILE)).map_err(|e| {
+            error!("Failed to open the nullifier store in {}: {}", key_dir.display(), e);
+            VoteError::from(e)
+        })?;
+        Self::load_or_init_with_backend(key_dir, force_new_keys, prf_key, nullifiers)
+    }
+}
+
+impl<P: ProofSystem<Fr>, N: NullifierBackend> AppState<P, N> {
+    // 同 load_or_init，防重标识记录在指定的存储后端中（后端须持久化，否则重启后已投票的用户可以再投）
+    // 设置 prf_key 时默认密钥绑定其承诺，承诺记录在 PRF_COMMITMENT_FILE 中，加载时与密钥目录中的记录核对；
+    // 未设置时派生投票人秘密的密钥从 VOTER_SECRET_KEY_FILE 加载或生成
+    pub fn load_or_init_with_backend(
+        key_dir: &Path,
+        force_new_keys: bool,
+        prf_key: Option<PrfKey<Fr>>,
+        nullifiers: N,
+    ) -> Result<Self, VoteError> {
+        init_logger();
+        let config = CircuitConfig::default();
+        let (pk_path, vk_path) = (key_dir.join(PROVING_KEY_FILE), key_dir.join(VERIFYING_KEY_FILE));
+        let commitment = prf_key.as_ref().map(|key| key.commitment());
+        let mut state = match (force_new_keys, pk_path.exists(), vk_path.exists()) {
+            (false, true, true) => {
+                info!("Loading default keys from {}", key_dir.display());
+                check_prf_commitment(key_dir, commitment)?;
+                let (pk_file, vk_file) = (read_key_file(&pk_path)?, read_key_file(&vk_path)?);
+                Self::from_key_files(&pk_file, &vk_file, config, nullifiers)?
+            }
+            (false, true, false) | (false, false, true) => {
+                error!("Key directory {} holds only one of the default keys", key_dir.display());
+                return Err(VoteError::KeyMismatch(format!(
+                    "key directory {} holds only one of {} and {}; restore the missing key or force new keys",
+                    key_dir.display(),
+                    PROVING_KEY_FILE,
+                    VERIFYING_KEY_FILE
+                )));
+            }
+            _ => {
+                if force_new_keys {
+                    warn!("Forcing new default keys in {}; proofs issued under the previous keys will not verify", key_dir.display());
+                } else {
+                    info!("No default keys in {}, running setup", key_dir.display());
+                }
+                let mut rng = SharedRng::default();
+                let (vote_system, proving_key, verifying_key) = match commitment {
+                    Some(commitment) => {
+                        let blank = CandidateVoteCircuit::blank(NUM_OPTIONS, default_election_id()).with_prf_commitment(commitment);
+                        VoteSystem::<Fr, P>::setup_for_circuit(blank, &mut rng)?
+                    }
+                    None => VoteSystem::<Fr, P>::setup(default_election_id(), &mut rng)?,
+                };
+                write_default_keys::<P>(key_dir, &config, &proving_key, &verifying_key)?;
+                write_prf_commitment(key_dir, commitment)?;
+                Self::from_parts(vote_system, proving_key, verifying_key, nullifiers, rng)
+            }
+        };
+        match prf_key {
+            Some(prf_key) => state.prf_key = Some(Arc::new(prf_key)),
+            None => {
+                let secret_key = load_or_init_voter_secret_key(key_dir, &mut state.rng.clone())?;
+                state = state.with_voter_secret_key(secret_key);
+            }
+        }
+        state.key_dir = Some(Arc::from(key_dir));
+        Ok(state)
+    }
+
+    // 使用指定的防重标识存储后端初始化应用状态，密钥生成失败时返回错误
+    pub fn try_with_nullifier_backend(nullifiers: N) -> Result<Self, VoteError> {
+        // 初始化日志
+        init_logger();
+        info!("Initializing application state");
+        
+        // 初始化投票系统，默认密钥属于默认选举（不带选举标识的请求）
+        let mut rng = SharedRng::default();
+        let (vote_system, proving_key, verifying_key) = VoteSystem::<Fr, P>::setup(default_election_id(), &mut rng)?;
+        
+        Ok(Self::from_parts(vote_system, proving_key, verifying_key, nullifiers, rng))
+    }
+    
+    // 由已有密钥（如可信设置仪式的产出）初始化应用状态，密钥不配对时返回 KeyMismatch
+    // 密钥须为默认选举生成，其他选举的密钥用 setup_election 生成
+    pub fn from_keys(proving_key: P::ProvingKey, verifying_key: P::VerifyingKey, nullifiers: N) -> Result<Self, VoteError> {
+        init_logger();
+        info!("Initializing application state from existing keys");
+        keys::check_consistency::<Fr, P>(&proving_key, &verifying_key)?;
+        Ok(Self::from_parts(VoteSystem::new(), proving_key, verifying_key, nullifiers, SharedRng::default()))
+    }
+    
+    // 由记录了电路配置摘要的密钥文件（见 fingerprint::encode_key_for_config）初始化应用状态，按 config 运行
+    // 两个密钥文件记录的配置都须与 config 一致，否则拒绝启动并返回 ConfigMismatch（不同配置的密钥生成的证明全部无法通过验证）；
+    // 没有记录配置的旧格式密钥文件同样拒绝，原始密钥用 from_keys 加载
+    pub fn from_key_files(pk_file: &[u8], vk_file: &[u8], config: CircuitConfig, nullifiers: N) -> Result<Self, VoteError> {
+        init_logger();
+        info!("Initializing application state from key files for {} candidates", config.num_candidates);
+        config.validate()?;
+        if config.allow_abstain || config.allow_spoiled {
+            return Err(VoteError::ConfigMismatch(
+                "default keys cannot use a circuit config that allows abstaining or spoiled ballots".to_string(),
+            ));
+        }
+        let expected = config.digest();
+        for (name, file) in [("proving key", pk_file), ("verifying key", vk_file)] {
+            match crate::fingerprint::key_config_digest::<Fr>(file)? {
+                Some(digest) if digest == expected => {}
+                Some(digest) => {
+                    error!("The {} was generated for circuit config {}, expected {}", name, hex::encode(digest), hex::encode(expected));
+                    return Err(VoteError::ConfigMismatch(format!(
+                        "{} was generated for circuit config {}, but the server is configured for {} candidates (config {})",
+                        name,
+                        hex::encode(digest),
+                        config.num_candidates,
+                        hex::encode(expected)
+                    )));
+                }
+                None => {
+                    error!("The {} file does not record its circuit config", name);
+                    return Err(VoteError::ConfigMismatch(format!("{} file does not record its circuit config", name)));
+                }
+            }
+        }
+        let proving_key = crate::fingerprint::decode_key::<Fr, P::ProvingKey>(pk_file)?;
+        let verifying_key = crate::fingerprint::decode_key::<Fr, P::VerifyingKey>(vk_file)?;
+        keys::check_consistency::<Fr, P>(&proving_key, &verifying_key)?;
+        
+        let mut state = Self::from_parts(VoteSystem::new(), proving_key, verifying_key, nullifiers, SharedRng::default());
+        state.num_candidates = config.num_candidates;
+        state.running_tally = Arc::new(RwLock::new(RunningTally::new(config.num_candidates)));
+        Ok(state)
+    }
+    
+    // 默认密钥对应的电路配置摘要（见 CircuitConfig::digest），从密钥文件加载时与文件头部记录的一致
+    pub fn config_digest(&self) -> [u8; 32] {
+        self.circuit_config().digest()
+    }
+    
+    // 使用指定的防重标识存储后端初始化应用状态，密钥生成失败时panic
+    pub fn with_nullifier_backend(nullifiers: N) -> Self {
+        Self::try_with_nullifier_backend(nullifiers).expect("Failed to setup vote system")
+    }
+    
+    // 由已初始化的投票系统和默认密钥构造应用状态（不检查密钥是否配对）
+    pub(crate) fn from_parts(
+        vote_system: VoteSystem<Fr, P>,
+        proving_key: P::ProvingKey,
+        verifying_key: P::VerifyingKey,
+        nullifiers: N,
+        rng: SharedRng,
+    ) -> Self {
+        // 初始化区块链提供者
+        let blockchain_providers = Arc::new(RwLock::new(HashMap::new()));
+        let vote_contracts = Arc::new(RwLock::new(HashMap::new()));
+        
+        // 配置默认区块链网络
+        Self::configure_default_networks(blockchain_providers.clone(), vote_contracts.clone());
+        let secret_key = Arc::new(PrfKey::generate(&mut rng.clone()));
+        
+        Self {
+            vote_system: Arc::new(vote_system),
+            default_keys: Arc::new(RwLock::new(DefaultKeys {
+                proving_key: Arc::new(proving_key),
+                verifying_key: Arc::new(verifying_key),
+                retiring: Vec::new(),
+            })),
+            blockchain_providers,
+            vote_contracts,
+            voted_users: Arc::new(RwLock::new(HashMap::new())),
+            elections: Arc::new(RwLock::new(HashMap::new())),
+            config_keys: Arc::new(RwLock::new(HashMap::new())),
+            tally_keys: Arc::new(RwLock::new(HashMap::new())),
+            nullifiers: Arc::new(RwLock::new(nullifiers)),
+            accepted_proofs: Arc::new(RwLock::new(HashSet::new())),
+            phases: Arc::new(RwLock::new(HashMap::new())),
+            commitments: Arc::new(RwLock::new(HashMap::new())),
+            ballots: Arc::new(RwLock::new(BallotStore::default())),
+            tally_cache: Arc::new(RwLock::new(HashMap::new())),
+            interim_results: false,
+            envelopes: Arc::new(RwLock::new(HashMap::new())),
+            running_tally: Arc::new(RwLock::new(RunningTally::default())),
+            running_votes: Arc::new(RwLock::new(HashMap::new())),
+            num_candidates: NUM_OPTIONS,
+            authority_token: None,
+            rng,
+            election_end: DateTime::<Utc>::MAX_UTC,
+            proving_timeout: DEFAULT_PROVING_TIMEOUT,
+            audit_log: Arc::new(RwLock::new(AuditLog::new())),
+            prf_key: None,
+            secret_key,
+            secret_key_pinned: false,
+            proof_sink: None,
+            jobs: None,
+            key_dir: None,
+            circuit_indexer: None,
+        }
+    }
+    
+    // 设置投票截止时间
+    pub fn with_election_end(mut self, election_end: DateTime<Utc>) -> Self {
+        self.election_end = election_end;
+        self
+    }
+    
+    // 设置单张选票证明生成的超时时间（默认 DEFAULT_PROVING_TIMEOUT）
+    // 超时或客户端断开后不再开始新的证明阶段，避免卡住的证明长时间占用CPU
+    pub fn with_proving_timeout(mut self, timeout: Duration) -> Self {
+        self.proving_timeout = timeout;
+        self
+    }
+    
+    // 设置服务端证明使用的证明池（并发证明数上限、排队上限，排队已满时返回 503 at_capacity）
+    // 须在克隆状态（如启动服务）之前调用
+    pub fn with_prover_pool(mut self, pool: Arc<ProverPool>) -> Self {
+        Arc::get_mut(&mut self.vote_system)
+            .expect("with_prover_pool must be called before the application state is cloned")
+            .set_prover_pool(pool);
+        self
+    }
+    
+    // 设置已受理证明的归档目标，证明在后台按重试策略写入，归档失败只记录日志，不影响投票响应
+    pub fn with_proof_sink(mut self, sink: Arc<dyn ProofSink>, policy: RetryPolicy) -> Self {
+        self.proof_sink = Some((sink, policy));
+        self
+    }
+    
+    // 服务端生成证明改为异步任务：/vote 登记任务后立即返回 202，最多 workers 个任务同时生成证明，
+    // 已结束任务的结果保留 ttl 时间，期间可通过 GET /vote/status/<任务标识> 查询；
+    // 未结束的任务达到 DEFAULT_MAX_PENDING_JOBS 个时新的投票返回 503 at_capacity
+    pub fn with_job_queue(self, workers: usize, ttl: Duration) -> Self {
+        self.with_bounded_job_queue(workers, DEFAULT_MAX_PENDING_JOBS, ttl)
+    }
+    
+    // 同 with_job_queue，未结束的任务（排队和正在证明的合计）不超过 max_pending 个
+    pub fn with_bounded_job_queue(mut self, workers: usize, max_pending: usize, ttl: Duration) -> Self {
+        self.jobs = Some(Arc::new(JobQueue::new(workers, ttl).with_max_pending(max_pending)));
+        self
+    }
+    
+    // 允许在选举结束前查询计票结果（GET /elections/{id}/tally），默认只在选举结束后公布
+    pub fn with_interim_results(mut self, enabled: bool) -> Self {
+        self.interim_results = enabled;
+        self
+    }
+    
+    // 注入随机数源（默认为 OsRng），之后生成的所有随机值都从该随机数源取
+    // 派生投票人秘密的密钥同时由新的随机数源重新生成（用 with_voter_secret_key 设置或从密钥目录加载的除外）
+    pub fn with_rng(mut self, rng: impl SecureRng + 'static) -> Self {
+        self.rng = SharedRng::new(rng);
+        if !self.secret_key_pinned {
+            self.secret_key = Arc::new(PrfKey::generate(&mut self.rng.clone()));
+        }
+        self
+    }
+    
+    // 设置未配置 PRF 密钥时派生投票人秘密的密钥（默认由随机数源生成）
+    // 重启后须沿用同一密钥，同一用户才会得到同一防重标识，持久化的防重标识才能拒绝重复投票
+    pub fn with_voter_secret_key(mut self, key: PrfKey<Fr>) -> Self {
+        self.secret_key = Arc::new(key);
+        self.secret_key_pinned = true;
+        self
+    }
+    
+    // 设置计票方令牌，导出接口要求请求携带 `Authorization: Bearer <令牌>`
+    pub fn with_authority_token(mut self, token: impl Into<String>) -> Self {
+        self.authority_token = Some(Arc::from(token.into()));
+        self
+    }
+    
+    // 请求头是否携带正确的计票方令牌（比较摘要，避免逐字节比较泄露时间信息）
+    fn is_authority(&self, authorization: Option<&str>) -> bool {
+        match (&self.authority_token, authorization.and_then(|h| h.strip_prefix("Bearer "))) {
+            (Some(expected), Some(token)) => blake3::hash(expected.as_bytes()) == blake3::hash(token.as_bytes()),
+            _ => false,
+        }
+    }
+    
+    // PRF 密钥承诺（未设置 PRF 密钥时为 None），可以公开
+    pub fn prf_key_commitment(&self) -> Option<Fr> {
+        self.prf_key.as_ref().map(|key| key.commitment())
+    }
+    
+    // 为本状态生成密钥的空电路绑定 PRF 密钥承诺（未设置 PRF 密钥时原样返回）
+    fn bind_prf(&self, blank: CandidateVoteCircuit<Fr>) -> CandidateVoteCircuit<Fr> {
+        match self.prf_key_commitment() {
+            Some(commitment) => blank.with_prf_commitment(commitment),
+            None => blank,
+        }
+    }
+    
+    // 默认选举的当前证明密钥和验证密钥
+    async fn current_keys(&self) -> (Arc<P::ProvingKey>, Arc<P::VerifyingKey>) {
+        let keys = self.default_keys.read().await;
+        (keys.proving_key.clone(), keys.verifying_key.clone())
+    }
+    
+    // 默认选举此刻接受的验证密钥，当前密钥在前，其后为过渡期内的旧验证密钥
+    pub async fn active_verifying_keys(&self) -> Vec<Arc<P::VerifyingKey>> {
+        self.default_keys.read().await.active(Utc::now())
+    }
+    
+    // 轮换默认选举的密钥（例如怀疑密钥泄露时）：生成新密钥作为当前密钥，新选票改用新密钥证明；
+    // 旧验证密钥在新密钥启用后的 transition 时间内仍被接受，之后停用。返回新的验证密钥及旧密钥的停用时间
+    // 导出包只用当前验证密钥复核选票，过渡期结束前不应导出
+    pub async fn rotate_keys(&self, transition: Duration) -> Result<(Arc<P::VerifyingKey>, DateTime<Utc>), VoteError> {
+        let transition = chrono::Duration::from_std(transition)
+            .map_err(|e| VoteError::InvalidBallot(format!("invalid transition window: {}", e)))?;
+    
+        // 密钥生成计算量较大，放到阻塞线程池执行
+        let blank = self.bind_prf(CandidateVoteCircuit::blank(self.num_candidates, default_election_id()));
+        let mut rng = self.rng.clone();
+        // 从密钥目录启动的状态同时覆盖密钥文件，重启后加载轮换后的密钥（过渡期内的旧密钥不保存）
+        let key_dir = self.key_dir.clone();
+        let config = self.circuit_config();
+        let (proving_key, verifying_key) = tokio::task::spawn_blocking(move || {
+            let (_, proving_key, verifying_key) = VoteSystem::<Fr, P>::setup_for_circuit(blank, &mut rng)?;
+            if let Some(key_dir) = key_dir {
+                write_default_keys::<P>(&key_dir, &config, &proving_key, &verifying_key)?;
+            }
+            Ok((proving_key, verifying_key))
+        })
+        .await
+        .map_err(|e| VoteError::Internal(format!("{:?}", e)))
+        .and_then(|r| r)?;
+        let verifying_key = Arc::new(verifying_key);
+    
+        // 过渡期从新密钥启用时算起，已停用的旧密钥顺带清除
+        let now = Utc::now();
+        let retire_at = now + transition;
+        let mut keys = self.default_keys.write().await;
+        keys.retiring.retain(|(_, at)| *at > now);
+        let retired = std::mem::replace(&mut keys.verifying_key, verifying_key.clone());
+        keys.proving_key = Arc::new(proving_key);
+        info!(
+            "Rotated default keys to {}, verifying key {} retires at {}",
+            hex::encode(vk_fingerprint::<Fr, P>(&verifying_key)),
+            hex::encode(vk_fingerprint::<Fr, P>(&retired)),
+            retire_at
+        );
+        keys.retiring.push((retired, retire_at));
+        Ok((verifying_key, retire_at))
+    }
+    
+    // 选举当前所处的阶段
+    pub async fn phase(&self, election_id: &str) -> ElectionPhase {
+        self.phases.read().await.get(election_id).copied().unwrap_or(ElectionPhase::Commit)
+    }
+    
+    // 将选举推进到下一阶段，已关闭的选举返回 WrongPhase
+    pub async fn advance_phase(&self, election_id: &str) -> Result<ElectionPhase, VoteError> {
+        let mut phases = self.phases.write().await;
+        let current = phases.get(election_id).copied().unwrap_or(ElectionPhase::Commit);
+        let next = current.next().ok_or_else(|| {
+            VoteError::WrongPhase(format!("election {} is already closed", election_id))
+        })?;
+        phases.insert(election_id.to_string(), next);
+        info!("Election {} advanced to {:?} phase", election_id, next);
+        Ok(next)
+    }
+    
+    // 创建纳入生命周期管理的选举：按选举参数生成密钥，选举处于 Created 状态，开放后才接受选票
+    // 已创建的选举返回 WrongPhase，已有密钥但未纳入生命周期管理的选举按 setup_election_with_params 的规则处理
+    pub async fn create_election(
+        &self,
+        election_id: &str,
+        params: ElectionParams,
+    ) -> Result<Arc<P::VerifyingKey>, VoteError> {
+        if let Some(state) = self.election_state(election_id).await {
+            return Err(VoteError::WrongPhase(format!("election {} already exists ({:?})", election_id, state)));
+        }
+        let verifying_key = self.setup_election_with_params(election_id, params).await?;
+        let mut elections = self.elections.write().await;
+        let election = elections
+            .get_mut(election_id)
+            .ok_or_else(|| VoteError::UnknownElection(election_id.to_string()))?;
+        if let Some(state) = election.state {
+            return Err(VoteError::WrongPhase(format!("election {} already exists ({:?})", election_id, state)));
+        }
+        election.state = Some(ElectionState::Created);
+        info!("Created election {}", election_id);
+        Ok(verifying_key)
+    }
+    
+    // 登记一批投票人凭证承诺，返回本批第一个承诺的位置以及登记后的树根和人数
+    // 只有处于 Created 状态的选举接受登记，开放后名册固定；重复的承诺整批拒绝（DuplicateCommitment）
+    pub async fn register_voters(&self, election_id: &str, commitments: &[Fr]) -> Result<(u64, Fr, u64), VoteError> {
+        let mut elections = self.elections.write().await;
+        let election = elections
+            .get_mut(election_id)
+            .ok_or_else(|| VoteError::UnknownElection(election_id.to_string()))?;
+        match election.state {
+            Some(ElectionState::Created) => {}
+            Some(state) => {
+                return Err(VoteError::WrongPhase(format!(
+                    "cannot register voters for election {} in the {:?} state",
+                    election_id, state
+                )))
+            }
+            None => {
+                return Err(VoteError::WrongPhase(format!(
+                    "election {} is not lifecycle-managed, voter registration requires create_election",
+                    election_id
+                )))
+            }
+        }
+        let first = election.voters.register(commitments)?;
+        let tree = election.voters.tree();
+        info!("Registered {} voters for election {}, roll size {}", commitments.len(), election_id, tree.len());
+        Ok((first, tree.root(), tree.len()))
+    }
+    
+    // 投票人名册树的根与已登记人数
+    pub async fn voter_root(&self, election_id: &str) -> Result<(Fr, u64), VoteError> {
+        let elections = self.elections.read().await;
+        let election = elections
+            .get(election_id)
+            .ok_or_else(|| VoteError::UnknownElection(election_id.to_string()))?;
+        Ok((election.voters.tree().root(), election.voters.tree().len()))
+    }
+    
+    // 选举的生命周期状态，未纳入生命周期管理的选举为 None
+    pub async fn election_state(&self, election_id: &str) -> Option<ElectionState> {
+        self.elections.read().await.get(election_id).and_then(|election| election.state)
+    }
+    
+    // 选举标识列表
+    pub async fn election_ids(&self) -> Vec<ElectionId> {
+        let mut ids: Vec<ElectionId> = self.elections.read().await.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+    
+    // 开放投票
+    pub async fn open_election(&self, election_id: &str) -> Result<ElectionState, VoteError> {
+        self.transition(election_id, ElectionState::open).await
+    }
+    
+    // 结束投票，可重复调用
+    pub async fn close_election(&self, election_id: &str) -> Result<ElectionState, VoteError> {
+        self.transition(election_id, ElectionState::close).await
+    }
+    
+    // 推进选举的生命周期状态，未纳入生命周期管理的选举返回 UnknownElection
+    async fn transition(
+        &self,
+        election_id: &str,
+        next: fn(ElectionState) -> Result<ElectionState, VoteError>,
+    ) -> Result<ElectionState, VoteError> {
+        let mut elections = self.elections.write().await;
+        let state = elections
+            .get_mut(election_id)
+            .and_then(|election| election.state.as_mut())
+            .ok_or_else(|| VoteError::UnknownElection(election_id.to_string()))?;
+        let previous = *state;
+        *state = next(previous)?;
+        if *state != previous {
+            info!("Election {} moved from {:?} to {:?}", election_id, previous, *state);
+        }
+        Ok(*state)
+    }
+    
+    // 按选票标识（/vote 响应中的 ballot_id）查询已受理选票的证明信封
+    pub async fn recorded_envelope(&self, ballot_id: &str) -> Option<ProofEnvelopeJson> {
+        self.envelopes.read().await.get(ballot_id).cloned()
+    }
+    
+    // 运行计票中各候选人的当前票数（只覆盖默认密钥下的选票，与导出包一致）
+    pub async fn running_counts(&self) -> Vec<u64> {
+        self.running_tally.read().await.counts().to_vec()
+    }
+    
+    // 公开运行计票结果及各候选人承诺之和的打开证明，可用 running_tally_params 给出的参数验证
+    pub async fn finalize_running_tally(&self) -> FinalTally<TallyGroup> {
+        self.running_tally.read().await.finalize(&mut self.rng.clone())
+    }
+    
+    // 运行计票使用的承诺参数
+    pub async fn running_tally_params(&self) -> PedersenParams<TallyGroup> {
+        self.running_tally.read().await.params().clone()
+    }
+    
+    // 候选人数量
+    pub fn num_candidates(&self) -> usize {
+        self.num_candidates
+    }
+    
+    // 默认密钥对应的电路配置
+    fn circuit_config(&self) -> CircuitConfig {
+        CircuitConfig::with_candidates(self.num_candidates)
+    }
+    
+    // 为选举生成密钥（按当前的候选人数量），之后该选举的选票使用这套密钥证明和验证
+    // 已有密钥的选举保持不变，返回已有的验证密钥
+    pub async fn setup_election(&self, election_id: &str) -> Result<Arc<P::VerifyingKey>, VoteError> {
+        self.setup_election_with_params(election_id, ElectionParams::from(self.circuit_config())).await
+    }
+    
+    // 按选举参数为选举生成密钥，参数摘要绑定到密钥，参数可通过 GET /elections/<选举标识>/params 查询
+    // 已按相同参数生成密钥的选举返回已有的验证密钥，已有其他密钥的选举返回 KeyMismatch
+    pub async fn setup_election_with_params(
+        &self,
+        election_id: &str,
+        params: ElectionParams,
+    ) -> Result<Arc<P::VerifyingKey>, VoteError> {
+        if let Some(existing) = Self::existing_election_key(&*self.elections.read().await, election_id, &params)? {
+            return Ok(existing);
+        }
+        
+        // 密钥生成计算量较大，放到阻塞线程池执行
+        params.validate().map_err(|e| VoteError::Setup(e.to_string().into()))?;
+        let election = election_id_to_field(election_id);
+        let blank = self.bind_prf(CandidateVoteCircuit::for_params(&params, election));
+        let mut rng = self.rng.clone();
+        let indexer = self.circuit_indexer.clone();
+        let (proving_key, verifying_key) = tokio::task::spawn_blocking(move || match indexer {
+            Some(index) => index(blank),
+            None => VoteSystem::<Fr, P>::setup_for_circuit(blank, &mut rng).map(|(_, pk, vk)| (pk, vk)),
+        })
+        .await
+        .map_err(|e| VoteError::Internal(format!("{:?}", e)))
+        .and_then(|r| r)?;
+        // 并发请求同一选举时保留先写入的密钥
+        let nullifiers = self.open_election_nullifiers(election_id).await?;
+        let mut elections = self.elections.write().await;
+        if let Some(existing) = Self::existing_election_key(&elections, election_id, &params)? {
+            return Ok(existing);
+        }
+        let verifying_key = Arc::new(verifying_key);
+        elections.insert(
+            election_id.to_string(),
+            ElectionHandle::new(params.config(), proving_key, verifying_key.clone(), Some(params), nullifiers),
+        );
+        info!("Generated keys for election {} with params {}", election_id, hex::encode(params.digest()));
+        Ok(verifying_key)
+    }
+    
+    // 选举已按相同参数生成密钥时返回其验证密钥，已有其他密钥时返回 KeyMismatch
+    fn existing_election_key(
+        elections: &HashMap<ElectionId, ElectionHandle<P, N>>,
+        election_id: &str,
+        params: &ElectionParams,
+    ) -> Result<Option<Arc<P::VerifyingKey>>, VoteError> {
+        let Some(election) = elections.get(election_id) else {
+            return Ok(None);
+        };
+        match &election.params {
+            Some(existing) if existing == params => Ok(Some(election.verifying_key.clone())),
+            _ => Err(VoteError::KeyMismatch(format!(
+                "election {} already has keys for different parameters",
+                election_id
+            ))),
+        }
+    }
+    
+    // 登记独立可信设置为 num_ballots 张计入选票生成并公布的计票电路密钥（见 tally::setup_tally），导出时按计入选票数量选用
+    // 计票方不自行生成计票密钥：持有设置随机数即可伪造计票证明；密钥不配对或不是为该数量生成时返回 KeyMismatch
+    pub async fn add_tally_keys(
+        &self,
+        num_ballots: usize,
+        proving_key: P::ProvingKey,
+        verifying_key: P::VerifyingKey,
+    ) -> Result<(), VoteError> {
+        keys::check_consistency::<Fr, P>(&proving_key, &verifying_key)?;
+        check_tally_keys::<Fr, P>(&verifying_key, num_ballots)?;
+        self.tally_keys.write().await.insert(num_ballots, (Arc::new(proving_key), Arc::new(verifying_key)));
+        info!("Registered tally keys for {} ballots", num_ballots);
+        Ok(())
+    }
+    
+    // 登记选举的已有密钥（如为该选举举行的可信设置仪式的产出），密钥不配对或该选举已有密钥时返回 KeyMismatch
+    pub async fn add_election_keys(
+        &self,
+        election_id: &str,
+        proving_key: P::ProvingKey,
+        verifying_key: P::VerifyingKey,
+    ) -> Result<(), VoteError> {
+        keys::check_consistency::<Fr, P>(&proving_key, &verifying_key)?;
+        let nullifiers = self.open_election_nullifiers(election_id).await?;
+        let mut elections = self.elections.write().await;
+        if elections.contains_key(election_id) {
+            return Err(VoteError::KeyMismatch(format!("election {} already has keys", election_id)));
+        }
+        elections.insert(
+            election_id.to_string(),
+            ElectionHandle::new(self.circuit_config(), proving_key, Arc::new(verifying_key), None, nullifiers),
+        );
+        info!("Registered existing keys for election {}", election_id);
+        Ok(())
+    }
+    
+    // 由默认选举的防重标识后端按选举标识打开该选举的防重标识存储
+    async fn open_election_nullifiers(&self, election_id: &str) -> Result<N, VoteError> {
+        self.nullifiers.read().await.open_election(election_id).map_err(|e| {
+            error!("Failed to open nullifier store for election {}: {}", election_id, e);
+            VoteError::from(e)
+        })
+    }
+    
+    // 当前是否已过投票截止时间
+    fn voting_closed(&self) -> bool {
+        Utc::now() > self.election_end
+    }
+    
+    // 配置默认区块链网络
+    fn configure_default_networks(
+        providers: Arc<RwLock<HashMap<String, Arc<Provider<Http>>>>>,
+        contracts: Arc<RwLock<HashMap<String, Address>>>
+    ) {
+        tokio::spawn(async move {
+            let mut providers_write = providers.write().await;
+            let mut contracts_write = contracts.write().await;
+            
+            // 配置本地网络
+            if let Ok(provider) = Provider::try_from("http://127.0.0.1:8545") {
+                providers_write.insert("localhost".to_string(), Arc::new(provider));
+                // 这里应该从部署脚本获取合约地址
+                // 暂时使用占位地址
+                let contract_address: Address = "0x0000000000000000000000000000000000000000".parse().unwrap();
+                contracts_write.insert("localhost".to_string(), contract_address);
+                info!("Configured localhost network");
+            }
+            
+            // 可以添加其他默认网络配置
+        });
+    }
+}
+
+impl<P: UniversalProofSystem<Fr>> AppState<P> {
+    // 以通用SRS初始化应用状态，返回的SRS用于之后为新选举索引电路
+    pub fn new_universal(max_constraints: usize) -> (Self, Arc<P::UniversalSrs>) {
+        init_logger();
+        info!("Initializing application state with universal setup");
+        
+        let mut rng = SharedRng::default();
+        let (vote_system, srs) = VoteSystem::<Fr, P>::setup_universal(max_constraints, &mut rng)
+            .expect("Failed to generate universal SRS");
+        let (proving_key, verifying_key) = vote_system
+            .index_circuit(&srs, VoteCircuit::blank(default_election_id()))
+            .expect("Failed to index vote circuit");
+        
+        let srs = Arc::new(srs);
+        let state = Self::from_parts(vote_system, proving_key, verifying_key, MemoryBackend::default(), rng);
+        (state.with_universal_srs(srs.clone()), srs)
+    }
+}
+
+impl<P: UniversalProofSystem<Fr>, N: NullifierBackend> AppState<P, N> {
+    // 之后创建的选举（create_election、setup_election、POST /elections）用通用SRS索引电路，不再单独生成密钥
+    pub fn with_universal_srs(mut self, srs: Arc<P::UniversalSrs>) -> Self {
+        let vote_system = self.vote_system.clone();
+        self.circuit_indexer = Some(Arc::new(move |blank| vote_system.index_circuit(&srs, blank)));
+        self
+    }
+}
+
+// 使用模拟证明后端的应用状态（test-mock 特性，本 crate 的单元测试总是可用）
+// 密钥生成和证明都是常数级耗时，供集成测试和压力测试跑完整的Web流程；证明可任意伪造，不得用于真实选举
+#[cfg(any(test, feature = "test-mock"))]
+impl AppState<crate::MockSNARK> {
+    pub fn new_mock() -> Self {
+        init_logger();
+        warn!("Initializing application state with the mock proof system; proofs provide NO security");
+        Self::new()
+    }
+}
+
+// 投票路由
+pub fn vote_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("vote")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<VoteRequest>())
+        .and_then(handle_vote)
+}
+
+// 证明任务状态查询路由
+pub fn vote_status_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("vote" / "status" / String)
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(handle_vote_status)
+}
+
+// 批量投票路由
+pub fn vote_batch_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("vote" / "batch")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<Vec<VoteRequest>>())
+        .and_then(handle_vote_batch)
+}
+
+// 创建选举路由（仅计票方可用），新建的选举处于 Created 状态；通用可信设置的状态用通用SRS索引电路，无需新的仪式
+pub fn create_election_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json::<CreateElectionRequest>())
+        .and_then(handle_create_lifecycle_election)
+}
+
+// 开放投票路由（仅计票方可用）
+pub fn open_election_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "open")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(|election_id: String, state: AppState<P, N>, authorization: Option<String>| async move {
+            handle_election_transition(election_id, state, authorization, ElectionState::open).await
+        })
+}
+
+// 结束投票路由（仅计票方可用，可重复调用）
+pub fn close_election_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "close")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(|election_id: String, state: AppState<P, N>, authorization: Option<String>| async move {
+            handle_election_transition(election_id, state, authorization, ElectionState::close).await
+        })
+}
+
+// 投票人登记路由（仅计票方可用，选举开放前）
+pub fn register_voters_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "voters")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(handle_register_voters)
+}
+
+// 名册树根查询路由
+pub fn merkle_root_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "merkle-root")
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(handle_merkle_root)
+}
+
+// 选举参数查询路由
+pub fn election_params_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "params")
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(handle_election_params)
+}
+
+// 选举计票路由（选举结束后公布，设置 with_interim_results 时可随时查询）
+pub fn tally_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "tally")
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(handle_tally)
+}
+
+// 选举投票路由，选举标识取自路径，请求体中的 election_id 可省略
+pub fn election_vote_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "vote")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<VoteRequest>())
+        .and_then(|election_id: String, state: AppState<P, N>, mut req: VoteRequest| async move {
+            match path_election(&state, election_id, req.election_id.take()).await {
+                Ok(election_id) => {
+                    req.election_id = Some(election_id);
+                    handle_vote(state, req).await.map(warp::Reply::into_response)
+                }
+                Err(e) => Ok(error_reply(&e)),
+            }
+        })
+}
+
+// 选举验证路由，按路径中选举的验证密钥验证
+pub fn election_verify_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "verify")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<VerifyRequest>())
+        .and_then(|election_id: String, state: AppState<P, N>, mut req: VerifyRequest| async move {
+            match path_election(&state, election_id, req.election_id.take()).await {
+                Ok(election_id) => {
+                    req.election_id = Some(election_id);
+                    handle_verify(state, req).await
+                }
+                Err(e) => Ok(error_reply(&e)),
+            }
+        })
+}
+
+// 选举验证密钥路由
+pub fn election_vk_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("elections" / String / "vk")
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(|election_id: String, state: AppState<P, N>| async move {
+            handle_vk(state, VerifyingKeyQuery { election_id: Some(election_id) }).await
+        })
+}
+
+// 按电路配置生成密钥路由（仅计票方可用）
+pub fn setup_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("setup")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json::<CircuitConfig>())
+        .and_then(handle_setup)
+}
+
+// 承诺路由
+pub fn commit_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("commit")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<CommitRequest>())
+        .and_then(handle_commit)
+}
+
+// 揭示路由
+pub fn reveal_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("reveal")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<RevealRequest>())
+        .and_then(handle_reveal)
+}
+
+// 验证路由
+pub fn verify_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("verify")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<VerifyRequest>())
+        .and_then(handle_verify)
+}
+
+// 批量验证路由
+pub fn verify_batch_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("verify" / "batch")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::body::json::<Vec<BatchVerifyEntry>>())
+        .and_then(handle_verify_batch)
+}
+
+// 验证密钥路由
+pub fn vk_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("vk")
+        .and(warp::get())
+        .and(with_state(state))
+        .and(warp::query::<VerifyingKeyQuery>())
+        .and_then(handle_vk)
+}
+
+// 轮换默认密钥路由（仅计票方可用）
+pub fn rotate_vk_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("vk" / "rotate")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json::<RotateKeyRequest>())
+        .and_then(handle_rotate_vk)
+}
+
+// 结果路由（投票截止后公布各候选人票数）
+pub fn results_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("results")
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(handle_results)
+}
+
+// 导出选举结果路由（仅计票方可用）
+pub fn export_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("export")
+        .and(warp::post())
+        .and(with_state(state))
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(handle_export)
+}
+
+// 审计日志路由（公开，供外部复核）
+pub fn audit_route<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("audit")
+        .and(warp::get())
+        .and(with_state(state))
+        .and_then(handle_audit)
+}
+
+// 启动Web服务
+pub async fn serve(addr: impl Into<std::net::SocketAddr>) -> Result<(), VoteError> {
+    // 初始化tracing订阅器，日志级别由RUST_LOG控制
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .try_init();
+    
+    // 设置了 ZKP_VOTE_PRF_KEY 时防重标识由服务端 PRF 密钥派生；
+    // 设置了 ZKP_VOTE_KEY_DIR 时从该目录加载默认密钥、派生密钥和防重标识，重启后之前签发的证明仍可验证，已投票的用户不能再投
+    let key_dir = std::env::var_os(KEY_DIR_ENV).map(std::path::PathBuf::from);
+    let force_new_keys = std::env::var(FORCE_NEW_KEYS_ENV).is_ok_and(|v| v == "1");
+    let addr = addr.into();
+    match (PrfKey::from_env()?, key_dir) {
+        (Some(prf_key), Some(key_dir)) => {
+            let state = AppState::<Groth16<DefaultPairing>, FileBackend>::load_or_init_with_prf_key(&key_dir, force_new_keys, prf_key);
+            serve_state(state, addr).await
+        }
+        (Some(prf_key), None) => serve_state(AppState::<Groth16<DefaultPairing>>::try_with_prf_key(prf_key), addr).await,
+        (None, Some(key_dir)) => {
+            let state = AppState::<Groth16<DefaultPairing>, FileBackend>::load_or_init_with(&key_dir, force_new_keys);
+            serve_state(state, addr).await
+        }
+        (None, None) => {
+            warn!("{} is not set; keys and nullifiers are kept in memory and voters can vote again after a restart", KEY_DIR_ENV);
+            serve_state(AppState::<Groth16<DefaultPairing>>::try_new(), addr).await
+        }
+    }
+}
+
+// 用初始化好的应用状态启动Web服务
+async fn serve_state<N: NullifierBackend>(
+    state: Result<AppState<Groth16<DefaultPairing>, N>, VoteError>,
+    addr: std::net::SocketAddr,
+) -> Result<(), VoteError> {
+    let state = state.map_err(|e| {
+        error!("Failed to initialize application state: {}", e);
+        e
+    })?;
+    // 未设置计票方令牌时导出、/setup、密钥轮换和选举管理接口一律返回 401
+    let state = match std::env::var(AUTHORITY_TOKEN_ENV) {
+        Ok(token) if !token.trim().is_empty() => state.with_authority_token(token.trim()),
+        _ => {
+            warn!(
+                "{} is not set; /export, /setup, /vk/rotate and election management requests will be rejected with 401",
+                AUTHORITY_TOKEN_ENV
+            );
+            state
+        }
+    };
+    // 截止时间之后拒绝新选票并公布 /results，格式错误时拒绝启动
+    let state = match std::env::var(ELECTION_END_ENV) {
+        Ok(value) => {
+            let election_end = DateTime::parse_from_rfc3339(value.trim()).map_err(|e| {
+                error!("Invalid {}: {}", ELECTION_END_ENV, e);
+                VoteError::Setup(format!("{} must be an RFC 3339 timestamp: {}", ELECTION_END_ENV, e).into())
+            })?;
+            info!("Voting closes at {}", election_end);
+            state.with_election_end(election_end.with_timezone(&Utc))
+        }
+        Err(_) => {
+            warn!("{} is not set; voting never closes and /results will not be published", ELECTION_END_ENV);
+            state
+        }
+    };
+    // 证明在后台生成，/vote 不必等到证明完成
+    let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let state = state.with_job_queue(workers, DEFAULT_JOB_TTL);
+    let routes = vote_route(state.clone())
+        .or(vote_status_route(state.clone()))
+        .or(vote_batch_route(state.clone()))
+        .or(commit_route(state.clone()))
+        .or(reveal_route(state.clone()))
+        .or(verify_route(state.clone()))
+        .or(verify_batch_route(state.clone()))
+        .or(vk_route(state.clone()))
+        .or(rotate_vk_route(state.clone()))
+        .or(election_params_route(state.clone()))
+        .or(register_voters_route(state.clone()))
+        .or(merkle_root_route(state.clone()))
+        .or(tally_route(state.clone()))
+        .or(election_vote_route(state.clone()))
+        .or(election_verify_route(state.clone()))
+        .or(election_vk_route(state.clone()))
+        .or(create_election_route(state.clone()))
+        .or(open_election_route(state.clone()))
+        .or(close_election_route(state.clone()))
+        .or(export_route(state.clone()))
+        .or(setup_route(state.clone()))
+        .or(audit_route(state.clone()))
+        .or(results_route(state))
+        .recover(handle_rejection);
+    
+    info!("Starting web server on {}", addr);
+    warp::serve(routes).run(addr).await;
+    Ok(())
+}
+
+// 结果响应
+#[derive(serde::Serialize, Debug)]
+struct ResultsResponse {
+    success: bool,
+    num_candidates: usize,
+    counts: Vec<u64>, // 各候选人票数（只覆盖默认密钥下的选票，与运行计票一致）
+    total_votes: u64,
+}
+
+// 选举计票响应
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TallyResponse {
+    pub success: bool,
+    pub election_id: String,
+    pub counts: Vec<u64>,            // 各候选人票数
+    pub abstained: u64,              // 弃权票数
+    pub spoiled: u64,                // 废票数
+    pub turnout: u64,                // 投票率：计入的选票数，包括弃权票和废票
+    pub accepted: usize,             // 计入的选票数
+    pub duplicate_nullifiers: usize, // 因防重标识重复未计入的选票数
+    pub superseded: usize,           // 被同一投票人序号更大的选票取代的选票数
+    pub rejected: usize,             // 未计入的选票总数（含防重标识重复和被取代的选票）
+    pub tallied_at: DateTime<Utc>,   // 计票时间，结果来自缓存时为缓存生成的时间
+}
+
+// 状态注入
+fn with_state<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> impl Filter<Extract = (AppState<P, N>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || state.clone())
+}
+
+// 计算用户ID的哈希（用于日志关联，不记录原始用户ID）
+fn hash_user_id(user_id: &str) -> String {
+    let hash = blake3::hash(user_id.as_bytes()).to_hex();
+    hash[..16].to_string()
+}
+
+// 处理投票请求
+async fn handle_vote<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    req: VoteRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // 注意：span中不记录投票值和防重标识
+    let span = info_span!(
+        "handle_vote",
+        user_id_hash = %hash_user_id(&req.user_id),
+        election_id = ?req.election_id,
+        duration_ms = field::Empty,
+    );
+    let start = std::time::Instant::now();
+    let result = process_vote(state, req).instrument(span.clone()).await;
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    result
+}
+
+// 投票请求处理逻辑
+async fn process_vote<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    req: VoteRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    debug!("Received vote request on chain: {}", req.chain);
+    
+    if state.voting_closed() {
+        warn!("Rejected vote submitted after the deadline");
+        return Ok(voting_closed_reply());
+    }
+    
+    // 客户端已生成证明：不登记用户、不派生防重标识，只验证并记录
+    if req.nullifier.is_some() || req.commitment.is_some() || req.proof.is_some() {
+        let result = record_client_ballot(&state, &req).await;
+        let nullifier = req.nullifier.as_deref().and_then(|n| fr_from_hex::<Fr>(n).ok());
+        let outcome = result.as_ref().map_or_else(|e| e.code(), |_| "valid");
+        record_audit(&state, AuditAction::ClientBallot, req.election_id.as_deref(), nullifier, outcome).await;
+        return match result {
+            Ok((proof, public_inputs)) => {
+                let response = vote_submitted(&state, &req, &proof, &public_inputs, None).await;
+                Ok(warp::reply::json(&response).into_response())
+            }
+            Err(e) => {
+                warn!("Rejected client-proved ballot: {}", e);
+                Ok(error_reply(&e))
+            }
+        };
+    }
+    
+    if let Err(e) = validate_user_id(&req.user_id) {
+        warn!("Rejected vote: {}", e);
+        return Ok(error_reply(&e));
+    }
+    
+    // 防止重复投票：同一用户同时到达的请求只有一个能登记，其余返回 409 duplicate_nullifier
+    let Some(previous) = claim_voter(&state, &req).await else {
+        warn!("Rejected duplicate vote");
+        return Ok(error_reply(&VoteError::DuplicateNullifier));
+    };
+    
+    // 启用任务队列时在后台生成证明，先返回任务标识
+    if let Some(jobs) = state.jobs.clone() {
+        let mut job_id = [0u8; 16];
+        state.rng.clone().fill_bytes(&mut job_id);
+        let job_id = hex::encode(job_id);
+        let (job_state, job_req) = (state.clone(), req.clone());
+        let job = async move {
+            // 证明过程崩溃时同样恢复登记前的序号，投票人可以重试
+            let proved = futures::FutureExt::catch_unwind(std::panic::AssertUnwindSafe(prove_ballot(&job_state, &job_req, previous)));
+            match proved.await {
+                Ok(response) => {
+                    let response = response?;
+                    Ok((response.ballot_id, response.envelope))
+                }
+                Err(panic) => {
+                    release_voter(&job_state, &job_req, previous).await;
+                    std::panic::resume_unwind(panic)
+                }
+            }
+        };
+        if let Err(e) = jobs.spawn(job_id.clone(), job.instrument(tracing::Span::current())).await {
+            // 队列已满：释放投票资格，客户端稍后重试
+            release_voter(&state, &req, previous).await;
+            return Ok(error_reply(&e));
+        }
+        info!("Queued proving job {}", job_id);
+        let response = JobStatusResponse { success: true, job_id, status: JobStatus::Queued };
+        return Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::ACCEPTED).into_response());
+    }
+    
+    match prove_ballot(&state, &req, previous).await {
+        Ok(response) => Ok(warp::reply::json(&response).into_response()),
+        Err(e) => Ok(error_reply(&e)),
+    }
+}
+
+// 为已登记的投票人生成证明并提交选票，previous 为登记前该投票人的选票序号
+async fn prove_ballot<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+    previous: Option<u64>,
+) -> Result<VoteResponse, VoteError> {
+    let (proof, public_inputs, commitment) = match generate_ballot_proof(state, req, None).await {
+        Ok(ballot) => ballot,
+        Err(e) => {
+            // 证明失败时恢复登记前的序号，允许用户重试
+            release_voter(state, req, previous).await;
+            return Err(e);
+        }
+    };
+    Ok(vote_submitted(state, req, &proof, &public_inputs, Some(commitment)).await)
+}
+
+// 处理证明任务状态查询
+async fn handle_vote_status<P: ProofSystem<Fr>, N: NullifierBackend>(
+    job_id: String,
+    state: AppState<P, N>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let status = match &state.jobs {
+        Some(jobs) => jobs.status(&job_id).await,
+        None => Err(VoteError::UnknownJob(job_id.clone())),
+    };
+    match status {
+        Ok(status) => Ok(warp::reply::json(&JobStatusResponse { success: true, job_id, status }).into_response()),
+        Err(e) => Ok(error_reply(&e)),
+    }
+}
+
+// 保存选票的证明信封，将已验证的选票提交到区块链并生成响应
+async fn vote_submitted<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+    proof: &P::Proof,
+    public_inputs: &BallotPublicInputs<Fr>,
+    commitment: Option<Fr>,
+) -> VoteResponse {
+    // 先保存信封再响应，投票人拿到选票标识时即可查询
+    let ballot_id = hex::encode(proof_digest::<P>(proof));
+    let envelope = ballot_envelope(state, req, public_inputs, proof).await;
+    if let Some(envelope) = &envelope {
+        state.envelopes.write().await.insert(ballot_id.clone(), envelope.clone());
+    }
+    
+    // 证明已通过验证，在后台归档，不等待归档结果
+    if let Some((proof_sink, policy)) = &state.proof_sink {
+        match P::serialize_proof(proof) {
+            Ok(bytes) => sink::spawn_archive(proof_sink.clone(), *policy, ballot_id.clone(), bytes),
+            Err(e) => error!("Failed to encode proof {} for archiving: {}", ballot_id, e),
+        }
+    }
+    
+    // 尝试提交到区块链
+    let transaction_result = submit_to_blockchain(state, req).await;
+    let vk_fingerprint = ballot_vk_fingerprint(state, req).await;
+    let nullifier = Some(fr_to_hex(public_inputs.nullifier));
+    let commitment = commitment.map(fr_to_decimal);
+    let public_inputs = Some(public_inputs.to_vec().into_iter().map(fr_to_decimal).collect::<Vec<_>>());
+    let ballot_id = Some(ballot_id);
+    
+    match transaction_result {
+        Ok((tx_hash, gas_cost)) => {
+            info!("Vote submitted successfully, transaction hash: {:?}", tx_hash);
+            VoteResponse {
+                success: true,
+                message: "Vote submitted successfully and transaction sent to blockchain.".to_string(),
+                gas_cost: Some(gas_cost),
+                transaction_hash: Some(tx_hash),
+                vk_fingerprint,
+                public_inputs,
+                ballot_id,
+                nullifier,
+                commitment,
+                envelope,
+            }
+        }
+        Err(e) => {
+            // 即使区块链提交失败，也返回成功，因为证明已生成
+            // 可以在后台重试提交到区块链
+            let response = VoteResponse {
+                success: true,
+                message: format!("Vote submitted successfully, but failed to send to blockchain: {}. Will retry in background.", e),
+                gas_cost: None,
+                transaction_hash: None,
+                vk_fingerprint,
+                public_inputs,
+                ballot_id,
+                nullifier,
+                commitment,
+                envelope,
+            };
+            warn!("Failed to submit to blockchain: {}, will retry in background", e);
+            response
+        }
+    }
+}
+
+// 解析客户端生成的选票中的防重标识、选票承诺和证明，三者必须同时提供
+fn parse_client_ballot<P: ProofSystem<Fr>>(req: &VoteRequest) -> Result<(Fr, Fr, P::Proof), VoteError> {
+    let (Some(nullifier), Some(commitment), Some(proof)) =
+        (req.nullifier.as_deref(), req.commitment.as_deref(), req.proof.as_deref())
+    else {
+        return Err(VoteError::InvalidBallot(
+            "a client-generated ballot must carry the nullifier, the commitment and the proof".to_string(),
+        ));
+    };
+    Ok((fr_from_hex::<Fr>(nullifier)?, fr_from_hex::<Fr>(commitment)?, parse_proof::<P>(proof)?))
+}
+
+// 验证客户端生成的选票证明并记录其防重标识；服务端不接触投票人秘密，也不知道投票选择
+async fn record_client_ballot<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+) -> Result<(P::Proof, BallotPublicInputs<Fr>), VoteError> {
+    check_election_open(state, req.election_id.as_deref()).await?;
+    let (nullifier, commitment, proof) = parse_client_ballot::<P>(req)?;
+    
+    // 只接受当前时段或上一时段的证明（容忍跨越时段边界的提交）
+    let current = ballot_epoch(Utc::now());
+    let epoch = req.epoch.unwrap_or(current);
+    if epoch > current || epoch.saturating_add(1) < current {
+        return Err(VoteError::InvalidBallot(format!(
+            "epoch {} is outside the accepted window, current epoch is {}",
+            epoch, current
+        )));
+    }
+    
+    // 原样重放的证明按摘要拒绝，不再重复验证和处理
+    // 摘要按规范编码计算，同一证明的压缩与非压缩编码视为相同
+    let digest = proof_digest::<P>(&proof);
+    if state.accepted_proofs.read().await.contains(&digest) {
+        return Err(VoteError::ReplayedProof);
+    }
+    
+    // 已使用的防重标识只能以更大的序号重新投票（提前拒绝，免去验证；记录时在写锁内再次检查）
+    if nullifier_superseded(state, req.election_id.as_deref(), &nullifier, req.sequence).await {
+        return Err(VoteError::DuplicateNullifier);
+    }
+    
+    let public_inputs = PublicInputs {
+        nullifier,
+        election_id: req
+            .election_id
+            .as_deref()
+            .map(election_id_to_field)
+            .unwrap_or_else(default_election_id),
+        sequence: Fr::from(req.sequence),
+        epoch: Fr::from(epoch),
+        commitment,
+    };
+    let verifying_key = ballot_verifying_key(state, req).await?;
+    let outcome = state
+        .vote_system
+        .verify_ballot_async(verifying_key, public_inputs.clone(), proof.clone())
+        .await?;
+    if let Some(reason) = outcome.invalid_reason() {
+        return Err(VoteError::Verification(format!("client-generated proof is invalid: {}", reason.code()).into()));
+    }
+    
+    // 并发提交同一证明时只有一个能写入摘要
+    if !state.accepted_proofs.write().await.insert(digest) {
+        return Err(VoteError::ReplayedProof);
+    }
+    if let Err(e) = register_nullifier(state, req.election_id.as_deref(), nullifier, req.sequence).await {
+        state.accepted_proofs.write().await.remove(&digest);
+        return Err(e);
+    }
+    info!("Recorded client-proved ballot");
+    Ok((proof, public_inputs))
+}
+
+// 在同一把写锁内检查并记录防重标识及其选票序号：同一防重标识的序号必须严格递增，
+// 并发请求同一防重标识和序号时只有一个能通过，其余（以及序号不大于已接受序号的选票）返回 DuplicateNullifier
+// 带选举标识的选票记入该选举的防重标识集合，否则记入默认选举的后端
+async fn register_nullifier<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: Option<&str>,
+    nullifier: Fr,
+    sequence: u64,
+) -> Result<(), VoteError> {
+    fn insert(nullifiers: &mut impl NullifierBackend, nullifier: Fr, sequence: u64) -> Result<(), VoteError> {
+        if superseded(nullifiers, &nullifier, sequence) {
+            return Err(VoteError::DuplicateNullifier);
+        }
+        nullifiers.record(nullifier, sequence).map_err(|e| {
+            error!("Failed to record nullifier: {}", e);
+            VoteError::from(e)
+        })
+    }
+    match election_id {
+        Some(id) => {
+            let mut elections = state.elections.write().await;
+            let election = elections.get_mut(id).ok_or_else(|| VoteError::UnknownElection(id.to_string()))?;
+            insert(&mut election.nullifiers, nullifier, sequence)
+        }
+        None => insert(&mut *state.nullifiers.write().await, nullifier, sequence),
+    }
+}
+
+// 该序号的选票是否已被同一防重标识相同或更大序号的选票占用（序号由存储后端记录，重启后仍然有效）
+fn superseded(nullifiers: &impl NullifierBackend, nullifier: &Fr, sequence: u64) -> bool {
+    nullifiers.sequence(nullifier).is_some_and(|last| sequence <= last)
+}
+
+// 防重标识在该选举中是否已以不小于该序号的选票使用
+async fn nullifier_superseded<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: Option<&str>,
+    nullifier: &Fr,
+    sequence: u64,
+) -> bool {
+    match election_id {
+        Some(id) => state
+            .elections
+            .read()
+            .await
+            .get(id)
+            .is_some_and(|election| superseded(&election.nullifiers, nullifier, sequence)),
+        None => superseded(&*state.nullifiers.read().await, nullifier, sequence),
+    }
+}
+
+// 纳入生命周期管理的选举只在 Open 状态下接受选票，其他状态返回 WrongPhase（403）
+// 默认选举和未纳入生命周期管理的选举不受限制
+async fn check_election_open<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: Option<&str>,
+) -> Result<(), VoteError> {
+    match election_id {
+        Some(id) => match state.election_state(id).await {
+            Some(election) if !election.accepts_votes() => {
+                warn!("Rejected vote for election {} in the {:?} state", id, election);
+                Err(VoteError::WrongPhase(format!("election {} is not open for voting", id)))
+            }
+            _ => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
+
+// 计算投票人登记键
+fn voter_key(user_id: &str) -> String {
+    blake3::hash(user_id.as_bytes()).to_hex().to_string()
+}
+
+// 按选举登记投票人，仅当选票序号大于该选举中已登记序号时成功（允许以更大的序号重新投票）
+// 成功时返回登记前的序号（首次投票为 None），失败时返回 None
+async fn claim_voter<P: ProofSystem<Fr>, N: NullifierBackend>(state: &AppState<P, N>, req: &VoteRequest) -> Option<Option<u64>> {
+    let key = (req.election_id.clone().unwrap_or_default(), voter_key(&req.user_id));
+    let mut voted = state.voted_users.write().await;
+    match voted.get(&key).copied() {
+        Some(last) if last >= req.sequence => None,
+        previous => {
+            voted.insert(key, req.sequence);
+            Some(previous)
+        }
+    }
+}
+
+// 撤销投票人登记，恢复为登记前的序号（仅当登记的仍是该序号时）
+// 重新投票失败时保留之前的序号，已接受的选票仍然占用投票资格
+async fn release_voter<P: ProofSystem<Fr>, N: NullifierBackend>(state: &AppState<P, N>, req: &VoteRequest, previous: Option<u64>) {
+    let key = (req.election_id.clone().unwrap_or_default(), voter_key(&req.user_id));
+    let mut voted = state.voted_users.write().await;
+    if voted.get(&key) == Some(&req.sequence) {
+        match previous {
+            Some(previous) => voted.insert(key, previous),
+            None => voted.remove(&key),
+        };
+    }
+}
+
+// 处理承诺请求
+async fn handle_commit<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    req: CommitRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!(
+        "handle_commit",
+        user_id_hash = %hash_user_id(&req.user_id),
+        election_id = %req.election_id,
+    );
+    match record_commitment(&state, req).instrument(span).await {
+        Ok(phase) => Ok(warp::reply::json(&CommitResponse { success: true, phase }).into_response()),
+        Err(e) => {
+            warn!("Rejected commitment: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 记录选票承诺，承诺阶段内可以覆盖之前的承诺
+// 揭示时按该选举的密钥生成证明，选举须先有密钥（setup_election、add_election_keys 或 POST /elections），否则返回 UnknownElection
+async fn record_commitment<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: CommitRequest,
+) -> Result<ElectionPhase, VoteError> {
+    validate_user_id(&req.user_id)?;
+    let commitment = fr_from_decimal::<Fr>(&req.commitment)?;
+    verifying_key_for(state, Some(&req.election_id)).await?;
+    // 持有阶段读锁直到写入完成，避免与阶段推进交错
+    let phases = state.phases.read().await;
+    let phase = phases.get(&req.election_id).copied().unwrap_or(ElectionPhase::Commit);
+    if phase != ElectionPhase::Commit {
+        return Err(VoteError::WrongPhase(format!("election is in {:?} phase", phase)));
+    }
+    state
+        .commitments
+        .write()
+        .await
+        .insert((req.election_id, voter_key(&req.user_id)), commitment);
+    info!("Recorded ballot commitment");
+    Ok(phase)
+}
+
+// 处理揭示请求
+async fn handle_reveal<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    req: RevealRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    // 注意：span中不记录投票值和开启值
+    let span = info_span!(
+        "handle_reveal",
+        user_id_hash = %hash_user_id(&req.user_id),
+        election_id = %req.election_id,
+    );
+    match reveal_ballot(&state, req).instrument(span).await {
+        Ok(response) => Ok(warp::reply::json(&response).into_response()),
+        Err(e) => {
+            warn!("Rejected reveal: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 核对开启值与承诺，一致时生成选票证明；每个承诺只能揭示一次
+// 揭示与 /vote 一样登记投票人，已投票的投票人不能再揭示，揭示后也不能再以序号0投票
+async fn reveal_ballot<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: RevealRequest,
+) -> Result<RevealResponse, VoteError> {
+    let phase = state.phase(&req.election_id).await;
+    if phase != ElectionPhase::Reveal {
+        return Err(VoteError::WrongPhase(format!("election is in {:?} phase", phase)));
+    }
+    
+    let key = (req.election_id.clone(), voter_key(&req.user_id));
+    let commitment = state.commitments.read().await.get(&key).copied().ok_or_else(|| {
+        VoteError::InvalidBallot("no commitment was recorded for this voter".to_string())
+    })?;
+    // 按该选举的电路配置映射投票选择，与生成证明时一致
+    let (config, ..) = election_keys_for(state, Some(&req.election_id)).await?;
+    let opening = BallotOpening {
+        vote: req.vote.to_field(&config)?,
+        randomness: fr_from_decimal::<Fr>(&req.randomness)?,
+    };
+    if opening.commitment() != commitment {
+        return Err(VoteError::CommitmentMismatch);
+    }
+    
+    let vote_req = VoteRequest {
+        vote: Some(req.vote),
+        user_id: req.user_id,
+        nullifier: None,
+        proof: None,
+        commitment: None,
+        epoch: None,
+        chain: req.chain,
+        gas_option: req.gas_option,
+        election_id: Some(req.election_id),
+        sequence: req.sequence,
+        key_handle: None,
+    };
+    let Some(previous) = claim_voter(state, &vote_req).await else {
+        return Err(VoteError::DuplicateNullifier);
+    };
+    // 先移除承诺，并发的重复揭示只有一个能通过
+    if state.commitments.write().await.remove(&key).is_none() {
+        release_voter(state, &vote_req, previous).await;
+        return Err(VoteError::InvalidBallot("no commitment was recorded for this voter".to_string()));
+    }
+    // 证明使用投票人揭示的随机值，证明中的选票承诺即投票人提交的承诺
+    let (proof, public_inputs, _) = match generate_ballot_proof(state, &vote_req, Some(opening.randomness)).await {
+        Ok(ballot) => ballot,
+        Err(e) => {
+            // 证明失败时恢复承诺和投票人登记，允许投票人重试
+            release_voter(state, &vote_req, previous).await;
+            state.commitments.write().await.insert(key, commitment);
+            return Err(e);
+        }
+    };
+    if public_inputs.commitment != commitment {
+        error!("Revealed ballot was proven under a different commitment");
+        return Err(VoteError::CommitmentMismatch);
+    }
+    info!("Revealed ballot matches its commitment");
+    
+    let verifying_key = verifying_key_for(state, vote_req.election_id.as_deref()).await?;
+    let envelope = ProofEnvelope::<Fr, P>::new(&verifying_key, public_inputs.clone(), proof.clone())?.to_json()?;
+    Ok(RevealResponse {
+        success: true,
+        proof: encode_proof::<P>(&proof),
+        public_inputs: public_inputs.to_vec().into_iter().map(fr_to_decimal).collect(),
+        vk_fingerprint: envelope.vk_fingerprint.clone(),
+        envelope,
+    })
+}
+
+// 处理创建选举请求（生命周期管理的选举，任意证明后端）
+async fn handle_create_lifecycle_election<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    authorization: Option<String>,
+    req: CreateElectionRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("create_election", election_id = %req.election_id);
+    if !state.is_authority(authorization.as_deref()) {
+        warn!(parent: &span, "Rejected unauthorized election request");
+        return Ok(unauthorized_reply("Managing elections requires the authority token"));
+    }
+    let params = req.params;
+    let created = state
+        .create_election(&req.election_id, params)
+        .instrument(span.clone())
+        .await
+        .and_then(|verifying_key| P::serialize_verifying_key(&verifying_key));
+    match created {
+        Ok(verifying_key) => {
+            let response = CreateElectionResponse {
+                success: true,
+                election_id: req.election_id,
+                params,
+                params_hash: hex::encode(params.digest()),
+                verifying_key: hex::encode(verifying_key),
+                state: ElectionState::Created,
+            };
+            Ok(warp::reply::json(&response).into_response())
+        }
+        Err(e) => {
+            warn!(parent: &span, "Failed to create election: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 处理开放/结束投票请求
+async fn handle_election_transition<P: ProofSystem<Fr>, N: NullifierBackend>(
+    election_id: String,
+    state: AppState<P, N>,
+    authorization: Option<String>,
+    next: fn(ElectionState) -> Result<ElectionState, VoteError>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("election_transition", election_id = %election_id);
+    if !state.is_authority(authorization.as_deref()) {
+        warn!(parent: &span, "Rejected unauthorized election request");
+        return Ok(unauthorized_reply("Managing elections requires the authority token"));
+    }
+    match state.transition(&election_id, next).instrument(span.clone()).await {
+        Ok(election) => {
+            let response = ElectionStateResponse { success: true, election_id, state: election };
+            Ok(warp::reply::json(&response).into_response())
+        }
+        Err(e) => {
+            warn!(parent: &span, "Rejected election transition: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 处理投票人登记请求
+async fn handle_register_voters<P: ProofSystem<Fr>, N: NullifierBackend>(
+    election_id: String,
+    state: AppState<P, N>,
+    authorization: Option<String>,
+    req: RegisterVotersRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("register_voters", election_id = %election_id, count = req.commitments.len());
+    if !state.is_authority(authorization.as_deref()) {
+        warn!(parent: &span, "Rejected unauthorized voter registration");
+        return Ok(unauthorized_reply("Registering voters requires the authority token"));
+    }
+    let commitments = match req.commitments.iter().map(|c| fr_from_decimal::<Fr>(c)).collect::<Result<Vec<_>, _>>() {
+        Ok(commitments) => commitments,
+        Err(e) => return Ok(error_reply(&e)),
+    };
+    match state.register_voters(&election_id, &commitments).instrument(span.clone()).await {
+        Ok((first_index, root, size)) => {
+            let response = RegisterVotersResponse {
+                success: true,
+                election_id,
+                registered: commitments.len(),
+                first_index,
+                root: fr_to_decimal(root),
+                size,
+            };
+            Ok(warp::reply::json(&response).into_response())
+        }
+        Err(e) => {
+            warn!(parent: &span, "Rejected voter registration: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 处理名册树根查询
+async fn handle_merkle_root<P: ProofSystem<Fr>, N: NullifierBackend>(
+    election_id: String,
+    state: AppState<P, N>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match state.voter_root(&election_id).await {
+        Ok((root, size)) => {
+            let response = MerkleRootResponse {
+                success: true,
+                election_id,
+                root: fr_to_decimal(root),
+                size,
+                depth: VOTER_TREE_DEPTH,
+            };
+            Ok(warp::reply::json(&response).into_response())
+        }
+        Err(e) => Ok(error_reply(&e)),
+    }
+}
+
+// 缺少计票方令牌时的响应
+fn unauthorized_reply(message: &str) -> warp::reply::Response {
+    let body = ErrorResponse {
+        success: false,
+        code: "unauthorized",
+        message: message.to_string(),
+    };
+    warp::reply::with_status(warp::reply::json(&body), StatusCode::UNAUTHORIZED).into_response()
+}
+
+// 处理验证请求
+async fn handle_verify<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    req: VerifyRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_verify", election_id = ?req.election_id);
+    let election_id = req.election_id.clone();
+    let nullifier = req
+        .public_inputs
+        .first()
+        .or_else(|| req.envelope.as_ref().and_then(|e| e.public_inputs.first()))
+        .and_then(|s| fr_from_decimal::<Fr>(s).ok());
+    let result = verify_request(&state, req).instrument(span).await;
+    let outcome = match &result {
+        Ok((outcome, _)) => outcome.invalid_reason().map_or("valid", InvalidReason::code),
+        Err(e) => e.code(),
+    };
+    record_audit(&state, AuditAction::Verify, election_id.as_deref(), nullifier, outcome).await;
+    match result {
+        Ok((outcome, verified_by)) => {
+            let response = VerifyResponse {
+                success: true,
+                valid: outcome.is_valid(),
+                reason: outcome.invalid_reason(),
+                vk_fingerprint: verified_by.map(|fingerprint| fingerprint.to_string()),
+            };
+            Ok(warp::reply::json(&response).into_response())
+        }
+        Err(e) => {
+            warn!("Rejected verify request: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 解析十六进制证明和十进制公开输入
+fn parse_proof_and_inputs<P: ProofSystem<Fr>>(
+    proof: &str,
+    public_inputs: &[String],
+) -> Result<(Vec<Fr>, P::Proof), VoteError> {
+    // 先校验公开输入范围，再进行任何密码学运算
+    let public_inputs = public_inputs
+        .iter()
+        .map(|s| fr_from_decimal::<Fr>(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((public_inputs, parse_proof::<P>(proof)?))
+}
+
+// 解析十六进制证明（压缩或非压缩格式）
+fn parse_proof<P: ProofSystem<Fr>>(proof: &str) -> Result<P::Proof, VoteError> {
+    let proof_bytes = hex::decode(proof)
+        .map_err(|e| VoteError::InvalidBallot(format!("invalid proof encoding: {}", e)))?;
+    let (proof, _) = P::deserialize_proof_auto(&proof_bytes)?;
+    Ok(proof)
+}
+
+// 解析并校验投票请求正文（与 /vote 相同的JSON解析和字段校验），
+// 不访问服务状态、不生成也不验证证明，供模糊测试使用（见 fuzz/）
+pub fn check_vote_request<P: ProofSystem<Fr>>(body: &[u8]) -> Result<(), VoteError> {
+    let req: VoteRequest = serde_json::from_slice(body)
+        .map_err(|e| VoteError::InvalidBallot(format!("invalid vote request: {}", e)))?;
+    if req.nullifier.is_some() || req.commitment.is_some() || req.proof.is_some() {
+        parse_client_ballot::<P>(&req)?;
+    } else {
+        validate_user_id(&req.user_id)?;
+        req.vote
+            .ok_or_else(|| VoteError::InvalidBallot("missing vote choice".to_string()))?
+            .slot(&CircuitConfig::default())?;
+    }
+    Ok(())
 }
 
-// 投票响应
-#[derive(serde::Serialize, Debug)]
-struct VoteResponse {
-    success: bool,
-    message: String,
-    gas_cost: Option<String>,
-    transaction_hash: Option<String>,
+// 解析验证请求并验证证明，证明有效时同时返回验证通过所用的验证密钥指纹
+async fn verify_request<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: VerifyRequest,
+) -> Result<(VerificationOutcome, Option<KeyFingerprint>), VoteError> {
+    let mut expected_fingerprint = req.vk_fingerprint.as_deref().map(KeyFingerprint::from_hex).transpose()?;
+    let (public_inputs, proof) = match (req.envelope, req.proof) {
+        (Some(envelope), None) => {
+            // 信封格式错误返回 400；声称的公开输入与信封记录的不同时以声称的为准，冒用他人防重标识的证明因此验证不通过
+            let envelope = ProofEnvelope::<Fr, P>::from_json(&envelope)?;
+            expected_fingerprint.get_or_insert(envelope.vk_fingerprint);
+            let public_inputs = if req.public_inputs.is_empty() {
+                envelope.public_inputs
+            } else {
+                BallotPublicInputs::from_slice(
+                    &req.public_inputs.iter().map(|s| fr_from_decimal::<Fr>(s)).collect::<Result<Vec<_>, _>>()?,
+                )?
+            };
+            (public_inputs, envelope.proof)
+        }
+        (None, Some(proof)) => {
+            let (public_inputs, proof) = parse_proof_and_inputs::<P>(&proof, &req.public_inputs)?;
+            (BallotPublicInputs::from_slice(&public_inputs)?, proof)
+        }
+        _ => {
+            return Err(VoteError::InvalidBallot(
+                "a verify request must carry exactly one of proof and envelope".to_string(),
+            ))
+        }
+    };
+    
+    // 默认选举在密钥轮换的过渡期内同时接受当前和旧的验证密钥（见 AppState::rotate_keys），其他选举只有一个验证密钥
+    let verifying_keys = match req.election_id.as_deref() {
+        Some(id) => vec![verifying_key_for(state, Some(id)).await?],
+        None => state.active_verifying_keys().await,
+    };
+    let mut candidates = verifying_keys
+        .into_iter()
+        .map(|vk| Ok((fingerprint::<Fr, _>(&*vk)?, vk)))
+        .collect::<Result<Vec<_>, VoteError>>()?;
+    if let Some(expected) = expected_fingerprint {
+        let active: Vec<String> = candidates.iter().map(|(actual, _)| actual.to_string()).collect();
+        candidates.retain(|(actual, _)| *actual == expected);
+        if candidates.is_empty() {
+            return Err(VoteError::KeyMismatch(format!(
+                "proof was generated for verifying key {}, got {}",
+                expected,
+                active.join(", ")
+            )));
+        }
+    }
+    // 请求已通过解析和密钥校验，此后验证出错说明服务端的密钥或验证过程有问题，
+    // 一律作为内部错误（500）返回，与证明为假（200、valid=false 并附原因）区分开
+    // 任一密钥验证通过即有效，并返回该密钥的指纹；都不通过时返回当前密钥的验证结果
+    let mut rejected = None;
+    for (actual, verifying_key) in candidates {
+        let outcome = state
+            .vote_system
+            .verify_ballot_async(verifying_key, public_inputs.clone(), proof.clone())
+            .await
+            .map_err(|e| match e {
+                VoteError::Internal(_) => e,
+                e => {
+                    error!("Verification failed with server-side error: {}", e);
+                    VoteError::Internal(format!("{}: {}", e.code(), e))
+                }
+            })?;
+        if outcome.is_valid() {
+            debug!("Proof verified under verifying key {}", actual);
+            return Ok((outcome, Some(actual)));
+        }
+        rejected.get_or_insert(outcome);
+    }
+    rejected
+        .map(|outcome| (outcome, None))
+        .ok_or_else(|| VoteError::Internal("no active verifying key".to_string()))
 }
 
-// 应用状态
-#[derive(Clone)]
-pub struct AppState {
-    vote_system: Arc<RwLock<VoteSystem<Fr, Groth16<Bn254>>>>,
-    proving_key: Arc<Groth16<Bn254>::ProvingKey>,
-    verifying_key: Arc<Groth16<Bn254>::VerifyingKey>,
-    blockchain_providers: Arc<RwLock<HashMap<String, Arc<Provider<Http>>>>>,
-    vote_contracts: Arc<RwLock<HashMap<String, Address>>>,
+// 解析后的一条待验证证明：公开输入与证明
+type ParsedProof<P> = (Vec<Fr>, <P as ProofSystem<Fr>>::Proof);
+
+// 同一验证密钥下待批量验证的条目：密钥指纹、验证密钥、条目在请求中的位置及解析后的证明
+type KeyedBatch<P> = (KeyFingerprint, Arc<<P as ProofSystem<Fr>>::VerifyingKey>, Vec<usize>, Vec<ParsedProof<P>>);
+
+// 按条目的选举标识和验证密钥指纹选择验证密钥（与 /verify 相同的查找方式）：
+// 带指纹时在该选举的有效验证密钥中按指纹选择，对不上返回 KeyMismatch；不带指纹时使用该选举的当前验证密钥
+async fn batch_verifying_key<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    entry: &BatchVerifyEntry,
+) -> Result<(KeyFingerprint, Arc<P::VerifyingKey>), VoteError> {
+    let expected = entry.vk_fingerprint.as_deref().map(KeyFingerprint::from_hex).transpose()?;
+    let verifying_keys = match entry.election_id.as_deref() {
+        Some(id) => vec![verifying_key_for(state, Some(id)).await?],
+        None => state.active_verifying_keys().await,
+    };
+    let mut candidates = verifying_keys
+        .into_iter()
+        .map(|vk| Ok((fingerprint::<Fr, _>(&*vk)?, vk)))
+        .collect::<Result<Vec<_>, VoteError>>()?;
+    match expected {
+        Some(expected) => candidates
+            .into_iter()
+            .find(|(actual, _)| *actual == expected)
+            .ok_or_else(|| VoteError::KeyMismatch(format!("proof was generated for verifying key {}", expected))),
+        None if candidates.is_empty() => Err(VoteError::Internal("no active verifying key".to_string())),
+        None => Ok(candidates.swap_remove(0)),
+    }
 }
 
-impl AppState {
-    pub fn new() -> Self {
-        // 初始化日志
-        init_logger();
-        info!("Initializing application state");
-        
-        // 初始化投票系统
-        let mut rng = thread_rng();
-        let (vote_system, proving_key, verifying_key) = VoteSystem::<Fr, Groth16<Bn254>>::setup(&mut rng)
-            .expect("Failed to setup vote system");
-        
-        // 初始化区块链提供者
-        let blockchain_providers = Arc::new(RwLock::new(HashMap::new()));
-        let vote_contracts = Arc::new(RwLock::new(HashMap::new()));
-        
-        // 配置默认区块链网络
-        Self::configure_default_networks(blockchain_providers.clone(), vote_contracts.clone());
-        
-        Self {
-            vote_system: Arc::new(RwLock::new(vote_system)),
-            proving_key: Arc::new(proving_key),
-            verifying_key: Arc::new(verifying_key),
-            blockchain_providers,
-            vote_contracts,
+// 处理批量验证请求
+// 结果与请求逐条对齐：有效为true，无效为false，格式错误或找不到验证密钥的条目为null
+// 每个条目按自己的选举标识和验证密钥指纹选择验证密钥，同一验证密钥下的条目合并为一次批量验证
+async fn handle_verify_batch<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    entries: Vec<BatchVerifyEntry>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_verify_batch", batch_size = entries.len(), duration_ms = field::Empty);
+    let start = std::time::Instant::now();
+    
+    // 先解析全部条目并查找各自的验证密钥，格式错误或找不到验证密钥的条目不参与验证
+    let parsed: Vec<Result<ParsedProof<P>, VoteError>> = entries
+        .iter()
+        .map(|entry| {
+            parse_proof_and_inputs::<P>(&entry.proof, &entry.public_inputs)
+                .inspect_err(|e| debug!(parent: &span, "Malformed batch entry: {}", e))
+        })
+        .collect();
+    let nullifiers: Vec<Option<Fr>> = parsed
+        .iter()
+        .map(|entry| entry.as_ref().ok().and_then(|(inputs, _)| inputs.first().copied()))
+        .collect();
+    let mut outcomes: Vec<Result<bool, VoteError>> = Vec::with_capacity(entries.len());
+    let mut groups: Vec<KeyedBatch<P>> = Vec::new();
+    for (index, (entry, parsed)) in entries.iter().zip(parsed).enumerate() {
+        let keyed = match parsed {
+            Ok(parsed) => batch_verifying_key(&state, entry).await.map(|key| (key, parsed)),
+            Err(e) => Err(e),
+        };
+        match keyed {
+            Ok(((actual, verifying_key), parsed)) => {
+                match groups.iter_mut().find(|(fingerprint, ..)| *fingerprint == actual) {
+                    Some((_, _, indices, batch)) => {
+                        indices.push(index);
+                        batch.push(parsed);
+                    }
+                    None => groups.push((actual, verifying_key, vec![index], vec![parsed])),
+                }
+                outcomes.push(Err(VoteError::Internal("missing batch verification result".to_string())));
+            }
+            Err(e) => {
+                debug!(parent: &span, "Skipping batch entry {}: {}", index, e);
+                outcomes.push(Err(e));
+            }
         }
     }
     
-    // 配置默认区块链网络
-    fn configure_default_networks(
-        providers: Arc<RwLock<HashMap<String, Arc<Provider<Http>>>>>,
-        contracts: Arc<RwLock<HashMap<String, Address>>>
-    ) {
-        tokio::spawn(async move {
-            let mut providers_write = providers.write().await;
-            let mut contracts_write = contracts.write().await;
-            
-            // 配置本地网络
-            if let Ok(provider) = Provider::try_from("http://127.0.0.1:8545") {
-                providers_write.insert("localhost".to_string(), Arc::new(provider));
-                // 这里应该从部署脚本获取合约地址
-                // 暂时使用占位地址
-                let contract_address: Address = "0x0000000000000000000000000000000000000000".parse().unwrap();
-                contracts_write.insert("localhost".to_string(), contract_address);
-                info!("Configured localhost network");
+    // 批量配对检查计算量较大，放到阻塞线程池执行
+    let vote_system = state.vote_system.clone();
+    let verified = tokio::task::spawn_blocking(move || {
+        groups
+            .into_iter()
+            .map(|(_, verifying_key, indices, batch)| (indices, vote_system.verify_batch(&verifying_key, &batch)))
+            .collect::<Vec<_>>()
+    })
+    .await;
+    let verified = match verified {
+        Ok(verified) => verified,
+        Err(e) => {
+            error!(parent: &span, "Failed to spawn batch verification task: {:?}", e);
+            return Ok(error_reply(&VoteError::Internal(format!("{:?}", e))));
+        }
+    };
+    for (indices, results) in verified {
+        for (index, result) in indices.into_iter().zip(results) {
+            outcomes[index] = result;
+        }
+    }
+    
+    // 逐条记录审计事件，格式错误的条目记录解析错误码
+    {
+        let mut audit_log = state.audit_log.write().await;
+        for ((entry, nullifier), outcome) in entries.iter().zip(nullifiers).zip(&outcomes) {
+            let outcome = match outcome {
+                Ok(true) => "valid",
+                Ok(false) => InvalidReason::ProofRejected.code(),
+                Err(e) => e.code(),
+            };
+            audit_log.append(AuditEvent {
+                action: AuditAction::VerifyBatch,
+                election_id: entry.election_id.clone(),
+                nullifier: nullifier.map(fr_to_decimal),
+                outcome: outcome.to_string(),
+            });
+        }
+    }
+    
+    let results: Vec<Option<bool>> = outcomes.into_iter().map(Result::ok).collect();
+    
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    Ok(warp::reply::json(&results).into_response())
+}
+
+// 默认选举（请求不带选举标识）的选举标识字段
+fn default_election_id() -> Fr {
+    Fr::zero()
+}
+
+// 查找选举的电路配置及密钥，不带选举标识时为默认密钥；没有密钥的选举返回 UnknownElection
+async fn election_keys_for<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: Option<&str>,
+) -> Result<ElectionKeys<P>, VoteError> {
+    match election_id {
+        Some(id) => state
+            .elections
+            .read()
+            .await
+            .get(id)
+            .map(ElectionHandle::keys)
+            .ok_or_else(|| VoteError::UnknownElection(id.to_string())),
+        None => {
+            let (proving_key, verifying_key) = state.current_keys().await;
+            Ok((state.circuit_config(), proving_key, verifying_key))
+        }
+    }
+}
+
+// 查找选举的验证密钥
+async fn verifying_key_for<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: Option<&str>,
+) -> Result<Arc<P::VerifyingKey>, VoteError> {
+    Ok(election_keys_for(state, election_id).await?.2)
+}
+
+// 记录一次验证事件到审计日志
+async fn record_audit<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    action: AuditAction,
+    election_id: Option<&str>,
+    nullifier: Option<Fr>,
+    outcome: &str,
+) {
+    state.audit_log.write().await.append(AuditEvent {
+        action,
+        election_id: election_id.map(str::to_string),
+        nullifier: nullifier.map(fr_to_decimal),
+        outcome: outcome.to_string(),
+    });
+}
+
+// 处理审计日志查询
+async fn handle_audit<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let audit_log = state.audit_log.read().await;
+    let response = AuditResponse {
+        success: true,
+        head: audit_log.head(),
+        entries: audit_log.entries(),
+    };
+    Ok(warp::reply::json(&response).into_response())
+}
+
+// 处理验证密钥查询
+async fn handle_vk<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    query: VerifyingKeyQuery,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let verifying_key = match verifying_key_for(&state, query.election_id.as_deref()).await {
+        Ok(verifying_key) => verifying_key,
+        Err(e) => return Ok(error_reply(&e)),
+    };
+    let verifying_key_bytes = match P::serialize_verifying_key(&verifying_key) {
+        Ok(bytes) => bytes,
+        Err(e) => return Ok(error_reply(&e)),
+    };
+    let response = VerifyingKeyResponse {
+        success: true,
+        verifying_key: hex::encode(verifying_key_bytes),
+        vk_fingerprint: hex::encode(vk_fingerprint::<Fr, P>(&verifying_key)),
+        prf_key_commitment: state.prf_key_commitment().map(fr_to_hex),
+    };
+    Ok(warp::reply::json(&response).into_response())
+}
+
+// 处理密钥轮换请求：生成新的默认密钥，旧验证密钥在过渡期结束后停用
+async fn handle_rotate_vk<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    authorization: Option<String>,
+    req: RotateKeyRequest,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_rotate_vk", transition_secs = req.transition_secs);
+    if !state.is_authority(authorization.as_deref()) {
+        warn!(parent: &span, "Rejected unauthorized key rotation request");
+        return Ok(unauthorized_reply("Rotating keys requires the authority token"));
+    }
+    
+    let rotated = state.rotate_keys(Duration::from_secs(req.transition_secs)).instrument(span).await;
+    let response = rotated.and_then(|(verifying_key, retire_at)| {
+        Ok(RotateKeyResponse {
+            success: true,
+            verifying_key: hex::encode(P::serialize_verifying_key(&verifying_key)?),
+            vk_fingerprint: hex::encode(vk_fingerprint::<Fr, P>(&verifying_key)),
+            retire_at,
+        })
+    });
+    match response {
+        Ok(response) => Ok(warp::reply::json(&response).into_response()),
+        Err(e) => {
+            warn!("Failed to rotate keys: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 查找 /setup 生成的密钥，句柄未知时返回 InvalidBallot
+async fn config_keys_for<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    handle: &str,
+) -> Result<ConfigKeys<P>, VoteError> {
+    state
+        .config_keys
+        .read()
+        .await
+        .get(handle)
+        .cloned()
+        .ok_or_else(|| VoteError::InvalidBallot(format!("unknown key handle {}", handle)))
+}
+
+// 选票所用的电路配置及密钥：携带密钥句柄时为 /setup 生成的密钥（只属于默认选举），否则按选举查找
+async fn ballot_keys<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+) -> Result<ConfigKeys<P>, VoteError> {
+    match req.key_handle.as_deref() {
+        Some(handle) => {
+            if let Some(id) = &req.election_id {
+                return Err(VoteError::KeyMismatch(format!(
+                    "keys for key handle {} belong to the default election, not election {}",
+                    handle, id
+                )));
             }
-            
-            // 可以添加其他默认网络配置
-        });
+            config_keys_for(state, handle).await
+        }
+        None => election_keys_for(state, req.election_id.as_deref()).await,
     }
 }
 
-// 投票路由
-pub fn vote_route(
-    state: AppState,
-) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    warp::path!("vote")
-        .and(warp::post())
-        .and(with_state(state))
-        .and(warp::body::json::<VoteRequest>())
-        .and_then(handle_vote)
+// 路径中的选举标识：请求体也带选举标识时须与路径一致，选举不存在时返回 UnknownElection（404）
+async fn path_election<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: String,
+    body_election_id: Option<String>,
+) -> Result<ElectionId, VoteError> {
+    if let Some(body_election_id) = body_election_id.filter(|id| *id != election_id) {
+        return Err(VoteError::InvalidBallot(format!(
+            "election {} in the request body does not match election {} in the path",
+            body_election_id, election_id
+        )));
+    }
+    if !state.elections.read().await.contains_key(&election_id) {
+        warn!("Rejected request for unknown election {}", election_id);
+        return Err(VoteError::UnknownElection(election_id));
+    }
+    Ok(election_id)
 }
 
-// 结果路由
-pub fn results_route(
-    state: AppState,
-) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    warp::path!("results")
-        .and(warp::get())
-        .and(with_state(state))
-        .and(warp::query::<ResultsQuery>())
-        .and_then(handle_results)
+// 选票所用密钥绑定的选举参数，密钥未绑定选举参数时为None
+async fn ballot_params<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+) -> Option<ElectionParams> {
+    match (&req.key_handle, &req.election_id) {
+        (None, Some(id)) => state.elections.read().await.get(id).and_then(|election| election.params),
+        _ => None,
+    }
 }
 
-// 结果查询参数
-#[derive(serde::Deserialize, Debug)]
-struct ResultsQuery {
-    chain: Option<String>,
+// 处理选举参数查询，没有绑定参数的选举返回 UnknownElection
+async fn handle_election_params<P: ProofSystem<Fr>, N: NullifierBackend>(
+    election_id: String,
+    state: AppState<P, N>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let Some(params) = state.elections.read().await.get(&election_id).and_then(|election| election.params) else {
+        return Ok(error_reply(&VoteError::UnknownElection(election_id)));
+    };
+    let response = ElectionParamsResponse {
+        success: true,
+        params_hash: hex::encode(params.digest()),
+        election_id,
+        params,
+    };
+    Ok(warp::reply::json(&response).into_response())
 }
 
-// 状态注入
-fn with_state(
-    state: AppState,
-) -> impl Filter<Extract = (AppState,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || state.clone())
+// 选票所用的验证密钥
+async fn ballot_verifying_key<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+) -> Result<Arc<P::VerifyingKey>, VoteError> {
+    Ok(ballot_keys(state, req).await?.2)
 }
 
-// 处理投票请求
-async fn handle_vote(
-    state: AppState,
-    req: VoteRequest,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    debug!("Received vote request: {:?}", req);
+// 选票所用验证密钥的十六进制指纹，密钥句柄未知时为None
+async fn ballot_vk_fingerprint<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+) -> Option<String> {
+    let verifying_key = ballot_verifying_key(state, req).await.ok()?;
+    Some(hex::encode(vk_fingerprint::<Fr, P>(&verifying_key)))
+}
+
+// 以选票所用验证密钥封装证明，密钥句柄未知时为None
+async fn ballot_envelope<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+    public_inputs: &BallotPublicInputs<Fr>,
+    proof: &P::Proof,
+) -> Option<ProofEnvelopeJson> {
+    let verifying_key = ballot_verifying_key(state, req).await.ok()?;
+    let envelope = ProofEnvelope::<Fr, P>::new(&verifying_key, public_inputs.clone(), proof.clone());
+    match envelope.and_then(|envelope| envelope.to_json()) {
+        Ok(envelope) => Some(envelope),
+        Err(e) => {
+            warn!("Failed to build proof envelope: {}", e);
+            None
+        }
+    }
+}
+
+// 校验投票请求并生成证明（及选票承诺），失败时返回错误信息
+// randomness 为承诺随机值：两阶段投票揭示时传入投票人承诺所用的随机值，否则为 None，由服务端随机生成
+async fn generate_ballot_proof<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    req: &VoteRequest,
+    randomness: Option<Fr>,
+) -> Result<(P::Proof, PublicInputs<Fr>, Fr), VoteError> {
+    // 防重标识由电路从投票人秘密派生，服务端生成证明时不接受前端提供的防重标识；
+    // 客户端自行生成证明的选票须单独提交到 /vote
+    if req.nullifier.is_some() || req.commitment.is_some() || req.proof.is_some() {
+        warn!("Rejected nullifier submitted by the client");
+        return Err(VoteError::InvalidBallot(
+            "client-generated ballots must be submitted individually to /vote".to_string(),
+        ));
+    }
+    
+    check_election_open(state, req.election_id.as_deref()).await?;
+    
+    // 携带密钥句柄时使用 /setup 按电路配置生成的密钥，否则使用该选举的密钥
+    let (config, proving_key, verifying_key) = ballot_keys(state, req).await?;
+    let params = ballot_params(state, req).await;
+    // 导出包只覆盖默认密钥下的选票，单独索引的选举和按配置生成的密钥使用各自的验证密钥
+    // 按请求判断而不比较密钥，证明期间默认密钥被轮换时选票仍计入默认选举
+    let default_key = req.key_handle.is_none() && req.election_id.is_none();
     
     // 验证投票选择
-    if req.vote != 0 && req.vote != 1 {
-        let response = VoteResponse {
+    let choice = req.vote.ok_or_else(|| VoteError::InvalidBallot("missing vote choice".to_string()))?;
+    let slot = choice.slot(&config)?;
+    let election_id = req
+        .election_id
+        .as_deref()
+        .map(election_id_to_field)
+        .unwrap_or_else(default_election_id);
+    // 投票人秘密由密钥和用户ID派生，同一投票人在同一选举中的防重标识固定，重新投票才能取代之前的选票；
+    // 设置了 PRF 密钥时电路同时证明派生关系，否则使用状态自带的派生密钥
+    let mut rng = state.rng.clone();
+    validate_user_id(&req.user_id)?;
+    let mut secret = state.prf_key.as_deref().unwrap_or(&*state.secret_key).voter_secret(&req.user_id);
+    let nullifier = poseidon_hash2(secret, election_id);
+    
+    // 已使用的防重标识只能以更大的序号重新投票
+    // 证明前的检查只为提前拒绝，防重标识在证明生成成功后才登记（见 register_nullifier），
+    // 并发请求同一防重标识和序号时都可能生成证明，但只有先登记的一个被接受
+    if nullifier_superseded(state, req.election_id.as_deref(), &nullifier, req.sequence).await {
+        warn!("Rejected reused nullifier");
+        return Err(VoteError::DuplicateNullifier);
+    }
+    
+    // 创建投票电路
+    let mut randomness = randomness.unwrap_or_else(|| Fr::rand(&mut rng));
+    let builder = match &params {
+        Some(params) => VoteCircuitBuilder::from_params(params),
+        None => VoteCircuitBuilder::new(config),
+    };
+    let builder = match &state.prf_key {
+        Some(prf_key) => builder.prf_key(prf_key, &req.user_id),
+        None => builder.secret(secret),
+    };
+    let circuit = builder
+        .choice(choice)
+        .randomness(randomness)
+        .election_id(election_id)
+        .sequence(req.sequence)
+        .epoch(ballot_epoch(Utc::now()))
+        .build()?;
+    // 计票方保留承诺开启值用于导出时计票（BallotOpening 释放时清零）
+    let opening = BallotOpening { vote: circuit.ballot.vote, randomness };
+    let commitment = opening.commitment();
+    // 私有输入只保留在电路中，栈上的副本立即清零
+    secret.zeroize();
+    randomness.zeroize();
+    let public_inputs = circuit.ballot.public_inputs();
+    
+    // 在提交证明任务前预先校验电路约束
+    if !VoteSystem::<Fr, P>::check_circuit(circuit.clone())? {
+        warn!("Vote rejected by circuit pre-validation");
+        return Err(VoteError::UnsatisfiedConstraint(
+            "Vote does not satisfy circuit constraints.".to_string(),
+        ));
+    }
+    
+    // 异步生成证明，超时或请求被丢弃（客户端断开）时放弃
+    let (proof, report) = state
+        .vote_system
+        .clone()
+        .vote_with_report_async_with_timeout(proving_key, circuit, rng.clone(), state.proving_timeout)
+        .await
+        .map_err(|e| {
+            error!("Failed to generate proof: {:?}", e);
+            e
+        })?;
+    info!(
+        synthesis_ms = report.synthesis_time.as_millis() as u64,
+        proving_ms = report.proving_time.as_millis() as u64,
+        proof_size = report.proof_size,
+        num_constraints = report.num_constraints,
+        estimated_memory_bytes = report.estimated_memory_bytes,
+        "Generated ballot proof"
+    );
+    
+    // 计票只计入验证通过的证明：验证与证明生成一样不持有任何锁，不同选票的验证可以并发
+    let outcome = state
+        .vote_system
+        .verify_ballot_async(verifying_key, public_inputs.clone(), proof.clone())
+        .await?;
+    if let Some(reason) = outcome.invalid_reason() {
+        error!("Generated proof failed verification: {}", reason.code());
+        return Err(VoteError::Verification(format!("generated proof is invalid: {}", reason.code()).into()));
+    }
+    
+    // 证明验证通过后登记防重标识，登记失败时不计入运行计票和导出包
+    register_nullifier(state, req.election_id.as_deref(), nullifier, req.sequence).await?;
+    if default_key {
+        // 承诺在锁外计算，运行计票的写锁只覆盖累加本身
+        let tally_params = state.running_tally.read().await.params().clone();
+        let commitment = VoteCommitment::new(&tally_params, &mut rng);
+        // 重新投票替换该投票人之前计入的一票；并发的重新投票只保留序号最大的一票
+        let mut votes = state.running_votes.write().await;
+        if votes.get(&nullifier).is_none_or(|(latest, ..)| *latest < req.sequence) {
+            let mut running_tally = state.running_tally.write().await;
+            running_tally.add(&commitment, slot)?;
+            if let Some((_, previous, candidate)) = votes.insert(nullifier, (req.sequence, commitment, slot)) {
+                running_tally.remove(&previous, candidate)?;
+            }
+        }
+        drop(votes);
+        // 导出包的计票证明电路只支持两个选项
+        if state.num_candidates == NUM_OPTIONS {
+            state.ballots.write().await.push(None, (public_inputs.clone(), proof.clone(), opening));
+        }
+    } else if let (None, Some(id)) = (&req.key_handle, &req.election_id) {
+        // 单独生成密钥的选举按选举记录选票，供 /elections/{id}/tally 按选举的电路配置计票
+        state.ballots.write().await.push(Some(id), (public_inputs.clone(), proof.clone(), opening));
+    }
+    Ok((proof, public_inputs, commitment))
+}
+
+// 处理按电路配置生成密钥的请求
+async fn handle_setup<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    authorization: Option<String>,
+    config: CircuitConfig,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_setup", num_candidates = config.num_candidates);
+    if !state.is_authority(authorization.as_deref()) {
+        warn!(parent: &span, "Rejected unauthorized setup request");
+        let body = ErrorResponse {
             success: false,
-            message: "Invalid vote choice. Must be 0 or 1.".to_string(),
-            gas_cost: None,
-            transaction_hash: None,
+            code: "unauthorized",
+            message: "Generating keys requires the authority token".to_string(),
         };
-        warn!("Invalid vote choice: {}", req.vote);
-        return Ok(warp::reply::json(&response));
+        return Ok(warp::reply::with_status(warp::reply::json(&body), StatusCode::UNAUTHORIZED).into_response());
     }
     
-    // 生成或使用前端提供的防重标识
-    let nullifier = if let Some(n) = req.nullifier {
-        // 使用前端提供的防重标识
-        Fr::from_str(&n).unwrap_or_else(|_| {
-            // 如果解析失败，生成新的
-            let mut rng = thread_rng();
-            generate_nullifier(&req.user_id, &mut rng)
-        })
-    } else {
-        // 生成新的防重标识
-        let mut rng = thread_rng();
-        generate_nullifier(&req.user_id, &mut rng)
-    };
+    match setup_config_keys(&state, config).instrument(span).await {
+        Ok(response) => Ok(warp::reply::json(&response).into_response()),
+        Err(e) => {
+            warn!("Failed to generate keys for circuit config: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 为电路配置生成默认选举的密钥，以配置摘要为句柄保存证明密钥；同一配置再次请求时返回已有的密钥
+async fn setup_config_keys<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    config: CircuitConfig,
+) -> Result<SetupResponse, VoteError> {
+    config.validate()?;
     
-    // 创建投票电路
-    let vote = if req.vote == 1 { Fr::one() } else { Fr::zero() };
-    let randomness = {
-        let mut rng = thread_rng();
-        Fr::from(rng.next_u64())
+    let key_handle = hex::encode(config.digest());
+    let existing = state.config_keys.read().await.get(&key_handle).map(|(_, _, vk)| vk.clone());
+    let verifying_key = match existing {
+        Some(verifying_key) => verifying_key,
+        None => {
+            // 密钥生成计算量较大，放到阻塞线程池执行
+            let mut rng = state.rng.clone();
+            let blank = state.bind_prf(CandidateVoteCircuit::blank(config.num_slots(), default_election_id()));
+            let (_, proving_key, verifying_key) =
+                tokio::task::spawn_blocking(move || VoteSystem::<Fr, P>::setup_for_circuit(blank, &mut rng))
+                    .await
+                    .map_err(|e| VoteError::Internal(format!("{:?}", e)))
+                    .and_then(|r| r)?;
+            // 并发请求同一配置时保留先写入的密钥
+            let mut config_keys = state.config_keys.write().await;
+            let (_, _, verifying_key) = config_keys
+                .entry(key_handle.clone())
+                .or_insert_with(|| (config, Arc::new(proving_key), Arc::new(verifying_key)));
+            info!("Generated keys for circuit config {}", key_handle);
+            verifying_key.clone()
+        }
     };
-    let circuit = VoteCircuit { vote, nullifier, randomness };
     
-    // 异步生成证明
-    let proof_result = {
-        let mut rng = thread_rng();
-        let vote_system = state.vote_system.read().await;
-        vote_system.vote_async(state.proving_key.clone(), circuit, rng).await
+    Ok(SetupResponse {
+        success: true,
+        key_handle,
+        config,
+        verifying_key: hex::encode(P::serialize_verifying_key(&verifying_key)?),
+        vk_fingerprint: hex::encode(vk_fingerprint::<Fr, P>(&verifying_key)),
+    })
+}
+
+// 处理导出请求：对已记录的选票计票并返回可独立复核的导出包
+async fn handle_export<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    authorization: Option<String>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_export", num_ballots = field::Empty);
+    if !state.is_authority(authorization.as_deref()) {
+        warn!(parent: &span, "Rejected unauthorized export request");
+        let body = ErrorResponse {
+            success: false,
+            code: "unauthorized",
+            message: "Exporting the election requires the authority token".to_string(),
+        };
+        return Ok(warp::reply::with_status(warp::reply::json(&body), StatusCode::UNAUTHORIZED).into_response());
+    }
+    
+    let ballots = state.ballots.read().await.default.clone();
+    span.record("num_ballots", ballots.len());
+    // 记录的选票都已通过验证，同一防重标识只计入一张，计入的选票数量即不同防重标识的数量
+    let num_accepted = ballots.iter().map(|(public_inputs, ..)| public_inputs.nullifier).collect::<HashSet<_>>().len();
+    let Some((tally_pk, tally_vk)) = state.tally_keys.read().await.get(&num_accepted).cloned() else {
+        let e = VoteError::KeyMismatch(format!("no published tally keys for {} ballots", num_accepted));
+        warn!(parent: &span, "Failed to export election bundle: {}", e);
+        return Ok(error_reply(&e));
     };
+    // 计票证明计算量较大，放到阻塞线程池执行
+    let vote_system = state.vote_system.clone();
+    let verifying_key = state.current_keys().await.1;
+    let mut rng = state.rng.clone();
+    let exported = tokio::task::spawn_blocking(move || {
+        export_bundle(&vote_system, &verifying_key, &tally_pk, &tally_vk, ballots, &mut rng)
+    })
+    .await
+    .map_err(|e| VoteError::Internal(format!("{:?}", e)))
+    .and_then(|r| r);
+    match exported {
+        Ok(bundle) => {
+            info!(parent: &span, "Exported election bundle {}", bundle.digest);
+            Ok(warp::reply::json(&bundle).into_response())
+        }
+        Err(e) => {
+            error!(parent: &span, "Failed to export election bundle: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 序列化证明为十六进制字符串
+fn encode_proof<P: ProofSystem<Fr>>(proof: &P::Proof) -> String {
+    let bytes = P::serialize_proof(proof).expect("serializing a proof into a Vec cannot fail");
+    hex::encode(bytes)
+}
+
+// 证明规范编码的 BLAKE3 摘要（用于识别重放的证明，十六进制编码后作为选票标识）
+fn proof_digest<P: ProofSystem<Fr>>(proof: &P::Proof) -> [u8; 32] {
+    let bytes = P::serialize_proof(proof).expect("serializing a proof into a Vec cannot fail");
+    *blake3::hash(&bytes).as_bytes()
+}
+
+// 处理批量投票请求
+async fn handle_vote_batch<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    reqs: Vec<VoteRequest>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let span = info_span!("handle_vote_batch", batch_size = reqs.len(), duration_ms = field::Empty);
+    let start = std::time::Instant::now();
+    let result = process_vote_batch(state, reqs).instrument(span.clone()).await;
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    result
+}
+
+// 批量投票处理逻辑
+async fn process_vote_batch<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+    reqs: Vec<VoteRequest>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    debug!("Received batch of {} vote requests", reqs.len());
+    
+    if state.voting_closed() {
+        warn!("Rejected vote batch submitted after the deadline");
+        return Ok(voting_closed_reply());
+    }
+    
+    // 先按顺序登记投票人，保证批内重复用户只有第一张选票被接受
+    // 用户ID不合法的选票不登记
+    let mut claimed = Vec::with_capacity(reqs.len());
+    for req in &reqs {
+        claimed.push(match validate_user_id(&req.user_id) {
+            Ok(()) => Ok(claim_voter(&state, req).await),
+            Err(e) => Err(e),
+        });
+    }
     
-    match proof_result {
-        Ok(proof) => {
-            // 尝试提交到区块链
-            let transaction_result = submit_to_blockchain(&state, &req, &proof).await;
+    // 并发生成证明（由工作线程池调度）
+    let tasks = reqs.iter().zip(claimed).map(|(req, claimed)| {
+        let state = state.clone();
+        async move {
+            let claimed = match claimed {
+                Ok(claimed) => claimed,
+                Err(e) => {
+                    warn!("Rejected vote in batch: {}", e);
+                    return BatchVoteResult {
+                        success: false,
+                        proof: None,
+                        transaction_hash: None,
+                        public_inputs: None,
+                        vk_fingerprint: None,
+                        envelope: None,
+                        error: Some(e.to_string()),
+                        error_code: Some(e.code()),
+                    };
+                }
+            };
+            let Some(previous) = claimed else {
+                warn!("Rejected duplicate vote in batch");
+                return BatchVoteResult {
+                    success: false,
+                    proof: None,
+                    transaction_hash: None,
+                    public_inputs: None,
+                    vk_fingerprint: None,
+                    envelope: None,
+                    error: Some(VoteError::DuplicateNullifier.to_string()),
+                    error_code: Some(VoteError::DuplicateNullifier.code()),
+                };
+            };
             
-            match transaction_result {
-                Ok((tx_hash, gas_cost)) => {
-                    let response = VoteResponse {
-                        success: true,
-                        message: "Vote submitted successfully and transaction sent to blockchain.".to_string(),
-                        gas_cost: Some(gas_cost),
-                        transaction_hash: Some(tx_hash),
+            match generate_ballot_proof(&state, req, None).await {
+                Ok((proof, public_inputs, _)) => {
+                    let transaction_hash = match submit_to_blockchain(&state, req).await {
+                        Ok((tx_hash, _)) => Some(tx_hash),
+                        Err(e) => {
+                            warn!("Failed to submit to blockchain: {}, will retry in background", e);
+                            None
+                        }
                     };
-                    info!("Vote submitted successfully, transaction hash: {:?}", tx_hash);
-                    Ok(warp::reply::json(&response))
+                    BatchVoteResult {
+                        success: true,
+                        proof: Some(encode_proof::<P>(&proof)),
+                        transaction_hash,
+                        public_inputs: Some(public_inputs.to_vec().into_iter().map(fr_to_decimal).collect()),
+                        vk_fingerprint: ballot_vk_fingerprint(&state, req).await,
+                        envelope: ballot_envelope(&state, req, &public_inputs, &proof).await,
+                        error: None,
+                        error_code: None,
+                    }
                 }
                 Err(e) => {
-                    // 即使区块链提交失败，也返回成功，因为证明已生成
-                    // 可以在后台重试提交到区块链
-                    let response = VoteResponse {
-                        success: true,
-                        message: format!("Vote submitted successfully, but failed to send to blockchain: {}. Will retry in background.", e),
-                        gas_cost: None,
+                    release_voter(&state, req, previous).await;
+                    BatchVoteResult {
+                        success: false,
+                        proof: None,
                         transaction_hash: None,
-                    };
-                    warn!("Failed to submit to blockchain: {}, will retry in background", e);
-                    Ok(warp::reply::json(&response))
+                        public_inputs: None,
+                        vk_fingerprint: None,
+                        envelope: None,
+                        error: Some(e.to_string()),
+                        error_code: Some(e.code()),
+                    }
                 }
             }
         }
+    });
+    let results = futures::future::join_all(tasks).await;
+    
+    info!(
+        "Processed vote batch: {} accepted, {} rejected",
+        results.iter().filter(|r| r.success).count(),
+        results.iter().filter(|r| !r.success).count()
+    );
+    Ok(warp::reply::json(&results).into_response())
+}
+
+// 处理结果查询：投票截止前不公布，避免泄露中间票数
+async fn handle_results<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: AppState<P, N>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_results", total_votes = field::Empty);
+    match current_results(&state).instrument(span.clone()).await {
+        Ok(response) => {
+            span.record("total_votes", response.total_votes);
+            Ok(warp::reply::json(&response).into_response())
+        }
         Err(e) => {
-            let response = VoteResponse {
-                success: false,
-                message: format!("Failed to generate proof: {}", e),
-                gas_cost: None,
-                transaction_hash: None,
-            };
-            error!("Failed to generate proof: {:?}", e);
-            Ok(warp::reply::json(&response))
+            warn!(parent: &span, "Rejected results request: {}", e);
+            Ok(error_reply(&e))
         }
     }
 }
 
-// 处理结果查询
-async fn handle_results(
-    state: AppState,
-    query: ResultsQuery,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    debug!("Received results query: {:?}", query);
-    
-    // 默认使用localhost网络
-    let chain = query.chain.unwrap_or_else(|| "localhost".to_string());
-    
-    // 从区块链获取结果
-    let results = get_results_from_blockchain(&state, &chain).await;
-    
-    match results {
-        Ok(data) => {
-            info!("Retrieved results from blockchain: {:?}", data);
-            Ok(warp::reply::json(&data))
+// 处理选举计票查询
+async fn handle_tally<P: ProofSystem<Fr>, N: NullifierBackend>(
+    election_id: String,
+    state: AppState<P, N>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let span = info_span!("handle_tally", election_id = %election_id, accepted = field::Empty);
+    match election_tally(&state, election_id).instrument(span.clone()).await {
+        Ok(response) => {
+            span.record("accepted", response.accepted);
+            Ok(warp::reply::json(&response).into_response())
         }
         Err(e) => {
-            let error_response = serde_json::json!({
-                "success": false,
-                "message": format!("Failed to retrieve results: {}", e)
-            });
-            error!("Failed to retrieve results: {:?}", e);
-            Ok(warp::reply::json(&error_response))
+            warn!(parent: &span, "Rejected tally request: {}", e);
+            Ok(error_reply(&e))
+        }
+    }
+}
+
+// 对选举的已记录选票计票，选票存储版本未变时直接返回缓存的结果，不重新验证
+// 选举结束（投票截止或阶段已关闭）前返回 WrongPhase，除非允许查询中间结果
+async fn election_tally<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+    election_id: String,
+) -> Result<TallyResponse, VoteError> {
+    let (config, _, verifying_key) = election_keys_for(state, Some(&election_id)).await?;
+    let lifecycle = state.election_state(&election_id).await;
+    let closed = state.voting_closed()
+        || state.phase(&election_id).await == ElectionPhase::Closed
+        || lifecycle.is_some_and(ElectionState::is_closed);
+    if !closed && !state.interim_results {
+        return Err(VoteError::WrongPhase("tally is published after the election closes".to_string()));
+    }
+    
+    let (version, ballots) = {
+        let store = state.ballots.read().await;
+        (store.version, store.elections.get(&election_id).cloned().unwrap_or_default())
+    };
+    if let Some((cached, response)) = state.tally_cache.read().await.get(&election_id) {
+        if *cached == version {
+            debug!("Serving cached tally for store version {}", version);
+            return Ok(response.clone());
         }
     }
+    
+    // 逐张验证选票证明，放到阻塞线程池执行
+    let vote_system = state.vote_system.clone();
+    let result = tokio::task::spawn_blocking(move || vote_system.tally_with_config(&verifying_key, &config, ballots))
+        .await
+        .map_err(|e| VoteError::Internal(format!("{:?}", e)))??;
+    let rejected_for = |reason: &str| result.rejected.iter().filter(|rejected| rejected.reason == reason).count();
+    let duplicate_nullifiers = rejected_for(VoteError::DuplicateNullifier.code());
+    let superseded = rejected_for("superseded_ballot");
+    let response = TallyResponse {
+        success: true,
+        election_id: election_id.clone(),
+        accepted: result.accepted.len(),
+        rejected: result.rejected.len(),
+        duplicate_nullifiers,
+        superseded,
+        turnout: result.turnout(),
+        abstained: result.abstained,
+        spoiled: result.spoiled,
+        counts: result.counts,
+        tallied_at: Utc::now(),
+    };
+    info!("Tallied {} ballots at store version {}", response.accepted, version);
+    // 并发的计票请求不会用较旧版本的结果覆盖较新的缓存
+    let mut cache = state.tally_cache.write().await;
+    if cache.get(&election_id).is_none_or(|(cached, _)| *cached <= version) {
+        cache.insert(election_id.clone(), (version, response.clone()));
+    }
+    drop(cache);
+    // 已结束的选举公布计票结果后进入 Tallied 状态
+    if lifecycle.is_some_and(ElectionState::is_closed) {
+        state.transition(&election_id, ElectionState::tallied).await?;
+    }
+    Ok(response)
+}
+
+// 运行计票的当前结果，投票未截止时返回 WrongPhase
+async fn current_results<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
+) -> Result<ResultsResponse, VoteError> {
+    if !state.voting_closed() {
+        return Err(VoteError::WrongPhase("results are published after voting closes".to_string()));
+    }
+    let counts = state.running_counts().await;
+    info!("Published results for {} candidates", counts.len());
+    Ok(ResultsResponse {
+        success: true,
+        num_candidates: state.num_candidates,
+        total_votes: counts.iter().sum(),
+        counts,
+    })
 }
 
 // 提交到区块链
-async fn submit_to_blockchain(
-    state: &AppState,
+async fn submit_to_blockchain<P: ProofSystem<Fr>, N: NullifierBackend>(
+    state: &AppState<P, N>,
     req: &VoteRequest,
-    proof: &Groth16<Bn254>::Proof,
 ) -> Result<(String, String), String> {
     // 这里应该实现与智能合约的交互
     // 暂时返回模拟结果
-    debug!("Submitting to blockchain: {:?}", req.chain);
+    debug!("Submitting to blockchain: {:?} (gas option {:?})", req.chain, req.gas_option);
     
     // 模拟区块链提交
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
     
     // 模拟交易哈希和Gas费用
-    let tx_hash = format!("0x{:x}", rand::random::<u128>());
+    let tx_hash = format!("0x{:x}", state.rng.clone().gen::<u128>());
     let gas_cost = "0.001 ETH".to_string();
     
     Ok((tx_hash, gas_cost))
 }
 
-// 从区块链获取结果
-async fn get_results_from_blockchain(
-    state: &AppState,
-    chain: &str,
-) -> Result<serde_json::Value, String> {
-    // 这里应该实现与智能合约的交互
-    // 暂时返回模拟结果
-    debug!("Getting results from blockchain: {}", chain);
-    
-    // 模拟区块链查询
-    tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-    
-    // 模拟结果
-    let results = serde_json::json!({
-        "success": true,
-        "chain": chain,
-        "results": {
-            "option_0": 100,
-            "option_1": 150,
-            "total_votes": 250
-        },
-        "last_updated": chrono::Utc::now().to_rfc3339()
-    });
-    
-    Ok(results)
-}
-
-// 自定义错误类型
-#[derive(Debug)]
-struct ProofGenerationError;
-
-impl warp::reject::Reject for ProofGenerationError {}
-
-// 自定义错误类型
-#[derive(Debug)]
-struct BlockchainError {
-    message: String,
-}
-
-impl warp::reject::Reject for BlockchainError {}
-
 // 导入必要的依赖
-use std::collections::HashMap;
-use ark_ff::Field;
-use chrono;
-use rand;
\ No newline at end of file
+use std::collections::{HashMap, HashSet};
+use ark_ff::Zero;
+use zeroize::Zeroize;
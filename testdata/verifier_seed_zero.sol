@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+// Generated by zkp-vote. Do not edit.
+pragma solidity ^0.8.0;
+
+contract BallotVerifier {
+    uint256 constant SNARK_SCALAR_FIELD = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    uint256 constant PRIME_Q = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+
+    uint256 constant ALPHA_X = 5659261313302227808962845827735251451261678268437599156207670908983224745098;
+    uint256 constant ALPHA_Y = 9529968883614368773304460554679684211078703335942681381809592262741553869251;
+    uint256 constant BETA_X1 = 4335569062518497928076156137147058668901050811551553413481174768496640568267;
+    uint256 constant BETA_X0 = 11317014787955481694121199362319180748752520657302650602100804418617372957795;
+    uint256 constant BETA_Y1 = 21661323856798416399375751352793587587813332172290790967185999309730985974233;
+    uint256 constant BETA_Y0 = 21244364993402843296227643028416997654779095520341182791323057241302128477291;
+    uint256 constant GAMMA_X1 = 3478538194299658152567583394619128432398343994765315831968776555161193144161;
+    uint256 constant GAMMA_X0 = 888865501366419173981880750594842651115733619644999018753332828191427544404;
+    uint256 constant GAMMA_Y1 = 14336132859915912339002087016525052064411148972912257278129435201309471595104;
+    uint256 constant GAMMA_Y0 = 11788497481183315345707132887015605446461773177916231051858162927907526519276;
+    uint256 constant DELTA_X1 = 17144178262268751326153771704141408711288456634713348250685908907917271357201;
+    uint256 constant DELTA_X0 = 10870651749977354841827284753325483565659892330640104754976086413898557169601;
+    uint256 constant DELTA_Y1 = 4145032483420823174928619639151304418892961167430051225920030626994840470686;
+    uint256 constant DELTA_Y0 = 228067100771428857388671924021474894599180157905127261251909682816899592885;
+    uint256 constant IC0_X = 704859216643874815369786714106113223187379385243262760574356876452108186536;
+    uint256 constant IC0_Y = 14336099409128609905278671539604590744478480996432194324984457121381806571960;
+    uint256 constant IC1_X = 6155840246153427280216740631848207883509069195453852176019998250543151857026;
+    uint256 constant IC1_Y = 1002856120330435064586352544252962372591736070716258364545798133766463709843;
+    uint256 constant IC2_X = 7435483138826822967340263952795279284102461760098425337828790546660388064379;
+    uint256 constant IC2_Y = 17121916371547638884561236331378076539644230058481675821400478367911987026417;
+    uint256 constant IC3_X = 10641467879570389563256949765273568745187745519891044773026918967281851683990;
+    uint256 constant IC3_Y = 7894099167530229754555281184915848861987909344565128554504173443483646470049;
+    uint256 constant IC4_X = 19772953461201963542910812842082114547621362882305962194435915655384571128274;
+    uint256 constant IC4_Y = 16996761009844843924099066094075408961783513555651251699586686750166502327550;
+    uint256 constant IC5_X = 869435021815007282459037074292643817295437181566034771855055909927056646134;
+    uint256 constant IC5_Y = 13080563530301481059178093548018491715540394820483428974787459066222558814833;
+
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[5] calldata input
+    ) external view returns (bool) {
+        for (uint256 i = 0; i < 5; i++) {
+            require(input[i] < SNARK_SCALAR_FIELD, "input out of range");
+        }
+
+        // vk_x = IC0 + sum(input[i] * IC{i+1})
+        uint256[2] memory vkX = [IC0_X, IC0_Y];
+        vkX = ecAdd(vkX, ecMul([IC1_X, IC1_Y], input[0]));
+        vkX = ecAdd(vkX, ecMul([IC2_X, IC2_Y], input[1]));
+        vkX = ecAdd(vkX, ecMul([IC3_X, IC3_Y], input[2]));
+        vkX = ecAdd(vkX, ecMul([IC4_X, IC4_Y], input[3]));
+        vkX = ecAdd(vkX, ecMul([IC5_X, IC5_Y], input[4]));
+
+        // e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (PRIME_Q - (a[1] % PRIME_Q)) % PRIME_Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = vkX[0];
+        p[13] = vkX[1];
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+
+        uint256[1] memory result;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x08, p, 768, result, 0x20)
+        }
+        return success && result[0] == 1;
+    }
+
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2) internal view returns (uint256[2] memory r) {
+        uint256[4] memory inp = [p1[0], p1[1], p2[0], p2[1]];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x06, inp, 0x80, r, 0x40)
+        }
+        require(success, "ecAdd failed");
+    }
+
+    function ecMul(uint256[2] memory p1, uint256 s) internal view returns (uint256[2] memory r) {
+        uint256[3] memory inp = [p1[0], p1[1], s];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x07, inp, 0x60, r, 0x40)
+        }
+        require(success, "ecMul failed");
+    }
+}
@@ -0,0 +1,112 @@
+// 命令行集成测试：导出选举结果包后用 zkp-vote tally 离线复核
+
+use ark_ff::{One, Zero};
+use ark_groth16::Groth16;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use zkp_vote::curve::{DefaultField as Fr, DefaultPairing};
+use zkp_vote::tally::setup_tally;
+use zkp_vote::{export_bundle, BallotOpening, BallotPublicInputs, ElectionBundle, ProofSystem, VoteCircuit, VoteSystem};
+
+type Backend = Groth16<DefaultPairing>;
+
+fn write_bundle(name: &str, bundle: &ElectionBundle) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, serde_json::to_vec(bundle).unwrap()).unwrap();
+    path
+}
+
+// 写出可信设置公布的计票验证密钥（十六进制编码）
+fn write_tally_vk(name: &str, vk: &<Backend as ProofSystem<Fr>>::VerifyingKey) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, hex::encode(Backend::serialize_verifying_key(vk).unwrap())).unwrap();
+    path
+}
+
+fn run_tally(path: &Path, tally_vk_path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_zkp-vote"))
+        .args(["tally", "--bundle"])
+        .arg(path)
+        .arg("--tally-vk")
+        .arg(tally_vk_path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_tally_command_recomputes_bundle_counts() {
+    let mut rng = rand::thread_rng();
+    let (system, pk, vk) = VoteSystem::<Fr, Backend>::setup(Fr::from(2024u64), &mut rng).unwrap();
+    let mut ballot = |secret: u64, vote: u64, sequence: u64| {
+        let randomness = Fr::from(1000u64 + secret);
+        let circuit = VoteCircuit {
+            vote: Fr::from(vote),
+            secret: Fr::from(secret),
+            randomness,
+            election_id: Fr::from(2024u64),
+            sequence: Fr::from(sequence),
+            epoch: Fr::zero(),
+        };
+        let public_inputs: BallotPublicInputs<Fr> = circuit.public_inputs();
+        let proof = system.vote(&pk, circuit, &mut rng).unwrap();
+        (public_inputs, proof, BallotOpening { vote: Fr::from(vote), randomness })
+    };
+    let (alice, bob, carol, bob_again) = (ballot(1, 1, 0), ballot(2, 0, 0), ballot(3, 1, 0), ballot(2, 1, 0));
+    let bob_revote = ballot(2, 1, 1);
+    let mut forged = ballot(4, 0, 0);
+    forged.0.sequence = Fr::one();
+
+    // 计票密钥由独立的可信设置按计入选票数量生成并公布
+    let (tally_pk3, tally_vk3) = setup_tally::<Fr, Backend, _>(3, &mut rng).unwrap();
+    let (tally_pk2, tally_vk2) = setup_tally::<Fr, Backend, _>(2, &mut rng).unwrap();
+    let (tally_pk1, tally_vk1) = setup_tally::<Fr, Backend, _>(1, &mut rng).unwrap();
+    let tally_vk3_path = write_tally_vk("cli_tally_vk3.hex", &tally_vk3);
+
+    // 重复投票按计票规则拒绝，不影响复核结果
+    let ballots = vec![alice.clone(), bob.clone(), carol.clone(), bob_again];
+    let bundle = export_bundle(&system, &vk, &tally_pk3, &tally_vk3, ballots, &mut rng).unwrap();
+    let output = run_tally(&write_bundle("cli_bundle.json", &bundle), &tally_vk3_path);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        stdout,
+        "ballots: 4 (accepted 3, rejected 1)\noption 0: 1\noption 1: 2\nrejected ballot 3: duplicate_nullifier\n"
+    );
+
+    // 以更大序号重新投票取代之前的选票，同样不影响复核结果
+    let ballots = vec![alice.clone(), bob.clone(), carol.clone(), bob_revote];
+    let bundle = export_bundle(&system, &vk, &tally_pk3, &tally_vk3, ballots, &mut rng).unwrap();
+    let output = run_tally(&write_bundle("cli_bundle_revote.json", &bundle), &tally_vk3_path);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        stdout,
+        "ballots: 4 (accepted 3, rejected 1)\noption 0: 0\noption 1: 3\nrejected ballot 1: superseded_ballot\n"
+    );
+
+    // 有选票证明不成立时以非零状态退出
+    let bundle = export_bundle(&system, &vk, &tally_pk2, &tally_vk2, vec![alice, bob, forged], &mut rng).unwrap();
+    let output = run_tally(&write_bundle("cli_bundle_forged.json", &bundle), &write_tally_vk("cli_tally_vk2.hex", &tally_vk2));
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("rejected ballot 2: proof_rejected"));
+
+    // 篡改票数后导出包无法通过复核
+    let tally_vk1_path = write_tally_vk("cli_tally_vk1.hex", &tally_vk1);
+    let mut tampered = export_bundle(&system, &vk, &tally_pk1, &tally_vk1, vec![carol.clone()], &mut rng).unwrap();
+    tampered.tally.counts = vec![1, 0];
+    tampered.digest = tampered.compute_digest();
+    let output = run_tally(&write_bundle("cli_bundle_tampered.json", &tampered), &tally_vk1_path);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("bundle verification failed"));
+
+    // 导出方自行生成计票密钥伪造的计票证明不被公布的计票验证密钥接受
+    let (forged_pk, forged_vk) = setup_tally::<Fr, Backend, _>(1, &mut rng).unwrap();
+    let forged_bundle = export_bundle(&system, &vk, &forged_pk, &forged_vk, vec![carol], &mut rng).unwrap();
+    let output = run_tally(&write_bundle("cli_bundle_forged_tally_vk.json", &forged_bundle), &tally_vk1_path);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("key_mismatch"));
+
+    // 参数错误
+    let output = Command::new(env!("CARGO_BIN_EXE_zkp-vote")).args(["tally"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,63 @@
+// C 接口集成测试：用系统 C 编译器（环境变量 CC，默认 cc）编译 tests/ffi/smoke.c，
+// 链接本库的动态库并运行。需要启用 ffi 特性：cargo test --features ffi --test ffi
+#![cfg(feature = "ffi")]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zkp_vote::client::{self, ClientBackend};
+use zkp_vote::curve::DefaultField as Fr;
+use zkp_vote::fingerprint::encode_key;
+use zkp_vote::{ProofSystem, VoteSystem};
+
+const BALLOT: &str = r#"{"vote": "1", "secret": "123456789", "randomness": "987654321", "election_id": "2024", "sequence": "0", "epoch": "0"}"#;
+
+// 测试可执行文件位于 target/<profile>/deps，动态库位于 target/<profile>
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().and_then(Path::parent).unwrap().to_path_buf()
+}
+
+#[test]
+fn test_c_program_proves_and_verifies() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let work_dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let lib_dir = library_dir();
+
+    let (_, pk, vk) = VoteSystem::<Fr, ClientBackend>::setup(Fr::from(2024u64), &mut rand::thread_rng()).unwrap();
+    let pk_path = work_dir.join("ffi_pk.bin");
+    let vk_path = work_dir.join("ffi_vk.bin");
+    std::fs::write(&pk_path, encode_key::<Fr, _>(&pk).unwrap()).unwrap();
+    std::fs::write(&vk_path, ClientBackend::serialize_verifying_key(&vk).unwrap()).unwrap();
+
+    let exe = work_dir.join("zkp_vote_ffi_smoke");
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .arg(manifest_dir.join("tests/ffi/smoke.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-L")
+        .arg(lib_dir.join("deps"))
+        .arg("-lzkp_vote")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling smoke.c failed");
+
+    let output = Command::new(&exe)
+        .arg(&pk_path)
+        .arg(&vk_path)
+        .env("ZKP_VOTE_INPUTS", client::ballot_public_inputs(BALLOT).unwrap())
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .expect("failed to run smoke test program");
+    assert!(
+        output.status.success(),
+        "smoke test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+}
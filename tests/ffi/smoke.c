@@ -0,0 +1,96 @@
+/* C 接口冒烟测试：加载密钥、生成证明、验证证明，并检查出错路径
+ * 用法：smoke <证明密钥文件> <验证密钥文件>
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include "zkp_vote.h"
+
+#define BALLOT "{\"vote\": \"1\", \"secret\": \"123456789\", \"randomness\": \"987654321\", " \
+               "\"election_id\": \"2024\", \"sequence\": \"0\", \"epoch\": \"0\"}"
+
+static unsigned char *read_file(const char *path, size_t *len) {
+    FILE *f = fopen(path, "rb");
+    if (!f) {
+        perror(path);
+        exit(1);
+    }
+    fseek(f, 0, SEEK_END);
+    *len = (size_t)ftell(f);
+    fseek(f, 0, SEEK_SET);
+    unsigned char *buf = malloc(*len);
+    if (fread(buf, 1, *len, f) != *len) {
+        fprintf(stderr, "short read: %s\n", path);
+        exit(1);
+    }
+    fclose(f);
+    return buf;
+}
+
+static void fail(const char *what) {
+    char message[256];
+    zkp_vote_last_error(message, sizeof(message));
+    fprintf(stderr, "%s: %s\n", what, message);
+    exit(1);
+}
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s <pk> <vk>\n", argv[0]);
+        return 2;
+    }
+    size_t pk_len, vk_len;
+    unsigned char *pk = read_file(argv[1], &pk_len);
+    unsigned char *vk = read_file(argv[2], &vk_len);
+
+    ZkpVoteKeys *keys = zkp_vote_load_keys(pk, pk_len, vk, vk_len);
+    if (!keys) {
+        fail("load keys");
+    }
+
+    /* 先以空缓冲区查询证明长度 */
+    size_t proof_len = 0;
+    if (zkp_vote_prove(keys, BALLOT, NULL, 0, &proof_len) != ZKP_VOTE_BUFFER_TOO_SMALL || proof_len == 0) {
+        fail("query proof length");
+    }
+    unsigned char *proof = malloc(proof_len);
+    if (zkp_vote_prove(keys, BALLOT, proof, proof_len, &proof_len) != ZKP_VOTE_OK) {
+        fail("prove");
+    }
+
+    /* 公开输入顺序与电路一致，这里由测试程序通过环境变量传入 */
+    const char *inputs = getenv("ZKP_VOTE_INPUTS");
+    if (!inputs) {
+        fprintf(stderr, "ZKP_VOTE_INPUTS is not set\n");
+        return 1;
+    }
+    if (zkp_vote_verify(keys, inputs, proof, proof_len) != 1) {
+        fail("verify");
+    }
+
+    /* 篡改证明后应验证失败或报错，但不能通过 */
+    proof[proof_len / 2] ^= 0x01;
+    if (zkp_vote_verify(keys, inputs, proof, proof_len) == 1) {
+        fprintf(stderr, "tampered proof verified\n");
+        return 1;
+    }
+
+    /* 非法选票应报错，并能取到带错误码的信息 */
+    if (zkp_vote_prove(keys, "{\"vote\": \"1\"}", proof, proof_len, &proof_len) != ZKP_VOTE_ERROR) {
+        fprintf(stderr, "invalid ballot was accepted\n");
+        return 1;
+    }
+    char message[256];
+    size_t message_len = zkp_vote_last_error(message, sizeof(message));
+    if (message_len == 0 || strncmp(message, "invalid_ballot", strlen("invalid_ballot")) != 0) {
+        fprintf(stderr, "unexpected error: %s\n", message);
+        return 1;
+    }
+
+    zkp_vote_free_keys(keys);
+    free(proof);
+    free(pk);
+    free(vk);
+    printf("ok\n");
+    return 0;
+}